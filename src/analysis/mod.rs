@@ -0,0 +1,3 @@
+//! Stream analysis built on top of [`MpegTsParser`](crate::MpegTsParser).
+
+pub mod tr101290;
@@ -0,0 +1,436 @@
+//! Measurement of the priority 1 and 2 checks of ETSI TR 101 290.
+//!
+//! [`Tr101290Analyzer`] parses packets together with their arrival times and reports each failed
+//! check as a [`Violation`]. Times are in 27MHz ticks; streams without arrival timestamps can be
+//! analyzed at a constant bitrate with [`Tr101290Analyzer::analyze`].
+
+use crate::prelude::*;
+use crate::{
+    AppDetails, DefaultAppDetails, ErrorDetails, MpegTsParser, ParserEvent, Payload, PcrTimestamp,
+    TransportScramblingControl,
+};
+use core::convert::TryInto;
+use hashbrown::HashMap;
+
+/// Ticks of the 27MHz system clock per second.
+const TICKS_PER_SECOND: u64 = 27_000_000;
+/// Largest interval between PAT or PMT sections.
+const MAX_SECTION_INTERVAL: u64 = TICKS_PER_SECOND / 2;
+/// Largest interval between PCRs of a PID.
+const MAX_PCR_INTERVAL: u64 = TICKS_PER_SECOND / 25;
+/// Largest step between consecutive PCRs not signaled as a discontinuity (100 ms).
+const MAX_PCR_STEP: u64 = TICKS_PER_SECOND / 10;
+/// Largest PCR inaccuracy (500 ns).
+const MAX_PCR_INACCURACY: u64 = 13;
+/// Default period within which referenced PIDs must occur.
+const DEFAULT_PID_TIMEOUT: u64 = 5 * TICKS_PER_SECOND;
+
+/// Cause of a PAT or PMT error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TableError {
+    /// No section of the table occurred within 0.5 s.
+    Interval,
+    /// A section with another table ID occurred on PID 0.
+    WrongTableId(u8),
+    /// A packet of the table was scrambled.
+    Scrambled,
+}
+
+/// Failed check of ETSI TR 101 290.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Check {
+    /// 1.1: Sync was lost after two or more consecutive corrupted sync bytes.
+    TsSyncLoss,
+    /// 1.2: The sync byte of a packet is not 0x47.
+    SyncByteError,
+    /// 1.3: PAT error.
+    PatError(TableError),
+    /// 1.4: Continuity counter error.
+    ContinuityCountError {
+        /// PID of the packet.
+        pid: u16,
+        /// Continuity counter following the previous packet.
+        expected: u8,
+        /// Continuity counter of the packet.
+        got: u8,
+    },
+    /// 1.5: PMT error.
+    PmtError {
+        /// PID of the PMT.
+        pid: u16,
+        /// Cause of the error.
+        error: TableError,
+    },
+    /// 1.6: A PID referenced by a PMT did not occur within the PID timeout.
+    PidError {
+        /// Referenced PID.
+        pid: u16,
+    },
+    /// 2.1: The transport error indicator is set.
+    TransportError {
+        /// PID of the packet.
+        pid: u16,
+    },
+    /// 2.2: A PSI section failed its CRC check.
+    CrcError {
+        /// PID of the section.
+        pid: u16,
+    },
+    /// 2.3a: PCRs of a PID arrived more than 40 ms apart.
+    PcrRepetitionError {
+        /// PID carrying the PCR.
+        pid: u16,
+        /// Arrival interval in 27MHz ticks.
+        interval: u64,
+    },
+    /// 2.3b: Consecutive PCRs differ by more than 100 ms or step backwards without the
+    /// discontinuity indicator.
+    PcrDiscontinuityIndicatorError {
+        /// PID carrying the PCR.
+        pid: u16,
+    },
+    /// 2.4: A PCR deviates by more than 500 ns from its arrival time.
+    PcrAccuracyError {
+        /// PID carrying the PCR.
+        pid: u16,
+        /// Deviation in 27MHz ticks.
+        inaccuracy: i64,
+    },
+}
+
+impl Check {
+    /// Priority of the check: 1 for checks necessary for decodability, 2 for recommended
+    /// continuous monitoring.
+    pub fn priority(&self) -> u8 {
+        match self {
+            Check::TsSyncLoss
+            | Check::SyncByteError
+            | Check::PatError(_)
+            | Check::ContinuityCountError { .. }
+            | Check::PmtError { .. }
+            | Check::PidError { .. } => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// Failed check with the position it was detected at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Violation {
+    /// Index of the packet (counted from the first pushed packet).
+    pub packet_index: u64,
+    /// Arrival time of the packet in 27MHz ticks.
+    pub arrival: u64,
+    /// Failed check.
+    pub check: Check,
+}
+
+/// Last PCR of a PID with its arrival time.
+#[derive(Debug, Copy, Clone)]
+struct PcrArrival {
+    pcr: PcrTimestamp,
+    arrival: u64,
+}
+
+/// Analyzer evaluating ETSI TR 101 290 priority 1 and 2 checks.
+///
+/// Continuity checking is enabled on the wrapped parser and strict mode is disabled, so
+/// continuity errors are reported as events instead of failing the packet.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::analysis::tr101290::{Check, Tr101290Analyzer};
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+///
+/// let mut analyzer = Tr101290Analyzer::new(MpegTsParser::<DefaultAppDetails>::default());
+/// let mut packet = [0xff_u8; 188];
+/// packet[..4].copy_from_slice(&[0x12, 0x1f, 0xff, 0x10]);
+/// analyzer.push(&packet, 0);
+/// let violations = analyzer.push(&packet, 100);
+/// assert_eq!(violations[0].check, Check::SyncByteError);
+/// assert_eq!(violations[1].check, Check::TsSyncLoss);
+/// ```
+pub struct Tr101290Analyzer<D: AppDetails = DefaultAppDetails> {
+    parser: MpegTsParser<D>,
+    packet_index: u64,
+    bad_sync_count: u32,
+    last_pat: Option<u64>,
+    last_pmts: HashMap<u16, u64>,
+    last_pids: HashMap<u16, u64>,
+    last_pcrs: HashMap<u16, PcrArrival>,
+    pid_timeout: u64,
+}
+
+impl<D: AppDetails> Tr101290Analyzer<D> {
+    /// Creates an analyzer parsing with `parser`.
+    pub fn new(mut parser: MpegTsParser<D>) -> Self {
+        parser.config.check_continuity = true;
+        parser.config.strict = false;
+        parser.config.lenient = false;
+        Self {
+            parser,
+            packet_index: 0,
+            bad_sync_count: 0,
+            last_pat: None,
+            last_pmts: HashMap::new(),
+            last_pids: HashMap::new(),
+            last_pcrs: HashMap::new(),
+            pid_timeout: DEFAULT_PID_TIMEOUT,
+        }
+    }
+
+    /// Sets the period in 27MHz ticks within which PIDs referenced by a PMT must occur (default
+    /// 5 s).
+    pub fn set_pid_timeout(&mut self, pid_timeout: u64) -> &mut Self {
+        self.pid_timeout = pid_timeout;
+        self
+    }
+
+    /// Underlying parser.
+    pub fn parser(&self) -> &MpegTsParser<D> {
+        &self.parser
+    }
+
+    /// Analyzes a buffer of 188-byte packets arriving at a constant `bitrate` in bits/s,
+    /// continuing the timeline of previously pushed packets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bitrate` is 0.
+    pub fn analyze(&mut self, data: &[u8], bitrate: u64) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for packet in data.chunks_exact(188) {
+            let arrival = (self.packet_index as u128 * 188 * 8 * TICKS_PER_SECOND as u128
+                / bitrate as u128) as u64;
+            violations.extend(self.push(packet.try_into().unwrap(), arrival));
+        }
+        violations
+    }
+
+    /// Analyzes one packet arriving at `arrival` 27MHz ticks, returning the checks it failed.
+    ///
+    /// Arrival times must not decrease.
+    pub fn push(&mut self, packet: &[u8; 188], arrival: u64) -> Vec<Violation> {
+        let mut checks = Vec::new();
+        self.check_packet(packet, arrival, &mut checks);
+        self.check_timeouts(arrival, &mut checks);
+        let packet_index = self.packet_index;
+        self.packet_index += 1;
+        checks
+            .into_iter()
+            .map(|check| Violation {
+                packet_index,
+                arrival,
+                check,
+            })
+            .collect()
+    }
+
+    fn check_packet(&mut self, packet: &[u8; 188], arrival: u64, checks: &mut Vec<Check>) {
+        if self.last_pat.is_none() {
+            self.last_pat = Some(arrival);
+        }
+        if packet[0] != 0x47 {
+            checks.push(Check::SyncByteError);
+            self.bad_sync_count += 1;
+            if self.bad_sync_count == 2 {
+                checks.push(Check::TsSyncLoss);
+            }
+            return;
+        }
+        self.bad_sync_count = 0;
+
+        let pid = u16::from_be_bytes([packet[1], packet[2]]) & 0x1fff;
+        if let Some(last) = self.last_pids.get_mut(&pid) {
+            *last = arrival;
+        }
+        let packet = match self.parser.parse(packet) {
+            Ok(packet) => packet,
+            Err(error) => {
+                if let ErrorDetails::PsiCrcMismatch = error.details {
+                    checks.push(Check::CrcError { pid });
+                }
+                return;
+            }
+        };
+
+        let header = &packet.header;
+        if header.tei() {
+            checks.push(Check::TransportError { pid });
+        }
+        let scrambled =
+            header.has_payload() && header.tsc() != TransportScramblingControl::NotScrambled;
+        let is_pmt = self.parser.known_pmt_pids.contains(&pid);
+        if pid == 0 && scrambled {
+            checks.push(Check::PatError(TableError::Scrambled));
+        } else if is_pmt && scrambled {
+            checks.push(Check::PmtError {
+                pid,
+                error: TableError::Scrambled,
+            });
+        }
+        if let Some(Payload::Psi(psi)) = &packet.payload {
+            let table_id = psi.header.table_id();
+            if pid == 0 && table_id == 0 {
+                self.last_pat = Some(arrival);
+            } else if pid == 0 {
+                checks.push(Check::PatError(TableError::WrongTableId(table_id)));
+            } else if is_pmt && table_id == 2 {
+                self.last_pmts.insert(pid, arrival);
+            }
+        }
+
+        let pcr = packet
+            .adaptation_field
+            .as_ref()
+            .and_then(|field| Some((field.pcr?, field.header.discontinuity())));
+        if let Some((pcr, indicated)) = pcr {
+            let current = PcrArrival { pcr, arrival };
+            if let Some(last) = self.last_pcrs.insert(pid, current) {
+                let interval = arrival - last.arrival;
+                if interval > MAX_PCR_INTERVAL {
+                    checks.push(Check::PcrRepetitionError { pid, interval });
+                }
+                let step = pcr.base.wrapping_sub(last.pcr.base) * 300 + pcr.extension as u64;
+                let step = step as i64 - last.pcr.extension as i64;
+                if indicated {
+                    /* Accuracy is not measured across a signaled discontinuity */
+                } else if step < 0 || step as u64 > MAX_PCR_STEP {
+                    checks.push(Check::PcrDiscontinuityIndicatorError { pid });
+                } else {
+                    let inaccuracy = step - interval as i64;
+                    if inaccuracy.unsigned_abs() > MAX_PCR_INACCURACY {
+                        checks.push(Check::PcrAccuracyError { pid, inaccuracy });
+                    }
+                }
+            }
+        }
+
+        for event in packet.events.iter() {
+            match *event {
+                ParserEvent::ContinuityError { pid, expected, got } => {
+                    checks.push(Check::ContinuityCountError { pid, expected, got });
+                }
+                ParserEvent::StreamAdded { pid, .. } => {
+                    self.last_pids.entry(pid).or_insert(arrival);
+                }
+                ParserEvent::StreamRemoved { pid, .. } => {
+                    self.last_pids.remove(&pid);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Reports tables and PIDs that did not occur in time, once per elapsed period.
+    fn check_timeouts(&mut self, arrival: u64, checks: &mut Vec<Check>) {
+        if let Some(last) = self.last_pat.as_mut() {
+            if arrival - *last > MAX_SECTION_INTERVAL {
+                checks.push(Check::PatError(TableError::Interval));
+                *last = arrival;
+            }
+        }
+        for pid in self.parser.known_pmt_pids.iter() {
+            let last = self.last_pmts.entry(*pid).or_insert(arrival);
+            if arrival - *last > MAX_SECTION_INTERVAL {
+                checks.push(Check::PmtError {
+                    pid: *pid,
+                    error: TableError::Interval,
+                });
+                *last = arrival;
+            }
+        }
+        for (pid, last) in self.last_pids.iter_mut() {
+            if arrival - *last > self.pid_timeout {
+                checks.push(Check::PidError { pid: *pid });
+                *last = arrival;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tr101290() {
+    use crate::testgen::TestStreamGenerator;
+
+    /* 25 fps video units with a PCR each, and repeated PAT and PMT */
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+    gen.add_stream(0x1100, 0x81, 0xbd);
+    for i in 0..10 {
+        gen.write_pat().write_pmt();
+        gen.write_pes(0x1011, Some(i * 3600), None, &[0; 100]);
+    }
+    let arrivals: Vec<u64> = gen
+        .packets()
+        .iter()
+        .scan(0, |arrival, packet| {
+            if packet[1] & 0x40 != 0 && packet[2] == 0x11 {
+                *arrival += 3600 * 300;
+            }
+            Some(*arrival)
+        })
+        .collect();
+
+    let mut analyzer = Tr101290Analyzer::new(MpegTsParser::<DefaultAppDetails>::default());
+    analyzer.set_pid_timeout(TICKS_PER_SECOND / 5);
+    let mut violations = Vec::new();
+    for (packet, arrival) in gen.packets().iter().zip(arrivals.iter()) {
+        violations.extend(analyzer.push(packet, *arrival));
+    }
+    /* Only the audio PID never occurs */
+    let checks: Vec<Check> = violations.iter().map(|violation| violation.check).collect();
+    assert!(checks.contains(&Check::PidError { pid: 0x1100 }));
+    assert!(checks
+        .iter()
+        .all(|check| *check == Check::PidError { pid: 0x1100 }));
+
+    /* A late PCR, a CRC error and a lost PAT */
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(0), None, &[0; 100]);
+    gen.write_pes(0x1011, Some(3600), None, &[0; 100]);
+    gen.inject_crc_error().write_pmt();
+    let packets = gen.packets();
+    let mut analyzer = Tr101290Analyzer::new(MpegTsParser::<DefaultAppDetails>::default());
+    let mut checks = Vec::new();
+    for (i, packet) in packets.iter().enumerate() {
+        /* PCRs 40 ms apart arrive 60 ms apart */
+        let arrival = if i < 3 { 0 } else { TICKS_PER_SECOND * 6 / 100 };
+        checks.extend(analyzer.push(packet, arrival).into_iter().map(|v| v.check));
+    }
+    assert_eq!(
+        checks,
+        [
+            Check::PcrRepetitionError {
+                pid: 0x1011,
+                interval: TICKS_PER_SECOND * 6 / 100
+            },
+            Check::PcrAccuracyError {
+                pid: 0x1011,
+                inaccuracy: -(TICKS_PER_SECOND as i64) * 2 / 100
+            },
+            Check::CrcError { pid: 0x100 },
+        ]
+    );
+    /* Null packet after the PAT and PMT went missing */
+    let mut null = [0xff_u8; 188];
+    null[..4].copy_from_slice(&[0x47, 0x1f, 0xff, 0x10]);
+    let violations = analyzer.push(&null, TICKS_PER_SECOND);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].check, Check::PatError(TableError::Interval));
+    assert_eq!(
+        violations[1].check,
+        Check::PmtError {
+            pid: 0x100,
+            error: TableError::Interval
+        }
+    );
+    assert_eq!(violations[0].packet_index, packets.len() as u64);
+    assert!(analyzer.push(&null, TICKS_PER_SECOND + 1).is_empty());
+}
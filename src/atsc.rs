@@ -0,0 +1,632 @@
+//! Module for ATSC PSIP tables (ATSC A/65) carried in PSI sections on the PSIP base PID.
+//!
+//! Parsed tables are returned as variants of [`PsiData`](crate::PsiData).
+
+use crate::prelude::*;
+use crate::{read_bitfield, AppDetails, Descriptor, LanguageCode, PsiData, Result, SliceReader};
+use core::convert::TryInto;
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+
+/// Base PID carrying the MGT, VCT, RRT and STT.
+pub const PSIP_PID: u16 = 0x1ffb;
+
+/// Table ID of the Master Guide Table.
+pub const MGT_TABLE_ID: u8 = 0xc7;
+/// Table ID of the Terrestrial Virtual Channel Table.
+pub const TVCT_TABLE_ID: u8 = 0xc8;
+/// Table ID of the Cable Virtual Channel Table.
+pub const CVCT_TABLE_ID: u8 = 0xc9;
+/// Table ID of the Rating Region Table.
+pub const RRT_TABLE_ID: u8 = 0xca;
+/// Table ID of the System Time Table.
+pub const STT_TABLE_ID: u8 = 0xcd;
+
+/// Seconds between the Unix epoch and the GPS epoch (1980-01-06 00:00:00 UTC).
+const GPS_EPOCH_UNIX_TIME: i64 = 315_964_800;
+
+/// Reads a descriptor loop preceded by a length of `bits` bits.
+fn read_descriptor_loop<D: AppDetails>(
+    reader: &mut SliceReader<D>,
+    bits: u32,
+) -> Result<Vec<Descriptor>, D> {
+    let length = reader.read_be_u16()? & ((1 << bits) - 1);
+    let mut loop_reader = reader.new_sub_reader(length as usize)?;
+    let mut descriptors = Vec::new();
+    while loop_reader.remaining_len() > 0 {
+        descriptors.push(Descriptor::new_from_reader(&mut loop_reader)?);
+    }
+    Ok(descriptors)
+}
+
+/// Appends a descriptor loop preceded by a length of `bits` bits with reserved bits set.
+fn write_descriptor_loop(descriptors: &[Descriptor], bits: u32, out: &mut Vec<u8>) {
+    let mut data = Vec::new();
+    for descriptor in descriptors.iter() {
+        descriptor.write_to(&mut data);
+    }
+    let reserved = !((1_u16 << bits) - 1);
+    out.extend_from_slice(&(reserved | data.len() as u16).to_be_bytes());
+    out.extend_from_slice(&data);
+}
+
+/// Text in one language of a [`MultipleString`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocalizedString {
+    /// Language of the text.
+    pub language: LanguageCode,
+    /// Text.
+    pub text: String,
+}
+
+/// Multiple string structure carrying text in several languages.
+///
+/// Only uncompressed segments are decoded; segments using Huffman compression are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MultipleString {
+    /// Text of each language.
+    pub strings: Vec<LocalizedString>,
+}
+
+impl MultipleString {
+    /// Text of the first language, or an empty string.
+    pub fn first(&self) -> &str {
+        self.strings.first().map_or("", |s| s.text.as_str())
+    }
+
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let number_strings = reader.read_u8()?;
+        let mut strings = Vec::with_capacity(number_strings as usize);
+        for _ in 0..number_strings {
+            let language = LanguageCode::from_bytes(*reader.read_array_ref::<3>()?);
+            let number_segments = reader.read_u8()?;
+            let mut text = String::new();
+            for _ in 0..number_segments {
+                let compression_type = reader.read_u8()?;
+                let mode = reader.read_u8()?;
+                let number_bytes = reader.read_u8()?;
+                let bytes = reader.read(number_bytes as usize)?;
+                if compression_type != 0 {
+                    warn!("Skipping compressed string segment");
+                } else if mode == 0x3f {
+                    /* UTF-16 */
+                    let units: Vec<u16> = bytes
+                        .chunks_exact(2)
+                        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                        .collect();
+                    text.push_str(&String::from_utf16_lossy(&units));
+                } else if mode <= 0x33 {
+                    /* Mode selects the upper byte of each Unicode character */
+                    text.extend(
+                        bytes
+                            .iter()
+                            .filter_map(|&b| char::from_u32((mode as u32) << 8 | b as u32)),
+                    );
+                } else {
+                    warn!("Skipping string segment of unknown mode {:x}", mode);
+                }
+            }
+            strings.push(LocalizedString { language, text });
+        }
+        Ok(Self { strings })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.strings.len() as u8);
+        for string in self.strings.iter() {
+            out.extend_from_slice(string.language.as_bytes());
+            if string.text.is_empty() {
+                out.push(0);
+                continue;
+            }
+            out.push(1);
+            out.push(0);
+            if string.text.chars().all(|c| (c as u32) < 0x100) {
+                out.push(0);
+                let bytes: Vec<u8> = string.text.chars().map(|c| c as u8).collect();
+                out.push(bytes.len() as u8);
+                out.extend_from_slice(&bytes);
+            } else {
+                out.push(0x3f);
+                let bytes: Vec<u8> = string
+                    .text
+                    .encode_utf16()
+                    .flat_map(|u| u.to_be_bytes())
+                    .collect();
+                out.push(bytes.len() as u8);
+                out.extend_from_slice(&bytes);
+            }
+        }
+    }
+}
+
+/// Table listed in the MGT.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MgtTable {
+    /// Table type (0x0000 = current TVCT, 0x0100-0x017f = EIT-0 to EIT-127, ...).
+    pub table_type: u16,
+    /// PID carrying the table.
+    pub pid: u16,
+    /// Version of the table.
+    pub version: u8,
+    /// Total size of the table sections in bytes.
+    pub number_bytes: u32,
+    /// Table type descriptors.
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Parsed MGT section listing the PIDs and versions of all other PSIP tables.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Mgt {
+    /// PSIP protocol version.
+    pub protocol_version: u8,
+    /// Listed tables.
+    pub tables: Vec<MgtTable>,
+    /// Descriptors of the MGT.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl Mgt {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let protocol_version = reader.read_u8()?;
+        let tables_defined = reader.read_be_u16()?;
+        let mut tables = Vec::with_capacity(tables_defined as usize);
+        for _ in 0..tables_defined {
+            let table_type = reader.read_be_u16()?;
+            let pid = reader.read_be_u16()? & 0x1fff;
+            let version = reader.read_u8()? & 0x1f;
+            let number_bytes = reader.read_be_u32()?;
+            let descriptors = read_descriptor_loop(reader, 12)?;
+            tables.push(MgtTable {
+                table_type,
+                pid,
+                version,
+                number_bytes,
+                descriptors,
+            });
+        }
+        let descriptors = read_descriptor_loop(reader, 12)?;
+        Ok(Self {
+            protocol_version,
+            tables,
+            descriptors,
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.protocol_version);
+        out.extend_from_slice(&(self.tables.len() as u16).to_be_bytes());
+        for table in self.tables.iter() {
+            out.extend_from_slice(&table.table_type.to_be_bytes());
+            out.extend_from_slice(&(0xe000 | table.pid).to_be_bytes());
+            out.push(0xe0 | table.version);
+            out.extend_from_slice(&table.number_bytes.to_be_bytes());
+            write_descriptor_loop(&table.descriptors, 12, out);
+        }
+        write_descriptor_loop(&self.descriptors, 12, out);
+    }
+}
+
+/// Fixed fields of a [`VirtualChannel`] following the short name.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct VirtualChannelHeader {
+    #[skip]
+    pub reserved: B4,
+    /// Major channel number.
+    pub major_channel_number: B10,
+    /// Minor channel number.
+    pub minor_channel_number: B10,
+    /// 0x01 = analog, 0x02 = SCTE mode 1 (64-QAM), 0x03 = SCTE mode 2 (256-QAM),
+    /// 0x04 = 8-VSB, 0x05 = 16-VSB.
+    pub modulation_mode: B8,
+    /// Deprecated carrier frequency in Hz.
+    pub carrier_frequency: B32,
+    /// Transport stream ID carrying the channel.
+    pub channel_tsid: B16,
+    /// Program number of the channel in the PAT.
+    pub program_number: B16,
+    /// Location of the extended text message: 0 = none, 1 = this PID, 2 = channel TSID.
+    pub etm_location: B2,
+    /// Events of the channel are scrambled.
+    pub access_controlled: bool,
+    /// Channel is not directly accessible by the user.
+    pub hidden: bool,
+    /// Cable path select (CVCT only).
+    pub path_select: bool,
+    /// Carried out of band (CVCT only).
+    pub out_of_band: bool,
+    /// Channel is hidden from the program guide.
+    pub hide_guide: bool,
+    #[skip]
+    pub reserved2: B3,
+    /// 0x01 = analog television, 0x02 = digital television, 0x03 = audio, 0x04 = data.
+    pub service_type: B6,
+    /// Source ID linking the channel to EIT and ETT entries.
+    pub source_id: B16,
+}
+
+/// Virtual channel of a TVCT or CVCT.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VirtualChannel {
+    /// Short channel name of up to 7 characters.
+    pub short_name: String,
+    /// Channel fields.
+    pub header: VirtualChannelHeader,
+    /// Channel descriptors.
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Parsed TVCT or CVCT section. The transport stream ID is the table ID extension of the
+/// section.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Payload, PsiData};
+///
+/// fn print_channels(parser: &mut MpegTsParser<DefaultAppDetails>, packet: &[u8; 188]) {
+///     let packet = parser.parse(packet).expect("Parse Error!");
+///     if let Some(Payload::Psi(psi)) = packet.payload {
+///         if let PsiData::Vct(vct) = psi.data {
+///             for channel in vct.channels.iter() {
+///                 println!(
+///                     "{}.{} {}",
+///                     channel.header.major_channel_number(),
+///                     channel.header.minor_channel_number(),
+///                     channel.short_name
+///                 );
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Vct {
+    /// Table is a CVCT rather than a TVCT.
+    pub cable: bool,
+    /// PSIP protocol version.
+    pub protocol_version: u8,
+    /// Virtual channels of the section.
+    pub channels: Vec<VirtualChannel>,
+    /// Additional descriptors of the table.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl Vct {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>, cable: bool) -> Result<Self, D> {
+        let protocol_version = reader.read_u8()?;
+        let num_channels = reader.read_u8()?;
+        let mut channels = Vec::with_capacity(num_channels as usize);
+        for _ in 0..num_channels {
+            let units: Vec<u16> = reader
+                .read(14)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            let short_name = String::from_utf16_lossy(&units)
+                .trim_end_matches('\0')
+                .to_string();
+            let header = read_bitfield!(reader, VirtualChannelHeader);
+            let descriptors = read_descriptor_loop(reader, 10)?;
+            channels.push(VirtualChannel {
+                short_name,
+                header,
+                descriptors,
+            });
+        }
+        let descriptors = read_descriptor_loop(reader, 10)?;
+        Ok(Self {
+            cable,
+            protocol_version,
+            channels,
+            descriptors,
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.protocol_version);
+        out.push(self.channels.len() as u8);
+        for channel in self.channels.iter() {
+            let mut units: Vec<u16> = channel.short_name.encode_utf16().take(7).collect();
+            units.resize(7, 0);
+            for unit in units {
+                out.extend_from_slice(&unit.to_be_bytes());
+            }
+            out.extend_from_slice(&channel.header.into_bytes());
+            write_descriptor_loop(&channel.descriptors, 10, out);
+        }
+        write_descriptor_loop(&self.descriptors, 10, out);
+    }
+}
+
+/// Value of a [`RatingDimension`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RatingValue {
+    /// Abbreviated rating text (e.g. `TV-PG`).
+    pub abbrev_rating_value: MultipleString,
+    /// Full rating text.
+    pub rating_value: MultipleString,
+}
+
+/// Rating dimension of the RRT (e.g. the MPAA rating).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RatingDimension {
+    /// Name of the dimension.
+    pub name: MultipleString,
+    /// Values represent increasing levels of the same scale.
+    pub graduated_scale: bool,
+    /// Values of the dimension.
+    pub values: Vec<RatingValue>,
+}
+
+/// Parsed RRT section. The rating region is the low byte of the table ID extension.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Rrt {
+    /// PSIP protocol version.
+    pub protocol_version: u8,
+    /// Name of the rating region.
+    pub rating_region_name: MultipleString,
+    /// Rating dimensions of the region.
+    pub dimensions: Vec<RatingDimension>,
+    /// Descriptors of the table.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl Rrt {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let protocol_version = reader.read_u8()?;
+        let rating_region_name = read_sized_string(reader)?;
+        let dimensions_defined = reader.read_u8()?;
+        let mut dimensions = Vec::with_capacity(dimensions_defined as usize);
+        for _ in 0..dimensions_defined {
+            let name = read_sized_string(reader)?;
+            let bits = reader.read_u8()?;
+            let mut values = Vec::with_capacity((bits & 0xf) as usize);
+            for _ in 0..bits & 0xf {
+                values.push(RatingValue {
+                    abbrev_rating_value: read_sized_string(reader)?,
+                    rating_value: read_sized_string(reader)?,
+                });
+            }
+            dimensions.push(RatingDimension {
+                name,
+                graduated_scale: bits & 0x10 != 0,
+                values,
+            });
+        }
+        let descriptors = read_descriptor_loop(reader, 10)?;
+        Ok(Self {
+            protocol_version,
+            rating_region_name,
+            dimensions,
+            descriptors,
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.protocol_version);
+        write_sized_string(&self.rating_region_name, out);
+        out.push(self.dimensions.len() as u8);
+        for dimension in self.dimensions.iter() {
+            write_sized_string(&dimension.name, out);
+            out.push(
+                0xe0 | if dimension.graduated_scale { 0x10 } else { 0 }
+                    | (dimension.values.len() as u8 & 0xf),
+            );
+            for value in dimension.values.iter() {
+                write_sized_string(&value.abbrev_rating_value, out);
+                write_sized_string(&value.rating_value, out);
+            }
+        }
+        write_descriptor_loop(&self.descriptors, 10, out);
+    }
+}
+
+/// Reads a multiple string structure preceded by an 8-bit length.
+fn read_sized_string<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<MultipleString, D> {
+    let length = reader.read_u8()?;
+    if length == 0 {
+        return Ok(MultipleString::default());
+    }
+    MultipleString::parse(&mut reader.new_sub_reader(length as usize)?)
+}
+
+/// Appends a multiple string structure preceded by an 8-bit length.
+fn write_sized_string(string: &MultipleString, out: &mut Vec<u8>) {
+    let mut data = Vec::new();
+    string.encode(&mut data);
+    out.push(data.len() as u8);
+    out.extend_from_slice(&data);
+}
+
+/// Parsed STT section.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Stt {
+    /// PSIP protocol version.
+    pub protocol_version: u8,
+    /// Seconds since the GPS epoch (1980-01-06 00:00:00 UTC).
+    pub system_time: u32,
+    /// Leap seconds between GPS and UTC time.
+    pub gps_utc_offset: u8,
+    /// Daylight saving status and transition day and hour.
+    pub daylight_saving: u16,
+    /// Descriptors of the table.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl Stt {
+    /// Current UTC time in seconds since the Unix epoch.
+    pub fn unix_time(&self) -> i64 {
+        self.system_time as i64 + GPS_EPOCH_UNIX_TIME - self.gps_utc_offset as i64
+    }
+
+    /// Daylight saving time is in effect.
+    pub fn ds_status(&self) -> bool {
+        self.daylight_saving & 0x8000 != 0
+    }
+
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let protocol_version = reader.read_u8()?;
+        let system_time = reader.read_be_u32()?;
+        let gps_utc_offset = reader.read_u8()?;
+        let daylight_saving = reader.read_be_u16()?;
+        let mut descriptors = Vec::new();
+        while reader.remaining_len() > 0 {
+            descriptors.push(Descriptor::new_from_reader(reader)?);
+        }
+        Ok(Self {
+            protocol_version,
+            system_time,
+            gps_utc_offset,
+            daylight_saving,
+            descriptors,
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.protocol_version);
+        out.extend_from_slice(&self.system_time.to_be_bytes());
+        out.push(self.gps_utc_offset);
+        out.extend_from_slice(&self.daylight_saving.to_be_bytes());
+        for descriptor in self.descriptors.iter() {
+            descriptor.write_to(out);
+        }
+    }
+}
+
+/// Parses the body of a PSIP table. Returns [`None`] for unhandled table IDs.
+pub(crate) fn parse_psip<D: AppDetails>(
+    table_id: u8,
+    reader: &mut SliceReader<D>,
+) -> Result<Option<PsiData>, D> {
+    Ok(Some(match table_id {
+        MGT_TABLE_ID => PsiData::Mgt(Mgt::parse(reader)?),
+        TVCT_TABLE_ID => PsiData::Vct(Vct::parse(reader, false)?),
+        CVCT_TABLE_ID => PsiData::Vct(Vct::parse(reader, true)?),
+        RRT_TABLE_ID => PsiData::Rrt(Rrt::parse(reader)?),
+        STT_TABLE_ID => PsiData::Stt(Stt::parse(reader)?),
+        _ => return Ok(None),
+    }))
+}
+
+/// Appends the body of a PSIP table to `out`.
+pub(crate) fn encode_psip(data: &PsiData, out: &mut Vec<u8>) {
+    match data {
+        PsiData::Mgt(mgt) => mgt.encode(out),
+        PsiData::Vct(vct) => vct.encode(out),
+        PsiData::Rrt(rrt) => rrt.encode(out),
+        PsiData::Stt(stt) => stt.encode(out),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_psip() {
+    use crate::{
+        DefaultAppDetails, MpegTsParser, MpegTsWriter, Payload, Psi, PsiHeader, PsiTableSyntax,
+    };
+
+    let psip_section = |table_id, data| Psi {
+        header: PsiHeader::new()
+            .with_table_id(table_id)
+            .with_section_syntax_indicator(true)
+            .with_private_bit(true)
+            .with_reserved_bits(0x3),
+        table_syntax: Some(
+            PsiTableSyntax::new()
+                .with_table_id_extension(1)
+                .with_reserved_bits(0x3)
+                .with_current_next_indicator(true),
+        ),
+        data,
+    };
+    let vct = psip_section(
+        TVCT_TABLE_ID,
+        PsiData::Vct(Vct {
+            cable: false,
+            protocol_version: 0,
+            channels: vec![VirtualChannel {
+                short_name: "KQED".to_string(),
+                header: VirtualChannelHeader::new()
+                    .with_major_channel_number(9)
+                    .with_minor_channel_number(1)
+                    .with_modulation_mode(4)
+                    .with_program_number(3)
+                    .with_service_type(2),
+                descriptors: Vec::new(),
+            }],
+            descriptors: Vec::new(),
+        }),
+    );
+    let rrt = psip_section(
+        RRT_TABLE_ID,
+        PsiData::Rrt(Rrt {
+            protocol_version: 0,
+            rating_region_name: MultipleString {
+                strings: vec![LocalizedString {
+                    language: LanguageCode::from_bytes(*b"eng"),
+                    text: "U.S.".to_string(),
+                }],
+            },
+            dimensions: Vec::new(),
+            descriptors: Vec::new(),
+        }),
+    );
+    let stt = psip_section(
+        STT_TABLE_ID,
+        PsiData::Stt(Stt {
+            protocol_version: 0,
+            system_time: 1_000_000_000,
+            gps_utc_offset: 18,
+            daylight_saving: 0,
+            descriptors: Vec::new(),
+        }),
+    );
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    for psi in [&vct, &rrt, &stt].iter() {
+        writer
+            .write_unit(&mut out, PSIP_PID, &psi.to_payload_unit(), None)
+            .unwrap();
+    }
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut tables = Vec::new();
+    for (packet, expected) in out.chunks(188).zip([&vct, &rrt, &stt].iter()) {
+        match parser.parse(packet.try_into().unwrap()).unwrap().payload {
+            Some(Payload::Psi(psi)) => {
+                assert_eq!(psi.to_section(), expected.to_section());
+                tables.push(psi.data);
+            }
+            _ => panic!("PSIP table not parsed"),
+        }
+    }
+    match &tables[0] {
+        PsiData::Vct(vct) => {
+            assert_eq!(vct.channels[0].short_name, "KQED");
+            assert_eq!(vct.channels[0].header.major_channel_number(), 9);
+            assert_eq!(vct.channels[0].header.minor_channel_number(), 1);
+        }
+        _ => panic!("VCT not parsed"),
+    }
+    match &tables[1] {
+        PsiData::Rrt(rrt) => assert_eq!(rrt.rating_region_name.first(), "U.S."),
+        _ => panic!("RRT not parsed"),
+    }
+    match &tables[2] {
+        PsiData::Stt(stt) => assert_eq!(stt.unix_time(), 1_315_964_782),
+        _ => panic!("STT not parsed"),
+    }
+}
@@ -0,0 +1,200 @@
+//! Module for the MovieObject.bdmv file found in the `BDMV` directory.
+//!
+//! Movie objects are the navigation programs run by the player for titles, the top menu, and
+//! the first play. Each one is a table of [`MObjCmd`] commands.
+
+use super::mobj::MObjCmd;
+use super::{BdavAppDetails, BdavErrorDetails};
+use crate::prelude::*;
+use crate::{read_bitfield, ErrorDetails, Result, SliceReader};
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+
+/// Flags of one [`MovieObject`].
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct MovieObjectFlags {
+    /// Playback may be resumed after a menu call.
+    pub resume_intention_flag: bool,
+    /// Menu calls are prohibited.
+    pub menu_call_mask: bool,
+    /// Title search is prohibited.
+    pub title_search_mask: bool,
+    #[skip]
+    pub reserved: B13,
+}
+
+/// Navigation program of one title, menu, or first play.
+#[derive(Debug)]
+pub struct MovieObject {
+    /// Object flags.
+    pub flags: MovieObjectFlags,
+    /// Navigation commands.
+    pub nav_cmds: Vec<MObjCmd>,
+}
+
+impl MovieObject {
+    fn parse<D: BdavAppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let flags = read_bitfield!(reader, MovieObjectFlags);
+        let num_nav_cmds = reader.read_be_u16()?;
+        let mut nav_cmds = Vec::with_capacity(num_nav_cmds as usize);
+        for _ in 0..num_nav_cmds {
+            nav_cmds.push(MObjCmd::parse(reader)?);
+        }
+        Ok(Self { flags, nav_cmds })
+    }
+}
+
+/// Entry of [`ExtensionData`].
+#[derive(Debug)]
+pub struct ExtensionDataEntry {
+    /// ID1 of the extension type.
+    pub ext_data_type: u16,
+    /// ID2 of the extension version.
+    pub ext_data_version: u16,
+    /// Unparsed extension data.
+    pub data: Vec<u8>,
+}
+
+/// Vendor and format extensions appended to BDMV files.
+#[derive(Debug)]
+pub struct ExtensionData {
+    /// Extension entries.
+    pub entries: Vec<ExtensionDataEntry>,
+}
+
+impl ExtensionData {
+    fn parse<D: BdavAppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let length = reader.read_be_u32()? as usize;
+        if length == 0 {
+            return Ok(Self {
+                entries: Vec::new(),
+            });
+        }
+        let block_data = reader.peek(length)?;
+        let mut reader = reader.new_sub_reader(length)?;
+        reader.skip(4 + 3)?;
+        let num_entries = reader.read_u8()?;
+        let mut entries = Vec::with_capacity(num_entries as usize);
+        for _ in 0..num_entries {
+            let ext_data_type = reader.read_be_u16()?;
+            let ext_data_version = reader.read_be_u16()?;
+            /* Start address is relative to the length field */
+            let start = (reader.read_be_u32()? as usize).saturating_sub(4);
+            let ext_length = reader.read_be_u32()? as usize;
+            let data = match block_data.get(start..start + ext_length) {
+                Some(data) => data.to_vec(),
+                None => {
+                    warn!("Extension data entry out of bounds");
+                    return Err(
+                        reader.make_error(ErrorDetails::AppError(BdavErrorDetails::BadBdmv))
+                    );
+                }
+            };
+            entries.push(ExtensionDataEntry {
+                ext_data_type,
+                ext_data_version,
+                data,
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+/// Parsed MovieObject.bdmv file.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::bdmv::MovieObjectFile;
+/// use mpegts_io::bdav::DefaultBdavAppDetails;
+/// use std::fs::read;
+///
+/// let data = read("BDMV/MovieObject.bdmv").expect("Unable to read!");
+/// let file = MovieObjectFile::parse::<DefaultBdavAppDetails>(&data).expect("Parse Error!");
+/// for (i, movie_object) in file.movie_objects.iter().enumerate() {
+///     println!("Movie object {}:", i);
+///     for cmd in movie_object.nav_cmds.iter() {
+///         println!("  {}", cmd);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MovieObjectFile {
+    /// Format version string (e.g. `0200`).
+    pub version: [u8; 4],
+    /// Movie objects, indexed by the object IDs referenced from index.bdmv and `jump_object`.
+    pub movie_objects: Vec<MovieObject>,
+    /// Extension data, if present.
+    pub extension_data: Option<ExtensionData>,
+}
+
+impl MovieObjectFile {
+    /// Parses the contents of a MovieObject.bdmv file.
+    pub fn parse<D: BdavAppDetails>(data: &[u8]) -> Result<Self, D> {
+        let mut reader = SliceReader::<D>::new(data);
+        if reader.read(4)? != b"MOBJ" {
+            warn!("Missing MovieObject type indicator");
+            return Err(reader.make_error(ErrorDetails::AppError(BdavErrorDetails::BadBdmv)));
+        }
+        let version = *reader.read_array_ref::<4>()?;
+        let extension_data_start = reader.read_be_u32()? as usize;
+        reader.skip(28)?;
+
+        let length = reader.read_be_u32()? as usize;
+        let mut objects_reader = reader.new_sub_reader(length)?;
+        objects_reader.skip(4)?;
+        let num_movie_objects = objects_reader.read_be_u16()?;
+        let mut movie_objects = Vec::with_capacity(num_movie_objects as usize);
+        for _ in 0..num_movie_objects {
+            movie_objects.push(MovieObject::parse(&mut objects_reader)?);
+        }
+
+        let extension_data = if extension_data_start != 0 {
+            let mut ext_reader = SliceReader::<D>::new(data);
+            ext_reader.skip(extension_data_start)?;
+            Some(ExtensionData::parse(&mut ext_reader)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            version,
+            movie_objects,
+            extension_data,
+        })
+    }
+}
+
+#[test]
+fn test_movie_object_file() {
+    use super::DefaultBdavAppDetails;
+
+    let cmds = [
+        MObjCmd::assemble("move r0, 1").unwrap(),
+        MObjCmd::assemble("jump_title r0").unwrap(),
+    ];
+    let mut objects = Vec::new();
+    objects.extend_from_slice(&[0, 0, 0, 0, 0, 1, 0x80, 0]);
+    objects.extend_from_slice(&(cmds.len() as u16).to_be_bytes());
+    for cmd in cmds.iter() {
+        objects.extend_from_slice(&cmd.into_bytes());
+    }
+
+    let mut data = Vec::new();
+    data.extend_from_slice(b"MOBJ0200");
+    data.extend_from_slice(&0_u32.to_be_bytes());
+    data.extend_from_slice(&[0; 28]);
+    data.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+    data.extend_from_slice(&objects);
+
+    let file = MovieObjectFile::parse::<DefaultBdavAppDetails>(&data).unwrap();
+    assert_eq!(&file.version, b"0200");
+    assert_eq!(file.movie_objects.len(), 1);
+    assert!(file.movie_objects[0].flags.resume_intention_flag());
+    assert_eq!(
+        file.movie_objects[0].nav_cmds[1].to_string(),
+        "jump_title r0"
+    );
+    assert!(file.extension_data.is_none());
+}
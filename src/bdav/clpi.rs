@@ -0,0 +1,250 @@
+//! Module for clip information (CLPI) files found in the `BDMV/CLIPINF` directory.
+//!
+//! Currently only the characteristic point information (CPI) is parsed, exposing the EP_map used
+//! to locate entry points of a clip's M2TS file by presentation time.
+
+use super::{BdavAppDetails, BdavErrorDetails};
+use crate::prelude::*;
+use crate::{read_bitfield, ErrorDetails, Result, SliceReader};
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+#[cfg(feature = "std")]
+use std::io::{Seek, SeekFrom};
+
+/// Size of one source packet in an M2TS file.
+const SOURCE_PACKET_SIZE: u64 = 192;
+
+/// Header of EP_map entries for one stream.
+#[bitfield]
+#[derive(Debug)]
+struct EpMapStreamHeader {
+    stream_pid: B16,
+    #[skip]
+    reserved: B10,
+    ep_stream_type: B4,
+    num_coarse_entries: B16,
+    num_fine_entries: B18,
+    start_address: B32,
+}
+
+/// Coarse EP_map entry holding the upper bits of a PTS and SPN.
+#[bitfield]
+#[derive(Debug)]
+pub struct EpCoarseEntry {
+    pub ref_ep_fine_id: B18,
+    pub pts_ep: B14,
+    pub spn_ep: B32,
+}
+
+/// Fine EP_map entry holding the lower bits of a PTS and SPN.
+#[bitfield]
+#[derive(Debug)]
+pub struct EpFineEntry {
+    pub is_angle_change_point: bool,
+    pub i_end_position_offset: B3,
+    pub pts_ep: B11,
+    pub spn_ep: B17,
+}
+
+/// Entry point resolved from a coarse and fine EP_map entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    /// 90kHz presentation time stamp of the entry point. May be formatted with
+    /// [`pts_format_args`](crate::pts_format_args).
+    pub pts: u64,
+    /// Source packet number of the entry point within the M2TS file.
+    pub spn: u32,
+    /// The entry point may be used to change angles.
+    pub angle_change_point: bool,
+}
+
+impl EntryPoint {
+    /// Byte offset of the entry point within the M2TS file.
+    pub fn byte_offset(&self) -> u64 {
+        self.spn as u64 * SOURCE_PACKET_SIZE
+    }
+}
+
+/// EP_map entries for one stream (normally the video stream).
+#[derive(Debug)]
+pub struct EpMapStream {
+    /// PID of the stream.
+    pub pid: u16,
+    /// Stream type code (1 = video).
+    pub ep_stream_type: u8,
+    /// Coarse entries.
+    pub coarse_entries: Vec<EpCoarseEntry>,
+    /// Fine entries.
+    pub fine_entries: Vec<EpFineEntry>,
+}
+
+impl EpMapStream {
+    fn parse<D: BdavAppDetails>(header: &EpMapStreamHeader, ep_map_data: &[u8]) -> Result<Self, D> {
+        let mut reader = SliceReader::<D>::new(ep_map_data);
+        reader.skip(header.start_address() as usize)?;
+        let stream_start = reader.remaining_len();
+        let fine_start = reader.read_be_u32()? as usize;
+
+        let mut coarse_entries = Vec::with_capacity(header.num_coarse_entries() as usize);
+        for _ in 0..header.num_coarse_entries() {
+            coarse_entries.push(read_bitfield!(reader, EpCoarseEntry));
+        }
+
+        let consumed = stream_start - reader.remaining_len();
+        if fine_start < consumed {
+            warn!("EP_map fine entries overlap coarse entries");
+            return Err(reader.make_error(ErrorDetails::AppError(BdavErrorDetails::BadClpi)));
+        }
+        reader.skip(fine_start - consumed)?;
+        let mut fine_entries = Vec::with_capacity(header.num_fine_entries() as usize);
+        for _ in 0..header.num_fine_entries() {
+            fine_entries.push(read_bitfield!(reader, EpFineEntry));
+        }
+
+        Ok(Self {
+            pid: header.stream_pid(),
+            ep_stream_type: header.ep_stream_type(),
+            coarse_entries,
+            fine_entries,
+        })
+    }
+
+    /// Resolves all entry points in stream order.
+    pub fn entry_points(&self) -> Vec<EntryPoint> {
+        let mut out = Vec::with_capacity(self.fine_entries.len());
+        for (i, coarse) in self.coarse_entries.iter().enumerate() {
+            let fine_end = self
+                .coarse_entries
+                .get(i + 1)
+                .map_or(self.fine_entries.len(), |next| {
+                    next.ref_ep_fine_id() as usize
+                });
+            let fine_start = coarse.ref_ep_fine_id() as usize;
+            for fine in self.fine_entries.get(fine_start..fine_end).unwrap_or(&[]) {
+                /* Bit 19 of the PTS is present in both entries */
+                out.push(EntryPoint {
+                    pts: ((coarse.pts_ep() as u64 & !0x1) << 19) + ((fine.pts_ep() as u64) << 9),
+                    spn: (coarse.spn_ep() & !0x1ffff) + fine.spn_ep(),
+                    angle_change_point: fine.is_angle_change_point(),
+                });
+            }
+        }
+        out
+    }
+
+    /// Finds the last entry point at or before `pts`.
+    pub fn lookup(&self, pts: u64) -> Option<EntryPoint> {
+        let entry_points = self.entry_points();
+        let idx = entry_points.partition_point(|ep| ep.pts <= pts);
+        idx.checked_sub(1).map(|idx| entry_points[idx])
+    }
+
+    /// Positions an M2TS reader at the last entry point at or before `pts`.
+    ///
+    /// Returns the entry point that was seeked to, or [`None`] if `pts` precedes all entry points
+    /// (the reader is left unchanged).
+    #[cfg(feature = "std")]
+    pub fn seek_to_entry_point<R: Seek>(
+        &self,
+        reader: &mut R,
+        pts: u64,
+    ) -> std::io::Result<Option<EntryPoint>> {
+        match self.lookup(pts) {
+            Some(entry_point) => {
+                reader.seek(SeekFrom::Start(entry_point.byte_offset()))?;
+                Ok(Some(entry_point))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Map of presentation times to source packet numbers for each stream of a clip.
+#[derive(Debug)]
+pub struct EpMap {
+    /// Per-stream entries.
+    pub streams: Vec<EpMapStream>,
+}
+
+impl EpMap {
+    fn parse<D: BdavAppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let ep_map_data = reader.peek(reader.remaining_len())?;
+        reader.skip(1)?;
+        let num_streams = reader.read_u8()?;
+        let mut headers = Vec::with_capacity(num_streams as usize);
+        for _ in 0..num_streams {
+            headers.push(read_bitfield!(reader, EpMapStreamHeader));
+        }
+        let mut streams = Vec::with_capacity(headers.len());
+        for header in headers.iter() {
+            streams.push(EpMapStream::parse(header, ep_map_data)?);
+        }
+        Ok(Self { streams })
+    }
+
+    /// Entries of the stream with the given PID.
+    pub fn stream(&self, pid: u16) -> Option<&EpMapStream> {
+        self.streams.iter().find(|s| s.pid == pid)
+    }
+
+    /// Finds the last entry point at or before `pts` in the first stream of the map.
+    pub fn lookup(&self, pts: u64) -> Option<EntryPoint> {
+        self.streams.first().and_then(|s| s.lookup(pts))
+    }
+}
+
+/// Parsed clip information file.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::clpi::ClipInfo;
+/// use mpegts_io::bdav::DefaultBdavAppDetails;
+/// use std::fs::{read, File};
+///
+/// let data = read("BDMV/CLIPINF/00000.clpi").expect("Unable to read!");
+/// let clip_info = ClipInfo::parse::<DefaultBdavAppDetails>(&data).expect("Parse Error!");
+/// let mut m2ts = File::open("BDMV/STREAM/00000.m2ts").expect("Unable to open!");
+/// if let Some(stream) = clip_info.ep_map.as_ref().and_then(|m| m.streams.first()) {
+///     /* Seek to 1 minute */
+///     stream.seek_to_entry_point(&mut m2ts, 60 * 90000).expect("IO Error!");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ClipInfo {
+    /// Format version string (e.g. `0200`).
+    pub version: [u8; 4],
+    /// EP_map of the clip, if the CPI is present and of the EP_map type.
+    pub ep_map: Option<EpMap>,
+}
+
+impl ClipInfo {
+    /// Parses the contents of a `.clpi` file.
+    pub fn parse<D: BdavAppDetails>(data: &[u8]) -> Result<Self, D> {
+        let mut reader = SliceReader::<D>::new(data);
+        if reader.read(4)? != b"HDMV" {
+            warn!("Missing CLPI type indicator");
+            return Err(reader.make_error(ErrorDetails::AppError(BdavErrorDetails::BadClpi)));
+        }
+        let version = *reader.read_array_ref::<4>()?;
+        reader.skip(8)?;
+        let cpi_start = reader.read_be_u32()? as usize;
+
+        let mut cpi_reader = SliceReader::<D>::new(data);
+        cpi_reader.skip(cpi_start)?;
+        let cpi_length = cpi_reader.read_be_u32()? as usize;
+        let ep_map = if cpi_start != 0 && cpi_length >= 2 {
+            let mut cpi_reader = cpi_reader.new_sub_reader(cpi_length)?;
+            let cpi_type = cpi_reader.read_be_u16()? & 0xf;
+            if cpi_type == 1 {
+                Some(EpMap::parse(&mut cpi_reader)?)
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        Ok(Self { version, ep_map })
+    }
+}
@@ -0,0 +1,359 @@
+//! Module for checking PG streams against the HDMV graphics decoder model.
+//!
+//! The model decodes objects into the decoded object buffer at 128 Mbit/s and draws windows to
+//! the graphics plane at 256 Mbit/s. An epoch start also clears the whole plane. Display sets must
+//! be decoded and drawn before their PTS, and the objects of an epoch must fit in the 4 MiB
+//! decoded object buffer.
+
+use super::pg::{DisplaySet, PgCompositionUnitState, PgSegmentData};
+use crate::prelude::*;
+use crate::Timestamp90k;
+use hashbrown::HashMap;
+
+/// Size of the decoded object buffer in bytes.
+pub const DECODED_OBJECT_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Rate of decoding objects into the decoded object buffer in bits per second.
+const OBJECT_DECODE_RATE: u64 = 128_000_000;
+
+/// Rate of drawing to the graphics plane in bits per second.
+const PLANE_DRAW_RATE: u64 = 256_000_000;
+
+/// Maximum number of objects of an epoch.
+const MAX_OBJECTS: usize = 64;
+
+/// Maximum number of palettes of an epoch.
+const MAX_PALETTES: usize = 8;
+
+/// 90kHz ticks to transfer `pixels` 8-bit pixels at `rate` bits per second, rounded up.
+fn transfer_ticks(pixels: u64, rate: u64) -> u64 {
+    (90000 * 8 * pixels).div_ceil(rate)
+}
+
+/// Way in which a display set violates the decoder model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgModelViolation {
+    /// Decoding and drawing take longer than the time since the previous display set.
+    MissedPts {
+        /// Ticks needed to decode and draw the display set.
+        required: u64,
+        /// Ticks between the previous display set and this one.
+        available: u64,
+    },
+    /// The objects of the epoch exceed the decoded object buffer.
+    ObjectBufferOverflow {
+        /// Bytes of decoded objects.
+        occupancy: usize,
+    },
+    /// The epoch defines more than 64 objects.
+    TooManyObjects(usize),
+    /// The epoch defines more than 8 palettes.
+    TooManyPalettes(usize),
+    /// The composition places more than 2 objects or the epoch defines more than 2 windows.
+    TooManyWindowsOrPlacements,
+    /// The display set has no PG composition or PTS.
+    MissingComposition,
+}
+
+/// Decode timing and buffer occupancy of one display set.
+#[derive(Debug, Clone)]
+pub struct PgDisplaySetTiming {
+    /// Presentation time stamp of the display set.
+    pub pts: Option<Timestamp90k>,
+    /// Ticks to clear the plane, or the windows outside of an epoch start.
+    pub plane_initialization: u64,
+    /// Ticks to decode the objects defined in the display set.
+    pub object_decode: u64,
+    /// Ticks to draw the windows of the composition.
+    pub window_drawing: u64,
+    /// Bytes of the decoded object buffer occupied after the display set.
+    pub object_buffer_occupancy: usize,
+    /// Violations of the decoder model.
+    pub violations: Vec<PgModelViolation>,
+}
+
+impl PgDisplaySetTiming {
+    /// Ticks between the earliest decoding time and the PTS of the display set.
+    pub fn decode_duration(&self) -> u64 {
+        self.plane_initialization + self.object_decode + self.window_drawing
+    }
+
+    /// Earliest decoding time of the display set so that it is presented at its PTS.
+    pub fn dts(&self) -> Option<Timestamp90k> {
+        let pts = self.pts?;
+        Some(Timestamp90k(pts.0.saturating_sub(self.decode_duration())))
+    }
+}
+
+/// Decoder model fed with the display sets of one PG stream in order.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::decoder_model::PgDecoderModel;
+/// use mpegts_io::bdav::pg::DisplaySet;
+///
+/// # let display_sets: Vec<DisplaySet> = vec![];
+/// let mut model = PgDecoderModel::default();
+/// for display_set in display_sets.iter() {
+///     let timing = model.push(display_set);
+///     for violation in timing.violations.iter() {
+///         println!("{:?}: {:?}", timing.pts, violation);
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PgDecoderModel {
+    windows: HashMap<u8, (u16, u16)>,
+    objects: HashMap<u16, (u16, u16)>,
+    palettes: HashMap<u8, ()>,
+    previous_pts: Option<Timestamp90k>,
+}
+
+impl PgDecoderModel {
+    /// Computes the timing of the next display set and updates the model.
+    pub fn push(&mut self, display_set: &DisplaySet) -> PgDisplaySetTiming {
+        let mut timing = PgDisplaySetTiming {
+            pts: display_set.pts,
+            plane_initialization: 0,
+            object_decode: 0,
+            window_drawing: 0,
+            object_buffer_occupancy: 0,
+            violations: Vec::new(),
+        };
+        let composition = match (display_set.pg_composition(), display_set.pts) {
+            (Some(composition), Some(_)) => composition,
+            _ => {
+                timing.violations.push(PgModelViolation::MissingComposition);
+                return timing;
+            }
+        };
+        let epoch_start =
+            composition.composition_descriptor.state == PgCompositionUnitState::EpochStart;
+        if epoch_start {
+            self.windows.clear();
+            self.objects.clear();
+            self.palettes.clear();
+            self.previous_pts = None;
+        }
+
+        for segment in display_set.segments.iter() {
+            match segment {
+                PgSegmentData::PgsWindow(window) => {
+                    for window in window.windows.iter() {
+                        self.windows
+                            .insert(window.id, (window.width, window.height));
+                    }
+                }
+                PgSegmentData::PgsPalette(palette) => {
+                    self.palettes.insert(palette.id, ());
+                }
+                PgSegmentData::PgsObject(object) => {
+                    if let Some(data) = object.data.as_ref() {
+                        self.objects.insert(object.id, (data.width, data.height));
+                        timing.object_decode += transfer_ticks(
+                            data.width as u64 * data.height as u64,
+                            OBJECT_DECODE_RATE,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let window_pixels = self
+            .windows
+            .values()
+            .map(|(width, height)| *width as u64 * *height as u64)
+            .sum::<u64>();
+        timing.plane_initialization = if epoch_start {
+            let video = &composition.video_descriptor;
+            transfer_ticks(
+                video.video_width as u64 * video.video_height as u64,
+                PLANE_DRAW_RATE,
+            )
+        } else {
+            transfer_ticks(window_pixels, PLANE_DRAW_RATE)
+        };
+        if !composition.composition_objects.is_empty() {
+            timing.window_drawing = transfer_ticks(window_pixels, PLANE_DRAW_RATE);
+        }
+        timing.object_buffer_occupancy = self
+            .objects
+            .values()
+            .map(|(width, height)| *width as usize * *height as usize)
+            .sum();
+
+        let pts = display_set.pts.unwrap();
+        if let Some(previous_pts) = self.previous_pts {
+            let available = pts.wrapping_sub(previous_pts);
+            if timing.decode_duration() > available {
+                timing.violations.push(PgModelViolation::MissedPts {
+                    required: timing.decode_duration(),
+                    available,
+                });
+            }
+        }
+        self.previous_pts = Some(pts);
+        if timing.object_buffer_occupancy > DECODED_OBJECT_BUFFER_SIZE {
+            timing
+                .violations
+                .push(PgModelViolation::ObjectBufferOverflow {
+                    occupancy: timing.object_buffer_occupancy,
+                });
+        }
+        if self.objects.len() > MAX_OBJECTS {
+            timing
+                .violations
+                .push(PgModelViolation::TooManyObjects(self.objects.len()));
+        }
+        if self.palettes.len() > MAX_PALETTES {
+            timing
+                .violations
+                .push(PgModelViolation::TooManyPalettes(self.palettes.len()));
+        }
+        if self.windows.len() > 2 || composition.composition_objects.len() > 2 {
+            timing
+                .violations
+                .push(PgModelViolation::TooManyWindowsOrPlacements);
+        }
+        timing
+    }
+}
+
+#[test]
+fn test_pg_decoder_model() {
+    use super::pg::{
+        FrameRate, PgCompositionDescriptor, PgCompositionObject, PgSequenceDescriptor,
+        PgVideoDescriptor, PgWindow, PgsEndOfDisplay, PgsObject, PgsObjectData, PgsPgComposition,
+        PgsWindow,
+    };
+
+    let display_set = |pts, state, placements: u16, mut segments: Vec<PgSegmentData>| {
+        segments.insert(
+            0,
+            PgSegmentData::PgsPgComposition(PgsPgComposition {
+                video_descriptor: PgVideoDescriptor {
+                    video_width: 1920,
+                    video_height: 1080,
+                    frame_rate: FrameRate::Drop24,
+                },
+                composition_descriptor: PgCompositionDescriptor { number: 0, state },
+                palette_update_flag: false,
+                palette_id_ref: 0,
+                composition_objects: (0..placements)
+                    .map(|object_id_ref| PgCompositionObject {
+                        object_id_ref,
+                        window_id_ref: 0,
+                        forced_on_flag: false,
+                        x: 0,
+                        y: 0,
+                        crop: None,
+                    })
+                    .collect(),
+            }),
+        );
+        segments.push(PgSegmentData::PgsEndOfDisplay(PgsEndOfDisplay {}));
+        DisplaySet {
+            pts: Some(Timestamp90k(pts)),
+            segments,
+        }
+    };
+    let object = |id, width, height| {
+        PgSegmentData::PgsObject(PgsObject {
+            id,
+            version: 0,
+            sequence_descriptor: PgSequenceDescriptor {
+                first_in_seq: true,
+                last_in_seq: true,
+            },
+            data: Some(PgsObjectData {
+                width,
+                height,
+                data: Vec::new(),
+            }),
+        })
+    };
+    let window = PgSegmentData::PgsWindow(PgsWindow {
+        windows: vec![PgWindow {
+            id: 0,
+            x: 0,
+            y: 880,
+            width: 1920,
+            height: 200,
+        }],
+    });
+
+    let mut model = PgDecoderModel::default();
+    /* Epoch start clearing the plane, decoding one object and drawing one window */
+    let timing = model.push(&display_set(
+        100_000,
+        PgCompositionUnitState::EpochStart,
+        1,
+        vec![window, object(0, 1000, 100)],
+    ));
+    assert_eq!(
+        (
+            timing.plane_initialization,
+            timing.object_decode,
+            timing.window_drawing
+        ),
+        (5832, 563, 1080)
+    );
+    assert_eq!(timing.dts(), Some(Timestamp90k(100_000 - 7475)));
+    assert_eq!(timing.object_buffer_occupancy, 100_000);
+    assert!(timing.violations.is_empty());
+
+    /* Clearing the window in time, then redrawing too soon */
+    let timing = model.push(&display_set(
+        105_000,
+        PgCompositionUnitState::Incremental,
+        0,
+        Vec::new(),
+    ));
+    assert_eq!(timing.decode_duration(), 1080);
+    assert!(timing.violations.is_empty());
+    let timing = model.push(&display_set(
+        106_000,
+        PgCompositionUnitState::Incremental,
+        1,
+        Vec::new(),
+    ));
+    assert_eq!(
+        timing.violations,
+        [PgModelViolation::MissedPts {
+            required: 2160,
+            available: 1000,
+        }]
+    );
+
+    /* Objects exceeding the decoded object buffer and placements exceeding the limit */
+    let timing = model.push(&display_set(
+        200_000,
+        PgCompositionUnitState::Incremental,
+        3,
+        vec![
+            object(1, 2000, 1100),
+            object(2, 2000, 1100),
+            object(3, 1, 1),
+        ],
+    ));
+    assert!(timing
+        .violations
+        .contains(&PgModelViolation::ObjectBufferOverflow {
+            occupancy: 4_500_001
+        }));
+    assert!(timing
+        .violations
+        .contains(&PgModelViolation::TooManyWindowsOrPlacements));
+
+    assert_eq!(
+        model
+            .push(&DisplaySet {
+                pts: None,
+                segments: Vec::new(),
+            })
+            .violations,
+        [PgModelViolation::MissingComposition]
+    );
+}
@@ -0,0 +1,320 @@
+//! Module for descriptors found in the PMT and SIT of BDAV (M2TS) streams.
+//!
+//! Blu-ray authoring tools register every elementary stream with an `HDMV` registration
+//! descriptor carrying its stream coding type and format attributes, which allows listing the
+//! streams of a clip without inspecting the elementary streams themselves.
+
+use crate::prelude::*;
+use crate::Descriptor;
+use core::convert::TryInto;
+use modular_bitfield_msb::prelude::*;
+
+/// Tag of the registration descriptor.
+pub const REGISTRATION_DESCRIPTOR_TAG: u8 = 0x05;
+/// Tag of the partial transport stream descriptor carried in the SIT.
+pub const PARTIAL_TS_DESCRIPTOR_TAG: u8 = 0x63;
+/// Tag of the AC-3 audio descriptor.
+pub const AC3_AUDIO_DESCRIPTOR_TAG: u8 = 0x81;
+/// Tag of the HDMV copy control descriptor.
+pub const COPY_CONTROL_DESCRIPTOR_TAG: u8 = 0x88;
+
+/// Format attributes of an elementary stream registered as `HDMV`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HdmvStreamAttributes {
+    /// Video stream attributes.
+    Video {
+        /// 1 = 480i, 2 = 576i, 3 = 480p, 4 = 1080i, 5 = 720p, 6 = 1080p, 7 = 576p, 8 = 2160p.
+        video_format: u8,
+        /// 1 = 23.976, 2 = 24, 3 = 25, 4 = 29.97, 6 = 50, 7 = 59.94.
+        frame_rate: u8,
+        /// 2 = 4:3, 3 = 16:9.
+        aspect_ratio: u8,
+    },
+    /// Audio stream attributes.
+    Audio {
+        /// 1 = mono, 3 = stereo, 6 = multichannel, 12 = stereo and multichannel.
+        audio_presentation_type: u8,
+        /// 1 = 48kHz, 4 = 96kHz, 5 = 192kHz, 12 = 48kHz core with 192kHz extension,
+        /// 14 = 48kHz core with 96kHz extension.
+        sampling_frequency: u8,
+    },
+    /// Graphics, text subtitle or unknown stream without attributes.
+    Other,
+}
+
+/// Stream coding information of an elementary stream registered as `HDMV`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HdmvStreamCoding {
+    /// Stream coding type, matching the stream type of the PMT (e.g. 0x1b for AVC video, 0x80
+    /// for LPCM audio or 0x90 for presentation graphics).
+    pub stream_coding_type: u8,
+    /// Format attributes.
+    pub attributes: HdmvStreamAttributes,
+}
+
+impl HdmvStreamCoding {
+    /// Checks whether the stream coding type denotes a video stream.
+    pub fn is_video(&self) -> bool {
+        matches!(
+            self.stream_coding_type,
+            0x01 | 0x02 | 0x1b | 0x20 | 0x24 | 0xea
+        )
+    }
+
+    /// Checks whether the stream coding type denotes an audio stream.
+    pub fn is_audio(&self) -> bool {
+        matches!(
+            self.stream_coding_type,
+            0x03 | 0x04 | 0x80..=0x86 | 0xa1 | 0xa2
+        )
+    }
+
+    /// Checks whether the stream coding type denotes a graphics or text subtitle stream.
+    pub fn is_graphics(&self) -> bool {
+        matches!(self.stream_coding_type, 0x90..=0x92)
+    }
+}
+
+/// Registration descriptor (tag 0x05) with the `HDMV` format identifier.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HdmvRegistrationDescriptor {
+    /// Stream coding information, absent in the program-level descriptor.
+    pub coding: Option<HdmvStreamCoding>,
+}
+
+impl HdmvRegistrationDescriptor {
+    /// Parses an `HDMV` registration descriptor. Returns [`None`] for other or malformed
+    /// descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != REGISTRATION_DESCRIPTOR_TAG || descriptor.data.get(..4)? != b"HDMV" {
+            return None;
+        }
+        /* A stuffing byte precedes the stream coding type */
+        let data = &descriptor.data[4..];
+        if data.len() < 2 {
+            return Some(Self { coding: None });
+        }
+        let mut coding = HdmvStreamCoding {
+            stream_coding_type: data[1],
+            attributes: HdmvStreamAttributes::Other,
+        };
+        if let Some(&attributes) = data.get(2) {
+            if coding.is_video() {
+                coding.attributes = HdmvStreamAttributes::Video {
+                    video_format: attributes >> 4,
+                    frame_rate: attributes & 0xf,
+                    aspect_ratio: data.get(3)? >> 4,
+                };
+            } else if coding.is_audio() {
+                coding.attributes = HdmvStreamAttributes::Audio {
+                    audio_presentation_type: attributes >> 4,
+                    sampling_frequency: attributes & 0xf,
+                };
+            }
+        }
+        Some(Self {
+            coding: Some(coding),
+        })
+    }
+}
+
+/// HDMV copy control descriptor (tag 0x88) in the program info of a BD PMT.
+#[derive(Debug, Clone)]
+pub struct CopyControlDescriptor {
+    /// Copy control system, 0x0fff for the HDMV system.
+    pub ca_system_id: u16,
+    /// Copy control information of the system.
+    pub private_data: Vec<u8>,
+}
+
+impl CopyControlDescriptor {
+    /// Parses a copy control descriptor. Returns [`None`] for other or malformed descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != COPY_CONTROL_DESCRIPTOR_TAG {
+            return None;
+        }
+        let data = descriptor.data.as_slice();
+        Some(Self {
+            ca_system_id: u16::from_be_bytes(data.get(..2)?.try_into().ok()?),
+            private_data: data[2..].to_vec(),
+        })
+    }
+}
+
+/// Partial transport stream descriptor (tag 0x63) in the SIT of a recorded stream.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct PartialTsDescriptor {
+    #[skip]
+    pub reserved: B2,
+    /// Maximum momentary transport packet rate in units of 400 bits/s.
+    pub peak_rate: B22,
+    #[skip]
+    pub reserved2: B2,
+    /// Minimum smoothing buffer leak rate in units of 400 bits/s, or 0x3fffff if undefined.
+    pub minimum_overall_smoothing_rate: B22,
+    #[skip]
+    pub reserved3: B2,
+    /// Maximum smoothing buffer size in bytes, or 0x3fff if undefined.
+    pub maximum_overall_smoothing_buffer: B14,
+}
+
+impl PartialTsDescriptor {
+    /// Parses a partial transport stream descriptor. Returns [`None`] for other or malformed
+    /// descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != PARTIAL_TS_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(Self::from_bytes(descriptor.data.get(..8)?.try_into().ok()?))
+    }
+
+    /// Peak rate in bits per second.
+    pub fn peak_rate_bps(&self) -> u64 {
+        self.peak_rate() as u64 * 400
+    }
+}
+
+/// AC-3 audio descriptor (tag 0x81) of Dolby Digital streams.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct Ac3AudioDescriptor {
+    /// 0 = 48kHz, 1 = 44.1kHz, 2 = 32kHz.
+    pub sample_rate_code: B3,
+    /// Bit stream identification.
+    pub bsid: B5,
+    /// Nominal or upper limit bit rate code.
+    pub bit_rate_code: B6,
+    /// Dolby surround mode.
+    pub surround_mode: B2,
+    /// Bit stream mode.
+    pub bsmod: B3,
+    /// Audio coding mode and channel count.
+    pub num_channels: B4,
+    /// The stream is a full service.
+    pub full_svc: bool,
+}
+
+impl Ac3AudioDescriptor {
+    /// Parses an AC-3 audio descriptor. Returns [`None`] for other or malformed descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != AC3_AUDIO_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(Self::from_bytes(descriptor.data.get(..3)?.try_into().ok()?))
+    }
+}
+
+/// Descriptor of a BD PMT or SIT parsed according to its tag.
+#[derive(Debug, Clone)]
+pub enum HdmvDescriptor {
+    /// `HDMV` registration descriptor.
+    Registration(HdmvRegistrationDescriptor),
+    /// Copy control descriptor.
+    CopyControl(CopyControlDescriptor),
+    /// Partial transport stream descriptor.
+    PartialTs(PartialTsDescriptor),
+    /// AC-3 audio descriptor.
+    Ac3Audio(Ac3AudioDescriptor),
+}
+
+impl HdmvDescriptor {
+    /// Parses a BD specific descriptor. Returns [`None`] for other or malformed descriptors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::bdav::descriptor::{HdmvDescriptor, HdmvStreamAttributes};
+    /// use mpegts_io::Descriptor;
+    ///
+    /// let descriptor = Descriptor {
+    ///     tag: 0x05,
+    ///     data: b"HDMV\xff\x1b\x61\x3f".iter().copied().collect(),
+    /// };
+    /// match HdmvDescriptor::from_descriptor(&descriptor) {
+    ///     Some(HdmvDescriptor::Registration(registration)) => {
+    ///         let coding = registration.coding.unwrap();
+    ///         assert_eq!(coding.stream_coding_type, 0x1b);
+    ///         assert_eq!(
+    ///             coding.attributes,
+    ///             HdmvStreamAttributes::Video {
+    ///                 video_format: 6,
+    ///                 frame_rate: 1,
+    ///                 aspect_ratio: 3
+    ///             }
+    ///         );
+    ///     }
+    ///     other => panic!("Unexpected descriptor {:?}", other),
+    /// }
+    /// ```
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        match descriptor.tag {
+            REGISTRATION_DESCRIPTOR_TAG => HdmvRegistrationDescriptor::from_descriptor(descriptor)
+                .map(HdmvDescriptor::Registration),
+            COPY_CONTROL_DESCRIPTOR_TAG => {
+                CopyControlDescriptor::from_descriptor(descriptor).map(HdmvDescriptor::CopyControl)
+            }
+            PARTIAL_TS_DESCRIPTOR_TAG => {
+                PartialTsDescriptor::from_descriptor(descriptor).map(HdmvDescriptor::PartialTs)
+            }
+            AC3_AUDIO_DESCRIPTOR_TAG => {
+                Ac3AudioDescriptor::from_descriptor(descriptor).map(HdmvDescriptor::Ac3Audio)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_hdmv_descriptors() {
+    let descriptor = |tag: u8, data: &[u8]| Descriptor {
+        tag,
+        data: data.iter().copied().collect(),
+    };
+
+    let program = HdmvRegistrationDescriptor::from_descriptor(&descriptor(0x05, b"HDMV")).unwrap();
+    assert!(program.coding.is_none());
+    let audio =
+        HdmvRegistrationDescriptor::from_descriptor(&descriptor(0x05, b"HDMV\xff\x80\x31\xff"))
+            .unwrap()
+            .coding
+            .unwrap();
+    assert!(audio.is_audio());
+    assert_eq!(
+        audio.attributes,
+        HdmvStreamAttributes::Audio {
+            audio_presentation_type: 3,
+            sampling_frequency: 1
+        }
+    );
+    let graphics =
+        HdmvRegistrationDescriptor::from_descriptor(&descriptor(0x05, b"HDMV\xff\x90\xff\xff"))
+            .unwrap()
+            .coding
+            .unwrap();
+    assert!(graphics.is_graphics());
+    assert_eq!(graphics.attributes, HdmvStreamAttributes::Other);
+    assert!(HdmvRegistrationDescriptor::from_descriptor(&descriptor(0x05, b"AC-3")).is_none());
+
+    let copy_control =
+        CopyControlDescriptor::from_descriptor(&descriptor(0x88, &[0x0f, 0xff, 0x84, 0xfc]))
+            .unwrap();
+    assert_eq!(copy_control.ca_system_id, 0x0fff);
+    assert_eq!(copy_control.private_data, [0x84, 0xfc]);
+
+    let partial_ts = PartialTsDescriptor::from_descriptor(&descriptor(
+        0x63,
+        &[0xc0, 0x9c, 0x40, 0xff, 0xff, 0xff, 0xff, 0xff],
+    ))
+    .unwrap();
+    assert_eq!(partial_ts.peak_rate_bps(), 40000 * 400);
+    assert_eq!(partial_ts.minimum_overall_smoothing_rate(), 0x3fffff);
+    assert_eq!(partial_ts.maximum_overall_smoothing_buffer(), 0x3fff);
+
+    let ac3 = Ac3AudioDescriptor::from_descriptor(&descriptor(0x81, &[0x08, 0x3c, 0x0f])).unwrap();
+    assert_eq!(ac3.sample_rate_code(), 0);
+    assert_eq!(ac3.bsid(), 8);
+    assert_eq!(ac3.bit_rate_code(), 0x0f);
+    assert_eq!(ac3.num_channels(), 7);
+    assert!(ac3.full_svc());
+}
@@ -0,0 +1,373 @@
+//! Module for simulating the user interaction with IG menus.
+//!
+//! [`IgMenuSession`] follows a parsed [`IgInteractiveComposition`] the way a player does: it
+//! tracks the current page, the selected button and the enabled button of each button group,
+//! and applies user operations permitted by the [`UoMask`](super::pg::UoMask) of the page.
+//! Navigation commands of activated buttons are returned to the caller, which may apply their
+//! menu effects with [`IgMenuSession::set_page`], [`IgMenuSession::enable_button`] and
+//! [`IgMenuSession::disable_button`].
+
+use super::mobj::MObjCmd;
+use super::pg::{IgButton, IgDirection, IgInteractiveComposition, IgPage};
+use crate::prelude::*;
+
+/// Button ID reference meaning no button.
+const NO_BUTTON: u16 = 0xffff;
+
+/// Outcome of a user operation on an [`IgMenuSession`].
+#[derive(Debug)]
+pub enum IgUserOperationResult<'a> {
+    /// The operation is prohibited by the UO mask of the page or refers to a button that is not
+    /// enabled.
+    Prohibited,
+    /// The selection changed to the button ID, or stayed unchanged.
+    Selected(Option<u16>),
+    /// A button was activated, triggering its navigation commands.
+    Activated {
+        /// ID of the activated button.
+        button_id: u16,
+        /// Navigation commands to execute.
+        nav_cmds: &'a [MObjCmd],
+    },
+}
+
+/// Menu state of one interactive composition.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::menu::{IgMenuSession, IgUserOperationResult};
+/// use mpegts_io::bdav::pg::{IgDirection, IgInteractiveComposition};
+///
+/// # fn load() -> IgInteractiveComposition { unimplemented!() }
+/// let composition = load();
+/// let mut session = IgMenuSession::new(&composition).unwrap();
+/// session.move_selection(IgDirection::Down);
+/// if let IgUserOperationResult::Activated { nav_cmds, .. } = session.activate() {
+///     for cmd in nav_cmds {
+///         println!("{}", cmd.mnemonic());
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct IgMenuSession<'a> {
+    composition: &'a IgInteractiveComposition,
+    page: &'a IgPage,
+    selected: Option<u16>,
+    enabled: Vec<Option<u16>>,
+}
+
+impl<'a> IgMenuSession<'a> {
+    /// Starts a session on the first page of the composition, or returns [`None`] if it has no
+    /// pages.
+    pub fn new(composition: &'a IgInteractiveComposition) -> Option<Self> {
+        let page = composition.pages.first()?;
+        let mut session = Self {
+            composition,
+            page,
+            selected: None,
+            enabled: Vec::new(),
+        };
+        session.enter_page(page, None);
+        Some(session)
+    }
+
+    fn enter_page(&mut self, page: &'a IgPage, button_id: Option<u16>) {
+        self.page = page;
+        self.enabled = page
+            .bogs
+            .iter()
+            .map(|bog| match bog.default_valid_button_id_ref {
+                NO_BUTTON => None,
+                id => Some(id),
+            })
+            .collect();
+        let button_id = button_id.unwrap_or(page.default_selected_button_id_ref);
+        self.selected = if self.is_enabled(button_id) {
+            Some(button_id)
+        } else {
+            self.enabled.iter().flatten().next().copied()
+        };
+    }
+
+    /// Current page.
+    pub fn page(&self) -> &'a IgPage {
+        self.page
+    }
+
+    /// Currently selected button.
+    pub fn selected_button(&self) -> Option<&'a IgButton> {
+        self.page.button(self.selected?)
+    }
+
+    /// Checks whether a button is the enabled button of its button group.
+    pub fn is_enabled(&self, button_id: u16) -> bool {
+        self.enabled.contains(&Some(button_id)) && self.page.button(button_id).is_some()
+    }
+
+    /// Enabled button of each button group of the current page.
+    pub fn enabled_buttons(&self) -> &[Option<u16>] {
+        &self.enabled
+    }
+
+    /// Switches to a page, selecting `button_id` if enabled or otherwise the default selected
+    /// button, as for `set_button_page`. Returns `false` if the page does not exist.
+    pub fn set_page(&mut self, page_id: u8, button_id: Option<u16>) -> bool {
+        match self.composition.page(page_id) {
+            Some(page) => {
+                self.enter_page(page, button_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Makes a button the enabled button of its group, as for `enable_button`.
+    pub fn enable_button(&mut self, button_id: u16) {
+        if let Some(index) = self
+            .page
+            .bogs
+            .iter()
+            .position(|bog| bog.buttons.iter().any(|button| button.id == button_id))
+        {
+            self.enabled[index] = Some(button_id);
+        }
+    }
+
+    /// Disables a button, leaving its group without an enabled button, as for `disable_button`.
+    pub fn disable_button(&mut self, button_id: u16) {
+        for enabled in self.enabled.iter_mut() {
+            if *enabled == Some(button_id) {
+                *enabled = None;
+            }
+        }
+        if self.selected == Some(button_id) {
+            self.selected = None;
+        }
+    }
+
+    /// Selects an enabled button, activating it if it has `auto_action_flag` set.
+    fn select_button(&mut self, button_id: u16) -> IgUserOperationResult<'a> {
+        if !self.is_enabled(button_id) {
+            return IgUserOperationResult::Prohibited;
+        }
+        self.selected = Some(button_id);
+        match self.page.button(button_id) {
+            Some(button) if button.auto_action_flag => self.activate_button(button),
+            _ => IgUserOperationResult::Selected(self.selected),
+        }
+    }
+
+    fn activate_button(&self, button: &'a IgButton) -> IgUserOperationResult<'a> {
+        IgUserOperationResult::Activated {
+            button_id: button.id,
+            nav_cmds: &button.nav_cmds,
+        }
+    }
+
+    /// Moves the selection with a navigation key. The selection stays unchanged if the neighbor
+    /// is the button itself or not enabled.
+    pub fn move_selection(&mut self, direction: IgDirection) -> IgUserOperationResult<'a> {
+        let uo_mask = &self.page.uo_mask;
+        let prohibited = match direction {
+            IgDirection::Up => uo_mask.move_up(),
+            IgDirection::Down => uo_mask.move_down(),
+            IgDirection::Left => uo_mask.move_left(),
+            IgDirection::Right => uo_mask.move_right(),
+        };
+        if prohibited {
+            return IgUserOperationResult::Prohibited;
+        }
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return IgUserOperationResult::Selected(None),
+        };
+        match self.page.neighbor(selected, direction) {
+            Some(neighbor) if neighbor.id != selected && self.is_enabled(neighbor.id) => {
+                self.select_button(neighbor.id)
+            }
+            _ => IgUserOperationResult::Selected(self.selected),
+        }
+    }
+
+    /// Selects a button directly, as with its numeric select value or a pointer.
+    pub fn select(&mut self, button_id: u16) -> IgUserOperationResult<'a> {
+        if self.page.uo_mask.select() {
+            return IgUserOperationResult::Prohibited;
+        }
+        self.select_button(button_id)
+    }
+
+    /// Selects the button with a numeric select value.
+    pub fn select_numeric(&mut self, value: u16) -> IgUserOperationResult<'a> {
+        match self
+            .page
+            .buttons()
+            .find(|button| button.numeric_select_value == value && self.is_enabled(button.id))
+        {
+            Some(button) => self.select(button.id),
+            None => IgUserOperationResult::Prohibited,
+        }
+    }
+
+    /// Activates the selected button.
+    pub fn activate(&mut self) -> IgUserOperationResult<'a> {
+        if self.page.uo_mask.activate() {
+            return IgUserOperationResult::Prohibited;
+        }
+        match self.selected_button() {
+            Some(button) => self.activate_button(button),
+            None => IgUserOperationResult::Prohibited,
+        }
+    }
+
+    /// Selects and activates a button in one operation.
+    pub fn select_and_activate(&mut self, button_id: u16) -> IgUserOperationResult<'a> {
+        if self.page.uo_mask.select_and_activate() || !self.is_enabled(button_id) {
+            return IgUserOperationResult::Prohibited;
+        }
+        self.selected = Some(button_id);
+        match self.page.button(button_id) {
+            Some(button) => self.activate_button(button),
+            None => IgUserOperationResult::Prohibited,
+        }
+    }
+}
+
+#[test]
+fn test_ig_menu_session() {
+    use super::pg::{IgBog, IgEffectSequence, IgUiModel, UoMask};
+
+    let button = |id, numeric_select_value, auto_action_flag, lower, right, cmd: &str| IgButton {
+        id,
+        numeric_select_value,
+        auto_action_flag,
+        x_pos: 0,
+        y_pos: 0,
+        upper_button_id_ref: id,
+        lower_button_id_ref: lower,
+        left_button_id_ref: id,
+        right_button_id_ref: right,
+        normal_start_object_id_ref: 0,
+        normal_end_object_id_ref: 0,
+        normal_repeat_flag: false,
+        selected_sound_id_ref: 0xff,
+        selected_start_object_id_ref: 0,
+        selected_end_object_id_ref: 0,
+        selected_repeat_flag: false,
+        activated_sound_id_ref: 0xff,
+        activated_start_object_id_ref: 0,
+        activated_end_object_id_ref: 0,
+        nav_cmds: vec![MObjCmd::assemble(cmd).unwrap()],
+    };
+    let page = |id, uo_mask, bogs: Vec<(u16, Vec<IgButton>)>| IgPage {
+        id,
+        version: 0,
+        uo_mask,
+        in_effects: IgEffectSequence {
+            windows: Vec::new(),
+            effects: Vec::new(),
+        },
+        out_effects: IgEffectSequence {
+            windows: Vec::new(),
+            effects: Vec::new(),
+        },
+        animation_frame_rate_code: 0,
+        default_selected_button_id_ref: 0xffff,
+        default_activated_button_id_ref: 0xffff,
+        palette_id_ref: 0,
+        bogs: bogs
+            .into_iter()
+            .map(|(default_valid_button_id_ref, buttons)| IgBog {
+                default_valid_button_id_ref,
+                buttons,
+            })
+            .collect(),
+    };
+    /* Button 1 leads down to 2, which leads down to the disabled 4 and right to auto action 3 */
+    let composition = IgInteractiveComposition {
+        stream_model: false,
+        ui_model: IgUiModel::AlwaysOn,
+        composition_timeout_pts: Some(0),
+        selection_timeout_pts: Some(0),
+        user_timeout_duration: 0,
+        pages: vec![
+            page(
+                0,
+                UoMask::new(),
+                vec![
+                    (1, vec![button(1, 1, false, 2, 1, "jump_title 1")]),
+                    (2, vec![button(2, 2, false, 4, 3, "jump_title 2")]),
+                    (
+                        3,
+                        vec![
+                            button(3, 3, true, 3, 3, "jump_title 3"),
+                            button(4, 4, false, 4, 4, "jump_title 4"),
+                        ],
+                    ),
+                ],
+            ),
+            page(
+                1,
+                UoMask::new().with_move_down(true).with_activate(true),
+                vec![(0xffff, vec![button(7, 7, false, 7, 7, "jump_title 7")])],
+            ),
+        ],
+    };
+
+    let mut session = IgMenuSession::new(&composition).unwrap();
+    assert_eq!(session.page().id, 0);
+    assert_eq!(session.selected_button().unwrap().id, 1);
+    assert_eq!(session.enabled_buttons(), [Some(1), Some(2), Some(3)]);
+    assert!(matches!(
+        session.move_selection(IgDirection::Down),
+        IgUserOperationResult::Selected(Some(2))
+    ));
+    assert!(matches!(
+        session.move_selection(IgDirection::Down),
+        IgUserOperationResult::Selected(Some(2))
+    ));
+    match session.activate() {
+        IgUserOperationResult::Activated {
+            button_id,
+            nav_cmds,
+        } => {
+            assert_eq!(button_id, 2);
+            assert_eq!(nav_cmds[0].mnemonic(), "jump_title");
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(matches!(
+        session.move_selection(IgDirection::Right),
+        IgUserOperationResult::Activated { button_id: 3, .. }
+    ));
+    assert!(matches!(
+        session.select(4),
+        IgUserOperationResult::Prohibited
+    ));
+    session.enable_button(4);
+    assert!(!session.is_enabled(3));
+    assert!(matches!(
+        session.select_numeric(4),
+        IgUserOperationResult::Selected(Some(4))
+    ));
+    session.disable_button(4);
+    assert!(session.selected_button().is_none());
+
+    assert!(!session.set_page(5, None));
+    assert!(session.set_page(1, Some(7)));
+    assert!(session.selected_button().is_none());
+    session.enable_button(7);
+    assert!(matches!(
+        session.move_selection(IgDirection::Down),
+        IgUserOperationResult::Prohibited
+    ));
+    assert!(matches!(
+        session.activate(),
+        IgUserOperationResult::Prohibited
+    ));
+    assert!(matches!(
+        session.select_and_activate(7),
+        IgUserOperationResult::Activated { button_id: 7, .. }
+    ));
+}
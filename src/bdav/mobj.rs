@@ -4,15 +4,20 @@
 use super::{
     from_primitive_map_err, read_bitfield, BdavAppDetails, BdavErrorDetails, Result, SliceReader,
 };
+use crate::prelude::*;
 use crate::ErrorDetails;
+use core::fmt::{Debug, Display, Formatter};
+use core::ops::Range;
+use core::str::FromStr;
+use hashbrown::{HashMap, HashSet};
+#[cfg(feature = "std")]
 use lalrpop_util::{lalrpop_mod, lexer::Token, ParseError};
 use modular_bitfield_msb::prelude::*;
 use num_derive::FromPrimitive;
-use std::fmt::{Debug, Display, Formatter};
+#[cfg(feature = "std")]
 use std::io::Write;
-use std::ops::Range;
-use std::str::FromStr;
 
+#[cfg(feature = "std")]
 lalrpop_mod!(
     #[allow(clippy::all)]
     mobj,
@@ -20,6 +25,7 @@ lalrpop_mod!(
 );
 
 /// Errors that may be encountered by the MObj assembly parser.
+#[cfg(feature = "std")]
 #[derive(Debug, PartialEq)]
 pub enum MObjParseErrorType {
     /// A number out of [`u32`] range was encountered.
@@ -34,6 +40,7 @@ pub enum MObjParseErrorType {
 }
 
 /// MObj errors from the MObj assembly parser.
+#[cfg(feature = "std")]
 #[derive(Debug, PartialEq)]
 pub struct MObjParseErrorDetails {
     range: Range<usize>,
@@ -41,9 +48,11 @@ pub struct MObjParseErrorDetails {
 }
 
 /// Aliased [`ParseError`] that adds MObj-specific errors.
+#[cfg(feature = "std")]
 pub type MObjParseError<'a> = ParseError<usize, Token<'a>, MObjParseErrorDetails>;
 
 /// Writes out a highlighted-text string displaying the [`MObjParseError`].
+#[cfg(feature = "std")]
 pub fn write_parse_error(
     text: &str,
     error: &MObjParseError,
@@ -52,7 +61,7 @@ pub fn write_parse_error(
     struct Repeat(char, usize);
 
     impl Display for Repeat {
-        fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fn fmt(&self, fmt: &mut Formatter) -> core::fmt::Result {
             for _ in 0..self.1 {
                 write!(fmt, "{}", self.0)?;
             }
@@ -117,7 +126,7 @@ pub fn write_parse_error(
 }
 
 /// MObj errors from the MObj command decoder.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum MObjCmdErrorDetails {
     /// Encountered an unknown [`MObjGroup`].
     UnknownMObjGroup(u8),
@@ -161,7 +170,7 @@ macro_rules! instruction_enum {
         }
 
         impl Display for $name {
-            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
                 f.write_str(self.mnemonic())
             }
         }
@@ -187,6 +196,17 @@ macro_rules! instruction_enum {
     };
 }
 
+macro_rules! mobj_cmd_constructors {
+    ($($(#[$attr:meta])* $name:ident($($op:ident),*) => $grp:ident, $sub_grp:expr, $opt:expr;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name($($op: impl Into<MObjOperand>),*) -> Self {
+                Self::build(MObjGroup::$grp, $sub_grp as u8, $opt as u8, &[$($op.into()),*])
+            }
+        )*
+    };
+}
+
 /// Top-level MObj instruction group.
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, FromPrimitive)]
@@ -355,7 +375,7 @@ instruction_enum! {
 
 /// Operation information of one [`MObjCmd`]
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct MObjInstruction {
     pub op_cnt: B3,
     pub grp: B2,
@@ -374,6 +394,7 @@ pub struct MObjInstruction {
 }
 
 /// A command in the MObj VM.
+#[derive(Copy, Clone)]
 pub struct MObjCmd {
     /// Operation information.
     pub inst: MObjInstruction,
@@ -396,8 +417,33 @@ impl MObjCmd {
         Ok(new_cmd)
     }
 
+    /// Encodes 12 bytes of command bytecode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::bdav::mobj::MObjCmd;
+    /// let cmd = MObjCmd::assemble("jump_title 2").unwrap();
+    /// assert_eq!(cmd.into_bytes()[8..], [0, 0, 0, 0]);
+    /// assert_eq!(cmd.into_bytes()[4..8], [0, 0, 0, 2]);
+    /// ```
+    pub fn into_bytes(self) -> [u8; 12] {
+        let mut out = [0_u8; 12];
+        out[..4].copy_from_slice(&self.inst.into_bytes());
+        out[4..8].copy_from_slice(&self.dst.to_be_bytes());
+        out[8..].copy_from_slice(&self.src.to_be_bytes());
+        out
+    }
+
+    /// Writes 12 bytes of command bytecode to `out`.
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        out.write_all(&self.into_bytes())
+    }
+
     /// Assembles a command from an assembly string.
-    pub fn assemble(s: &str) -> std::result::Result<Self, MObjParseError> {
+    #[cfg(feature = "std")]
+    pub fn assemble(s: &str) -> core::result::Result<Self, MObjParseError> {
         mobj::CmdParser::new().parse(s)
     }
 
@@ -405,7 +451,7 @@ impl MObjCmd {
     pub fn visit<V: MObjCmdVisitor<R>, R>(
         &self,
         visitor: V,
-    ) -> std::result::Result<R, MObjCmdErrorDetails> {
+    ) -> core::result::Result<R, MObjCmdErrorDetails> {
         Ok(
             match from_primitive_map_err(self.inst.grp(), |v| {
                 MObjCmdErrorDetails::UnknownMObjGroup(v)
@@ -447,7 +493,7 @@ impl MObjCmd {
     }
 
     /// Ensures a valid command hierarchy is present.
-    pub fn validate(&self) -> std::result::Result<(), MObjCmdErrorDetails> {
+    pub fn validate(&self) -> core::result::Result<(), MObjCmdErrorDetails> {
         self.visit(CmdValidate)
     }
 
@@ -471,6 +517,158 @@ impl MObjCmd {
         }
     }
 
+    /// Encodes a command from its instruction codes and up to two operands.
+    fn build(grp: MObjGroup, sub_grp: u8, opt: u8, operands: &[MObjOperand]) -> Self {
+        let mut inst = MObjInstruction::new()
+            .with_op_cnt(operands.len() as u8)
+            .with_grp(grp as u8);
+        inst = match grp {
+            MObjGroup::Branch => inst.with_sub_grp(sub_grp).with_branch_opt(opt),
+            MObjGroup::Cmp => inst.with_cmp_opt(opt),
+            MObjGroup::Set => inst.with_sub_grp(sub_grp).with_set_opt(opt),
+        };
+        let mut cmd = Self {
+            inst,
+            dst: 0,
+            src: 0,
+        };
+        if let Some(dst) = operands.first() {
+            cmd.inst.set_imm_op1(dst.is_imm());
+            cmd.dst = dst.into_val();
+        }
+        if let Some(src) = operands.get(1) {
+            cmd.inst.set_imm_op2(src.is_imm());
+            cmd.src = src.into_val();
+        }
+        cmd
+    }
+
+    mobj_cmd_constructors! {
+        /// `nop`
+        nop() => Branch, BranchSubGroup::Goto, GotoInstruction::Nop;
+        /// `goto <pc>`
+        goto(pc) => Branch, BranchSubGroup::Goto, GotoInstruction::Goto;
+        /// `break`
+        break_() => Branch, BranchSubGroup::Goto, GotoInstruction::Break;
+        /// `jump_object <id>`
+        jump_object(id) => Branch, BranchSubGroup::Jump, JumpInstruction::JumpObject;
+        /// `jump_title <id>`
+        jump_title(id) => Branch, BranchSubGroup::Jump, JumpInstruction::JumpTitle;
+        /// `call_object <id>`
+        call_object(id) => Branch, BranchSubGroup::Jump, JumpInstruction::CallObject;
+        /// `call_title <id>`
+        call_title(id) => Branch, BranchSubGroup::Jump, JumpInstruction::CallTitle;
+        /// `resume`
+        resume() => Branch, BranchSubGroup::Jump, JumpInstruction::Resume;
+        /// `play_pl <id>`
+        play_pl(id) => Branch, BranchSubGroup::Play, PlayInstruction::PlayPlaylist;
+        /// `play_pl_pi <id> <id>`
+        play_pl_pi(playlist, item) => Branch, BranchSubGroup::Play, PlayInstruction::PlayPlaylistItem;
+        /// `play_pl_pm <id> <id>`
+        play_pl_pm(playlist, mark) => Branch, BranchSubGroup::Play, PlayInstruction::PlayPlaylistMark;
+        /// `terminate_pl`
+        terminate_pl() => Branch, BranchSubGroup::Play, PlayInstruction::TerminatePlaylist;
+        /// `link_pi <id>`
+        link_pi(item) => Branch, BranchSubGroup::Play, PlayInstruction::LinkItem;
+        /// `link_mk <id>`
+        link_mk(mark) => Branch, BranchSubGroup::Play, PlayInstruction::LinkMark;
+        /// `bc <a> <b>`
+        bc(a, b) => Cmp, 0, CmpInstruction::Bc;
+        /// `eq <a> <b>`
+        eq(a, b) => Cmp, 0, CmpInstruction::Eq;
+        /// `ne <a> <b>`
+        ne(a, b) => Cmp, 0, CmpInstruction::Ne;
+        /// `ge <a> <b>`
+        ge(a, b) => Cmp, 0, CmpInstruction::Ge;
+        /// `gt <a> <b>`
+        gt(a, b) => Cmp, 0, CmpInstruction::Gt;
+        /// `le <a> <b>`
+        le(a, b) => Cmp, 0, CmpInstruction::Le;
+        /// `lt <a> <b>`
+        lt(a, b) => Cmp, 0, CmpInstruction::Lt;
+        /// `move <a> <b>`
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use mpegts_io::bdav::mobj::{MObjCmd, MObjOperand};
+        /// let cmd = MObjCmd::mov(MObjOperand::Gpr(1), MObjOperand::Psr(4));
+        /// assert_eq!(cmd.to_string(), "move r1, PSR4");
+        /// assert_eq!(cmd.into_bytes(), MObjCmd::assemble("move r1, PSR4").unwrap().into_bytes());
+        /// ```
+        mov(a, b) => Set, SetSubGroup::Set, SetInstruction::Move;
+        /// `swap <a> <b>`
+        swap(a, b) => Set, SetSubGroup::Set, SetInstruction::Swap;
+        /// `add <a> <b>`
+        add(a, b) => Set, SetSubGroup::Set, SetInstruction::Add;
+        /// `sub <a> <b>`
+        sub(a, b) => Set, SetSubGroup::Set, SetInstruction::Sub;
+        /// `mul <a> <b>`
+        mul(a, b) => Set, SetSubGroup::Set, SetInstruction::Mul;
+        /// `div <a> <b>`
+        div(a, b) => Set, SetSubGroup::Set, SetInstruction::Div;
+        /// `mod <a> <b>`
+        modulo(a, b) => Set, SetSubGroup::Set, SetInstruction::Mod;
+        /// `rnd <a> <b>`
+        rnd(a, b) => Set, SetSubGroup::Set, SetInstruction::Rnd;
+        /// `and <a> <b>`
+        and(a, b) => Set, SetSubGroup::Set, SetInstruction::And;
+        /// `or <a> <b>`
+        or(a, b) => Set, SetSubGroup::Set, SetInstruction::Or;
+        /// `xor <a> <b>`
+        xor(a, b) => Set, SetSubGroup::Set, SetInstruction::Xor;
+        /// `bset <a> <b>`
+        bset(a, b) => Set, SetSubGroup::Set, SetInstruction::Bitset;
+        /// `bclr <a> <b>`
+        bclr(a, b) => Set, SetSubGroup::Set, SetInstruction::Bitclr;
+        /// `shl <a> <b>`
+        shl(a, b) => Set, SetSubGroup::Set, SetInstruction::Shl;
+        /// `shr <a> <b>`
+        shr(a, b) => Set, SetSubGroup::Set, SetInstruction::Shr;
+        /// `set_nv_timer <a> <b>`
+        set_nv_timer(a, b) => Set, SetSubGroup::SetSystem, SetSystemInstruction::SetNvTimer;
+        /// `enable_button <button-id>`
+        enable_button(button) => Set, SetSubGroup::SetSystem, SetSystemInstruction::EnableButton;
+        /// `disable_button <button-id>`
+        disable_button(button) => Set, SetSubGroup::SetSystem, SetSystemInstruction::DisableButton;
+        /// `set_sec_stream <a> <b>`
+        set_sec_stream(a, b) => Set, SetSubGroup::SetSystem, SetSystemInstruction::SetSecStream;
+        /// `popup_off`
+        popup_off() => Set, SetSubGroup::SetSystem, SetSystemInstruction::PopupOff;
+        /// `still_on`
+        still_on() => Set, SetSubGroup::SetSystem, SetSystemInstruction::StillOn;
+        /// `still_off`
+        still_off() => Set, SetSubGroup::SetSystem, SetSystemInstruction::StillOff;
+        /// `set_output_mode <a>`
+        set_output_mode(a) => Set, SetSubGroup::SetSystem, SetSystemInstruction::SetOutputMode;
+        /// `bd_plus_msg <a> <b>`
+        bd_plus_msg(a, b) => Set, SetSubGroup::SetSystem, SetSystemInstruction::BdPlusMsg;
+    }
+
+    /// `set_button_page <button-id> <page-id>`, leaving out [`None`] operands and skipping the
+    /// out effects of the current page with `skip_out`.
+    pub fn set_button_page(
+        button: Option<MObjOperand>,
+        page: Option<MObjOperand>,
+        skip_out: bool,
+    ) -> Self {
+        let dst_val = set_button_page_operand_to_val(&button);
+        let src_val =
+            set_button_page_operand_to_val(&page) | if skip_out { 0x40000000 } else { 0x0 };
+
+        MObjCmd {
+            inst: MObjInstruction::new()
+                .with_op_cnt(2)
+                .with_grp(MObjGroup::Set as u8)
+                .with_sub_grp(SetSubGroup::SetSystem as u8)
+                .with_imm_op1(is_optional_operand_imm(&button))
+                .with_imm_op2(is_optional_operand_imm(&page))
+                .with_set_opt(SetSystemInstruction::SetButtonPage as u8),
+            dst: dst_val,
+            src: src_val,
+        }
+    }
+
     fn make_operand(v: u32, is_imm: bool) -> MObjOperand {
         if is_imm {
             MObjOperand::Imm(v)
@@ -493,15 +691,15 @@ impl MObjCmd {
 macro_rules! format_cmd {
     ($fmt_type:ident) => {
         impl $fmt_type for MObjCmd {
-            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
                 if let MObjGroup::Set =
-                    from_primitive_map_err(self.inst.grp(), |_| std::fmt::Error)?
+                    from_primitive_map_err(self.inst.grp(), |_| core::fmt::Error)?
                 {
                     let sub_grp: SetSubGroup =
-                        from_primitive_map_err(self.inst.sub_grp(), |_| std::fmt::Error)?;
+                        from_primitive_map_err(self.inst.sub_grp(), |_| core::fmt::Error)?;
                     if sub_grp == SetSubGroup::SetSystem {
                         let inst: SetSystemInstruction =
-                            from_primitive_map_err(self.inst.set_opt(), |_| std::fmt::Error)?;
+                            from_primitive_map_err(self.inst.set_opt(), |_| core::fmt::Error)?;
                         match inst {
                             // TODO: Operands of SetStreamSs not known
                             SetSystemInstruction::SetStream | SetSystemInstruction::SetStreamSs => {
@@ -639,6 +837,92 @@ impl MObjCmdVisitor<()> for CmdValidate {
     fn visit_set_system(self, inst: SetSystemInstruction) {}
 }
 
+struct GetCmdOperandCount;
+
+impl MObjCmdVisitor<u8> for GetCmdOperandCount {
+    fn visit_goto(self, inst: GotoInstruction) -> u8 {
+        match inst {
+            GotoInstruction::Goto => 1,
+            _ => 0,
+        }
+    }
+    fn visit_jump(self, inst: JumpInstruction) -> u8 {
+        match inst {
+            JumpInstruction::Resume => 0,
+            _ => 1,
+        }
+    }
+    fn visit_play(self, inst: PlayInstruction) -> u8 {
+        match inst {
+            PlayInstruction::PlayPlaylistItem | PlayInstruction::PlayPlaylistMark => 2,
+            PlayInstruction::TerminatePlaylist => 0,
+            _ => 1,
+        }
+    }
+    fn visit_cmp(self, inst: CmpInstruction) -> u8 {
+        2
+    }
+    fn visit_set(self, inst: SetInstruction) -> u8 {
+        2
+    }
+    fn visit_set_system(self, inst: SetSystemInstruction) -> u8 {
+        match inst {
+            SetSystemInstruction::EnableButton
+            | SetSystemInstruction::DisableButton
+            | SetSystemInstruction::SetOutputMode => 1,
+            SetSystemInstruction::PopupOff
+            | SetSystemInstruction::StillOn
+            | SetSystemInstruction::StillOff => 0,
+            _ => 2,
+        }
+    }
+}
+
+/// Where execution continues after a command.
+enum CmdFlow {
+    /// The following command.
+    Next,
+    /// The following command or the one after it, for comparisons.
+    Skip,
+    /// The target of a `goto`.
+    Goto,
+    /// Execution of the program ends.
+    Stop,
+}
+
+struct GetCmdFlow;
+
+impl MObjCmdVisitor<CmdFlow> for GetCmdFlow {
+    fn visit_goto(self, inst: GotoInstruction) -> CmdFlow {
+        match inst {
+            GotoInstruction::Nop => CmdFlow::Next,
+            GotoInstruction::Goto => CmdFlow::Goto,
+            GotoInstruction::Break => CmdFlow::Stop,
+        }
+    }
+    fn visit_jump(self, inst: JumpInstruction) -> CmdFlow {
+        match inst {
+            JumpInstruction::JumpObject | JumpInstruction::JumpTitle | JumpInstruction::Resume => {
+                CmdFlow::Stop
+            }
+            /* Called objects and titles return with resume */
+            JumpInstruction::CallObject | JumpInstruction::CallTitle => CmdFlow::Next,
+        }
+    }
+    fn visit_play(self, inst: PlayInstruction) -> CmdFlow {
+        CmdFlow::Next
+    }
+    fn visit_cmp(self, inst: CmpInstruction) -> CmdFlow {
+        CmdFlow::Skip
+    }
+    fn visit_set(self, inst: SetInstruction) -> CmdFlow {
+        CmdFlow::Next
+    }
+    fn visit_set_system(self, inst: SetSystemInstruction) -> CmdFlow {
+        CmdFlow::Next
+    }
+}
+
 struct GetCmdMnemonic;
 
 impl MObjCmdVisitor<&'static str> for GetCmdMnemonic {
@@ -662,13 +946,23 @@ impl MObjCmdVisitor<&'static str> for GetCmdMnemonic {
     }
 }
 
+/// Operand of an [`MObjCmd`].
 #[derive(PartialEq, Copy, Clone)]
-pub(crate) enum MObjOperand {
+pub enum MObjOperand {
+    /// General purpose register 0 to 4095.
     Gpr(u32),
+    /// Player status register 0 to 127.
     Psr(u32),
+    /// Immediate value.
     Imm(u32),
 }
 
+impl From<u32> for MObjOperand {
+    fn from(v: u32) -> Self {
+        MObjOperand::Imm(v)
+    }
+}
+
 impl MObjOperand {
     fn into_val(self) -> u32 {
         match self {
@@ -682,6 +976,10 @@ impl MObjOperand {
         matches!(self, MObjOperand::Imm(_))
     }
 
+    fn is_read_only_psr(&self) -> bool {
+        matches!(self, MObjOperand::Psr(v) if super::psr::is_read_only(*v))
+    }
+
     fn psr_comment(&self) -> &'static str {
         match self {
             MObjOperand::Psr(v) => match v {
@@ -750,7 +1048,7 @@ impl MObjOperand {
 }
 
 impl Display for MObjOperand {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             MObjOperand::Gpr(v) => {
                 f.write_str("r")?;
@@ -766,7 +1064,7 @@ impl Display for MObjOperand {
 }
 
 impl Debug for MObjOperand {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             MObjOperand::Gpr(v) => {
                 f.write_str("r")?;
@@ -787,11 +1085,12 @@ impl Debug for MObjOperand {
     }
 }
 
+#[cfg(feature = "std")]
 fn check_set_stream_operands<'a>(
     range: Range<usize>,
     op1: &Option<MObjOperand>,
     op2: &Option<MObjOperand>,
-) -> std::result::Result<(), MObjParseError<'a>> {
+) -> core::result::Result<(), MObjParseError<'a>> {
     if let (Some(op1), Some(op2)) = (op1, op2) {
         if op1.is_imm() != op2.is_imm() {
             return Err(ParseError::User {
@@ -822,6 +1121,7 @@ fn set_stream_operand_to_val(op: &Option<MObjOperand>) -> u32 {
 }
 
 #[allow(clippy::too_many_arguments)]
+#[cfg(feature = "std")]
 pub(crate) fn make_set_stream_cmd<'a>(
     instruction: SetSystemInstruction,
     range1: Range<usize>,
@@ -831,7 +1131,7 @@ pub(crate) fn make_set_stream_cmd<'a>(
     range2: Range<usize>,
     ig: Option<MObjOperand>,
     angle: Option<MObjOperand>,
-) -> std::result::Result<MObjCmd, MObjParseError<'a>> {
+) -> core::result::Result<MObjCmd, MObjParseError<'a>> {
     assert!(
         instruction == SetSystemInstruction::SetStream
             || instruction == SetSystemInstruction::SetStreamSs
@@ -869,31 +1169,441 @@ fn set_button_page_operand_to_val(op: &Option<MObjOperand>) -> u32 {
     }
 }
 
+#[cfg(feature = "std")]
 pub(crate) fn make_set_button_page_cmd<'a>(
     button: Option<MObjOperand>,
     page: Option<MObjOperand>,
     skip_out: bool,
-) -> std::result::Result<MObjCmd, MObjParseError<'a>> {
-    let dst_val = set_button_page_operand_to_val(&button);
-    let src_val = set_button_page_operand_to_val(&page) | if skip_out { 0x40000000 } else { 0x0 };
+) -> core::result::Result<MObjCmd, MObjParseError<'a>> {
+    Ok(MObjCmd::set_button_page(button, page, skip_out))
+}
 
-    Ok(MObjCmd {
+/// Errors that may be encountered by the MObj program assembler.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub enum MObjProgramErrorType {
+    /// A command failed to assemble. Contains the message written by [`write_parse_error`].
+    Parse(String),
+    /// A label was defined more than once.
+    DuplicateLabel(String),
+    /// A `goto` referenced a label that is not defined.
+    UndefinedLabel(String),
+    /// A label was named like a register or number.
+    InvalidLabel(String),
+}
+
+/// MObj errors from the MObj program assembler.
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub struct MObjProgramError {
+    /// Zero-based line number of the offending line.
+    pub line: usize,
+    /// Type of error.
+    pub error_type: MObjProgramErrorType,
+}
+
+#[cfg(feature = "std")]
+impl Display for MObjProgramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "line {}: ", self.line + 1)?;
+        match &self.error_type {
+            MObjProgramErrorType::Parse(message) => f.write_str(message),
+            MObjProgramErrorType::DuplicateLabel(label) => {
+                write!(f, "label `{}` defined more than once", label)
+            }
+            MObjProgramErrorType::UndefinedLabel(label) => {
+                write!(f, "label `{}` is not defined", label)
+            }
+            MObjProgramErrorType::InvalidLabel(label) => {
+                write!(f, "label `{}` is named like an operand", label)
+            }
+        }
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_operand_like(s: &str) -> bool {
+    let lower = s.to_ascii_lowercase();
+    let number = lower
+        .strip_prefix("psr")
+        .or_else(|| lower.strip_prefix('r'))
+        .unwrap_or(&lower);
+    !number.is_empty()
+        && (number.chars().all(|c| c.is_ascii_digit())
+            || number
+                .strip_prefix("0x")
+                .is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit())))
+}
+
+/// Blanks out `/* */` and `//` comments while keeping line breaks in place.
+fn strip_comments(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    out.push(if c == '\n' { '\n' } else { ' ' });
+                    last = c;
+                }
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn make_goto_cmd(pc: u32) -> MObjCmd {
+    MObjCmd {
         inst: MObjInstruction::new()
-            .with_op_cnt(2)
-            .with_grp(MObjGroup::Set as u8)
-            .with_sub_grp(SetSubGroup::SetSystem as u8)
-            .with_imm_op1(is_optional_operand_imm(&button))
-            .with_imm_op2(is_optional_operand_imm(&page))
-            .with_set_opt(SetSystemInstruction::SetButtonPage as u8),
-        dst: dst_val,
-        src: src_val,
-    })
+            .with_op_cnt(1)
+            .with_grp(MObjGroup::Branch as u8)
+            .with_sub_grp(BranchSubGroup::Goto as u8)
+            .with_imm_op1(true)
+            .with_branch_opt(GotoInstruction::Goto as u8),
+        dst: pc,
+        src: 0,
+    }
 }
 
+/// Assembles a sequence of commands, one per line.
+///
+/// Lines may be empty or contain only comments. A line may start with a `label:` definition,
+/// which names the index of the command following it. `goto label` is resolved to the index of
+/// the labelled command.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::mobj::assemble_program;
+/// let program = assemble_program(
+///     "
+///     move r0, 0
+///     loop:           // count to 10
+///     add r0, 1
+///     lt r0, 10
+///     goto loop
+///     jump_title 1
+///     ",
+/// )
+/// .unwrap();
+/// assert_eq!(program.len(), 5);
+/// assert_eq!(program[3].to_string(), "goto 1");
+/// ```
+#[cfg(feature = "std")]
+pub fn assemble_program(s: &str) -> core::result::Result<Vec<MObjCmd>, MObjProgramError> {
+    let stripped = strip_comments(s);
+
+    /* Collect labels and command text */
+    let mut labels = HashMap::new();
+    let mut lines = Vec::new();
+    for (line, mut text) in stripped.lines().enumerate() {
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if is_identifier(label) {
+                if is_operand_like(label) {
+                    return Err(MObjProgramError {
+                        line,
+                        error_type: MObjProgramErrorType::InvalidLabel(label.to_string()),
+                    });
+                }
+                if labels.insert(label, lines.len() as u32).is_some() {
+                    return Err(MObjProgramError {
+                        line,
+                        error_type: MObjProgramErrorType::DuplicateLabel(label.to_string()),
+                    });
+                }
+                text = rest;
+            }
+        }
+        let text = text.trim();
+        if !text.is_empty() {
+            lines.push((line, text));
+        }
+    }
+
+    /* Assemble commands with labels resolved */
+    let mut cmds = Vec::with_capacity(lines.len());
+    for (line, text) in lines {
+        let mut words = text.split_whitespace();
+        if let (Some(mnemonic), Some(target), None) = (words.next(), words.next(), words.next()) {
+            if mnemonic.eq_ignore_ascii_case("goto") && is_identifier(target) {
+                if let Some(pc) = labels.get(target) {
+                    cmds.push(make_goto_cmd(*pc));
+                    continue;
+                } else if !is_operand_like(target) {
+                    return Err(MObjProgramError {
+                        line,
+                        error_type: MObjProgramErrorType::UndefinedLabel(target.to_string()),
+                    });
+                }
+            }
+        }
+        match MObjCmd::assemble(text) {
+            Ok(cmd) => cmds.push(cmd),
+            Err(error) => {
+                let mut message = Vec::new();
+                write_parse_error(text, &error, &mut message).expect("Writing to Vec cannot fail");
+                return Err(MObjProgramError {
+                    line,
+                    error_type: MObjProgramErrorType::Parse(
+                        String::from_utf8_lossy(&message).into_owned(),
+                    ),
+                });
+            }
+        }
+    }
+    Ok(cmds)
+}
+
+/// Disassembles a sequence of commands, one per line, naming the targets of `goto` commands
+/// with labels so the output may be passed back to [`assemble_program`].
+pub fn disassemble_program(cmds: &[MObjCmd]) -> String {
+    /* Targets past the end of the program other than its end are left numeric */
+    let labelled_goto = |cmd: &MObjCmd| {
+        cmd.inst.grp() == MObjGroup::Branch as u8
+            && cmd.inst.sub_grp() == BranchSubGroup::Goto as u8
+            && cmd.inst.branch_opt() == GotoInstruction::Goto as u8
+            && cmd.inst.imm_op1()
+            && cmd.dst as usize <= cmds.len()
+    };
+    let targets: HashSet<u32> = cmds
+        .iter()
+        .filter(|cmd| labelled_goto(cmd))
+        .map(|cmd| cmd.dst)
+        .collect();
+
+    let mut out = String::new();
+    for (pc, cmd) in cmds.iter().enumerate() {
+        if targets.contains(&(pc as u32)) {
+            out.push_str(&format!("label_{}:\n", pc));
+        }
+        if labelled_goto(cmd) {
+            out.push_str(&format!("goto label_{}\n", cmd.dst));
+        } else {
+            out.push_str(&format!("{}\n", cmd));
+        }
+    }
+    if targets.contains(&(cmds.len() as u32)) {
+        out.push_str(&format!("label_{}:\n", cmds.len()));
+    }
+    out
+}
+
+/// Decodes a table of commands stored back to back, such as button navigation commands or the
+/// commands of a movie object.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::mobj::{disassemble, format_listing, MObjCmd};
+/// use mpegts_io::bdav::DefaultBdavAppDetails;
+/// let mut data = Vec::new();
+/// MObjCmd::assemble("move r0, PSR4").unwrap().write_to(&mut data).unwrap();
+/// MObjCmd::assemble("jump_title r0").unwrap().write_to(&mut data).unwrap();
+/// let cmds = disassemble::<DefaultBdavAppDetails>(&data).unwrap();
+/// assert_eq!(
+///     format_listing(&cmds),
+///     "   0: move r0, PSR4 /* Title number */\n   1: jump_title r0\n"
+/// );
+/// ```
+pub fn disassemble<D: BdavAppDetails>(data: &[u8]) -> Result<Vec<MObjCmd>, D> {
+    let mut reader = SliceReader::new(data);
+    let mut cmds = Vec::with_capacity(data.len() / 12);
+    while reader.remaining_len() > 0 {
+        cmds.push(MObjCmd::parse(&mut reader)?);
+    }
+    Ok(cmds)
+}
+
+/// Formats a listing of commands, one per line, prefixed with their index and with PSR operands
+/// annotated.
+pub fn format_listing(cmds: &[MObjCmd]) -> String {
+    let mut out = String::new();
+    for (pc, cmd) in cmds.iter().enumerate() {
+        out.push_str(&format!("{:4}: {:?}\n", pc, cmd));
+    }
+    out
+}
+
+/// Problems that may be reported by [`lint_program`].
+#[derive(Debug, PartialEq)]
+pub enum MObjLintType {
+    /// The command does not decode to a known instruction.
+    InvalidCommand(MObjCmdErrorDetails),
+    /// The operand count of the command differs from the one its instruction takes.
+    OperandCountMismatch {
+        /// Operand count taken by the instruction.
+        expected: u8,
+        /// Operand count encoded in the command.
+        found: u8,
+    },
+    /// A `goto` targets a command index past the end of the program.
+    InvalidBranchTarget(u32),
+    /// The command and those following it up to the next reachable command can never execute.
+    Unreachable,
+    /// A set instruction writes to a read-only PSR.
+    ReadOnlyPsrWrite(u32),
+}
+
+/// Diagnostic reported by [`lint_program`].
+#[derive(Debug, PartialEq)]
+pub struct MObjLint {
+    /// Index of the offending command.
+    pub pc: usize,
+    /// Type of problem.
+    pub lint_type: MObjLintType,
+}
+
+impl Display for MObjLint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "command {}: ", self.pc)?;
+        match &self.lint_type {
+            MObjLintType::InvalidCommand(error) => write!(f, "invalid command {:?}", error),
+            MObjLintType::OperandCountMismatch { expected, found } => {
+                write!(
+                    f,
+                    "instruction takes {} operands, found {}",
+                    expected, found
+                )
+            }
+            MObjLintType::InvalidBranchTarget(target) => {
+                write!(f, "goto target {} is past the end of the program", target)
+            }
+            MObjLintType::Unreachable => f.write_str("unreachable code"),
+            MObjLintType::ReadOnlyPsrWrite(psr) => write!(f, "write to read-only PSR{}", psr),
+        }
+    }
+}
+
+/// Checks a sequence of commands for problems a player would trip over, returning diagnostics
+/// ordered by command index.
+///
+/// Commands that can never execute are reported once per contiguous run. Reachability is not
+/// checked if a reachable `goto` has a register target.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::mobj::{assemble_program, lint_program, MObjLintType};
+/// let program = assemble_program("goto 3\njump_title 1\nmove PSR20, 1\ngoto 5").unwrap();
+/// let lints = lint_program(&program);
+/// assert_eq!(lints[0].pc, 1);
+/// assert_eq!(lints[0].lint_type, MObjLintType::Unreachable);
+/// assert_eq!(lints[1].lint_type, MObjLintType::ReadOnlyPsrWrite(20));
+/// assert_eq!(lints[2].lint_type, MObjLintType::InvalidBranchTarget(5));
+/// ```
+pub fn lint_program(cmds: &[MObjCmd]) -> Vec<MObjLint> {
+    let mut lints = Vec::new();
+    let mut flows = Vec::with_capacity(cmds.len());
+    for (pc, cmd) in cmds.iter().enumerate() {
+        let mut lint = |lint_type| lints.push(MObjLint { pc, lint_type });
+        let (expected, flow) = match cmd
+            .visit(GetCmdOperandCount)
+            .and_then(|expected| Ok((expected, cmd.visit(GetCmdFlow)?)))
+        {
+            Ok(result) => result,
+            Err(error) => {
+                lint(MObjLintType::InvalidCommand(error));
+                flows.push(CmdFlow::Stop);
+                continue;
+            }
+        };
+
+        let found = cmd.inst.op_cnt();
+        if found != expected {
+            lint(MObjLintType::OperandCountMismatch { expected, found });
+        }
+
+        if let CmdFlow::Goto = flow {
+            if cmd.inst.imm_op1() && cmd.dst as usize > cmds.len() {
+                lint(MObjLintType::InvalidBranchTarget(cmd.dst));
+            }
+        }
+
+        if cmd.inst.grp() == MObjGroup::Set as u8 && cmd.inst.sub_grp() == SetSubGroup::Set as u8 {
+            let mut written = vec![cmd.dst_operand()];
+            if cmd.inst.set_opt() == SetInstruction::Swap as u8 {
+                written.push(cmd.src_operand());
+            }
+            for operand in written {
+                if let MObjOperand::Psr(psr) = operand {
+                    if operand.is_read_only_psr() {
+                        lint(MObjLintType::ReadOnlyPsrWrite(psr));
+                    }
+                }
+            }
+        }
+
+        flows.push(flow);
+    }
+
+    /* Walk the control flow from the first command */
+    let mut reachable = vec![false; cmds.len()];
+    let mut pending = vec![0];
+    while let Some(pc) = pending.pop() {
+        if pc >= cmds.len() || reachable[pc] {
+            continue;
+        }
+        reachable[pc] = true;
+        match flows[pc] {
+            CmdFlow::Next => pending.push(pc + 1),
+            CmdFlow::Skip => pending.extend_from_slice(&[pc + 1, pc + 2]),
+            CmdFlow::Goto if cmds[pc].inst.imm_op1() => pending.push(cmds[pc].dst as usize),
+            CmdFlow::Goto => {
+                reachable.iter_mut().for_each(|r| *r = true);
+                break;
+            }
+            CmdFlow::Stop => {}
+        }
+    }
+    for pc in 0..cmds.len() {
+        if !reachable[pc] && (pc == 0 || reachable[pc - 1]) {
+            lints.push(MObjLint {
+                pc,
+                lint_type: MObjLintType::Unreachable,
+            });
+        }
+    }
+
+    lints.sort_by_key(|lint| lint.pc);
+    lints
+}
+
+#[cfg(test)]
 fn assemble_cmd(s: &str) -> String {
-    MObjCmd::assemble(s).unwrap().to_string()
+    let cmd = MObjCmd::assemble(s).unwrap();
+
+    /* Encoded bytecode must decode to the same command */
+    let bytes = cmd.into_bytes();
+    let decoded = MObjCmd::parse(&mut SliceReader::<super::DefaultBdavAppDetails>::new(
+        &bytes,
+    ))
+    .unwrap();
+    assert_eq!(decoded.into_bytes(), bytes);
+    assert_eq!(decoded.to_string(), cmd.to_string());
+
+    cmd.to_string()
 }
 
+#[cfg(test)]
 fn test_cmd(s: &str) {
     assert_eq!(assemble_cmd(s), s);
 }
@@ -1018,3 +1728,189 @@ fn test_assemble_cmds() {
     test_cmd("set_stream_ss r1, r2, enabled, r3, r4");
     test_cmd("bd_plus_msg r1, r2");
 }
+
+#[test]
+fn test_cmd_constructors() {
+    use MObjOperand::{Gpr, Psr};
+
+    let cmds = vec![
+        (MObjCmd::nop(), "nop"),
+        (MObjCmd::goto(3), "goto 3"),
+        (MObjCmd::break_(), "break"),
+        (MObjCmd::jump_title(Gpr(1)), "jump_title r1"),
+        (MObjCmd::resume(), "resume"),
+        (MObjCmd::play_pl_pm(1, Gpr(2)), "play_pl_pm 1, r2"),
+        (MObjCmd::terminate_pl(), "terminate_pl"),
+        (MObjCmd::link_mk(Psr(5)), "link_mk PSR5"),
+        (MObjCmd::lt(Gpr(1), 10), "lt r1, 10"),
+        (MObjCmd::mov(Gpr(4095), Psr(127)), "move r4095, PSR127"),
+        (MObjCmd::modulo(Gpr(1), Gpr(2)), "mod r1, r2"),
+        (MObjCmd::shr(Gpr(1), 2), "shr r1, 2"),
+        (MObjCmd::set_nv_timer(1, 60), "set_nv_timer 1, 60"),
+        (MObjCmd::enable_button(Gpr(1)), "enable_button r1"),
+        (MObjCmd::popup_off(), "popup_off"),
+        (MObjCmd::set_output_mode(Gpr(1)), "set_output_mode r1"),
+        (MObjCmd::bd_plus_msg(Gpr(1), Gpr(2)), "bd_plus_msg r1, r2"),
+        (
+            MObjCmd::set_button_page(Some(Gpr(1)), None, true),
+            "set_button_page r1, none, skip_out",
+        ),
+    ];
+    for (cmd, s) in cmds {
+        assert_eq!(cmd.into_bytes(), MObjCmd::assemble(s).unwrap().into_bytes());
+        assert_eq!(assemble_cmd(s), cmd.to_string());
+    }
+}
+
+#[test]
+fn test_assemble_program() {
+    let text = "
+        /* Multi-line
+           comment: with a colon */
+        start:  move r0, 0
+        loop:
+            add r0, 1   // increment
+            lt r0, 10
+            goto loop
+            goto end
+            goto start
+        end:
+    ";
+    let program = assemble_program(text).unwrap();
+    assert_eq!(program.len(), 6);
+    assert_eq!(program[3].to_string(), "goto 1");
+    assert_eq!(program[4].to_string(), "goto 6");
+    assert_eq!(program[5].to_string(), "goto 0");
+
+    let disassembled = disassemble_program(&program);
+    let reassembled = assemble_program(&disassembled).unwrap();
+    assert_eq!(
+        reassembled
+            .iter()
+            .map(|c| c.into_bytes())
+            .collect::<Vec<_>>(),
+        program.iter().map(|c| c.into_bytes()).collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        assemble_program("a: nop\na: nop").unwrap_err(),
+        MObjProgramError {
+            line: 1,
+            error_type: MObjProgramErrorType::DuplicateLabel("a".to_string())
+        }
+    );
+    assert_eq!(
+        assemble_program("nop\ngoto nowhere").unwrap_err(),
+        MObjProgramError {
+            line: 1,
+            error_type: MObjProgramErrorType::UndefinedLabel("nowhere".to_string())
+        }
+    );
+    assert_eq!(
+        assemble_program("r1: nop").unwrap_err(),
+        MObjProgramError {
+            line: 0,
+            error_type: MObjProgramErrorType::InvalidLabel("r1".to_string())
+        }
+    );
+    assert!(matches!(
+        assemble_program("nop\nbogus r1").unwrap_err(),
+        MObjProgramError {
+            line: 1,
+            error_type: MObjProgramErrorType::Parse(_)
+        }
+    ));
+    assert_eq!(
+        assemble_program("goto r1").unwrap()[0].to_string(),
+        "goto r1"
+    );
+}
+
+#[test]
+fn test_lint_program() {
+    let mut program = assemble_program(
+        "
+        eq r0, 1
+        goto skip
+        call_title 1
+        resume
+        nop
+        skip:
+        swap r1, PSR13
+        play_pl_pi 1, 2
+        still_on
+        goto 100
+        break
+        ",
+    )
+    .unwrap();
+    /* play_pl_pi with a single operand */
+    program[6].inst.set_op_cnt(1);
+    program.push(MObjCmd {
+        inst: MObjInstruction::new().with_grp(3),
+        dst: 0,
+        src: 0,
+    });
+    assert_eq!(
+        lint_program(&program),
+        vec![
+            MObjLint {
+                pc: 4,
+                lint_type: MObjLintType::Unreachable
+            },
+            MObjLint {
+                pc: 5,
+                lint_type: MObjLintType::ReadOnlyPsrWrite(13)
+            },
+            MObjLint {
+                pc: 6,
+                lint_type: MObjLintType::OperandCountMismatch {
+                    expected: 2,
+                    found: 1
+                }
+            },
+            MObjLint {
+                pc: 8,
+                lint_type: MObjLintType::InvalidBranchTarget(100)
+            },
+            MObjLint {
+                pc: 9,
+                lint_type: MObjLintType::Unreachable
+            },
+            MObjLint {
+                pc: 10,
+                lint_type: MObjLintType::InvalidCommand(MObjCmdErrorDetails::UnknownMObjGroup(3))
+            },
+        ]
+    );
+    assert_eq!(
+        lint_program(&program)[1].to_string(),
+        "command 5: write to read-only PSR13"
+    );
+
+    /* Register goto targets may reach anything */
+    let program = assemble_program("goto r1\nnop").unwrap();
+    assert!(lint_program(&program).is_empty());
+}
+
+#[test]
+fn test_disassemble() {
+    use super::DefaultBdavAppDetails;
+
+    let program = assemble_program("move r1, PSR20\nlt r1, 2\nplay_pl_pi 1, r1\nresume").unwrap();
+    let mut data = Vec::new();
+    for cmd in &program {
+        cmd.write_to(&mut data).unwrap();
+    }
+    let cmds = disassemble::<DefaultBdavAppDetails>(&data).unwrap();
+    assert_eq!(
+        format_listing(&cmds),
+        "   0: move r1, PSR20 /* RO: Region code */ /* 1 - A, 2 - B, 4 - C */\n   \
+         1: lt r1, 2\n   \
+         2: play_pl_pi 1, r1\n   \
+         3: resume\n"
+    );
+
+    /* Partial trailing command */
+    assert!(disassemble::<DefaultBdavAppDetails>(&data[..30]).is_err());
+}
@@ -3,23 +3,46 @@
 //! Supports parsing program graphics (PG) and interactive graphics (IG) data.
 
 use super::{
-    read_bitfield, AppDetails, Error, MpegTsParser, Packet, Payload, PesUnitObject, Result,
-    SliceReader,
+    read_bitfield, AppDetails, Error, EsStreamInfo, Framing, MpegTsParser, MpegTsParserBuilder,
+    Packet, Payload, PesUnitObject, Result, SliceReader,
 };
+use crate::prelude::*;
 use log::warn;
 use modular_bitfield_msb::prelude::*;
 use num_traits::FromPrimitive;
 
+pub mod bdmv;
+
+pub mod clpi;
+
+pub mod decoder_model;
+
+pub mod descriptor;
+
+pub mod menu;
+
 pub mod mobj;
 use mobj::{MObjCmd, MObjCmdErrorDetails};
 
 pub mod pg;
 use crate::ErrorDetails;
+use hashbrown::HashMap;
 use pg::{
     FrameRate, PgCompositionDescriptor, PgCompositionUnitState, PgSegmentData, TgHAlign,
     TgOutlineThickness, TgTextFlow, TgVAlign,
 };
-use std::collections::HashMap;
+
+pub mod psr;
+
+#[cfg(feature = "std")]
+pub mod sup;
+
+pub mod textst;
+
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub use writer::BdavWriter;
 
 fn from_primitive_map_err<
     T: num_traits::FromPrimitive,
@@ -29,7 +52,7 @@ fn from_primitive_map_err<
 >(
     val: U,
     err_fn: F,
-) -> std::result::Result<T, E> {
+) -> core::result::Result<T, E> {
     match FromPrimitive::from_u64(val.clone().into()) {
         Some(v) => Ok(v),
         None => Err(err_fn(val)),
@@ -48,7 +71,7 @@ pub struct BdavPacketHeader {
 
 /// Top-level parsed structure for one BDAV packet.
 #[derive(Debug)]
-pub struct BdavPacket<'a, D> {
+pub struct BdavPacket<'a, D: AppDetails> {
     /// BDAV-specific header.
     pub header: BdavPacketHeader,
     /// MPEG-TS packet.
@@ -78,6 +101,12 @@ pub enum BdavErrorDetails {
     UnknownTgVAlign(u8),
     /// Encountered an unknown [`TgOutlineThickness`].
     UnknownTgOutlineThickness(u8),
+    /// Encountered a malformed [`ClipInfo`](clpi::ClipInfo) file.
+    BadClpi,
+    /// Encountered a malformed [`MovieObjectFile`](bdmv::MovieObjectFile).
+    BadBdmv,
+    /// Encountered a segment without the `PG` magic in a [`SupReader`](sup::SupReader).
+    BadSupMagic,
 }
 
 /// Cross-payload state for BDAV parsing.
@@ -95,7 +124,9 @@ pub trait BdavAppDetails:
 
 /// [`BdavAppDetails`] implementation for [`BdavParser::default`].
 ///
-/// Currently just handles parsing [`PgSegmentData`].
+/// Currently just handles parsing [`PgSegmentData`]. Streams are selected by the PMT stream type
+/// (0x90 PG, 0x91 IG, 0x92 text subtitles), falling back to the standard BDAV PID ranges when no
+/// PMT has listed the PID.
 #[derive(Default, Debug)]
 pub struct DefaultBdavAppDetails;
 
@@ -104,18 +135,33 @@ impl AppDetails for DefaultBdavAppDetails {
 
     type AppParserStorage = BdavParserStorage;
 
-    fn new_pes_unit_data(pid: u16, unit_length: usize) -> Option<Box<dyn PesUnitObject<Self>>> {
-        match pid {
-            0x1200..=0x121f | 0x1400..=0x141f | 0x1800 => {
-                Some(Box::new(PgSegmentData::new(unit_length)))
-            }
-            _ => None,
+    fn new_pes_unit_data(
+        &self,
+        pid: u16,
+        unit_length: usize,
+        stream_info: Option<&EsStreamInfo>,
+    ) -> Option<Box<dyn PesUnitObject<Self>>> {
+        let is_pg_data = match stream_info {
+            Some(info) => matches!(info.stream_type, 0x90..=0x92),
+            None => matches!(pid, 0x1200..=0x121f | 0x1400..=0x141f | 0x1800),
+        };
+        if is_pg_data {
+            Some(Box::new(PgSegmentData::new(unit_length)))
+        } else {
+            None
         }
     }
 }
 
 impl BdavAppDetails for DefaultBdavAppDetails {}
 
+impl<D: BdavAppDetails> MpegTsParserBuilder<D> {
+    /// Creates the configured parser for BDAV packets.
+    pub fn build_bdav(self) -> BdavParser<D> {
+        BdavParser(self.build())
+    }
+}
+
 /// Top-level parser state for 192-byte packets found in BDAV (aka M2TS) streams.
 ///
 /// # Example
@@ -150,6 +196,20 @@ impl Default for BdavParser {
 }
 
 impl<D: BdavAppDetails> BdavParser<D> {
+    /// Starts configuring a parser. Finish with [`MpegTsParserBuilder::build_bdav`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::bdav::{BdavParser, DefaultBdavAppDetails};
+    /// let parser = BdavParser::<DefaultBdavAppDetails>::builder()
+    ///     .check_continuity(true)
+    ///     .build_bdav();
+    /// ```
+    pub fn builder() -> MpegTsParserBuilder<D> {
+        MpegTsParser::builder()
+    }
+
     /// Parse data for exactly one 192-byte BDAV packet.
     ///
     /// All information about the packet is returned as [`BdavPacket`].
@@ -158,11 +218,11 @@ impl<D: BdavAppDetails> BdavParser<D> {
     /// [`Payload`]. Once the final packet of the unit is read, the entire unit is parsed and made
     /// available in the [`Payload`].
     pub fn parse<'a>(&mut self, packet: &'a [u8; 192]) -> Result<BdavPacket<'a, D>, D> {
-        let mut reader = SliceReader::new(packet);
-        let header = read_bitfield!(reader, BdavPacketHeader);
+        let framed = self.0.parse_framed(Framing::TIMED, packet)?;
+        let header = read_bitfield!(SliceReader::<D>::new(framed.prefix), BdavPacketHeader);
         Ok(BdavPacket {
             header,
-            packet: self.0.parse_internal(reader)?,
+            packet: framed.packet,
         })
     }
 }
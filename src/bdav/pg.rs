@@ -5,16 +5,19 @@ use super::{
     from_primitive_map_err, mobj::MObjCmd, read_bitfield, BdavAppDetails, BdavErrorDetails,
     BdavParserStorage, MpegTsParser, PesUnitObject, SliceReader,
 };
-use crate::{ErrorDetails, Result};
+use crate::prelude::*;
+use crate::{ErrorDetails, Result, Timestamp90k};
+use core::cmp::min;
+use core::fmt::{Debug, Formatter};
+use hashbrown::HashMap;
 use log::warn;
 use modular_bitfield_msb::prelude::*;
 use num_derive::FromPrimitive;
 use smallvec::SmallVec;
-use std::cmp::min;
-use std::fmt::{Debug, Formatter};
 
 /// A YCbCrA palette entry.
 #[derive(Debug, Default, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsPaletteEntry {
     /// Luminance
     pub y: u8,
@@ -26,18 +29,88 @@ pub struct PgsPaletteEntry {
     pub t: u8,
 }
 
+/// YCbCr to RGB conversion matrix of a video format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorMatrix {
+    /// ITU-R BT.601, used by SD video.
+    Bt601,
+    /// ITU-R BT.709, used by HD video.
+    Bt709,
+}
+
+impl ColorMatrix {
+    /// Matrix of video with `video_height` lines: BT.709 above 576 lines, BT.601 otherwise.
+    pub fn for_video_height(video_height: u16) -> Self {
+        if video_height > 576 {
+            ColorMatrix::Bt709
+        } else {
+            ColorMatrix::Bt601
+        }
+    }
+}
+
+impl PgsPaletteEntry {
+    /// Converts the limited range entry to non-premultiplied RGBA.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::bdav::pg::{ColorMatrix, PgsPaletteEntry};
+    /// let white = PgsPaletteEntry { y: 235, cr: 128, cb: 128, t: 255 };
+    /// assert_eq!(white.to_rgba(ColorMatrix::Bt709), [255, 255, 255, 255]);
+    /// let green = PgsPaletteEntry { y: 145, cr: 54, cb: 54, t: 128 };
+    /// assert_eq!(green.to_rgba(ColorMatrix::Bt601), [32, 239, 1, 128]);
+    /// assert_eq!(green.to_rgba(ColorMatrix::Bt709), [17, 205, 0, 128]);
+    /// ```
+    pub fn to_rgba(&self, matrix: ColorMatrix) -> [u8; 4] {
+        let y = 1.164 * (self.y as f32 - 16.0);
+        let cr = self.cr as f32 - 128.0;
+        let cb = self.cb as f32 - 128.0;
+        let (r_cr, g_cr, g_cb, b_cb) = match matrix {
+            ColorMatrix::Bt601 => (1.596, 0.813, 0.391, 2.018),
+            ColorMatrix::Bt709 => (1.793, 0.533, 0.213, 2.112),
+        };
+        let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+        [
+            clamp(y + r_cr * cr),
+            clamp(y - g_cr * cr - g_cb * cb),
+            clamp(y + b_cb * cb),
+            self.t,
+        ]
+    }
+}
+
+/// Converts all 256 entries of a palette to RGBA with [`PgsPaletteEntry::to_rgba`].
+pub fn palette_to_rgba(entries: &[PgsPaletteEntry; 256], matrix: ColorMatrix) -> [[u8; 4]; 256] {
+    let mut out = [[0; 4]; 256];
+    for (out, entry) in out.iter_mut().zip(entries.iter()) {
+        *out = entry.to_rgba(matrix);
+    }
+    out
+}
+
 /// A palette object that defines colors for [`PgsObject`] objects.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsPalette {
     /// Palette ID
     pub id: u8,
     /// Format version
     pub version: u8,
     /// 256 palette entries
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialize::serialize_palette")
+    )]
     pub entries: Box<[PgsPaletteEntry; 256]>,
 }
 
 impl PgsPalette {
+    /// Converts all entries to RGBA with [`PgsPaletteEntry::to_rgba`].
+    pub fn to_rgba(&self, matrix: ColorMatrix) -> [[u8; 4]; 256] {
+        palette_to_rgba(&self.entries, matrix)
+    }
+
     fn parse<D: BdavAppDetails>(
         reader: &mut SliceReader<D>,
         storage: &mut BdavParserStorage,
@@ -60,9 +133,18 @@ impl PgsPalette {
 
         Ok(out)
     }
+
+    /// Appends the segment body to `out`. Entries that are entirely zero are omitted.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.id);
+        out.push(self.version);
+        write_palette_entries(out, &self.entries);
+    }
 }
 
 /// Final parsed data of [`PgsObject`].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsObjectData {
     /// Object width.
     pub width: u16,
@@ -73,7 +155,7 @@ pub struct PgsObjectData {
 }
 
 impl Debug for PgsObjectData {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("PgsObjectData")
             .field("width", &self.width)
             .field("height", &self.height)
@@ -96,10 +178,111 @@ impl PgsObjectData {
             data,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.width.to_be_bytes());
+        out.extend_from_slice(&self.height.to_be_bytes());
+        out.extend_from_slice(&self.data);
+    }
+
+    /// Expands the RLE data into `width * height` palette indices, row-major.
+    ///
+    /// Lines are padded or truncated to the object width; missing lines are left as index 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::bdav::pg::PgsObjectData;
+    /// let object = PgsObjectData::encode(4, 2, &[1, 1, 1, 0, 0, 0, 0, 2]);
+    /// assert_eq!(object.data, [0x00, 0x83, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x02, 0x00, 0x00]);
+    /// assert_eq!(object.decode(), [1, 1, 1, 0, 0, 0, 0, 2]);
+    /// ```
+    pub fn decode(&self) -> Vec<u8> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let mut pixels = vec![0; width * height];
+        let mut line = 0;
+        let mut x = 0;
+        let mut pos = 0;
+        let data = self.data.as_slice();
+        while pos < data.len() && line < height {
+            let (count, color) = match data[pos] {
+                0 => {
+                    let flags = match data.get(pos + 1) {
+                        Some(flags) => *flags,
+                        None => break,
+                    };
+                    pos += 2;
+                    let mut count = (flags & 0x3f) as usize;
+                    if flags & 0x40 != 0 {
+                        count = count << 8 | *data.get(pos).unwrap_or(&0) as usize;
+                        pos += 1;
+                    }
+                    let color = if flags & 0x80 != 0 {
+                        pos += 1;
+                        *data.get(pos - 1).unwrap_or(&0)
+                    } else {
+                        0
+                    };
+                    if flags == 0 {
+                        /* End of line */
+                        if x != width {
+                            warn!("PgsObject line {} has {} of {} pixels", line, x, width);
+                        }
+                        line += 1;
+                        x = 0;
+                        continue;
+                    }
+                    (count, color)
+                }
+                color => {
+                    pos += 1;
+                    (1, color)
+                }
+            };
+            let end = min(x + count, width);
+            pixels[line * width + x..line * width + end].fill(color);
+            x = end;
+        }
+        pixels
+    }
+
+    /// Run-length encodes `width * height` palette indices, row-major.
+    pub fn encode(width: u16, height: u16, pixels: &[u8]) -> Self {
+        let mut data = Vec::new();
+        for row in pixels.chunks(width as usize).take(height as usize) {
+            let mut x = 0;
+            while x < row.len() {
+                let color = row[x];
+                let count = row[x..]
+                    .iter()
+                    .take(0x3fff)
+                    .take_while(|pixel| **pixel == color)
+                    .count();
+                match (color, count) {
+                    (0, 1..=0x3f) => data.extend_from_slice(&[0, count as u8]),
+                    (0, _) => data.extend_from_slice(&[0, 0x40 | (count >> 8) as u8, count as u8]),
+                    (_, 1..=2) => data.extend(core::iter::repeat_n(color, count)),
+                    (_, 3..=0x3f) => data.extend_from_slice(&[0, 0x80 | count as u8, color]),
+                    _ => {
+                        data.extend_from_slice(&[0, 0xc0 | (count >> 8) as u8, count as u8, color])
+                    }
+                }
+                x += count;
+            }
+            data.extend_from_slice(&[0, 0]);
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
 }
 
 /// An indexed-color image used within a graphics composition.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsObject {
     /// Object ID
     pub id: u16,
@@ -195,10 +378,30 @@ impl PgsObject {
             }
         }
     }
+
+    /// Serializes the object as one or more complete segments, splitting data that does not fit
+    /// in one segment into a fragment sequence.
+    ///
+    /// Intermediate fragments carry no reassembled data and produce no segments; the final
+    /// fragment of a parsed sequence produces the entire object.
+    pub fn to_segments(&self) -> Vec<Vec<u8>> {
+        match self.data.as_ref() {
+            Some(data) => {
+                let mut prefix = Vec::with_capacity(3);
+                prefix.extend_from_slice(&self.id.to_be_bytes());
+                prefix.push(self.version);
+                let mut body = Vec::with_capacity(4 + data.data.len());
+                data.write_to(&mut body);
+                fragment_segments(0x15, &prefix, &body)
+            }
+            None => Vec::new(),
+        }
+    }
 }
 
 /// A program graphics composition.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsPgComposition {
     /// Viewport and frame rate information.
     pub video_descriptor: PgVideoDescriptor,
@@ -236,10 +439,23 @@ impl PgsPgComposition {
             composition_objects,
         })
     }
+
+    /// Appends the segment body to `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        self.video_descriptor.write_to(out);
+        self.composition_descriptor.write_to(out);
+        out.push(if self.palette_update_flag { 0x80 } else { 0 });
+        out.push(self.palette_id_ref);
+        out.push(self.composition_objects.len() as u8);
+        for object in self.composition_objects.iter() {
+            object.write_to(out);
+        }
+    }
 }
 
 /// A collection of windows for referencing by [`PgCompositionObject`] objects.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsWindow {
     /// Windows in the collection.
     pub windows: Vec<PgWindow>,
@@ -257,10 +473,19 @@ impl PgsWindow {
         }
         Ok(Self { windows })
     }
+
+    /// Appends the segment body to `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.windows.len() as u8);
+        for window in self.windows.iter() {
+            window.write_to(out);
+        }
+    }
 }
 
 /// Frame rate used for timing in an [`PgsIgComposition`].
 #[derive(Debug, Copy, Clone, PartialEq, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FrameRate {
     /// Unspecified frame rate; animated effects not possible.
     Invalid,
@@ -279,14 +504,15 @@ pub enum FrameRate {
 }
 
 /// Video viewport information for the graphics composition.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgVideoDescriptor {
     /// Width in pixels.
-    video_width: u16,
+    pub video_width: u16,
     /// Height in pixels.
-    video_height: u16,
+    pub video_height: u16,
     /// Frame rate.
-    frame_rate: FrameRate,
+    pub frame_rate: FrameRate,
 }
 
 impl PgVideoDescriptor {
@@ -304,11 +530,18 @@ impl PgVideoDescriptor {
             frame_rate,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.video_width.to_be_bytes());
+        out.extend_from_slice(&self.video_height.to_be_bytes());
+        out.push((self.frame_rate as u8) << 4);
+    }
 }
 
 /// Streaming information about a PG PES unit.
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PgCompositionUnitState {
     /// An object that adds to the composition being streamed.
     Incremental,
@@ -320,6 +553,7 @@ pub enum PgCompositionUnitState {
 
 /// Information about the sequence of PES units that make up a composition.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgCompositionDescriptor {
     /// Unique identifier of composition for assembling unit fragments.
     pub number: u16,
@@ -337,10 +571,16 @@ impl PgCompositionDescriptor {
         })?;
         Ok(Self { number, state })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.number.to_be_bytes());
+        out.push((self.state.clone() as u8) << 6);
+    }
 }
 
 /// Flags that indicate the position of a segment split across multiple units.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgSequenceDescriptor {
     /// Is first in sequence.
     pub first_in_seq: bool,
@@ -360,7 +600,7 @@ impl PgSequenceDescriptor {
 
 /// User operations mask.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct UoMask {
     pub menu_call: bool,
     pub title_search: bool,
@@ -404,7 +644,8 @@ pub struct UoMask {
 
 /// Sub-rectangle in a composition for positioning [`PgCompositionObject`] objects in an
 /// [`IgEffectSequence`] or for [`PgsWindow`] objects within a [`PgsPgComposition`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgWindow {
     /// Window ID.
     pub id: u8,
@@ -433,10 +674,19 @@ impl PgWindow {
             height,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.id);
+        out.extend_from_slice(&self.x.to_be_bytes());
+        out.extend_from_slice(&self.y.to_be_bytes());
+        out.extend_from_slice(&self.width.to_be_bytes());
+        out.extend_from_slice(&self.height.to_be_bytes());
+    }
 }
 
 /// Clipping dimensions for a [`PgCompositionObject`]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgCrop {
     /// X Pos.
     pub x: u16,
@@ -456,10 +706,18 @@ impl PgCrop {
         let h = reader.read_be_u16()?;
         Ok(Self { x, y, w, h })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.x.to_be_bytes());
+        out.extend_from_slice(&self.y.to_be_bytes());
+        out.extend_from_slice(&self.w.to_be_bytes());
+        out.extend_from_slice(&self.h.to_be_bytes());
+    }
 }
 
 /// A positioned graphical element of a composition.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgCompositionObject {
     /// Object ID.
     pub object_id_ref: u16,
@@ -496,10 +754,29 @@ impl PgCompositionObject {
             crop,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.object_id_ref.to_be_bytes());
+        out.push(self.window_id_ref);
+        let mut bits = 0;
+        if self.crop.is_some() {
+            bits |= 0x80;
+        }
+        if self.forced_on_flag {
+            bits |= 0x40;
+        }
+        out.push(bits);
+        out.extend_from_slice(&self.x.to_be_bytes());
+        out.extend_from_slice(&self.y.to_be_bytes());
+        if let Some(crop) = self.crop.as_ref() {
+            crop.write_to(out);
+        }
+    }
 }
 
 /// A set of [`PgCompositionObject`] objects that are displayed for a fixed duration.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgEffect {
     /// Display duration in 90kHz ticks.
     pub duration: u32,
@@ -524,10 +801,20 @@ impl IgEffect {
             composition_objects,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        write_be_u24(out, self.duration);
+        out.push(self.palette_id_ref);
+        out.push(self.composition_objects.len() as u8);
+        for object in self.composition_objects.iter() {
+            object.write_to(out);
+        }
+    }
 }
 
 /// Collects windows and effects to animate hide/show transitions of a composition.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgEffectSequence {
     /// Windows for composition objects contained in effects.
     pub windows: Vec<PgWindow>,
@@ -549,10 +836,22 @@ impl IgEffectSequence {
         }
         Ok(Self { windows, effects })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.windows.len() as u8);
+        for window in self.windows.iter() {
+            window.write_to(out);
+        }
+        out.push(self.effects.len() as u8);
+        for effect in self.effects.iter() {
+            effect.write_to(out);
+        }
+    }
 }
 
 /// Complete definition of an interactive button.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgButton {
     /// Button ID.
     pub id: u16,
@@ -645,10 +944,37 @@ impl IgButton {
             nav_cmds,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.numeric_select_value.to_be_bytes());
+        out.push(if self.auto_action_flag { 0x80 } else { 0 });
+        out.extend_from_slice(&self.x_pos.to_be_bytes());
+        out.extend_from_slice(&self.y_pos.to_be_bytes());
+        out.extend_from_slice(&self.upper_button_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.lower_button_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.left_button_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.right_button_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.normal_start_object_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.normal_end_object_id_ref.to_be_bytes());
+        out.push(if self.normal_repeat_flag { 0x80 } else { 0 });
+        out.push(self.selected_sound_id_ref);
+        out.extend_from_slice(&self.selected_start_object_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.selected_end_object_id_ref.to_be_bytes());
+        out.push(if self.selected_repeat_flag { 0x80 } else { 0 });
+        out.push(self.activated_sound_id_ref);
+        out.extend_from_slice(&self.activated_start_object_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.activated_end_object_id_ref.to_be_bytes());
+        out.extend_from_slice(&(self.nav_cmds.len() as u16).to_be_bytes());
+        for cmd in self.nav_cmds.iter() {
+            out.extend_from_slice(&cmd.into_bytes());
+        }
+    }
 }
 
 /// Logical grouping of buttons used to implement selection hierarchies.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgBog {
     /// Default button ID within group.
     pub default_valid_button_id_ref: u16,
@@ -669,10 +995,19 @@ impl IgBog {
             buttons,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.default_valid_button_id_ref.to_be_bytes());
+        out.push(self.buttons.len() as u8);
+        for button in self.buttons.iter() {
+            button.write_to(out);
+        }
+    }
 }
 
 /// Collection of buttons such that only one is visible at a time.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgPage {
     /// Page ID.
     pub id: u8,
@@ -725,11 +1060,174 @@ impl IgPage {
             bogs,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.id);
+        out.push(self.version);
+        out.extend_from_slice(&self.uo_mask.into_bytes());
+        self.in_effects.write_to(out);
+        self.out_effects.write_to(out);
+        out.push(self.animation_frame_rate_code);
+        out.extend_from_slice(&self.default_selected_button_id_ref.to_be_bytes());
+        out.extend_from_slice(&self.default_activated_button_id_ref.to_be_bytes());
+        out.push(self.palette_id_ref);
+        out.push(self.bogs.len() as u8);
+        for bog in self.bogs.iter() {
+            bog.write_to(out);
+        }
+    }
+
+    /// Buttons of all button groups of the page.
+    pub fn buttons(&self) -> impl Iterator<Item = &IgButton> {
+        self.bogs.iter().flat_map(|bog| bog.buttons.iter())
+    }
+
+    /// Looks up a button of the page by ID.
+    pub fn button(&self, id: u16) -> Option<&IgButton> {
+        self.buttons().find(|button| button.id == id)
+    }
+
+    /// Looks up the neighbor of a button in a navigation direction. A button without a neighbor in
+    /// that direction refers to itself.
+    pub fn neighbor(&self, id: u16, direction: IgDirection) -> Option<&IgButton> {
+        let button = self.button(id)?;
+        self.button(match direction {
+            IgDirection::Up => button.upper_button_id_ref,
+            IgDirection::Down => button.lower_button_id_ref,
+            IgDirection::Left => button.left_button_id_ref,
+            IgDirection::Right => button.right_button_id_ref,
+        })
+    }
+
+    /// Builds the navigation graph of the page.
+    pub fn navigation_graph(&self) -> IgNavigationGraph {
+        let mut edges = Vec::new();
+        for button in self.buttons() {
+            let neighbors = [
+                (IgDirection::Up, button.upper_button_id_ref),
+                (IgDirection::Down, button.lower_button_id_ref),
+                (IgDirection::Left, button.left_button_id_ref),
+                (IgDirection::Right, button.right_button_id_ref),
+            ];
+            for (direction, to) in neighbors.iter() {
+                if *to != button.id {
+                    edges.push(IgNavigationEdge {
+                        from: button.id,
+                        to: *to,
+                        direction: *direction,
+                    });
+                }
+            }
+        }
+        IgNavigationGraph {
+            page_id: self.id,
+            bogs: self
+                .bogs
+                .iter()
+                .map(|bog| bog.buttons.iter().map(|button| button.id).collect())
+                .collect(),
+            edges,
+        }
+    }
+}
+
+/// Direction of a navigation key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IgDirection {
+    /// Up key.
+    Up,
+    /// Down key.
+    Down,
+    /// Left key.
+    Left,
+    /// Right key.
+    Right,
+}
+
+/// Navigation from one button to another, see [`IgNavigationGraph`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IgNavigationEdge {
+    /// Button ID navigated from.
+    pub from: u16,
+    /// Button ID navigated to.
+    pub to: u16,
+    /// Navigation key.
+    pub direction: IgDirection,
+}
+
+/// Button navigation of one [`IgPage`], built with [`IgPage::navigation_graph`].
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::pg::PgsIgComposition;
+///
+/// # fn load() -> PgsIgComposition { unimplemented!() }
+/// let composition = load();
+/// let page = composition.page(0).unwrap();
+/// let graph = page.navigation_graph();
+/// for edge in graph.dangling_edges() {
+///     println!("button {} leads to missing button {}", edge.from, edge.to);
+/// }
+/// let reachable = graph.reachable_from(page.default_selected_button_id_ref);
+/// println!("{} of {} buttons reachable", reachable.len(), page.buttons().count());
+/// ```
+#[derive(Debug, Clone)]
+pub struct IgNavigationGraph {
+    /// Page ID.
+    pub page_id: u8,
+    /// Button IDs of each button group of the page.
+    pub bogs: Vec<Vec<u16>>,
+    /// Navigation between distinct buttons; edges of buttons referring to themselves are omitted.
+    pub edges: Vec<IgNavigationEdge>,
+}
+
+impl IgNavigationGraph {
+    /// Checks whether the page contains a button.
+    pub fn contains(&self, id: u16) -> bool {
+        self.bogs.iter().any(|bog| bog.contains(&id))
+    }
+
+    /// Index of the button group containing a button.
+    pub fn bog_of(&self, id: u16) -> Option<usize> {
+        self.bogs.iter().position(|bog| bog.contains(&id))
+    }
+
+    /// Edges leading from a button.
+    pub fn edges_from(&self, id: u16) -> impl Iterator<Item = &IgNavigationEdge> {
+        self.edges.iter().filter(move |edge| edge.from == id)
+    }
+
+    /// Edges leading to buttons missing from the page.
+    pub fn dangling_edges(&self) -> impl Iterator<Item = &IgNavigationEdge> {
+        self.edges
+            .iter()
+            .filter(move |edge| !self.contains(edge.to))
+    }
+
+    /// Button IDs reachable from a button by navigation keys, starting with the button itself.
+    pub fn reachable_from(&self, id: u16) -> Vec<u16> {
+        if !self.contains(id) {
+            return Vec::new();
+        }
+        let mut reachable = vec![id];
+        let mut pos = 0;
+        while pos < reachable.len() {
+            for edge in self.edges_from(reachable[pos]) {
+                if self.contains(edge.to) && !reachable.contains(&edge.to) {
+                    reachable.push(edge.to);
+                }
+            }
+            pos += 1;
+        }
+        reachable
+    }
 }
 
 /// UI Model used in an [`IgInteractiveComposition`].
 #[repr(u8)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum IgUiModel {
     /// Always on menu.
     AlwaysOn,
@@ -739,6 +1237,7 @@ pub enum IgUiModel {
 
 /// Interactive UI composition containing pages of buttons.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IgInteractiveComposition {
     /// TODO: Figure this out
     pub stream_model: bool,
@@ -787,10 +1286,36 @@ impl IgInteractiveComposition {
             pages,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        let mut model_bits = 0;
+        if self.stream_model {
+            model_bits |= 0x80;
+        }
+        if let IgUiModel::Popup = self.ui_model {
+            model_bits |= 0x40;
+        }
+        out.push(model_bits);
+        if !self.stream_model {
+            write_be_u33(out, self.composition_timeout_pts.unwrap_or(0));
+            write_be_u33(out, self.selection_timeout_pts.unwrap_or(0));
+        }
+        write_be_u24(out, self.user_timeout_duration);
+        out.push(self.pages.len() as u8);
+        for page in self.pages.iter() {
+            page.write_to(out);
+        }
+    }
+
+    /// Looks up a page by ID.
+    pub fn page(&self, id: u8) -> Option<&IgPage> {
+        self.pages.iter().find(|page| page.id == id)
+    }
 }
 
 /// Interactive composition unit containing top-level metadata.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsIgComposition {
     /// Viewport and frame rate information.
     pub video_descriptor: PgVideoDescriptor,
@@ -895,10 +1420,35 @@ impl PgsIgComposition {
             }
         }
     }
+
+    /// Looks up a page of the reassembled interactive composition by ID.
+    pub fn page(&self, id: u8) -> Option<&IgPage> {
+        self.interactive_composition.as_ref()?.page(id)
+    }
+
+    /// Serializes the composition as one or more complete segments, splitting data that does
+    /// not fit in one segment into a fragment sequence.
+    ///
+    /// Intermediate fragments carry no reassembled data and produce no segments; the final
+    /// fragment of a parsed sequence produces the entire composition.
+    pub fn to_segments(&self) -> Vec<Vec<u8>> {
+        match self.interactive_composition.as_ref() {
+            Some(interactive_composition) => {
+                let mut prefix = Vec::with_capacity(8);
+                self.video_descriptor.write_to(&mut prefix);
+                self.composition_descriptor.write_to(&mut prefix);
+                let mut body = Vec::new();
+                interactive_composition.write_to(&mut body);
+                fragment_segments(0x18, &prefix, &body)
+            }
+            None => Vec::new(),
+        }
+    }
 }
 
 /// Marks final PES unit and player is now be ready to display composition.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PgsEndOfDisplay {}
 
 impl PgsEndOfDisplay {
@@ -908,10 +1458,15 @@ impl PgsEndOfDisplay {
     ) -> Result<Self, D> {
         Ok(Self {})
     }
+
+    /// Appends the segment body to `out` (the body is empty).
+    #[allow(clippy::ptr_arg)]
+    pub fn write_to(&self, _out: &mut Vec<u8>) {}
 }
 
 /// Filled background rectangle for presenting text.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgRegionInfo {
     /// Rectangle region.
     pub region: TgRect,
@@ -929,10 +1484,17 @@ impl TgRegionInfo {
             background_color,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        self.region.write_to(out);
+        out.push(self.background_color);
+        out.push(0);
+    }
 }
 
 /// Rectangle dimensions.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgRect {
     /// X Pos.
     pub xpos: u16,
@@ -957,11 +1519,19 @@ impl TgRect {
             height,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.xpos.to_be_bytes());
+        out.extend_from_slice(&self.ypos.to_be_bytes());
+        out.extend_from_slice(&self.width.to_be_bytes());
+        out.extend_from_slice(&self.height.to_be_bytes());
+    }
 }
 
 /// Text flow.
 #[repr(u8)]
-#[derive(Debug, FromPrimitive)]
+#[derive(Debug, Copy, Clone, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TgTextFlow {
     /// Left-to-right, top-to-bottom.
     LeftRight = 1,
@@ -973,7 +1543,8 @@ pub enum TgTextFlow {
 
 /// Text horizontal alignment.
 #[repr(u8)]
-#[derive(Debug, FromPrimitive)]
+#[derive(Debug, Copy, Clone, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TgHAlign {
     /// Left alignment.
     Left = 1,
@@ -985,7 +1556,8 @@ pub enum TgHAlign {
 
 /// Text vertical alignment.
 #[repr(u8)]
-#[derive(Debug, FromPrimitive)]
+#[derive(Debug, Copy, Clone, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TgVAlign {
     /// Top alignment.
     Top = 1,
@@ -997,7 +1569,7 @@ pub enum TgVAlign {
 
 /// Text font style bits.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct TgFontStyle {
     #[skip]
     pub padding: B5,
@@ -1008,7 +1580,8 @@ pub struct TgFontStyle {
 
 /// Text outline thickness.
 #[repr(u8)]
-#[derive(Debug, FromPrimitive)]
+#[derive(Debug, Copy, Clone, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TgOutlineThickness {
     /// Thin.
     Thin = 1,
@@ -1020,6 +1593,7 @@ pub enum TgOutlineThickness {
 
 /// Style parameters for a text region.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgRegionStyle {
     /// Region style ID.
     pub region_style_id: u8,
@@ -1092,11 +1666,28 @@ impl TgRegionStyle {
             outline_thickness,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.region_style_id);
+        self.region_info.write_to(out);
+        self.text_box.write_to(out);
+        out.push(self.text_flow as u8);
+        out.push(self.text_halign as u8);
+        out.push(self.text_valign as u8);
+        out.push(self.line_space);
+        out.push(self.font_id_ref);
+        out.extend_from_slice(&self.font_style.into_bytes());
+        out.push(self.font_size);
+        out.push(self.font_color);
+        out.push(self.outline_color);
+        out.push(self.outline_thickness as u8);
+    }
 }
 
 /// TODO: Document me.
 #[allow(missing_docs)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgUserStyle {
     pub user_style_id: u8,
     pub region_hpos_delta: i16,
@@ -1132,6 +1723,22 @@ impl TgUserStyle {
             line_space_delta,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.user_style_id);
+        for delta in [
+            self.region_hpos_delta,
+            self.region_vpos_delta,
+            self.text_box_hpos_delta,
+            self.text_box_vpos_delta,
+            self.text_box_width_delta,
+            self.text_box_height_delta,
+        ] {
+            write_be_sm16(out, delta);
+        }
+        write_sm8(out, self.font_size_delta);
+        write_sm8(out, self.line_space_delta);
+    }
 }
 
 fn read_palette_entries<D: BdavAppDetails>(
@@ -1149,8 +1756,83 @@ fn read_palette_entries<D: BdavAppDetails>(
     Ok(palette_entries)
 }
 
+fn write_palette_entries(out: &mut Vec<u8>, palette_entries: &[PgsPaletteEntry; 256]) {
+    for (idx, entry) in palette_entries.iter().enumerate() {
+        if entry.y != 0 || entry.cr != 0 || entry.cb != 0 || entry.t != 0 {
+            out.extend_from_slice(&[idx as u8, entry.y, entry.cr, entry.cb, entry.t]);
+        }
+    }
+}
+
+fn write_palette_entries_with_length(out: &mut Vec<u8>, palette_entries: &[PgsPaletteEntry; 256]) {
+    let mut entries = Vec::new();
+    write_palette_entries(&mut entries, palette_entries);
+    out.extend_from_slice(&(entries.len() as u16).to_be_bytes());
+    out.extend_from_slice(&entries);
+}
+
+fn write_be_u24(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes()[1..]);
+}
+
+fn write_be_u33(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&(value & 0x1_ffff_ffff).to_be_bytes()[3..]);
+}
+
+fn write_be_sm16(out: &mut Vec<u8>, value: i16) {
+    let magnitude = value.unsigned_abs() & 0x7fff;
+    let sign = if value < 0 { 0x8000 } else { 0 };
+    out.extend_from_slice(&(sign | magnitude).to_be_bytes());
+}
+
+fn write_sm8(out: &mut Vec<u8>, value: i8) {
+    let magnitude = value.unsigned_abs() & 0x7f;
+    out.push(if value < 0 {
+        0x80 | magnitude
+    } else {
+        magnitude
+    });
+}
+
+/// Prepends the segment type and length to a segment body.
+///
+/// # Panics
+///
+/// Panics if the body exceeds the maximum segment length.
+fn make_segment(seg_type: u8, body: &[u8]) -> Vec<u8> {
+    assert!(body.len() <= 0xffff, "PG segment too long");
+    let mut segment = Vec::with_capacity(3 + body.len());
+    segment.push(seg_type);
+    segment.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    segment.extend_from_slice(body);
+    segment
+}
+
+/// Splits reassembled segment data into a sequence of segments. Each segment starts with
+/// `prefix` and a [`PgSequenceDescriptor`], and the first also carries the total data length.
+fn fragment_segments(seg_type: u8, prefix: &[u8], mut data: &[u8]) -> Vec<Vec<u8>> {
+    let mut segments = Vec::new();
+    let mut first = true;
+    while first || !data.is_empty() {
+        let mut body = Vec::from(prefix);
+        let header_len = prefix.len() + 1 + if first { 3 } else { 0 };
+        let chunk_len = data.len().min(0xffff - header_len);
+        let last = chunk_len == data.len();
+        body.push(if first { 0x80 } else { 0 } | if last { 0x40 } else { 0 });
+        if first {
+            write_be_u24(&mut body, data.len() as u32);
+        }
+        body.extend_from_slice(&data[..chunk_len]);
+        segments.push(make_segment(seg_type, &body));
+        data = &data[chunk_len..];
+        first = false;
+    }
+    segments
+}
+
 /// Container of text styles.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgDialogStyle {
     /// Unknown
     pub player_style_flag: bool,
@@ -1159,6 +1841,10 @@ pub struct TgDialogStyle {
     /// Text user styles.
     pub user_styles: Vec<TgUserStyle>,
     /// 256 palette entries
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialize::serialize_palette")
+    )]
     pub palette_entries: Box<[PgsPaletteEntry; 256]>,
 }
 
@@ -1187,10 +1873,31 @@ impl TgDialogStyle {
             palette_entries,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(
+            &(if self.player_style_flag {
+                0x8000_u16
+            } else {
+                0
+            })
+            .to_be_bytes(),
+        );
+        out.push(self.region_styles.len() as u8);
+        for region_style in self.region_styles.iter() {
+            region_style.write_to(out);
+        }
+        out.push(self.user_styles.len() as u8);
+        for user_style in self.user_styles.iter() {
+            user_style.write_to(out);
+        }
+        write_palette_entries_with_length(out, &self.palette_entries);
+    }
 }
 
 /// Set of dialog styles.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgsDialogStyle {
     /// Styles of the dialogs.
     pub style: TgDialogStyle,
@@ -1207,10 +1914,17 @@ impl TgsDialogStyle {
         let num_dialogs = reader.read_be_u16()?;
         Ok(Self { style, num_dialogs })
     }
+
+    /// Appends the segment body to `out`. Palette entries that are entirely zero are omitted.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        self.style.write_to(out);
+        out.extend_from_slice(&self.num_dialogs.to_be_bytes());
+    }
 }
 
 /// A presentation of one dialog region.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgDialogRegion {
     /// Unknown
     pub continuous_present_flag: bool,
@@ -1218,11 +1932,199 @@ pub struct TgDialogRegion {
     pub forced_on_flag: bool,
     /// Region style ID.
     pub region_style_id_ref: u8,
-    /// Data of presentation (TODO parse formatting tags)
+    /// Text with inline formatting tags, see [`TgDialogRegion::elements`].
     pub data: Vec<u8>,
 }
 
+/// Inline element of the text data of a [`TgDialogRegion`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TgInlineElement {
+    /// Text in the character code of the stream.
+    Text(Vec<u8>),
+    /// Changes the font ID.
+    FontId(u8),
+    /// Changes the font style and outline.
+    FontStyle {
+        /// Font style bits.
+        style: TgFontStyle,
+        /// Outline color palette index.
+        outline_color: u8,
+        /// Outline thickness.
+        outline_thickness: TgOutlineThickness,
+    },
+    /// Changes the font size.
+    FontSize(u8),
+    /// Changes the font color palette index.
+    FontColor(u8),
+    /// Starts a new line.
+    LineBreak,
+    /// Returns to the style of the region.
+    ResetStyle,
+    /// Unknown or malformed tag type and its data.
+    Unknown(u8, Vec<u8>),
+}
+
+impl TgInlineElement {
+    fn parse(tag_type: u8, data: &[u8]) -> Self {
+        match (tag_type, data) {
+            (0x01, text) => TgInlineElement::Text(text.to_vec()),
+            (0x02, [font_id]) => TgInlineElement::FontId(*font_id),
+            (0x03, [style, outline_color, outline_thickness]) => {
+                match num_traits::FromPrimitive::from_u8(*outline_thickness) {
+                    Some(outline_thickness) => TgInlineElement::FontStyle {
+                        style: TgFontStyle::from_bytes([*style]),
+                        outline_color: *outline_color,
+                        outline_thickness,
+                    },
+                    None => TgInlineElement::Unknown(tag_type, data.to_vec()),
+                }
+            }
+            (0x04, [font_size]) => TgInlineElement::FontSize(*font_size),
+            (0x05, [font_color]) => TgInlineElement::FontColor(*font_color),
+            (0x0a, []) => TgInlineElement::LineBreak,
+            (0x0b, []) => TgInlineElement::ResetStyle,
+            _ => TgInlineElement::Unknown(tag_type, data.to_vec()),
+        }
+    }
+
+    /// Appends the element to `out` as an escape sequence.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        let (tag_type, data) = match self {
+            TgInlineElement::Text(text) => (0x01, text.clone()),
+            TgInlineElement::FontId(font_id) => (0x02, vec![*font_id]),
+            TgInlineElement::FontStyle {
+                style,
+                outline_color,
+                outline_thickness,
+            } => (
+                0x03,
+                vec![
+                    style.into_bytes()[0],
+                    *outline_color,
+                    *outline_thickness as u8,
+                ],
+            ),
+            TgInlineElement::FontSize(font_size) => (0x04, vec![*font_size]),
+            TgInlineElement::FontColor(font_color) => (0x05, vec![*font_color]),
+            TgInlineElement::LineBreak => (0x0a, Vec::new()),
+            TgInlineElement::ResetStyle => (0x0b, Vec::new()),
+            TgInlineElement::Unknown(tag_type, data) => (*tag_type, data.clone()),
+        };
+        out.push(0x1b);
+        out.push(tag_type);
+        out.push(data.len() as u8);
+        out.extend_from_slice(&data);
+    }
+}
+
+/// Run of text with the style in effect at its position.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TgTextRun {
+    /// Text in the character code of the stream.
+    pub text: Vec<u8>,
+    /// Line of the region the text is on, counting from 0.
+    pub line: usize,
+    /// Font ID.
+    pub font_id_ref: u8,
+    /// Font style bits.
+    pub font_style: TgFontStyle,
+    /// Font size.
+    pub font_size: u8,
+    /// Font color palette index.
+    pub font_color: u8,
+    /// Outline color palette index.
+    pub outline_color: u8,
+    /// Outline thickness.
+    pub outline_thickness: TgOutlineThickness,
+}
+
 impl TgDialogRegion {
+    /// Splits the data into its escape sequences.
+    ///
+    /// Bytes outside of escape sequences are skipped and a truncated escape sequence ends the
+    /// elements.
+    pub fn elements(&self) -> Vec<TgInlineElement> {
+        let mut elements = Vec::new();
+        let mut pos = 0;
+        while pos < self.data.len() {
+            if self.data[pos] != 0x1b {
+                warn!("Skipping byte outside of TextST escape sequence");
+                pos += 1;
+                continue;
+            }
+            let (tag_type, length) = match self.data.get(pos + 1..pos + 3) {
+                Some(header) => (header[0], header[1] as usize),
+                None => break,
+            };
+            pos += 3;
+            match self.data.get(pos..pos + length) {
+                Some(data) => elements.push(TgInlineElement::parse(tag_type, data)),
+                None => {
+                    warn!("Truncated TextST escape sequence");
+                    break;
+                }
+            }
+            pos += length;
+        }
+        elements
+    }
+
+    /// Replaces the data with the escape sequences of `elements`.
+    pub fn set_elements(&mut self, elements: &[TgInlineElement]) {
+        self.data.clear();
+        for element in elements.iter() {
+            element.write_to(&mut self.data);
+        }
+    }
+
+    /// Resolves the elements into text runs, starting from and resetting to the style of
+    /// `region_style`, which is the [`TgRegionStyle`] referenced by `region_style_id_ref`.
+    pub fn text_runs(&self, region_style: &TgRegionStyle) -> Vec<TgTextRun> {
+        let initial = TgTextRun {
+            text: Vec::new(),
+            line: 0,
+            font_id_ref: region_style.font_id_ref,
+            font_style: region_style.font_style,
+            font_size: region_style.font_size,
+            font_color: region_style.font_color,
+            outline_color: region_style.outline_color,
+            outline_thickness: region_style.outline_thickness,
+        };
+        let mut current = initial.clone();
+        let mut runs = Vec::new();
+        for element in self.elements() {
+            match element {
+                TgInlineElement::Text(text) => runs.push(TgTextRun {
+                    text,
+                    ..current.clone()
+                }),
+                TgInlineElement::FontId(font_id) => current.font_id_ref = font_id,
+                TgInlineElement::FontStyle {
+                    style,
+                    outline_color,
+                    outline_thickness,
+                } => {
+                    current.font_style = style;
+                    current.outline_color = outline_color;
+                    current.outline_thickness = outline_thickness;
+                }
+                TgInlineElement::FontSize(font_size) => current.font_size = font_size,
+                TgInlineElement::FontColor(font_color) => current.font_color = font_color,
+                TgInlineElement::LineBreak => current.line += 1,
+                TgInlineElement::ResetStyle => {
+                    current = TgTextRun {
+                        line: current.line,
+                        ..initial.clone()
+                    }
+                }
+                TgInlineElement::Unknown(_, _) => {}
+            }
+        }
+        runs
+    }
+
     fn parse<D: BdavAppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
         let bits = reader.read_u8()?;
         let continuous_present_flag = bits & 0x80 != 0;
@@ -1238,16 +2140,35 @@ impl TgDialogRegion {
             data,
         })
     }
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        let mut bits = 0;
+        if self.continuous_present_flag {
+            bits |= 0x80;
+        }
+        if self.forced_on_flag {
+            bits |= 0x40;
+        }
+        out.push(bits);
+        out.push(self.region_style_id_ref);
+        out.extend_from_slice(&(self.data.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.data);
+    }
 }
 
 /// Presentable text instance.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TgsDialogPresentation {
     /// Start timecode.
     pub start_pts: u64,
     /// End timecode.
     pub end_pts: u64,
     /// Optional palette update.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serialize::serialize_palette_update")
+    )]
     pub palette_update: Option<Box<[PgsPaletteEntry; 256]>>,
     /// Up to 2 regions to present.
     pub regions: SmallVec<[TgDialogRegion; 2]>,
@@ -1281,6 +2202,23 @@ impl TgsDialogPresentation {
             regions,
         })
     }
+
+    /// Appends the segment body to `out`. Palette entries that are entirely zero are omitted.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        write_be_u33(out, self.start_pts);
+        write_be_u33(out, self.end_pts);
+        match self.palette_update.as_ref() {
+            Some(palette_entries) => {
+                out.push(0x80);
+                write_palette_entries_with_length(out, palette_entries);
+            }
+            None => out.push(0),
+        }
+        out.push(self.regions.len() as u8);
+        for region in self.regions.iter() {
+            region.write_to(out);
+        }
+    }
 }
 
 macro_rules! pg_segment_data {
@@ -1297,7 +2235,20 @@ macro_rules! pg_segment_data {
             $($(#[$vattr])* $var($var),)*
         }
 
-        fn parse_pg_segment_data<D: BdavAppDetails>(reader: &mut SliceReader<D>, storage: &mut BdavParserStorage) -> Result<PgSegmentData, D> {
+        impl PgSegmentData {
+            /// Segment type code of the parsed variant, or [`None`] if raw.
+            pub fn segment_type(&self) -> Option<u8> {
+                match self {
+                    PgSegmentData::Raw(_) => None,
+                    $(PgSegmentData::$var(_) => Some($val),)*
+                }
+            }
+        }
+
+        /// Segment type codes of the parsed variants.
+        pub(crate) const PG_SEGMENT_TYPES: &[u8] = &[$($val),*];
+
+        pub(crate) fn parse_pg_segment_data<D: BdavAppDetails>(reader: &mut SliceReader<D>, storage: &mut BdavParserStorage) -> Result<PgSegmentData, D> {
             let seg_type = reader.read_u8()?;
             let seg_length = reader.read_be_u16()?;
             let mut seg_reader = reader.new_sub_reader(seg_length as usize)?;
@@ -1358,6 +2309,31 @@ impl PgSegmentData {
     pub(crate) fn new(unit_length: usize) -> Self {
         PgSegmentData::Raw(Vec::with_capacity(unit_length))
     }
+
+    /// Serializes the data as complete segments (type, length, and body), each suitable as the
+    /// payload of one PES unit. Raw data is returned unchanged.
+    ///
+    /// Objects and interactive compositions too long for one segment are split into a fragment
+    /// sequence. Fragments without reassembled data produce no segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a segment that cannot be fragmented exceeds 65535 bytes.
+    pub fn to_segments(&self) -> Vec<Vec<u8>> {
+        let mut body = Vec::new();
+        match self {
+            PgSegmentData::Raw(data) => return vec![data.clone()],
+            PgSegmentData::PgsObject(object) => return object.to_segments(),
+            PgSegmentData::PgsIgComposition(composition) => return composition.to_segments(),
+            PgSegmentData::PgsPalette(palette) => palette.write_to(&mut body),
+            PgSegmentData::PgsPgComposition(composition) => composition.write_to(&mut body),
+            PgSegmentData::PgsWindow(window) => window.write_to(&mut body),
+            PgSegmentData::PgsEndOfDisplay(end) => end.write_to(&mut body),
+            PgSegmentData::TgsDialogStyle(style) => style.write_to(&mut body),
+            PgSegmentData::TgsDialogPresentation(presentation) => presentation.write_to(&mut body),
+        }
+        vec![make_segment(self.segment_type().unwrap(), &body)]
+    }
 }
 
 impl<D: BdavAppDetails> PesUnitObject<D> for PgSegmentData {
@@ -1380,4 +2356,976 @@ impl<D: BdavAppDetails> PesUnitObject<D> for PgSegmentData {
             panic!("PgSegmentData must be raw before finishing")
         }
     }
+
+    fn finish_from_slice(
+        &mut self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<(), D> {
+        *self =
+            parse_pg_segment_data(&mut SliceReader::new(slice), &mut parser.app_parser_storage)?;
+        Ok(())
+    }
+}
+
+/// Full video frame rendered from a PG composition.
+pub struct PgFrame {
+    /// Width of the video viewport in pixels.
+    pub width: u16,
+    /// Height of the video viewport in pixels.
+    pub height: u16,
+    /// `width * height * 4` bytes of non-premultiplied RGBA, row-major.
+    pub rgba: Vec<u8>,
+}
+
+impl Debug for PgFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PgFrame")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+/// Decoder model holding the composition, windows, palettes and decoded objects of the current
+/// epoch.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::pg::{PgRenderer, PgSegmentData};
+///
+/// # let segments: Vec<PgSegmentData> = vec![];
+/// let mut renderer = PgRenderer::default();
+/// for segment in segments.iter() {
+///     renderer.push_segment(segment);
+///     if let PgSegmentData::PgsEndOfDisplay(_) = segment {
+///         if let Some(frame) = renderer.render() {
+///             println!("{}x{} frame", frame.width, frame.height);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PgRenderer {
+    composition: Option<PgsPgComposition>,
+    windows: HashMap<u8, PgWindow>,
+    palettes: HashMap<u8, Box<[PgsPaletteEntry; 256]>>,
+    objects: HashMap<u16, (u16, u16, Vec<u8>)>,
+}
+
+impl PgRenderer {
+    /// Applies one segment to the decoder model. Objects are decoded as they arrive.
+    pub fn push_segment(&mut self, segment: &PgSegmentData) {
+        match segment {
+            PgSegmentData::PgsPgComposition(composition) => {
+                if composition.composition_descriptor.state == PgCompositionUnitState::EpochStart {
+                    self.windows.clear();
+                    self.palettes.clear();
+                    self.objects.clear();
+                }
+                self.composition = Some(composition.clone());
+            }
+            PgSegmentData::PgsWindow(window) => {
+                for window in window.windows.iter() {
+                    self.windows.insert(window.id, window.clone());
+                }
+            }
+            PgSegmentData::PgsPalette(palette) => {
+                self.palettes.insert(palette.id, palette.entries.clone());
+            }
+            PgSegmentData::PgsObject(object) => {
+                if let Some(data) = object.data.as_ref() {
+                    self.objects
+                        .insert(object.id, (data.width, data.height, data.decode()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies all segments of a display set to the decoder model.
+    pub fn push_display_set(&mut self, display_set: &DisplaySet) {
+        for segment in display_set.segments.iter() {
+            self.push_segment(segment);
+        }
+    }
+
+    /// Renders the current composition to a full frame of the video viewport.
+    ///
+    /// Composition objects are cropped, positioned and clipped to their windows. Returns
+    /// [`None`] before the first composition.
+    pub fn render(&self) -> Option<PgFrame> {
+        let composition = self.composition.as_ref()?;
+        let width = composition.video_descriptor.video_width;
+        let height = composition.video_descriptor.video_height;
+        let mut rgba = vec![0; width as usize * height as usize * 4];
+        let palette = self.palettes.get(&composition.palette_id_ref);
+        let colors = match palette {
+            Some(palette) => palette_to_rgba(palette, ColorMatrix::for_video_height(height)),
+            None => [[0; 4]; 256],
+        };
+
+        for placement in composition.composition_objects.iter() {
+            let (object_width, object_height, pixels) =
+                match self.objects.get(&placement.object_id_ref) {
+                    Some(object) => object,
+                    None => continue,
+                };
+            let (src_x, src_y, src_w, src_h) = match placement.crop.as_ref() {
+                Some(crop) => (crop.x, crop.y, crop.w, crop.h),
+                None => (0, 0, *object_width, *object_height),
+            };
+            /* Clip to the window, the object and the frame */
+            let (min_x, min_y, max_x, max_y) = match self.windows.get(&placement.window_id_ref) {
+                Some(window) => (
+                    window.x as usize,
+                    window.y as usize,
+                    window.x as usize + window.width as usize,
+                    window.y as usize + window.height as usize,
+                ),
+                None => (0, 0, width as usize, height as usize),
+            };
+            let max_x = max_x.min(width as usize);
+            let max_y = max_y.min(height as usize);
+            for row in 0..src_h as usize {
+                let (oy, fy) = (src_y as usize + row, placement.y as usize + row);
+                if oy >= *object_height as usize || fy < min_y || fy >= max_y {
+                    continue;
+                }
+                for col in 0..src_w as usize {
+                    let (ox, fx) = (src_x as usize + col, placement.x as usize + col);
+                    if ox >= *object_width as usize || fx < min_x || fx >= max_x {
+                        continue;
+                    }
+                    let color = colors[pixels[oy * *object_width as usize + ox] as usize];
+                    let offset = (fy * width as usize + fx) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(&color);
+                }
+            }
+        }
+
+        Some(PgFrame {
+            width,
+            height,
+            rgba,
+        })
+    }
+}
+
+/// Segments of one display set, from a PG or IG composition segment up to the end of display
+/// segment.
+#[derive(Debug)]
+pub struct DisplaySet {
+    /// Presentation time stamp of the PES unit carrying the composition segment.
+    pub pts: Option<Timestamp90k>,
+    /// Segments in stream order, starting with the composition and ending with the end of display.
+    pub segments: Vec<PgSegmentData>,
+}
+
+impl DisplaySet {
+    /// Composition segment of a PG display set.
+    pub fn pg_composition(&self) -> Option<&PgsPgComposition> {
+        self.segments.iter().find_map(|segment| match segment {
+            PgSegmentData::PgsPgComposition(composition) => Some(composition),
+            _ => None,
+        })
+    }
+
+    fn pg_composition_mut(&mut self) -> Option<&mut PgsPgComposition> {
+        self.segments.iter_mut().find_map(|segment| match segment {
+            PgSegmentData::PgsPgComposition(composition) => Some(composition),
+            _ => None,
+        })
+    }
+
+    /// Checks whether a PG display set shows any composition object with `forced_on_flag` set.
+    pub fn is_forced(&self) -> bool {
+        self.pg_composition().is_some_and(|composition| {
+            composition
+                .composition_objects
+                .iter()
+                .any(|object| object.forced_on_flag)
+        })
+    }
+
+    /// Composition segment of an IG display set. For fragmented compositions this is the last
+    /// fragment, which carries the reassembled data.
+    pub fn ig_composition(&self) -> Option<&PgsIgComposition> {
+        self.segments
+            .iter()
+            .rev()
+            .find_map(|segment| match segment {
+                PgSegmentData::PgsIgComposition(composition) => Some(composition),
+                _ => None,
+            })
+    }
+
+    /// Composition descriptor of the PG or IG composition segment.
+    pub fn composition_descriptor(&self) -> Option<&PgCompositionDescriptor> {
+        match self.segments.first()? {
+            PgSegmentData::PgsPgComposition(composition) => {
+                Some(&composition.composition_descriptor)
+            }
+            PgSegmentData::PgsIgComposition(composition) => {
+                Some(&composition.composition_descriptor)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Groups PG and IG segments into [`DisplaySet`] objects and tracks the palettes, objects and
+/// windows defined during the current epoch.
+///
+/// A composition segment starts a display set and the end of display segment completes it. An
+/// [`EpochStart`](PgCompositionUnitState::EpochStart) composition clears the epoch store.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::pg::{PgDisplaySetCollector, PgSegmentData};
+/// use mpegts_io::bdav::DefaultBdavAppDetails;
+/// use mpegts_io::Pes;
+///
+/// # let units: Vec<Pes<DefaultBdavAppDetails>> = vec![];
+/// let mut collector = PgDisplaySetCollector::default();
+/// for pes in units {
+///     let pts = pes.pts;
+///     if let Some(segment) = pes.into_data::<PgSegmentData>() {
+///         if let Some(display_set) = collector.push(pts, segment) {
+///             println!("{:?}: {:?}", display_set.pts, display_set.composition_descriptor());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PgDisplaySetCollector {
+    current: Option<DisplaySet>,
+    palettes: HashMap<u8, PgsPalette>,
+    objects: HashMap<u16, PgsObjectData>,
+    windows: HashMap<u8, PgWindow>,
+}
+
+impl PgDisplaySetCollector {
+    /// Adds one segment with the PTS of its PES unit, returning the display set it completes.
+    ///
+    /// Segments outside of a display set are dropped.
+    pub fn push(
+        &mut self,
+        pts: Option<Timestamp90k>,
+        segment: PgSegmentData,
+    ) -> Option<DisplaySet> {
+        let state = match &segment {
+            PgSegmentData::PgsPgComposition(composition) => {
+                Some(&composition.composition_descriptor.state)
+            }
+            PgSegmentData::PgsIgComposition(composition)
+                if composition.sequence_descriptor.first_in_seq =>
+            {
+                Some(&composition.composition_descriptor.state)
+            }
+            _ => None,
+        };
+        if let Some(state) = state {
+            if self.current.is_some() {
+                warn!("PG display set without end of display; discarding");
+            }
+            if *state == PgCompositionUnitState::EpochStart {
+                self.palettes.clear();
+                self.objects.clear();
+                self.windows.clear();
+            }
+            self.current = Some(DisplaySet {
+                pts,
+                segments: Vec::new(),
+            });
+        }
+
+        let display_set = self.current.as_mut()?;
+        match &segment {
+            PgSegmentData::PgsPalette(palette) => {
+                self.palettes.insert(palette.id, palette.clone());
+            }
+            PgSegmentData::PgsObject(object) => {
+                if let Some(data) = object.data.as_ref() {
+                    self.objects.insert(object.id, data.clone());
+                }
+            }
+            PgSegmentData::PgsWindow(window) => {
+                for window in window.windows.iter() {
+                    self.windows.insert(window.id, window.clone());
+                }
+            }
+            _ => {}
+        }
+        let end = matches!(segment, PgSegmentData::PgsEndOfDisplay(_));
+        display_set.segments.push(segment);
+        if end {
+            self.current.take()
+        } else {
+            None
+        }
+    }
+
+    /// Palette of the current epoch.
+    pub fn palette(&self, id: u8) -> Option<&PgsPalette> {
+        self.palettes.get(&id)
+    }
+
+    /// Reassembled object of the current epoch.
+    pub fn object(&self, id: u16) -> Option<&PgsObjectData> {
+        self.objects.get(&id)
+    }
+
+    /// Window of the current epoch.
+    pub fn window(&self, id: u8) -> Option<&PgWindow> {
+        self.windows.get(&id)
+    }
+}
+
+/// Iterator adapter over PG display sets yielding only forced subtitles, created with
+/// [`forced_display_sets`].
+#[derive(Debug)]
+pub struct ForcedDisplaySets<I> {
+    display_sets: I,
+    showing: bool,
+}
+
+impl<I: Iterator<Item = DisplaySet>> Iterator for ForcedDisplaySets<I> {
+    type Item = DisplaySet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut display_set = self.display_sets.next()?;
+            let composition = match display_set.pg_composition_mut() {
+                Some(composition) => composition,
+                None => continue,
+            };
+            composition
+                .composition_objects
+                .retain(|object| object.forced_on_flag);
+            let forced = !composition.composition_objects.is_empty();
+            if forced || self.showing {
+                self.showing = forced;
+                return Some(display_set);
+            }
+        }
+    }
+}
+
+/// Filters PG display sets down to forced subtitles for building forced subtitle tracks.
+///
+/// Composition objects without `forced_on_flag` are removed from every composition. Sets left
+/// with forced objects are yielded, as is the first set after them, which clears the forced
+/// subtitle at its PTS. Other sets, including IG display sets, are dropped; a renderer fed with
+/// the yielded sets misses objects and palettes defined only in dropped sets of the same epoch.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::pg::{forced_display_sets, DisplaySet};
+///
+/// # let display_sets: Vec<DisplaySet> = vec![];
+/// for display_set in forced_display_sets(display_sets) {
+///     println!("{:?}: forced {}", display_set.pts, display_set.is_forced());
+/// }
+/// ```
+pub fn forced_display_sets<I: IntoIterator<Item = DisplaySet>>(
+    display_sets: I,
+) -> ForcedDisplaySets<I::IntoIter> {
+    ForcedDisplaySets {
+        display_sets: display_sets.into_iter(),
+        showing: false,
+    }
+}
+
+#[test]
+fn test_pg_segment_roundtrip() {
+    use super::DefaultBdavAppDetails;
+
+    let mut storage = BdavParserStorage::default();
+    let mut roundtrip = |segments: Vec<Vec<u8>>| {
+        let mut last = None;
+        for segment in segments.iter() {
+            let data = parse_pg_segment_data(
+                &mut SliceReader::<DefaultBdavAppDetails>::new(segment),
+                &mut storage,
+            )
+            .unwrap();
+            last = Some(data);
+        }
+        last.unwrap().to_segments()
+    };
+
+    let mut palette = PgsPalette {
+        id: 1,
+        version: 0,
+        entries: Box::new([PgsPaletteEntry::default(); 256]),
+    };
+    palette.entries[3] = PgsPaletteEntry {
+        y: 235,
+        cr: 128,
+        cb: 128,
+        t: 255,
+    };
+    let segments = PgSegmentData::PgsPalette(palette).to_segments();
+    assert_eq!(segments[0].len(), 3 + 2 + 5);
+    assert_eq!(roundtrip(segments.clone()), segments);
+
+    let composition = PgSegmentData::PgsPgComposition(PgsPgComposition {
+        video_descriptor: PgVideoDescriptor {
+            video_width: 1920,
+            video_height: 1080,
+            frame_rate: FrameRate::Drop24,
+        },
+        composition_descriptor: PgCompositionDescriptor {
+            number: 5,
+            state: PgCompositionUnitState::EpochStart,
+        },
+        palette_update_flag: false,
+        palette_id_ref: 1,
+        composition_objects: vec![PgCompositionObject {
+            object_id_ref: 0,
+            window_id_ref: 0,
+            forced_on_flag: true,
+            x: 100,
+            y: 900,
+            crop: Some(PgCrop {
+                x: 0,
+                y: 0,
+                w: 50,
+                h: 20,
+            }),
+        }],
+    });
+    let segments = composition.to_segments();
+    assert_eq!(roundtrip(segments.clone()), segments);
+
+    let button = IgButton {
+        id: 1,
+        numeric_select_value: 0xffff,
+        auto_action_flag: false,
+        x_pos: 10,
+        y_pos: 20,
+        upper_button_id_ref: 1,
+        lower_button_id_ref: 1,
+        left_button_id_ref: 1,
+        right_button_id_ref: 1,
+        normal_start_object_id_ref: 0,
+        normal_end_object_id_ref: 0,
+        normal_repeat_flag: false,
+        selected_sound_id_ref: 0xff,
+        selected_start_object_id_ref: 1,
+        selected_end_object_id_ref: 1,
+        selected_repeat_flag: true,
+        activated_sound_id_ref: 0xff,
+        activated_start_object_id_ref: 2,
+        activated_end_object_id_ref: 2,
+        nav_cmds: vec![MObjCmd::assemble("jump_title 2").unwrap()],
+    };
+    let composition = PgSegmentData::PgsIgComposition(PgsIgComposition {
+        video_descriptor: PgVideoDescriptor {
+            video_width: 1920,
+            video_height: 1080,
+            frame_rate: FrameRate::Drop24,
+        },
+        composition_descriptor: PgCompositionDescriptor {
+            number: 0,
+            state: PgCompositionUnitState::EpochStart,
+        },
+        sequence_descriptor: PgSequenceDescriptor {
+            first_in_seq: true,
+            last_in_seq: true,
+        },
+        interactive_composition: Some(IgInteractiveComposition {
+            stream_model: false,
+            ui_model: IgUiModel::Popup,
+            composition_timeout_pts: Some(0),
+            selection_timeout_pts: Some(0),
+            user_timeout_duration: 90000,
+            pages: vec![IgPage {
+                id: 0,
+                version: 0,
+                uo_mask: UoMask::new(),
+                in_effects: IgEffectSequence {
+                    windows: Vec::new(),
+                    effects: Vec::new(),
+                },
+                out_effects: IgEffectSequence {
+                    windows: Vec::new(),
+                    effects: Vec::new(),
+                },
+                animation_frame_rate_code: 0,
+                default_selected_button_id_ref: 1,
+                default_activated_button_id_ref: 0xffff,
+                palette_id_ref: 0,
+                bogs: vec![IgBog {
+                    default_valid_button_id_ref: 1,
+                    buttons: vec![button],
+                }],
+            }],
+        }),
+    });
+    let segments = composition.to_segments();
+    assert_eq!(roundtrip(segments.clone()), segments);
+
+    /* Objects larger than one segment are fragmented */
+    let object = PgSegmentData::PgsObject(PgsObject {
+        id: 0,
+        version: 0,
+        sequence_descriptor: PgSequenceDescriptor {
+            first_in_seq: true,
+            last_in_seq: true,
+        },
+        data: Some(PgsObjectData {
+            width: 1000,
+            height: 100,
+            data: (0..100000).map(|i| i as u8).collect(),
+        }),
+    });
+    let segments = object.to_segments();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(roundtrip(segments.clone()), segments);
+}
+
+#[test]
+fn test_pgs_rle() {
+    /* Long transparent run, short and long colored runs, and single pixels */
+    let width = 400;
+    let mut pixels = vec![0; 100];
+    pixels.extend_from_slice(&[5; 3]);
+    pixels.extend_from_slice(&[7, 7, 9]);
+    pixels.extend_from_slice(&[0; 94]);
+    pixels.extend_from_slice(&[0xff; 200]);
+    pixels.extend_from_slice(&[1; 400]);
+    let object = PgsObjectData::encode(width, 2, &pixels);
+    assert_eq!(
+        &object.data[..12],
+        [0x00, 0x40, 0x64, 0x00, 0x83, 0x05, 0x07, 0x07, 0x09, 0x00, 0x40, 0x5e]
+    );
+    assert_eq!(object.decode(), pixels);
+
+    /* Short lines are padded and missing lines are left transparent */
+    let object = PgsObjectData {
+        width: 3,
+        height: 2,
+        data: vec![0x04, 0x00, 0x00],
+    };
+    assert_eq!(object.decode(), [4, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_pg_renderer() {
+    let mut renderer = PgRenderer::default();
+    assert!(renderer.render().is_none());
+
+    let mut entries = Box::new([PgsPaletteEntry::default(); 256]);
+    /* Opaque white and half transparent black */
+    entries[1] = PgsPaletteEntry {
+        y: 235,
+        cr: 128,
+        cb: 128,
+        t: 255,
+    };
+    entries[2] = PgsPaletteEntry {
+        y: 16,
+        cr: 128,
+        cb: 128,
+        t: 128,
+    };
+    let segments = [
+        PgSegmentData::PgsPgComposition(PgsPgComposition {
+            video_descriptor: PgVideoDescriptor {
+                video_width: 8,
+                video_height: 4,
+                frame_rate: FrameRate::NonDrop25,
+            },
+            composition_descriptor: PgCompositionDescriptor {
+                number: 0,
+                state: PgCompositionUnitState::EpochStart,
+            },
+            palette_update_flag: false,
+            palette_id_ref: 0,
+            composition_objects: vec![PgCompositionObject {
+                object_id_ref: 7,
+                window_id_ref: 0,
+                forced_on_flag: false,
+                x: 3,
+                y: 1,
+                crop: Some(PgCrop {
+                    x: 1,
+                    y: 0,
+                    w: 3,
+                    h: 2,
+                }),
+            }],
+        }),
+        PgSegmentData::PgsWindow(PgsWindow {
+            windows: vec![PgWindow {
+                id: 0,
+                x: 0,
+                y: 0,
+                width: 5,
+                height: 4,
+            }],
+        }),
+        PgSegmentData::PgsPalette(PgsPalette {
+            id: 0,
+            version: 0,
+            entries,
+        }),
+        PgSegmentData::PgsObject(PgsObject {
+            id: 7,
+            version: 0,
+            sequence_descriptor: PgSequenceDescriptor {
+                first_in_seq: true,
+                last_in_seq: true,
+            },
+            data: Some(PgsObjectData::encode(4, 2, &[2, 1, 2, 1, 2, 2, 1, 1])),
+        }),
+    ];
+    for segment in segments.iter() {
+        renderer.push_segment(segment);
+    }
+
+    let frame = renderer.render().unwrap();
+    assert_eq!((frame.width, frame.height), (8, 4));
+    let pixel = |x: usize, y: usize| &frame.rgba[(y * 8 + x) * 4..(y * 8 + x) * 4 + 4];
+    /* Crop starts at object column 1; the window clips columns 5 and beyond */
+    assert_eq!(pixel(3, 1), [255, 255, 255, 255]);
+    assert_eq!(pixel(4, 1), [0, 0, 0, 128]);
+    assert_eq!(pixel(5, 1), [0, 0, 0, 0]);
+    assert_eq!(pixel(3, 2), [0, 0, 0, 128]);
+    assert_eq!(pixel(4, 2), [255, 255, 255, 255]);
+    assert_eq!(pixel(2, 1), [0, 0, 0, 0]);
+    assert_eq!(pixel(3, 3), [0, 0, 0, 0]);
+}
+
+#[test]
+fn test_pg_display_set_collector() {
+    let composition = |number, state| {
+        PgSegmentData::PgsPgComposition(PgsPgComposition {
+            video_descriptor: PgVideoDescriptor {
+                video_width: 1920,
+                video_height: 1080,
+                frame_rate: FrameRate::Drop24,
+            },
+            composition_descriptor: PgCompositionDescriptor { number, state },
+            palette_update_flag: false,
+            palette_id_ref: 0,
+            composition_objects: Vec::new(),
+        })
+    };
+    let palette = |id| {
+        PgSegmentData::PgsPalette(PgsPalette {
+            id,
+            version: 0,
+            entries: Box::new([PgsPaletteEntry::default(); 256]),
+        })
+    };
+    let end = || PgSegmentData::PgsEndOfDisplay(PgsEndOfDisplay {});
+
+    let mut collector = PgDisplaySetCollector::default();
+    /* Segments before the first composition are dropped */
+    assert!(collector.push(None, palette(9)).is_none());
+    assert!(collector.push(None, end()).is_none());
+
+    let epoch_start = vec![
+        composition(0, PgCompositionUnitState::EpochStart),
+        PgSegmentData::PgsWindow(PgsWindow {
+            windows: vec![PgWindow {
+                id: 0,
+                x: 0,
+                y: 900,
+                width: 1920,
+                height: 180,
+            }],
+        }),
+        palette(0),
+        PgSegmentData::PgsObject(PgsObject {
+            id: 3,
+            version: 0,
+            sequence_descriptor: PgSequenceDescriptor {
+                first_in_seq: true,
+                last_in_seq: true,
+            },
+            data: Some(PgsObjectData::encode(2, 1, &[1, 1])),
+        }),
+    ];
+    for segment in epoch_start {
+        assert!(collector.push(Some(Timestamp90k(1000)), segment).is_none());
+    }
+    let display_set = collector.push(Some(Timestamp90k(1000)), end()).unwrap();
+    assert_eq!(display_set.pts, Some(Timestamp90k(1000)));
+    assert_eq!(display_set.segments.len(), 5);
+    assert_eq!(display_set.composition_descriptor().unwrap().number, 0);
+    assert!(display_set.pg_composition().is_some());
+    assert!(display_set.ig_composition().is_none());
+    assert_eq!(collector.object(3).unwrap().width, 2);
+    assert_eq!(collector.window(0).unwrap().y, 900);
+
+    /* Palette update within the epoch keeps the store */
+    collector.push(
+        Some(Timestamp90k(2000)),
+        composition(1, PgCompositionUnitState::NewPalette),
+    );
+    collector.push(Some(Timestamp90k(2000)), palette(1));
+    let display_set = collector.push(Some(Timestamp90k(2000)), end()).unwrap();
+    assert_eq!(
+        display_set.composition_descriptor().unwrap().state,
+        PgCompositionUnitState::NewPalette
+    );
+    assert!(collector.palette(0).is_some() && collector.palette(1).is_some());
+    assert!(collector.object(3).is_some());
+
+    /* An unterminated display set is replaced and a new epoch clears the store */
+    collector.push(None, composition(2, PgCompositionUnitState::Incremental));
+    collector.push(
+        Some(Timestamp90k(3000)),
+        composition(3, PgCompositionUnitState::EpochStart),
+    );
+    let display_set = collector.push(None, end()).unwrap();
+    assert_eq!(display_set.pts, Some(Timestamp90k(3000)));
+    assert_eq!(display_set.composition_descriptor().unwrap().number, 3);
+    assert!(collector.palette(0).is_none());
+    assert!(collector.object(3).is_none());
+    assert!(collector.window(0).is_none());
+}
+
+#[test]
+fn test_tg_dialog_region_elements() {
+    let region_style = TgRegionStyle {
+        region_style_id: 0,
+        region_info: TgRegionInfo {
+            region: TgRect {
+                xpos: 0,
+                ypos: 800,
+                width: 1920,
+                height: 200,
+            },
+            background_color: 0,
+        },
+        text_box: TgRect {
+            xpos: 100,
+            ypos: 820,
+            width: 1720,
+            height: 160,
+        },
+        text_flow: TgTextFlow::LeftRight,
+        text_halign: TgHAlign::Center,
+        text_valign: TgVAlign::Bottom,
+        line_space: 10,
+        font_id_ref: 0,
+        font_style: TgFontStyle::new(),
+        font_size: 48,
+        font_color: 1,
+        outline_color: 2,
+        outline_thickness: TgOutlineThickness::Medium,
+    };
+
+    /* Plain text, an italic line after a line break and a style reset, followed by garbage */
+    let mut region = TgDialogRegion {
+        continuous_present_flag: false,
+        forced_on_flag: false,
+        region_style_id_ref: 0,
+        data: Vec::new(),
+    };
+    region.set_elements(&[
+        TgInlineElement::Text(b"Hello".to_vec()),
+        TgInlineElement::LineBreak,
+        TgInlineElement::FontStyle {
+            style: TgFontStyle::new().with_italic(true),
+            outline_color: 3,
+            outline_thickness: TgOutlineThickness::Thick,
+        },
+        TgInlineElement::FontColor(4),
+        TgInlineElement::Text(b"world".to_vec()),
+        TgInlineElement::ResetStyle,
+        TgInlineElement::Text(b"!".to_vec()),
+    ]);
+    assert_eq!(
+        &region.data[..8],
+        [0x1b, 0x01, 0x05, b'H', b'e', b'l', b'l', b'o']
+    );
+    region
+        .data
+        .extend_from_slice(&[0x00, 0x1b, 0x05, 0x02, 0x01]);
+
+    let elements = region.elements();
+    assert_eq!(elements.len(), 7);
+    assert!(matches!(elements[1], TgInlineElement::LineBreak));
+
+    let runs = region.text_runs(&region_style);
+    assert_eq!(runs.len(), 3);
+    assert_eq!((runs[0].text.as_slice(), runs[0].line), (&b"Hello"[..], 0));
+    assert_eq!((runs[0].font_color, runs[0].font_size), (1, 48));
+    assert_eq!((runs[1].text.as_slice(), runs[1].line), (&b"world"[..], 1));
+    assert!(runs[1].font_style.italic());
+    assert_eq!((runs[1].font_color, runs[1].outline_color), (4, 3));
+    assert_eq!((runs[2].text.as_slice(), runs[2].line), (&b"!"[..], 1));
+    assert!(!runs[2].font_style.italic());
+    assert_eq!(runs[2].font_color, 1);
+}
+
+#[test]
+fn test_forced_display_sets() {
+    let display_set = |pts, forced: &[bool]| DisplaySet {
+        pts: Some(Timestamp90k(pts)),
+        segments: vec![
+            PgSegmentData::PgsPgComposition(PgsPgComposition {
+                video_descriptor: PgVideoDescriptor {
+                    video_width: 1920,
+                    video_height: 1080,
+                    frame_rate: FrameRate::Drop24,
+                },
+                composition_descriptor: PgCompositionDescriptor {
+                    number: 0,
+                    state: PgCompositionUnitState::EpochStart,
+                },
+                palette_update_flag: false,
+                palette_id_ref: 0,
+                composition_objects: forced
+                    .iter()
+                    .enumerate()
+                    .map(|(i, forced_on_flag)| PgCompositionObject {
+                        object_id_ref: i as u16,
+                        window_id_ref: 0,
+                        forced_on_flag: *forced_on_flag,
+                        x: 0,
+                        y: 0,
+                        crop: None,
+                    })
+                    .collect(),
+            }),
+            PgSegmentData::PgsEndOfDisplay(PgsEndOfDisplay {}),
+        ],
+    };
+
+    let display_sets = vec![
+        display_set(1000, &[false]),
+        display_set(2000, &[]),
+        display_set(3000, &[true, false]),
+        display_set(4000, &[false]),
+        display_set(5000, &[]),
+        display_set(6000, &[true]),
+        display_set(7000, &[true]),
+        display_set(8000, &[]),
+    ];
+    assert!(!display_sets[0].is_forced());
+    assert!(display_sets[2].is_forced());
+    let forced = forced_display_sets(display_sets).collect::<Vec<_>>();
+    let pts = forced
+        .iter()
+        .map(|display_set| display_set.pts.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(pts, [3000, 4000, 6000, 7000, 8000]);
+    let objects = |i: usize| {
+        forced[i]
+            .pg_composition()
+            .unwrap()
+            .composition_objects
+            .len()
+    };
+    assert_eq!(objects(0), 1);
+    assert_eq!(objects(1), 0);
+}
+
+#[test]
+fn test_ig_navigation_graph() {
+    let button = |id, upper, lower, left, right| IgButton {
+        id,
+        numeric_select_value: 0xffff,
+        auto_action_flag: false,
+        x_pos: 0,
+        y_pos: 0,
+        upper_button_id_ref: upper,
+        lower_button_id_ref: lower,
+        left_button_id_ref: left,
+        right_button_id_ref: right,
+        normal_start_object_id_ref: 0,
+        normal_end_object_id_ref: 0,
+        normal_repeat_flag: false,
+        selected_sound_id_ref: 0xff,
+        selected_start_object_id_ref: 0,
+        selected_end_object_id_ref: 0,
+        selected_repeat_flag: false,
+        activated_sound_id_ref: 0xff,
+        activated_start_object_id_ref: 0,
+        activated_end_object_id_ref: 0,
+        nav_cmds: Vec::new(),
+    };
+    let effects = || IgEffectSequence {
+        windows: Vec::new(),
+        effects: Vec::new(),
+    };
+    /* Buttons 1 and 2 link vertically, button 3 is isolated and button 2 links to missing 9 */
+    let composition = PgsIgComposition {
+        video_descriptor: PgVideoDescriptor {
+            video_width: 1920,
+            video_height: 1080,
+            frame_rate: FrameRate::Drop24,
+        },
+        composition_descriptor: PgCompositionDescriptor {
+            number: 0,
+            state: PgCompositionUnitState::EpochStart,
+        },
+        sequence_descriptor: PgSequenceDescriptor {
+            first_in_seq: true,
+            last_in_seq: true,
+        },
+        interactive_composition: Some(IgInteractiveComposition {
+            stream_model: false,
+            ui_model: IgUiModel::AlwaysOn,
+            composition_timeout_pts: Some(0),
+            selection_timeout_pts: Some(0),
+            user_timeout_duration: 0,
+            pages: vec![IgPage {
+                id: 4,
+                version: 0,
+                uo_mask: UoMask::new(),
+                in_effects: effects(),
+                out_effects: effects(),
+                animation_frame_rate_code: 0,
+                default_selected_button_id_ref: 1,
+                default_activated_button_id_ref: 0xffff,
+                palette_id_ref: 0,
+                bogs: vec![
+                    IgBog {
+                        default_valid_button_id_ref: 1,
+                        buttons: vec![button(1, 1, 2, 1, 1), button(2, 1, 2, 2, 9)],
+                    },
+                    IgBog {
+                        default_valid_button_id_ref: 3,
+                        buttons: vec![button(3, 3, 3, 3, 3)],
+                    },
+                ],
+            }],
+        }),
+    };
+    assert!(composition.page(0).is_none());
+    let page = composition.page(4).unwrap();
+    assert_eq!(page.buttons().count(), 3);
+    assert_eq!(page.button(3).unwrap().id, 3);
+    assert_eq!(page.neighbor(1, IgDirection::Down).unwrap().id, 2);
+    assert_eq!(page.neighbor(1, IgDirection::Left).unwrap().id, 1);
+    assert!(page.neighbor(2, IgDirection::Right).is_none());
+
+    let graph = page.navigation_graph();
+    assert_eq!(graph.page_id, 4);
+    assert_eq!(graph.bogs, [vec![1, 2], vec![3]]);
+    assert_eq!(graph.bog_of(3), Some(1));
+    assert_eq!(graph.edges.len(), 3);
+    assert_eq!(
+        graph.dangling_edges().collect::<Vec<_>>(),
+        [&IgNavigationEdge {
+            from: 2,
+            to: 9,
+            direction: IgDirection::Right,
+        }]
+    );
+    assert_eq!(graph.reachable_from(1), [1, 2]);
+    assert_eq!(graph.reachable_from(3), [3]);
+    assert!(graph.reachable_from(9).is_empty());
 }
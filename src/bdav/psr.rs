@@ -0,0 +1,356 @@
+//! Module modelling the player status registers (PSRs) of an HDMV player.
+//!
+//! [`PsrBank`] holds the 128 registers initialized to the values a player has after power-on.
+//! Registers describing player capabilities and user preferences are read-only to MObj programs
+//! but may be configured by the player with [`PsrBank::set_player_setting`].
+
+/// Number of player status registers.
+pub const PSR_COUNT: usize = 128;
+
+/// Interactive graphics stream number.
+pub const PSR_IG_STREAM: u32 = 0;
+/// Primary audio stream number.
+pub const PSR_PRIMARY_AUDIO_STREAM: u32 = 1;
+/// PG TextST stream number and PiP PG stream number.
+pub const PSR_PG_STREAM: u32 = 2;
+/// Angle number.
+pub const PSR_ANGLE: u32 = 3;
+/// Title number.
+pub const PSR_TITLE: u32 = 4;
+/// Chapter number.
+pub const PSR_CHAPTER: u32 = 5;
+/// PlayList ID.
+pub const PSR_PLAYLIST: u32 = 6;
+/// PlayItem ID.
+pub const PSR_PLAY_ITEM: u32 = 7;
+/// Presentation time in 45kHz ticks.
+pub const PSR_PRESENTATION_TIME: u32 = 8;
+/// Navigation timer.
+pub const PSR_NAV_TIMER: u32 = 9;
+/// Selected button ID.
+pub const PSR_SELECTED_BUTTON: u32 = 10;
+/// Page ID.
+pub const PSR_PAGE: u32 = 11;
+/// User style number.
+pub const PSR_USER_STYLE: u32 = 12;
+/// User age for parental control.
+pub const PSR_PARENTAL_AGE: u32 = 13;
+/// Secondary audio stream number and secondary video stream number.
+pub const PSR_SECONDARY_STREAMS: u32 = 14;
+/// Language code for audio.
+pub const PSR_AUDIO_LANGUAGE: u32 = 16;
+/// Language code for PG and text subtitles.
+pub const PSR_PG_LANGUAGE: u32 = 17;
+/// Menu description language code.
+pub const PSR_MENU_LANGUAGE: u32 = 18;
+/// Country code.
+pub const PSR_COUNTRY: u32 = 19;
+/// Region code: 1 for region A, 2 for B, 4 for C.
+pub const PSR_REGION: u32 = 20;
+/// Player profile and version.
+pub const PSR_PROFILE_VERSION: u32 = 31;
+/// First of the registers backing up PSR4 to PSR12 during a title call.
+pub const PSR_BACKUP_START: u32 = 36;
+
+/// Registers saved by [`PsrBank::backup`].
+const BACKUP_PSRS: [u32; 8] = [4, 5, 6, 7, 8, 10, 11, 12];
+
+/// Name and power-on value of each documented register.
+const PSR_INFO: [(u32, &str, u32); 43] = [
+    (0, "Interactive graphics stream number", 1),
+    (1, "Primary audio stream number", 0xff),
+    (
+        2,
+        "PG TextST stream number and PiP PG stream number",
+        0x0fff0fff,
+    ),
+    (3, "Angle number", 1),
+    (4, "Title number", 0xffff),
+    (5, "Chapter number", 0xffff),
+    (6, "PlayList ID", 0),
+    (7, "PlayItem ID", 0),
+    (8, "Presentation time", 0),
+    (9, "Navigation timer", 0),
+    (10, "Selected button ID", 0xffff),
+    (11, "Page ID", 0),
+    (12, "User style number", 0xff),
+    (13, "User age", 0xff),
+    (
+        14,
+        "Secondary audio stream number and secondary video stream number",
+        0xffff,
+    ),
+    (15, "Player capability for audio", 0),
+    (16, "Language code for audio", 0xffffff),
+    (17, "Language code for PG and Text subtitles", 0xffffff),
+    (18, "Menu description language code", 0xffffff),
+    (19, "Country code", 0xffff),
+    (20, "Region code", 0),
+    (21, "Output Mode Preference", 0),
+    (22, "Stereoscopic status", 0),
+    (23, "Display capability", 0),
+    (24, "3D capability", 0),
+    (25, "UHD capability", 0),
+    (26, "UHD display capability", 0),
+    (27, "HDR preference", 0),
+    (28, "SDR conversion preference", 0),
+    (29, "Player capability for video", 0),
+    (30, "Player capability for text subtitle", 0x1ffff),
+    (31, "Player profile and version", 0x00030200),
+    (36, "Backup PSR4", 0xffff),
+    (37, "Backup PSR5", 0xffff),
+    (38, "Backup PSR6", 0),
+    (39, "Backup PSR7", 0),
+    (40, "Backup PSR8", 0),
+    (42, "Backup PSR10", 0xffff),
+    (43, "Backup PSR11", 0),
+    (44, "Backup PSR12", 0xff),
+    (102, "BD+ receive", 0),
+    (103, "BD+ send", 0),
+    (104, "BD+ shared", 0),
+];
+
+/// Checks whether MObj programs may only read `psr`.
+pub fn is_read_only(psr: u32) -> bool {
+    matches!(psr, 13 | 15..=21 | 23..=31 | 48..=61)
+}
+
+/// Name of `psr` if it is documented.
+pub fn psr_name(psr: u32) -> Option<&'static str> {
+    match psr {
+        48..=61 => Some("Characteristic text caps"),
+        _ => PSR_INFO
+            .iter()
+            .find(|(number, _, _)| *number == psr)
+            .map(|(_, name, _)| *name),
+    }
+}
+
+/// Player status registers of an HDMV player.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::psr::{PsrBank, PSR_REGION};
+/// let mut psrs = PsrBank::new();
+/// assert_eq!(psrs.title(), 0xffff);
+/// assert!(psrs.set(4, 1));
+/// assert_eq!(psrs.title(), 1);
+///
+/// /* The region code is configured by the player only */
+/// assert!(!psrs.set(PSR_REGION, 4));
+/// psrs.set_player_setting(PSR_REGION, 4);
+/// assert_eq!(psrs.region_code(), 4);
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct PsrBank {
+    regs: [u32; PSR_COUNT],
+}
+
+impl Default for PsrBank {
+    fn default() -> Self {
+        let mut regs = [0; PSR_COUNT];
+        for (psr, _, value) in PSR_INFO.iter() {
+            regs[*psr as usize] = *value;
+        }
+        Self { regs }
+    }
+}
+
+impl PsrBank {
+    /// Creates registers with their power-on values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `psr`, returning 0 for registers out of range.
+    pub fn get(&self, psr: u32) -> u32 {
+        self.regs.get(psr as usize).copied().unwrap_or(0)
+    }
+
+    /// Writes `psr` as an MObj program would.
+    ///
+    /// Returns `false` without writing if the register is read-only or out of range.
+    pub fn set(&mut self, psr: u32, value: u32) -> bool {
+        if is_read_only(psr) {
+            return false;
+        }
+        match self.regs.get_mut(psr as usize) {
+            Some(reg) => {
+                *reg = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Writes `psr` as the player would, including read-only player settings and capabilities.
+    /// Registers out of range are ignored.
+    pub fn set_player_setting(&mut self, psr: u32, value: u32) {
+        if let Some(reg) = self.regs.get_mut(psr as usize) {
+            *reg = value;
+        }
+    }
+
+    /// Saves the playback position registers PSR4 to PSR12 to PSR36 to PSR44, as when a title
+    /// is called.
+    pub fn backup(&mut self) {
+        for psr in BACKUP_PSRS.iter() {
+            self.regs[(PSR_BACKUP_START + psr - 4) as usize] = self.regs[*psr as usize];
+        }
+    }
+
+    /// Restores the playback position registers saved by [`PsrBank::backup`], as when a called
+    /// title resumes.
+    pub fn restore(&mut self) {
+        for psr in BACKUP_PSRS.iter() {
+            self.regs[*psr as usize] = self.regs[(PSR_BACKUP_START + psr - 4) as usize];
+        }
+    }
+
+    /// Interactive graphics stream number.
+    pub fn ig_stream(&self) -> u32 {
+        self.get(PSR_IG_STREAM)
+    }
+
+    /// Primary audio stream number.
+    pub fn primary_audio_stream(&self) -> u32 {
+        self.get(PSR_PRIMARY_AUDIO_STREAM)
+    }
+
+    /// PG or TextST stream number.
+    pub fn pg_stream(&self) -> u32 {
+        self.get(PSR_PG_STREAM) & 0xfff
+    }
+
+    /// Checks whether PG or TextST subtitles are displayed.
+    pub fn pg_enabled(&self) -> bool {
+        self.get(PSR_PG_STREAM) & 0x80000000 != 0
+    }
+
+    /// Angle number.
+    pub fn angle(&self) -> u32 {
+        self.get(PSR_ANGLE)
+    }
+
+    /// Title number, 0xffff before the first title plays.
+    pub fn title(&self) -> u32 {
+        self.get(PSR_TITLE)
+    }
+
+    /// Chapter number, 0xffff outside of a chapter.
+    pub fn chapter(&self) -> u32 {
+        self.get(PSR_CHAPTER)
+    }
+
+    /// ID of the current PlayList.
+    pub fn playlist(&self) -> u32 {
+        self.get(PSR_PLAYLIST)
+    }
+
+    /// ID of the current PlayItem.
+    pub fn play_item(&self) -> u32 {
+        self.get(PSR_PLAY_ITEM)
+    }
+
+    /// Presentation time in 45kHz ticks.
+    pub fn presentation_time(&self) -> u32 {
+        self.get(PSR_PRESENTATION_TIME)
+    }
+
+    /// Navigation timer.
+    pub fn nav_timer(&self) -> u32 {
+        self.get(PSR_NAV_TIMER)
+    }
+
+    /// Selected button ID.
+    pub fn selected_button(&self) -> u32 {
+        self.get(PSR_SELECTED_BUTTON)
+    }
+
+    /// Page ID.
+    pub fn page(&self) -> u32 {
+        self.get(PSR_PAGE)
+    }
+
+    /// User style number.
+    pub fn user_style(&self) -> u32 {
+        self.get(PSR_USER_STYLE)
+    }
+
+    /// User age for parental control, 0xff if unrestricted.
+    pub fn parental_age(&self) -> u32 {
+        self.get(PSR_PARENTAL_AGE)
+    }
+
+    /// ISO 639-2 language code for audio.
+    pub fn audio_language(&self) -> u32 {
+        self.get(PSR_AUDIO_LANGUAGE)
+    }
+
+    /// ISO 639-2 language code for subtitles.
+    pub fn pg_language(&self) -> u32 {
+        self.get(PSR_PG_LANGUAGE)
+    }
+
+    /// ISO 639-2 language code for menus.
+    pub fn menu_language(&self) -> u32 {
+        self.get(PSR_MENU_LANGUAGE)
+    }
+
+    /// ISO 3166-1 country code.
+    pub fn country_code(&self) -> u32 {
+        self.get(PSR_COUNTRY)
+    }
+
+    /// Region code: 1 for region A, 2 for B, 4 for C.
+    pub fn region_code(&self) -> u32 {
+        self.get(PSR_REGION)
+    }
+
+    /// Player profile and version.
+    pub fn profile_version(&self) -> u32 {
+        self.get(PSR_PROFILE_VERSION)
+    }
+}
+
+impl core::fmt::Debug for PsrBank {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.regs
+                    .iter()
+                    .enumerate()
+                    .filter(|(psr, value)| **value != 0 || psr_name(*psr as u32).is_some()),
+            )
+            .finish()
+    }
+}
+
+#[test]
+fn test_psr_bank() {
+    let mut psrs = PsrBank::new();
+    assert_eq!(psrs.ig_stream(), 1);
+    assert_eq!(psrs.selected_button(), 0xffff);
+    assert!(!psrs.pg_enabled());
+    assert_eq!(psr_name(PSR_REGION), Some("Region code"));
+    assert_eq!(psr_name(55), Some("Characteristic text caps"));
+    assert_eq!(psr_name(90), None);
+
+    assert!(psrs.set(PSR_TITLE, 3));
+    assert!(psrs.set(PSR_PLAYLIST, 10));
+    assert!(psrs.set(PSR_PAGE, 2));
+    assert!(!psrs.set(PSR_PARENTAL_AGE, 18));
+    assert!(!psrs.set(PSR_COUNT as u32, 1));
+    assert_eq!(psrs.get(PSR_COUNT as u32), 0);
+
+    psrs.backup();
+    assert_eq!(psrs.get(36), 3);
+    assert_eq!(psrs.get(38), 10);
+    assert_eq!(psrs.get(43), 2);
+    psrs.set(PSR_TITLE, 1);
+    psrs.set(PSR_PLAYLIST, 0);
+    psrs.restore();
+    assert_eq!(psrs.title(), 3);
+    assert_eq!(psrs.playlist(), 10);
+}
@@ -0,0 +1,265 @@
+//! Module for standalone PGS subtitle (`.sup`) files.
+//!
+//! A `.sup` file is the sequence of PG segments of one subtitle stream, each prefixed by the `PG`
+//! magic and the PTS and DTS of the PES unit carrying it. [`SupWriter`] stores segments extracted
+//! from M2TS streams in this form and [`SupReader`] parses them back into [`PgSegmentData`].
+
+use super::pg::{parse_pg_segment_data, PgSegmentData};
+use super::{BdavAppDetails, BdavErrorDetails, BdavParserStorage, DefaultBdavAppDetails};
+use crate::prelude::*;
+use crate::{Error, ErrorDetails, Result, SliceReader, Timestamp90k};
+use core::marker::PhantomData;
+use std::io::{ErrorKind, Read, Write};
+
+/// Magic bytes starting every segment of a `.sup` file.
+pub const SUP_MAGIC: [u8; 2] = *b"PG";
+
+/// Length of the header preceding each segment: magic, PTS, DTS, segment type and length.
+const SUP_HEADER_LENGTH: usize = 13;
+
+/// One PG segment of a `.sup` file with its timing.
+#[derive(Debug)]
+pub struct SupSegment {
+    /// Presentation time stamp, truncated to 32 bits.
+    pub pts: Timestamp90k,
+    /// Decoding time stamp, truncated to 32 bits; zero when the PES unit carried none.
+    pub dts: Timestamp90k,
+    /// Parsed segment.
+    pub data: PgSegmentData,
+}
+
+/// Iterator parsing [`SupSegment`] objects from a [`Read`] stream.
+///
+/// Objects and interactive compositions split across several segments are reassembled as in
+/// transport streams. A trailing partial segment is reported once as
+/// [`ErrorDetails::BadPacketLength`] and a missing magic as [`BdavErrorDetails::BadSupMagic`].
+/// Both end the iteration, as do read failures reported as [`ErrorDetails::IoError`].
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::pg::{PgSegmentData, PgsEndOfDisplay};
+/// use mpegts_io::bdav::sup::{SupReader, SupWriter};
+/// use mpegts_io::Timestamp90k;
+/// use std::io::Cursor;
+///
+/// let mut writer = SupWriter::new(Vec::new());
+/// let end = PgSegmentData::PgsEndOfDisplay(PgsEndOfDisplay {});
+/// writer.write_segment(Timestamp90k(90000), None, &end)?;
+///
+/// let mut reader = SupReader::<_>::new(Cursor::new(writer.into_inner()));
+/// let segment = reader.next().unwrap().expect("Parse Error!");
+/// assert_eq!(segment.pts, Timestamp90k(90000));
+/// assert!(matches!(segment.data, PgSegmentData::PgsEndOfDisplay(_)));
+/// assert!(reader.next().is_none());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct SupReader<R, D: BdavAppDetails = DefaultBdavAppDetails> {
+    reader: R,
+    storage: BdavParserStorage,
+    buf: Vec<u8>,
+    done: bool,
+    phantom: PhantomData<D>,
+}
+
+impl<R: Read, D: BdavAppDetails> SupReader<R, D> {
+    /// Creates a reader of the segments of `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            storage: BdavParserStorage::default(),
+            buf: Vec::new(),
+            done: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Fills the buffer from `start` unless the stream ends first, returning the filled length.
+    fn read_full(&mut self, start: usize) -> std::io::Result<usize> {
+        let mut pos = start;
+        while pos < self.buf.len() {
+            match self.reader.read(&mut self.buf[pos..]) {
+                Ok(0) => break,
+                Ok(len) => pos += len,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(pos)
+    }
+
+    fn read_segment(&mut self) -> Result<Option<SupSegment>, D> {
+        let make_error = |details| Error {
+            location: 0,
+            details,
+        };
+        self.buf.resize(SUP_HEADER_LENGTH, 0);
+        let len = self
+            .read_full(0)
+            .map_err(|e| make_error(ErrorDetails::IoError(e)))?;
+        if len == 0 {
+            return Ok(None);
+        } else if len < SUP_HEADER_LENGTH {
+            return Err(make_error(ErrorDetails::BadPacketLength(len)));
+        } else if self.buf[..2] != SUP_MAGIC {
+            return Err(make_error(ErrorDetails::AppError(
+                BdavErrorDetails::BadSupMagic,
+            )));
+        }
+
+        let header = &self.buf[..SUP_HEADER_LENGTH];
+        let pts = u32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+        let dts = u32::from_be_bytes([header[6], header[7], header[8], header[9]]);
+        let segment_length = u16::from_be_bytes([header[11], header[12]]) as usize;
+
+        /* Keep the segment type and length for the segment parser */
+        self.buf.drain(..10);
+        self.buf.resize(3 + segment_length, 0);
+        let len = self
+            .read_full(3)
+            .map_err(|e| make_error(ErrorDetails::IoError(e)))?;
+        if len < self.buf.len() {
+            return Err(make_error(ErrorDetails::BadPacketLength(
+                SUP_HEADER_LENGTH - 3 + len,
+            )));
+        }
+        let data = parse_pg_segment_data(&mut SliceReader::new(&self.buf), &mut self.storage)?;
+        Ok(Some(SupSegment {
+            pts: Timestamp90k(pts as u64),
+            dts: Timestamp90k(dts as u64),
+            data,
+        }))
+    }
+}
+
+impl<R: Read, D: BdavAppDetails> Iterator for SupReader<R, D> {
+    type Item = Result<SupSegment, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_segment() {
+            Ok(Some(segment)) => Some(Ok(segment)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                /* Unknown segment types can be skipped; other errors lose the segment framing */
+                if !matches!(
+                    e.details,
+                    ErrorDetails::AppError(BdavErrorDetails::UnknownPgSegmentType(_))
+                ) {
+                    self.done = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Serializer of PG segments to a `.sup` stream.
+///
+/// Segments are typically the [`PgSegmentData`] of PG stream PES units, written with the PTS
+/// and DTS of their unit.
+#[derive(Debug)]
+pub struct SupWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> SupWriter<W> {
+    /// Creates a writer of segments to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes `segment` with its header. Segments too long for one `.sup` segment are split as
+    /// by [`PgSegmentData::to_segments`], each fragment carrying the same timing.
+    pub fn write_segment(
+        &mut self,
+        pts: Timestamp90k,
+        dts: Option<Timestamp90k>,
+        segment: &PgSegmentData,
+    ) -> std::io::Result<()> {
+        let dts = dts.unwrap_or_default();
+        for segment in segment.to_segments() {
+            let mut header = [0; SUP_HEADER_LENGTH - 3];
+            header[..2].copy_from_slice(&SUP_MAGIC);
+            header[2..6].copy_from_slice(&(pts.0 as u32).to_be_bytes());
+            header[6..10].copy_from_slice(&(dts.0 as u32).to_be_bytes());
+            self.writer.write_all(&header)?;
+            self.writer.write_all(&segment)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[test]
+fn test_sup_roundtrip() {
+    use super::pg::{PgSequenceDescriptor, PgsEndOfDisplay, PgsObject, PgsObjectData};
+    use std::io::Cursor;
+
+    let mut writer = SupWriter::new(Vec::new());
+    let object = PgSegmentData::PgsObject(PgsObject {
+        id: 2,
+        version: 0,
+        sequence_descriptor: PgSequenceDescriptor {
+            first_in_seq: true,
+            last_in_seq: true,
+        },
+        data: Some(PgsObjectData {
+            width: 1000,
+            height: 100,
+            data: (0..100000).map(|i| i as u8).collect(),
+        }),
+    });
+    writer
+        .write_segment(Timestamp90k(0x1_0000_0005), Some(Timestamp90k(3)), &object)
+        .unwrap();
+    writer
+        .write_segment(
+            Timestamp90k(10),
+            None,
+            &PgSegmentData::PgsEndOfDisplay(PgsEndOfDisplay {}),
+        )
+        .unwrap();
+    let mut data = writer.into_inner();
+    assert_eq!(data[..10], [b'P', b'G', 0, 0, 0, 5, 0, 0, 0, 3]);
+
+    /* The fragmented object is reassembled */
+    let segments = SupReader::<_>::new(Cursor::new(data.clone()))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(segments.len(), 3);
+    assert!(matches!(&segments[0].data, PgSegmentData::PgsObject(object) if object.data.is_none()));
+    match &segments[1].data {
+        PgSegmentData::PgsObject(object) => {
+            assert_eq!(object.data.as_ref().unwrap().data.len(), 100000)
+        }
+        data => panic!("unexpected segment {:?}", data),
+    }
+    assert_eq!(segments[2].pts, Timestamp90k(10));
+    assert_eq!(segments[2].dts, Timestamp90k(0));
+
+    /* Truncated trailing segment */
+    data.truncate(data.len() - 1);
+    let mut reader = SupReader::<_>::new(Cursor::new(data));
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(
+        reader.next().unwrap().unwrap_err().details,
+        ErrorDetails::BadPacketLength(12)
+    ));
+    assert!(reader.next().is_none());
+}
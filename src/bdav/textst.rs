@@ -0,0 +1,434 @@
+//! Module for converting Blu-Ray text subtitles (TextST) to SubRip and ASS.
+//!
+//! The [`TgsDialogStyle`] segment of a text subtitle stream defines region styles and a palette,
+//! and each [`TgsDialogPresentation`] becomes one subtitle event. Text is decoded as UTF-8, the
+//! character code used by nearly all discs; the actual code is signalled in the clip info.
+
+use super::pg::{
+    ColorMatrix, PgsPaletteEntry, TgHAlign, TgRegionStyle, TgTextRun, TgVAlign,
+    TgsDialogPresentation, TgsDialogStyle,
+};
+use crate::prelude::*;
+use core::fmt::Write;
+use log::warn;
+
+/// Font name of the ASS styles; TextST fonts are only referenced by ID.
+const ASS_FONT_NAME: &str = "Arial";
+
+/// Tracks palette updates across presentations.
+struct PresentationPalette {
+    entries: Box<[PgsPaletteEntry; 256]>,
+}
+
+impl PresentationPalette {
+    fn new(style: &TgsDialogStyle) -> Self {
+        Self {
+            entries: style.style.palette_entries.clone(),
+        }
+    }
+
+    fn update(&mut self, presentation: &TgsDialogPresentation) {
+        if let Some(entries) = presentation.palette_update.as_ref() {
+            self.entries = entries.clone();
+        }
+    }
+
+    fn rgba(&self, index: u8) -> [u8; 4] {
+        self.entries[index as usize].to_rgba(ColorMatrix::Bt709)
+    }
+}
+
+fn find_region_style(style: &TgsDialogStyle, id: u8) -> Option<&TgRegionStyle> {
+    let region_style = style
+        .style
+        .region_styles
+        .iter()
+        .find(|region_style| region_style.region_style_id == id);
+    if region_style.is_none() {
+        warn!("TextST region references unknown region style");
+    }
+    region_style
+}
+
+/// Formats 90kHz ticks as `HH:MM:SS` followed by `separator` and a fraction of `digits` digits.
+fn format_time(ticks: u64, separator: char, digits: u32) -> String {
+    let scale = 10u64.pow(digits);
+    let units = ticks * scale / 90000;
+    let seconds = units / scale;
+    format!(
+        "{:02}:{:02}:{:02}{}{:0width$}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        separator,
+        units % scale,
+        width = digits as usize
+    )
+}
+
+/// Converts text subtitle presentations to a SubRip document.
+///
+/// Regions of one presentation are joined into one event. Bold and italic runs are tagged, as is
+/// the font color of runs that differ from their region style.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::bdav::pg::{TgsDialogPresentation, TgsDialogStyle};
+/// use mpegts_io::bdav::textst;
+///
+/// # fn load() -> (TgsDialogStyle, Vec<TgsDialogPresentation>) { unimplemented!() }
+/// let (style, presentations) = load();
+/// std::fs::write("out.srt", textst::to_srt(&style, &presentations)).unwrap();
+/// ```
+pub fn to_srt(style: &TgsDialogStyle, presentations: &[TgsDialogPresentation]) -> String {
+    let mut out = String::new();
+    let mut palette = PresentationPalette::new(style);
+    let mut index = 0;
+    for presentation in presentations.iter() {
+        palette.update(presentation);
+        let mut text = String::new();
+        for region in presentation.regions.iter() {
+            let region_style = match find_region_style(style, region.region_style_id_ref) {
+                Some(region_style) => region_style,
+                None => continue,
+            };
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            let default_color = palette.rgba(region_style.font_color);
+            let mut line = 0;
+            for run in region.text_runs(region_style) {
+                while line < run.line {
+                    text.push('\n');
+                    line += 1;
+                }
+                let mut span = String::from_utf8_lossy(&run.text).into_owned();
+                let color = palette.rgba(run.font_color);
+                if color != default_color {
+                    span = format!(
+                        "<font color=\"#{:02x}{:02x}{:02x}\">{}</font>",
+                        color[0], color[1], color[2], span
+                    );
+                }
+                if run.font_style.italic() {
+                    span = format!("<i>{}</i>", span);
+                }
+                if run.font_style.bold() {
+                    span = format!("<b>{}</b>", span);
+                }
+                text.push_str(&span);
+            }
+        }
+        if text.is_empty() {
+            continue;
+        }
+        index += 1;
+        let _ = write!(
+            out,
+            "{}\n{} --> {}\n{}\n\n",
+            index,
+            format_time(presentation.start_pts, ',', 3),
+            format_time(presentation.end_pts, ',', 3),
+            text
+        );
+    }
+    out
+}
+
+/// Formats RGBA as an ASS style color, which is `&HAABBGGRR` with inverted alpha.
+fn ass_color(rgba: [u8; 4]) -> String {
+    format!(
+        "&H{:02X}{:02X}{:02X}{:02X}",
+        255 - rgba[3],
+        rgba[2],
+        rgba[1],
+        rgba[0]
+    )
+}
+
+/// Formats RGB as an ASS override tag color, which is `&HBBGGRR&`.
+fn ass_override_color(rgba: [u8; 4]) -> String {
+    format!("&H{:02X}{:02X}{:02X}&", rgba[2], rgba[1], rgba[0])
+}
+
+/// Outline width of a run or region style in pixels.
+fn ass_outline(outline_border: bool, thickness: u8) -> u8 {
+    if outline_border {
+        thickness
+    } else {
+        0
+    }
+}
+
+/// Override tags of a run that differ from its region style.
+fn ass_overrides(
+    run: &TgTextRun,
+    region_style: &TgRegionStyle,
+    palette: &PresentationPalette,
+    style_palette: &PresentationPalette,
+) -> String {
+    let mut overrides = String::new();
+    if run.font_style.bold() != region_style.font_style.bold() {
+        let _ = write!(overrides, "\\b{}", run.font_style.bold() as u8);
+    }
+    if run.font_style.italic() != region_style.font_style.italic() {
+        let _ = write!(overrides, "\\i{}", run.font_style.italic() as u8);
+    }
+    if run.font_size != region_style.font_size {
+        let _ = write!(overrides, "\\fs{}", run.font_size);
+    }
+    let color = palette.rgba(run.font_color);
+    if color != style_palette.rgba(region_style.font_color) {
+        let _ = write!(overrides, "\\c{}", ass_override_color(color));
+        let _ = write!(overrides, "\\1a&H{:02X}&", 255 - color[3]);
+    }
+    let outline_color = palette.rgba(run.outline_color);
+    if outline_color != style_palette.rgba(region_style.outline_color) {
+        let _ = write!(overrides, "\\3c{}", ass_override_color(outline_color));
+    }
+    let outline = ass_outline(run.font_style.outline_border(), run.outline_thickness as u8);
+    if outline
+        != ass_outline(
+            region_style.font_style.outline_border(),
+            region_style.outline_thickness as u8,
+        )
+    {
+        let _ = write!(overrides, "\\bord{}", outline);
+    }
+    overrides
+}
+
+/// Converts text subtitle presentations to an ASS document for video of `video_width` by
+/// `video_height` pixels.
+///
+/// Every region style becomes an ASS style named `Region<id>` positioned by its text box, and
+/// every region of a presentation becomes one event of that style. Runs that differ from their
+/// region style carry override tags.
+pub fn to_ass(
+    style: &TgsDialogStyle,
+    presentations: &[TgsDialogPresentation],
+    video_width: u16,
+    video_height: u16,
+) -> String {
+    let style_palette = PresentationPalette::new(style);
+    let mut out = format!(
+        "[Script Info]\nScriptType: v4.00+\nPlayResX: {}\nPlayResY: {}\n\n[V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, \
+         BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, \
+         BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n",
+        video_width, video_height
+    );
+    for region_style in style.style.region_styles.iter() {
+        let text_box = &region_style.text_box;
+        let alignment = match region_style.text_valign {
+            TgVAlign::Bottom => 1,
+            TgVAlign::Middle => 4,
+            TgVAlign::Top => 7,
+        } + match region_style.text_halign {
+            TgHAlign::Left => 0,
+            TgHAlign::Center => 1,
+            TgHAlign::Right => 2,
+        };
+        let margin_v = match region_style.text_valign {
+            TgVAlign::Top => text_box.ypos,
+            _ => video_height.saturating_sub(text_box.ypos.saturating_add(text_box.height)),
+        };
+        let font_color = ass_color(style_palette.rgba(region_style.font_color));
+        let _ = writeln!(
+            out,
+            "Style: Region{},{},{},{},{},{},{},{},{},0,0,100,100,0,0,1,{},0,{},{},{},{},1",
+            region_style.region_style_id,
+            ASS_FONT_NAME,
+            region_style.font_size,
+            font_color,
+            font_color,
+            ass_color(style_palette.rgba(region_style.outline_color)),
+            ass_color(style_palette.rgba(region_style.region_info.background_color)),
+            -(region_style.font_style.bold() as i8),
+            -(region_style.font_style.italic() as i8),
+            ass_outline(
+                region_style.font_style.outline_border(),
+                region_style.outline_thickness as u8
+            ),
+            alignment,
+            text_box.xpos,
+            video_width.saturating_sub(text_box.xpos.saturating_add(text_box.width)),
+            margin_v
+        );
+    }
+
+    out.push_str(
+        "\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, \
+         Text\n",
+    );
+    let mut palette = PresentationPalette::new(style);
+    for presentation in presentations.iter() {
+        palette.update(presentation);
+        for region in presentation.regions.iter() {
+            let region_style = match find_region_style(style, region.region_style_id_ref) {
+                Some(region_style) => region_style,
+                None => continue,
+            };
+            let mut text = String::new();
+            let mut line = 0;
+            let mut overridden = false;
+            for run in region.text_runs(region_style) {
+                while line < run.line {
+                    text.push_str("\\N");
+                    line += 1;
+                }
+                let overrides = ass_overrides(&run, region_style, &palette, &style_palette);
+                if !overrides.is_empty() || overridden {
+                    let _ = write!(text, "{{\\r{}}}", overrides);
+                }
+                overridden = !overrides.is_empty();
+                text.push_str(
+                    &String::from_utf8_lossy(&run.text)
+                        .replace('{', "\\{")
+                        .replace('}', "\\}"),
+                );
+            }
+            if text.is_empty() {
+                continue;
+            }
+            /* ASS times have centisecond precision and a single digit hour */
+            let time = |ticks: u64| format_time(ticks, '.', 2)[1..].to_string();
+            let _ = writeln!(
+                out,
+                "Dialogue: 0,{},{},Region{},,0,0,0,,{}",
+                time(presentation.start_pts),
+                time(presentation.end_pts),
+                region_style.region_style_id,
+                text
+            );
+        }
+    }
+    out
+}
+
+#[test]
+fn test_textst_export() {
+    use super::pg::{
+        TgDialogRegion, TgDialogStyle, TgFontStyle, TgInlineElement, TgOutlineThickness, TgRect,
+        TgRegionInfo, TgTextFlow,
+    };
+    use smallvec::smallvec;
+
+    let mut palette_entries = Box::new([PgsPaletteEntry::default(); 256]);
+    /* White, black and yellow */
+    palette_entries[1] = PgsPaletteEntry {
+        y: 235,
+        cr: 128,
+        cb: 128,
+        t: 255,
+    };
+    palette_entries[2] = PgsPaletteEntry {
+        y: 16,
+        cr: 128,
+        cb: 128,
+        t: 255,
+    };
+    palette_entries[4] = PgsPaletteEntry {
+        y: 210,
+        cr: 146,
+        cb: 16,
+        t: 255,
+    };
+    let style = TgsDialogStyle {
+        style: TgDialogStyle {
+            player_style_flag: false,
+            region_styles: vec![TgRegionStyle {
+                region_style_id: 3,
+                region_info: TgRegionInfo {
+                    region: TgRect {
+                        xpos: 0,
+                        ypos: 800,
+                        width: 1920,
+                        height: 200,
+                    },
+                    background_color: 2,
+                },
+                text_box: TgRect {
+                    xpos: 100,
+                    ypos: 820,
+                    width: 1720,
+                    height: 160,
+                },
+                text_flow: TgTextFlow::LeftRight,
+                text_halign: TgHAlign::Center,
+                text_valign: TgVAlign::Bottom,
+                line_space: 10,
+                font_id_ref: 0,
+                font_style: TgFontStyle::new().with_outline_border(true),
+                font_size: 48,
+                font_color: 1,
+                outline_color: 2,
+                outline_thickness: TgOutlineThickness::Medium,
+            }],
+            user_styles: Vec::new(),
+            palette_entries,
+        },
+        num_dialogs: 2,
+    };
+    let presentation = |start_pts, end_pts, elements: &[TgInlineElement]| {
+        let mut region = TgDialogRegion {
+            continuous_present_flag: false,
+            forced_on_flag: false,
+            region_style_id_ref: 3,
+            data: Vec::new(),
+        };
+        region.set_elements(elements);
+        TgsDialogPresentation {
+            start_pts,
+            end_pts,
+            palette_update: None,
+            regions: smallvec![region],
+        }
+    };
+    let presentations = [
+        presentation(
+            90090,
+            270000,
+            &[
+                TgInlineElement::Text(b"Hello".to_vec()),
+                TgInlineElement::LineBreak,
+                TgInlineElement::FontStyle {
+                    style: TgFontStyle::new()
+                        .with_italic(true)
+                        .with_outline_border(true),
+                    outline_color: 2,
+                    outline_thickness: TgOutlineThickness::Medium,
+                },
+                TgInlineElement::FontColor(4),
+                TgInlineElement::Text(b"{world}".to_vec()),
+                TgInlineElement::ResetStyle,
+                TgInlineElement::Text(b"!".to_vec()),
+            ],
+        ),
+        presentation(
+            3600 * 90000 + 45,
+            3601 * 90000,
+            &[TgInlineElement::Text(b"Later".to_vec())],
+        ),
+    ];
+
+    assert_eq!(
+        to_srt(&style, &presentations),
+        "1\n00:00:01,001 --> 00:00:03,000\nHello\n\
+         <i><font color=\"#fff000\">{world}</font></i>!\n\n\
+         2\n01:00:00,000 --> 01:00:01,000\nLater\n\n"
+    );
+
+    let ass = to_ass(&style, &presentations, 1920, 1080);
+    assert!(ass.contains("PlayResX: 1920\nPlayResY: 1080\n"));
+    assert!(ass.contains(
+        "\nStyle: Region3,Arial,48,&H00FFFFFF,&H00FFFFFF,&H00000000,&H00000000,0,0,0,0,100,100,\
+         0,0,1,2,0,2,100,100,100,1\n"
+    ));
+    assert!(ass.contains(
+        "\nDialogue: 0,0:00:01.00,0:00:03.00,Region3,,0,0,0,,\
+         Hello\\N{\\r\\i1\\c&H00F0FF&\\1a&H00&}\\{world\\}{\\r}!\n"
+    ));
+    assert!(ass.ends_with("\nDialogue: 0,1:00:00.00,1:00:01.00,Region3,,0,0,0,,Later\n"));
+}
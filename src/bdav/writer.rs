@@ -0,0 +1,148 @@
+use super::BdavPacketHeader;
+use crate::{
+    parse_pcr, AdaptationField, AdaptationFieldHeader, MpegTsWriter, PacketHeader, PcrTimestamp,
+};
+use core::convert::TryInto;
+use std::io::{Result, Write};
+
+/// BDAV (M2TS) packet serializer stamping each packet with a 27MHz arrival timestamp.
+///
+/// Arrival timestamps advance by a fixed amount per packet according to the mux rate. When a
+/// PCR PID is set, the arrival clock is resynchronized to every PCR written on that PID so the
+/// timestamps follow the pacing of the program. The clock may also be set directly with
+/// [`BdavWriter::set_arrival_time`].
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::bdav::{BdavParser, BdavWriter};
+/// use mpegts_io::{PcrTimestamp, Timestamp90k};
+/// use std::convert::TryInto;
+///
+/// let mut writer = BdavWriter::new(48_000_000);
+/// writer.set_pcr_pid(Some(0x1011));
+/// let mut out = Vec::new();
+/// let pcr = PcrTimestamp { base: Timestamp90k(90000), extension: 0 };
+/// writer.write_unit(&mut out, 0x1011, &[0; 400], Some(pcr))?;
+///
+/// let mut parser = BdavParser::default();
+/// let first = parser.parse(out[..192].try_into().unwrap()).unwrap();
+/// let second = parser.parse(out[192..384].try_into().unwrap()).unwrap();
+/// assert_eq!(first.header.timestamp(), 90000 * 300);
+/// assert_eq!(second.header.timestamp(), 90000 * 300 + 864);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BdavWriter {
+    writer: MpegTsWriter,
+    cpi: u8,
+    arrival_time: u64,
+    packet_interval: u64,
+    pcr_pid: Option<u16>,
+}
+
+impl BdavWriter {
+    /// Creates a writer spacing packets for a mux rate of `mux_rate` bits per second.
+    pub fn new(mux_rate: u64) -> Self {
+        Self {
+            writer: MpegTsWriter::default(),
+            cpi: 0,
+            arrival_time: 0,
+            packet_interval: 192 * 8 * 27_000_000 / mux_rate.max(1),
+            pcr_pid: None,
+        }
+    }
+
+    /// Sets the copy permission indicator written in every header.
+    pub fn set_cpi(&mut self, cpi: u8) {
+        self.cpi = cpi & 0x3;
+    }
+
+    /// Sets the PID whose PCRs resynchronize the arrival clock, or [`None`] to pace packets by
+    /// the mux rate alone.
+    pub fn set_pcr_pid(&mut self, pcr_pid: Option<u16>) {
+        self.pcr_pid = pcr_pid;
+    }
+
+    /// Sets the 27MHz arrival timestamp of the next packet.
+    pub fn set_arrival_time(&mut self, arrival_time: u32) {
+        self.arrival_time = arrival_time as u64;
+    }
+
+    /// 27MHz arrival timestamp of the next packet.
+    pub fn arrival_time(&self) -> u32 {
+        (self.arrival_time & 0x3fff_ffff) as u32
+    }
+
+    /// Underlying MPEG-TS packet serializer.
+    pub fn ts_writer(&mut self) -> &mut MpegTsWriter {
+        &mut self.writer
+    }
+
+    /// Prepends a BDAV header to an already serialized MPEG-TS packet.
+    pub fn stamp_packet(&mut self, packet: &[u8; 188]) -> [u8; 192] {
+        if let Some(pcr) = self.pcr_of(packet) {
+            self.arrival_time = pcr.base.0 * 300 + pcr.extension as u64;
+        }
+        let header = BdavPacketHeader::new()
+            .with_cpi(self.cpi)
+            .with_timestamp(self.arrival_time());
+        self.arrival_time = (self.arrival_time + self.packet_interval) & 0x3fff_ffff;
+
+        let mut out = [0_u8; 192];
+        out[..4].copy_from_slice(&header.into_bytes());
+        out[4..].copy_from_slice(packet);
+        out
+    }
+
+    /// Stamps and writes an already serialized MPEG-TS packet to `out`.
+    pub fn write_ts_packet<W: Write>(&mut self, out: &mut W, packet: &[u8; 188]) -> Result<()> {
+        out.write_all(&self.stamp_packet(packet))
+    }
+
+    /// Serializes and stamps one packet to `out`. See [`MpegTsWriter::build_packet`].
+    pub fn write_packet<W: Write>(
+        &mut self,
+        out: &mut W,
+        header: &PacketHeader,
+        adaptation_field: Option<&AdaptationField>,
+        payload: &[u8],
+    ) -> Result<()> {
+        let packet = self
+            .writer
+            .build_packet(header, adaptation_field, payload)?;
+        self.write_ts_packet(out, &packet)
+    }
+
+    /// Packetizes and stamps a complete payload unit to `out`.
+    /// See [`MpegTsWriter::packetize_unit`].
+    ///
+    /// Returns the number of packets written.
+    pub fn write_unit<W: Write>(
+        &mut self,
+        out: &mut W,
+        pid: u16,
+        unit: &[u8],
+        pcr: Option<PcrTimestamp>,
+    ) -> Result<usize> {
+        let packets = self.writer.packetize_unit(pid, unit, pcr);
+        for packet in packets.iter() {
+            self.write_ts_packet(out, packet)?;
+        }
+        Ok(packets.len())
+    }
+
+    /// Extracts the PCR of a packet on the PCR PID.
+    fn pcr_of(&self, packet: &[u8; 188]) -> Option<PcrTimestamp> {
+        let header = PacketHeader::from_bytes(packet[..4].try_into().unwrap());
+        if Some(header.pid()) != self.pcr_pid || !header.has_adaptation_field() || packet[4] < 7 {
+            return None;
+        }
+        let af_header = AdaptationFieldHeader::from_bytes(packet[4..6].try_into().unwrap());
+        if af_header.has_pcr() {
+            Some(parse_pcr(packet[6..12].try_into().unwrap()))
+        } else {
+            None
+        }
+    }
+}
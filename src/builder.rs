@@ -0,0 +1,525 @@
+use super::{
+    AppDetails, ErrorDetails, MpegTsParser, PacketHeader, ParserEvent, PidFilter, Result,
+    SliceReader, Warning,
+};
+use crate::prelude::*;
+use log::warn;
+
+/// Handling of packets with the transport error indicator set, chosen with
+/// [`MpegTsParserBuilder::tei_policy`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum TeiPolicy {
+    /// Parses the packet as if it were valid. The indicator remains visible in the
+    /// [`PacketHeader`].
+    #[default]
+    Parse,
+    /// Returns the packet with only its header parsed and discards the pending payload unit of
+    /// its PID.
+    Drop,
+    /// Fails with [`ErrorDetails::TransportError`] and discards the pending payload unit of the
+    /// packet's PID.
+    Error,
+}
+
+/// Behavior toggles of [`MpegTsParser`] set via [`MpegTsParserBuilder`].
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ParserConfig {
+    pub strict: bool,
+    pub lenient: bool,
+    pub check_continuity: bool,
+    pub keep_interrupted_units: bool,
+    pub abort_units_on_discontinuity: bool,
+    pub tei_policy: TeiPolicy,
+    pub max_pending_units: Option<usize>,
+}
+
+/// Builder for configuring a [`MpegTsParser`].
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+/// let parser = MpegTsParser::<DefaultAppDetails>::builder()
+///     .strict(true)
+///     .check_continuity(true)
+///     .max_pending_units(64)
+///     .stats(true)
+///     .build();
+/// assert!(parser.stats().is_some());
+/// ```
+pub struct MpegTsParserBuilder<D: AppDetails> {
+    config: ParserConfig,
+    stats: bool,
+    program: Option<u16>,
+    pid_filter: Option<PidFilter>,
+    app_details: D,
+    app_parser_storage: D::AppParserStorage,
+}
+
+impl<D: AppDetails> MpegTsParser<D>
+where
+    D::AppParserStorage: Default,
+{
+    /// Starts configuring a parser with the same settings as [`MpegTsParser::default`].
+    pub fn builder() -> MpegTsParserBuilder<D> {
+        MpegTsParserBuilder {
+            config: ParserConfig::default(),
+            stats: false,
+            program: None,
+            pid_filter: None,
+            app_details: Default::default(),
+            app_parser_storage: Default::default(),
+        }
+    }
+}
+
+impl<D: AppDetails> MpegTsParserBuilder<D> {
+    /// Turns conditions that are otherwise logged and recovered from into errors: discarded
+    /// unfinished payload units, continuations without a unit start, continuity errors, and
+    /// exceeding the pending unit limit.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.config.strict = strict;
+        self
+    }
+
+    /// Returns packets whose payload fails to parse, e.g. due to a bad PES header or PSI CRC
+    /// mismatch, with the payload as [`Payload::Raw`](crate::Payload::Raw) and the error in
+    /// [`Packet::payload_error`](crate::Packet::payload_error) rather than failing
+    /// [`MpegTsParser::parse`]. The pending payload unit of the PID is discarded.
+    ///
+    /// This also applies to the errors raised by [`MpegTsParserBuilder::strict`].
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.config.lenient = lenient;
+        self
+    }
+
+    /// Verifies the continuity counter of every packet carrying payload. Payload units
+    /// interrupted by lost packets are discarded, duplicate packets are not assembled twice and
+    /// [`ParserEvent::ContinuityError`](crate::ParserEvent::ContinuityError) is reported.
+    pub fn check_continuity(mut self, check_continuity: bool) -> Self {
+        self.config.check_continuity = check_continuity;
+        self
+    }
+
+    /// Keeps assembling payload units interrupted by a continuity error rather than discarding
+    /// them, for applications that prefer damaged units over missing ones.
+    pub fn keep_interrupted_units(mut self, keep_interrupted_units: bool) -> Self {
+        self.config.keep_interrupted_units = keep_interrupted_units;
+        self
+    }
+
+    /// Discards the pending payload unit of a PID when its adaptation field sets the
+    /// discontinuity indicator, so data is not assembled across splice points.
+    pub fn abort_units_on_discontinuity(mut self, abort_units_on_discontinuity: bool) -> Self {
+        self.config.abort_units_on_discontinuity = abort_units_on_discontinuity;
+        self
+    }
+
+    /// Sets how packets with the transport error indicator set interact with payload unit
+    /// assembly. Defaults to [`TeiPolicy::Parse`].
+    pub fn tei_policy(mut self, tei_policy: TeiPolicy) -> Self {
+        self.config.tei_policy = tei_policy;
+        self
+    }
+
+    /// Limits the number of payload units assembled concurrently. Units started beyond the
+    /// limit are left unassembled.
+    pub fn max_pending_units(mut self, max_pending_units: usize) -> Self {
+        self.config.max_pending_units = Some(max_pending_units);
+        self
+    }
+
+    /// Enables gathering of [`StreamStats`](crate::StreamStats).
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Restricts payload assembly to the PIDs of one program.
+    /// See [`MpegTsParser::select_program`].
+    pub fn select_program(mut self, program_num: u16) -> Self {
+        self.program = Some(program_num);
+        self
+    }
+
+    /// Skips packets of PIDs rejected by `filter` after their header.
+    /// See [`MpegTsParser::set_pid_filter`].
+    pub fn pid_filter(mut self, filter: PidFilter) -> Self {
+        self.pid_filter = Some(filter);
+        self
+    }
+
+    /// Sets the [`AppDetails`] instance deciding which PES units are parsed into
+    /// [`PesUnitObject`](crate::PesUnitObject)s. Defaults to `D::default()`.
+    pub fn app_details(mut self, app_details: D) -> Self {
+        self.app_details = app_details;
+        self
+    }
+
+    /// Sets the initial [`AppDetails::AppParserStorage`] instance.
+    pub fn app_parser_storage(mut self, app_parser_storage: D::AppParserStorage) -> Self {
+        self.app_parser_storage = app_parser_storage;
+        self
+    }
+
+    /// Creates the configured parser.
+    pub fn build(self) -> MpegTsParser<D> {
+        let mut parser = MpegTsParser {
+            pending_payload_units: Default::default(),
+            known_pmt_pids: Default::default(),
+            programs: Default::default(),
+            pmts: Default::default(),
+            es_streams: Default::default(),
+            ca_pids: Default::default(),
+            program_states: Default::default(),
+            table_versions: Default::default(),
+            pending_events: Default::default(),
+            pending_warnings: Default::default(),
+            ended_unit: None,
+            buffer_pool: Default::default(),
+            program_filter: None,
+            pid_filter: self.pid_filter,
+            stats: None,
+            config: self.config,
+            continuity_counters: Default::default(),
+            demux_handlers: Default::default(),
+            app_details: self.app_details,
+            app_parser_storage: self.app_parser_storage,
+        };
+        parser.set_stats_enabled(self.stats);
+        if let Some(program_num) = self.program {
+            parser.select_program(program_num);
+        }
+        parser
+    }
+}
+
+/// Continuity state of the last packet carrying payload on a PID.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ContinuityState {
+    counter: u8,
+    duplicated: bool,
+    payload: Vec<u8>,
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Forgets the continuity state of a PID whose adaptation field sets the discontinuity
+    /// indicator, optionally discarding its pending payload unit.
+    pub(crate) fn reset_for_discontinuity(&mut self, pid: u16) {
+        self.continuity_counters.remove(&pid);
+        if self.config.abort_units_on_discontinuity && self.discard_payload_unit(pid) {
+            self.push_warning(Warning::UnitInterruptedByDiscontinuity { pid });
+        }
+    }
+
+    /// Checks the continuity counter of a packet when enabled.
+    ///
+    /// Returns `false` if the payload duplicates the previous packet and must not be assembled.
+    /// Packets with the previous counter are only duplicates if their payload is byte-identical.
+    pub(crate) fn check_continuity(
+        &mut self,
+        header: &PacketHeader,
+        reader: &mut SliceReader<D>,
+    ) -> Result<bool, D> {
+        if !self.config.check_continuity || !header.has_payload() {
+            return Ok(true);
+        }
+        let pid = header.pid();
+        let counter = header.continuity_counter();
+        let payload = reader.peek(reader.remaining_len())?;
+
+        let last = self
+            .continuity_counters
+            .get(&pid)
+            .map(|last| (last.counter, last.duplicated, last.payload == payload));
+        let repeated = last.is_some_and(|(last, _, same)| last == counter && same);
+        /* A packet may be sent twice, but not more */
+        let duplicate = repeated && last.is_some_and(|(_, duplicated, _)| !duplicated);
+        let state = self.continuity_counters.entry(pid).or_default();
+        state.counter = counter;
+        state.duplicated = duplicate;
+        state.payload.clear();
+        state.payload.extend_from_slice(payload);
+        if duplicate {
+            self.push_warning(Warning::DuplicatePacket { pid });
+            return Ok(false);
+        }
+        match last.map(|(last, _, _)| last) {
+            Some(last) if (last + 1) & 0xf != counter => {
+                let expected = (last + 1) & 0xf;
+                warn!(
+                    "Continuity error on PID: {:x} (expected {}, got {})",
+                    pid, expected, counter
+                );
+                if !self.config.keep_interrupted_units {
+                    self.discard_payload_unit(pid);
+                }
+                if self.config.strict {
+                    Err(reader.make_error(ErrorDetails::<D>::ContinuityError(pid)))
+                } else {
+                    self.pending_events.push(ParserEvent::ContinuityError {
+                        pid,
+                        expected,
+                        got: counter,
+                    });
+                    /* Excess repeats are still not assembled */
+                    Ok(!repeated)
+                }
+            }
+            _ => Ok(true),
+        }
+    }
+}
+
+#[test]
+fn test_continuity_events() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, Payload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    let packets = gen.packets();
+
+    /* PAT, PMT, then the first unit with its second packet sent three times */
+    let mut stream = vec![packets[0], packets[1], packets[2], packets[3]];
+    stream.extend_from_slice(&[packets[3], packets[3], packets[4]]);
+    /* Second unit with its second packet lost */
+    stream.extend_from_slice(&[packets[5], packets[7]]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .check_continuity(true)
+        .build();
+    let results: Vec<_> = stream.iter().map(|p| parser.parse(p).unwrap()).collect();
+    assert!(!results[3].duplicate);
+    assert!(results[4].duplicate && results[4].events.is_empty());
+    assert!(matches!(results[4].payload, Some(Payload::Raw(_))));
+    assert_eq!(
+        results[5].events,
+        [ParserEvent::ContinuityError {
+            pid: 0x1200,
+            expected: 2,
+            got: 1
+        }]
+    );
+    /* The first unit is discarded, so its last packet is an unexpected continuation */
+    assert!(!matches!(results[6].payload, Some(Payload::Pes(_))));
+    assert_eq!(
+        results[8].events,
+        [ParserEvent::ContinuityError {
+            pid: 0x1200,
+            expected: 4,
+            got: 5
+        }]
+    );
+    assert!(!matches!(results[8].payload, Some(Payload::Pes(_))));
+
+    /* Interrupted units may be kept instead */
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .check_continuity(true)
+        .keep_interrupted_units(true)
+        .build();
+    let results: Vec<_> = stream.iter().map(|p| parser.parse(p).unwrap()).collect();
+    assert!(matches!(results[6].payload, Some(Payload::Pes(_))));
+
+    /* A repeated counter with a different payload is not a duplicate */
+    let mut corrupt = packets[3];
+    corrupt[187] ^= 0xff;
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .check_continuity(true)
+        .build();
+    for packet in &packets[..4] {
+        parser.parse(packet).unwrap();
+    }
+    let result = parser.parse(&corrupt).unwrap();
+    assert!(!result.duplicate);
+    assert_eq!(
+        result.events,
+        [ParserEvent::ContinuityError {
+            pid: 0x1200,
+            expected: 2,
+            got: 1
+        }]
+    );
+}
+
+#[test]
+fn test_tei_policy() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, Payload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1200, Some(0), None, &[0; 300]);
+    let mut packets = gen.packets().to_vec();
+    packets[3][1] |= 0x80;
+
+    let parse = |policy| {
+        let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+            .tei_policy(policy)
+            .build();
+        packets.iter().map(|p| parser.parse(p)).collect::<Vec<_>>()
+    };
+
+    let results = parse(TeiPolicy::Parse);
+    let packet = results[3].as_ref().unwrap();
+    assert!(matches!(packet.payload, Some(Payload::Pes(_))));
+    assert!(packet.warnings.is_empty());
+
+    let results = parse(TeiPolicy::Drop);
+    let packet = results[3].as_ref().unwrap();
+    assert!(packet.payload.is_none());
+    assert_eq!(
+        packet.warnings,
+        [Warning::TransportErrorDropped { pid: 0x1200 }]
+    );
+
+    let results = parse(TeiPolicy::Error);
+    assert!(matches!(
+        results[3].as_ref().unwrap_err().details,
+        ErrorDetails::TransportError(0x1200)
+    ));
+}
+
+#[test]
+fn test_lenient() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, Payload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().inject_crc_error().write_pmt();
+    let packets = gen.packets();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    parser.parse(&packets[0]).unwrap();
+    let err = parser.parse(&packets[1]).unwrap_err();
+    assert!(matches!(err.details, ErrorDetails::PsiCrcMismatch));
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .lenient(true)
+        .build();
+    parser.parse(&packets[0]).unwrap();
+    let packet = parser.parse(&packets[1]).unwrap();
+    assert_eq!(packet.header.pid(), 0x100);
+    let payload_len = 188 - packet.payload_offset.unwrap();
+    assert!(
+        matches!(packet.payload, Some(Payload::Raw(ref raw)) if raw.remaining_len() == payload_len)
+    );
+    assert!(matches!(
+        packet.payload_error.unwrap().details,
+        ErrorDetails::PsiCrcMismatch
+    ));
+}
+
+#[test]
+fn test_discontinuity_indicator() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, Payload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    let packets = gen.packets();
+
+    /* Adaptation-only packet signalling a splice in the middle of the first unit */
+    let mut splice = [0xff_u8; 188];
+    splice[..6].copy_from_slice(&[0x47, 0x12, 0x00, 0x20, 183, 0x80]);
+    let stream = [
+        packets[0], packets[1], packets[2], splice, packets[6], packets[7],
+    ];
+
+    let parse = |abort| {
+        let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+            .check_continuity(true)
+            .abort_units_on_discontinuity(abort)
+            .build();
+        stream
+            .iter()
+            .map(|p| parser.parse(p).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let results = parse(false);
+    assert!(results[3..].iter().all(|packet| packet.events.is_empty()));
+    assert!(matches!(results[4].payload, Some(Payload::PesPending)));
+
+    let results = parse(true);
+    assert!(results[3..].iter().all(|packet| packet.events.is_empty()));
+    assert!(matches!(results[4].payload, Some(Payload::Raw(_))));
+}
+
+#[test]
+fn test_app_details() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{EsStreamInfo, Payload, PesUnitObject};
+
+    /// Keeps only the length of the units of one selected PID.
+    #[derive(Default, Debug)]
+    struct SelectedPid(u16);
+
+    #[derive(Debug)]
+    struct UnitLength(usize);
+
+    impl PesUnitObject<SelectedPid> for UnitLength {
+        fn extend_from_slice(&mut self, slice: &[u8]) {
+            self.0 += slice.len();
+        }
+
+        fn finish(
+            &mut self,
+            _pid: u16,
+            _parser: &mut MpegTsParser<SelectedPid>,
+        ) -> Result<(), SelectedPid> {
+            Ok(())
+        }
+    }
+
+    impl AppDetails for SelectedPid {
+        type AppErrorDetails = ();
+
+        type AppParserStorage = ();
+
+        fn new_pes_unit_data(
+            &self,
+            pid: u16,
+            _unit_length: usize,
+            _stream_info: Option<&EsStreamInfo>,
+        ) -> Option<Box<dyn PesUnitObject<Self>>> {
+            if pid == self.0 {
+                Some(Box::new(UnitLength(0)))
+            } else {
+                None
+            }
+        }
+    }
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0);
+    gen.add_stream(0x1100, 0x81, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(0), None, &[0; 100]);
+    gen.write_pes(0x1100, Some(0), None, &[0; 100]);
+
+    let mut parser = MpegTsParser::<SelectedPid>::builder()
+        .app_details(SelectedPid(0x1100))
+        .build();
+    assert_eq!(parser.app_details().0, 0x1100);
+    let units: Vec<_> = gen
+        .packets()
+        .iter()
+        .filter_map(|packet| match parser.parse(packet).unwrap().payload {
+            Some(Payload::Pes(pes)) => Some((
+                pes.header.stream_id(),
+                pes.data_ref::<UnitLength>().map(|unit| unit.0),
+            )),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(units, [(0xe0, None), (0xbd, Some(100))]);
+}
@@ -0,0 +1,195 @@
+//! Module for conditional access tables and messages (ISO/IEC 13818-1 CAT, ECMs and EMMs).
+//!
+//! The CAT on PID 0x0001 is returned as [`PsiData::Cat`](crate::PsiData::Cat). The CA
+//! descriptors of the CAT and PMTs register the PIDs of EMMs and ECMs with the parser, and
+//! sections on these PIDs are returned as [`PsiData::CaMessage`](crate::PsiData::CaMessage).
+
+use crate::descriptor::KnownDescriptor;
+use crate::prelude::*;
+use crate::{AppDetails, Descriptor, MpegTsParser, Pmt};
+
+/// PID carrying the CAT.
+pub const CAT_PID: u16 = 0x01;
+/// Table ID of the CAT.
+pub const CAT_TABLE_ID: u8 = 0x01;
+
+/// Kind of conditional access messages carried on a PID.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum CaPidKind {
+    /// Entitlement control messages of a program, listed in its PMT.
+    Ecm {
+        /// Program number of the PMT listing the PID.
+        program_num: u16,
+    },
+    /// Entitlement management messages, listed in the CAT.
+    Emm,
+}
+
+/// Conditional access PID registered from a CA descriptor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CaPid {
+    /// Conditional access system.
+    pub ca_system_id: u16,
+    /// Kind of messages carried on the PID.
+    pub kind: CaPidKind,
+}
+
+/// Section carrying an ECM or EMM.
+///
+/// The message payload is specific to the conditional access system and is kept raw.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CaMessage {
+    /// Conditional access system of the PID.
+    pub ca_system_id: u16,
+    /// Kind of messages carried on the PID.
+    pub kind: CaPidKind,
+    /// Section data following the section header.
+    pub data: Vec<u8>,
+}
+
+/// Iterates the (CA system ID, CA PID) pairs of the CA descriptors in a list.
+fn ca_pids_of<'a>(descriptors: &'a [Descriptor]) -> impl Iterator<Item = (u16, u16)> + 'a {
+    descriptors
+        .iter()
+        .filter_map(|descriptor| match descriptor.parse() {
+            KnownDescriptor::Ca(ca) => Some((ca.ca_system_id, ca.ca_pid)),
+            _ => None,
+        })
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Replaces the known EMM PIDs with those of a new CAT.
+    pub(crate) fn update_emm_pids(&mut self, descriptors: &[Descriptor]) {
+        self.ca_pids
+            .retain(|_, ca_pid| ca_pid.kind != CaPidKind::Emm);
+        for (ca_system_id, pid) in ca_pids_of(descriptors) {
+            self.ca_pids.insert(
+                pid,
+                CaPid {
+                    ca_system_id,
+                    kind: CaPidKind::Emm,
+                },
+            );
+        }
+    }
+
+    /// Replaces the known ECM PIDs of a program with those of a new PMT.
+    pub(crate) fn update_ecm_pids(&mut self, program_num: u16, pmt: &Pmt) {
+        let kind = CaPidKind::Ecm { program_num };
+        self.ca_pids.retain(|_, ca_pid| ca_pid.kind != kind);
+        let es_descriptors = pmt
+            .es_infos
+            .iter()
+            .flat_map(|es_info| ca_pids_of(&es_info.es_descriptors));
+        for (ca_system_id, pid) in ca_pids_of(&pmt.program_descriptors).chain(es_descriptors) {
+            self.ca_pids.insert(pid, CaPid { ca_system_id, kind });
+        }
+    }
+
+    /// Drops the ECM PIDs of programs no longer listed in the PAT.
+    pub(crate) fn retain_ecm_pids(&mut self) {
+        let programs = &self.programs;
+        self.ca_pids.retain(|_, ca_pid| match ca_pid.kind {
+            CaPidKind::Ecm { program_num } => programs.contains_key(&program_num),
+            CaPidKind::Emm => true,
+        });
+    }
+
+    /// Conditional access PID registered from the CA descriptors of the CAT and PMTs.
+    pub fn ca_pid(&self, pid: u16) -> Option<CaPid> {
+        self.ca_pids.get(&pid).copied()
+    }
+
+    /// All conditional access PIDs registered from the CA descriptors of the CAT and PMTs.
+    pub fn ca_pids(&self) -> impl Iterator<Item = (u16, CaPid)> + '_ {
+        self.ca_pids.iter().map(|(&pid, &ca_pid)| (pid, ca_pid))
+    }
+}
+
+#[test]
+fn test_ca_pid_routing() {
+    use crate::{
+        DefaultAppDetails, MpegTsWriter, PatBuilder, Payload, PmtBuilder, Psi, PsiData, PsiHeader,
+        PsiTableSyntax,
+    };
+    use core::convert::TryInto;
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    let cat = Psi {
+        header: PsiHeader::new()
+            .with_table_id(CAT_TABLE_ID)
+            .with_section_syntax_indicator(true)
+            .with_reserved_bits(0x3),
+        table_syntax: Some(
+            PsiTableSyntax::new()
+                .with_table_id_extension(0xffff)
+                .with_reserved_bits(0x3)
+                .with_current_next_indicator(true),
+        ),
+        data: PsiData::Cat(vec![Descriptor::new_ca(0x0b00, 0x20, &[])]),
+    };
+    let pmt = PmtBuilder::new(1, 0x100)
+        .program_descriptor(Descriptor::new_ca(0x0b00, 0x30, &[]))
+        .stream(0x1b, 0x100, None)
+        .build();
+    let ecm = Psi {
+        header: PsiHeader::new()
+            .with_table_id(0x80)
+            .with_private_bit(true)
+            .with_reserved_bits(0x3),
+        table_syntax: None,
+        data: PsiData::Raw(vec![0x12, 0x34, 0x56]),
+    };
+    let pat = PatBuilder::new(1).program(1, 0x1000).build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, CAT_PID, &cat.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, 0x1000, &pmt.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, 0x30, &ecm.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+
+    let mut payloads = Vec::new();
+    for packet in out.chunks(188) {
+        payloads.push(parser.parse(packet.try_into().unwrap()).unwrap().payload);
+    }
+    match &payloads[1] {
+        Some(Payload::Psi(Psi {
+            data: PsiData::Cat(descriptors),
+            ..
+        })) => assert_eq!(descriptors.len(), 1),
+        other => panic!("unexpected payload {:?}", other),
+    }
+    assert_eq!(
+        parser.ca_pid(0x20),
+        Some(CaPid {
+            ca_system_id: 0x0b00,
+            kind: CaPidKind::Emm
+        })
+    );
+    assert_eq!(
+        parser.ca_pid(0x30).map(|ca_pid| ca_pid.kind),
+        Some(CaPidKind::Ecm { program_num: 1 })
+    );
+    match &payloads[3] {
+        Some(Payload::Psi(Psi {
+            data: PsiData::CaMessage(message),
+            ..
+        })) => {
+            assert_eq!(message.ca_system_id, 0x0b00);
+            assert_eq!(message.kind, CaPidKind::Ecm { program_num: 1 });
+            assert_eq!(message.data, [0x12, 0x34, 0x56]);
+        }
+        other => panic!("unexpected payload {:?}", other),
+    }
+}
@@ -0,0 +1,332 @@
+use super::{
+    AppDetails, DefaultAppDetails, FramedPacket, Framing, MpegTsParser, OwnedPacket, Result,
+};
+use crate::prelude::*;
+use log::warn;
+use memchr::memchr_iter;
+
+/// Push-based parser accepting data in arbitrarily sized chunks.
+///
+/// Partial packets are buffered until the rest of their data is pushed. Whenever the buffered
+/// data does not start with a sync byte (at the offset given by the [`Framing`] prefix), the
+/// parser resynchronizes: bytes are discarded up to the next sync byte that is repeated at the
+/// following packet strides (see [`ChunkedParser::set_resync_strides`]), so parsing can continue
+/// after garbage or a lost chunk. The number of discarded bytes is reported by
+/// [`ChunkedParser::skipped_bytes`].
+///
+/// Candidate sync bytes are located with a vectorized scan, so long runs of garbage are skipped
+/// quickly.
+///
+/// Parsed packets are either drained from the iterator returned by [`ChunkedParser::push`] or
+/// handed to a callback by [`ChunkedParser::push_with`], which avoids copying payload data.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{ChunkedParser, DefaultAppDetails, MpegTsParser, MpegTsWriter};
+///
+/// let mut data = Vec::new();
+/// MpegTsWriter::default().write_unit(&mut data, 0x100, &[0; 1000], None)?;
+///
+/// let mut parser = ChunkedParser::new(MpegTsParser::<DefaultAppDetails>::default());
+/// let mut count = 0;
+/// for chunk in data.chunks(100) {
+///     for packet in parser.push(chunk) {
+///         assert_eq!(packet.expect("Parse Error!").header.pid(), 0x100);
+///         count += 1;
+///     }
+/// }
+/// assert_eq!(count, data.len() / 188);
+/// assert_eq!(parser.buffered_len(), 0);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ChunkedParser<D: AppDetails = DefaultAppDetails> {
+    parser: MpegTsParser<D>,
+    framing: Framing,
+    buf: Vec<u8>,
+    start: usize,
+    discarded: usize,
+    skipped: u64,
+    resync_strides: usize,
+    resyncing: bool,
+}
+
+impl<D: AppDetails> ChunkedParser<D> {
+    /// Creates a chunked parser of plain 188-byte packets.
+    pub fn new(parser: MpegTsParser<D>) -> Self {
+        Self::with_framing(parser, Framing::PLAIN)
+    }
+
+    /// Creates a chunked parser of packets stored with the given [`Framing`].
+    pub fn with_framing(parser: MpegTsParser<D>, framing: Framing) -> Self {
+        Self {
+            parser,
+            framing,
+            buf: Vec::new(),
+            start: 0,
+            discarded: 0,
+            skipped: 0,
+            resync_strides: 1,
+            resyncing: false,
+        }
+    }
+
+    /// Sets the number of consecutive packet strides a sync byte must be found at before parsing
+    /// resumes after sync is lost (default 1).
+    ///
+    /// Higher values avoid locking onto 0x47 bytes within garbage or payload data, at the cost
+    /// of buffering that many packets before resuming.
+    pub fn set_resync_strides(&mut self, strides: usize) -> &mut Self {
+        self.resync_strides = strides.max(1);
+        self
+    }
+
+    /// Total number of bytes discarded to regain sync.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Underlying parser.
+    pub fn parser(&self) -> &MpegTsParser<D> {
+        &self.parser
+    }
+
+    /// Underlying parser.
+    pub fn parser_mut(&mut self) -> &mut MpegTsParser<D> {
+        &mut self.parser
+    }
+
+    /// Returns the underlying parser. Buffered data that was not yet parsed is lost.
+    pub fn into_parser(self) -> MpegTsParser<D> {
+        self.parser
+    }
+
+    /// Number of bytes buffered but not yet parsed.
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len() - self.start
+    }
+
+    /// Discards buffered data, e.g. after seeking the source.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+        self.discarded = 0;
+        self.resyncing = false;
+    }
+
+    /// Buffers `data` and returns an iterator over the packets completed so far.
+    ///
+    /// Packets not taken from the iterator remain buffered for the next call.
+    pub fn push(&mut self, data: &[u8]) -> ChunkedPackets<'_, D> {
+        self.extend(data);
+        ChunkedPackets { parser: self }
+    }
+
+    /// Buffers `data` and calls `f` with every packet completed so far.
+    pub fn push_with<F>(&mut self, data: &[u8], mut f: F)
+    where
+        F: FnMut(Result<FramedPacket<'_, D>, D>),
+    {
+        self.extend(data);
+        while self.align() {
+            let packet_size = self.framing.packet_size();
+            let data = &self.buf[self.start..self.start + packet_size];
+            self.start += packet_size;
+            f(self.parser.parse_framed(self.framing, data));
+        }
+    }
+
+    /// Parses the next buffered packet, if one is complete.
+    pub fn next_packet(&mut self) -> Option<Result<OwnedPacket<D>, D>> {
+        if !self.align() {
+            return None;
+        }
+        let packet_size = self.framing.packet_size();
+        let data = &self.buf[self.start..self.start + packet_size];
+        self.start += packet_size;
+        Some(
+            self.parser
+                .parse_framed(self.framing, data)
+                .map(OwnedPacket::from),
+        )
+    }
+
+    /// Appends `data`, first dropping already parsed bytes from the buffer.
+    fn extend(&mut self, data: &[u8]) {
+        self.buf.drain(..self.start);
+        self.start = 0;
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Checks for sync bytes at each resync stride of `data`.
+    /// Returns [`None`] if more data is needed to decide.
+    fn check_sync(&self, data: &[u8]) -> Option<bool> {
+        let packet_size = self.framing.packet_size();
+        for stride in 0..self.resync_strides {
+            match data.get(stride * packet_size + self.framing.prefix) {
+                Some(0x47) => {}
+                Some(_) => return Some(false),
+                None => return None,
+            }
+        }
+        Some(true)
+    }
+
+    /// Discards bytes until the buffer starts with a sync byte.
+    /// Returns `true` if a whole packet is buffered.
+    fn align(&mut self) -> bool {
+        let prefix = self.framing.prefix;
+        let remaining = &self.buf[self.start..];
+        if remaining.len() <= prefix {
+            return false;
+        }
+        if !self.resyncing && remaining[prefix] != 0x47 {
+            self.resyncing = true;
+        }
+        if self.resyncing {
+            /* Keep enough bytes to hold the prefix of the next packet if no sync byte is found */
+            let mut skip = remaining.len() - prefix;
+            let mut synced = false;
+            for i in memchr_iter(0x47, &remaining[prefix..]) {
+                match self.check_sync(&remaining[i..]) {
+                    Some(true) => {
+                        skip = i;
+                        synced = true;
+                        break;
+                    }
+                    Some(false) => {}
+                    None => {
+                        skip = i;
+                        break;
+                    }
+                }
+            }
+            self.start += skip;
+            self.discarded += skip;
+            self.skipped += skip as u64;
+            if !synced {
+                return false;
+            }
+            self.resyncing = false;
+            warn!("Discarded {} bytes to regain sync", self.discarded);
+            self.discarded = 0;
+        }
+        self.buffered_len() >= self.framing.packet_size()
+    }
+}
+
+/// Iterator over the complete packets buffered by a [`ChunkedParser`].
+pub struct ChunkedPackets<'a, D: AppDetails> {
+    parser: &'a mut ChunkedParser<D>,
+}
+
+impl<'a, D: AppDetails> Iterator for ChunkedPackets<'a, D> {
+    type Item = Result<OwnedPacket<D>, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.next_packet()
+    }
+}
+
+#[test]
+fn test_chunked_push() {
+    use crate::MpegTsWriter;
+
+    let mut data = Vec::new();
+    let mut writer = MpegTsWriter::default();
+    writer
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+    let packet_count = data.len() / 188;
+    /* Garbage between two runs of packets */
+    data.extend_from_slice(&[0x12; 50]);
+    writer
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+
+    /* Deterministic pseudo-random chunk sizes */
+    let mut seed = 12345_u32;
+    let mut chunked = ChunkedParser::<DefaultAppDetails>::new(MpegTsParser::default());
+    let mut pids = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        let len = (1 + (seed >> 16) as usize % 400).min(data.len() - pos);
+        for packet in chunked.push(&data[pos..pos + len]) {
+            pids.push(packet.unwrap().header.pid());
+        }
+        pos += len;
+    }
+    assert_eq!(pids.len(), packet_count * 2);
+    assert!(pids.iter().all(|&pid| pid == 0x100));
+    assert_eq!(chunked.buffered_len(), 0);
+
+    let mut count = 0;
+    let mut chunked = ChunkedParser::<DefaultAppDetails>::new(MpegTsParser::default());
+    for chunk in data.chunks(7) {
+        chunked.push_with(chunk, |packet| {
+            assert!(packet.is_ok());
+            count += 1;
+        });
+    }
+    assert_eq!(count, packet_count * 2);
+}
+
+#[test]
+fn test_chunked_resync() {
+    use crate::MpegTsWriter;
+
+    let mut data = Vec::new();
+    let mut writer = MpegTsWriter::default();
+    writer
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+    let packet_count = data.len() / 188;
+    /* Garbage containing a false sync byte */
+    data.extend_from_slice(&[0x12; 20]);
+    data.push(0x47);
+    data.extend_from_slice(&[0x12; 29]);
+    writer
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+
+    let mut chunked = ChunkedParser::<DefaultAppDetails>::new(MpegTsParser::default());
+    chunked.set_resync_strides(3);
+    let mut count = 0;
+    for chunk in data.chunks(50) {
+        for packet in chunked.push(chunk) {
+            assert_eq!(packet.unwrap().header.pid(), 0x100);
+            count += 1;
+        }
+    }
+    assert_eq!(count, packet_count * 2);
+    assert_eq!(chunked.skipped_bytes(), 50);
+}
+
+#[test]
+fn test_chunked_resync_scan() {
+    use crate::MpegTsWriter;
+
+    let mut data = Vec::new();
+    let mut writer = MpegTsWriter::default();
+    /* Long garbage run full of false sync bytes */
+    for i in 0..5000 {
+        data.push(if i % 3 == 1 { 0x47 } else { 0x12 });
+    }
+    writer
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+    let packet_count = (data.len() - 5000) / 188;
+
+    let mut chunked = ChunkedParser::<DefaultAppDetails>::new(MpegTsParser::default());
+    chunked.set_resync_strides(2);
+    let mut count = 0;
+    for chunk in data.chunks(1000) {
+        for packet in chunked.push(chunk) {
+            assert_eq!(packet.unwrap().header.pid(), 0x100);
+            count += 1;
+        }
+    }
+    assert_eq!(count, packet_count);
+    assert_eq!(chunked.skipped_bytes(), 5000);
+}
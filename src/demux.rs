@@ -0,0 +1,165 @@
+use super::{AppDetails, EsStreamInfo, MpegTsParser, ParserEvent, Payload, Pes, Psi};
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// Completed payload unit delivered to handlers registered with [`MpegTsParser::on_pid`].
+#[derive(Debug)]
+pub enum DemuxUnit<D> {
+    /// Complete parsed PSI payload.
+    Psi(Psi),
+    /// Complete parsed PES payload.
+    Pes(Pes<D>),
+}
+
+type PidHandler<D> = Box<dyn FnMut(u16, DemuxUnit<D>) + Send>;
+type StreamHandler = Box<dyn FnMut(u16, &EsStreamInfo) + Send>;
+
+/// Handlers registered with [`MpegTsParser::on_pid`] and [`MpegTsParser::on_new_stream`].
+pub(crate) struct DemuxHandlers<D> {
+    pids: HashMap<u16, PidHandler<D>>,
+    new_stream: Option<StreamHandler>,
+}
+
+impl<D> Default for DemuxHandlers<D> {
+    fn default() -> Self {
+        Self {
+            pids: HashMap::new(),
+            new_stream: None,
+        }
+    }
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Delivers units of `pid` to `handler` once they are complete, replacing any handler
+    /// previously registered for the PID.
+    ///
+    /// Delivered units are moved to the handler, so the [`Packet`](crate::Packet) completing
+    /// them carries no payload.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::{DefaultAppDetails, DemuxUnit, MpegTsParser};
+    /// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    /// parser.on_new_stream(|pid, info| println!("{:x}: stream type {:x}", pid, info.stream_type));
+    /// parser.on_pid(0x1011, |pid, unit| {
+    ///     if let DemuxUnit::Pes(pes) = unit {
+    ///         println!("{:x}: PES with PTS {:?}", pid, pes.pts);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_pid<F: FnMut(u16, DemuxUnit<D>) + Send + 'static>(&mut self, pid: u16, handler: F) {
+        self.demux_handlers.pids.insert(pid, Box::new(handler));
+    }
+
+    /// Removes the handler registered for `pid`, so its units are returned with their packets
+    /// again.
+    pub fn remove_pid_handler(&mut self, pid: u16) {
+        self.demux_handlers.pids.remove(&pid);
+    }
+
+    /// Calls `handler` with the PID and stream information of each elementary stream added to a
+    /// program, replacing any handler previously registered.
+    pub fn on_new_stream<F: FnMut(u16, &EsStreamInfo) + Send + 'static>(&mut self, handler: F) {
+        self.demux_handlers.new_stream = Some(Box::new(handler));
+    }
+
+    /// Hands a completed unit to its registered handler, returning the payload if there is none.
+    pub(crate) fn dispatch_payload<'a>(
+        &mut self,
+        pid: u16,
+        payload: Payload<'a, D>,
+    ) -> Option<Payload<'a, D>> {
+        let handler = match self.demux_handlers.pids.get_mut(&pid) {
+            Some(handler) => handler,
+            None => return Some(payload),
+        };
+        match payload {
+            Payload::Psi(psi) => handler(pid, DemuxUnit::Psi(psi)),
+            Payload::Pes(pes) => handler(pid, DemuxUnit::Pes(pes)),
+            payload => return Some(payload),
+        }
+        None
+    }
+
+    /// Hands a PES unit completed outside the payload of its packet to its registered handler,
+    /// returning the unit if there is none.
+    pub(crate) fn dispatch_pes(&mut self, pid: u16, pes: Pes<D>) -> Option<Pes<D>> {
+        match self.demux_handlers.pids.get_mut(&pid) {
+            Some(handler) => {
+                handler(pid, DemuxUnit::Pes(pes));
+                None
+            }
+            None => Some(pes),
+        }
+    }
+
+    /// Calls the new stream handler for streams added by `events`.
+    pub(crate) fn dispatch_new_streams(&mut self, events: &[ParserEvent]) {
+        let handler = match self.demux_handlers.new_stream.as_mut() {
+            Some(handler) => handler,
+            None => return,
+        };
+        for event in events {
+            if let ParserEvent::StreamAdded { pid, .. } = event {
+                if let Some(info) = self.es_streams.get(pid) {
+                    handler(*pid, info);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_demux_handlers() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::DefaultAppDetails;
+    use std::sync::{Arc, Mutex};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x24, 0xe0);
+    gen.add_stream(0x1100, 0x81, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(0), None, &[0; 300]);
+    gen.write_pes(0x1100, Some(3000), None, &[0; 10]);
+    gen.write_pes(0x1011, Some(3003), None, &[0; 10]);
+
+    let streams = Arc::new(Mutex::new(Vec::new()));
+    let units = Arc::new(Mutex::new(Vec::new()));
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let streams_ref = streams.clone();
+    parser
+        .on_new_stream(move |pid, info| streams_ref.lock().unwrap().push((pid, info.stream_type)));
+    let units_ref = units.clone();
+    parser.on_pid(0x1011, move |pid, unit| {
+        if let DemuxUnit::Pes(pes) = unit {
+            units_ref
+                .lock()
+                .unwrap()
+                .push((pid, pes.pts.map(u64::from)));
+        }
+    });
+
+    let mut returned = 0;
+    for packet in gen.packets() {
+        if let Some(Payload::Pes(_)) = parser.parse(packet).unwrap().payload {
+            returned += 1;
+        }
+    }
+    assert_eq!(*streams.lock().unwrap(), [(0x1011, 0x24), (0x1100, 0x81)]);
+    assert_eq!(
+        *units.lock().unwrap(),
+        [(0x1011, Some(0)), (0x1011, Some(3003))]
+    );
+    assert_eq!(returned, 1);
+}
+
+#[test]
+fn test_send() {
+    use crate::{DefaultAppDetails, OwnedPacket, Packet};
+
+    fn assert_send<T: Send>() {}
+    assert_send::<MpegTsParser<DefaultAppDetails>>();
+    assert_send::<Packet<'static, DefaultAppDetails>>();
+    assert_send::<OwnedPacket<DefaultAppDetails>>();
+}
@@ -0,0 +1,636 @@
+//! Module for typed descriptors defined by ISO 13818-1 and DVB (ETSI EN 300 468).
+//!
+//! [`Descriptor::parse`](crate::Descriptor::parse) resolves the raw tag and data of a descriptor
+//! into a [`KnownDescriptor`]. DVB descriptor types are defined in [`crate::dvb`].
+
+use crate::dvb::{
+    self, ComponentDescriptor, ContentEntry, DeliverySystem, ExtendedEventDescriptor,
+    LocalTimeOffset, ServiceDescriptor, ShortEventDescriptor, SubtitlingEntry, TeletextEntry,
+};
+use crate::prelude::*;
+use crate::{Descriptor, LanguageCode};
+use core::convert::TryInto;
+use modular_bitfield_msb::prelude::*;
+use smallvec::SmallVec;
+
+/// Tag of the video stream descriptor.
+pub const VIDEO_STREAM_DESCRIPTOR_TAG: u8 = 0x02;
+/// Tag of the audio stream descriptor.
+pub const AUDIO_STREAM_DESCRIPTOR_TAG: u8 = 0x03;
+/// Tag of the registration descriptor.
+pub const REGISTRATION_DESCRIPTOR_TAG: u8 = 0x05;
+/// Tag of the data stream alignment descriptor.
+pub const DATA_STREAM_ALIGNMENT_DESCRIPTOR_TAG: u8 = 0x06;
+/// Tag of the conditional access descriptor.
+pub const CA_DESCRIPTOR_TAG: u8 = 0x09;
+/// Tag of the ISO 639 language descriptor.
+pub const ISO_639_LANGUAGE_DESCRIPTOR_TAG: u8 = 0x0a;
+/// Tag of the maximum bitrate descriptor.
+pub const MAXIMUM_BITRATE_DESCRIPTOR_TAG: u8 = 0x0e;
+/// Tag of the AVC video descriptor.
+pub const AVC_VIDEO_DESCRIPTOR_TAG: u8 = 0x28;
+/// Tag of the Dolby Vision video stream descriptor.
+pub const DOVI_VIDEO_STREAM_DESCRIPTOR_TAG: u8 = 0xb0;
+
+/// Video stream descriptor (tag 0x02) of MPEG-1 and MPEG-2 video.
+#[derive(Debug, Copy, Clone)]
+pub struct VideoStreamDescriptor {
+    /// Multiple frame rates may be present.
+    pub multiple_frame_rate_flag: bool,
+    /// Frame rate code as in the sequence header.
+    pub frame_rate_code: u8,
+    /// The stream is MPEG-1 video only.
+    pub mpeg1_only_flag: bool,
+    /// The stream does not contain unconstrained MPEG-1 parameters.
+    pub constrained_parameter_flag: bool,
+    /// The stream contains only still pictures.
+    pub still_picture_flag: bool,
+    /// Profile and level indication, absent for MPEG-1 only streams.
+    pub profile_and_level_indication: Option<u8>,
+    /// Chroma format, absent for MPEG-1 only streams.
+    pub chroma_format: Option<u8>,
+    /// Frame rate extension flag, absent for MPEG-1 only streams.
+    pub frame_rate_extension_flag: Option<bool>,
+}
+
+impl VideoStreamDescriptor {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let flags = *data.first()?;
+        let mpeg1_only_flag = flags & 0x04 != 0;
+        let extension = if mpeg1_only_flag {
+            None
+        } else {
+            Some(data.get(1..3)?)
+        };
+        Some(Self {
+            multiple_frame_rate_flag: flags & 0x80 != 0,
+            frame_rate_code: (flags >> 3) & 0xf,
+            mpeg1_only_flag,
+            constrained_parameter_flag: flags & 0x02 != 0,
+            still_picture_flag: flags & 0x01 != 0,
+            profile_and_level_indication: extension.map(|e| e[0]),
+            chroma_format: extension.map(|e| e[1] >> 6),
+            frame_rate_extension_flag: extension.map(|e| e[1] & 0x20 != 0),
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(
+            (self.multiple_frame_rate_flag as u8) << 7
+                | (self.frame_rate_code & 0xf) << 3
+                | (self.mpeg1_only_flag as u8) << 2
+                | (self.constrained_parameter_flag as u8) << 1
+                | self.still_picture_flag as u8,
+        );
+        if !self.mpeg1_only_flag {
+            out.push(self.profile_and_level_indication.unwrap_or_default());
+            out.push(
+                self.chroma_format.unwrap_or_default() << 6
+                    | (self.frame_rate_extension_flag.unwrap_or_default() as u8) << 5
+                    | 0x1f,
+            );
+        }
+    }
+}
+
+/// Audio stream descriptor (tag 0x03) of MPEG-1 and MPEG-2 audio.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct AudioStreamDescriptor {
+    /// The stream may use free format bitrates.
+    pub free_format_flag: bool,
+    /// ID field as in the audio frame headers.
+    pub id: bool,
+    /// Highest layer used in the stream.
+    pub layer: B2,
+    /// The bitrate may change between frames.
+    pub variable_rate_audio_indicator: bool,
+    #[skip]
+    pub reserved: B3,
+}
+
+/// Registration descriptor (tag 0x05) identifying the format of private data.
+#[derive(Debug, Clone)]
+pub struct RegistrationDescriptor {
+    /// Format identifier registered with SMPTE, e.g. `HDMV` or `AC-3`.
+    pub format_identifier: [u8; 4],
+    /// Additional identification info defined by the registration owner.
+    pub additional_identification_info: Vec<u8>,
+}
+
+/// Conditional access descriptor (tag 0x09).
+#[derive(Debug, Clone)]
+pub struct CaDescriptor {
+    /// Conditional access system.
+    pub ca_system_id: u16,
+    /// PID of the ECMs (in a PMT) or EMMs (in a CAT).
+    pub ca_pid: u16,
+    /// Private data of the conditional access system.
+    pub private_data: Vec<u8>,
+}
+
+/// Entry of an ISO 639 language descriptor (tag 0x0a).
+#[derive(Debug, Copy, Clone)]
+pub struct LanguageEntry {
+    /// Language of the stream.
+    pub language: LanguageCode,
+    /// 0 = undefined, 1 = clean effects, 2 = hearing impaired, 3 = visual impaired commentary.
+    pub audio_type: u8,
+}
+
+/// AVC video descriptor (tag 0x28).
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct AvcVideoDescriptor {
+    /// Profile as in the sequence parameter set.
+    pub profile_idc: B8,
+    /// Constraint set 0 flag.
+    pub constraint_set0_flag: bool,
+    /// Constraint set 1 flag.
+    pub constraint_set1_flag: bool,
+    /// Constraint set 2 flag.
+    pub constraint_set2_flag: bool,
+    /// Constraint set 3 flag.
+    pub constraint_set3_flag: bool,
+    /// Constraint set 4 flag.
+    pub constraint_set4_flag: bool,
+    /// Constraint set 5 flag.
+    pub constraint_set5_flag: bool,
+    /// Compatibility flags as in the sequence parameter set.
+    pub avc_compatible_flags: B2,
+    /// Level as in the sequence parameter set.
+    pub level_idc: B8,
+    /// The stream may contain still pictures.
+    pub avc_still_present: bool,
+    /// The stream may contain pictures presented more than 24 hours after decoding.
+    pub avc_24_hour_picture_flag: bool,
+    /// Frame packing arrangement SEI messages are absent.
+    pub frame_packing_sei_not_present_flag: bool,
+    #[skip]
+    pub reserved: B5,
+}
+
+/// Dolby Vision video stream descriptor (tag 0xb0) carrying the decoder configuration record.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DoviConfigurationDescriptor {
+    /// Major version of the configuration record.
+    pub version_major: u8,
+    /// Minor version of the configuration record.
+    pub version_minor: u8,
+    /// Dolby Vision profile, e.g. 7 for UHD Blu-ray dual layer or 8 for single layer.
+    pub profile: u8,
+    /// Dolby Vision level.
+    pub level: u8,
+    /// The stream carries reference processing unit (RPU) metadata.
+    pub rpu_present_flag: bool,
+    /// The stream carries an enhancement layer.
+    pub el_present_flag: bool,
+    /// The stream carries a base layer.
+    pub bl_present_flag: bool,
+    /// PID of the base layer, present if the stream carries no base layer.
+    pub dependency_pid: Option<u16>,
+    /// Compatibility of the base layer with other systems, e.g. 1 for HDR10 or 2 for SDR.
+    pub bl_signal_compatibility_id: Option<u8>,
+}
+
+impl DoviConfigurationDescriptor {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let header = data.get(..4)?;
+        let bits = u16::from_be_bytes([header[2], header[3]]);
+        let bl_present_flag = bits & 0x1 != 0;
+        let (dependency_pid, rest) = if bl_present_flag {
+            (None, &data[4..])
+        } else {
+            let pid = data.get(4..6)?;
+            (Some(u16::from_be_bytes([pid[0], pid[1]]) >> 3), &data[6..])
+        };
+        Some(Self {
+            version_major: header[0],
+            version_minor: header[1],
+            profile: (bits >> 9) as u8,
+            level: (bits >> 3) as u8 & 0x3f,
+            rpu_present_flag: bits & 0x4 != 0,
+            el_present_flag: bits & 0x2 != 0,
+            bl_present_flag,
+            dependency_pid,
+            bl_signal_compatibility_id: rest.first().map(|byte| byte >> 4),
+        })
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.version_major);
+        out.push(self.version_minor);
+        out.extend_from_slice(
+            &((self.profile as u16 & 0x7f) << 9
+                | (self.level as u16 & 0x3f) << 3
+                | (self.rpu_present_flag as u16) << 2
+                | (self.el_present_flag as u16) << 1
+                | self.bl_present_flag as u16)
+                .to_be_bytes(),
+        );
+        if !self.bl_present_flag {
+            out.extend_from_slice(
+                &(self.dependency_pid.unwrap_or(0x1fff) << 3 | 0x7).to_be_bytes(),
+            );
+        }
+        if let Some(id) = self.bl_signal_compatibility_id {
+            out.push(id << 4 | 0xf);
+        }
+    }
+}
+
+/// Descriptor with its data parsed according to its tag.
+#[derive(Debug, Clone)]
+pub enum KnownDescriptor {
+    /// Video stream descriptor.
+    VideoStream(VideoStreamDescriptor),
+    /// Audio stream descriptor.
+    AudioStream(AudioStreamDescriptor),
+    /// Registration descriptor.
+    Registration(RegistrationDescriptor),
+    /// Data stream alignment descriptor carrying the alignment type.
+    DataStreamAlignment(u8),
+    /// Conditional access descriptor.
+    Ca(CaDescriptor),
+    /// ISO 639 language descriptor.
+    Iso639Language(Vec<LanguageEntry>),
+    /// Maximum bitrate descriptor carrying the bitrate in units of 50 bytes/s.
+    MaximumBitrate(u32),
+    /// AVC video descriptor.
+    AvcVideo(AvcVideoDescriptor),
+    /// Dolby Vision video stream descriptor.
+    DoviConfiguration(DoviConfigurationDescriptor),
+    /// DVB service descriptor.
+    Service(ServiceDescriptor),
+    /// DVB satellite, cable or terrestrial delivery system descriptor.
+    DeliverySystem(DeliverySystem),
+    /// DVB short event descriptor.
+    ShortEvent(ShortEventDescriptor),
+    /// DVB extended event descriptor.
+    ExtendedEvent(ExtendedEventDescriptor),
+    /// DVB component descriptor.
+    Component(ComponentDescriptor),
+    /// DVB stream identifier descriptor carrying the component tag.
+    StreamIdentifier(u8),
+    /// DVB content descriptor.
+    Content(Vec<ContentEntry>),
+    /// DVB teletext descriptor.
+    Teletext(Vec<TeletextEntry>),
+    /// DVB local time offset descriptor.
+    LocalTimeOffset(Vec<LocalTimeOffset>),
+    /// DVB subtitling descriptor.
+    Subtitling(Vec<SubtitlingEntry>),
+    /// Descriptor with an unsupported tag or malformed data.
+    Unknown(u8, SmallVec<[u8; 8]>),
+}
+
+impl KnownDescriptor {
+    /// Parses the data of a known tag. Returns [`None`] for other tags or malformed data.
+    fn parse_known(descriptor: &Descriptor) -> Option<Self> {
+        let data = descriptor.data.as_slice();
+        Some(match descriptor.tag {
+            VIDEO_STREAM_DESCRIPTOR_TAG => {
+                KnownDescriptor::VideoStream(VideoStreamDescriptor::parse(data)?)
+            }
+            AUDIO_STREAM_DESCRIPTOR_TAG => KnownDescriptor::AudioStream(
+                AudioStreamDescriptor::from_bytes(data.get(..1)?.try_into().ok()?),
+            ),
+            REGISTRATION_DESCRIPTOR_TAG => KnownDescriptor::Registration(RegistrationDescriptor {
+                format_identifier: data.get(..4)?.try_into().ok()?,
+                additional_identification_info: data[4..].to_vec(),
+            }),
+            DATA_STREAM_ALIGNMENT_DESCRIPTOR_TAG => {
+                KnownDescriptor::DataStreamAlignment(*data.first()?)
+            }
+            CA_DESCRIPTOR_TAG => {
+                let header = data.get(..4)?;
+                KnownDescriptor::Ca(CaDescriptor {
+                    ca_system_id: u16::from_be_bytes([header[0], header[1]]),
+                    ca_pid: u16::from_be_bytes([header[2], header[3]]) & 0x1fff,
+                    private_data: data[4..].to_vec(),
+                })
+            }
+            ISO_639_LANGUAGE_DESCRIPTOR_TAG => {
+                if !data.len().is_multiple_of(4) {
+                    return None;
+                }
+                KnownDescriptor::Iso639Language(
+                    data.chunks_exact(4)
+                        .map(|entry| LanguageEntry {
+                            language: LanguageCode::from_bytes([entry[0], entry[1], entry[2]]),
+                            audio_type: entry[3],
+                        })
+                        .collect(),
+                )
+            }
+            MAXIMUM_BITRATE_DESCRIPTOR_TAG => {
+                let bytes = data.get(..3)?;
+                KnownDescriptor::MaximumBitrate(
+                    u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]) & 0x3fffff,
+                )
+            }
+            AVC_VIDEO_DESCRIPTOR_TAG => KnownDescriptor::AvcVideo(AvcVideoDescriptor::from_bytes(
+                data.get(..4)?.try_into().ok()?,
+            )),
+            DOVI_VIDEO_STREAM_DESCRIPTOR_TAG => {
+                KnownDescriptor::DoviConfiguration(DoviConfigurationDescriptor::parse(data)?)
+            }
+            dvb::SERVICE_DESCRIPTOR_TAG => {
+                KnownDescriptor::Service(ServiceDescriptor::from_descriptor(descriptor)?)
+            }
+            dvb::SATELLITE_DELIVERY_DESCRIPTOR_TAG
+            | dvb::CABLE_DELIVERY_DESCRIPTOR_TAG
+            | dvb::TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG => {
+                KnownDescriptor::DeliverySystem(DeliverySystem::from_descriptor(descriptor)?)
+            }
+            dvb::SHORT_EVENT_DESCRIPTOR_TAG => {
+                KnownDescriptor::ShortEvent(ShortEventDescriptor::from_descriptor(descriptor)?)
+            }
+            dvb::EXTENDED_EVENT_DESCRIPTOR_TAG => KnownDescriptor::ExtendedEvent(
+                ExtendedEventDescriptor::from_descriptor(descriptor)?,
+            ),
+            dvb::COMPONENT_DESCRIPTOR_TAG => {
+                KnownDescriptor::Component(ComponentDescriptor::from_descriptor(descriptor)?)
+            }
+            dvb::STREAM_IDENTIFIER_DESCRIPTOR_TAG => {
+                KnownDescriptor::StreamIdentifier(dvb::stream_identifier(descriptor)?)
+            }
+            dvb::CONTENT_DESCRIPTOR_TAG => {
+                KnownDescriptor::Content(ContentEntry::from_descriptor(descriptor)?)
+            }
+            dvb::TELETEXT_DESCRIPTOR_TAG => {
+                KnownDescriptor::Teletext(TeletextEntry::from_descriptor(descriptor)?)
+            }
+            dvb::LOCAL_TIME_OFFSET_DESCRIPTOR_TAG => {
+                KnownDescriptor::LocalTimeOffset(LocalTimeOffset::from_descriptor(descriptor)?)
+            }
+            dvb::SUBTITLING_DESCRIPTOR_TAG => {
+                KnownDescriptor::Subtitling(SubtitlingEntry::from_descriptor(descriptor)?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Encodes the descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        match self {
+            KnownDescriptor::VideoStream(video) => {
+                let mut data = Vec::new();
+                video.encode(&mut data);
+                Descriptor::new(VIDEO_STREAM_DESCRIPTOR_TAG, &data)
+            }
+            KnownDescriptor::AudioStream(audio) => {
+                Descriptor::new(AUDIO_STREAM_DESCRIPTOR_TAG, &audio.into_bytes())
+            }
+            KnownDescriptor::Registration(registration) => Descriptor::new_registration(
+                registration.format_identifier,
+                &registration.additional_identification_info,
+            ),
+            KnownDescriptor::DataStreamAlignment(alignment_type) => {
+                Descriptor::new_data_stream_alignment(*alignment_type)
+            }
+            KnownDescriptor::Ca(ca) => {
+                Descriptor::new_ca(ca.ca_system_id, ca.ca_pid, &ca.private_data)
+            }
+            KnownDescriptor::Iso639Language(entries) => Descriptor::new_iso639_language(entries),
+            KnownDescriptor::MaximumBitrate(rate) => Descriptor::new_maximum_bitrate(*rate),
+            KnownDescriptor::AvcVideo(avc) => {
+                Descriptor::new(AVC_VIDEO_DESCRIPTOR_TAG, &avc.into_bytes())
+            }
+            KnownDescriptor::DoviConfiguration(dovi) => {
+                let mut data = Vec::new();
+                dovi.encode(&mut data);
+                Descriptor::new(DOVI_VIDEO_STREAM_DESCRIPTOR_TAG, &data)
+            }
+            KnownDescriptor::Service(service) => service.to_descriptor(),
+            KnownDescriptor::DeliverySystem(delivery) => delivery.to_descriptor(),
+            KnownDescriptor::ShortEvent(event) => event.to_descriptor(),
+            KnownDescriptor::ExtendedEvent(event) => event.to_descriptor(),
+            KnownDescriptor::Component(component) => component.to_descriptor(),
+            KnownDescriptor::StreamIdentifier(component_tag) => {
+                dvb::new_stream_identifier(*component_tag)
+            }
+            KnownDescriptor::Content(entries) => ContentEntry::to_descriptor(entries),
+            KnownDescriptor::Teletext(entries) => TeletextEntry::to_descriptor(entries),
+            KnownDescriptor::LocalTimeOffset(entries) => LocalTimeOffset::to_descriptor(entries),
+            KnownDescriptor::Subtitling(entries) => SubtitlingEntry::to_descriptor(entries),
+            KnownDescriptor::Unknown(tag, data) => Descriptor {
+                tag: *tag,
+                data: data.clone(),
+            },
+        }
+    }
+
+    /// Appends the tag, length, and data of the encoded descriptor to `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        self.to_descriptor().write_to(out);
+    }
+}
+
+impl Descriptor {
+    /// Creates a registration descriptor (tag 0x05).
+    pub fn new_registration(format_identifier: [u8; 4], additional_info: &[u8]) -> Self {
+        let mut descriptor = Self::new(REGISTRATION_DESCRIPTOR_TAG, &format_identifier);
+        descriptor.data.extend_from_slice(additional_info);
+        descriptor
+    }
+
+    /// Creates a data stream alignment descriptor (tag 0x06).
+    pub fn new_data_stream_alignment(alignment_type: u8) -> Self {
+        Self::new(DATA_STREAM_ALIGNMENT_DESCRIPTOR_TAG, &[alignment_type])
+    }
+
+    /// Creates a conditional access descriptor (tag 0x09).
+    pub fn new_ca(ca_system_id: u16, ca_pid: u16, private_data: &[u8]) -> Self {
+        let mut descriptor = Self::new(CA_DESCRIPTOR_TAG, &ca_system_id.to_be_bytes());
+        descriptor
+            .data
+            .extend_from_slice(&(0xe000 | ca_pid).to_be_bytes());
+        descriptor.data.extend_from_slice(private_data);
+        descriptor
+    }
+
+    /// Creates an ISO 639 language descriptor (tag 0x0a) listing `entries`.
+    pub fn new_iso639_language(entries: &[LanguageEntry]) -> Self {
+        let mut descriptor = Self::new(ISO_639_LANGUAGE_DESCRIPTOR_TAG, &[]);
+        for entry in entries.iter() {
+            descriptor.data.extend_from_slice(entry.language.as_bytes());
+            descriptor.data.push(entry.audio_type);
+        }
+        descriptor
+    }
+
+    /// Creates a maximum bitrate descriptor (tag 0x0e) from a rate in units of 50 bytes/s.
+    pub fn new_maximum_bitrate(rate: u32) -> Self {
+        Self::new(
+            MAXIMUM_BITRATE_DESCRIPTOR_TAG,
+            &(0xc00000 | rate).to_be_bytes()[1..],
+        )
+    }
+
+    /// Parses the descriptor data according to its tag.
+    ///
+    /// Descriptors with unsupported tags or malformed data are returned as
+    /// [`KnownDescriptor::Unknown`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::descriptor::KnownDescriptor;
+    /// use mpegts_io::Descriptor;
+    ///
+    /// let descriptor = Descriptor {
+    ///     tag: 0x05,
+    ///     data: b"HDMV".iter().copied().collect(),
+    /// };
+    /// match descriptor.parse() {
+    ///     KnownDescriptor::Registration(registration) => {
+    ///         assert_eq!(&registration.format_identifier, b"HDMV")
+    ///     }
+    ///     other => panic!("Unexpected descriptor {:?}", other),
+    /// }
+    /// ```
+    pub fn parse(&self) -> KnownDescriptor {
+        KnownDescriptor::parse_known(self)
+            .unwrap_or_else(|| KnownDescriptor::Unknown(self.tag, self.data.clone()))
+    }
+}
+
+#[test]
+fn test_known_descriptors() {
+    let parse = |tag: u8, data: &[u8]| {
+        Descriptor {
+            tag,
+            data: data.iter().copied().collect(),
+        }
+        .parse()
+    };
+
+    match parse(0x0a, b"eng\x00ger\x03") {
+        KnownDescriptor::Iso639Language(entries) => {
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[1].language, LanguageCode::from_bytes(*b"ger"));
+            assert_eq!(entries[1].audio_type, 3);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x09, &[0x0b, 0x00, 0xe1, 0x23, 0xaa]) {
+        KnownDescriptor::Ca(ca) => {
+            assert_eq!(ca.ca_system_id, 0x0b00);
+            assert_eq!(ca.ca_pid, 0x123);
+            assert_eq!(ca.private_data, [0xaa]);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x02, &[0x1a]) {
+        KnownDescriptor::Unknown(0x02, _) => {}
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x02, &[0x18, 0x48, 0x5f]) {
+        KnownDescriptor::VideoStream(video) => {
+            assert_eq!(video.frame_rate_code, 3);
+            assert_eq!(video.profile_and_level_indication, Some(0x48));
+            assert_eq!(video.chroma_format, Some(1));
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x28, &[0x64, 0x00, 0x28, 0x3f]) {
+        KnownDescriptor::AvcVideo(avc) => {
+            assert_eq!(avc.profile_idc(), 100);
+            assert_eq!(avc.level_idc(), 40);
+            assert!(!avc.avc_still_present());
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0xb0, &[0x01, 0x00, 0x10, 0x35, 0x1f]) {
+        KnownDescriptor::DoviConfiguration(dovi) => {
+            assert_eq!((dovi.profile, dovi.level), (8, 6));
+            assert!(dovi.rpu_present_flag && !dovi.el_present_flag && dovi.bl_present_flag);
+            assert_eq!(dovi.dependency_pid, None);
+            assert_eq!(dovi.bl_signal_compatibility_id, Some(1));
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    assert!(matches!(
+        parse(0x0e, &[0xc0, 0x4e, 0x20]),
+        KnownDescriptor::MaximumBitrate(20000)
+    ));
+    assert!(matches!(
+        parse(0x80, &[1, 2]),
+        KnownDescriptor::Unknown(0x80, _)
+    ));
+}
+
+#[test]
+fn test_descriptor_encoding() {
+    use crate::dvb::DvbTime;
+
+    let descriptors = [
+        Descriptor::new_registration(*b"HDMV", &[0xff, 0x1b, 0x61, 0x3f]),
+        Descriptor::new_iso639_language(&[LanguageEntry {
+            language: LanguageCode::from_bytes(*b"eng"),
+            audio_type: 0,
+        }]),
+        Descriptor::new_ca(0x0b00, 0x123, &[0xaa]),
+        Descriptor::new_maximum_bitrate(20000),
+        dvb::new_stream_identifier(0x21),
+        ServiceDescriptor {
+            service_type: 1,
+            provider_name: "ARD".to_string(),
+            service_name: "Das Erste HD".to_string(),
+        }
+        .to_descriptor(),
+        ShortEventDescriptor {
+            language: LanguageCode::from_bytes(*b"deu"),
+            event_name: "Tagesschau".to_string(),
+            text: "Nachrichten\nmit Wetter — live".to_string(),
+        }
+        .to_descriptor(),
+        LocalTimeOffset::to_descriptor(&[LocalTimeOffset {
+            country_code: *b"DEU",
+            country_region_id: 0,
+            offset_minutes: -90,
+            time_of_change: Some(DvbTime {
+                mjd: 60000,
+                hour: 1,
+                minute: 0,
+                second: 0,
+            }),
+            next_offset_minutes: 120,
+        }]),
+        Descriptor::new(0xb0, &[0x01, 0x00, 0x0e, 0x36, 0x80, 0x8f, 0x6f]),
+    ];
+
+    /* Typed descriptors encode back to the same bytes */
+    for descriptor in descriptors.iter() {
+        let known = descriptor.parse();
+        assert!(
+            !matches!(known, KnownDescriptor::Unknown(..)),
+            "{:?}",
+            known
+        );
+        let mut expected = Vec::new();
+        descriptor.write_to(&mut expected);
+        let mut out = Vec::new();
+        known.write_to(&mut out);
+        assert_eq!(out, expected);
+    }
+
+    assert_eq!(
+        descriptors[2].data.as_slice(),
+        [0x0b, 0x00, 0xe1, 0x23, 0xaa]
+    );
+    match descriptors[6].parse() {
+        KnownDescriptor::ShortEvent(event) => {
+            assert_eq!(event.text, "Nachrichten\nmit Wetter — live")
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match descriptors[7].parse() {
+        KnownDescriptor::LocalTimeOffset(entries) => {
+            assert_eq!(entries[0].offset_minutes, -90);
+            assert_eq!(entries[0].time_of_change.unwrap().mjd, 60000);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+}
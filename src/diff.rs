@@ -0,0 +1,166 @@
+use super::psi::Pmt;
+use super::Descriptor;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+
+/// Descriptors present in only one of two descriptor loops.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DescriptorDiff {
+    /// Descriptors only present in the new loop.
+    pub added: Vec<Descriptor>,
+    /// Descriptors only present in the old loop.
+    pub removed: Vec<Descriptor>,
+}
+
+impl DescriptorDiff {
+    /// Compares two descriptor loops, ignoring the order of descriptors.
+    pub fn between(old: &[Descriptor], new: &[Descriptor]) -> Self {
+        Self {
+            added: new.iter().filter(|d| !old.contains(d)).cloned().collect(),
+            removed: old.iter().filter(|d| !new.contains(d)).cloned().collect(),
+        }
+    }
+
+    /// Checks whether both loops contain the same descriptors.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Changes of the program list between two PATs, reported by
+/// [`ParserEvent::ProgramsChanged`](crate::ParserEvent::ProgramsChanged).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PatDiff {
+    /// Added programs as (program number, PMT PID) pairs.
+    pub programs_added: Vec<(u16, u16)>,
+    /// Removed programs as (program number, PMT PID) pairs.
+    pub programs_removed: Vec<(u16, u16)>,
+    /// Programs whose PMT moved as (program number, old PMT PID, new PMT PID).
+    pub pmt_pid_changes: Vec<(u16, u16, u16)>,
+}
+
+impl PatDiff {
+    /// Compares two program lists mapping program numbers to PMT PIDs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::PatDiff;
+    /// let old = [(1, 0x100), (2, 0x200)].iter().copied().collect();
+    /// let new = [(1, 0x101), (3, 0x300)].iter().copied().collect();
+    /// let diff = PatDiff::between(&old, &new);
+    /// assert_eq!(diff.programs_added, [(3, 0x300)]);
+    /// assert_eq!(diff.programs_removed, [(2, 0x200)]);
+    /// assert_eq!(diff.pmt_pid_changes, [(1, 0x100, 0x101)]);
+    /// ```
+    pub fn between(old: &BTreeMap<u16, u16>, new: &BTreeMap<u16, u16>) -> Self {
+        let mut diff = Self::default();
+        for (&program_num, &pmt_pid) in new.iter() {
+            match old.get(&program_num) {
+                None => diff.programs_added.push((program_num, pmt_pid)),
+                Some(&old_pid) if old_pid != pmt_pid => {
+                    diff.pmt_pid_changes.push((program_num, old_pid, pmt_pid))
+                }
+                Some(_) => {}
+            }
+        }
+        for (&program_num, &pmt_pid) in old.iter() {
+            if !new.contains_key(&program_num) {
+                diff.programs_removed.push((program_num, pmt_pid));
+            }
+        }
+        diff
+    }
+
+    /// Checks whether both program lists are equal.
+    pub fn is_empty(&self) -> bool {
+        self.programs_added.is_empty()
+            && self.programs_removed.is_empty()
+            && self.pmt_pid_changes.is_empty()
+    }
+}
+
+/// Changes of a program between two PMTs, reported by
+/// [`ParserEvent::PmtChanged`](crate::ParserEvent::PmtChanged).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PmtDiff {
+    /// Changed PCR PID as (old PID, new PID).
+    pub pcr_pid: Option<(u16, u16)>,
+    /// Changes of the program descriptors.
+    pub program_descriptors: DescriptorDiff,
+    /// Added elementary streams as (PID, stream type) pairs.
+    pub streams_added: Vec<(u16, u8)>,
+    /// Removed elementary streams as (PID, stream type) pairs.
+    pub streams_removed: Vec<(u16, u8)>,
+    /// Streams whose type changed as (PID, old stream type, new stream type).
+    pub stream_type_changes: Vec<(u16, u8, u8)>,
+    /// Changes of the descriptors of streams present in both PMTs, by PID.
+    pub es_descriptors: Vec<(u16, DescriptorDiff)>,
+}
+
+impl PmtDiff {
+    /// Compares a PMT against the previous PMT of the program, if any.
+    pub fn between(old: Option<&Pmt>, new: &Pmt) -> Self {
+        let mut diff = Self {
+            pcr_pid: old
+                .map(|old| old.header.pcr_pid())
+                .filter(|old_pid| *old_pid != new.header.pcr_pid())
+                .map(|old_pid| (old_pid, new.header.pcr_pid())),
+            program_descriptors: DescriptorDiff::between(
+                old.map_or(&[], |old| &old.program_descriptors),
+                &new.program_descriptors,
+            ),
+            ..Self::default()
+        };
+        let old_infos = old.map_or(&[][..], |old| &old.es_infos);
+        for info in new.es_infos.iter() {
+            let pid = info.header.elementary_pid();
+            let stream_type = info.header.stream_type().into();
+            let old_info = old_infos
+                .iter()
+                .find(|old_info| old_info.header.elementary_pid() == pid);
+            let old_info = match old_info {
+                Some(old_info) => old_info,
+                None => {
+                    diff.streams_added.push((pid, stream_type));
+                    continue;
+                }
+            };
+            let old_stream_type = old_info.header.stream_type().into();
+            if old_stream_type != stream_type {
+                diff.stream_type_changes
+                    .push((pid, old_stream_type, stream_type));
+            }
+            let descriptors =
+                DescriptorDiff::between(&old_info.es_descriptors, &info.es_descriptors);
+            if !descriptors.is_empty() {
+                diff.es_descriptors.push((pid, descriptors));
+            }
+        }
+        for old_info in old_infos.iter() {
+            let pid = old_info.header.elementary_pid();
+            if !new
+                .es_infos
+                .iter()
+                .any(|info| info.header.elementary_pid() == pid)
+            {
+                diff.streams_removed
+                    .push((pid, old_info.header.stream_type().into()));
+            }
+        }
+        diff
+    }
+
+    /// Checks whether both PMTs describe the same program.
+    pub fn is_empty(&self) -> bool {
+        self.pcr_pid.is_none()
+            && self.program_descriptors.is_empty()
+            && self.streams_added.is_empty()
+            && self.streams_removed.is_empty()
+            && self.stream_type_changes.is_empty()
+            && self.es_descriptors.is_empty()
+    }
+}
@@ -0,0 +1,871 @@
+//! Module for DSM-CC sections (ISO/IEC 13818-6) and the data and object carousels carried in
+//! them.
+//!
+//! Sections with table IDs 0x3A to 0x3E are returned as
+//! [`PsiData::Dsmcc`](crate::PsiData::Dsmcc) on PIDs listed with a DSM-CC stream type in the PMT.
+//! [`CarouselCollector`] reassembles the download data blocks into modules, and the BIOP objects
+//! of object carousels into files.
+
+use crate::prelude::*;
+use crate::{
+    AppDetails, DefaultAppDetails, Descriptor, ErrorDetails, Packet, Payload, Psi, PsiData, Result,
+    SliceReader,
+};
+use alloc::collections::BTreeMap;
+use hashbrown::HashMap;
+use log::warn;
+
+/// Table ID of sections carrying multiprotocol encapsulated data.
+pub const MPE_TABLE_ID: u8 = 0x3a;
+/// Table ID of sections carrying user-to-network messages (DSI and DII).
+pub const UN_MESSAGE_TABLE_ID: u8 = 0x3b;
+/// Table ID of sections carrying download data messages (DDB).
+pub const DOWNLOAD_DATA_TABLE_ID: u8 = 0x3c;
+/// Table ID of sections carrying stream descriptors.
+pub const STREAM_DESCRIPTORS_TABLE_ID: u8 = 0x3d;
+/// Table ID of sections carrying private data.
+pub const PRIVATE_DATA_TABLE_ID: u8 = 0x3e;
+
+/// Message ID of the DownloadInfoIndication.
+pub const DII_MESSAGE_ID: u16 = 0x1002;
+/// Message ID of the DownloadDataBlock.
+pub const DDB_MESSAGE_ID: u16 = 0x1003;
+/// Message ID of the DownloadServerInitiate.
+pub const DSI_MESSAGE_ID: u16 = 0x1006;
+
+/// Tag of the compressed module descriptor in the module info of a DII.
+const COMPRESSED_MODULE_DESCRIPTOR_TAG: u8 = 0x09;
+
+/// Checks whether a stream type of the PMT denotes DSM-CC sections.
+pub(crate) fn is_dsmcc_stream_type(stream_type: u8) -> bool {
+    (0x0a..=0x0d).contains(&stream_type)
+}
+
+/// Checks whether a table ID is of a DSM-CC section.
+pub(crate) fn is_dsmcc_table_id(table_id: u8) -> bool {
+    (MPE_TABLE_ID..=PRIVATE_DATA_TABLE_ID).contains(&table_id)
+}
+
+/// Reads a field preceded by a 16-bit length.
+fn read_length_prefixed<'a, D: AppDetails>(reader: &mut SliceReader<'a, D>) -> Result<&'a [u8], D> {
+    let length = reader.read_be_u16()?;
+    reader.read(length as usize)
+}
+
+/// Appends a field preceded by a 16-bit length.
+fn write_length_prefixed(field: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(field.len() as u16).to_be_bytes());
+    out.extend_from_slice(field);
+}
+
+/// Header of a DSM-CC download message.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DsmccMessageHeader {
+    /// Message ID, e.g. [`DII_MESSAGE_ID`].
+    pub message_id: u16,
+    /// Transaction ID of control messages, or download ID of download data messages.
+    pub transaction_id: u32,
+    /// Adaptation header.
+    pub adaptation: Vec<u8>,
+}
+
+impl DsmccMessageHeader {
+    /// Reads the header and returns it along with a reader over the message body.
+    /// Returns [`None`] for messages other than U-N download messages.
+    fn parse<'a, D: AppDetails>(
+        reader: &mut SliceReader<'a, D>,
+    ) -> Result<Option<(Self, SliceReader<'a, D>)>, D> {
+        let protocol_discriminator = reader.read_u8()?;
+        let dsmcc_type = reader.read_u8()?;
+        if protocol_discriminator != 0x11 || dsmcc_type != 0x03 {
+            return Ok(None);
+        }
+        let message_id = reader.read_be_u16()?;
+        let transaction_id = reader.read_be_u32()?;
+        reader.skip(1)?;
+        let adaptation_length = reader.read_u8()?;
+        let message_length = reader.read_be_u16()?;
+        let mut body = reader.new_sub_reader(message_length as usize)?;
+        let adaptation = body.read(adaptation_length as usize)?.to_vec();
+        Ok(Some((
+            Self {
+                message_id,
+                transaction_id,
+                adaptation,
+            },
+            body,
+        )))
+    }
+
+    fn encode(&self, body: &[u8], out: &mut Vec<u8>) {
+        out.extend_from_slice(&[0x11, 0x03]);
+        out.extend_from_slice(&self.message_id.to_be_bytes());
+        out.extend_from_slice(&self.transaction_id.to_be_bytes());
+        out.push(0xff);
+        out.push(self.adaptation.len() as u8);
+        out.extend_from_slice(&((self.adaptation.len() + body.len()) as u16).to_be_bytes());
+        out.extend_from_slice(&self.adaptation);
+        out.extend_from_slice(body);
+    }
+}
+
+/// DownloadServerInitiate message announcing the carousel.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Dsi {
+    /// Message header.
+    pub header: DsmccMessageHeader,
+    /// Server ID, all bits set in broadcast carousels.
+    pub server_id: [u8; 20],
+    /// Compatibility descriptor.
+    pub compatibility_descriptor: Vec<u8>,
+    /// Private data, carrying the service gateway info in object carousels.
+    pub private_data: Vec<u8>,
+}
+
+/// Module announced by a [`Dii`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DiiModule {
+    /// Module ID.
+    pub module_id: u16,
+    /// Size of the module in bytes.
+    pub module_size: u32,
+    /// Version of the module.
+    pub module_version: u8,
+    /// Module info, a BIOP::ModuleInfo structure in object carousels.
+    pub module_info: Vec<u8>,
+}
+
+impl DiiModule {
+    /// Checks for a compressed module descriptor in the BIOP::ModuleInfo of the module.
+    pub fn is_compressed(&self) -> bool {
+        let mut reader = SliceReader::<DefaultAppDetails>::new(&self.module_info);
+        let mut find = || -> Result<bool, DefaultAppDetails> {
+            /* Skip moduleTimeOut, blockTimeOut and minBlockTime */
+            reader.skip(12)?;
+            let taps_count = reader.read_u8()?;
+            for _ in 0..taps_count {
+                reader.skip(6)?;
+                let selector_length = reader.read_u8()?;
+                reader.skip(selector_length as usize)?;
+            }
+            let user_info_length = reader.read_u8()?;
+            let mut user_info = reader.new_sub_reader(user_info_length as usize)?;
+            while user_info.remaining_len() > 0 {
+                if Descriptor::new_from_reader(&mut user_info)?.tag
+                    == COMPRESSED_MODULE_DESCRIPTOR_TAG
+                {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        };
+        find().unwrap_or(false)
+    }
+}
+
+/// DownloadInfoIndication message listing the modules of a carousel.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Dii {
+    /// Message header.
+    pub header: DsmccMessageHeader,
+    /// Download ID matching the DDBs of the modules.
+    pub download_id: u32,
+    /// Size of the data of all but the last block of a module.
+    pub block_size: u16,
+    /// Window size (unused in broadcast carousels).
+    pub window_size: u8,
+    /// Acknowledgement period (unused in broadcast carousels).
+    pub ack_period: u8,
+    /// Download window timeout (unused in broadcast carousels).
+    pub t_c_download_window: u32,
+    /// Download scenario timeout in microseconds.
+    pub t_c_download_scenario: u32,
+    /// Compatibility descriptor.
+    pub compatibility_descriptor: Vec<u8>,
+    /// Announced modules.
+    pub modules: Vec<DiiModule>,
+    /// Private data.
+    pub private_data: Vec<u8>,
+}
+
+/// DownloadDataBlock message carrying one block of a module.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Ddb {
+    /// Message header, carrying the download ID as transaction ID.
+    pub header: DsmccMessageHeader,
+    /// Module ID.
+    pub module_id: u16,
+    /// Version of the module.
+    pub module_version: u8,
+    /// Index of the block within the module.
+    pub block_number: u16,
+    /// Block data.
+    pub block_data: Vec<u8>,
+}
+
+/// Parsed DSM-CC section.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DsmccSection {
+    /// DownloadServerInitiate message.
+    Dsi(Dsi),
+    /// DownloadInfoIndication message.
+    Dii(Dii),
+    /// DownloadDataBlock message.
+    Ddb(Ddb),
+    /// Stream descriptors.
+    StreamDescriptors(Vec<Descriptor>),
+    /// Multiprotocol encapsulated data, private data, or an unsupported message.
+    Data(Vec<u8>),
+}
+
+impl DsmccSection {
+    pub(crate) fn parse<D: AppDetails>(
+        table_id: u8,
+        reader: &mut SliceReader<D>,
+    ) -> Result<Self, D> {
+        match table_id {
+            UN_MESSAGE_TABLE_ID | DOWNLOAD_DATA_TABLE_ID => {
+                let data = reader.peek(reader.remaining_len())?;
+                let (header, mut body) = match DsmccMessageHeader::parse(reader)? {
+                    Some(message) => message,
+                    None => return Ok(DsmccSection::Data(data.to_vec())),
+                };
+                Ok(match header.message_id {
+                    DSI_MESSAGE_ID => DsmccSection::Dsi(Dsi {
+                        server_id: *body.read_array_ref::<20>()?,
+                        compatibility_descriptor: read_length_prefixed(&mut body)?.to_vec(),
+                        private_data: read_length_prefixed(&mut body)?.to_vec(),
+                        header,
+                    }),
+                    DII_MESSAGE_ID => {
+                        let download_id = body.read_be_u32()?;
+                        let block_size = body.read_be_u16()?;
+                        let window_size = body.read_u8()?;
+                        let ack_period = body.read_u8()?;
+                        let t_c_download_window = body.read_be_u32()?;
+                        let t_c_download_scenario = body.read_be_u32()?;
+                        let compatibility_descriptor = read_length_prefixed(&mut body)?.to_vec();
+                        let module_count = body.read_be_u16()?;
+                        let mut modules = Vec::with_capacity(module_count as usize);
+                        for _ in 0..module_count {
+                            let module_id = body.read_be_u16()?;
+                            let module_size = body.read_be_u32()?;
+                            let module_version = body.read_u8()?;
+                            let module_info_length = body.read_u8()?;
+                            modules.push(DiiModule {
+                                module_id,
+                                module_size,
+                                module_version,
+                                module_info: body.read(module_info_length as usize)?.to_vec(),
+                            });
+                        }
+                        DsmccSection::Dii(Dii {
+                            header,
+                            download_id,
+                            block_size,
+                            window_size,
+                            ack_period,
+                            t_c_download_window,
+                            t_c_download_scenario,
+                            compatibility_descriptor,
+                            modules,
+                            private_data: read_length_prefixed(&mut body)?.to_vec(),
+                        })
+                    }
+                    DDB_MESSAGE_ID => {
+                        let module_id = body.read_be_u16()?;
+                        let module_version = body.read_u8()?;
+                        body.skip(1)?;
+                        DsmccSection::Ddb(Ddb {
+                            header,
+                            module_id,
+                            module_version,
+                            block_number: body.read_be_u16()?,
+                            block_data: body.read_to_end()?.to_vec(),
+                        })
+                    }
+                    _ => DsmccSection::Data(data.to_vec()),
+                })
+            }
+            STREAM_DESCRIPTORS_TABLE_ID => {
+                let mut descriptors = Vec::new();
+                while reader.remaining_len() > 0 {
+                    descriptors.push(Descriptor::new_from_reader(reader)?);
+                }
+                Ok(DsmccSection::StreamDescriptors(descriptors))
+            }
+            _ => Ok(DsmccSection::Data(reader.read_to_end()?.to_vec())),
+        }
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        let mut body = Vec::new();
+        match self {
+            DsmccSection::Dsi(dsi) => {
+                body.extend_from_slice(&dsi.server_id);
+                write_length_prefixed(&dsi.compatibility_descriptor, &mut body);
+                write_length_prefixed(&dsi.private_data, &mut body);
+                dsi.header.encode(&body, out);
+            }
+            DsmccSection::Dii(dii) => {
+                body.extend_from_slice(&dii.download_id.to_be_bytes());
+                body.extend_from_slice(&dii.block_size.to_be_bytes());
+                body.push(dii.window_size);
+                body.push(dii.ack_period);
+                body.extend_from_slice(&dii.t_c_download_window.to_be_bytes());
+                body.extend_from_slice(&dii.t_c_download_scenario.to_be_bytes());
+                write_length_prefixed(&dii.compatibility_descriptor, &mut body);
+                body.extend_from_slice(&(dii.modules.len() as u16).to_be_bytes());
+                for module in dii.modules.iter() {
+                    body.extend_from_slice(&module.module_id.to_be_bytes());
+                    body.extend_from_slice(&module.module_size.to_be_bytes());
+                    body.push(module.module_version);
+                    body.push(module.module_info.len() as u8);
+                    body.extend_from_slice(&module.module_info);
+                }
+                write_length_prefixed(&dii.private_data, &mut body);
+                dii.header.encode(&body, out);
+            }
+            DsmccSection::Ddb(ddb) => {
+                body.extend_from_slice(&ddb.module_id.to_be_bytes());
+                body.push(ddb.module_version);
+                body.push(0xff);
+                body.extend_from_slice(&ddb.block_number.to_be_bytes());
+                body.extend_from_slice(&ddb.block_data);
+                ddb.header.encode(&body, out);
+            }
+            DsmccSection::StreamDescriptors(descriptors) => {
+                for descriptor in descriptors.iter() {
+                    descriptor.write_to(out);
+                }
+            }
+            DsmccSection::Data(data) => out.extend_from_slice(data),
+        }
+    }
+}
+
+/// Binding of a name within a BIOP directory to an object.
+#[derive(Debug, Clone)]
+pub struct BiopBinding {
+    /// Name of the object.
+    pub name: String,
+    /// Kind of the object, e.g. `fil` or `dir`.
+    pub kind: String,
+    /// Module carrying the object.
+    pub module_id: u16,
+    /// Key of the object within the module.
+    pub object_key: Vec<u8>,
+}
+
+/// Body of a BIOP object.
+#[derive(Debug, Clone)]
+pub enum BiopBody {
+    /// File content.
+    File(Vec<u8>),
+    /// Bindings of a directory or service gateway.
+    Directory(Vec<BiopBinding>),
+    /// Body of other objects, e.g. streams and stream events.
+    Other(Vec<u8>),
+}
+
+/// Object of an object carousel.
+#[derive(Debug, Clone)]
+pub struct BiopObject {
+    /// Key of the object within its module.
+    pub object_key: Vec<u8>,
+    /// Kind of the object: `fil`, `dir`, `srg` (service gateway), `str` or `ste`.
+    pub kind: String,
+    /// Object body.
+    pub body: BiopBody,
+}
+
+/// Decodes a NUL terminated BIOP string.
+fn biop_string(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+/// Reads an IOR and returns the module ID and object key of its BIOP::ObjectLocation.
+fn read_ior<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Option<(u16, Vec<u8>)>, D> {
+    let type_id_length = reader.read_be_u32()?;
+    reader.skip(type_id_length as usize)?;
+    let profile_count = reader.read_be_u32()?;
+    let mut location = None;
+    for _ in 0..profile_count {
+        let profile_id_tag = reader.read_be_u32()?;
+        let profile_data_length = reader.read_be_u32()?;
+        let mut profile = reader.new_sub_reader(profile_data_length as usize)?;
+        /* TAG_BIOP profile body */
+        if profile_id_tag != 0x4953_4f06 {
+            continue;
+        }
+        profile.skip(1)?;
+        let component_count = profile.read_u8()?;
+        for _ in 0..component_count {
+            let component_id_tag = profile.read_be_u32()?;
+            let component_data_length = profile.read_u8()?;
+            let mut component = profile.new_sub_reader(component_data_length as usize)?;
+            /* TAG_ObjectLocation */
+            if component_id_tag == 0x4953_4f50 {
+                component.skip(4)?;
+                let module_id = component.read_be_u16()?;
+                component.skip(2)?;
+                let object_key_length = component.read_u8()?;
+                location = Some((
+                    module_id,
+                    component.read(object_key_length as usize)?.to_vec(),
+                ));
+            }
+        }
+    }
+    Ok(location)
+}
+
+impl BiopObject {
+    /// Parses all BIOP messages of a module.
+    fn parse_module<D: AppDetails>(data: &[u8]) -> Result<Vec<Self>, D> {
+        let mut reader = SliceReader::<D>::new(data);
+        let mut objects = Vec::new();
+        while reader.remaining_len() > 0 {
+            if reader.read(4)? != b"BIOP" {
+                warn!("Bad BIOP message magic");
+                return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
+            }
+            /* Skip version, byte order and message type */
+            reader.skip(4)?;
+            let message_size = reader.read_be_u32()?;
+            let mut message = reader.new_sub_reader(message_size as usize)?;
+            let object_key_length = message.read_u8()?;
+            let object_key = message.read(object_key_length as usize)?.to_vec();
+            let kind_length = message.read_be_u32()?;
+            let kind = biop_string(message.read(kind_length as usize)?);
+            read_length_prefixed(&mut message)?;
+            let context_count = message.read_u8()?;
+            for _ in 0..context_count {
+                message.skip(4)?;
+                read_length_prefixed(&mut message)?;
+            }
+            let body_length = message.read_be_u32()?;
+            let mut body = message.new_sub_reader(body_length as usize)?;
+            let body = match kind.as_str() {
+                "fil" => {
+                    let content_length = body.read_be_u32()?;
+                    BiopBody::File(body.read(content_length as usize)?.to_vec())
+                }
+                "dir" | "srg" => {
+                    let binding_count = body.read_be_u16()?;
+                    let mut bindings = Vec::with_capacity(binding_count as usize);
+                    for _ in 0..binding_count {
+                        let mut name = String::new();
+                        let mut binding_kind = String::new();
+                        let component_count = body.read_u8()?;
+                        for _ in 0..component_count {
+                            let id_length = body.read_u8()?;
+                            name = biop_string(body.read(id_length as usize)?);
+                            let kind_length = body.read_u8()?;
+                            binding_kind = biop_string(body.read(kind_length as usize)?);
+                        }
+                        /* Skip the binding type */
+                        body.skip(1)?;
+                        let location = read_ior(&mut body)?;
+                        read_length_prefixed(&mut body)?;
+                        if let Some((module_id, object_key)) = location {
+                            bindings.push(BiopBinding {
+                                name,
+                                kind: binding_kind,
+                                module_id,
+                                object_key,
+                            });
+                        }
+                    }
+                    BiopBody::Directory(bindings)
+                }
+                _ => BiopBody::Other(body.read_to_end()?.to_vec()),
+            };
+            objects.push(BiopObject {
+                object_key,
+                kind,
+                body,
+            });
+        }
+        Ok(objects)
+    }
+}
+
+/// Module reassembled from the blocks of a carousel.
+#[derive(Debug, Clone)]
+pub struct CarouselModule {
+    /// Download ID of the carousel.
+    pub download_id: u32,
+    /// Module ID.
+    pub module_id: u16,
+    /// Version of the module.
+    pub version: u8,
+    /// The module data is compressed with zlib and is not parsed for objects.
+    pub compressed: bool,
+    /// Module data.
+    pub data: Vec<u8>,
+}
+
+/// File of an object carousel with its path from the service gateway.
+#[derive(Debug, Clone)]
+pub struct CarouselFile<'a> {
+    /// Path of the file, with components separated by `/`.
+    pub path: String,
+    /// File content.
+    pub data: &'a [u8],
+}
+
+/// Module announced by a DII whose blocks are being collected.
+#[derive(Debug)]
+struct PendingModule {
+    version: u8,
+    size: usize,
+    block_size: usize,
+    compressed: bool,
+    blocks: BTreeMap<u16, Vec<u8>>,
+}
+
+impl PendingModule {
+    fn block_count(&self) -> usize {
+        if self.block_size == 0 {
+            0
+        } else {
+            self.size.div_ceil(self.block_size)
+        }
+    }
+}
+
+/// Reassembles the modules of DSM-CC data and object carousels, and the files of object
+/// carousels.
+///
+/// Modules are collected once announced by a DII; blocks received before the DII are ignored
+/// and picked up when the carousel repeats. A module announced with a new version replaces the
+/// previous one.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::dsmcc::CarouselCollector;
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+///
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let mut carousel = CarouselCollector::default();
+/// # let packets: Vec<[u8; 188]> = Vec::new();
+/// for packet in packets.iter() {
+///     let packet = parser.parse(packet).expect("Parse Error!");
+///     if let Some(module) = carousel.add_packet(&packet) {
+///         println!("Module {} complete", module.module_id);
+///     }
+/// }
+/// for file in carousel.files() {
+///     println!("{}: {} bytes", file.path, file.data.len());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CarouselCollector {
+    pending: HashMap<(u32, u16), PendingModule>,
+    modules: HashMap<(u32, u16), CarouselModule>,
+    objects: HashMap<(u16, Vec<u8>), BiopObject>,
+}
+
+impl CarouselCollector {
+    /// Adds a parsed PSI section. Returns the module completed by the section, if any.
+    pub fn add_psi(&mut self, psi: &Psi) -> Option<&CarouselModule> {
+        match &psi.data {
+            PsiData::Dsmcc(DsmccSection::Dii(dii)) => {
+                self.add_dii(dii);
+                None
+            }
+            PsiData::Dsmcc(DsmccSection::Ddb(ddb)) => self.add_ddb(ddb),
+            _ => None,
+        }
+    }
+
+    /// Adds the payload of a parsed packet. Returns the module completed by the packet, if any.
+    pub fn add_packet<D: AppDetails>(&mut self, packet: &Packet<D>) -> Option<&CarouselModule> {
+        match packet.payload.as_ref() {
+            Some(Payload::Psi(psi)) => self.add_psi(psi),
+            _ => None,
+        }
+    }
+
+    fn add_dii(&mut self, dii: &Dii) {
+        for module in dii.modules.iter() {
+            let key = (dii.download_id, module.module_id);
+            let known = self
+                .modules
+                .get(&key)
+                .map(|m| m.version)
+                .or_else(|| self.pending.get(&key).map(|m| m.version));
+            if known == Some(module.module_version) {
+                continue;
+            }
+            self.pending.insert(
+                key,
+                PendingModule {
+                    version: module.module_version,
+                    size: module.module_size as usize,
+                    block_size: dii.block_size as usize,
+                    compressed: module.is_compressed(),
+                    blocks: BTreeMap::new(),
+                },
+            );
+            /* Complete modules are replaced once the new version is complete */
+            self.complete_if_ready(key);
+        }
+    }
+
+    fn add_ddb(&mut self, ddb: &Ddb) -> Option<&CarouselModule> {
+        let key = (ddb.header.transaction_id, ddb.module_id);
+        let pending = self.pending.get_mut(&key)?;
+        if pending.version != ddb.module_version
+            || ddb.block_number as usize >= pending.block_count()
+        {
+            return None;
+        }
+        pending
+            .blocks
+            .insert(ddb.block_number, ddb.block_data.clone());
+        if self.complete_if_ready(key) {
+            self.modules.get(&key)
+        } else {
+            None
+        }
+    }
+
+    /// Moves a pending module with all blocks received to the complete modules.
+    fn complete_if_ready(&mut self, key: (u32, u16)) -> bool {
+        match self.pending.get(&key) {
+            Some(pending) if pending.blocks.len() == pending.block_count() => {}
+            _ => return false,
+        }
+        let pending = self.pending.remove(&key).unwrap();
+        let mut data: Vec<u8> = pending.blocks.into_values().flatten().collect();
+        if data.len() != pending.size {
+            warn!(
+                "Carousel module {} has size {} instead of {}",
+                key.1,
+                data.len(),
+                pending.size
+            );
+            data.resize(pending.size, 0);
+        }
+        let module_id = key.1;
+        self.objects.retain(|(id, _), _| *id != module_id);
+        if !pending.compressed {
+            match BiopObject::parse_module::<DefaultAppDetails>(&data) {
+                Ok(objects) => {
+                    for object in objects {
+                        self.objects
+                            .insert((module_id, object.object_key.clone()), object);
+                    }
+                }
+                Err(e) => warn!(
+                    "Carousel module {} is not a BIOP module: {:?}",
+                    module_id, e
+                ),
+            }
+        }
+        self.modules.insert(
+            key,
+            CarouselModule {
+                download_id: key.0,
+                module_id,
+                version: pending.version,
+                compressed: pending.compressed,
+                data,
+            },
+        );
+        true
+    }
+
+    /// Complete module of a carousel.
+    pub fn module(&self, download_id: u32, module_id: u16) -> Option<&CarouselModule> {
+        self.modules.get(&(download_id, module_id))
+    }
+
+    /// All complete modules.
+    pub fn modules(&self) -> impl Iterator<Item = &CarouselModule> {
+        self.modules.values()
+    }
+
+    /// Object of an object carousel.
+    pub fn object(&self, module_id: u16, object_key: &[u8]) -> Option<&BiopObject> {
+        self.objects.get(&(module_id, object_key.to_vec()))
+    }
+
+    /// Files reachable from the service gateway of an object carousel, sorted by path.
+    ///
+    /// Files in modules that are not complete yet are omitted.
+    pub fn files(&self) -> Vec<CarouselFile<'_>> {
+        let mut files = Vec::new();
+        if let Some(gateway) = self.objects.values().find(|o| o.kind == "srg") {
+            self.collect_files(gateway, "", 0, &mut files);
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    fn collect_files<'a>(
+        &'a self,
+        directory: &'a BiopObject,
+        prefix: &str,
+        depth: usize,
+        files: &mut Vec<CarouselFile<'a>>,
+    ) {
+        let bindings = match &directory.body {
+            /* Guard against directory cycles */
+            BiopBody::Directory(bindings) if depth < 32 => bindings,
+            _ => return,
+        };
+        for binding in bindings.iter() {
+            let path = format!("{}/{}", prefix, binding.name);
+            match self.object(binding.module_id, &binding.object_key) {
+                Some(BiopObject {
+                    body: BiopBody::File(data),
+                    ..
+                }) => files.push(CarouselFile { path, data }),
+                Some(object) => self.collect_files(object, &path, depth + 1, files),
+                None => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn test_object_carousel() {
+    use crate::{MpegTsParser, MpegTsWriter, PatBuilder, PmtBuilder, PsiHeader, PsiTableSyntax};
+    use core::convert::TryInto;
+
+    fn biop(object_key: u8, kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut message = vec![1, object_key, 0, 0, 0, 4];
+        message.extend_from_slice(kind);
+        /* Empty object info and service context list */
+        message.extend_from_slice(&[0, 0, 0]);
+        message.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        message.extend_from_slice(body);
+        let mut out = b"BIOP\x01\x00\x00\x00".to_vec();
+        out.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        out.extend_from_slice(&message);
+        out
+    }
+
+    fn directory(name: &str, kind: &[u8; 4], object_key: u8) -> Vec<u8> {
+        let mut body = vec![0, 1, 1, name.len() as u8 + 1];
+        body.extend_from_slice(name.as_bytes());
+        body.extend_from_slice(&[0, 4]);
+        body.extend_from_slice(kind);
+        body.push(1);
+        /* IOR with one BIOP profile holding the object location */
+        body.extend_from_slice(&[0, 0, 0, 4]);
+        body.extend_from_slice(kind);
+        body.extend_from_slice(&[0, 0, 0, 1, 0x49, 0x53, 0x4f, 0x06, 0, 0, 0, 17]);
+        body.extend_from_slice(&[0, 1, 0x49, 0x53, 0x4f, 0x50, 10]);
+        body.extend_from_slice(&[0, 0, 0, 1, 0, 1, 1, 0, 1, object_key]);
+        body.extend_from_slice(&[0, 0]);
+        body
+    }
+
+    let mut module = biop(0, b"srg\0", &directory("www", b"dir\0", 2));
+    module.extend(biop(2, b"dir\0", &directory("index.html", b"fil\0", 1)));
+    let content = b"<html>carousel</html>".repeat(8);
+    let mut file_body = (content.len() as u32).to_be_bytes().to_vec();
+    file_body.extend_from_slice(&content);
+    module.extend(biop(1, b"fil\0", &file_body));
+
+    let section = |table_id: u8, message: DsmccSection| {
+        Psi {
+            header: PsiHeader::new()
+                .with_table_id(table_id)
+                .with_section_syntax_indicator(true)
+                .with_reserved_bits(0x3),
+            table_syntax: Some(
+                PsiTableSyntax::new()
+                    .with_reserved_bits(0x3)
+                    .with_current_next_indicator(true),
+            ),
+            data: PsiData::Dsmcc(message),
+        }
+        .to_payload_unit()
+    };
+    let header = |message_id: u16| DsmccMessageHeader {
+        message_id,
+        transaction_id: 0x42,
+        adaptation: Vec::new(),
+    };
+    let block_size = 100;
+    let blocks: Vec<Vec<u8>> = module
+        .chunks(block_size)
+        .enumerate()
+        .map(|(i, block)| {
+            section(
+                DOWNLOAD_DATA_TABLE_ID,
+                DsmccSection::Ddb(Ddb {
+                    header: header(DDB_MESSAGE_ID),
+                    module_id: 1,
+                    module_version: 0,
+                    block_number: i as u16,
+                    block_data: block.to_vec(),
+                }),
+            )
+        })
+        .collect();
+    let dii = section(
+        UN_MESSAGE_TABLE_ID,
+        DsmccSection::Dii(Dii {
+            header: header(DII_MESSAGE_ID),
+            download_id: 0x42,
+            block_size: block_size as u16,
+            window_size: 0,
+            ack_period: 0,
+            t_c_download_window: 0,
+            t_c_download_scenario: 0,
+            compatibility_descriptor: Vec::new(),
+            modules: vec![DiiModule {
+                module_id: 1,
+                module_size: module.len() as u32,
+                module_version: 0,
+                module_info: vec![0; 14],
+            }],
+            private_data: Vec::new(),
+        }),
+    );
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    let pat = PatBuilder::new(1).program(1, 0x100).build();
+    let pmt = PmtBuilder::new(1, 0x1fff).stream(0x0b, 0x200, None).build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, 0x100, &pmt.to_payload_unit(), None)
+        .unwrap();
+    /* A block preceding the DII is ignored until the carousel repeats */
+    for unit in blocks[..1].iter().chain([&dii]).chain(blocks.iter()) {
+        writer.write_unit(&mut out, 0x200, unit, None).unwrap();
+    }
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut carousel = CarouselCollector::default();
+    let mut completed = Vec::new();
+    for packet in out.chunks(188) {
+        let packet = parser.parse(packet.try_into().unwrap()).unwrap();
+        if let Some(module) = carousel.add_packet(&packet) {
+            completed.push(module.module_id);
+        }
+    }
+    assert_eq!(completed, [1]);
+    assert_eq!(carousel.module(0x42, 1).unwrap().data, module);
+    let files = carousel.files();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "/www/index.html");
+    assert_eq!(files[0].data, &content[..]);
+}
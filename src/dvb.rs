@@ -0,0 +1,1688 @@
+//! Module for DVB service information tables (ETSI EN 300 468) carried in PSI sections.
+//!
+//! Parsed tables are returned as variants of [`PsiData`](crate::PsiData).
+
+use crate::prelude::*;
+use crate::{
+    AppDetails, Descriptor, ErrorDetails, LanguageCode, Packet, Payload, Psi, PsiData, Result,
+    SliceReader,
+};
+use alloc::collections::BTreeMap;
+use core::convert::TryInto;
+use core::fmt::{Display, Formatter};
+use hashbrown::HashMap;
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+
+/// PID carrying the NIT.
+pub const NIT_PID: u16 = 0x10;
+/// PID carrying the EIT.
+pub const EIT_PID: u16 = 0x12;
+/// PID carrying the TDT and TOT.
+pub const TDT_PID: u16 = 0x14;
+
+/// Table ID of the NIT describing the network of the current transport stream.
+pub const NIT_ACTUAL_TABLE_ID: u8 = 0x40;
+/// Table ID of the NIT describing another network.
+pub const NIT_OTHER_TABLE_ID: u8 = 0x41;
+/// Table ID of the present/following EIT of the current transport stream.
+pub const EIT_PF_ACTUAL_TABLE_ID: u8 = 0x4e;
+/// Table ID of the present/following EIT of another transport stream.
+pub const EIT_PF_OTHER_TABLE_ID: u8 = 0x4f;
+/// Table ID of the TDT.
+pub const TDT_TABLE_ID: u8 = 0x70;
+/// Table ID of the TOT.
+pub const TOT_TABLE_ID: u8 = 0x73;
+
+/// Tag of the network name descriptor.
+pub const NETWORK_NAME_DESCRIPTOR_TAG: u8 = 0x40;
+/// Tag of the service descriptor.
+pub const SERVICE_DESCRIPTOR_TAG: u8 = 0x48;
+/// Tag of the component descriptor.
+pub const COMPONENT_DESCRIPTOR_TAG: u8 = 0x50;
+/// Tag of the stream identifier descriptor.
+pub const STREAM_IDENTIFIER_DESCRIPTOR_TAG: u8 = 0x52;
+/// Tag of the content descriptor.
+pub const CONTENT_DESCRIPTOR_TAG: u8 = 0x54;
+/// Tag of the teletext descriptor.
+pub const TELETEXT_DESCRIPTOR_TAG: u8 = 0x56;
+/// Tag of the subtitling descriptor.
+pub const SUBTITLING_DESCRIPTOR_TAG: u8 = 0x59;
+/// Tag of the satellite delivery system descriptor.
+pub const SATELLITE_DELIVERY_DESCRIPTOR_TAG: u8 = 0x43;
+/// Tag of the cable delivery system descriptor.
+pub const CABLE_DELIVERY_DESCRIPTOR_TAG: u8 = 0x44;
+/// Tag of the terrestrial delivery system descriptor.
+pub const TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG: u8 = 0x5a;
+/// Tag of the short event descriptor.
+pub const SHORT_EVENT_DESCRIPTOR_TAG: u8 = 0x4d;
+/// Tag of the extended event descriptor.
+pub const EXTENDED_EVENT_DESCRIPTOR_TAG: u8 = 0x4e;
+/// Tag of the local time offset descriptor.
+pub const LOCAL_TIME_OFFSET_DESCRIPTOR_TAG: u8 = 0x58;
+
+/// Checks whether a PID is reserved for DVB service information parsed as PSI.
+pub(crate) fn is_si_pid(pid: u16) -> bool {
+    pid == NIT_PID || pid == EIT_PID || pid == TDT_PID
+}
+
+/// Checks whether a table ID is of an EIT (present/following or schedule).
+pub(crate) fn is_eit_table_id(table_id: u8) -> bool {
+    (EIT_PF_ACTUAL_TABLE_ID..=0x6f).contains(&table_id)
+}
+
+/// Decodes `digits` BCD digits from the least significant bits of `value`.
+fn decode_bcd(value: u32, digits: u32) -> u64 {
+    (0..digits)
+        .rev()
+        .fold(0, |acc, i| acc * 10 + ((value >> (i * 4)) & 0xf) as u64)
+}
+
+/// Reads a descriptor loop preceded by a 12-bit length.
+fn read_descriptor_loop<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Vec<Descriptor>, D> {
+    let length = reader.read_be_u16()? & 0xfff;
+    let mut loop_reader = reader.new_sub_reader(length as usize)?;
+    let mut descriptors = Vec::new();
+    while loop_reader.remaining_len() > 0 {
+        descriptors.push(Descriptor::new_from_reader(&mut loop_reader)?);
+    }
+    Ok(descriptors)
+}
+
+/// Appends a descriptor loop preceded by a 12-bit length with reserved bits set.
+fn write_descriptor_loop(descriptors: &[Descriptor], out: &mut Vec<u8>) {
+    let mut data = Vec::new();
+    for descriptor in descriptors.iter() {
+        descriptor.write_to(&mut data);
+    }
+    out.extend_from_slice(&(0xf000 | data.len() as u16).to_be_bytes());
+    out.extend_from_slice(&data);
+}
+
+/// Satellite delivery system descriptor (tag 0x43).
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct SatelliteDeliverySystemDescriptor {
+    /// Frequency in 10kHz units, as 8 BCD digits.
+    pub frequency: B32,
+    /// Orbital position in 0.1 degree units, as 4 BCD digits.
+    pub orbital_position: B16,
+    /// Orbital position is east (`true`) or west (`false`).
+    pub west_east_flag: bool,
+    /// 0 = linear horizontal, 1 = linear vertical, 2 = circular left, 3 = circular right.
+    pub polarization: B2,
+    /// DVB-S2 roll-off factor: 0 = 0.35, 1 = 0.25, 2 = 0.20.
+    pub roll_off: B2,
+    /// DVB-S2 (`true`) or DVB-S (`false`).
+    pub modulation_system: bool,
+    /// 0 = auto, 1 = QPSK, 2 = 8PSK, 3 = 16QAM.
+    pub modulation_type: B2,
+    /// Symbol rate in 100 symbol/s units, as 7 BCD digits.
+    pub symbol_rate: B28,
+    /// Inner FEC scheme.
+    pub fec_inner: B4,
+}
+
+impl SatelliteDeliverySystemDescriptor {
+    /// Decoded frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        decode_bcd(self.frequency(), 8) * 10_000
+    }
+
+    /// Decoded orbital position in 0.1 degree units.
+    pub fn orbital_position_tenths(&self) -> u16 {
+        decode_bcd(self.orbital_position() as u32, 4) as u16
+    }
+
+    /// Decoded symbol rate in symbols per second.
+    pub fn symbol_rate_sps(&self) -> u64 {
+        decode_bcd(self.symbol_rate(), 7) * 100
+    }
+}
+
+/// Cable delivery system descriptor (tag 0x44).
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct CableDeliverySystemDescriptor {
+    /// Frequency in 100Hz units, as 8 BCD digits.
+    pub frequency: B32,
+    #[skip]
+    pub reserved: B12,
+    /// Outer FEC scheme: 1 = none, 2 = RS(204/188).
+    pub fec_outer: B4,
+    /// 1 = 16QAM, 2 = 32QAM, 3 = 64QAM, 4 = 128QAM, 5 = 256QAM.
+    pub modulation: B8,
+    /// Symbol rate in 100 symbol/s units, as 7 BCD digits.
+    pub symbol_rate: B28,
+    /// Inner FEC scheme.
+    pub fec_inner: B4,
+}
+
+impl CableDeliverySystemDescriptor {
+    /// Decoded frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        decode_bcd(self.frequency(), 8) * 100
+    }
+
+    /// Decoded symbol rate in symbols per second.
+    pub fn symbol_rate_sps(&self) -> u64 {
+        decode_bcd(self.symbol_rate(), 7) * 100
+    }
+}
+
+/// Terrestrial delivery system descriptor (tag 0x5a).
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct TerrestrialDeliverySystemDescriptor {
+    /// Centre frequency in 10Hz units.
+    pub centre_frequency: B32,
+    /// 0 = 8MHz, 1 = 7MHz, 2 = 6MHz, 3 = 5MHz.
+    pub bandwidth: B3,
+    /// High priority stream of hierarchical transmission.
+    pub priority: bool,
+    /// Time slicing is not used (DVB-H).
+    pub time_slicing_indicator: bool,
+    /// MPE-FEC is not used (DVB-H).
+    pub mpe_fec_indicator: bool,
+    #[skip]
+    pub reserved: B2,
+    /// 0 = QPSK, 1 = 16QAM, 2 = 64QAM.
+    pub constellation: B2,
+    /// Hierarchy and interleaving mode.
+    pub hierarchy_information: B3,
+    /// Code rate of the high priority stream.
+    pub code_rate_hp_stream: B3,
+    /// Code rate of the low priority stream.
+    pub code_rate_lp_stream: B3,
+    /// 0 = 1/32, 1 = 1/16, 2 = 1/8, 3 = 1/4.
+    pub guard_interval: B2,
+    /// 0 = 2k, 1 = 8k, 2 = 4k.
+    pub transmission_mode: B2,
+    /// Other frequencies are in use.
+    pub other_frequency_flag: bool,
+    #[skip]
+    pub reserved2: B32,
+}
+
+impl TerrestrialDeliverySystemDescriptor {
+    /// Decoded centre frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        self.centre_frequency() as u64 * 10
+    }
+}
+
+/// Delivery system descriptor of a transport stream listed in the NIT.
+#[derive(Debug, Copy, Clone)]
+pub enum DeliverySystem {
+    /// DVB-S/S2 delivery.
+    Satellite(SatelliteDeliverySystemDescriptor),
+    /// DVB-C delivery.
+    Cable(CableDeliverySystemDescriptor),
+    /// DVB-T delivery.
+    Terrestrial(TerrestrialDeliverySystemDescriptor),
+}
+
+impl DeliverySystem {
+    /// Parses a delivery system descriptor. Returns [`None`] for other descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        let bytes: [u8; 11] = match descriptor.data.as_slice().try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => return None,
+        };
+        match descriptor.tag {
+            SATELLITE_DELIVERY_DESCRIPTOR_TAG => Some(DeliverySystem::Satellite(
+                SatelliteDeliverySystemDescriptor::from_bytes(bytes),
+            )),
+            CABLE_DELIVERY_DESCRIPTOR_TAG => Some(DeliverySystem::Cable(
+                CableDeliverySystemDescriptor::from_bytes(bytes),
+            )),
+            TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG => Some(DeliverySystem::Terrestrial(
+                TerrestrialDeliverySystemDescriptor::from_bytes(bytes),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Decoded frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        match self {
+            DeliverySystem::Satellite(d) => d.frequency_hz(),
+            DeliverySystem::Cable(d) => d.frequency_hz(),
+            DeliverySystem::Terrestrial(d) => d.frequency_hz(),
+        }
+    }
+
+    /// Encodes the delivery system descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        match self {
+            DeliverySystem::Satellite(d) => {
+                Descriptor::new(SATELLITE_DELIVERY_DESCRIPTOR_TAG, &d.into_bytes())
+            }
+            DeliverySystem::Cable(d) => {
+                Descriptor::new(CABLE_DELIVERY_DESCRIPTOR_TAG, &d.into_bytes())
+            }
+            DeliverySystem::Terrestrial(d) => {
+                Descriptor::new(TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG, &d.into_bytes())
+            }
+        }
+    }
+}
+
+/// Transport stream entry of the NIT.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NitTransportStream {
+    /// Transport stream ID.
+    pub transport_stream_id: u16,
+    /// Network ID of the originating delivery system.
+    pub original_network_id: u16,
+    /// Transport descriptors, including the delivery system descriptor.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl NitTransportStream {
+    /// First delivery system descriptor of the transport stream.
+    pub fn delivery_system(&self) -> Option<DeliverySystem> {
+        self.descriptors
+            .iter()
+            .find_map(DeliverySystem::from_descriptor)
+    }
+}
+
+/// Parsed NIT section. The network ID is the table ID extension of the section.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Payload, PsiData};
+///
+/// fn print_nit(parser: &mut MpegTsParser<DefaultAppDetails>, packet: &[u8; 188]) {
+///     let packet = parser.parse(packet).expect("Parse Error!");
+///     if let Some(Payload::Psi(psi)) = packet.payload {
+///         if let PsiData::Nit(nit) = psi.data {
+///             for ts in nit.transport_streams.iter() {
+///                 if let Some(delivery) = ts.delivery_system() {
+///                     println!("TS {}: {} Hz", ts.transport_stream_id, delivery.frequency_hz());
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Nit {
+    /// Network descriptors, including the network name.
+    pub network_descriptors: Vec<Descriptor>,
+    /// Transport streams of the network.
+    pub transport_streams: Vec<NitTransportStream>,
+}
+
+impl Nit {
+    /// Raw bytes of the network name descriptor, in DVB character coding.
+    pub fn network_name(&self) -> Option<&[u8]> {
+        self.network_descriptors
+            .iter()
+            .find(|d| d.tag == NETWORK_NAME_DESCRIPTOR_TAG)
+            .map(|d| d.data.as_slice())
+    }
+
+    pub(crate) fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let network_descriptors = read_descriptor_loop(reader)?;
+        let loop_length = reader.read_be_u16()? & 0xfff;
+        let mut ts_reader = reader.new_sub_reader(loop_length as usize)?;
+        let mut transport_streams = Vec::new();
+        while ts_reader.remaining_len() > 0 {
+            if ts_reader.remaining_len() < 6 {
+                warn!("Short read of NIT transport stream");
+                return Err(ts_reader.make_error(ErrorDetails::<D>::BadPsiHeader));
+            }
+            let transport_stream_id = ts_reader.read_be_u16()?;
+            let original_network_id = ts_reader.read_be_u16()?;
+            let descriptors = read_descriptor_loop(&mut ts_reader)?;
+            transport_streams.push(NitTransportStream {
+                transport_stream_id,
+                original_network_id,
+                descriptors,
+            });
+        }
+        Ok(Self {
+            network_descriptors,
+            transport_streams,
+        })
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        write_descriptor_loop(&self.network_descriptors, out);
+        let mut ts_loop = Vec::new();
+        for ts in self.transport_streams.iter() {
+            ts_loop.extend_from_slice(&ts.transport_stream_id.to_be_bytes());
+            ts_loop.extend_from_slice(&ts.original_network_id.to_be_bytes());
+            write_descriptor_loop(&ts.descriptors, &mut ts_loop);
+        }
+        out.extend_from_slice(&(0xf000 | ts_loop.len() as u16).to_be_bytes());
+        out.extend_from_slice(&ts_loop);
+    }
+}
+
+/// Upper half of ISO/IEC 8859-2, from 0xa0.
+const ISO_8859_2_UPPER: &str = "\u{a0}Ą˘Ł¤ĽŚ§¨ŠŞŤŹ\u{ad}ŽŻ°ą˛ł´ľśˇ¸šşťź˝žżŔÁÂĂÄĹĆÇČÉĘËĚÍÎĎ\
+                                ĐŃŇÓÔŐÖ×ŘŮÚŰÜÝŢßŕáâăäĺćçčéęëěíîďđńňóôőö÷řůúűüýţ˙";
+
+/// Upper half of ISO/IEC 6937 from 0xa0, with U+FFFD for unassigned codes and combining
+/// diacritical marks.
+const ISO_6937_UPPER: &str = "\u{a0}¡¢£$¥#§¤‘“«←↑→↓°±²³×µ¶·÷’”»¼½¾¿\
+                              \u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\
+                              \u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\u{fffd}\
+                              ―¹®©™♪¬¦\u{fffd}\u{fffd}\u{fffd}\u{fffd}⅛⅜⅝⅞\
+                              ΩÆĐªĦ\u{fffd}ĲĿŁØŒºÞŦŊŉĸæđðħıĳŀłøœßþŧŋ\u{ad}";
+
+/// Base letters and their precomposed forms for ISO/IEC 6937 diacritical marks 0xc1 to 0xcf.
+/// Letters missing from a list are followed by the combining mark instead.
+const ISO_6937_COMPOSED: [(char, &str, &str); 15] = [
+    ('\u{300}', "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+    (
+        '\u{301}',
+        "ACEILNORSUYZacegilnorsuyz",
+        "ÁĆÉÍĹŃÓŔŚÚÝŹáćéǵíĺńóŕśúýź",
+    ),
+    (
+        '\u{302}',
+        "ACEGHIJOSUWYaceghijosuwy",
+        "ÂĈÊĜĤÎĴÔŜÛŴŶâĉêĝĥîĵôŝûŵŷ",
+    ),
+    ('\u{303}', "AINOUainou", "ÃĨÑÕŨãĩñõũ"),
+    ('\u{304}', "AEIOUaeiou", "ĀĒĪŌŪāēīōū"),
+    ('\u{306}', "AGUagu", "ĂĞŬăğŭ"),
+    ('\u{307}', "CEGIZcegz", "ĊĖĠİŻċėġż"),
+    ('\u{308}', "AEIOUYaeiouy", "ÄËÏÖÜŸäëïöüÿ"),
+    ('\u{308}', "", ""),
+    ('\u{30a}', "AUau", "ÅŮåů"),
+    ('\u{327}', "CGKLNRSTcklnrst", "ÇĢĶĻŅŖŞŢçķļņŗşţ"),
+    ('\u{332}', "", ""),
+    ('\u{30b}', "OUou", "ŐŰőű"),
+    ('\u{328}', "AEIUaeiu", "ĄĘĮŲąęįų"),
+    ('\u{30c}', "CDELNRSTZcdelnrstz", "ČĎĚĽŇŘŠŤŽčďěľňřšťž"),
+];
+
+/// Character table selected by the first byte of DVB text.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum DvbCharacterTable {
+    /// ISO/IEC 6937, the default table.
+    Iso6937,
+    /// ISO/IEC 8859 part number.
+    Iso8859(u8),
+    /// UCS-2 big-endian.
+    Ucs2,
+    /// UTF-8.
+    Utf8,
+    /// Table without a decoder, decoded as ISO/IEC 8859-1.
+    Unsupported,
+}
+
+impl DvbCharacterTable {
+    /// Splits the table selection bytes off of `data`.
+    fn select(data: &[u8]) -> (Self, &[u8]) {
+        match data {
+            [selector @ 0x01..=0x0b, text @ ..] => (Self::Iso8859(selector + 4), text),
+            [0x10, _, part, text @ ..] => (Self::Iso8859(*part), text),
+            [0x11, text @ ..] => (Self::Ucs2, text),
+            [0x15, text @ ..] => (Self::Utf8, text),
+            [0x00..=0x1f, text @ ..] => (Self::Unsupported, text),
+            _ => (Self::Iso6937, data),
+        }
+    }
+}
+
+/// Decodes a byte of 0xa0 or above in a single byte ISO/IEC 8859 part.
+fn decode_iso_8859(part: u8, b: u8) -> char {
+    let code = match (part, b) {
+        (2, _) => return ISO_8859_2_UPPER.chars().nth((b - 0xa0) as usize).unwrap(),
+        (5, 0xf0) => 0x2116,
+        (5, 0xfd) => 0xa7,
+        (5, 0xa1..=0xff) if b != 0xad => b as u32 + 0x360,
+        (7, 0xa1) => 0x2018,
+        (7, 0xa2) => 0x2019,
+        (7, 0xa4) => 0x20ac,
+        (7, 0xa5) => 0x20af,
+        (7, 0xaa) => 0x37a,
+        (7, 0xae) | (7, 0xd2) | (7, 0xff) => 0xfffd,
+        (7, 0xaf) => 0x2015,
+        (7, 0xb4..=0xb6) | (7, 0xb8..=0xba) | (7, 0xbc) | (7, 0xbe..=0xfe) => b as u32 + 0x2d0,
+        (9, 0xd0) => 0x11e,
+        (9, 0xdd) => 0x130,
+        (9, 0xde) => 0x15e,
+        (9, 0xf0) => 0x11f,
+        (9, 0xfd) => 0x131,
+        (9, 0xfe) => 0x15f,
+        (15, 0xa4) => 0x20ac,
+        (15, 0xa6) => 0x160,
+        (15, 0xa8) => 0x161,
+        (15, 0xb4) => 0x17d,
+        (15, 0xb8) => 0x17e,
+        (15, 0xbc) => 0x152,
+        (15, 0xbd) => 0x153,
+        (15, 0xbe) => 0x178,
+        _ => b as u32,
+    };
+    core::char::from_u32(code).unwrap_or('\u{fffd}')
+}
+
+/// Decodes ISO/IEC 6937 text, composing diacritical marks with the letter following them.
+fn decode_iso_6937(text: &[u8], out: &mut String) {
+    let mut bytes = text.iter().copied();
+    while let Some(b) = bytes.next() {
+        match b {
+            0x8a => out.push('\n'),
+            0x00..=0x1f | 0x7f..=0x9f => {}
+            0xc1..=0xcf => {
+                let (mark, bases, composed) = ISO_6937_COMPOSED[(b - 0xc1) as usize];
+                let base = match bytes.next() {
+                    Some(base @ 0x20..=0x7e) => base as char,
+                    _ => continue,
+                };
+                match bases.chars().position(|c| c == base) {
+                    Some(i) => out.push(composed.chars().nth(i).unwrap()),
+                    None => {
+                        out.push(base);
+                        out.push(mark);
+                    }
+                }
+            }
+            0xa0..=0xff => out.push(ISO_6937_UPPER.chars().nth((b - 0xa0) as usize).unwrap()),
+            _ => out.push(b as char),
+        }
+    }
+}
+
+/// Decodes text in DVB character coding (ETSI EN 300 468 Annex A).
+///
+/// The character table is selected by the leading selection bytes: ISO/IEC 6937 by default,
+/// ISO/IEC 8859 parts 1, 2, 5, 7, 9 and 15, UCS-2 and UTF-8 are decoded as such. Other tables,
+/// including the remaining 8859 parts, are decoded as ISO/IEC 8859-1. Control codes are dropped
+/// except for the CR/LF code, which becomes a newline.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::dvb::decode_text;
+/// assert_eq!(decode_text(b"Caf\xc2e"), "Café");
+/// assert_eq!(decode_text(b"\x01\xbf\xe0\xd8\xd2\xd5\xe2"), "Привет");
+/// assert_eq!(decode_text(b"\x10\x00\x02\xa9koda"), "Škoda");
+/// assert_eq!(decode_text("\x15Zoë\u{8a}".as_bytes()), "Zoë\n");
+/// ```
+pub fn decode_text(data: &[u8]) -> String {
+    let (table, text) = DvbCharacterTable::select(data);
+    match table {
+        DvbCharacterTable::Utf8 => String::from_utf8_lossy(text).replace('\u{8a}', "\n"),
+        DvbCharacterTable::Ucs2 => {
+            let units: Vec<u16> = text
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units).replace('\u{e08a}', "\n")
+        }
+        DvbCharacterTable::Iso6937 => {
+            let mut out = String::with_capacity(text.len());
+            decode_iso_6937(text, &mut out);
+            out
+        }
+        DvbCharacterTable::Iso8859(_) | DvbCharacterTable::Unsupported => {
+            let part = match table {
+                DvbCharacterTable::Iso8859(part) => part,
+                _ => 1,
+            };
+            text.iter()
+                .filter_map(|&b| match b {
+                    0x8a => Some('\n'),
+                    0x00..=0x1f | 0x7f..=0x9f => None,
+                    0xa0..=0xff => Some(decode_iso_8859(part, b)),
+                    _ => Some(b as char),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Encodes text in DVB character coding, the inverse of [`decode_text`].
+///
+/// Printable ASCII text is encoded with the default character table; other text is encoded as
+/// UTF-8 behind the 0x15 selector. Newlines become the CR/LF code.
+pub fn encode_text(text: &str) -> Vec<u8> {
+    if text
+        .bytes()
+        .all(|b| b == b'\n' || (0x20..0x7f).contains(&b))
+    {
+        text.bytes()
+            .map(|b| if b == b'\n' { 0x8a } else { b })
+            .collect()
+    } else {
+        let mut data = vec![0x15];
+        data.extend_from_slice(text.replace('\n', "\u{8a}").as_bytes());
+        data
+    }
+}
+
+/// Decodes a BCD byte.
+fn bcd_byte(b: u8) -> u8 {
+    (b >> 4) * 10 + (b & 0xf)
+}
+
+/// Encodes a value below 100 as a BCD byte.
+fn to_bcd_byte(v: u8) -> u8 {
+    ((v / 10) << 4) | (v % 10)
+}
+
+/// UTC time coded as a Modified Julian Date and BCD hours, minutes, and seconds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DvbTime {
+    /// Modified Julian Date.
+    pub mjd: u16,
+    /// Hour (0-23).
+    pub hour: u8,
+    /// Minute (0-59).
+    pub minute: u8,
+    /// Second (0-59).
+    pub second: u8,
+}
+
+impl DvbTime {
+    /// Decodes the 40-bit time field. Returns [`None`] if the time is undefined (all bits set).
+    pub fn from_bytes(bytes: [u8; 5]) -> Option<Self> {
+        if bytes == [0xff; 5] {
+            return None;
+        }
+        Some(Self {
+            mjd: u16::from_be_bytes([bytes[0], bytes[1]]),
+            hour: bcd_byte(bytes[2]),
+            minute: bcd_byte(bytes[3]),
+            second: bcd_byte(bytes[4]),
+        })
+    }
+
+    /// Encodes the 40-bit time field.
+    pub fn to_bytes(&self) -> [u8; 5] {
+        let mjd = self.mjd.to_be_bytes();
+        [
+            mjd[0],
+            mjd[1],
+            to_bcd_byte(self.hour),
+            to_bcd_byte(self.minute),
+            to_bcd_byte(self.second),
+        ]
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn unix_time(&self) -> i64 {
+        (self.mjd as i64 - 40587) * 86400
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+
+    /// Calendar date as (year, month, day), per ETSI EN 300 468 Annex C.
+    pub fn date(&self) -> (u16, u8, u8) {
+        let mjd = self.mjd as f64;
+        let y = ((mjd - 15078.2) / 365.25) as i64;
+        let m = ((mjd - 14956.1 - (y as f64 * 365.25).floor()) / 30.6001) as i64;
+        let day =
+            self.mjd as i64 - 14956 - (y as f64 * 365.25) as i64 - (m as f64 * 30.6001) as i64;
+        let k = if m == 14 || m == 15 { 1 } else { 0 };
+        ((1900 + y + k) as u16, (m - 1 - k * 12) as u8, day as u8)
+    }
+
+    /// Calendar date and time.
+    pub fn to_calendar(&self) -> CalendarTime {
+        let (year, month, day) = self.date();
+        CalendarTime {
+            year,
+            month,
+            day,
+            hour: self.hour,
+            minute: self.minute,
+            second: self.second,
+        }
+    }
+}
+
+/// UTC calendar date and time, displayed in ISO 8601 format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarTime {
+    /// Year.
+    pub year: u16,
+    /// Month (1-12).
+    pub month: u8,
+    /// Day of the month (1-31).
+    pub day: u8,
+    /// Hour (0-23).
+    pub hour: u8,
+    /// Minute (0-59).
+    pub minute: u8,
+    /// Second (0-59).
+    pub second: u8,
+}
+
+impl Display for CalendarTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Decodes a 24-bit BCD duration (hours, minutes, seconds) to seconds.
+fn decode_duration(bytes: [u8; 3]) -> u32 {
+    bcd_byte(bytes[0]) as u32 * 3600 + bcd_byte(bytes[1]) as u32 * 60 + bcd_byte(bytes[2]) as u32
+}
+
+/// Encodes a duration in seconds as 24-bit BCD.
+fn encode_duration(seconds: u32) -> [u8; 3] {
+    [
+        to_bcd_byte((seconds / 3600).min(99) as u8),
+        to_bcd_byte((seconds / 60 % 60) as u8),
+        to_bcd_byte((seconds % 60) as u8),
+    ]
+}
+
+/// Short event descriptor (tag 0x4d) carrying the name and summary of an event.
+#[derive(Debug, Clone)]
+pub struct ShortEventDescriptor {
+    /// Language of the text.
+    pub language: LanguageCode,
+    /// Event name.
+    pub event_name: String,
+    /// Event summary.
+    pub text: String,
+}
+
+impl ShortEventDescriptor {
+    /// Parses a short event descriptor. Returns [`None`] for other or malformed descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != SHORT_EVENT_DESCRIPTOR_TAG {
+            return None;
+        }
+        let data = descriptor.data.as_slice();
+        let language = LanguageCode::from_bytes(data.get(..3)?.try_into().ok()?);
+        let (event_name, rest) = split_length_prefixed(data.get(3..)?)?;
+        let (text, _) = split_length_prefixed(rest)?;
+        Some(Self {
+            language,
+            event_name: decode_text(event_name),
+            text: decode_text(text),
+        })
+    }
+
+    /// Encodes the short event descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        let mut data = self.language.as_bytes().to_vec();
+        write_length_prefixed(&encode_text(&self.event_name), &mut data);
+        write_length_prefixed(&encode_text(&self.text), &mut data);
+        Descriptor::new(SHORT_EVENT_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Extended event descriptor (tag 0x4e) carrying a longer description of an event, possibly
+/// spread across several descriptors.
+#[derive(Debug, Clone)]
+pub struct ExtendedEventDescriptor {
+    /// Index of this descriptor.
+    pub descriptor_number: u8,
+    /// Index of the last descriptor of the description.
+    pub last_descriptor_number: u8,
+    /// Language of the text.
+    pub language: LanguageCode,
+    /// Itemized (description, item) pairs, e.g. cast members.
+    pub items: Vec<(String, String)>,
+    /// Non-itemized text.
+    pub text: String,
+}
+
+impl ExtendedEventDescriptor {
+    /// Parses an extended event descriptor. Returns [`None`] for other or malformed
+    /// descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != EXTENDED_EVENT_DESCRIPTOR_TAG {
+            return None;
+        }
+        let data = descriptor.data.as_slice();
+        let numbers = *data.first()?;
+        let language = LanguageCode::from_bytes(data.get(1..4)?.try_into().ok()?);
+        let (mut item_data, rest) = split_length_prefixed(data.get(4..)?)?;
+        let mut items = Vec::new();
+        while !item_data.is_empty() {
+            let (description, rest) = split_length_prefixed(item_data)?;
+            let (item, rest) = split_length_prefixed(rest)?;
+            items.push((decode_text(description), decode_text(item)));
+            item_data = rest;
+        }
+        let (text, _) = split_length_prefixed(rest)?;
+        Some(Self {
+            descriptor_number: numbers >> 4,
+            last_descriptor_number: numbers & 0xf,
+            language,
+            items,
+            text: decode_text(text),
+        })
+    }
+
+    /// Encodes the extended event descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        let mut data = vec![(self.descriptor_number << 4) | (self.last_descriptor_number & 0xf)];
+        data.extend_from_slice(self.language.as_bytes());
+        let mut item_data = Vec::new();
+        for (description, item) in self.items.iter() {
+            write_length_prefixed(&encode_text(description), &mut item_data);
+            write_length_prefixed(&encode_text(item), &mut item_data);
+        }
+        write_length_prefixed(&item_data, &mut data);
+        write_length_prefixed(&encode_text(&self.text), &mut data);
+        Descriptor::new(EXTENDED_EVENT_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Service descriptor (tag 0x48) carrying the type and names of a service.
+#[derive(Debug, Clone)]
+pub struct ServiceDescriptor {
+    /// Service type, e.g. 0x01 for digital television or 0x02 for digital radio.
+    pub service_type: u8,
+    /// Name of the service provider.
+    pub provider_name: String,
+    /// Name of the service.
+    pub service_name: String,
+}
+
+impl ServiceDescriptor {
+    /// Parses a service descriptor. Returns [`None`] for other or malformed descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != SERVICE_DESCRIPTOR_TAG {
+            return None;
+        }
+        let data = descriptor.data.as_slice();
+        let service_type = *data.first()?;
+        let (provider_name, rest) = split_length_prefixed(&data[1..])?;
+        let (service_name, _) = split_length_prefixed(rest)?;
+        Some(Self {
+            service_type,
+            provider_name: decode_text(provider_name),
+            service_name: decode_text(service_name),
+        })
+    }
+
+    /// Encodes the service descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        let mut data = vec![self.service_type];
+        write_length_prefixed(&encode_text(&self.provider_name), &mut data);
+        write_length_prefixed(&encode_text(&self.service_name), &mut data);
+        Descriptor::new(SERVICE_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Component descriptor (tag 0x50) describing an elementary stream of an event or service.
+#[derive(Debug, Clone)]
+pub struct ComponentDescriptor {
+    /// Extension of `stream_content`.
+    pub stream_content_ext: u8,
+    /// Kind of stream, e.g. 0x1 for MPEG-2 video or 0x2 for MPEG-1 layer 2 audio.
+    pub stream_content: u8,
+    /// Type of component within the `stream_content`.
+    pub component_type: u8,
+    /// Tag matching the stream identifier descriptor of the stream in the PMT.
+    pub component_tag: u8,
+    /// Language of the component and text.
+    pub language: LanguageCode,
+    /// Description of the component.
+    pub text: String,
+}
+
+impl ComponentDescriptor {
+    /// Parses a component descriptor. Returns [`None`] for other or malformed descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Self> {
+        if descriptor.tag != COMPONENT_DESCRIPTOR_TAG {
+            return None;
+        }
+        let data = descriptor.data.as_slice();
+        let header = data.get(..3)?;
+        Some(Self {
+            stream_content_ext: header[0] >> 4,
+            stream_content: header[0] & 0xf,
+            component_type: header[1],
+            component_tag: header[2],
+            language: LanguageCode::from_bytes(data.get(3..6)?.try_into().ok()?),
+            text: decode_text(&data[6..]),
+        })
+    }
+
+    /// Encodes the component descriptor.
+    pub fn to_descriptor(&self) -> Descriptor {
+        let mut data = vec![
+            (self.stream_content_ext << 4) | (self.stream_content & 0xf),
+            self.component_type,
+            self.component_tag,
+        ];
+        data.extend_from_slice(self.language.as_bytes());
+        data.extend_from_slice(&encode_text(&self.text));
+        Descriptor::new(COMPONENT_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Parses a stream identifier descriptor (tag 0x52) into its component tag. Returns [`None`]
+/// for other or malformed descriptors.
+pub fn stream_identifier(descriptor: &Descriptor) -> Option<u8> {
+    if descriptor.tag != STREAM_IDENTIFIER_DESCRIPTOR_TAG {
+        return None;
+    }
+    descriptor.data.first().copied()
+}
+
+/// Creates a stream identifier descriptor (tag 0x52) carrying a component tag.
+pub fn new_stream_identifier(component_tag: u8) -> Descriptor {
+    Descriptor::new(STREAM_IDENTIFIER_DESCRIPTOR_TAG, &[component_tag])
+}
+
+/// Entry of the content descriptor (tag 0x54) classifying the genre of an event.
+#[derive(Debug, Copy, Clone)]
+pub struct ContentEntry {
+    /// Genre, e.g. 0x1 for movie/drama or 0x4 for sports.
+    pub content_nibble_level_1: u8,
+    /// Subgenre within `content_nibble_level_1`.
+    pub content_nibble_level_2: u8,
+    /// Broadcaster defined classification.
+    pub user_byte: u8,
+}
+
+impl ContentEntry {
+    /// Parses the entries of a content descriptor. Returns [`None`] for other descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Vec<Self>> {
+        if descriptor.tag != CONTENT_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(
+            descriptor
+                .data
+                .chunks_exact(2)
+                .map(|entry| ContentEntry {
+                    content_nibble_level_1: entry[0] >> 4,
+                    content_nibble_level_2: entry[0] & 0xf,
+                    user_byte: entry[1],
+                })
+                .collect(),
+        )
+    }
+
+    /// Encodes a content descriptor listing `entries`.
+    pub fn to_descriptor(entries: &[Self]) -> Descriptor {
+        let mut data = Vec::new();
+        for entry in entries.iter() {
+            data.push((entry.content_nibble_level_1 << 4) | (entry.content_nibble_level_2 & 0xf));
+            data.push(entry.user_byte);
+        }
+        Descriptor::new(CONTENT_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Entry of the teletext descriptor (tag 0x56) locating a page within a teletext stream.
+#[derive(Debug, Copy, Clone)]
+pub struct TeletextEntry {
+    /// Language of the page.
+    pub language: LanguageCode,
+    /// 1 = initial page, 2 = subtitle page, 5 = subtitle page for the hearing impaired.
+    pub teletext_type: u8,
+    /// Magazine number, 0 denoting magazine 8.
+    pub magazine_number: u8,
+    /// Page number as two BCD digits.
+    pub page_number: u8,
+}
+
+impl TeletextEntry {
+    /// Parses the entries of a teletext descriptor. Returns [`None`] for other descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Vec<Self>> {
+        if descriptor.tag != TELETEXT_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(
+            descriptor
+                .data
+                .chunks_exact(5)
+                .map(|entry| TeletextEntry {
+                    language: LanguageCode::from_bytes([entry[0], entry[1], entry[2]]),
+                    teletext_type: entry[3] >> 3,
+                    magazine_number: entry[3] & 0x7,
+                    page_number: entry[4],
+                })
+                .collect(),
+        )
+    }
+
+    /// Encodes a teletext descriptor listing `entries`.
+    pub fn to_descriptor(entries: &[Self]) -> Descriptor {
+        let mut data = Vec::new();
+        for entry in entries.iter() {
+            data.extend_from_slice(entry.language.as_bytes());
+            data.push((entry.teletext_type << 3) | (entry.magazine_number & 0x7));
+            data.push(entry.page_number);
+        }
+        Descriptor::new(TELETEXT_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Entry of the subtitling descriptor (tag 0x59) locating a DVB subtitle service.
+#[derive(Debug, Copy, Clone)]
+pub struct SubtitlingEntry {
+    /// Language of the subtitles.
+    pub language: LanguageCode,
+    /// Subtitling type, e.g. 0x10 for normal or 0x20 for hard of hearing subtitles.
+    pub subtitling_type: u8,
+    /// Page ID of the composition page.
+    pub composition_page_id: u16,
+    /// Page ID of the ancillary page shared between services.
+    pub ancillary_page_id: u16,
+}
+
+impl SubtitlingEntry {
+    /// Parses the entries of a subtitling descriptor. Returns [`None`] for other descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Vec<Self>> {
+        if descriptor.tag != SUBTITLING_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(
+            descriptor
+                .data
+                .chunks_exact(8)
+                .map(|entry| SubtitlingEntry {
+                    language: LanguageCode::from_bytes([entry[0], entry[1], entry[2]]),
+                    subtitling_type: entry[3],
+                    composition_page_id: u16::from_be_bytes([entry[4], entry[5]]),
+                    ancillary_page_id: u16::from_be_bytes([entry[6], entry[7]]),
+                })
+                .collect(),
+        )
+    }
+
+    /// Encodes a subtitling descriptor listing `entries`.
+    pub fn to_descriptor(entries: &[Self]) -> Descriptor {
+        let mut data = Vec::new();
+        for entry in entries.iter() {
+            data.extend_from_slice(entry.language.as_bytes());
+            data.push(entry.subtitling_type);
+            data.extend_from_slice(&entry.composition_page_id.to_be_bytes());
+            data.extend_from_slice(&entry.ancillary_page_id.to_be_bytes());
+        }
+        Descriptor::new(SUBTITLING_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Splits a field preceded by an 8-bit length from the following data.
+fn split_length_prefixed(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&len, rest) = data.split_first()?;
+    if rest.len() < len as usize {
+        return None;
+    }
+    Some(rest.split_at(len as usize))
+}
+
+/// Appends a field preceded by an 8-bit length.
+fn write_length_prefixed(field: &[u8], out: &mut Vec<u8>) {
+    out.push(field.len() as u8);
+    out.extend_from_slice(field);
+}
+
+/// Event of the EIT.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EitEvent {
+    /// Event ID, unique within the service.
+    pub event_id: u16,
+    /// Start time, if defined.
+    pub start_time: Option<DvbTime>,
+    /// Duration in seconds.
+    pub duration: u32,
+    /// 0 = undefined, 1 = not running, 2 = starts in a few seconds, 3 = pausing, 4 = running.
+    pub running_status: u8,
+    /// Components of the event are scrambled.
+    pub free_ca_mode: bool,
+    /// Event descriptors.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl EitEvent {
+    /// First short event descriptor of the event.
+    pub fn short_event(&self) -> Option<ShortEventDescriptor> {
+        self.descriptors
+            .iter()
+            .find_map(ShortEventDescriptor::from_descriptor)
+    }
+
+    /// Extended event descriptors of the event, in order.
+    pub fn extended_events(&self) -> Vec<ExtendedEventDescriptor> {
+        self.descriptors
+            .iter()
+            .filter_map(ExtendedEventDescriptor::from_descriptor)
+            .collect()
+    }
+
+    /// Concatenated non-itemized text of the extended event descriptors.
+    pub fn extended_text(&self) -> String {
+        self.extended_events().into_iter().map(|e| e.text).collect()
+    }
+}
+
+/// Parsed EIT section. The service ID is the table ID extension of the section.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Eit {
+    /// Transport stream ID of the service.
+    pub transport_stream_id: u16,
+    /// Network ID of the originating delivery system.
+    pub original_network_id: u16,
+    /// Number of the last section of the segment.
+    pub segment_last_section_num: u8,
+    /// Last table ID in use.
+    pub last_table_id: u8,
+    /// Events of the section.
+    pub events: Vec<EitEvent>,
+}
+
+impl Eit {
+    pub(crate) fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let transport_stream_id = reader.read_be_u16()?;
+        let original_network_id = reader.read_be_u16()?;
+        let segment_last_section_num = reader.read_u8()?;
+        let last_table_id = reader.read_u8()?;
+        let mut events = Vec::new();
+        while reader.remaining_len() > 0 {
+            if reader.remaining_len() < 12 {
+                warn!("Short read of EIT event");
+                return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
+            }
+            let event_id = reader.read_be_u16()?;
+            let start_time = DvbTime::from_bytes(*reader.read_array_ref::<5>()?);
+            let duration = decode_duration(*reader.read_array_ref::<3>()?);
+            let flags = reader.peek_array_ref::<1>()?[0];
+            let descriptors = read_descriptor_loop(reader)?;
+            events.push(EitEvent {
+                event_id,
+                start_time,
+                duration,
+                running_status: flags >> 5,
+                free_ca_mode: flags & 0x10 != 0,
+                descriptors,
+            });
+        }
+        Ok(Self {
+            transport_stream_id,
+            original_network_id,
+            segment_last_section_num,
+            last_table_id,
+            events,
+        })
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.transport_stream_id.to_be_bytes());
+        out.extend_from_slice(&self.original_network_id.to_be_bytes());
+        out.push(self.segment_last_section_num);
+        out.push(self.last_table_id);
+        for event in self.events.iter() {
+            out.extend_from_slice(&event.event_id.to_be_bytes());
+            match event.start_time.as_ref() {
+                Some(start_time) => out.extend_from_slice(&start_time.to_bytes()),
+                None => out.extend_from_slice(&[0xff; 5]),
+            }
+            out.extend_from_slice(&encode_duration(event.duration));
+            let loop_start = out.len();
+            write_descriptor_loop(&event.descriptors, out);
+            out[loop_start] = (out[loop_start] & 0x0f)
+                | ((event.running_status & 0x7) << 5)
+                | if event.free_ca_mode { 0x10 } else { 0 };
+        }
+    }
+}
+
+/// Checks whether a table ID is of a present/following EIT.
+fn is_eit_present_following(table_id: u8) -> bool {
+    table_id == EIT_PF_ACTUAL_TABLE_ID || table_id == EIT_PF_OTHER_TABLE_ID
+}
+
+/// Identifies a service across networks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EpgServiceKey {
+    /// Network ID of the originating delivery system.
+    pub original_network_id: u16,
+    /// Transport stream ID of the service.
+    pub transport_stream_id: u16,
+    /// Service ID (program number).
+    pub service_id: u16,
+}
+
+/// Program guide of one service collected by [`EpgCollector`].
+#[derive(Debug, Clone, Default)]
+pub struct ServiceGuide {
+    /// Currently running event.
+    pub present: Option<EitEvent>,
+    /// Next event.
+    pub following: Option<EitEvent>,
+    /* Schedule events by (table ID, section number) */
+    schedule_sections: BTreeMap<(u8, u8), Vec<EitEvent>>,
+}
+
+impl ServiceGuide {
+    /// Scheduled events, ordered by start time.
+    pub fn schedule(&self) -> Vec<&EitEvent> {
+        let mut events: Vec<&EitEvent> = self.schedule_sections.values().flatten().collect();
+        events.sort_by_key(|e| e.start_time);
+        events
+    }
+}
+
+/// Aggregates EIT sections into per-service program guides.
+///
+/// Present/following sections replace the present and following events of the service, and
+/// schedule sections replace the events previously collected from the same section.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::dvb::EpgCollector;
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+///
+/// fn collect(parser: &mut MpegTsParser<DefaultAppDetails>, packets: &[[u8; 188]]) {
+///     let mut epg = EpgCollector::default();
+///     for packet in packets.iter() {
+///         if let Ok(packet) = parser.parse(packet) {
+///             epg.add_packet(&packet);
+///         }
+///     }
+///     for (key, guide) in epg.services() {
+///         if let Some(name) = guide.present.as_ref().and_then(|e| e.short_event()) {
+///             println!("Service {}: {}", key.service_id, name.event_name);
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EpgCollector {
+    services: HashMap<EpgServiceKey, ServiceGuide>,
+}
+
+impl EpgCollector {
+    /// Adds a parsed PSI section. Returns `false` if it is not a current EIT section.
+    pub fn add_psi(&mut self, psi: &Psi) -> bool {
+        let (eit, table_syntax) = match (&psi.data, psi.table_syntax.as_ref()) {
+            (PsiData::Eit(eit), Some(table_syntax)) if table_syntax.current_next_indicator() => {
+                (eit, table_syntax)
+            }
+            _ => return false,
+        };
+        let key = EpgServiceKey {
+            original_network_id: eit.original_network_id,
+            transport_stream_id: eit.transport_stream_id,
+            service_id: table_syntax.table_id_extension(),
+        };
+        let guide = self.services.entry(key).or_default();
+        let table_id = psi.header.table_id();
+        if is_eit_present_following(table_id) {
+            let event = eit.events.first().cloned();
+            match table_syntax.section_num() {
+                0 => guide.present = event,
+                1 => guide.following = event,
+                _ => {}
+            }
+        } else {
+            guide
+                .schedule_sections
+                .insert((table_id, table_syntax.section_num()), eit.events.clone());
+        }
+        true
+    }
+
+    /// Adds the payload of a parsed packet. Returns `false` if it is not a current EIT section.
+    pub fn add_packet<D: AppDetails>(&mut self, packet: &Packet<D>) -> bool {
+        match packet.payload.as_ref() {
+            Some(Payload::Psi(psi)) => self.add_psi(psi),
+            _ => false,
+        }
+    }
+
+    /// Program guide of a service.
+    pub fn service(&self, key: &EpgServiceKey) -> Option<&ServiceGuide> {
+        self.services.get(key)
+    }
+
+    /// Program guides of all services seen.
+    pub fn services(&self) -> impl Iterator<Item = (&EpgServiceKey, &ServiceGuide)> {
+        self.services.iter()
+    }
+}
+
+/// Entry of the local time offset descriptor (tag 0x58).
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LocalTimeOffset {
+    /// ISO 3166 country code.
+    pub country_code: [u8; 3],
+    /// Time zone region within the country, or 0 for the whole country.
+    pub country_region_id: u8,
+    /// Offset from UTC in minutes.
+    pub offset_minutes: i16,
+    /// Time at which the offset changes to `next_offset_minutes`, if defined.
+    pub time_of_change: Option<DvbTime>,
+    /// Offset from UTC in minutes after `time_of_change`.
+    pub next_offset_minutes: i16,
+}
+
+impl LocalTimeOffset {
+    /// Parses the entries of a local time offset descriptor. Returns [`None`] for other
+    /// descriptors.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Option<Vec<Self>> {
+        if descriptor.tag != LOCAL_TIME_OFFSET_DESCRIPTOR_TAG {
+            return None;
+        }
+        Some(
+            descriptor
+                .data
+                .chunks_exact(13)
+                .map(|entry| {
+                    let negative = entry[3] & 0x1 != 0;
+                    let offset = |hours: u8, minutes: u8| {
+                        let minutes = bcd_byte(hours) as i16 * 60 + bcd_byte(minutes) as i16;
+                        if negative {
+                            -minutes
+                        } else {
+                            minutes
+                        }
+                    };
+                    LocalTimeOffset {
+                        country_code: [entry[0], entry[1], entry[2]],
+                        country_region_id: entry[3] >> 2,
+                        offset_minutes: offset(entry[4], entry[5]),
+                        time_of_change: DvbTime::from_bytes(entry[6..11].try_into().unwrap()),
+                        next_offset_minutes: offset(entry[11], entry[12]),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Encodes a local time offset descriptor listing `entries`.
+    ///
+    /// The polarity of each entry is taken from `offset_minutes`.
+    pub fn to_descriptor(entries: &[Self]) -> Descriptor {
+        let mut data = Vec::new();
+        for entry in entries.iter() {
+            let negative = entry.offset_minutes < 0;
+            let offset = |minutes: i16| {
+                let minutes = minutes.unsigned_abs();
+                [
+                    to_bcd_byte((minutes / 60) as u8),
+                    to_bcd_byte((minutes % 60) as u8),
+                ]
+            };
+            data.extend_from_slice(&entry.country_code);
+            data.push((entry.country_region_id << 2) | 0x2 | negative as u8);
+            data.extend_from_slice(&offset(entry.offset_minutes));
+            data.extend_from_slice(
+                &entry
+                    .time_of_change
+                    .map_or([0xff; 5], |time| time.to_bytes()),
+            );
+            data.extend_from_slice(&offset(entry.next_offset_minutes));
+        }
+        Descriptor::new(LOCAL_TIME_OFFSET_DESCRIPTOR_TAG, &data)
+    }
+}
+
+/// Parsed TOT section.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tot {
+    /// Current UTC time.
+    pub utc_time: DvbTime,
+    /// Descriptors, including local time offset descriptors.
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl Tot {
+    /// Local time offsets of all local time offset descriptors.
+    pub fn local_time_offsets(&self) -> Vec<LocalTimeOffset> {
+        self.descriptors
+            .iter()
+            .filter_map(LocalTimeOffset::from_descriptor)
+            .flatten()
+            .collect()
+    }
+
+    pub(crate) fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let utc_time = read_utc_time(reader)?;
+        let descriptors = read_descriptor_loop(reader)?;
+        Ok(Self {
+            utc_time,
+            descriptors,
+        })
+    }
+
+    pub(crate) fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.utc_time.to_bytes());
+        write_descriptor_loop(&self.descriptors, out);
+    }
+}
+
+/// Reads the UTC time of a TDT or TOT, which must be defined.
+pub(crate) fn read_utc_time<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<DvbTime, D> {
+    match DvbTime::from_bytes(*reader.read_array_ref::<5>()?) {
+        Some(time) => Ok(time),
+        None => {
+            warn!("Undefined UTC time");
+            Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader))
+        }
+    }
+}
+
+#[test]
+fn test_nit() {
+    use crate::{
+        DefaultAppDetails, MpegTsParser, MpegTsWriter, Payload, Psi, PsiData, PsiHeader,
+        PsiTableSyntax,
+    };
+    use smallvec::SmallVec;
+
+    let cable = [
+        0x03, 0x46, 0x00, 0x00, 0xff, 0xf2, 0x03, 0x00, 0x68, 0x75, 0x03,
+    ];
+    let nit = Nit {
+        network_descriptors: vec![Descriptor {
+            tag: NETWORK_NAME_DESCRIPTOR_TAG,
+            data: SmallVec::from_slice(b"Network"),
+        }],
+        transport_streams: vec![NitTransportStream {
+            transport_stream_id: 5,
+            original_network_id: 0x2000,
+            descriptors: vec![Descriptor {
+                tag: CABLE_DELIVERY_DESCRIPTOR_TAG,
+                data: SmallVec::from_slice(&cable),
+            }],
+        }],
+    };
+    let psi = Psi {
+        header: PsiHeader::new()
+            .with_table_id(NIT_ACTUAL_TABLE_ID)
+            .with_section_syntax_indicator(true)
+            .with_private_bit(true)
+            .with_reserved_bits(0x3),
+        table_syntax: Some(
+            PsiTableSyntax::new()
+                .with_table_id_extension(0x2000)
+                .with_reserved_bits(0x3)
+                .with_current_next_indicator(true),
+        ),
+        data: PsiData::Nit(nit),
+    };
+    let mut out = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut out, NIT_PID, &psi.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let packet = parser.parse(out[..188].try_into().unwrap()).unwrap();
+    let nit = match packet.payload {
+        Some(Payload::Psi(Psi {
+            data: PsiData::Nit(nit),
+            ..
+        })) => nit,
+        _ => panic!("NIT not parsed"),
+    };
+    assert_eq!(nit.network_name(), Some(&b"Network"[..]));
+    match nit.transport_streams[0].delivery_system() {
+        Some(DeliverySystem::Cable(cable)) => {
+            assert_eq!(cable.frequency_hz(), 346_000_000);
+            assert_eq!(cable.modulation(), 3);
+            assert_eq!(cable.symbol_rate_sps(), 6_875_000);
+        }
+        _ => panic!("Cable delivery system not parsed"),
+    }
+}
+
+#[test]
+fn test_eit() {
+    use crate::{DefaultAppDetails, MpegTsParser, MpegTsWriter, PsiHeader, PsiTableSyntax};
+    use smallvec::SmallVec;
+
+    let mut short_event = Vec::new();
+    short_event.extend_from_slice(b"eng");
+    short_event.push(4);
+    short_event.extend_from_slice(b"News");
+    short_event.push(8);
+    short_event.extend_from_slice(b"Headline");
+    let event = EitEvent {
+        event_id: 0x100,
+        start_time: DvbTime::from_bytes([0xc0, 0x79, 0x12, 0x45, 0x00]),
+        duration: 6330,
+        running_status: 4,
+        free_ca_mode: false,
+        descriptors: vec![Descriptor {
+            tag: SHORT_EVENT_DESCRIPTOR_TAG,
+            data: SmallVec::from_slice(&short_event),
+        }],
+    };
+    let psi = Psi {
+        header: PsiHeader::new()
+            .with_table_id(EIT_PF_ACTUAL_TABLE_ID)
+            .with_section_syntax_indicator(true)
+            .with_private_bit(true)
+            .with_reserved_bits(0x3),
+        table_syntax: Some(
+            PsiTableSyntax::new()
+                .with_table_id_extension(7)
+                .with_reserved_bits(0x3)
+                .with_current_next_indicator(true)
+                .with_last_section_num(1),
+        ),
+        data: PsiData::Eit(Eit {
+            transport_stream_id: 1,
+            original_network_id: 2,
+            segment_last_section_num: 1,
+            last_table_id: EIT_PF_ACTUAL_TABLE_ID,
+            events: vec![event],
+        }),
+    };
+    let section = psi.to_section();
+    let mut out = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut out, EIT_PID, &psi.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let packet = parser.parse(out[..188].try_into().unwrap()).unwrap();
+    let mut epg = EpgCollector::default();
+    assert!(epg.add_packet(&packet));
+    match packet.payload {
+        Some(Payload::Psi(psi)) => assert_eq!(psi.to_section(), section),
+        _ => panic!("EIT not parsed"),
+    }
+
+    let guide = epg
+        .service(&EpgServiceKey {
+            original_network_id: 2,
+            transport_stream_id: 1,
+            service_id: 7,
+        })
+        .unwrap();
+    let present = guide.present.as_ref().unwrap();
+    assert_eq!(present.start_time.unwrap().date(), (1993, 10, 13));
+    assert_eq!(present.duration, 6330);
+    assert_eq!(present.running_status, 4);
+    let short_event = present.short_event().unwrap();
+    assert_eq!(short_event.event_name, "News");
+    assert_eq!(short_event.text, "Headline");
+    assert!(guide.following.is_none());
+}
+
+#[test]
+fn test_tdt_tot() {
+    use crate::{DefaultAppDetails, MpegTsParser, MpegTsWriter, PsiHeader};
+    use smallvec::SmallVec;
+
+    let time = DvbTime::from_bytes([0xc0, 0x79, 0x12, 0x45, 0x00]).unwrap();
+    let short_header = |table_id| {
+        PsiHeader::new()
+            .with_table_id(table_id)
+            .with_private_bit(true)
+            .with_reserved_bits(0x3)
+    };
+    let tdt = Psi {
+        header: short_header(TDT_TABLE_ID),
+        table_syntax: None,
+        data: PsiData::Tdt(time),
+    };
+    let mut offset = Vec::new();
+    offset.extend_from_slice(b"DEU");
+    offset.extend_from_slice(&[0x02, 0x01, 0x00]);
+    offset.extend_from_slice(&[0xc0, 0x7a, 0x01, 0x00, 0x00]);
+    offset.extend_from_slice(&[0x02, 0x00]);
+    let tot = Psi {
+        header: short_header(TOT_TABLE_ID),
+        table_syntax: None,
+        data: PsiData::Tot(Tot {
+            utc_time: time,
+            descriptors: vec![Descriptor {
+                tag: LOCAL_TIME_OFFSET_DESCRIPTOR_TAG,
+                data: SmallVec::from_slice(&offset),
+            }],
+        }),
+    };
+    let tot_section = tot.to_section();
+    assert_eq!(tdt.to_section().len(), 8);
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    writer
+        .write_unit(&mut out, TDT_PID, &tdt.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, TDT_PID, &tot.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    match parser
+        .parse(out[..188].try_into().unwrap())
+        .unwrap()
+        .payload
+    {
+        Some(Payload::Psi(Psi {
+            data: PsiData::Tdt(time),
+            ..
+        })) => assert_eq!(time.to_calendar().to_string(), "1993-10-13T12:45:00Z"),
+        _ => panic!("TDT not parsed"),
+    }
+    match parser
+        .parse(out[188..].try_into().unwrap())
+        .unwrap()
+        .payload
+    {
+        Some(Payload::Psi(psi)) => {
+            assert_eq!(psi.to_section(), tot_section);
+            let offsets = match psi.data {
+                PsiData::Tot(tot) => tot.local_time_offsets(),
+                _ => panic!("TOT not parsed"),
+            };
+            assert_eq!(&offsets[0].country_code, b"DEU");
+            assert_eq!(offsets[0].offset_minutes, 60);
+            assert_eq!(offsets[0].next_offset_minutes, 120);
+            assert_eq!(offsets[0].time_of_change.unwrap().mjd, 0xc07a);
+        }
+        _ => panic!("TOT not parsed"),
+    }
+}
+
+#[test]
+fn test_decode_text() {
+    use crate::DefaultAppDetails;
+
+    assert_eq!(ISO_8859_2_UPPER.chars().count(), 96);
+    assert_eq!(ISO_6937_UPPER.chars().count(), 96);
+    for (_, bases, composed) in ISO_6937_COMPOSED.iter() {
+        assert_eq!(bases.chars().count(), composed.chars().count());
+    }
+
+    /* Default table with a diacritical mark lacking a precomposed form */
+    assert_eq!(
+        decode_text(b"\xa9Fran\xcbcois\xb9 \xc8x"),
+        "‘François’ x\u{308}"
+    );
+    assert_eq!(decode_text(b"\x03\xc1\xe8\xe7\xdc"), "Αθηά");
+    assert_eq!(decode_text(b"\x05\xdeeker"), "Şeker");
+    assert_eq!(decode_text(b"\x0b\xa4 5"), "€ 5");
+    assert_eq!(decode_text(b"\x11\x00A\xe0\x8a\x00B"), "A\nB");
+    assert_eq!(decode_text(b"\x13\xe9"), "é");
+
+    let data = b"\x03\xc1\xe8\xe7\xdcrest";
+    let mut reader = SliceReader::<DefaultAppDetails>::new(data);
+    assert_eq!(reader.read_dvb_string(5).unwrap(), "Αθηά");
+    assert!(reader.read_dvb_string(5).is_err());
+}
+
+#[test]
+fn test_dvb_descriptors() {
+    use crate::descriptor::KnownDescriptor;
+
+    let parse = |tag: u8, data: &[u8]| {
+        Descriptor {
+            tag,
+            data: data.iter().copied().collect(),
+        }
+        .parse()
+    };
+
+    match parse(0x48, b"\x01\x03ARD\x09Das Erste") {
+        KnownDescriptor::Service(service) => {
+            assert_eq!(service.service_type, 1);
+            assert_eq!(service.provider_name, "ARD");
+            assert_eq!(service.service_name, "Das Erste");
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x50, b"\x02\x03\x11gerStereo") {
+        KnownDescriptor::Component(component) => {
+            assert_eq!(component.stream_content, 2);
+            assert_eq!(component.component_tag, 0x11);
+            assert_eq!(component.text, "Stereo");
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x56, b"ger\x11\x50eng\x2a\x88") {
+        KnownDescriptor::Teletext(entries) => {
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].teletext_type, 2);
+            assert_eq!(entries[0].magazine_number, 1);
+            assert_eq!(entries[1].teletext_type, 5);
+            assert_eq!(entries[1].magazine_number, 2);
+            assert_eq!(entries[1].page_number, 0x88);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x59, b"deu\x10\x00\x01\x00\x02") {
+        KnownDescriptor::Subtitling(entries) => {
+            assert_eq!(entries[0].language, LanguageCode::from_bytes(*b"deu"));
+            assert_eq!(entries[0].composition_page_id, 1);
+            assert_eq!(entries[0].ancillary_page_id, 2);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    match parse(0x54, &[0x43, 0x00]) {
+        KnownDescriptor::Content(entries) => {
+            assert_eq!(entries[0].content_nibble_level_1, 4);
+            assert_eq!(entries[0].content_nibble_level_2, 3);
+        }
+        other => panic!("Unexpected descriptor {:?}", other),
+    }
+    assert!(matches!(
+        parse(0x52, &[0x21]),
+        KnownDescriptor::StreamIdentifier(0x21)
+    ));
+    assert!(matches!(
+        parse(0x48, b"\x01\x09ARD"),
+        KnownDescriptor::Unknown(0x48, _)
+    ));
+}
@@ -0,0 +1,213 @@
+//! Module for AAC audio carried in PES units with ADTS framing (ISO/IEC 13818-7).
+//!
+//! [`AacUnit`] splits PES payloads into ADTS frames, whose headers describe the profile, sample
+//! rate and channel configuration of the stream.
+
+use crate::prelude::*;
+use crate::{AppDetails, MpegTsParser, PesUnitObject, Result};
+use core::convert::TryInto;
+use core::fmt::{Debug, Formatter};
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+
+/// Sample rates of the `sampling_frequency_index` values 0 to 12.
+const SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+/// Fixed and variable header of an ADTS frame.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct AdtsHeader {
+    pub syncword: B12,
+    pub mpeg2: bool,
+    pub layer: B2,
+    pub protection_absent: bool,
+    pub profile: B2,
+    pub sampling_frequency_index: B4,
+    pub private_bit: bool,
+    pub channel_configuration: B3,
+    pub original: bool,
+    pub home: bool,
+    pub copyright_identification_bit: bool,
+    pub copyright_identification_start: bool,
+    pub frame_length: B13,
+    pub buffer_fullness: B11,
+    pub num_raw_data_blocks: B2,
+}
+
+impl AdtsHeader {
+    /// Audio object type, e.g. 2 = AAC LC.
+    pub fn audio_object_type(&self) -> u8 {
+        self.profile() + 1
+    }
+
+    /// Sample rate in Hz, if the index is not reserved.
+    pub fn sample_rate(&self) -> Option<u32> {
+        SAMPLE_RATES
+            .get(self.sampling_frequency_index() as usize)
+            .copied()
+    }
+
+    /// Number of channels, if the configuration is not signaled in the raw data instead.
+    pub fn channels(&self) -> Option<u8> {
+        match self.channel_configuration() {
+            0 => None,
+            7 => Some(8),
+            count => Some(count),
+        }
+    }
+
+    /// Number of PCM samples per channel of the frame.
+    pub fn samples(&self) -> u32 {
+        1024 * (self.num_raw_data_blocks() as u32 + 1)
+    }
+}
+
+/// One ADTS frame.
+#[derive(Clone)]
+pub struct AdtsFrame {
+    /// Frame header.
+    pub header: AdtsHeader,
+    /// CRC of the frame, if protected.
+    pub crc: Option<u16>,
+    /// Raw data blocks following the header.
+    pub data: Vec<u8>,
+}
+
+impl Debug for AdtsFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AdtsFrame")
+            .field("header", &self.header)
+            .field("crc", &self.crc)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Splits ADTS data into frames, skipping garbage between frames and dropping a truncated
+/// final frame.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::aac::split_adts;
+/// let frame = [0xff, 0xf1, 0x50, 0x80, 0x01, 0x1f, 0xfc, 0x21];
+/// let frames = split_adts(&frame);
+/// assert_eq!(frames[0].header.sample_rate(), Some(44100));
+/// assert_eq!(frames[0].header.channels(), Some(2));
+/// assert_eq!(frames[0].data, [0x21]);
+/// ```
+pub fn split_adts(data: &[u8]) -> Vec<AdtsFrame> {
+    let mut frames = Vec::new();
+    let mut pos = 0;
+    while pos + 7 <= data.len() {
+        if data[pos] != 0xff || data[pos + 1] & 0xf0 != 0xf0 {
+            pos += 1;
+            continue;
+        }
+        let header = AdtsHeader::from_bytes(data[pos..pos + 7].try_into().unwrap());
+        let header_length = if header.protection_absent() { 7 } else { 9 };
+        let frame_length = header.frame_length() as usize;
+        if frame_length < header_length {
+            warn!("Invalid ADTS frame length");
+            pos += 1;
+            continue;
+        }
+        if pos + frame_length > data.len() {
+            warn!("Truncated ADTS frame");
+            break;
+        }
+        let crc = if header.protection_absent() {
+            None
+        } else {
+            Some(u16::from_be_bytes([data[pos + 7], data[pos + 8]]))
+        };
+        frames.push(AdtsFrame {
+            header,
+            crc,
+            data: data[pos + header_length..pos + frame_length].to_vec(),
+        });
+        pos += frame_length;
+    }
+    frames
+}
+
+/// AAC PES unit split into [`AdtsFrame`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) like
+/// [`H264Unit`](super::h264::H264Unit), e.g. for PIDs of stream type
+/// [`StreamType::AdtsAac`](crate::StreamType::AdtsAac).
+pub struct AacUnit {
+    raw: Vec<u8>,
+    /// ADTS frames contained in the unit once finished.
+    pub frames: Vec<AdtsFrame>,
+}
+
+impl AacUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Header of the first frame, describing the stream properties.
+    pub fn header(&self) -> Option<&AdtsHeader> {
+        self.frames.first().map(|frame| &frame.header)
+    }
+
+    /// Number of PCM samples per channel of all frames.
+    pub fn samples(&self) -> u32 {
+        self.frames.iter().map(|frame| frame.header.samples()).sum()
+    }
+}
+
+impl Debug for AacUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AacUnit")
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for AacUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.frames = split_adts(&self.raw);
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_adts_frames() {
+    use crate::DefaultAppDetails;
+
+    /* AAC LC, 48 kHz, 5.1 channels, protected by CRC */
+    let mut data = vec![0x00, 0x12];
+    data.extend_from_slice(&[
+        0xff, 0xf0, 0x4d, 0x80, 0x01, 0x7f, 0xfc, 0xab, 0xcd, 0x01, 0x02,
+    ]);
+    data.extend_from_slice(&[0xff, 0xf1, 0x4d, 0x80, 0x01, 0x1f, 0xfc, 0x03]);
+    /* Truncated frame */
+    data.extend_from_slice(&[0xff, 0xf1, 0x4d, 0x80, 0x02, 0x1f, 0xfc]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = AacUnit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.frames.len(), 2);
+    let header = unit.header().unwrap();
+    assert_eq!(header.audio_object_type(), 2);
+    assert_eq!(header.sample_rate(), Some(48000));
+    assert_eq!(header.channels(), Some(6));
+    assert_eq!(unit.frames[0].crc, Some(0xabcd));
+    assert_eq!(unit.frames[0].data, [0x01, 0x02]);
+    assert_eq!(unit.frames[1].data, [0x03]);
+    assert_eq!(unit.samples(), 2048);
+}
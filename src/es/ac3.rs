@@ -0,0 +1,336 @@
+//! Module for Dolby Digital (AC-3) and Dolby Digital Plus (E-AC-3) audio carried in PES units
+//! (ATSC A/52).
+//!
+//! [`Ac3Unit`] splits PES payloads into syncframes, whose sync info and bit stream information
+//! describe the sample rate, bitrate, channel layout and dialogue level of the stream.
+
+use crate::prelude::*;
+use crate::{AppDetails, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Bitrates in kbit/s of each pair of AC-3 `frmsizecod` values.
+const AC3_BITRATES: [u32; 19] = [
+    32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640,
+];
+
+/// Sample rates of the `fscod` values 0 to 2.
+const SAMPLE_RATES: [u32; 3] = [48000, 44100, 32000];
+
+/// Number of audio blocks of the E-AC-3 `numblkscod` values.
+const EAC3_BLOCKS: [u32; 4] = [1, 2, 3, 6];
+
+/// Number of full-bandwidth channels of each `acmod` value.
+const ACMOD_CHANNELS: [u8; 8] = [2, 1, 2, 3, 3, 4, 4, 5];
+
+/// Channel layout of a syncframe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AudioCodingMode {
+    /// Two independent mono channels (1+1).
+    DualMono,
+    /// Center (1/0).
+    Mono,
+    /// Left, right (2/0).
+    Stereo,
+    /// Left, center, right (3/0).
+    ThreeFront,
+    /// Left, right, surround (2/1).
+    StereoSurround,
+    /// Left, center, right, surround (3/1).
+    ThreeFrontSurround,
+    /// Left, right, left surround, right surround (2/2).
+    Quadraphonic,
+    /// Left, center, right, left surround, right surround (3/2).
+    ThreeFrontTwoSurround,
+}
+
+impl AudioCodingMode {
+    fn from_acmod(acmod: u8) -> Self {
+        match acmod & 0x7 {
+            0 => AudioCodingMode::DualMono,
+            1 => AudioCodingMode::Mono,
+            2 => AudioCodingMode::Stereo,
+            3 => AudioCodingMode::ThreeFront,
+            4 => AudioCodingMode::StereoSurround,
+            5 => AudioCodingMode::ThreeFrontSurround,
+            6 => AudioCodingMode::Quadraphonic,
+            _ => AudioCodingMode::ThreeFrontTwoSurround,
+        }
+    }
+
+    /// Number of full-bandwidth channels.
+    pub fn channels(&self) -> u8 {
+        ACMOD_CHANNELS[*self as usize]
+    }
+}
+
+/// Sync info and bit stream information of an AC-3 or E-AC-3 syncframe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Ac3FrameInfo {
+    /// Bit stream identification; 8 or lower for AC-3, 11 to 16 for E-AC-3.
+    pub bsid: u8,
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+    /// Size of the syncframe in bytes.
+    pub frame_size: usize,
+    /// Number of PCM samples per channel of the syncframe.
+    pub samples: u32,
+    /// Channel layout.
+    pub acmod: AudioCodingMode,
+    /// Set if the low frequency effects channel is present.
+    pub lfeon: bool,
+    /// Dialogue normalization value 1 to 31, meaning -1 to -31 dBFS; 0 is reserved.
+    pub dialnorm: u8,
+    /// Bit stream mode (AC-3 only), e.g. 0 = complete main.
+    pub bsmod: Option<u8>,
+    /// Stream type (E-AC-3 only): 0 = independent, 1 = dependent, 2 = AC-3 converted.
+    pub strmtyp: Option<u8>,
+    /// Substream ID (E-AC-3 only).
+    pub substreamid: Option<u8>,
+}
+
+impl Ac3FrameInfo {
+    /// Parses the header of a syncframe starting with the sync word.
+    ///
+    /// Returns [`None`] if the sync word is missing or the header uses reserved values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::es::ac3::{Ac3FrameInfo, AudioCodingMode};
+    /// let header = [0x0b, 0x77, 0, 0, 0x1e, 0x40, 0xe1, 0xff];
+    /// let info = Ac3FrameInfo::parse(&header).unwrap();
+    /// assert_eq!(info.sample_rate, 48000);
+    /// assert_eq!(info.bitrate(), 448000);
+    /// assert_eq!(info.acmod, AudioCodingMode::ThreeFrontTwoSurround);
+    /// assert_eq!(info.channels(), 6);
+    /// ```
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[0] != 0x0b || data[1] != 0x77 {
+            return None;
+        }
+        let bsid = data[5] >> 3;
+        if bsid > 10 {
+            Self::parse_eac3(data, bsid)
+        } else {
+            Self::parse_ac3(data, bsid)
+        }
+    }
+
+    fn parse_ac3(data: &[u8], bsid: u8) -> Option<Self> {
+        let fscod = data[4] >> 6;
+        let frmsizecod = data[4] & 0x3f;
+        let sample_rate = *SAMPLE_RATES.get(fscod as usize)?;
+        let bitrate = *AC3_BITRATES.get(frmsizecod as usize / 2)?;
+        let words = match fscod {
+            0 => bitrate * 2,
+            1 => bitrate * 2 * 48000 / 44100 + (frmsizecod as u32 & 0x1),
+            _ => bitrate * 3,
+        };
+
+        /* Skip the mix levels present for the channel layout to reach lfeon and dialnorm */
+        let acmod = data[6] >> 5;
+        let mut bit = 3;
+        if acmod & 0x1 != 0 && acmod != 1 {
+            bit += 2;
+        }
+        if acmod & 0x4 != 0 {
+            bit += 2;
+        }
+        if acmod == 2 {
+            bit += 2;
+        }
+        let bits = u16::from_be_bytes([data[6], data[7]]);
+        let lfeon = bits & (0x8000 >> bit) != 0;
+        let dialnorm = ((bits << (bit + 1)) >> 11) as u8;
+
+        Some(Self {
+            bsid,
+            sample_rate,
+            frame_size: words as usize * 2,
+            samples: 1536,
+            acmod: AudioCodingMode::from_acmod(acmod),
+            lfeon,
+            dialnorm,
+            bsmod: Some(data[5] & 0x7),
+            strmtyp: None,
+            substreamid: None,
+        })
+    }
+
+    fn parse_eac3(data: &[u8], bsid: u8) -> Option<Self> {
+        let frmsiz = u16::from_be_bytes([data[2], data[3]]) & 0x7ff;
+        let fscod = data[4] >> 6;
+        let (sample_rate, blocks) = if fscod == 3 {
+            let fscod2 = (data[4] >> 4) & 0x3;
+            (*SAMPLE_RATES.get(fscod2 as usize)? / 2, 6)
+        } else {
+            (
+                SAMPLE_RATES[fscod as usize],
+                EAC3_BLOCKS[(data[4] >> 4) as usize & 0x3],
+            )
+        };
+        Some(Self {
+            bsid,
+            sample_rate,
+            frame_size: (frmsiz as usize + 1) * 2,
+            samples: 256 * blocks,
+            acmod: AudioCodingMode::from_acmod((data[4] >> 1) & 0x7),
+            lfeon: data[4] & 0x1 != 0,
+            dialnorm: (u16::from_be_bytes([data[5], data[6]]) >> 6) as u8 & 0x1f,
+            bsmod: None,
+            strmtyp: Some(data[2] >> 6),
+            substreamid: Some((data[2] >> 3) & 0x7),
+        })
+    }
+
+    /// Checks whether the syncframe is E-AC-3.
+    pub fn is_eac3(&self) -> bool {
+        self.bsid > 10
+    }
+
+    /// Bitrate in bits per second.
+    pub fn bitrate(&self) -> u32 {
+        (self.frame_size as u64 * 8 * self.sample_rate as u64 / self.samples as u64) as u32
+    }
+
+    /// Total number of channels, including the low frequency effects channel.
+    pub fn channels(&self) -> u8 {
+        self.acmod.channels() + self.lfeon as u8
+    }
+
+    /// Dialogue level in dBFS.
+    pub fn dialogue_level(&self) -> i8 {
+        match self.dialnorm {
+            0 => -31,
+            dialnorm => -(dialnorm as i8),
+        }
+    }
+}
+
+/// One AC-3 or E-AC-3 syncframe.
+#[derive(Clone)]
+pub struct Ac3Frame {
+    /// Header of the syncframe.
+    pub info: Ac3FrameInfo,
+    /// Complete syncframe data, starting with the sync word.
+    pub data: Vec<u8>,
+}
+
+impl Debug for Ac3Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ac3Frame")
+            .field("info", &self.info)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Splits AC-3 or E-AC-3 data into syncframes, skipping garbage between frames and dropping a
+/// truncated final frame.
+pub fn split_syncframes(data: &[u8]) -> Vec<Ac3Frame> {
+    let mut frames = Vec::new();
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let info = match Ac3FrameInfo::parse(&data[pos..]) {
+            Some(info) => info,
+            None => {
+                pos += 1;
+                continue;
+            }
+        };
+        if pos + info.frame_size > data.len() {
+            warn!("Truncated AC-3 syncframe");
+            break;
+        }
+        frames.push(Ac3Frame {
+            info,
+            data: data[pos..pos + info.frame_size].to_vec(),
+        });
+        pos += info.frame_size;
+    }
+    frames
+}
+
+/// AC-3 or E-AC-3 PES unit split into [`Ac3Frame`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) like
+/// [`H264Unit`](super::h264::H264Unit), e.g. for PIDs of stream type
+/// [`StreamType::Ac3`](crate::StreamType::Ac3) or [`StreamType::Eac3`](crate::StreamType::Eac3).
+pub struct Ac3Unit {
+    raw: Vec<u8>,
+    /// Syncframes contained in the unit once finished.
+    pub frames: Vec<Ac3Frame>,
+}
+
+impl Ac3Unit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Header of the first syncframe, describing the stream properties.
+    pub fn info(&self) -> Option<&Ac3FrameInfo> {
+        self.frames.first().map(|frame| &frame.info)
+    }
+}
+
+impl Debug for Ac3Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ac3Unit")
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for Ac3Unit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.frames = split_syncframes(&self.raw);
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_ac3_syncframes() {
+    use crate::DefaultAppDetails;
+
+    /* AC-3 stereo at 44.1 kHz and 192 kbit/s: 418 words with odd frmsizecod 0x15 */
+    let mut ac3 = vec![0; 836];
+    ac3[..8].copy_from_slice(&[0x0b, 0x77, 0, 0, 0x55, 0x40, 0x43, 0x00]);
+    let frames = split_syncframes(&ac3);
+    assert_eq!(frames.len(), 1);
+    let info = &frames[0].info;
+    assert_eq!(info.sample_rate, 44100);
+    assert_eq!(info.frame_size, 836);
+    assert_eq!(info.bsmod, Some(0));
+    assert_eq!(info.acmod, AudioCodingMode::Stereo);
+    assert!(!info.lfeon);
+    assert_eq!(info.dialogue_level(), -24);
+
+    /* E-AC-3 5.1 at 48 kHz with 6 blocks, 768 bytes: 192 kbit/s */
+    let mut eac3 = vec![0x12, 0x34];
+    let mut frame = vec![0; 768];
+    frame[..8].copy_from_slice(&[0x0b, 0x77, 0x01, 0x7f, 0x3f, 0x80, 0x80, 0x00]);
+    eac3.extend_from_slice(&frame);
+    eac3.extend_from_slice(&frame[..100]);
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = Ac3Unit::new(eac3.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &eac3);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.frames.len(), 1);
+    let info = unit.info().unwrap();
+    assert!(info.is_eac3());
+    assert_eq!(info.strmtyp, Some(0));
+    assert_eq!(info.sample_rate, 48000);
+    assert_eq!(info.bitrate(), 192000);
+    assert_eq!(info.channels(), 6);
+    assert_eq!(info.dialogue_level(), -2);
+}
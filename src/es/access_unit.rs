@@ -0,0 +1,370 @@
+//! Module for grouping demuxed elementary stream data into complete access units.
+
+use super::aac::AdtsHeader;
+use super::ac3::Ac3FrameInfo;
+use crate::prelude::*;
+use core::convert::TryInto;
+use core::fmt::{Debug, Formatter};
+
+/// How access unit boundaries are found in the elementary stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccessUnitFraming {
+    /// Every PES unit contains exactly one access unit (e.g. subtitles). Each unit is marked as a
+    /// random access point.
+    PesAligned,
+    /// H.264 Annex B byte stream. Access units start with an access unit delimiter, SEI or
+    /// parameter set NAL unit, or the first slice of a picture, following a slice of the
+    /// previous picture. IDR slices mark random access points.
+    H264,
+    /// H.265 Annex B byte stream. Access units start like [`AccessUnitFraming::H264`] with the
+    /// H.265 NAL unit types. IRAP pictures mark random access points.
+    Hevc,
+    /// ADTS AAC audio split into frames by their headers, which may span PES units. Each frame
+    /// is marked as a random access point.
+    Adts,
+    /// AC-3 or E-AC-3 audio split into syncframes like [`AccessUnitFraming::Adts`].
+    Ac3,
+}
+
+/// Result of looking for an audio frame at the start of the buffer.
+enum AudioFrame {
+    /// A complete frame of the given length is buffered.
+    Complete(usize),
+    /// The frame header or body is not complete yet.
+    Incomplete,
+    /// The buffer does not start with a frame header.
+    NoSync,
+}
+
+impl AccessUnitFraming {
+    /// Classifies a NAL unit starting with its header as a VCL NAL unit, a NAL unit beginning a
+    /// new access unit after a VCL NAL unit, and a random access point.
+    ///
+    /// `nal` holds at least [`AccessUnitFraming::nal_prefix_len`] bytes.
+    fn classify_nal(&self, nal: &[u8]) -> (bool, bool, bool) {
+        match self {
+            AccessUnitFraming::H264 => {
+                let nal_type = nal[0] & 0x1f;
+                let is_vcl = (1..=5).contains(&nal_type);
+                /* first_mb_in_slice is 0 if its exp-Golomb code starts with a set bit */
+                let first_slice = is_vcl && nal[1] & 0x80 != 0;
+                let starts_unit = matches!(nal_type, 6..=9 | 14..=18) || first_slice;
+                (is_vcl, starts_unit, nal_type == 5)
+            }
+            AccessUnitFraming::Hevc => {
+                let nal_type = (nal[0] >> 1) & 0x3f;
+                let is_vcl = nal_type <= 31;
+                /* first_slice_segment_in_pic_flag follows the 2-byte header */
+                let first_slice = is_vcl && nal[2] & 0x80 != 0;
+                let starts_unit =
+                    matches!(nal_type, 32..=35 | 39 | 41..=44 | 48..=55) || first_slice;
+                (is_vcl, starts_unit, (16..=23).contains(&nal_type))
+            }
+            _ => (false, false, false),
+        }
+    }
+
+    /// Number of NAL unit bytes needed by [`AccessUnitFraming::classify_nal`].
+    fn nal_prefix_len(&self) -> usize {
+        match self {
+            AccessUnitFraming::Hevc => 3,
+            _ => 2,
+        }
+    }
+
+    /// Looks for an audio frame at the start of `data`.
+    fn audio_frame(&self, data: &[u8]) -> AudioFrame {
+        let length = match self {
+            AccessUnitFraming::Adts => {
+                if data.len() < 7 {
+                    return AudioFrame::Incomplete;
+                }
+                if data[0] != 0xff || data[1] & 0xf0 != 0xf0 {
+                    return AudioFrame::NoSync;
+                }
+                let header = AdtsHeader::from_bytes(data[..7].try_into().unwrap());
+                let header_length = if header.protection_absent() { 7 } else { 9 };
+                let length = header.frame_length() as usize;
+                if length < header_length {
+                    return AudioFrame::NoSync;
+                }
+                length
+            }
+            AccessUnitFraming::Ac3 => {
+                if data.len() < 8 {
+                    return AudioFrame::Incomplete;
+                }
+                match Ac3FrameInfo::parse(data) {
+                    Some(info) if info.frame_size > 0 => info.frame_size,
+                    _ => return AudioFrame::NoSync,
+                }
+            }
+            _ => return AudioFrame::NoSync,
+        };
+        if data.len() < length {
+            AudioFrame::Incomplete
+        } else {
+            AudioFrame::Complete(length)
+        }
+    }
+
+    /// First byte of the audio frame sync word.
+    fn audio_sync_byte(&self) -> u8 {
+        match self {
+            AccessUnitFraming::Ac3 => 0x0b,
+            _ => 0xff,
+        }
+    }
+}
+
+/// Decoder-ready elementary stream data of one access unit.
+#[derive(Clone)]
+pub struct AccessUnit {
+    /// Presentation time stamp of the PES unit in which the access unit started.
+    pub pts: Option<u64>,
+    /// Decoder time stamp of the PES unit in which the access unit started.
+    pub dts: Option<u64>,
+    /// Decoding may start at this access unit.
+    pub random_access: bool,
+    /// Elementary stream data, including any delimiter.
+    pub data: Vec<u8>,
+}
+
+impl Debug for AccessUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AccessUnit")
+            .field("pts", &self.pts)
+            .field("dts", &self.dts)
+            .field("random_access", &self.random_access)
+            .field("data.len()", &self.data.len())
+            .finish()
+    }
+}
+
+/// Timestamps of a PES unit whose data begins at `offset` within the collector buffer.
+#[derive(Debug)]
+struct PesTimestamps {
+    offset: isize,
+    pts: Option<u64>,
+    dts: Option<u64>,
+}
+
+/// Collects the data of consecutive PES units of one PID and splits it into [`AccessUnit`]s.
+///
+/// Following ISO/IEC 13818-1, the timestamps of a PES unit are applied to the first access unit
+/// that starts within it. Data preceding the first audio frame header, or between frames, is
+/// dropped.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::access_unit::{AccessUnitCollector, AccessUnitFraming};
+/// let mut collector = AccessUnitCollector::new(AccessUnitFraming::H264);
+/// let aud = [0, 0, 0, 1, 0x09, 0xf0];
+/// let idr = [0, 0, 1, 0x65, 0x88];
+/// assert!(collector.push(Some(0), None, &[&aud[..], &idr[..]].concat()).is_empty());
+/// let units = collector.push(Some(3003), None, &aud);
+/// assert_eq!(units.len(), 1);
+/// assert_eq!(units[0].pts, Some(0));
+/// assert!(units[0].random_access);
+/// assert_eq!(collector.flush().unwrap().pts, Some(3003));
+/// ```
+#[derive(Debug)]
+pub struct AccessUnitCollector {
+    framing: AccessUnitFraming,
+    buffer: Vec<u8>,
+    scan_pos: usize,
+    random_access: bool,
+    /// A VCL NAL unit of the access unit in progress was found.
+    vcl_seen: bool,
+    timestamps: Vec<PesTimestamps>,
+}
+
+impl AccessUnitCollector {
+    /// Creates an empty collector for the given framing.
+    pub fn new(framing: AccessUnitFraming) -> Self {
+        Self {
+            framing,
+            buffer: Vec::new(),
+            scan_pos: 0,
+            random_access: false,
+            vcl_seen: false,
+            timestamps: Vec::new(),
+        }
+    }
+
+    /// Framing used to find access unit boundaries.
+    pub fn framing(&self) -> AccessUnitFraming {
+        self.framing
+    }
+
+    /// Adds the data of one complete PES unit and returns the access units it completed.
+    pub fn push(&mut self, pts: Option<u64>, dts: Option<u64>, data: &[u8]) -> Vec<AccessUnit> {
+        if self.framing == AccessUnitFraming::PesAligned {
+            return vec![AccessUnit {
+                pts,
+                dts,
+                random_access: true,
+                data: data.to_vec(),
+            }];
+        }
+
+        if pts.is_some() || dts.is_some() {
+            self.timestamps.push(PesTimestamps {
+                offset: self.buffer.len() as isize,
+                pts,
+                dts,
+            });
+        }
+        self.buffer.extend_from_slice(data);
+
+        if matches!(
+            self.framing,
+            AccessUnitFraming::Adts | AccessUnitFraming::Ac3
+        ) {
+            return self.split_audio_frames();
+        }
+
+        let mut out = Vec::new();
+        let prefix_len = self.framing.nal_prefix_len();
+        while self.scan_pos + 3 + prefix_len <= self.buffer.len() {
+            let pos = self.scan_pos;
+            if self.buffer[pos..pos + 3] != [0, 0, 1] {
+                self.scan_pos += 1;
+                continue;
+            }
+            self.scan_pos += 3;
+            let (is_vcl, starts_unit, is_random_access) =
+                self.framing.classify_nal(&self.buffer[pos + 3..]);
+            if starts_unit && self.vcl_seen {
+                /* Include the leading zero of 4-byte start codes */
+                let split = if pos > 0 && self.buffer[pos - 1] == 0 {
+                    pos - 1
+                } else {
+                    pos
+                };
+                out.push(self.take_unit(split));
+            }
+            self.vcl_seen |= is_vcl;
+            self.random_access |= is_random_access;
+        }
+        out
+    }
+
+    /// Takes all complete audio frames off the buffer, dropping data without a frame header.
+    fn split_audio_frames(&mut self) -> Vec<AccessUnit> {
+        let mut out = Vec::new();
+        loop {
+            match self.framing.audio_frame(&self.buffer) {
+                AudioFrame::Complete(length) => {
+                    self.random_access = true;
+                    out.push(self.take_unit(length));
+                }
+                AudioFrame::Incomplete => break,
+                AudioFrame::NoSync => {
+                    /* Resume at the next candidate sync byte */
+                    let sync_byte = self.framing.audio_sync_byte();
+                    let skip = self.buffer[1..]
+                        .iter()
+                        .position(|b| *b == sync_byte)
+                        .map_or(self.buffer.len(), |pos| pos + 1);
+                    self.discard(skip);
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the access unit in progress, if any. Intended for the end of the stream.
+    pub fn flush(&mut self) -> Option<AccessUnit> {
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(self.take_unit(self.buffer.len()))
+        }
+    }
+
+    /// Drops the first `len` buffered bytes, keeping the timestamps of PES units starting in them
+    /// for the next access unit.
+    fn discard(&mut self, len: usize) {
+        for ts in self.timestamps.iter_mut() {
+            ts.offset -= len as isize;
+        }
+        self.buffer.drain(..len);
+        self.scan_pos -= len.min(self.scan_pos);
+    }
+
+    /// Removes the first `len` buffered bytes as one access unit.
+    fn take_unit(&mut self, len: usize) -> AccessUnit {
+        /* The unit starts at offset 0; the last PES starting at or before it owns the timestamps */
+        let claimed = self.timestamps.iter().rposition(|ts| ts.offset <= 0);
+        let (pts, dts) = match claimed {
+            Some(idx) => {
+                let ts = &self.timestamps[idx];
+                let out = (ts.pts, ts.dts);
+                self.timestamps.drain(..=idx);
+                out
+            }
+            None => (None, None),
+        };
+        for ts in self.timestamps.iter_mut() {
+            ts.offset -= len as isize;
+        }
+
+        let data = self.buffer.drain(..len).collect();
+        self.scan_pos -= len.min(self.scan_pos);
+        self.vcl_seen = false;
+        AccessUnit {
+            pts,
+            dts,
+            random_access: core::mem::take(&mut self.random_access),
+            data,
+        }
+    }
+}
+
+#[test]
+fn test_access_unit_boundaries() {
+    /* H.264 without access unit delimiters: SPS, PPS and two slices of an IDR picture, then
+     * a P picture */
+    let sps = [0, 0, 0, 1, 0x67, 0x64, 0x00, 0x28];
+    let pps = [0, 0, 0, 1, 0x68, 0xee];
+    let idr_first = [0, 0, 1, 0x65, 0x88, 0x80];
+    let idr_second = [0, 0, 1, 0x65, 0x40, 0x80];
+    let p_slice = [0, 0, 1, 0x41, 0x9a, 0x00];
+    let mut collector = AccessUnitCollector::new(AccessUnitFraming::H264);
+    let idr = [&sps[..], &pps, &idr_first, &idr_second].concat();
+    assert!(collector.push(Some(0), None, &idr).is_empty());
+    /* The SPS after the P slice starts the next access unit within the same PES unit */
+    let units = collector.push(Some(3003), None, &[&p_slice[..], &sps].concat());
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0].data, idr);
+    assert_eq!(units[0].pts, Some(0));
+    assert!(units[0].random_access);
+    assert_eq!(units[1].data, p_slice);
+    assert_eq!(units[1].pts, Some(3003));
+    assert!(!units[1].random_access);
+    assert!(collector
+        .push(None, None, &[&pps[..], &idr_first].concat())
+        .is_empty());
+    let last = collector.flush().unwrap();
+    assert_eq!(last.data, [&sps[..], &pps, &idr_first].concat());
+    assert!(last.random_access);
+
+    /* Three ADTS frames split over two PES units behind some garbage */
+    let frame = [0xff, 0xf1, 0x50, 0x80, 0x01, 0x1f, 0xfc, 0x21];
+    let data = [&[0x00, 0xff, 0x12][..], &frame, &frame, &frame].concat();
+    let mut collector = AccessUnitCollector::new(AccessUnitFraming::Adts);
+    let units = collector.push(Some(0), None, &data[..15]);
+    assert_eq!(units.len(), 1);
+    assert_eq!(units[0].data, frame);
+    assert_eq!(units[0].pts, Some(0));
+    let units = collector.push(Some(3840), None, &data[15..]);
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0].pts, None);
+    assert_eq!(units[1].pts, Some(3840));
+    assert!(units
+        .iter()
+        .all(|unit| unit.random_access && unit.data == frame));
+    assert!(collector.flush().is_none());
+}
@@ -0,0 +1,227 @@
+//! Module for SMPTE ST 2038 ancillary data carried in PES units.
+//!
+//! Contribution feeds carry SDI vertical ancillary data such as CEA-708 captions, AFD and
+//! SCTE-104 messages as [`AncPacket`] objects within private PES streams registered as `VANC`.
+
+use crate::prelude::*;
+use crate::{AppDetails, BitReader, EsStreamInfo, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+
+/// Format identifier of ST 2038 ancillary data in registration descriptors.
+pub const ANC_FORMAT_IDENTIFIER: [u8; 4] = *b"VANC";
+
+/// Checks whether the PMT signals the stream as ST 2038 ancillary data with a `VANC` registration
+/// descriptor.
+pub fn is_anc_stream(info: &EsStreamInfo) -> bool {
+    super::is_metadata_stream(info, ANC_FORMAT_IDENTIFIER)
+}
+
+/// One ancillary data packet.
+#[derive(Clone)]
+pub struct AncPacket {
+    /// Set if the packet is carried in the color difference (C) channel rather than luma (Y).
+    pub c_not_y_channel: bool,
+    /// Line number of the packet in the SDI frame; 0x7ff if unspecified.
+    pub line_number: u16,
+    /// Horizontal offset of the packet in the line; 0xfff if unspecified.
+    pub horizontal_offset: u16,
+    /// Data identification word including parity bits.
+    pub did: u16,
+    /// Secondary data identification word including parity bits.
+    pub sdid: u16,
+    /// 10-bit user data words including parity bits.
+    pub user_data_words: Vec<u16>,
+    /// Checksum word.
+    pub checksum: u16,
+}
+
+impl AncPacket {
+    fn parse<D: AppDetails>(reader: &mut BitReader<D>) -> Result<Self, D> {
+        reader.skip_bits(6)?;
+        let c_not_y_channel = reader.read_bit()?;
+        let line_number = reader.read_bits(11)? as u16;
+        let horizontal_offset = reader.read_bits(12)? as u16;
+        let did = reader.read_bits(10)? as u16;
+        let sdid = reader.read_bits(10)? as u16;
+        let data_count = reader.read_bits(10)? as usize & 0xff;
+        let mut user_data_words = Vec::with_capacity(data_count);
+        for _ in 0..data_count {
+            user_data_words.push(reader.read_bits(10)? as u16);
+        }
+        let checksum = reader.read_bits(10)? as u16;
+        reader.byte_align();
+        Ok(Self {
+            c_not_y_channel,
+            line_number,
+            horizontal_offset,
+            did,
+            sdid,
+            user_data_words,
+            checksum,
+        })
+    }
+
+    /// Parses all packets of a PES payload, stopping at stuffing bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::es::anc::AncPacket;
+    /// use mpegts_io::DefaultAppDetails;
+    /// let payload = [0x00, 0x02, 0x40, 0x02, 0x41, 0x81, 0x50, 0x24, 0x05, 0x02, 0x52, 0xff];
+    /// let packets = AncPacket::parse_pes_payload::<DefaultAppDetails>(&payload).unwrap();
+    /// assert_eq!(packets[0].line_number, 9);
+    /// assert_eq!((packets[0].did_value(), packets[0].sdid_value()), (0x41, 0x05));
+    /// assert_eq!(packets[0].payload(), [0x01, 0x02]);
+    /// assert!(packets[0].checksum_valid());
+    /// ```
+    pub fn parse_pes_payload<D: AppDetails>(payload: &[u8]) -> Result<Vec<Self>, D> {
+        let mut reader = BitReader::<D>::new(payload);
+        let mut packets = Vec::new();
+        /* Packets start with six zero bits; stuffing bytes are 0xff */
+        while reader.remaining_bits() >= 8 && payload[reader.bytes_consumed()] & 0xfc == 0 {
+            packets.push(Self::parse(&mut reader)?);
+        }
+        Ok(packets)
+    }
+
+    /// Data identification value without parity bits, e.g. 0x61 for captions or 0x41 for AFD and
+    /// SCTE-104.
+    pub fn did_value(&self) -> u8 {
+        self.did as u8
+    }
+
+    /// Secondary data identification value without parity bits, e.g. 0x01 for CEA-708 or 0x07 for
+    /// SCTE-104.
+    pub fn sdid_value(&self) -> u8 {
+        self.sdid as u8
+    }
+
+    /// User data words without parity bits.
+    pub fn payload(&self) -> Vec<u8> {
+        self.user_data_words
+            .iter()
+            .map(|word| *word as u8)
+            .collect()
+    }
+
+    /// Checks the checksum word against the 9-bit sum of DID, SDID, data count and user data
+    /// words.
+    pub fn checksum_valid(&self) -> bool {
+        let data_count = self.user_data_words.len() as u16;
+        let data_count = data_count | parity_bits(data_count);
+        let sum = self
+            .user_data_words
+            .iter()
+            .fold(self.did + self.sdid + data_count, |sum, word| sum + word)
+            & 0x1ff;
+        self.checksum == sum | (!sum << 1 & 0x200)
+    }
+}
+
+/// Bits 8 and 9 of a 10-bit word: even parity of the lower 8 bits and its inverse.
+fn parity_bits(value: u16) -> u16 {
+    if (value as u8).count_ones() % 2 == 1 {
+        0x100
+    } else {
+        0x200
+    }
+}
+
+impl Debug for AncPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AncPacket")
+            .field("c_not_y_channel", &self.c_not_y_channel)
+            .field("line_number", &self.line_number)
+            .field("horizontal_offset", &self.horizontal_offset)
+            .field("did", &format_args!("{:#x}", self.did_value()))
+            .field("sdid", &format_args!("{:#x}", self.sdid_value()))
+            .field("len", &self.user_data_words.len())
+            .finish()
+    }
+}
+
+/// ST 2038 PES unit parsed into [`AncPacket`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) for
+/// streams identified by [`is_anc_stream`].
+pub struct AncUnit {
+    raw: Vec<u8>,
+    /// Packets contained in the unit once finished.
+    pub packets: Vec<AncPacket>,
+}
+
+impl AncUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            packets: Vec::new(),
+        }
+    }
+}
+
+impl Debug for AncUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AncUnit")
+            .field("packets", &self.packets)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for AncUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.packets = AncPacket::parse_pes_payload(&self.raw)?;
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_anc_packets() {
+    use crate::DefaultAppDetails;
+
+    /* Two packets: AFD on line 11 and a CEA-708 CDP fragment on line 9 of the C channel */
+    let mut writer = super::NalWriter::default();
+    let packets = [
+        (false, 11, 0x241, 0x105, vec![0x108, 0x200, 0x200]),
+        (true, 9, 0x161, 0x101, vec![0x196, 0x269]),
+    ];
+    for (c_not_y_channel, line_number, did, sdid, words) in packets.iter() {
+        writer.put(0, 6);
+        writer.put(*c_not_y_channel as u32, 1);
+        writer.put(*line_number, 11);
+        writer.put(0xfff, 12);
+        writer.put(*did, 10);
+        writer.put(*sdid, 10);
+        let data_count = words.len() as u16;
+        writer.put((data_count | parity_bits(data_count)) as u32, 10);
+        let mut sum = did + sdid + (data_count | parity_bits(data_count)) as u32;
+        for word in words.iter() {
+            writer.put(*word as u32, 10);
+            sum += *word as u32;
+        }
+        sum &= 0x1ff;
+        writer.put(sum | (!sum << 1 & 0x200), 10);
+        writer.align(1);
+    }
+    let mut data = writer.into_bytes();
+    data.extend_from_slice(&[0xff, 0xff]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = AncUnit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.packets.len(), 2);
+    assert_eq!(unit.packets[0].line_number, 11);
+    assert_eq!(unit.packets[0].did_value(), 0x41);
+    assert_eq!(unit.packets[0].payload(), [0x08, 0x00, 0x00]);
+    assert!(unit.packets[0].checksum_valid());
+    assert!(unit.packets[1].c_not_y_channel);
+    assert_eq!(unit.packets[1].sdid_value(), 0x01);
+    assert!(unit.packets[1].checksum_valid());
+}
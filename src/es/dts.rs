@@ -0,0 +1,370 @@
+//! Module for DTS and DTS-HD audio carried in PES units (ETSI TS 102 114).
+//!
+//! [`DtsUnit`] splits PES payloads into core frames and extension substreams. Core frame headers
+//! describe the channel layout, sample rate and bitrate of the stream; extension substreams
+//! signal DTS-HD extensions such as lossless (XLL) coding.
+//!
+//! Only the 16-bit big-endian bitstream format used by Blu-ray and broadcast is supported.
+
+use crate::prelude::*;
+use crate::{AppDetails, MpegTsParser, PesUnitObject, Result};
+use core::convert::TryInto;
+use core::fmt::{Debug, Formatter};
+use log::warn;
+use modular_bitfield_msb::prelude::*;
+
+/// Sync word of a core frame.
+const CORE_SYNC: u32 = 0x7ffe_8001;
+/// Sync word of an extension substream.
+const SUBSTREAM_SYNC: u32 = 0x6458_2025;
+
+/// Sample rates of the `sfreq` values; zero entries are invalid.
+const SAMPLE_RATES: [u32; 16] = [
+    0, 8000, 16000, 32000, 0, 0, 11025, 22050, 44100, 0, 0, 12000, 24000, 48000, 0, 0,
+];
+
+/// Bitrates in bit/s of the `rate` values; zero entries are open, variable or lossless.
+const BITRATES: [u32; 32] = [
+    32000, 56000, 64000, 96000, 112000, 128000, 192000, 224000, 256000, 320000, 384000, 448000,
+    512000, 576000, 640000, 768000, 960000, 1024000, 1152000, 1280000, 1344000, 1408000, 1411200,
+    1472000, 1536000, 1920000, 2048000, 3072000, 3840000, 0, 0, 0,
+];
+
+/// Number of primary channels of the `amode` values 0 to 15.
+const AMODE_CHANNELS: [u8; 16] = [1, 2, 2, 2, 2, 3, 3, 4, 4, 5, 6, 6, 6, 7, 8, 8];
+
+/// Header of a DTS core frame.
+#[bitfield]
+#[derive(Debug, Copy, Clone)]
+pub struct DtsCoreHeader {
+    pub sync: B32,
+    pub normal_frame: bool,
+    pub deficit_sample_count: B5,
+    pub crc_present: bool,
+    pub num_blocks: B7,
+    pub frame_size: B14,
+    pub amode: B6,
+    pub sfreq: B4,
+    pub rate: B5,
+    pub fixed_bit: bool,
+    pub dynamic_range: bool,
+    pub time_stamp: bool,
+    pub auxiliary_data: bool,
+    pub hdcd: bool,
+    pub ext_audio_id: B3,
+    pub ext_audio: bool,
+    pub audio_sync_word_insertion: bool,
+    pub lfe: B2,
+    pub predictor_history: bool,
+}
+
+impl DtsCoreHeader {
+    /// Size of the frame in bytes.
+    pub fn frame_bytes(&self) -> usize {
+        self.frame_size() as usize + 1
+    }
+
+    /// Number of PCM samples per channel of the frame.
+    pub fn samples(&self) -> u32 {
+        (self.num_blocks() as u32 + 1) * 32
+    }
+
+    /// Sample rate in Hz, if the value is not invalid.
+    pub fn sample_rate(&self) -> Option<u32> {
+        match SAMPLE_RATES[self.sfreq() as usize] {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Bitrate in bit/s, unless open, variable or lossless.
+    pub fn bitrate(&self) -> Option<u32> {
+        match BITRATES[self.rate() as usize] {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Checks whether the low frequency effects channel is present.
+    pub fn has_lfe(&self) -> bool {
+        matches!(self.lfe(), 1 | 2)
+    }
+
+    /// Total number of core channels including the low frequency effects channel, if the
+    /// channel arrangement is not user defined.
+    pub fn channels(&self) -> Option<u8> {
+        AMODE_CHANNELS
+            .get(self.amode() as usize)
+            .map(|channels| channels + self.has_lfe() as u8)
+    }
+}
+
+/// Extension coded in a DTS-HD extension substream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DtsExtension {
+    /// Lossless extension (DTS-HD Master Audio).
+    Xll,
+    /// Extended bitrate extension (DTS-HD High Resolution).
+    Xbr,
+    /// 96 kHz sample rate extension.
+    X96,
+    /// Additional channels extension.
+    Xxch,
+    /// Low bitrate extension (DTS Express).
+    Lbr,
+}
+
+impl DtsExtension {
+    fn from_sync(sync: u32) -> Option<Self> {
+        match sync {
+            0x41a2_9547 => Some(DtsExtension::Xll),
+            0x655e_315e => Some(DtsExtension::Xbr),
+            0x1d95_f262 => Some(DtsExtension::X96),
+            0x4700_4a03 => Some(DtsExtension::Xxch),
+            0x0a80_1921 => Some(DtsExtension::Lbr),
+            _ => None,
+        }
+    }
+}
+
+/// Header of a DTS-HD extension substream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DtsSubstreamHeader {
+    /// Index of the substream, 0 to 3.
+    pub index: u8,
+    /// Size of the substream header in bytes.
+    pub header_size: usize,
+    /// Size of the substream in bytes.
+    pub frame_size: usize,
+    /// Extensions found in the substream.
+    pub extensions: Vec<DtsExtension>,
+}
+
+impl DtsSubstreamHeader {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 13 {
+            return None;
+        }
+        let bits = u64::from_be_bytes(data[5..13].try_into().unwrap());
+        let (header_size, frame_size) = if bits & (1 << 61) == 0 {
+            ((bits >> 53) & 0xff, (bits >> 37) & 0xffff)
+        } else {
+            ((bits >> 49) & 0xfff, (bits >> 29) & 0xf_ffff)
+        };
+        Some(Self {
+            index: (bits >> 62) as u8,
+            header_size: header_size as usize + 1,
+            frame_size: frame_size as usize + 1,
+            extensions: Vec::new(),
+        })
+    }
+
+    /// Checks whether the substream carries lossless (DTS-HD Master Audio) data.
+    pub fn is_lossless(&self) -> bool {
+        self.extensions.contains(&DtsExtension::Xll)
+    }
+}
+
+/// Header of a DTS core frame or DTS-HD extension substream.
+#[derive(Debug, Clone)]
+pub enum DtsFrameHeader {
+    /// Core frame.
+    Core(DtsCoreHeader),
+    /// Extension substream.
+    Substream(DtsSubstreamHeader),
+}
+
+/// One DTS core frame or DTS-HD extension substream.
+#[derive(Clone)]
+pub struct DtsFrame {
+    /// Frame header.
+    pub header: DtsFrameHeader,
+    /// Complete frame data, starting with the sync word.
+    pub data: Vec<u8>,
+}
+
+impl Debug for DtsFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DtsFrame")
+            .field("header", &self.header)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Splits DTS data into core frames and extension substreams, skipping garbage between frames
+/// and dropping a truncated final frame.
+pub fn split_dts(data: &[u8]) -> Vec<DtsFrame> {
+    let mut frames = Vec::new();
+    let mut pos = 0;
+    while pos + 13 <= data.len() {
+        let sync = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap());
+        let header = match sync {
+            CORE_SYNC => {
+                let header = DtsCoreHeader::from_bytes(data[pos..pos + 11].try_into().unwrap());
+                /* Frames shorter than 96 bytes are invalid */
+                if header.frame_size() < 95 {
+                    pos += 1;
+                    continue;
+                }
+                DtsFrameHeader::Core(header)
+            }
+            SUBSTREAM_SYNC => match DtsSubstreamHeader::parse(&data[pos..]) {
+                Some(header) => DtsFrameHeader::Substream(header),
+                None => break,
+            },
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        let frame_size = match &header {
+            DtsFrameHeader::Core(header) => header.frame_bytes(),
+            DtsFrameHeader::Substream(header) => header.frame_size,
+        };
+        if pos + frame_size > data.len() {
+            warn!("Truncated DTS frame");
+            break;
+        }
+        let frame = &data[pos..pos + frame_size];
+        let header = match header {
+            DtsFrameHeader::Substream(mut header) => {
+                /* Find extension sync words within the asset data */
+                for window in frame[header.header_size.min(frame_size)..].windows(4) {
+                    let sync = u32::from_be_bytes(window.try_into().unwrap());
+                    if let Some(extension) = DtsExtension::from_sync(sync) {
+                        if !header.extensions.contains(&extension) {
+                            header.extensions.push(extension);
+                        }
+                    }
+                }
+                DtsFrameHeader::Substream(header)
+            }
+            header => header,
+        };
+        frames.push(DtsFrame {
+            header,
+            data: frame.to_vec(),
+        });
+        pos += frame_size;
+    }
+    frames
+}
+
+/// DTS or DTS-HD PES unit split into [`DtsFrame`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) like
+/// [`H264Unit`](super::h264::H264Unit), e.g. for PIDs of stream type
+/// [`StreamType::Dts`](crate::StreamType::Dts) or [`StreamType::DtsHd`](crate::StreamType::DtsHd).
+/// Blu-ray also uses stream type 0x86 for DTS-HD Master Audio, which other systems assign to
+/// [`StreamType::Scte35`](crate::StreamType::Scte35).
+pub struct DtsUnit {
+    raw: Vec<u8>,
+    /// Frames contained in the unit once finished.
+    pub frames: Vec<DtsFrame>,
+}
+
+impl DtsUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Header of the first core frame, describing the core stream properties.
+    pub fn core_header(&self) -> Option<&DtsCoreHeader> {
+        self.frames.iter().find_map(|frame| match &frame.header {
+            DtsFrameHeader::Core(header) => Some(header),
+            _ => None,
+        })
+    }
+
+    /// Extensions found in the extension substreams of the unit.
+    pub fn extensions(&self) -> Vec<DtsExtension> {
+        let mut extensions = Vec::new();
+        for frame in &self.frames {
+            if let DtsFrameHeader::Substream(header) = &frame.header {
+                for extension in &header.extensions {
+                    if !extensions.contains(extension) {
+                        extensions.push(*extension);
+                    }
+                }
+            }
+        }
+        extensions
+    }
+
+    /// Checks whether the unit carries lossless (DTS-HD Master Audio) data.
+    pub fn is_lossless(&self) -> bool {
+        self.extensions().contains(&DtsExtension::Xll)
+    }
+}
+
+impl Debug for DtsUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DtsUnit")
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for DtsUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.frames = split_dts(&self.raw);
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_dts_frames() {
+    use crate::DefaultAppDetails;
+
+    /* 5.1 core at 48 kHz and 1536 kbit/s with 512 samples per frame */
+    let header = DtsCoreHeader::new()
+        .with_sync(CORE_SYNC)
+        .with_normal_frame(true)
+        .with_deficit_sample_count(31)
+        .with_num_blocks(15)
+        .with_frame_size(2012)
+        .with_amode(9)
+        .with_sfreq(13)
+        .with_rate(24)
+        .with_lfe(2);
+    let mut data = vec![0x00];
+    data.extend_from_slice(&header.into_bytes());
+    data.resize(1 + 2013, 0);
+
+    /* Extension substream with a 16 byte header and an XLL asset */
+    let mut substream = vec![0x64, 0x58, 0x20, 0x25, 0x00, 0x01, 0xe0, 0x03, 0xe0];
+    substream.resize(16, 0);
+    substream.extend_from_slice(&[0x41, 0xa2, 0x95, 0x47]);
+    substream.resize(32, 0);
+    data.extend_from_slice(&substream);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = DtsUnit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.frames.len(), 2);
+    let core = unit.core_header().unwrap();
+    assert_eq!(core.frame_bytes(), 2013);
+    assert_eq!(core.samples(), 512);
+    assert_eq!(core.sample_rate(), Some(48000));
+    assert_eq!(core.bitrate(), Some(1536000));
+    assert_eq!(core.channels(), Some(6));
+    match &unit.frames[1].header {
+        DtsFrameHeader::Substream(header) => {
+            assert_eq!(header.header_size, 16);
+            assert_eq!(header.frame_size, 32);
+        }
+        header => panic!("unexpected header {:?}", header),
+    }
+    assert!(unit.is_lossless());
+}
@@ -0,0 +1,1054 @@
+//! Module for DVB subtitles (ETSI EN 300 743) carried in PES units, including decoding of
+//! pixel-data sub-blocks and composition of regions into RGBA bitmaps.
+
+use crate::dvb::SUBTITLING_DESCRIPTOR_TAG;
+use crate::prelude::*;
+use crate::{
+    AppDetails, BitReader, ErrorDetails, EsStreamInfo, MpegTsParser, PesUnitObject, Result,
+    SliceReader, StreamType,
+};
+use core::fmt::{Debug, Formatter};
+use hashbrown::HashMap;
+use log::warn;
+
+/// Placement of a region within the page.
+#[derive(Debug, Clone)]
+pub struct DvbPageRegion {
+    /// Region ID.
+    pub region_id: u8,
+    /// X Pos.
+    pub x: u16,
+    /// Y Pos.
+    pub y: u16,
+}
+
+/// Set of regions that make up the displayed page.
+#[derive(Debug, Clone)]
+pub struct DvbPageComposition {
+    /// Seconds the page may be displayed without being refreshed.
+    pub time_out: u8,
+    /// Format version.
+    pub version: u8,
+    /// 0 = normal case, 1 = acquisition point, 2 = mode change.
+    pub state: u8,
+    /// Positioned regions of the page.
+    pub regions: Vec<DvbPageRegion>,
+}
+
+impl DvbPageComposition {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let time_out = reader.read_u8()?;
+        let bits = reader.read_u8()?;
+        let mut regions = Vec::with_capacity(reader.remaining_len() / 6);
+        while reader.remaining_len() >= 6 {
+            let region_id = reader.read_u8()?;
+            reader.skip(1)?;
+            let x = reader.read_be_u16()?;
+            let y = reader.read_be_u16()?;
+            regions.push(DvbPageRegion { region_id, x, y });
+        }
+        Ok(Self {
+            time_out,
+            version: bits >> 4,
+            state: (bits >> 2) & 0x3,
+            regions,
+        })
+    }
+}
+
+/// A positioned object within a region.
+#[derive(Debug, Clone)]
+pub struct DvbRegionObject {
+    /// Object ID.
+    pub object_id: u16,
+    /// 0 = bitmap, 1 = character, 2 = string of characters.
+    pub object_type: u8,
+    /// 0 = provided in the stream, 1 = provided by ROM in the decoder.
+    pub provider_flag: u8,
+    /// X Pos relative to the region.
+    pub x: u16,
+    /// Y Pos relative to the region.
+    pub y: u16,
+    /// Foreground pixel code of character objects.
+    pub foreground_pixel_code: Option<u8>,
+    /// Background pixel code of character objects.
+    pub background_pixel_code: Option<u8>,
+}
+
+/// A rectangular area of the page containing objects.
+#[derive(Debug, Clone)]
+pub struct DvbRegionComposition {
+    /// Region ID.
+    pub region_id: u8,
+    /// Format version.
+    pub version: u8,
+    /// Region is filled with the background pixel code before drawing objects.
+    pub fill_flag: bool,
+    /// Width in pixels.
+    pub width: u16,
+    /// Height in pixels.
+    pub height: u16,
+    /// Minimum CLUT depth code required to decode the region.
+    pub level_of_compatibility: u8,
+    /// Pixel depth code: 1 = 2-bit, 2 = 4-bit, 3 = 8-bit.
+    pub depth: u8,
+    /// CLUT ID.
+    pub clut_id: u8,
+    /// Background pixel code of 8-bit regions.
+    pub pixel_code_8bit: u8,
+    /// Background pixel code of 4-bit regions.
+    pub pixel_code_4bit: u8,
+    /// Background pixel code of 2-bit regions.
+    pub pixel_code_2bit: u8,
+    /// Objects positioned in the region.
+    pub objects: Vec<DvbRegionObject>,
+}
+
+impl DvbRegionComposition {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let region_id = reader.read_u8()?;
+        let bits = reader.read_u8()?;
+        let width = reader.read_be_u16()?;
+        let height = reader.read_be_u16()?;
+        let depth_bits = reader.read_u8()?;
+        let clut_id = reader.read_u8()?;
+        let pixel_code_8bit = reader.read_u8()?;
+        let pixel_code_bits = reader.read_u8()?;
+        let mut objects = Vec::new();
+        while reader.remaining_len() >= 6 {
+            let object_id = reader.read_be_u16()?;
+            let position = reader.read_be_u32()?;
+            let object_type = (position >> 30) as u8;
+            let (foreground_pixel_code, background_pixel_code) =
+                if object_type == 0x1 || object_type == 0x2 {
+                    (Some(reader.read_u8()?), Some(reader.read_u8()?))
+                } else {
+                    (None, None)
+                };
+            objects.push(DvbRegionObject {
+                object_id,
+                object_type,
+                provider_flag: (position >> 28) as u8 & 0x3,
+                x: (position >> 16) as u16 & 0xfff,
+                y: position as u16 & 0xfff,
+                foreground_pixel_code,
+                background_pixel_code,
+            });
+        }
+        Ok(Self {
+            region_id,
+            version: bits >> 4,
+            fill_flag: bits & 0x8 != 0,
+            width,
+            height,
+            level_of_compatibility: depth_bits >> 5,
+            depth: (depth_bits >> 2) & 0x7,
+            clut_id,
+            pixel_code_8bit,
+            pixel_code_4bit: pixel_code_bits >> 4,
+            pixel_code_2bit: (pixel_code_bits >> 2) & 0x3,
+            objects,
+        })
+    }
+
+    /// Number of bits per pixel of the region.
+    pub fn bits_per_pixel(&self) -> u8 {
+        match self.depth {
+            1 => 2,
+            2 => 4,
+            _ => 8,
+        }
+    }
+
+    fn background_pixel_code(&self) -> u8 {
+        match self.depth {
+            1 => self.pixel_code_2bit,
+            2 => self.pixel_code_4bit,
+            _ => self.pixel_code_8bit,
+        }
+    }
+}
+
+/// A YCrCbT color lookup table entry.
+#[derive(Debug, Clone)]
+pub struct DvbClutEntry {
+    /// Entry index.
+    pub id: u8,
+    /// Entry applies to the 4-entry CLUT of 2-bit regions.
+    pub in_2bit_clut: bool,
+    /// Entry applies to the 16-entry CLUT of 4-bit regions.
+    pub in_4bit_clut: bool,
+    /// Entry applies to the 256-entry CLUT of 8-bit regions.
+    pub in_8bit_clut: bool,
+    /// Luminance. A value of 0 signals full transparency.
+    pub y: u8,
+    /// Red Chrominance
+    pub cr: u8,
+    /// Blue Chrominance
+    pub cb: u8,
+    /// Transparency (0 = opaque)
+    pub t: u8,
+}
+
+/// Color lookup table definition.
+#[derive(Debug, Clone)]
+pub struct DvbClut {
+    /// CLUT ID.
+    pub id: u8,
+    /// Format version.
+    pub version: u8,
+    /// Entries overriding the default CLUT.
+    pub entries: Vec<DvbClutEntry>,
+}
+
+impl DvbClut {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let id = reader.read_u8()?;
+        let version = reader.read_u8()? >> 4;
+        let mut entries = Vec::new();
+        while reader.remaining_len() >= 4 {
+            let entry_id = reader.read_u8()?;
+            let flags = reader.read_u8()?;
+            let (y, cr, cb, t) = if flags & 0x1 != 0 {
+                (
+                    reader.read_u8()?,
+                    reader.read_u8()?,
+                    reader.read_u8()?,
+                    reader.read_u8()?,
+                )
+            } else {
+                /* Reduced-range entry; scale values to 8 bits */
+                let bits = reader.read_be_u16()?;
+                (
+                    ((bits >> 10) as u8 & 0x3f) << 2,
+                    ((bits >> 6) as u8 & 0xf) << 4,
+                    ((bits >> 2) as u8 & 0xf) << 4,
+                    (bits as u8 & 0x3) * 0x55,
+                )
+            };
+            entries.push(DvbClutEntry {
+                id: entry_id,
+                in_2bit_clut: flags & 0x80 != 0,
+                in_4bit_clut: flags & 0x40 != 0,
+                in_8bit_clut: flags & 0x20 != 0,
+                y,
+                cr,
+                cb,
+                t,
+            });
+        }
+        Ok(Self {
+            id,
+            version,
+            entries,
+        })
+    }
+
+    /// Builds the RGBA table used by regions of the given depth code, starting from the default
+    /// CLUT and applying the entries of this definition.
+    pub fn to_rgba(&self, depth: u8) -> Vec<[u8; 4]> {
+        let mut table = default_clut(depth);
+        for entry in self.entries.iter() {
+            let applies = match depth {
+                1 => entry.in_2bit_clut,
+                2 => entry.in_4bit_clut,
+                _ => entry.in_8bit_clut,
+            };
+            if let Some(slot) = table.get_mut(entry.id as usize).filter(|_| applies) {
+                *slot = ycrcbt_to_rgba(entry.y, entry.cr, entry.cb, entry.t);
+            }
+        }
+        table
+    }
+}
+
+/// Converts a CLUT entry to RGBA using the ITU-R BT.601 matrix used for SD DVB subtitles.
+fn ycrcbt_to_rgba(y: u8, cr: u8, cb: u8, t: u8) -> [u8; 4] {
+    if y == 0 {
+        return [0, 0, 0, 0];
+    }
+    let y = 1.164 * (y as f32 - 16.0);
+    let cr = cr as f32 - 128.0;
+    let cb = cb as f32 - 128.0;
+    let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+    [
+        clamp(y + 1.596 * cr),
+        clamp(y - 0.813 * cr - 0.391 * cb),
+        clamp(y + 2.018 * cb),
+        255 - t,
+    ]
+}
+
+/// Default CLUT contents for each region depth code (EN 300 743 section 10).
+fn default_clut(depth: u8) -> Vec<[u8; 4]> {
+    let bit = |i: usize, mask: usize, v: u8| if i & mask != 0 { v } else { 0 };
+    match depth {
+        1 => vec![
+            [0, 0, 0, 0],
+            [255, 255, 255, 255],
+            [0, 0, 0, 255],
+            [127, 127, 127, 255],
+        ],
+        2 => (0..16)
+            .map(|i| {
+                if i == 0 {
+                    [0, 0, 0, 0]
+                } else {
+                    let v = if i < 8 { 255 } else { 127 };
+                    [bit(i, 1, v), bit(i, 2, v), bit(i, 4, v), 255]
+                }
+            })
+            .collect(),
+        _ => (0..256)
+            .map(|i| {
+                if i == 0 {
+                    [0, 0, 0, 0]
+                } else if i < 8 {
+                    [bit(i, 1, 255), bit(i, 2, 255), bit(i, 4, 255), 63]
+                } else {
+                    let (base, low, high, a) = match i & 0x88 {
+                        0x00 => (0, 85, 170, 255),
+                        0x08 => (0, 85, 170, 127),
+                        0x80 => (127, 43, 85, 255),
+                        _ => (0, 43, 85, 255),
+                    };
+                    [
+                        base + bit(i, 0x1, low) + bit(i, 0x10, high),
+                        base + bit(i, 0x2, low) + bit(i, 0x20, high),
+                        base + bit(i, 0x4, low) + bit(i, 0x40, high),
+                        a,
+                    ]
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Graphical data of an object.
+#[derive(Clone)]
+pub enum DvbObjectCoding {
+    /// Interlaced pixel-data sub-blocks for the top and bottom fields.
+    Pixels {
+        /// Sub-blocks of the even lines.
+        top_field: Vec<u8>,
+        /// Sub-blocks of the odd lines. Empty when the top field is repeated.
+        bottom_field: Vec<u8>,
+    },
+    /// Character codes rendered by the decoder.
+    Characters(Vec<u16>),
+    /// Unsupported coding method.
+    Unknown(u8),
+}
+
+impl Debug for DvbObjectCoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DvbObjectCoding::Pixels {
+                top_field,
+                bottom_field,
+            } => f
+                .debug_struct("Pixels")
+                .field("top_field.len()", &top_field.len())
+                .field("bottom_field.len()", &bottom_field.len())
+                .finish(),
+            DvbObjectCoding::Characters(c) => f.debug_tuple("Characters").field(c).finish(),
+            DvbObjectCoding::Unknown(m) => f.debug_tuple("Unknown").field(m).finish(),
+        }
+    }
+}
+
+/// Object definition referenced by regions.
+#[derive(Debug, Clone)]
+pub struct DvbObjectData {
+    /// Object ID.
+    pub id: u16,
+    /// Format version.
+    pub version: u8,
+    /// Pixel code 1 of the object is drawn as transparent (does not modify the region).
+    pub non_modifying_colour_flag: bool,
+    /// Graphical data.
+    pub coding: DvbObjectCoding,
+}
+
+impl DvbObjectData {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let id = reader.read_be_u16()?;
+        let bits = reader.read_u8()?;
+        let coding = match (bits >> 2) & 0x3 {
+            0 => {
+                let top_length = reader.read_be_u16()? as usize;
+                let bottom_length = reader.read_be_u16()? as usize;
+                DvbObjectCoding::Pixels {
+                    top_field: reader.read(top_length)?.to_vec(),
+                    bottom_field: reader.read(bottom_length)?.to_vec(),
+                }
+            }
+            1 => {
+                let num_codes = reader.read_u8()?;
+                let mut codes = Vec::with_capacity(num_codes as usize);
+                for _ in 0..num_codes {
+                    codes.push(reader.read_be_u16()?);
+                }
+                DvbObjectCoding::Characters(codes)
+            }
+            m => DvbObjectCoding::Unknown(m),
+        };
+        Ok(Self {
+            id,
+            version: bits >> 4,
+            non_modifying_colour_flag: bits & 0x2 != 0,
+            coding,
+        })
+    }
+}
+
+/// Display size the subtitles are authored for, defaulting to 720x576 if not signaled.
+#[derive(Debug, Clone)]
+pub struct DvbDisplayDefinition {
+    /// Format version.
+    pub version: u8,
+    /// Display width minus one.
+    pub display_width: u16,
+    /// Display height minus one.
+    pub display_height: u16,
+    /// Inclusive horizontal and vertical bounds of the window subtitles are placed in, if it is
+    /// smaller than the display.
+    pub window: Option<DvbDisplayWindow>,
+}
+
+/// Window within the display that subtitles are placed in.
+#[derive(Debug, Copy, Clone)]
+pub struct DvbDisplayWindow {
+    /// Left edge of the window.
+    pub horizontal_min: u16,
+    /// Right edge of the window.
+    pub horizontal_max: u16,
+    /// Top edge of the window.
+    pub vertical_min: u16,
+    /// Bottom edge of the window.
+    pub vertical_max: u16,
+}
+
+impl DvbDisplayDefinition {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let bits = reader.read_u8()?;
+        let display_width = reader.read_be_u16()?;
+        let display_height = reader.read_be_u16()?;
+        let window = if bits & 0x08 != 0 {
+            Some(DvbDisplayWindow {
+                horizontal_min: reader.read_be_u16()?,
+                horizontal_max: reader.read_be_u16()?,
+                vertical_min: reader.read_be_u16()?,
+                vertical_max: reader.read_be_u16()?,
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            version: bits >> 4,
+            display_width,
+            display_height,
+            window,
+        })
+    }
+
+    /// Display width and height in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (
+            self.display_width as u32 + 1,
+            self.display_height as u32 + 1,
+        )
+    }
+}
+
+/// Parsed data of one subtitling segment.
+#[derive(Debug, Clone)]
+pub enum DvbSegmentData {
+    /// Page composition segment.
+    PageComposition(DvbPageComposition),
+    /// Region composition segment.
+    RegionComposition(DvbRegionComposition),
+    /// CLUT definition segment.
+    Clut(DvbClut),
+    /// Object data segment.
+    ObjectData(DvbObjectData),
+    /// Display definition segment.
+    DisplayDefinition(DvbDisplayDefinition),
+    /// End of display set segment.
+    EndOfDisplaySet,
+    /// Segment of an unhandled type.
+    Unknown(u8, Vec<u8>),
+}
+
+/// One subtitling segment.
+#[derive(Debug, Clone)]
+pub struct DvbSegment {
+    /// Page the segment belongs to (composition or ancillary page).
+    pub page_id: u16,
+    /// Parsed segment data.
+    pub data: DvbSegmentData,
+}
+
+impl DvbSegment {
+    fn parse<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
+        let segment_type = reader.read_u8()?;
+        let page_id = reader.read_be_u16()?;
+        let segment_length = reader.read_be_u16()? as usize;
+        let mut seg_reader = reader.new_sub_reader(segment_length)?;
+        let data = match segment_type {
+            0x10 => DvbSegmentData::PageComposition(DvbPageComposition::parse(&mut seg_reader)?),
+            0x11 => {
+                DvbSegmentData::RegionComposition(DvbRegionComposition::parse(&mut seg_reader)?)
+            }
+            0x12 => DvbSegmentData::Clut(DvbClut::parse(&mut seg_reader)?),
+            0x13 => DvbSegmentData::ObjectData(DvbObjectData::parse(&mut seg_reader)?),
+            0x14 => {
+                DvbSegmentData::DisplayDefinition(DvbDisplayDefinition::parse(&mut seg_reader)?)
+            }
+            0x80 => DvbSegmentData::EndOfDisplaySet,
+            _ => DvbSegmentData::Unknown(segment_type, seg_reader.read_to_end()?.to_vec()),
+        };
+        Ok(Self { page_id, data })
+    }
+
+    /// Parses all segments of a PES payload beginning with `data_identifier` (0x20).
+    pub fn parse_pes_payload<D: AppDetails>(payload: &[u8]) -> Result<Vec<Self>, D> {
+        let mut reader = SliceReader::<D>::new(payload);
+        let mut segments = Vec::new();
+        let data_identifier = reader.read_u8()?;
+        let subtitle_stream_id = reader.read_u8()?;
+        if data_identifier != 0x20 || subtitle_stream_id != 0x00 {
+            warn!("Not DVB subtitle data: {:x}", data_identifier);
+            return Ok(segments);
+        }
+        while reader.remaining_len() > 0 {
+            match reader.read_u8()? {
+                0x0f => segments.push(Self::parse(&mut reader)?),
+                0xff => break,
+                _ => {
+                    warn!("Lost DVB subtitle segment sync");
+                    break;
+                }
+            }
+        }
+        Ok(segments)
+    }
+}
+
+/// Checks whether the PMT signals the stream as DVB subtitles, i.e. a private PES stream with a
+/// subtitling descriptor.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::dvbsub::{self, DvbSubtitleUnit};
+/// use mpegts_io::{AppDetails, EsStreamInfo, PesUnitObject};
+///
+/// #[derive(Default)]
+/// struct SubtitleAppDetails;
+///
+/// impl AppDetails for SubtitleAppDetails {
+///     type AppErrorDetails = ();
+///     type AppParserStorage = ();
+///
+///     fn new_pes_unit_data(
+///         &self,
+///         _pid: u16,
+///         unit_length: usize,
+///         stream_info: Option<&EsStreamInfo>,
+///     ) -> Option<Box<dyn PesUnitObject<Self>>> {
+///         match stream_info {
+///             Some(info) if dvbsub::is_dvb_subtitle_stream(info) => {
+///                 Some(Box::new(DvbSubtitleUnit::new(unit_length)))
+///             }
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub fn is_dvb_subtitle_stream(info: &EsStreamInfo) -> bool {
+    StreamType::from(info.stream_type) == StreamType::PrivatePes
+        && info
+            .es_descriptors
+            .iter()
+            .any(|descriptor| descriptor.tag == SUBTITLING_DESCRIPTOR_TAG)
+}
+
+/// DVB subtitle PES unit parsed into [`DvbSegment`] objects.
+pub struct DvbSubtitleUnit {
+    unit_length: usize,
+    raw: Vec<u8>,
+    /// Segments contained in the unit once finished.
+    pub segments: Vec<DvbSegment>,
+}
+
+impl DvbSubtitleUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            unit_length,
+            raw: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+}
+
+impl Debug for DvbSubtitleUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DvbSubtitleUnit")
+            .field("segments", &self.segments)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for DvbSubtitleUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        if self.raw.capacity() == 0 {
+            self.raw.reserve_exact(self.unit_length);
+        }
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.segments = DvbSegment::parse_pes_payload(&self.raw)?;
+        self.raw = Vec::new();
+        Ok(())
+    }
+
+    fn finish_from_slice(
+        &mut self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<(), D> {
+        self.segments = DvbSegment::parse_pes_payload(slice)?;
+        Ok(())
+    }
+}
+
+/// Map tables applied when a pixel string has fewer bits per pixel than its region.
+struct MapTables {
+    map_2_to_4: [u8; 4],
+    map_2_to_8: [u8; 4],
+    map_4_to_8: [u8; 16],
+}
+
+impl Default for MapTables {
+    fn default() -> Self {
+        let mut map_4_to_8 = [0_u8; 16];
+        for (i, v) in map_4_to_8.iter_mut().enumerate() {
+            *v = i as u8 * 0x11;
+        }
+        Self {
+            map_2_to_4: [0x0, 0x7, 0x8, 0xf],
+            map_2_to_8: [0x00, 0x77, 0x88, 0xff],
+            map_4_to_8,
+        }
+    }
+}
+
+impl MapTables {
+    fn map(&self, code: u8, code_bits: u8, region_bits: u8) -> u8 {
+        match (code_bits, region_bits) {
+            (2, 4) => self.map_2_to_4[code as usize & 0x3],
+            (2, 8) => self.map_2_to_8[code as usize & 0x3],
+            (4, 8) => self.map_4_to_8[code as usize & 0xf],
+            (c, r) if c > r => code >> (c - r),
+            _ => code,
+        }
+    }
+}
+
+/// Reads one run of pixels from a 2-bit/pixel code string as `(run_length, code)`.
+///
+/// A run length of 0 marks the end of the string.
+fn read_2bit_run<D: AppDetails>(reader: &mut BitReader<D>) -> Result<(usize, u8), D> {
+    let code = reader.read_bits(2)? as u8;
+    if code != 0 {
+        return Ok((1, code));
+    }
+    if reader.read_bit()? {
+        let run = reader.read_bits(3)? as usize + 3;
+        return Ok((run, reader.read_bits(2)? as u8));
+    }
+    if reader.read_bit()? {
+        return Ok((1, 0));
+    }
+    Ok(match reader.read_bits(2)? {
+        0 => (0, 0),
+        1 => (2, 0),
+        2 => {
+            let run = reader.read_bits(4)? as usize + 12;
+            (run, reader.read_bits(2)? as u8)
+        }
+        _ => {
+            let run = reader.read_bits(8)? as usize + 29;
+            (run, reader.read_bits(2)? as u8)
+        }
+    })
+}
+
+/// Reads one run of pixels from a 4-bit/pixel code string as `(run_length, code)`.
+fn read_4bit_run<D: AppDetails>(reader: &mut BitReader<D>) -> Result<(usize, u8), D> {
+    let code = reader.read_bits(4)? as u8;
+    if code != 0 {
+        return Ok((1, code));
+    }
+    if !reader.read_bit()? {
+        let run = reader.read_bits(3)? as usize;
+        return Ok(if run == 0 { (0, 0) } else { (run + 2, 0) });
+    }
+    if !reader.read_bit()? {
+        let run = reader.read_bits(2)? as usize + 4;
+        return Ok((run, reader.read_bits(4)? as u8));
+    }
+    Ok(match reader.read_bits(2)? {
+        0 => (1, 0),
+        1 => (2, 0),
+        2 => {
+            let run = reader.read_bits(4)? as usize + 9;
+            (run, reader.read_bits(4)? as u8)
+        }
+        _ => {
+            let run = reader.read_bits(8)? as usize + 25;
+            (run, reader.read_bits(4)? as u8)
+        }
+    })
+}
+
+/// Reads one run of pixels from an 8-bit/pixel code string as `(run_length, code)`.
+fn read_8bit_run<D: AppDetails>(reader: &mut BitReader<D>) -> Result<(usize, u8), D> {
+    let code = reader.read_bits(8)? as u8;
+    if code != 0 {
+        return Ok((1, code));
+    }
+    if !reader.read_bit()? {
+        Ok((reader.read_bits(7)? as usize, 0))
+    } else {
+        let run = reader.read_bits(7)? as usize;
+        Ok((run, reader.read_bits(8)? as u8))
+    }
+}
+
+/// Indexed-color bitmap of a region.
+#[derive(Debug, Clone)]
+pub struct DvbRegionBitmap {
+    /// Width in pixels.
+    pub width: u16,
+    /// Height in pixels.
+    pub height: u16,
+    /// Bits per pixel of the pixel codes (2, 4 or 8).
+    pub bits_per_pixel: u8,
+    /// `width * height` pixel codes, row-major.
+    pub pixels: Vec<u8>,
+}
+
+impl DvbRegionBitmap {
+    /// Creates a bitmap filled with `code`.
+    pub fn new(width: u16, height: u16, bits_per_pixel: u8, code: u8) -> Self {
+        Self {
+            width,
+            height,
+            bits_per_pixel,
+            pixels: vec![code; width as usize * height as usize],
+        }
+    }
+
+    fn draw_field<D: AppDetails>(
+        &mut self,
+        data: &[u8],
+        x: u16,
+        mut y: u16,
+        non_modifying_colour: bool,
+    ) -> Result<(), D> {
+        let mut reader = SliceReader::<D>::new(data);
+        let mut maps = MapTables::default();
+        let mut column = x;
+        while reader.remaining_len() > 0 {
+            let data_type = reader.read_u8()?;
+            let code_bits = match data_type {
+                0x10 => 2,
+                0x11 => 4,
+                0x12 => 8,
+                0x20 => {
+                    let bits = reader.read_be_u16()?;
+                    for (i, v) in maps.map_2_to_4.iter_mut().enumerate() {
+                        *v = (bits >> (12 - 4 * i)) as u8 & 0xf;
+                    }
+                    continue;
+                }
+                0x21 => {
+                    maps.map_2_to_8.copy_from_slice(reader.read(4)?);
+                    continue;
+                }
+                0x22 => {
+                    maps.map_4_to_8.copy_from_slice(reader.read(16)?);
+                    continue;
+                }
+                0xf0 => {
+                    /* End of object line; next line of this field */
+                    column = x;
+                    y += 2;
+                    continue;
+                }
+                _ => {
+                    warn!("Unknown DVB pixel data type: {:x}", data_type);
+                    return Err(reader.make_error(ErrorDetails::<D>::BadPesHeader));
+                }
+            };
+
+            let mut bits = BitReader::<D>::new(reader.peek(reader.remaining_len())?);
+            loop {
+                let (run, code) = match code_bits {
+                    2 => read_2bit_run(&mut bits)?,
+                    4 => read_4bit_run(&mut bits)?,
+                    _ => read_8bit_run(&mut bits)?,
+                };
+                if run == 0 {
+                    break;
+                }
+                let skip = non_modifying_colour && code == 1;
+                let code = maps.map(code, code_bits, self.bits_per_pixel);
+                for _ in 0..run {
+                    if !skip && column < self.width && y < self.height {
+                        self.pixels[y as usize * self.width as usize + column as usize] = code;
+                    }
+                    column = column.saturating_add(1);
+                }
+            }
+            bits.byte_align();
+            reader.skip(bits.bytes_consumed())?;
+        }
+        Ok(())
+    }
+
+    /// Decodes an object's pixel-data sub-blocks onto the bitmap at (`x`, `y`).
+    pub fn draw_object<D: AppDetails>(
+        &mut self,
+        object: &DvbObjectData,
+        x: u16,
+        y: u16,
+    ) -> Result<(), D> {
+        if let DvbObjectCoding::Pixels {
+            top_field,
+            bottom_field,
+        } = &object.coding
+        {
+            let bottom_field = if bottom_field.is_empty() {
+                top_field
+            } else {
+                bottom_field
+            };
+            let nmc = object.non_modifying_colour_flag;
+            self.draw_field::<D>(top_field, x, y, nmc)?;
+            self.draw_field::<D>(bottom_field, x, y + 1, nmc)?;
+        }
+        Ok(())
+    }
+
+    /// Converts the pixel codes to RGBA through `clut`.
+    pub fn to_rgba(&self, clut: &[[u8; 4]]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.pixels.len() * 4);
+        for code in self.pixels.iter() {
+            out.extend_from_slice(clut.get(*code as usize).unwrap_or(&[0, 0, 0, 0]));
+        }
+        out
+    }
+}
+
+/// A region rendered to an RGBA bitmap and positioned within the page.
+#[derive(Debug, Clone)]
+pub struct DvbRenderedRegion {
+    /// Region ID.
+    pub region_id: u8,
+    /// X Pos within the page.
+    pub x: u16,
+    /// Y Pos within the page.
+    pub y: u16,
+    /// Width in pixels.
+    pub width: u16,
+    /// Height in pixels.
+    pub height: u16,
+    /// `width * height * 4` bytes of non-premultiplied RGBA, row-major.
+    pub rgba: Vec<u8>,
+}
+
+/// Decoder model holding the page, regions, CLUTs, and objects of the current epoch.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::es::dvbsub::{DvbSegment, DvbSubtitleRenderer};
+/// use mpegts_io::DefaultAppDetails;
+///
+/// # let pes_payload: Vec<u8> = vec![];
+/// let mut renderer = DvbSubtitleRenderer::default();
+/// let segments = DvbSegment::parse_pes_payload::<DefaultAppDetails>(&pes_payload).unwrap();
+/// renderer.push_segments(&segments);
+/// for region in renderer.render::<DefaultAppDetails>().unwrap() {
+///     println!("{}x{} at {},{}", region.width, region.height, region.x, region.y);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct DvbSubtitleRenderer {
+    display: Option<DvbDisplayDefinition>,
+    page: Option<DvbPageComposition>,
+    regions: HashMap<u8, DvbRegionComposition>,
+    cluts: HashMap<u8, DvbClut>,
+    objects: HashMap<u16, DvbObjectData>,
+}
+
+impl DvbSubtitleRenderer {
+    /// Applies the segments of one PES unit to the decoder model.
+    pub fn push_segments(&mut self, segments: &[DvbSegment]) {
+        for segment in segments {
+            match &segment.data {
+                DvbSegmentData::PageComposition(page) => {
+                    if page.state != 0 {
+                        /* Acquisition point or mode change starts a new epoch */
+                        self.regions.clear();
+                        self.cluts.clear();
+                        self.objects.clear();
+                    }
+                    self.page = Some(page.clone());
+                }
+                DvbSegmentData::DisplayDefinition(display) => {
+                    self.display = Some(display.clone());
+                }
+                DvbSegmentData::RegionComposition(region) => {
+                    self.regions.insert(region.region_id, region.clone());
+                }
+                DvbSegmentData::Clut(clut) => {
+                    self.cluts.insert(clut.id, clut.clone());
+                }
+                DvbSegmentData::ObjectData(object) => {
+                    self.objects.insert(object.id, object.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Display width and height in pixels that region positions refer to.
+    pub fn display_size(&self) -> (u32, u32) {
+        self.display
+            .as_ref()
+            .map_or((720, 576), DvbDisplayDefinition::size)
+    }
+
+    /// Renders one region to an indexed bitmap.
+    pub fn render_region_bitmap<D: AppDetails>(
+        &self,
+        region: &DvbRegionComposition,
+    ) -> Result<DvbRegionBitmap, D> {
+        let bits_per_pixel = region.bits_per_pixel();
+        let background = if region.fill_flag {
+            region.background_pixel_code()
+        } else {
+            0
+        };
+        let mut bitmap =
+            DvbRegionBitmap::new(region.width, region.height, bits_per_pixel, background);
+        for placement in region.objects.iter() {
+            if let Some(object) = self.objects.get(&placement.object_id) {
+                bitmap.draw_object::<D>(object, placement.x, placement.y)?;
+            }
+        }
+        Ok(bitmap)
+    }
+
+    /// Renders every region of the current page to RGBA.
+    pub fn render<D: AppDetails>(&self) -> Result<Vec<DvbRenderedRegion>, D> {
+        let page = match &self.page {
+            Some(page) => page,
+            None => return Ok(Vec::new()),
+        };
+        let mut out = Vec::with_capacity(page.regions.len());
+        for placement in page.regions.iter() {
+            let region = match self.regions.get(&placement.region_id) {
+                Some(region) => region,
+                None => continue,
+            };
+            let clut = match self.cluts.get(&region.clut_id) {
+                Some(clut) => clut.to_rgba(region.depth),
+                None => default_clut(region.depth),
+            };
+            out.push(DvbRenderedRegion {
+                region_id: region.region_id,
+                x: placement.x,
+                y: placement.y,
+                width: region.width,
+                height: region.height,
+                rgba: self.render_region_bitmap::<D>(region)?.to_rgba(&clut),
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[test]
+fn test_dvb_pixel_strings() {
+    let object = DvbObjectData {
+        id: 0,
+        version: 0,
+        non_modifying_colour_flag: false,
+        coding: DvbObjectCoding::Pixels {
+            /* 4-bit: one pixel of 3, run of 5 pixels of 0, end; end of line */
+            top_field: vec![0x11, 0x30, 0x30, 0x00, 0xf0],
+            /* 8-bit: run of 4 pixels of 0x80, end */
+            bottom_field: vec![0x12, 0x00, 0x84, 0x80, 0x00, 0x00, 0xf0],
+        },
+    };
+    let mut bitmap = DvbRegionBitmap::new(8, 2, 8, 0xff);
+    bitmap
+        .draw_object::<crate::DefaultAppDetails>(&object, 1, 0)
+        .unwrap();
+    assert_eq!(
+        bitmap.pixels,
+        vec![
+            0xff, 0x33, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, /* top field */
+            0xff, 0x80, 0x80, 0x80, 0x80, 0xff, 0xff, 0xff, /* bottom field */
+        ]
+    );
+}
+
+#[test]
+fn test_dvb_subtitle_unit() {
+    use crate::{DefaultAppDetails, Descriptor};
+    use smallvec::smallvec;
+
+    let info = EsStreamInfo {
+        program_num: 1,
+        stream_type: StreamType::PrivatePes.into(),
+        es_descriptors: smallvec![Descriptor::new(
+            SUBTITLING_DESCRIPTOR_TAG,
+            b"eng\x10\x00\x01\x00\x01"
+        )],
+    };
+    assert!(is_dvb_subtitle_stream(&info));
+
+    let data = [
+        0x20, 0x00, // data_identifier, subtitle_stream_id
+        0x0f, 0x14, 0x00, 0x01, 0x00, 0x05, // display definition
+        0x00, 0x07, 0x7f, 0x04, 0x37, //
+        0x0f, 0x10, 0x00, 0x01, 0x00, 0x08, // page composition
+        0x05, 0x14, 0x00, 0x00, 0x00, 0x10, 0x03, 0x80, //
+        0x0f, 0x80, 0x00, 0x01, 0x00, 0x00, // end of display set
+        0xff,
+    ];
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = DvbSubtitleUnit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.segments.len(), 3);
+    assert!(matches!(
+        unit.segments[2].data,
+        DvbSegmentData::EndOfDisplaySet
+    ));
+
+    let mut renderer = DvbSubtitleRenderer::default();
+    assert_eq!(renderer.display_size(), (720, 576));
+    renderer.push_segments(&unit.segments);
+    assert_eq!(renderer.display_size(), (1920, 1080));
+    assert_eq!(renderer.page.as_ref().unwrap().regions[0].y, 0x380);
+}
@@ -0,0 +1,667 @@
+//! Module for H.264/AVC video carried in PES units as an Annex B byte stream (ITU-T H.264).
+//!
+//! [`H264Unit`] splits PES payloads into NAL units and parses the sequence and picture parameter
+//! sets, which describe the resolution, profile, level and frame rate of the stream.
+
+use crate::prelude::*;
+use crate::{AppDetails, BitReader, ErrorDetails, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Type of a NAL unit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NalUnitType {
+    /// Coded slice of a non-IDR picture.
+    NonIdrSlice,
+    /// Coded slice data partition A.
+    SlicePartitionA,
+    /// Coded slice data partition B.
+    SlicePartitionB,
+    /// Coded slice data partition C.
+    SlicePartitionC,
+    /// Coded slice of an IDR picture.
+    IdrSlice,
+    /// Supplemental enhancement information.
+    Sei,
+    /// Sequence parameter set.
+    Sps,
+    /// Picture parameter set.
+    Pps,
+    /// Access unit delimiter.
+    AccessUnitDelimiter,
+    /// End of sequence.
+    EndOfSequence,
+    /// End of stream.
+    EndOfStream,
+    /// Filler data.
+    FillerData,
+    /// Sequence parameter set extension.
+    SpsExtension,
+    /// Prefix NAL unit of SVC/MVC.
+    Prefix,
+    /// Subset sequence parameter set of SVC/MVC.
+    SubsetSps,
+    /// Coded slice of an auxiliary picture without partitioning.
+    AuxiliarySlice,
+    /// Coded slice extension of SVC/MVC.
+    SliceExtension,
+    /// Reserved or unspecified type.
+    Other(u8),
+}
+
+impl From<u8> for NalUnitType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => NalUnitType::NonIdrSlice,
+            2 => NalUnitType::SlicePartitionA,
+            3 => NalUnitType::SlicePartitionB,
+            4 => NalUnitType::SlicePartitionC,
+            5 => NalUnitType::IdrSlice,
+            6 => NalUnitType::Sei,
+            7 => NalUnitType::Sps,
+            8 => NalUnitType::Pps,
+            9 => NalUnitType::AccessUnitDelimiter,
+            10 => NalUnitType::EndOfSequence,
+            11 => NalUnitType::EndOfStream,
+            12 => NalUnitType::FillerData,
+            13 => NalUnitType::SpsExtension,
+            14 => NalUnitType::Prefix,
+            15 => NalUnitType::SubsetSps,
+            19 => NalUnitType::AuxiliarySlice,
+            20 => NalUnitType::SliceExtension,
+            other => NalUnitType::Other(other),
+        }
+    }
+}
+
+/// Splits an Annex B byte stream at its start codes into NAL units.
+///
+/// Leading data before the first start code and trailing zero bytes of each NAL unit are
+/// discarded.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::h264::split_annex_b;
+/// let stream = [0, 0, 0, 1, 0x09, 0xf0, 0, 0, 1, 0x67, 0x42, 0];
+/// assert_eq!(split_annex_b(&stream), [&[0x09, 0xf0][..], &[0x67, 0x42][..]]);
+/// ```
+pub fn split_annex_b(data: &[u8]) -> Vec<&[u8]> {
+    let mut nal_units = Vec::new();
+    let mut start = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            if let Some(start) = start {
+                nal_units.push(trim_trailing_zeros(&data[start..i]));
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(start) = start {
+        nal_units.push(trim_trailing_zeros(&data[start..]));
+    }
+    nal_units.retain(|nal| !nal.is_empty());
+    nal_units
+}
+
+fn trim_trailing_zeros(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+/// Removes the emulation prevention bytes of a NAL unit, producing its raw byte sequence
+/// payload (RBSP).
+pub fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &b in data.iter() {
+        if zeros >= 2 && b == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        out.push(b);
+    }
+    out
+}
+
+/// One NAL unit of an Annex B byte stream.
+#[derive(Clone)]
+pub struct NalUnit {
+    /// Reference importance of the NAL unit; 0 for non-reference data.
+    pub nal_ref_idc: u8,
+    /// Type of the NAL unit.
+    pub nal_unit_type: NalUnitType,
+    /// NAL unit data following the header, still containing emulation prevention bytes.
+    pub data: Vec<u8>,
+}
+
+impl NalUnit {
+    /// Parses the header of a NAL unit without start code.
+    pub fn new(nal: &[u8]) -> Option<Self> {
+        let (header, data) = nal.split_first()?;
+        Some(Self {
+            nal_ref_idc: (header >> 5) & 0x3,
+            nal_unit_type: NalUnitType::from(header & 0x1f),
+            data: data.to_vec(),
+        })
+    }
+
+    /// Raw byte sequence payload of the NAL unit.
+    pub fn rbsp(&self) -> Vec<u8> {
+        remove_emulation_prevention(&self.data)
+    }
+}
+
+impl Debug for NalUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NalUnit")
+            .field("nal_ref_idc", &self.nal_ref_idc)
+            .field("nal_unit_type", &self.nal_unit_type)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Sequence parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sps {
+    /// Profile, e.g. 66 = Baseline, 77 = Main, 100 = High.
+    pub profile_idc: u8,
+    /// Constraint set flags 0 to 5 in the most significant bits.
+    pub constraint_flags: u8,
+    /// Level multiplied by 10, e.g. 40 for level 4.0.
+    pub level_idc: u8,
+    /// ID referenced by picture parameter sets.
+    pub seq_parameter_set_id: u32,
+    /// 0 = monochrome, 1 = 4:2:0, 2 = 4:2:2, 3 = 4:4:4.
+    pub chroma_format_idc: u32,
+    /// Bit depth of luma samples.
+    pub bit_depth_luma: u32,
+    /// Bit depth of chroma samples.
+    pub bit_depth_chroma: u32,
+    /// Maximum number of reference frames.
+    pub max_num_ref_frames: u32,
+    /// Set if every picture is a frame rather than possibly a field.
+    pub frame_mbs_only: bool,
+    /// Width in pixels after cropping.
+    pub width: u32,
+    /// Height in pixels after cropping.
+    pub height: u32,
+    /// Sample aspect ratio as (width, height), if signaled.
+    pub sample_aspect_ratio: Option<(u16, u16)>,
+    /// Number of ticks of a `time_scale` Hz clock per field, if signaled.
+    pub num_units_in_tick: Option<u32>,
+    /// Frequency of the timing clock in Hz, if signaled.
+    pub time_scale: Option<u32>,
+    /// Set if the frame rate is constant.
+    pub fixed_frame_rate: bool,
+}
+
+/// Sample aspect ratios of the `aspect_ratio_idc` values 1 to 16.
+const SAMPLE_ASPECT_RATIOS: [(u16, u16); 16] = [
+    (1, 1),
+    (12, 11),
+    (10, 11),
+    (16, 11),
+    (40, 33),
+    (24, 11),
+    (20, 11),
+    (32, 11),
+    (80, 33),
+    (18, 11),
+    (15, 11),
+    (64, 33),
+    (160, 99),
+    (4, 3),
+    (3, 2),
+    (2, 1),
+];
+
+/// Fails with [`ErrorDetails::BadParameterSet`] if arithmetic on parameter set fields overflowed.
+pub(crate) fn in_range<D: AppDetails, T>(value: Option<T>, reader: &BitReader<D>) -> Result<T, D> {
+    value.ok_or_else(|| reader.make_error(ErrorDetails::<D>::BadParameterSet))
+}
+
+fn skip_scaling_list<D: AppDetails>(reader: &mut BitReader<D>, size: usize) -> Result<(), D> {
+    let mut last_scale: i32 = 8;
+    let mut next_scale = 8;
+    for _ in 0..size {
+        if next_scale != 0 {
+            let delta = reader.read_signed_exp_golomb()?;
+            next_scale = in_range(last_scale.checked_add(delta), reader)?.rem_euclid(256);
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+    Ok(())
+}
+
+impl Sps {
+    /// Parses the RBSP of a sequence parameter set NAL unit.
+    pub fn parse<D: AppDetails>(rbsp: &[u8]) -> Result<Self, D> {
+        let mut reader = BitReader::<D>::new(rbsp);
+        let profile_idc = reader.read_bits(8)? as u8;
+        let constraint_flags = reader.read_bits(8)? as u8;
+        let level_idc = reader.read_bits(8)? as u8;
+        let seq_parameter_set_id = reader.read_exp_golomb()?;
+
+        let mut chroma_format_idc = 1;
+        let mut separate_colour_plane = false;
+        let mut bit_depth_luma = 8;
+        let mut bit_depth_chroma = 8;
+        if matches!(
+            profile_idc,
+            100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+        ) {
+            chroma_format_idc = reader.read_exp_golomb()?;
+            if chroma_format_idc == 3 {
+                separate_colour_plane = reader.read_bit()?;
+            }
+            bit_depth_luma = in_range(reader.read_exp_golomb()?.checked_add(8), &reader)?;
+            bit_depth_chroma = in_range(reader.read_exp_golomb()?.checked_add(8), &reader)?;
+            /* qpprime_y_zero_transform_bypass_flag */
+            reader.skip_bits(1)?;
+            if reader.read_bit()? {
+                let count = if chroma_format_idc == 3 { 12 } else { 8 };
+                for i in 0..count {
+                    if reader.read_bit()? {
+                        skip_scaling_list(&mut reader, if i < 6 { 16 } else { 64 })?;
+                    }
+                }
+            }
+        }
+
+        /* log2_max_frame_num_minus4 */
+        reader.read_exp_golomb()?;
+        match reader.read_exp_golomb()? {
+            0 => {
+                /* log2_max_pic_order_cnt_lsb_minus4 */
+                reader.read_exp_golomb()?;
+            }
+            1 => {
+                /* delta_pic_order_always_zero_flag, offsets and the reference frame cycle */
+                reader.skip_bits(1)?;
+                reader.read_signed_exp_golomb()?;
+                reader.read_signed_exp_golomb()?;
+                for _ in 0..reader.read_exp_golomb()? {
+                    reader.read_signed_exp_golomb()?;
+                }
+            }
+            _ => {}
+        }
+
+        let max_num_ref_frames = reader.read_exp_golomb()?;
+        /* gaps_in_frame_num_value_allowed_flag */
+        reader.skip_bits(1)?;
+        let width_in_mbs_minus1 = reader.read_exp_golomb()?;
+        let height_in_map_units_minus1 = reader.read_exp_golomb()?;
+        let frame_mbs_only = reader.read_bit()?;
+        if !frame_mbs_only {
+            /* mb_adaptive_frame_field_flag */
+            reader.skip_bits(1)?;
+        }
+        /* direct_8x8_inference_flag */
+        reader.skip_bits(1)?;
+
+        let field_factor = if frame_mbs_only { 1 } else { 2 };
+        let mut width = in_range(
+            width_in_mbs_minus1
+                .checked_add(1)
+                .and_then(|n| n.checked_mul(16)),
+            &reader,
+        )?;
+        let mut height = in_range(
+            height_in_map_units_minus1
+                .checked_add(1)
+                .and_then(|n| n.checked_mul(16 * field_factor)),
+            &reader,
+        )?;
+        if reader.read_bit()? {
+            let chroma_array_type = if separate_colour_plane {
+                0
+            } else {
+                chroma_format_idc
+            };
+            let (crop_unit_x, crop_unit_y) = match chroma_array_type {
+                1 => (2, 2 * field_factor),
+                2 => (2, field_factor),
+                _ => (1, field_factor),
+            };
+            let left = reader.read_exp_golomb()?;
+            let right = reader.read_exp_golomb()?;
+            let top = reader.read_exp_golomb()?;
+            let bottom = reader.read_exp_golomb()?;
+            let crop_x = left
+                .checked_add(right)
+                .and_then(|n| n.checked_mul(crop_unit_x));
+            let crop_y = top
+                .checked_add(bottom)
+                .and_then(|n| n.checked_mul(crop_unit_y));
+            width = width.saturating_sub(in_range(crop_x, &reader)?);
+            height = height.saturating_sub(in_range(crop_y, &reader)?);
+        }
+
+        let mut sps = Self {
+            profile_idc,
+            constraint_flags,
+            level_idc,
+            seq_parameter_set_id,
+            chroma_format_idc,
+            bit_depth_luma,
+            bit_depth_chroma,
+            max_num_ref_frames,
+            frame_mbs_only,
+            width,
+            height,
+            sample_aspect_ratio: None,
+            num_units_in_tick: None,
+            time_scale: None,
+            fixed_frame_rate: false,
+        };
+        if reader.read_bit()? {
+            sps.read_vui(&mut reader)?;
+        }
+        Ok(sps)
+    }
+
+    fn read_vui<D: AppDetails>(&mut self, reader: &mut BitReader<D>) -> Result<(), D> {
+        if reader.read_bit()? {
+            let aspect_ratio_idc = reader.read_bits(8)? as u8;
+            self.sample_aspect_ratio = match aspect_ratio_idc {
+                1..=16 => Some(SAMPLE_ASPECT_RATIOS[aspect_ratio_idc as usize - 1]),
+                255 => Some((reader.read_bits(16)? as u16, reader.read_bits(16)? as u16)),
+                _ => None,
+            };
+        }
+        /* Overscan info */
+        if reader.read_bit()? {
+            reader.skip_bits(1)?;
+        }
+        /* Video signal type */
+        if reader.read_bit()? {
+            reader.skip_bits(4)?;
+            if reader.read_bit()? {
+                reader.skip_bits(24)?;
+            }
+        }
+        /* Chroma sample location */
+        if reader.read_bit()? {
+            reader.read_exp_golomb()?;
+            reader.read_exp_golomb()?;
+        }
+        if reader.read_bit()? {
+            self.num_units_in_tick = Some(reader.read_bits(32)?);
+            self.time_scale = Some(reader.read_bits(32)?);
+            self.fixed_frame_rate = reader.read_bit()?;
+        }
+        Ok(())
+    }
+
+    /// Frame rate in frames per second, if timing info is signaled.
+    pub fn frame_rate(&self) -> Option<f64> {
+        match (self.num_units_in_tick, self.time_scale) {
+            (Some(num_units_in_tick), Some(time_scale)) if num_units_in_tick > 0 => {
+                Some(time_scale as f64 / (2 * num_units_in_tick as u64) as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Picture parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pps {
+    /// ID referenced by slice headers.
+    pub pic_parameter_set_id: u32,
+    /// ID of the sequence parameter set in use.
+    pub seq_parameter_set_id: u32,
+    /// Set for CABAC entropy coding, clear for CAVLC.
+    pub entropy_coding_mode: bool,
+    /// Set if slice headers carry the field order of their picture.
+    pub bottom_field_pic_order_in_frame_present: bool,
+    /// Number of slice groups (flexible macroblock ordering).
+    pub num_slice_groups: u32,
+}
+
+impl Pps {
+    /// Parses the leading fields of the RBSP of a picture parameter set NAL unit.
+    pub fn parse<D: AppDetails>(rbsp: &[u8]) -> Result<Self, D> {
+        let mut reader = BitReader::<D>::new(rbsp);
+        Ok(Self {
+            pic_parameter_set_id: reader.read_exp_golomb()?,
+            seq_parameter_set_id: reader.read_exp_golomb()?,
+            entropy_coding_mode: reader.read_bit()?,
+            bottom_field_pic_order_in_frame_present: reader.read_bit()?,
+            num_slice_groups: in_range(reader.read_exp_golomb()?.checked_add(1), &reader)?,
+        })
+    }
+}
+
+/// H.264 PES unit split into [`NalUnit`] objects.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::h264::H264Unit;
+/// use mpegts_io::{AppDetails, EsStreamInfo, PesUnitObject, StreamType};
+///
+/// #[derive(Default)]
+/// struct VideoAppDetails;
+///
+/// impl AppDetails for VideoAppDetails {
+///     type AppErrorDetails = ();
+///     type AppParserStorage = ();
+///
+///     fn new_pes_unit_data(
+///         &self,
+///         _pid: u16,
+///         unit_length: usize,
+///         stream_info: Option<&EsStreamInfo>,
+///     ) -> Option<Box<dyn PesUnitObject<Self>>> {
+///         match stream_info {
+///             Some(info) if StreamType::from(info.stream_type) == StreamType::H264 => {
+///                 Some(Box::new(H264Unit::new(unit_length)))
+///             }
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub struct H264Unit {
+    raw: Vec<u8>,
+    /// NAL units contained in the unit once finished.
+    pub nal_units: Vec<NalUnit>,
+    /// Sequence parameter sets contained in the unit.
+    pub sps: Vec<Sps>,
+    /// Picture parameter sets contained in the unit.
+    pub pps: Vec<Pps>,
+}
+
+impl H264Unit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            nal_units: Vec::new(),
+            sps: Vec::new(),
+            pps: Vec::new(),
+        }
+    }
+
+    /// Checks whether the unit contains a slice of an IDR picture.
+    pub fn is_idr(&self) -> bool {
+        self.nal_units
+            .iter()
+            .any(|nal| nal.nal_unit_type == NalUnitType::IdrSlice)
+    }
+
+    /// Splits and parses a complete PES payload.
+    pub fn parse<D: AppDetails>(&mut self, data: &[u8]) {
+        for nal in split_annex_b(data) {
+            let nal = match NalUnit::new(nal) {
+                Some(nal) => nal,
+                None => continue,
+            };
+            match nal.nal_unit_type {
+                NalUnitType::Sps => match Sps::parse::<D>(&nal.rbsp()) {
+                    Ok(sps) => self.sps.push(sps),
+                    Err(_) => warn!("Unable to parse SPS"),
+                },
+                NalUnitType::Pps => match Pps::parse::<D>(&nal.rbsp()) {
+                    Ok(pps) => self.pps.push(pps),
+                    Err(_) => warn!("Unable to parse PPS"),
+                },
+                _ => {}
+            }
+            self.nal_units.push(nal);
+        }
+    }
+}
+
+impl Debug for H264Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("H264Unit")
+            .field("nal_units", &self.nal_units)
+            .field("sps", &self.sps)
+            .field("pps", &self.pps)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for H264Unit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        let raw = core::mem::take(&mut self.raw);
+        self.parse::<D>(&raw);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_h264_parameter_sets() {
+    use super::NalWriter;
+    use crate::DefaultAppDetails;
+
+    /* 1920x1080 High profile level 4.0 at 25 fps, cropped from 1088 lines */
+    let mut sps = NalWriter::default();
+    sps.put(100, 8);
+    sps.put(0, 8);
+    sps.put(40, 8);
+    sps.ue(0);
+    sps.ue(1);
+    sps.ue(0);
+    sps.ue(0);
+    sps.put(0, 2);
+    sps.ue(0);
+    sps.ue(0);
+    sps.ue(2);
+    sps.ue(4);
+    sps.put(0, 1);
+    sps.ue(119);
+    sps.ue(67);
+    sps.put(0b111, 3);
+    sps.ue(0);
+    sps.ue(0);
+    sps.ue(0);
+    sps.ue(4);
+    sps.put(1, 1);
+    sps.put(1, 1);
+    sps.put(1, 8);
+    sps.put(0, 3);
+    sps.put(1, 1);
+    sps.put(1, 32);
+    sps.put(50, 32);
+    sps.put(1, 1);
+
+    let mut pps = NalWriter::default();
+    pps.ue(0);
+    pps.ue(0);
+    pps.put(0b10, 2);
+    pps.ue(0);
+
+    let mut stream = vec![0, 0, 0, 1, 0x09, 0x10];
+    stream.extend(sps.nal(&[0x67]));
+    stream.extend(pps.nal(&[0x68]));
+    stream.extend_from_slice(&[0, 0, 1, 0x65, 0x88, 0x84]);
+
+    let mut unit = H264Unit::new(stream.len());
+    unit.parse::<DefaultAppDetails>(&stream);
+    let types: Vec<_> = unit.nal_units.iter().map(|nal| nal.nal_unit_type).collect();
+    assert_eq!(
+        types,
+        [
+            NalUnitType::AccessUnitDelimiter,
+            NalUnitType::Sps,
+            NalUnitType::Pps,
+            NalUnitType::IdrSlice
+        ]
+    );
+    assert!(unit.is_idr());
+
+    let sps = &unit.sps[0];
+    assert_eq!(sps.profile_idc, 100);
+    assert_eq!(sps.level_idc, 40);
+    assert_eq!(sps.max_num_ref_frames, 4);
+    assert_eq!((sps.width, sps.height), (1920, 1080));
+    assert_eq!(sps.sample_aspect_ratio, Some((1, 1)));
+    assert_eq!(sps.frame_rate(), Some(25.0));
+    assert_eq!(unit.pps[0].seq_parameter_set_id, 0);
+    assert!(unit.pps[0].entropy_coding_mode);
+}
+
+#[test]
+fn test_h264_sps_out_of_range() {
+    use super::NalWriter;
+    use crate::{DefaultAppDetails, ErrorDetails};
+
+    /* Baseline profile SPS with the given size and frame cropping */
+    let parse = |width_in_mbs_minus1: u32, crop: Option<(u32, u32)>| {
+        let mut sps = NalWriter::default();
+        sps.put(66, 8);
+        sps.put(0, 8);
+        sps.put(30, 8);
+        sps.ue(0);
+        sps.ue(0);
+        sps.ue(2);
+        sps.ue(1);
+        sps.put(0, 1);
+        sps.ue(width_in_mbs_minus1);
+        sps.ue(67);
+        sps.put(0b11, 2);
+        match crop {
+            Some((left, right)) => {
+                sps.put(1, 1);
+                sps.ue(left);
+                sps.ue(right);
+                sps.ue(0);
+                sps.ue(0);
+            }
+            None => sps.put(0, 1),
+        }
+        sps.put(0b01, 2);
+        Sps::parse::<DefaultAppDetails>(&sps.into_bytes())
+    };
+
+    assert_eq!(parse(119, Some((0, 4))).unwrap().width, 1912);
+    assert!(matches!(
+        parse(0x1000_0000, None).unwrap_err().details,
+        ErrorDetails::BadParameterSet
+    ));
+    assert!(matches!(
+        parse(119, Some((u32::MAX - 1, 1))).unwrap_err().details,
+        ErrorDetails::BadParameterSet
+    ));
+    assert!(matches!(
+        parse(119, Some((0x8000_0000, 0))).unwrap_err().details,
+        ErrorDetails::BadParameterSet
+    ));
+}
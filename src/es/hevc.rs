@@ -0,0 +1,840 @@
+//! Module for H.265/HEVC video carried in PES units as an Annex B byte stream (ITU-T H.265).
+//!
+//! [`HevcUnit`] splits PES payloads into NAL units and parses the video, sequence and picture
+//! parameter sets, which describe the resolution, profile, tier, level and frame rate of the
+//! stream.
+
+use super::h264::{in_range, remove_emulation_prevention, split_annex_b};
+use crate::prelude::*;
+use crate::{AppDetails, BitReader, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Type of a NAL unit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NalUnitType {
+    /// Trailing picture, non-reference (TRAIL_N).
+    TrailN,
+    /// Trailing picture, reference (TRAIL_R).
+    TrailR,
+    /// Temporal sub-layer access, non-reference (TSA_N).
+    TsaN,
+    /// Temporal sub-layer access, reference (TSA_R).
+    TsaR,
+    /// Step-wise temporal sub-layer access, non-reference (STSA_N).
+    StsaN,
+    /// Step-wise temporal sub-layer access, reference (STSA_R).
+    StsaR,
+    /// Random access decodable leading picture, non-reference (RADL_N).
+    RadlN,
+    /// Random access decodable leading picture, reference (RADL_R).
+    RadlR,
+    /// Random access skipped leading picture, non-reference (RASL_N).
+    RaslN,
+    /// Random access skipped leading picture, reference (RASL_R).
+    RaslR,
+    /// Broken link access with leading pictures (BLA_W_LP).
+    BlaWLp,
+    /// Broken link access with decodable leading pictures (BLA_W_RADL).
+    BlaWRadl,
+    /// Broken link access without leading pictures (BLA_N_LP).
+    BlaNLp,
+    /// Instantaneous decoding refresh with decodable leading pictures (IDR_W_RADL).
+    IdrWRadl,
+    /// Instantaneous decoding refresh without leading pictures (IDR_N_LP).
+    IdrNLp,
+    /// Clean random access (CRA_NUT).
+    Cra,
+    /// Video parameter set.
+    Vps,
+    /// Sequence parameter set.
+    Sps,
+    /// Picture parameter set.
+    Pps,
+    /// Access unit delimiter.
+    AccessUnitDelimiter,
+    /// End of sequence.
+    EndOfSequence,
+    /// End of bitstream.
+    EndOfBitstream,
+    /// Filler data.
+    FillerData,
+    /// Supplemental enhancement information preceding the slices.
+    PrefixSei,
+    /// Supplemental enhancement information following the slices.
+    SuffixSei,
+    /// Reserved or unspecified type.
+    Other(u8),
+}
+
+impl From<u8> for NalUnitType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => NalUnitType::TrailN,
+            1 => NalUnitType::TrailR,
+            2 => NalUnitType::TsaN,
+            3 => NalUnitType::TsaR,
+            4 => NalUnitType::StsaN,
+            5 => NalUnitType::StsaR,
+            6 => NalUnitType::RadlN,
+            7 => NalUnitType::RadlR,
+            8 => NalUnitType::RaslN,
+            9 => NalUnitType::RaslR,
+            16 => NalUnitType::BlaWLp,
+            17 => NalUnitType::BlaWRadl,
+            18 => NalUnitType::BlaNLp,
+            19 => NalUnitType::IdrWRadl,
+            20 => NalUnitType::IdrNLp,
+            21 => NalUnitType::Cra,
+            32 => NalUnitType::Vps,
+            33 => NalUnitType::Sps,
+            34 => NalUnitType::Pps,
+            35 => NalUnitType::AccessUnitDelimiter,
+            36 => NalUnitType::EndOfSequence,
+            37 => NalUnitType::EndOfBitstream,
+            38 => NalUnitType::FillerData,
+            39 => NalUnitType::PrefixSei,
+            40 => NalUnitType::SuffixSei,
+            other => NalUnitType::Other(other),
+        }
+    }
+}
+
+impl NalUnitType {
+    /// Checks whether the NAL unit is a slice of an intra random access point picture (BLA, IDR
+    /// or CRA).
+    pub fn is_irap(&self) -> bool {
+        matches!(
+            self,
+            NalUnitType::BlaWLp
+                | NalUnitType::BlaWRadl
+                | NalUnitType::BlaNLp
+                | NalUnitType::IdrWRadl
+                | NalUnitType::IdrNLp
+                | NalUnitType::Cra
+        )
+    }
+}
+
+/// One NAL unit of an Annex B byte stream.
+#[derive(Clone)]
+pub struct NalUnit {
+    /// Type of the NAL unit.
+    pub nal_unit_type: NalUnitType,
+    /// Layer of the NAL unit; 0 for the base layer.
+    pub nuh_layer_id: u8,
+    /// Temporal sub-layer of the NAL unit.
+    pub temporal_id: u8,
+    /// NAL unit data following the header, still containing emulation prevention bytes.
+    pub data: Vec<u8>,
+}
+
+impl NalUnit {
+    /// Parses the header of a NAL unit without start code.
+    pub fn new(nal: &[u8]) -> Option<Self> {
+        let header = nal.get(..2)?;
+        Some(Self {
+            nal_unit_type: NalUnitType::from((header[0] >> 1) & 0x3f),
+            nuh_layer_id: (header[0] & 0x1) << 5 | header[1] >> 3,
+            temporal_id: (header[1] & 0x7).saturating_sub(1),
+            data: nal[2..].to_vec(),
+        })
+    }
+
+    /// Raw byte sequence payload of the NAL unit.
+    pub fn rbsp(&self) -> Vec<u8> {
+        remove_emulation_prevention(&self.data)
+    }
+}
+
+impl Debug for NalUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NalUnit")
+            .field("nal_unit_type", &self.nal_unit_type)
+            .field("nuh_layer_id", &self.nuh_layer_id)
+            .field("temporal_id", &self.temporal_id)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// General profile, tier and level of a VPS or SPS.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProfileTierLevel {
+    /// Profile space; 0 for the profiles of ITU-T H.265.
+    pub profile_space: u8,
+    /// Set for the High tier, clear for the Main tier.
+    pub tier: bool,
+    /// Profile, e.g. 1 = Main, 2 = Main 10.
+    pub profile_idc: u8,
+    /// Profiles the stream conforms to, one bit per profile_idc with profile 0 as the MSB.
+    pub profile_compatibility_flags: u32,
+    /// Set if pictures are progressive.
+    pub progressive_source: bool,
+    /// Set if pictures are interlaced.
+    pub interlaced_source: bool,
+    /// Level multiplied by 30, e.g. 153 for level 5.1.
+    pub level_idc: u8,
+}
+
+impl ProfileTierLevel {
+    fn read<D: AppDetails>(
+        reader: &mut BitReader<D>,
+        max_sub_layers_minus1: u8,
+    ) -> Result<Self, D> {
+        let profile_space = reader.read_bits(2)? as u8;
+        let tier = reader.read_bit()?;
+        let profile_idc = reader.read_bits(5)? as u8;
+        let profile_compatibility_flags = reader.read_bits(32)?;
+        let progressive_source = reader.read_bit()?;
+        let interlaced_source = reader.read_bit()?;
+        /* Constraint flags */
+        reader.skip_bits(46)?;
+        let level_idc = reader.read_bits(8)? as u8;
+
+        let mut sub_layer_flags = Vec::with_capacity(max_sub_layers_minus1 as usize);
+        for _ in 0..max_sub_layers_minus1 {
+            sub_layer_flags.push((reader.read_bit()?, reader.read_bit()?));
+        }
+        if max_sub_layers_minus1 > 0 {
+            reader.skip_bits(2 * (8 - max_sub_layers_minus1 as usize))?;
+        }
+        for (profile_present, level_present) in sub_layer_flags {
+            if profile_present {
+                reader.skip_bits(88)?;
+            }
+            if level_present {
+                reader.skip_bits(8)?;
+            }
+        }
+
+        Ok(Self {
+            profile_space,
+            tier,
+            profile_idc,
+            profile_compatibility_flags,
+            progressive_source,
+            interlaced_source,
+            level_idc,
+        })
+    }
+}
+
+/// Video parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vps {
+    /// ID referenced by sequence parameter sets.
+    pub video_parameter_set_id: u8,
+    /// Maximum number of layers.
+    pub max_layers: u8,
+    /// Maximum number of temporal sub-layers.
+    pub max_sub_layers: u8,
+    /// Set if inter prediction is restricted to allow temporal sub-layer switching.
+    pub temporal_id_nesting: bool,
+    /// General profile, tier and level.
+    pub profile_tier_level: ProfileTierLevel,
+}
+
+impl Vps {
+    /// Parses the leading fields of the RBSP of a video parameter set NAL unit.
+    pub fn parse<D: AppDetails>(rbsp: &[u8]) -> Result<Self, D> {
+        let mut reader = BitReader::<D>::new(rbsp);
+        let video_parameter_set_id = reader.read_bits(4)? as u8;
+        /* vps_base_layer_internal_flag and vps_base_layer_available_flag */
+        reader.skip_bits(2)?;
+        let max_layers = reader.read_bits(6)? as u8 + 1;
+        let max_sub_layers_minus1 = reader.read_bits(3)? as u8;
+        let temporal_id_nesting = reader.read_bit()?;
+        reader.skip_bits(16)?;
+        Ok(Self {
+            video_parameter_set_id,
+            max_layers,
+            max_sub_layers: max_sub_layers_minus1 + 1,
+            temporal_id_nesting,
+            profile_tier_level: ProfileTierLevel::read(&mut reader, max_sub_layers_minus1)?,
+        })
+    }
+}
+
+/// Sequence parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sps {
+    /// ID of the video parameter set in use.
+    pub video_parameter_set_id: u8,
+    /// Maximum number of temporal sub-layers.
+    pub max_sub_layers: u8,
+    /// General profile, tier and level.
+    pub profile_tier_level: ProfileTierLevel,
+    /// ID referenced by picture parameter sets.
+    pub seq_parameter_set_id: u32,
+    /// 0 = monochrome, 1 = 4:2:0, 2 = 4:2:2, 3 = 4:4:4.
+    pub chroma_format_idc: u32,
+    /// Bit depth of luma samples.
+    pub bit_depth_luma: u32,
+    /// Bit depth of chroma samples.
+    pub bit_depth_chroma: u32,
+    /// Width in pixels after applying the conformance window.
+    pub width: u32,
+    /// Height in pixels after applying the conformance window.
+    pub height: u32,
+    /// Sample aspect ratio as (width, height), if signaled.
+    pub sample_aspect_ratio: Option<(u16, u16)>,
+    /// Set if pictures are coded as fields.
+    pub field_seq: bool,
+    /// Number of ticks of a `time_scale` Hz clock per picture, if signaled.
+    pub num_units_in_tick: Option<u32>,
+    /// Frequency of the timing clock in Hz, if signaled.
+    pub time_scale: Option<u32>,
+}
+
+/// Sample aspect ratios of the `aspect_ratio_idc` values 1 to 16.
+const SAMPLE_ASPECT_RATIOS: [(u16, u16); 16] = [
+    (1, 1),
+    (12, 11),
+    (10, 11),
+    (16, 11),
+    (40, 33),
+    (24, 11),
+    (20, 11),
+    (32, 11),
+    (80, 33),
+    (18, 11),
+    (15, 11),
+    (64, 33),
+    (160, 99),
+    (4, 3),
+    (3, 2),
+    (2, 1),
+];
+
+fn skip_scaling_list_data<D: AppDetails>(reader: &mut BitReader<D>) -> Result<(), D> {
+    for size_id in 0..4 {
+        let step = if size_id == 3 { 3 } else { 1 };
+        for _ in (0..6).step_by(step) {
+            if !reader.read_bit()? {
+                /* scaling_list_pred_matrix_id_delta */
+                reader.read_exp_golomb()?;
+            } else {
+                let coef_num = 64.min(1 << (4 + (size_id << 1)));
+                if size_id > 1 {
+                    reader.read_signed_exp_golomb()?;
+                }
+                for _ in 0..coef_num {
+                    reader.read_signed_exp_golomb()?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Skips a short-term reference picture set, returning its number of delta POCs.
+fn skip_st_ref_pic_set<D: AppDetails>(
+    reader: &mut BitReader<D>,
+    idx: usize,
+    num_delta_pocs: &[u32],
+) -> Result<u32, D> {
+    if idx != 0 && reader.read_bit()? {
+        /* Predicted from the previous set */
+        reader.skip_bits(1)?;
+        reader.read_exp_golomb()?;
+        let mut count = 0;
+        for _ in 0..=num_delta_pocs[idx - 1] {
+            let used_by_curr_pic = reader.read_bit()?;
+            if used_by_curr_pic || reader.read_bit()? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    } else {
+        let num_negative_pics = reader.read_exp_golomb()?;
+        let num_positive_pics = reader.read_exp_golomb()?;
+        for _ in 0..num_negative_pics as u64 + num_positive_pics as u64 {
+            reader.read_exp_golomb()?;
+            reader.skip_bits(1)?;
+        }
+        in_range(num_negative_pics.checked_add(num_positive_pics), reader)
+    }
+}
+
+impl Sps {
+    /// Parses the RBSP of a sequence parameter set NAL unit.
+    pub fn parse<D: AppDetails>(rbsp: &[u8]) -> Result<Self, D> {
+        let mut reader = BitReader::<D>::new(rbsp);
+        let video_parameter_set_id = reader.read_bits(4)? as u8;
+        let max_sub_layers_minus1 = reader.read_bits(3)? as u8;
+        /* sps_temporal_id_nesting_flag */
+        reader.skip_bits(1)?;
+        let profile_tier_level = ProfileTierLevel::read(&mut reader, max_sub_layers_minus1)?;
+        let seq_parameter_set_id = reader.read_exp_golomb()?;
+        let chroma_format_idc = reader.read_exp_golomb()?;
+        if chroma_format_idc == 3 {
+            /* separate_colour_plane_flag */
+            reader.skip_bits(1)?;
+        }
+        let mut width = reader.read_exp_golomb()?;
+        let mut height = reader.read_exp_golomb()?;
+        if reader.read_bit()? {
+            let (sub_width, sub_height) = match chroma_format_idc {
+                1 => (2, 2),
+                2 => (2, 1),
+                _ => (1, 1),
+            };
+            let left = reader.read_exp_golomb()?;
+            let right = reader.read_exp_golomb()?;
+            let top = reader.read_exp_golomb()?;
+            let bottom = reader.read_exp_golomb()?;
+            let crop_x = left
+                .checked_add(right)
+                .and_then(|n| n.checked_mul(sub_width));
+            let crop_y = top
+                .checked_add(bottom)
+                .and_then(|n| n.checked_mul(sub_height));
+            width = width.saturating_sub(in_range(crop_x, &reader)?);
+            height = height.saturating_sub(in_range(crop_y, &reader)?);
+        }
+        let bit_depth_luma = in_range(reader.read_exp_golomb()?.checked_add(8), &reader)?;
+        let bit_depth_chroma = in_range(reader.read_exp_golomb()?.checked_add(8), &reader)?;
+        let log2_max_pic_order_cnt_lsb =
+            in_range(reader.read_exp_golomb()?.checked_add(4), &reader)?;
+
+        /* Sub-layer ordering info */
+        let ordering_info_count = if reader.read_bit()? {
+            max_sub_layers_minus1 + 1
+        } else {
+            1
+        };
+        for _ in 0..ordering_info_count {
+            reader.read_exp_golomb()?;
+            reader.read_exp_golomb()?;
+            reader.read_exp_golomb()?;
+        }
+
+        /* Coding block and transform sizes */
+        for _ in 0..6 {
+            reader.read_exp_golomb()?;
+        }
+        if reader.read_bit()? && reader.read_bit()? {
+            skip_scaling_list_data(&mut reader)?;
+        }
+        /* amp_enabled_flag and sample_adaptive_offset_enabled_flag */
+        reader.skip_bits(2)?;
+        if reader.read_bit()? {
+            /* PCM sample bit depths and sizes */
+            reader.skip_bits(8)?;
+            reader.read_exp_golomb()?;
+            reader.read_exp_golomb()?;
+            reader.skip_bits(1)?;
+        }
+
+        let num_short_term_ref_pic_sets = reader.read_exp_golomb()? as usize;
+        let mut num_delta_pocs = Vec::with_capacity(num_short_term_ref_pic_sets);
+        for idx in 0..num_short_term_ref_pic_sets {
+            let count = skip_st_ref_pic_set(&mut reader, idx, &num_delta_pocs)?;
+            num_delta_pocs.push(count);
+        }
+        if reader.read_bit()? {
+            for _ in 0..reader.read_exp_golomb()? {
+                reader.skip_bits(log2_max_pic_order_cnt_lsb as usize + 1)?;
+            }
+        }
+        /* sps_temporal_mvp_enabled_flag and strong_intra_smoothing_enabled_flag */
+        reader.skip_bits(2)?;
+
+        let mut sps = Self {
+            video_parameter_set_id,
+            max_sub_layers: max_sub_layers_minus1 + 1,
+            profile_tier_level,
+            seq_parameter_set_id,
+            chroma_format_idc,
+            bit_depth_luma,
+            bit_depth_chroma,
+            width,
+            height,
+            sample_aspect_ratio: None,
+            field_seq: false,
+            num_units_in_tick: None,
+            time_scale: None,
+        };
+        if reader.read_bit()? {
+            sps.read_vui(&mut reader)?;
+        }
+        Ok(sps)
+    }
+
+    fn read_vui<D: AppDetails>(&mut self, reader: &mut BitReader<D>) -> Result<(), D> {
+        if reader.read_bit()? {
+            let aspect_ratio_idc = reader.read_bits(8)? as u8;
+            self.sample_aspect_ratio = match aspect_ratio_idc {
+                1..=16 => Some(SAMPLE_ASPECT_RATIOS[aspect_ratio_idc as usize - 1]),
+                255 => Some((reader.read_bits(16)? as u16, reader.read_bits(16)? as u16)),
+                _ => None,
+            };
+        }
+        /* Overscan info */
+        if reader.read_bit()? {
+            reader.skip_bits(1)?;
+        }
+        /* Video signal type */
+        if reader.read_bit()? {
+            reader.skip_bits(4)?;
+            if reader.read_bit()? {
+                reader.skip_bits(24)?;
+            }
+        }
+        /* Chroma sample location */
+        if reader.read_bit()? {
+            reader.read_exp_golomb()?;
+            reader.read_exp_golomb()?;
+        }
+        /* neutral_chroma_indication_flag */
+        reader.skip_bits(1)?;
+        self.field_seq = reader.read_bit()?;
+        /* frame_field_info_present_flag */
+        reader.skip_bits(1)?;
+        /* Default display window */
+        if reader.read_bit()? {
+            for _ in 0..4 {
+                reader.read_exp_golomb()?;
+            }
+        }
+        if reader.read_bit()? {
+            self.num_units_in_tick = Some(reader.read_bits(32)?);
+            self.time_scale = Some(reader.read_bits(32)?);
+        }
+        Ok(())
+    }
+
+    /// Picture rate in pictures per second, if timing info is signaled.
+    pub fn frame_rate(&self) -> Option<f64> {
+        match (self.num_units_in_tick, self.time_scale) {
+            (Some(num_units_in_tick), Some(time_scale)) if num_units_in_tick > 0 => {
+                Some(time_scale as f64 / num_units_in_tick as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Picture parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pps {
+    /// ID referenced by slice segment headers.
+    pub pic_parameter_set_id: u32,
+    /// ID of the sequence parameter set in use.
+    pub seq_parameter_set_id: u32,
+    /// Set if slices may consist of dependent slice segments.
+    pub dependent_slice_segments_enabled: bool,
+    /// Set if slice headers carry `pic_output_flag`.
+    pub output_flag_present: bool,
+    /// Number of extra bits in slice headers.
+    pub num_extra_slice_header_bits: u8,
+    /// Initial quantization parameter.
+    pub init_qp: i32,
+    /// Set if pictures are divided into tiles.
+    pub tiles_enabled: bool,
+    /// Set if wavefront parallel processing is enabled.
+    pub entropy_coding_sync_enabled: bool,
+}
+
+impl Pps {
+    /// Parses the leading fields of the RBSP of a picture parameter set NAL unit.
+    pub fn parse<D: AppDetails>(rbsp: &[u8]) -> Result<Self, D> {
+        let mut reader = BitReader::<D>::new(rbsp);
+        let pic_parameter_set_id = reader.read_exp_golomb()?;
+        let seq_parameter_set_id = reader.read_exp_golomb()?;
+        let dependent_slice_segments_enabled = reader.read_bit()?;
+        let output_flag_present = reader.read_bit()?;
+        let num_extra_slice_header_bits = reader.read_bits(3)? as u8;
+        /* sign_data_hiding_enabled_flag and cabac_init_present_flag */
+        reader.skip_bits(2)?;
+        /* Default number of active reference indices */
+        reader.read_exp_golomb()?;
+        reader.read_exp_golomb()?;
+        let init_qp = 26 + reader.read_signed_exp_golomb()?;
+        /* constrained_intra_pred_flag and transform_skip_enabled_flag */
+        reader.skip_bits(2)?;
+        if reader.read_bit()? {
+            /* diff_cu_qp_delta_depth */
+            reader.read_exp_golomb()?;
+        }
+        /* Chroma QP offsets */
+        reader.read_signed_exp_golomb()?;
+        reader.read_signed_exp_golomb()?;
+        /* Slice chroma QP offsets, weighted prediction and transquant bypass flags */
+        reader.skip_bits(4)?;
+        Ok(Self {
+            pic_parameter_set_id,
+            seq_parameter_set_id,
+            dependent_slice_segments_enabled,
+            output_flag_present,
+            num_extra_slice_header_bits,
+            init_qp,
+            tiles_enabled: reader.read_bit()?,
+            entropy_coding_sync_enabled: reader.read_bit()?,
+        })
+    }
+}
+
+/// HEVC PES unit split into [`NalUnit`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) like
+/// [`H264Unit`](super::h264::H264Unit), e.g. for PIDs of stream type
+/// [`StreamType::Hevc`](crate::StreamType::Hevc).
+pub struct HevcUnit {
+    raw: Vec<u8>,
+    /// NAL units contained in the unit once finished.
+    pub nal_units: Vec<NalUnit>,
+    /// Video parameter sets contained in the unit.
+    pub vps: Vec<Vps>,
+    /// Sequence parameter sets contained in the unit.
+    pub sps: Vec<Sps>,
+    /// Picture parameter sets contained in the unit.
+    pub pps: Vec<Pps>,
+}
+
+impl HevcUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            nal_units: Vec::new(),
+            vps: Vec::new(),
+            sps: Vec::new(),
+            pps: Vec::new(),
+        }
+    }
+
+    /// Checks whether the unit contains a slice of an intra random access point picture.
+    pub fn is_irap(&self) -> bool {
+        self.nal_units.iter().any(|nal| nal.nal_unit_type.is_irap())
+    }
+
+    /// Splits and parses a complete PES payload.
+    pub fn parse<D: AppDetails>(&mut self, data: &[u8]) {
+        for nal in split_annex_b(data) {
+            let nal = match NalUnit::new(nal) {
+                Some(nal) => nal,
+                None => continue,
+            };
+            match nal.nal_unit_type {
+                NalUnitType::Vps => match Vps::parse::<D>(&nal.rbsp()) {
+                    Ok(vps) => self.vps.push(vps),
+                    Err(_) => warn!("Unable to parse VPS"),
+                },
+                NalUnitType::Sps => match Sps::parse::<D>(&nal.rbsp()) {
+                    Ok(sps) => self.sps.push(sps),
+                    Err(_) => warn!("Unable to parse SPS"),
+                },
+                NalUnitType::Pps => match Pps::parse::<D>(&nal.rbsp()) {
+                    Ok(pps) => self.pps.push(pps),
+                    Err(_) => warn!("Unable to parse PPS"),
+                },
+                _ => {}
+            }
+            self.nal_units.push(nal);
+        }
+    }
+}
+
+impl Debug for HevcUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HevcUnit")
+            .field("nal_units", &self.nal_units)
+            .field("vps", &self.vps)
+            .field("sps", &self.sps)
+            .field("pps", &self.pps)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for HevcUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        let raw = core::mem::take(&mut self.raw);
+        self.parse::<D>(&raw);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_hevc_parameter_sets() {
+    use super::NalWriter;
+    use crate::DefaultAppDetails;
+
+    /* Main 10 profile, Main tier, level 5.1 */
+    fn profile_tier_level(writer: &mut NalWriter) {
+        writer.put(0, 3);
+        writer.put(2, 5);
+        writer.put(0x2000_0000, 32);
+        writer.put(0b10, 2);
+        writer.put(0, 23);
+        writer.put(0, 23);
+        writer.put(153, 8);
+    }
+
+    let mut vps = NalWriter::default();
+    vps.put(0, 4);
+    vps.put(0b11, 2);
+    vps.put(0, 6);
+    vps.put(0, 3);
+    vps.put(1, 1);
+    vps.put(0xffff, 16);
+    profile_tier_level(&mut vps);
+
+    /* 3840x2160 4:2:0 at 50 fps, coded as 3840x2176 */
+    let mut sps = NalWriter::default();
+    sps.put(0, 4);
+    sps.put(0, 3);
+    sps.put(1, 1);
+    profile_tier_level(&mut sps);
+    sps.ue(0);
+    sps.ue(1);
+    sps.ue(3840);
+    sps.ue(2176);
+    sps.put(1, 1);
+    for offset in [0, 0, 0, 8] {
+        sps.ue(offset);
+    }
+    sps.ue(2);
+    sps.ue(2);
+    sps.ue(4);
+    sps.put(1, 1);
+    sps.ue(4);
+    sps.ue(0);
+    sps.ue(0);
+    for size in [0, 3, 0, 3, 4, 4] {
+        sps.ue(size);
+    }
+    /* Scaling list with explicit coefficients for the first matrix */
+    sps.put(0b11, 2);
+    sps.put(1, 1);
+    for _ in 0..16 {
+        sps.se(1);
+    }
+    for _ in 1..6 {
+        sps.put(0, 1);
+        sps.ue(0);
+    }
+    for size_id in 1..4 {
+        for _ in (0..6).step_by(if size_id == 3 { 3 } else { 1 }) {
+            sps.put(0, 1);
+            sps.ue(0);
+        }
+    }
+    sps.put(0b110, 3);
+    /* Two short-term reference picture sets, the second predicted from the first */
+    sps.ue(2);
+    sps.ue(1);
+    sps.ue(1);
+    for _ in 0..2 {
+        sps.ue(0);
+        sps.put(1, 1);
+    }
+    sps.put(0b10, 2);
+    sps.ue(0);
+    sps.put(0b10100, 5);
+    /* No long-term reference pictures; temporal MVP and strong intra smoothing enabled */
+    sps.put(0b011, 3);
+    /* VUI with a 1:1 sample aspect ratio and timing info */
+    sps.put(1, 1);
+    sps.put(1, 1);
+    sps.put(1, 8);
+    sps.put(0, 7);
+    sps.put(1, 1);
+    sps.put(1, 32);
+    sps.put(50, 32);
+    sps.put(0, 2);
+
+    let mut pps = NalWriter::default();
+    pps.ue(0);
+    pps.ue(0);
+    pps.put(0, 7);
+    pps.ue(0);
+    pps.ue(0);
+    pps.se(-4);
+    pps.put(0, 3);
+    pps.se(0);
+    pps.se(0);
+    pps.put(0, 4);
+    pps.put(0b01, 2);
+
+    let mut stream = vps.nal(&[0x40, 0x01]);
+    stream.extend(sps.nal(&[0x42, 0x01]));
+    stream.extend(pps.nal(&[0x44, 0x01]));
+    stream.extend_from_slice(&[0, 0, 1, 0x26, 0x01, 0xaf]);
+
+    let mut unit = HevcUnit::new(stream.len());
+    unit.parse::<DefaultAppDetails>(&stream);
+    let types: Vec<_> = unit.nal_units.iter().map(|nal| nal.nal_unit_type).collect();
+    assert_eq!(
+        types,
+        [
+            NalUnitType::Vps,
+            NalUnitType::Sps,
+            NalUnitType::Pps,
+            NalUnitType::IdrWRadl
+        ]
+    );
+    assert!(unit.is_irap());
+
+    assert_eq!(unit.vps[0].profile_tier_level.level_idc, 153);
+    let sps = &unit.sps[0];
+    assert_eq!(sps.profile_tier_level.profile_idc, 2);
+    assert!(sps.profile_tier_level.progressive_source);
+    assert_eq!(sps.bit_depth_luma, 10);
+    assert_eq!((sps.width, sps.height), (3840, 2160));
+    assert_eq!(sps.sample_aspect_ratio, Some((1, 1)));
+    assert_eq!(sps.frame_rate(), Some(50.0));
+    assert_eq!(unit.pps[0].init_qp, 22);
+    assert!(unit.pps[0].entropy_coding_sync_enabled);
+}
+
+#[test]
+fn test_hevc_sps_out_of_range() {
+    use super::NalWriter;
+    use crate::{DefaultAppDetails, ErrorDetails};
+
+    /* SPS up to log2_max_pic_order_cnt_lsb_minus4 with the given cropping and bit depth */
+    let parse = |crop: (u32, u32), bit_depth_luma_minus8: u32, log2_lsb_minus4: u32| {
+        let mut sps = NalWriter::default();
+        sps.put(0, 4);
+        sps.put(0, 3);
+        sps.put(1, 1);
+        sps.put(0, 8);
+        sps.put(0, 32);
+        sps.put(0, 24);
+        sps.put(0, 24);
+        sps.put(93, 8);
+        sps.ue(0);
+        sps.ue(1);
+        sps.ue(1920);
+        sps.ue(1088);
+        sps.put(1, 1);
+        for offset in [crop.0, crop.1, 0, 8] {
+            sps.ue(offset);
+        }
+        sps.ue(bit_depth_luma_minus8);
+        sps.ue(0);
+        sps.ue(log2_lsb_minus4);
+        sps.put(1, 1);
+        Sps::parse::<DefaultAppDetails>(&sps.into_bytes())
+    };
+
+    for (crop, bit_depth_luma_minus8, log2_lsb_minus4) in [
+        ((u32::MAX - 1, 1), 0, 4),
+        ((0x8000_0000, 0), 0, 4),
+        ((0, 0), u32::MAX - 1, 4),
+        ((0, 0), 0, u32::MAX - 1),
+    ] {
+        assert!(matches!(
+            parse(crop, bit_depth_luma_minus8, log2_lsb_minus4)
+                .unwrap_err()
+                .details,
+            ErrorDetails::BadParameterSet
+        ));
+    }
+}
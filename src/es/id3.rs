@@ -0,0 +1,351 @@
+//! Module for ID3 timed metadata carried in PES units, as used by HLS (Apple "Timed Metadata for
+//! HTTP Live Streaming").
+//!
+//! [`Id3Unit`] parses the ID3v2 tags of a metadata PES payload into frames. The presentation time
+//! of the metadata is the PTS of the [`Pes`](crate::Pes) returning the unit.
+
+use crate::prelude::*;
+use crate::{AppDetails, EsStreamInfo, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Format identifier of ID3 metadata in registration and metadata descriptors.
+pub const ID3_FORMAT_IDENTIFIER: [u8; 4] = *b"ID3 ";
+
+/// Checks whether the PMT signals the stream as ID3 metadata, either with an `ID3 ` registration
+/// descriptor or a metadata descriptor of metadata format `ID3 `.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::id3::{self, Id3Unit};
+/// use mpegts_io::{AppDetails, EsStreamInfo, PesUnitObject};
+///
+/// #[derive(Default)]
+/// struct MetadataAppDetails;
+///
+/// impl AppDetails for MetadataAppDetails {
+///     type AppErrorDetails = ();
+///     type AppParserStorage = ();
+///
+///     fn new_pes_unit_data(
+///         &self,
+///         _pid: u16,
+///         unit_length: usize,
+///         stream_info: Option<&EsStreamInfo>,
+///     ) -> Option<Box<dyn PesUnitObject<Self>>> {
+///         match stream_info {
+///             Some(info) if id3::is_id3_stream(info) => Some(Box::new(Id3Unit::new(unit_length))),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub fn is_id3_stream(info: &EsStreamInfo) -> bool {
+    super::is_metadata_stream(info, ID3_FORMAT_IDENTIFIER)
+}
+
+/// Decodes a 28-bit syncsafe integer.
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |value, byte| (value << 7) | (*byte & 0x7f) as usize)
+}
+
+/// Reverses the unsynchronisation scheme, replacing `0xff 0x00` with `0xff`.
+fn remove_unsynchronisation(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for (i, byte) in data.iter().enumerate() {
+        if *byte == 0x00 && i > 0 && data[i - 1] == 0xff {
+            continue;
+        }
+        out.push(*byte);
+    }
+    out
+}
+
+/// Decodes an ID3 text string of the given encoding byte.
+fn decode_text(encoding: u8, data: &[u8]) -> Option<String> {
+    match encoding {
+        0 => Some(data.iter().map(|byte| *byte as char).collect()),
+        1 | 2 => {
+            let mut units = data
+                .chunks_exact(2)
+                .map(|pair| [pair[0], pair[1]])
+                .collect::<Vec<_>>();
+            let mut little_endian = false;
+            if encoding == 1 {
+                match units.first() {
+                    Some([0xff, 0xfe]) => little_endian = true,
+                    Some([0xfe, 0xff]) => {}
+                    _ => return None,
+                }
+                units.remove(0);
+            }
+            let units = units.into_iter().map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            char::decode_utf16(units)
+                .collect::<core::result::Result<_, _>>()
+                .ok()
+        }
+        3 => String::from_utf8(data.to_vec()).ok(),
+        _ => None,
+    }
+}
+
+/// One frame of an ID3v2 tag.
+#[derive(Clone)]
+pub struct Id3Frame {
+    /// Frame identifier, e.g. `TIT2` or `PRIV`; three characters for ID3v2.2.
+    pub id: String,
+    /// Frame flags; zero for ID3v2.2.
+    pub flags: u16,
+    /// Frame payload.
+    pub data: Vec<u8>,
+}
+
+impl Id3Frame {
+    /// Decodes the payload of a text information frame (`T***` other than `TXXX`), with multiple
+    /// strings separated by NUL characters.
+    pub fn text(&self) -> Option<String> {
+        if !self.id.starts_with('T') || self.id == "TXXX" {
+            return None;
+        }
+        let (encoding, data) = self.data.split_first()?;
+        decode_text(*encoding, data).map(|text| text.trim_end_matches('\0').to_string())
+    }
+
+    /// Splits the payload of a private frame (`PRIV`) into its owner identifier and private data.
+    ///
+    /// HLS carries the MPEG-2 timestamp of packed audio in a private frame owned by
+    /// `com.apple.streaming.transportStreamTimestamp`.
+    pub fn private(&self) -> Option<(&str, &[u8])> {
+        if self.id != "PRIV" {
+            return None;
+        }
+        let end = self.data.iter().position(|byte| *byte == 0)?;
+        let owner = core::str::from_utf8(&self.data[..end]).ok()?;
+        Some((owner, &self.data[end + 1..]))
+    }
+}
+
+impl Debug for Id3Frame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Id3Frame")
+            .field("id", &self.id)
+            .field("flags", &self.flags)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// ID3v2 tag.
+#[derive(Debug, Clone)]
+pub struct Id3Tag {
+    /// Major version, 2 to 4.
+    pub major_version: u8,
+    /// Revision of the major version.
+    pub revision: u8,
+    /// Frames of the tag.
+    pub frames: Vec<Id3Frame>,
+}
+
+impl Id3Tag {
+    /// Parses an ID3v2 tag, returning it with the number of bytes consumed.
+    ///
+    /// Returns [`None`] if the data does not start with a complete tag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::es::id3::Id3Tag;
+    /// let tag = b"ID3\x04\x00\x00\x00\x00\x00\x0fTIT2\x00\x00\x00\x05\x00\x00\x03Live";
+    /// let (tag, length) = Id3Tag::parse(tag).unwrap();
+    /// assert_eq!(length, 25);
+    /// assert_eq!(tag.frames[0].text().unwrap(), "Live");
+    /// ```
+    pub fn parse(data: &[u8]) -> Option<(Self, usize)> {
+        if data.len() < 10 || &data[..3] != b"ID3" {
+            return None;
+        }
+        let major_version = data[3];
+        let revision = data[4];
+        let flags = data[5];
+        let mut length = 10 + syncsafe(&data[6..10]);
+        if flags & 0x10 != 0 {
+            /* Footer */
+            length += 10;
+        }
+        let body = data.get(10..10 + syncsafe(&data[6..10]))?;
+        let body = if flags & 0x80 != 0 && major_version < 4 {
+            remove_unsynchronisation(body)
+        } else {
+            body.to_vec()
+        };
+        let mut pos = 0;
+        if flags & 0x40 != 0 && major_version >= 3 {
+            /* Extended header; the size excludes itself in ID3v2.3 */
+            let size = body.get(..4)?;
+            pos = if major_version == 3 {
+                4 + u32::from_be_bytes([size[0], size[1], size[2], size[3]]) as usize
+            } else {
+                syncsafe(size)
+            };
+        }
+
+        let mut frames = Vec::new();
+        let header_length = if major_version == 2 { 6 } else { 10 };
+        while pos + header_length <= body.len() && body[pos] != 0 {
+            let header = &body[pos..pos + header_length];
+            let (id, size, frame_flags) = match major_version {
+                2 => (
+                    &header[..3],
+                    u32::from_be_bytes([0, header[3], header[4], header[5]]) as usize,
+                    0,
+                ),
+                3 => (
+                    &header[..4],
+                    u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize,
+                    u16::from_be_bytes([header[8], header[9]]),
+                ),
+                _ => (
+                    &header[..4],
+                    syncsafe(&header[4..8]),
+                    u16::from_be_bytes([header[8], header[9]]),
+                ),
+            };
+            pos += header_length;
+            let data = match body.get(pos..pos + size) {
+                Some(data) => data,
+                None => {
+                    warn!("Truncated ID3 frame");
+                    break;
+                }
+            };
+            /* Frame-level unsynchronisation of ID3v2.4 */
+            let data = if major_version == 4 && (frame_flags & 0x02 != 0 || flags & 0x80 != 0) {
+                remove_unsynchronisation(data)
+            } else {
+                data.to_vec()
+            };
+            frames.push(Id3Frame {
+                id: String::from_utf8_lossy(id).into_owned(),
+                flags: frame_flags,
+                data,
+            });
+            pos += size;
+        }
+
+        Some((
+            Self {
+                major_version,
+                revision,
+                frames,
+            },
+            length,
+        ))
+    }
+}
+
+/// ID3 metadata PES unit split into [`Id3Tag`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) for
+/// streams identified by [`is_id3_stream`].
+pub struct Id3Unit {
+    raw: Vec<u8>,
+    /// Tags contained in the unit once finished.
+    pub tags: Vec<Id3Tag>,
+}
+
+impl Id3Unit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Iterates the frames of all tags.
+    pub fn frames(&self) -> impl Iterator<Item = &Id3Frame> {
+        self.tags.iter().flat_map(|tag| tag.frames.iter())
+    }
+}
+
+impl Debug for Id3Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Id3Unit").field("tags", &self.tags).finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for Id3Unit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        let mut pos = 0;
+        while pos < self.raw.len() {
+            match Id3Tag::parse(&self.raw[pos..]) {
+                Some((tag, length)) => {
+                    self.tags.push(tag);
+                    pos += length;
+                }
+                None => {
+                    warn!("Unable to parse ID3 tag");
+                    break;
+                }
+            }
+        }
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_id3_metadata() {
+    use super::METADATA_DESCRIPTOR_TAG;
+    use crate::descriptor::REGISTRATION_DESCRIPTOR_TAG;
+    use crate::{DefaultAppDetails, Descriptor, StreamType};
+    use smallvec::smallvec;
+
+    let mut info = EsStreamInfo {
+        program_num: 1,
+        stream_type: StreamType::MetadataPes.into(),
+        es_descriptors: smallvec![Descriptor::new(
+            METADATA_DESCRIPTOR_TAG,
+            b"\xff\xffID3 \xffID3 \x00\x0f"
+        )],
+    };
+    assert!(is_id3_stream(&info));
+    info.es_descriptors = smallvec![Descriptor::new(REGISTRATION_DESCRIPTOR_TAG, b"ID3 ")];
+    assert!(is_id3_stream(&info));
+    info.es_descriptors = smallvec![Descriptor::new(REGISTRATION_DESCRIPTOR_TAG, b"KLVA")];
+    assert!(!is_id3_stream(&info));
+
+    /* ID3v2.4 tag with a transport stream timestamp and a UTF-16 title */
+    let mut body = b"PRIV\x00\x00\x00\x35\x00\x00".to_vec();
+    body.extend_from_slice(b"com.apple.streaming.transportStreamTimestamp\x00");
+    body.extend_from_slice(&[0, 0, 0, 0, 0, 0x01, 0x5f, 0x90]);
+    body.extend_from_slice(b"TIT2\x00\x00\x00\x07\x00\x00\x01\xff\xfeH\x00i\x00");
+    let mut data = b"ID3\x04\x00\x00\x00\x00\x00".to_vec();
+    data.push(body.len() as u8);
+    data.extend_from_slice(&body);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = Id3Unit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.tags.len(), 1);
+    assert_eq!(unit.tags[0].major_version, 4);
+    let frames = unit.frames().collect::<Vec<_>>();
+    let (owner, timestamp) = frames[0].private().unwrap();
+    assert_eq!(owner, "com.apple.streaming.transportStreamTimestamp");
+    assert_eq!(timestamp, [0, 0, 0, 0, 0, 0x01, 0x5f, 0x90]);
+    assert_eq!(frames[1].text().unwrap(), "Hi");
+}
@@ -0,0 +1,285 @@
+//! Module for SMPTE 336 KLV metadata carried in PES units (MISB ST 1402), such as the MISB ST
+//! 0601 UAS Datalink Local Set.
+//!
+//! [`KlvUnit`] splits the PES payload of asynchronous (private PES) or synchronous (metadata PES)
+//! KLV streams into [`KlvItem`] triplets. The timing of the metadata is the PTS of the
+//! [`Pes`](crate::Pes) returning the unit.
+
+use crate::prelude::*;
+use crate::{AppDetails, EsStreamInfo, MpegTsParser, PesUnitObject, Result, StreamType, Warning};
+use core::convert::TryInto;
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Format identifier of KLV metadata in registration and metadata descriptors.
+pub const KLV_FORMAT_IDENTIFIER: [u8; 4] = *b"KLVA";
+
+/// Universal key of the MISB ST 0601 UAS Datalink Local Set.
+pub const UAS_LOCAL_SET_KEY: [u8; 16] = [
+    0x06, 0x0e, 0x2b, 0x34, 0x02, 0x0b, 0x01, 0x01, 0x0e, 0x01, 0x03, 0x01, 0x01, 0x00, 0x00, 0x00,
+];
+
+/// Local set tag of the ST 0601 precision time stamp.
+const PRECISION_TIME_STAMP_TAG: u32 = 2;
+
+/// Checks whether the PMT signals the stream as KLV metadata, either with a `KLVA` registration
+/// descriptor or a metadata descriptor of metadata format `KLVA`.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::es::klv::{self, KlvUnit};
+/// use mpegts_io::{AppDetails, EsStreamInfo, PesUnitObject, StreamType};
+///
+/// #[derive(Default)]
+/// struct KlvAppDetails;
+///
+/// impl AppDetails for KlvAppDetails {
+///     type AppErrorDetails = ();
+///     type AppParserStorage = ();
+///
+///     fn new_pes_unit_data(
+///         &self,
+///         _pid: u16,
+///         unit_length: usize,
+///         stream_info: Option<&EsStreamInfo>,
+///     ) -> Option<Box<dyn PesUnitObject<Self>>> {
+///         match stream_info {
+///             Some(info) if klv::is_klv_stream(info) => {
+///                 let synchronous = StreamType::from(info.stream_type) == StreamType::MetadataPes;
+///                 Some(Box::new(KlvUnit::new(unit_length, synchronous)))
+///             }
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub fn is_klv_stream(info: &EsStreamInfo) -> bool {
+    super::is_metadata_stream(info, KLV_FORMAT_IDENTIFIER)
+}
+
+/// Reads a BER encoded length, returning it with the number of bytes consumed.
+fn read_ber_length(data: &[u8]) -> Option<(usize, usize)> {
+    let first = *data.first()?;
+    if first & 0x80 == 0 {
+        return Some((first as usize, 1));
+    }
+    let count = (first & 0x7f) as usize;
+    if count > 8 {
+        return None;
+    }
+    let length = data
+        .get(1..1 + count)?
+        .iter()
+        .fold(0, |length, byte| (length << 8) | *byte as usize);
+    Some((length, 1 + count))
+}
+
+/// Reads a BER-OID encoded tag, returning it with the number of bytes consumed.
+fn read_ber_oid(data: &[u8]) -> Option<(u32, usize)> {
+    let mut tag = 0;
+    for (i, byte) in data.iter().take(4).enumerate() {
+        tag = (tag << 7) | (*byte & 0x7f) as u32;
+        if byte & 0x80 == 0 {
+            return Some((tag, i + 1));
+        }
+    }
+    None
+}
+
+/// One KLV triplet.
+#[derive(Clone)]
+pub struct KlvItem {
+    /// 16-byte SMPTE universal label.
+    pub key: [u8; 16],
+    /// Value of the item.
+    pub value: Vec<u8>,
+}
+
+impl KlvItem {
+    /// Parses one triplet, returning it with the number of bytes consumed.
+    pub fn parse(data: &[u8]) -> Option<(Self, usize)> {
+        let key = data.get(..16)?.try_into().unwrap();
+        let (length, length_size) = read_ber_length(&data[16..])?;
+        let start = 16 + length_size;
+        let value = data.get(start..start + length)?.to_vec();
+        Some((Self { key, value }, start + length))
+    }
+
+    /// Checks whether the item is a MISB ST 0601 UAS Datalink Local Set.
+    pub fn is_uas_local_set(&self) -> bool {
+        self.key == UAS_LOCAL_SET_KEY
+    }
+
+    /// Splits the value of a local set into its tags and values.
+    ///
+    /// Returns [`None`] if the value is malformed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::es::klv::{KlvItem, UAS_LOCAL_SET_KEY};
+    /// let item = KlvItem {
+    ///     key: UAS_LOCAL_SET_KEY,
+    ///     value: vec![0x03, 0x04, b'M', b'S', b'N', b'1', 0x81, 0x01, 0x01, 0x7f],
+    /// };
+    /// let local_set = item.local_set().unwrap();
+    /// assert_eq!(local_set[0], (3, &b"MSN1"[..]));
+    /// assert_eq!(local_set[1], (129, &[0x7f][..]));
+    /// ```
+    pub fn local_set(&self) -> Option<Vec<(u32, &[u8])>> {
+        let mut items = Vec::new();
+        let mut pos = 0;
+        while pos < self.value.len() {
+            let (tag, tag_size) = read_ber_oid(&self.value[pos..])?;
+            pos += tag_size;
+            let (length, length_size) = read_ber_length(&self.value[pos..])?;
+            pos += length_size;
+            items.push((tag, self.value.get(pos..pos + length)?));
+            pos += length;
+        }
+        Some(items)
+    }
+
+    /// Precision time stamp of a UAS Datalink Local Set in microseconds since the Unix epoch.
+    pub fn precision_time_stamp(&self) -> Option<u64> {
+        if !self.is_uas_local_set() {
+            return None;
+        }
+        self.local_set()?
+            .into_iter()
+            .find(|(tag, _)| *tag == PRECISION_TIME_STAMP_TAG)
+            .and_then(|(_, value)| Some(u64::from_be_bytes(value.try_into().ok()?)))
+    }
+}
+
+impl Debug for KlvItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KlvItem")
+            .field("key", &format_args!("{:02x?}", self.key))
+            .field("len", &self.value.len())
+            .finish()
+    }
+}
+
+/// KLV metadata PES unit split into [`KlvItem`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) for
+/// streams identified by [`is_klv_stream`]. Synchronous streams of stream type
+/// [`StreamType::MetadataPes`] wrap the KLV data in metadata access unit cells, which are removed.
+pub struct KlvUnit {
+    raw: Vec<u8>,
+    synchronous: bool,
+    /// KLV items contained in the unit once finished.
+    pub items: Vec<KlvItem>,
+}
+
+impl KlvUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes, with `synchronous` set for
+    /// metadata access unit cells.
+    pub fn new(unit_length: usize, synchronous: bool) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            synchronous,
+            items: Vec::new(),
+        }
+    }
+
+    /// Concatenates the data of the metadata access unit cells of a synchronous stream.
+    fn remove_au_cells(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len());
+        let mut pos = 0;
+        while pos + 5 <= data.len() {
+            let length = u16::from_be_bytes([data[pos + 3], data[pos + 4]]) as usize;
+            pos += 5;
+            match data.get(pos..pos + length) {
+                Some(cell) => out.extend_from_slice(cell),
+                None => {
+                    warn!("Truncated metadata AU cell");
+                    break;
+                }
+            }
+            pos += length;
+        }
+        out
+    }
+}
+
+impl Debug for KlvUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KlvUnit")
+            .field("synchronous", &self.synchronous)
+            .field("items", &self.items)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for KlvUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        let data = if self.synchronous {
+            Self::remove_au_cells(&self.raw)
+        } else {
+            core::mem::take(&mut self.raw)
+        };
+        let mut pos = 0;
+        while pos < data.len() {
+            match KlvItem::parse(&data[pos..]) {
+                Some((item, length)) => {
+                    self.items.push(item);
+                    pos += length;
+                }
+                None => {
+                    parser.push_warning(Warning::UnitData {
+                        pid,
+                        message: "Unable to parse KLV item".to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_klv_items() {
+    use crate::{DefaultAppDetails, Descriptor};
+    use smallvec::smallvec;
+
+    let info = EsStreamInfo {
+        program_num: 1,
+        stream_type: StreamType::PrivatePes.into(),
+        es_descriptors: smallvec![Descriptor::new(0x05, b"KLVA")],
+    };
+    assert!(is_klv_stream(&info));
+
+    /* UAS local set with a precision time stamp, in a long form length */
+    let mut klv = UAS_LOCAL_SET_KEY.to_vec();
+    klv.extend_from_slice(&[0x81, 0x0a, 0x02, 0x08]);
+    klv.extend_from_slice(&1_600_000_000_000_000u64.to_be_bytes());
+    /* Unknown item */
+    klv.extend_from_slice(&[0x06; 16]);
+    klv.extend_from_slice(&[0x02, 0xaa, 0xbb]);
+
+    /* Synchronous metadata AU cell wrapping both items */
+    let mut data = vec![0x00, 0x01, 0xdf, 0x00, klv.len() as u8];
+    data.extend_from_slice(&klv);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = KlvUnit::new(data.len(), true);
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.items.len(), 2);
+    assert!(unit.items[0].is_uas_local_set());
+    assert_eq!(
+        unit.items[0].precision_time_stamp(),
+        Some(1_600_000_000_000_000)
+    );
+    assert!(!unit.items[1].is_uas_local_set());
+    assert_eq!(unit.items[1].value, [0xaa, 0xbb]);
+}
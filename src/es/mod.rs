@@ -0,0 +1,112 @@
+//! Parsers for elementary stream payloads carried in PES units.
+//!
+//! Each parser implements [`PesUnitObject`](crate::PesUnitObject) so that it may be returned from
+//! [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data).
+
+pub mod aac;
+pub mod ac3;
+pub mod access_unit;
+pub mod anc;
+pub mod dts;
+pub mod dvbsub;
+pub mod h264;
+pub mod hevc;
+pub mod id3;
+pub mod klv;
+pub mod opus;
+pub mod teletext;
+
+use crate::descriptor::REGISTRATION_DESCRIPTOR_TAG;
+use crate::{EsStreamInfo, StreamType};
+
+/// Tag of the metadata descriptor.
+const METADATA_DESCRIPTOR_TAG: u8 = 0x26;
+
+/// Checks whether a private or metadata PES stream is signaled with `format_identifier`, either
+/// by a registration descriptor or a metadata descriptor.
+fn is_metadata_stream(info: &EsStreamInfo, format_identifier: [u8; 4]) -> bool {
+    let stream_type = StreamType::from(info.stream_type);
+    if stream_type != StreamType::MetadataPes && stream_type != StreamType::PrivatePes {
+        return false;
+    }
+    info.es_descriptors.iter().any(|descriptor| {
+        let data = descriptor.data.as_slice();
+        match descriptor.tag {
+            REGISTRATION_DESCRIPTOR_TAG => data.starts_with(&format_identifier),
+            METADATA_DESCRIPTOR_TAG => {
+                /* Skip the application format identifier if present */
+                let format = if data.starts_with(&[0xff, 0xff]) {
+                    data.get(6..)
+                } else {
+                    data.get(2..)
+                };
+                match format {
+                    Some([0xff, identifier @ ..]) => identifier.starts_with(&format_identifier),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Writer of NAL unit test data.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct NalWriter(Vec<u8>, usize);
+
+#[cfg(test)]
+impl NalWriter {
+    pub(crate) fn put(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.1.is_multiple_of(8) {
+                self.0.push(0);
+            }
+            *self.0.last_mut().unwrap() |= (((value >> i) & 1) as u8) << (7 - self.1 % 8);
+            self.1 += 1;
+        }
+    }
+
+    pub(crate) fn ue(&mut self, value: u32) {
+        let bits = 32 - (value + 1).leading_zeros() as usize;
+        self.put(0, bits - 1);
+        self.put(value + 1, bits);
+    }
+
+    pub(crate) fn se(&mut self, value: i32) {
+        self.ue(if value > 0 {
+            value as u32 * 2 - 1
+        } else {
+            value.unsigned_abs() * 2
+        });
+    }
+
+    /// Pads to the next byte boundary with `bit`.
+    pub(crate) fn align(&mut self, bit: u32) {
+        while !self.1.is_multiple_of(8) {
+            self.put(bit, 1);
+        }
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Appends the stop bit and emits the NAL unit with a start code, inserting emulation
+    /// prevention bytes.
+    pub(crate) fn nal(mut self, header: &[u8]) -> Vec<u8> {
+        self.put(1, 1);
+        let mut out = vec![0, 0, 0, 1];
+        out.extend_from_slice(header);
+        let mut zeros = 0;
+        for b in self.0 {
+            if zeros >= 2 && b <= 3 {
+                out.push(3);
+                zeros = 0;
+            }
+            zeros = if b == 0 { zeros + 1 } else { 0 };
+            out.push(b);
+        }
+        out
+    }
+}
@@ -0,0 +1,259 @@
+//! Module for Opus audio carried in PES units (ETSI TS 102 366 Opus-in-TS extension).
+//!
+//! Opus streams are private PES streams registered as `Opus`, with the channel configuration in a
+//! DVB extension descriptor. [`OpusUnit`] splits PES payloads into Opus packets, removing the
+//! control header preceding each access unit.
+
+use crate::descriptor::REGISTRATION_DESCRIPTOR_TAG;
+use crate::prelude::*;
+use crate::{AppDetails, EsStreamInfo, MpegTsParser, PesUnitObject, Result};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Format identifier of Opus in registration descriptors.
+pub const OPUS_FORMAT_IDENTIFIER: [u8; 4] = *b"Opus";
+
+/// Tag of the DVB extension descriptor.
+const EXTENSION_DESCRIPTOR_TAG: u8 = 0x7f;
+
+/// Extension tag of the Opus audio descriptor within the DVB extension descriptor.
+const OPUS_DESCRIPTOR_TAG_EXTENSION: u8 = 0x80;
+
+/// Checks whether the PMT signals the stream as Opus with an `Opus` registration descriptor.
+pub fn is_opus_stream(info: &EsStreamInfo) -> bool {
+    super::is_metadata_stream(info, OPUS_FORMAT_IDENTIFIER)
+}
+
+/// Opus audio descriptor, carried as a DVB extension descriptor.
+#[derive(Debug, Clone)]
+pub struct OpusAudioDescriptor {
+    /// Channel configuration code: 0x01 to 0x08 for that many channels in Vorbis order, 0x00 and
+    /// 0x80 for two channels coded as separate streams, or 0x81 for an explicit channel mapping.
+    pub channel_config_code: u8,
+    /// Channel count of an explicit channel mapping.
+    pub channel_count: Option<u8>,
+}
+
+impl OpusAudioDescriptor {
+    /// Finds the descriptor among the elementary stream descriptors of a registered Opus stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::es::opus::OpusAudioDescriptor;
+    /// use mpegts_io::{Descriptor, EsStreamInfo};
+    /// let info = EsStreamInfo {
+    ///     program_num: 1,
+    ///     stream_type: 0x06,
+    ///     es_descriptors: vec![
+    ///         Descriptor::new(0x05, b"Opus"),
+    ///         Descriptor::new(0x7f, &[0x80, 0x06]),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect(),
+    /// };
+    /// let descriptor = OpusAudioDescriptor::from_stream_info(&info).unwrap();
+    /// assert_eq!(descriptor.channels(), Some(6));
+    /// ```
+    pub fn from_stream_info(info: &EsStreamInfo) -> Option<Self> {
+        if !info.es_descriptors.iter().any(|descriptor| {
+            descriptor.tag == REGISTRATION_DESCRIPTOR_TAG
+                && descriptor.data.starts_with(&OPUS_FORMAT_IDENTIFIER)
+        }) {
+            return None;
+        }
+        info.es_descriptors.iter().find_map(|descriptor| {
+            match (descriptor.tag, descriptor.data.as_slice()) {
+                (EXTENSION_DESCRIPTOR_TAG, [OPUS_DESCRIPTOR_TAG_EXTENSION, code, rest @ ..]) => {
+                    Some(Self {
+                        channel_config_code: *code,
+                        channel_count: if *code == 0x81 {
+                            rest.first().copied()
+                        } else {
+                            None
+                        },
+                    })
+                }
+                _ => None,
+            }
+        })
+    }
+
+    /// Number of decoded channels.
+    pub fn channels(&self) -> Option<u8> {
+        match self.channel_config_code {
+            0x00 | 0x80 => Some(2),
+            code @ 0x01..=0x08 => Some(code),
+            0x81 => self.channel_count,
+            _ => None,
+        }
+    }
+}
+
+/// One Opus access unit.
+#[derive(Clone)]
+pub struct OpusPacket {
+    /// Number of samples at 48 kHz to discard from the start of the decoded packet.
+    pub start_trim: Option<u16>,
+    /// Number of samples at 48 kHz to discard from the end of the decoded packet.
+    pub end_trim: Option<u16>,
+    /// Opus packet data, starting with the TOC byte.
+    pub data: Vec<u8>,
+}
+
+impl OpusPacket {
+    /// Duration of the packet in samples at 48 kHz, derived from its TOC byte and frame count.
+    pub fn samples(&self) -> Option<u32> {
+        let toc = *self.data.first()?;
+        let config = toc >> 3;
+        let frame_samples = match config {
+            0..=11 => [480, 960, 1920, 2880][config as usize % 4],
+            12..=15 => [480, 960][config as usize % 2],
+            _ => [120, 240, 480, 960][config as usize % 4],
+        };
+        let frames = match toc & 0x3 {
+            0 => 1,
+            1 | 2 => 2,
+            _ => *self.data.get(1)? as u32 & 0x3f,
+        };
+        Some(frame_samples * frames)
+    }
+}
+
+impl Debug for OpusPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OpusPacket")
+            .field("start_trim", &self.start_trim)
+            .field("end_trim", &self.end_trim)
+            .field("len", &self.data.len())
+            .finish()
+    }
+}
+
+/// Splits Opus PES data into packets, parsing the control header of each access unit.
+///
+/// Returns the packets parsed before the first malformed control header.
+pub fn split_opus_packets(data: &[u8]) -> Vec<OpusPacket> {
+    let mut packets = Vec::new();
+    let mut pos = 0;
+    while pos + 2 <= data.len() {
+        let header = u16::from_be_bytes([data[pos], data[pos + 1]]);
+        if header & 0xffe0 != 0x7fe0 {
+            warn!("Lost Opus control header sync");
+            break;
+        }
+        pos += 2;
+        let mut size = 0;
+        loop {
+            match data.get(pos) {
+                Some(byte) => {
+                    size += *byte as usize;
+                    pos += 1;
+                    if *byte != 0xff {
+                        break;
+                    }
+                }
+                None => return packets,
+            }
+        }
+        let mut read_trim = |present: bool| -> Option<Option<u16>> {
+            if !present {
+                return Some(None);
+            }
+            let trim = data.get(pos..pos + 2)?;
+            pos += 2;
+            Some(Some(u16::from_be_bytes([trim[0], trim[1]]) & 0x1fff))
+        };
+        let start_trim = match read_trim(header & 0x10 != 0) {
+            Some(trim) => trim,
+            None => break,
+        };
+        let end_trim = match read_trim(header & 0x08 != 0) {
+            Some(trim) => trim,
+            None => break,
+        };
+        if header & 0x04 != 0 {
+            match data.get(pos) {
+                Some(length) => pos += 1 + *length as usize,
+                None => break,
+            }
+        }
+        match data.get(pos..pos + size) {
+            Some(packet) => packets.push(OpusPacket {
+                start_trim,
+                end_trim,
+                data: packet.to_vec(),
+            }),
+            None => {
+                warn!("Truncated Opus access unit");
+                break;
+            }
+        }
+        pos += size;
+    }
+    packets
+}
+
+/// Opus PES unit split into [`OpusPacket`] objects.
+///
+/// Returned from [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data) for
+/// streams identified by [`is_opus_stream`].
+pub struct OpusUnit {
+    raw: Vec<u8>,
+    /// Packets contained in the unit once finished.
+    pub packets: Vec<OpusPacket>,
+}
+
+impl OpusUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            raw: Vec::with_capacity(unit_length),
+            packets: Vec::new(),
+        }
+    }
+}
+
+impl Debug for OpusUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OpusUnit")
+            .field("packets", &self.packets)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for OpusUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.packets = split_opus_packets(&self.raw);
+        self.raw = Vec::new();
+        Ok(())
+    }
+}
+
+#[test]
+fn test_opus_packets() {
+    use crate::DefaultAppDetails;
+
+    /* 20 ms CELT packet with a start trim, then a 300 byte packet with a control extension */
+    let mut data = vec![0x7f, 0xf0, 0x02, 0x00, 0x78, 0xfc, 0x11];
+    data.extend_from_slice(&[0x7f, 0xe4, 0xff, 0x2d, 0x01, 0xaa, 0xf9]);
+    data.resize(data.len() + 299, 0x00);
+    /* Truncated access unit */
+    data.extend_from_slice(&[0x7f, 0xe0, 0x10, 0xf8]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut unit = OpusUnit::new(data.len());
+    PesUnitObject::<DefaultAppDetails>::extend_from_slice(&mut unit, &data);
+    unit.finish(0x100, &mut parser).unwrap();
+    assert_eq!(unit.packets.len(), 2);
+    assert_eq!(unit.packets[0].start_trim, Some(120));
+    assert_eq!(unit.packets[0].end_trim, None);
+    assert_eq!(unit.packets[0].data, [0xfc, 0x11]);
+    assert_eq!(unit.packets[0].samples(), Some(960));
+    assert_eq!(unit.packets[1].data.len(), 300);
+    assert_eq!(unit.packets[1].samples(), Some(1920));
+}
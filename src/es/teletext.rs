@@ -0,0 +1,452 @@
+//! Module for EBU teletext carried in PES units (ETSI EN 300 472) and extraction of subtitle
+//! pages as timed text.
+
+use crate::prelude::*;
+use crate::{AppDetails, MpegTsParser, PesUnitObject, Result, SliceReader};
+use core::fmt::{Debug, Formatter};
+use log::warn;
+
+/// Hamming 8/4 codewords for each nibble value, with the first-transmitted bit as the LSB.
+const HAMMING_8_4: [u8; 16] = [
+    0x15, 0x02, 0x49, 0x5e, 0x64, 0x73, 0x38, 0x2f, 0xd0, 0xc7, 0x8c, 0x9b, 0xa1, 0xb6, 0xfd, 0xea,
+];
+
+/// Characters at the 13 positions of the G0 Latin set that vary by national option.
+const NATIONAL_POSITIONS: [u8; 13] = [
+    0x23, 0x24, 0x40, 0x5b, 0x5c, 0x5d, 0x5e, 0x5f, 0x60, 0x7b, 0x7c, 0x7d, 0x7e,
+];
+
+/// National option sub-sets of the G0 Latin set indexed by control bits C12-C14.
+const NATIONAL_SUBSETS: [[char; 13]; 8] = [
+    // English
+    [
+        '£', '$', '@', '←', '½', '→', '↑', '#', '—', '¼', '‖', '¾', '÷',
+    ],
+    // German
+    [
+        '#', '$', '§', 'Ä', 'Ö', 'Ü', '^', '_', '°', 'ä', 'ö', 'ü', 'ß',
+    ],
+    // Swedish/Finnish/Hungarian
+    [
+        '#', '¤', 'É', 'Ä', 'Ö', 'Å', 'Ü', '_', 'é', 'ä', 'ö', 'å', 'ü',
+    ],
+    // Italian
+    [
+        '£', '$', 'é', '°', 'ç', '→', '↑', '#', 'ù', 'à', 'ò', 'è', 'ì',
+    ],
+    // French
+    [
+        'é', 'ï', 'à', 'ë', 'ê', 'ù', 'î', '#', 'è', 'â', 'ô', 'û', 'ç',
+    ],
+    // Portuguese/Spanish
+    [
+        'ç', '$', '¡', 'á', 'é', 'í', 'ó', 'ú', '¿', 'ü', 'ñ', 'è', 'à',
+    ],
+    // Czech/Slovak
+    [
+        '#', 'ů', 'č', 'ť', 'ž', 'ý', 'í', 'ř', 'é', 'á', 'ě', 'ú', 'š',
+    ],
+    // Unassigned; falls back to English
+    [
+        '£', '$', '@', '←', '½', '→', '↑', '#', '—', '¼', '‖', '¾', '÷',
+    ],
+];
+
+/// Decodes a Hamming 8/4 protected byte, correcting single-bit errors.
+///
+/// Returns [`None`] for uncorrectable errors.
+fn unham_8_4(byte: u8) -> Option<u8> {
+    let (nibble, distance) = HAMMING_8_4
+        .iter()
+        .enumerate()
+        .map(|(n, c)| (n as u8, (c ^ byte).count_ones()))
+        .min_by_key(|(_, d)| *d)
+        .unwrap();
+    if distance <= 1 {
+        Some(nibble)
+    } else {
+        None
+    }
+}
+
+/// Data unit ID of EBU teletext non-subtitle data.
+pub const DATA_UNIT_TELETEXT: u8 = 0x02;
+/// Data unit ID of EBU teletext subtitle data.
+pub const DATA_UNIT_SUBTITLE: u8 = 0x03;
+
+/// One 42-byte teletext packet: a page header (packet 0) or a page row.
+#[derive(Clone)]
+pub struct TeletextPacket {
+    /// Data unit ID ([`DATA_UNIT_TELETEXT`] or [`DATA_UNIT_SUBTITLE`]).
+    pub data_unit_id: u8,
+    /// Magazine number in 1..=8.
+    pub magazine: u8,
+    /// Packet number in 0..=31. Packet 0 is the page header; 1..=24 are display rows.
+    pub packet_number: u8,
+    /// Packet data with bits in transmission order (first-transmitted bit as the LSB).
+    pub data: [u8; 40],
+}
+
+impl Debug for TeletextPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TeletextPacket")
+            .field("data_unit_id", &self.data_unit_id)
+            .field("magazine", &self.magazine)
+            .field("packet_number", &self.packet_number)
+            .finish()
+    }
+}
+
+impl TeletextPacket {
+    /// Parses all teletext data units from a PES payload beginning with `data_identifier`.
+    ///
+    /// Packets with uncorrectable address errors and data units of other types are skipped.
+    pub fn parse_pes_payload<D: AppDetails>(payload: &[u8]) -> Result<Vec<Self>, D> {
+        let mut reader = SliceReader::<D>::new(payload);
+        let mut packets = Vec::new();
+        let data_identifier = reader.read_u8()?;
+        if !(0x10..=0x1f).contains(&data_identifier) {
+            warn!("Not EBU teletext data: {:x}", data_identifier);
+            return Ok(packets);
+        }
+
+        while reader.remaining_len() >= 2 {
+            let data_unit_id = reader.read_u8()?;
+            let data_unit_length = reader.read_u8()? as usize;
+            let mut unit_reader = reader.new_sub_reader(data_unit_length)?;
+            if (data_unit_id != DATA_UNIT_TELETEXT && data_unit_id != DATA_UNIT_SUBTITLE)
+                || data_unit_length != 44
+            {
+                continue;
+            }
+
+            /* field_parity, line_offset, and framing_code */
+            unit_reader.skip(2)?;
+            let address = unit_reader.read_array_ref::<2>()?;
+            let (low, high) = match (
+                unham_8_4(address[0].reverse_bits()),
+                unham_8_4(address[1].reverse_bits()),
+            ) {
+                (Some(low), Some(high)) => (low, high),
+                _ => {
+                    warn!("Uncorrectable teletext packet address");
+                    continue;
+                }
+            };
+            let magazine = match low & 0x7 {
+                0 => 8,
+                m => m,
+            };
+
+            let mut data = [0_u8; 40];
+            for (d, s) in data
+                .iter_mut()
+                .zip(unit_reader.read_array_ref::<40>()?.iter())
+            {
+                *d = s.reverse_bits();
+            }
+            packets.push(Self {
+                data_unit_id,
+                magazine,
+                packet_number: (low >> 3) | (high << 1),
+                data,
+            });
+        }
+
+        Ok(packets)
+    }
+}
+
+/// Teletext PES unit parsed into [`TeletextPacket`] objects.
+pub struct TeletextUnit {
+    unit_length: usize,
+    raw: Vec<u8>,
+    /// Packets contained in the unit once finished.
+    pub packets: Vec<TeletextPacket>,
+}
+
+impl TeletextUnit {
+    /// Creates an empty unit for a PES payload of `unit_length` bytes.
+    pub fn new(unit_length: usize) -> Self {
+        Self {
+            unit_length,
+            raw: Vec::new(),
+            packets: Vec::new(),
+        }
+    }
+}
+
+impl Debug for TeletextUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TeletextUnit")
+            .field("packets", &self.packets)
+            .finish()
+    }
+}
+
+impl<D: AppDetails> PesUnitObject<D> for TeletextUnit {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        if self.raw.capacity() == 0 {
+            self.raw.reserve_exact(self.unit_length);
+        }
+        self.raw.extend_from_slice(slice);
+    }
+
+    fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
+        self.packets = TeletextPacket::parse_pes_payload(&self.raw)?;
+        self.raw = Vec::new();
+        Ok(())
+    }
+
+    fn finish_from_slice(
+        &mut self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<(), D> {
+        self.packets = TeletextPacket::parse_pes_payload(slice)?;
+        Ok(())
+    }
+}
+
+/// A subtitle page displayed over a time range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeletextCue {
+    /// Presentation time the page is shown (90kHz).
+    pub start_pts: u64,
+    /// Presentation time the page is removed (90kHz).
+    pub end_pts: u64,
+    /// Non-empty text rows of the page, top to bottom.
+    pub rows: Vec<String>,
+}
+
+impl TeletextCue {
+    /// Joins the rows with newlines.
+    pub fn text(&self) -> String {
+        self.rows.join("\n")
+    }
+}
+
+/// Decodes the row data of one page into text rows.
+fn decode_rows(rows: &[Option<[u8; 40]>; 24], national_option: u8) -> Vec<String> {
+    let subset = &NATIONAL_SUBSETS[national_option as usize & 0x7];
+    let mut out = Vec::new();
+    for row in rows.iter().flatten() {
+        let boxed = row.iter().any(|b| b & 0x7f == 0x0b);
+        let mut in_box = !boxed;
+        let mut text = String::new();
+        for b in row.iter() {
+            if b.count_ones() & 1 == 0 {
+                /* Parity error */
+                if in_box {
+                    text.push(' ');
+                }
+                continue;
+            }
+            let c = b & 0x7f;
+            match c {
+                0x0b => in_box = true,
+                0x0a => in_box = false,
+                _ if !in_box => {}
+                0x00..=0x1f => text.push(' '),
+                0x7f => text.push('■'),
+                _ => match NATIONAL_POSITIONS.iter().position(|p| *p == c) {
+                    Some(i) => text.push(subset[i]),
+                    None => text.push(c as char),
+                },
+            }
+        }
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            out.push(trimmed.to_string());
+        }
+    }
+    out
+}
+
+/// Follows one teletext page and converts its transmissions into [`TeletextCue`] objects.
+///
+/// Each complete transmission of the page replaces the previously displayed one; a cue is
+/// emitted whenever the displayed text changes.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::es::teletext::{TeletextPacket, TeletextSubtitleDecoder};
+/// use mpegts_io::DefaultAppDetails;
+///
+/// # let pes_payloads: Vec<(u64, Vec<u8>)> = vec![];
+/// let mut decoder = TeletextSubtitleDecoder::new(8, 0x88);
+/// for (pts, payload) in pes_payloads {
+///     let packets = TeletextPacket::parse_pes_payload::<DefaultAppDetails>(&payload).unwrap();
+///     for cue in decoder.push(pts, &packets) {
+///         println!("{} --> {}: {}", cue.start_pts, cue.end_pts, cue.text());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct TeletextSubtitleDecoder {
+    magazine: u8,
+    page: u8,
+    receiving: bool,
+    page_pts: u64,
+    national_option: u8,
+    rows: [Option<[u8; 40]>; 24],
+    shown: Option<(u64, Vec<String>)>,
+}
+
+impl TeletextSubtitleDecoder {
+    /// Creates a decoder following `page` (BCD-style, e.g. `0x88`) of `magazine` (1..=8).
+    pub fn new(magazine: u8, page: u8) -> Self {
+        Self {
+            magazine,
+            page,
+            receiving: false,
+            page_pts: 0,
+            national_option: 0,
+            rows: [None; 24],
+            shown: None,
+        }
+    }
+
+    fn complete_page(&mut self, out: &mut Vec<TeletextCue>) {
+        self.receiving = false;
+        let rows = decode_rows(&self.rows, self.national_option);
+        match self.shown.take() {
+            Some((start_pts, shown_rows)) if shown_rows == rows => {
+                self.shown = Some((start_pts, shown_rows));
+                return;
+            }
+            Some((start_pts, shown_rows)) => out.push(TeletextCue {
+                start_pts,
+                end_pts: self.page_pts,
+                rows: shown_rows,
+            }),
+            None => {}
+        }
+        if !rows.is_empty() {
+            self.shown = Some((self.page_pts, rows));
+        }
+    }
+
+    /// Processes the packets of one PES unit presented at `pts`, returning finished cues.
+    pub fn push(&mut self, pts: u64, packets: &[TeletextPacket]) -> Vec<TeletextCue> {
+        let mut out = Vec::new();
+        for packet in packets {
+            if packet.packet_number == 0 {
+                let header: Vec<Option<u8>> =
+                    packet.data[..8].iter().map(|b| unham_8_4(*b)).collect();
+                let (units, tens, control) = match (header[0], header[1], header[7]) {
+                    (Some(units), Some(tens), Some(control)) => (units, tens, control),
+                    _ => {
+                        warn!("Uncorrectable teletext page header");
+                        continue;
+                    }
+                };
+                let serial = control & 0x1 != 0;
+                if self.receiving && (packet.magazine == self.magazine || serial) {
+                    self.complete_page(&mut out);
+                }
+                if packet.magazine == self.magazine && (tens << 4 | units) == self.page {
+                    self.receiving = true;
+                    self.page_pts = pts;
+                    self.national_option =
+                        ((control >> 1) & 0x1) << 2 | ((control >> 2) & 0x1) << 1 | (control >> 3);
+                    self.rows = [None; 24];
+                }
+            } else if self.receiving
+                && packet.magazine == self.magazine
+                && (1..=23).contains(&packet.packet_number)
+            {
+                self.rows[packet.packet_number as usize] = Some(packet.data);
+            }
+        }
+        out
+    }
+
+    /// Completes any page in progress and returns the remaining cues, ending the displayed page at
+    /// `pts`.
+    pub fn finish(&mut self, pts: u64) -> Vec<TeletextCue> {
+        let mut out = Vec::new();
+        if self.receiving {
+            self.complete_page(&mut out);
+        }
+        if let Some((start_pts, rows)) = self.shown.take() {
+            out.push(TeletextCue {
+                start_pts,
+                end_pts: pts,
+                rows,
+            });
+        }
+        out
+    }
+}
+
+#[test]
+fn test_teletext_subtitle_page() {
+    fn odd_parity(c: u8) -> u8 {
+        if c.count_ones() & 1 == 0 {
+            c | 0x80
+        } else {
+            c
+        }
+    }
+
+    fn data_unit(magazine: u8, packet_number: u8, data: [u8; 40]) -> Vec<u8> {
+        let mut unit = vec![DATA_UNIT_SUBTITLE, 44, 0xe0, 0xe4];
+        let address = (magazine & 0x7) | (packet_number & 0x1) << 3;
+        unit.push(HAMMING_8_4[address as usize].reverse_bits());
+        unit.push(HAMMING_8_4[(packet_number >> 1) as usize].reverse_bits());
+        unit.extend(data.iter().map(|b| b.reverse_bits()));
+        unit
+    }
+
+    fn header(magazine: u8, page: u8) -> Vec<u8> {
+        let mut data = [odd_parity(b' '); 40];
+        data[0] = HAMMING_8_4[(page & 0xf) as usize];
+        data[1] = HAMMING_8_4[(page >> 4) as usize];
+        for d in data[2..8].iter_mut() {
+            *d = HAMMING_8_4[0];
+        }
+        /* German national option (C14 set) */
+        data[7] = HAMMING_8_4[0x8];
+        data_unit(magazine, 0, data)
+    }
+
+    fn row(magazine: u8, row: u8, text: &[u8]) -> Vec<u8> {
+        let mut data = [odd_parity(b' '); 40];
+        data[0] = odd_parity(0x0b);
+        data[1] = odd_parity(0x0b);
+        for (d, c) in data[2..].iter_mut().zip(text.iter()) {
+            *d = odd_parity(*c);
+        }
+        data[2 + text.len()] = odd_parity(0x0a);
+        data_unit(magazine, row, data)
+    }
+
+    let parse = |units: Vec<Vec<u8>>| {
+        let mut payload = vec![0x10];
+        payload.extend(units.into_iter().flatten());
+        TeletextPacket::parse_pes_payload::<crate::DefaultAppDetails>(&payload).unwrap()
+    };
+
+    let mut decoder = TeletextSubtitleDecoder::new(8, 0x88);
+    let first = parse(vec![
+        header(8, 0x88),
+        row(8, 22, b"Gr{e"),
+        row(8, 20, b"Hallo"),
+    ]);
+    assert_eq!(first.len(), 3);
+    assert!(decoder.push(1000, &first).is_empty());
+    assert!(decoder.push(2000, &parse(vec![header(8, 0x88)])).is_empty());
+    let cues = decoder.push(3000, &parse(vec![header(8, 0x88)]));
+    assert_eq!(
+        cues,
+        vec![TeletextCue {
+            start_pts: 1000,
+            end_pts: 2000,
+            rows: vec!["Hallo".to_string(), "Gräe".to_string()],
+        }]
+    );
+    assert!(decoder.finish(4000).is_empty());
+}
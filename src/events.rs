@@ -0,0 +1,262 @@
+use super::psi::{Pmt, PsiTableSyntax};
+use super::{AdaptationField, AppDetails, MpegTsParser, PatDiff, PcrTimestamp, PmtDiff};
+use crate::prelude::*;
+
+/// Largest forward PCR step (in 90kHz ticks) not considered a discontinuity.
+const MAX_PCR_STEP: u64 = 90000;
+
+/// Notable change in the structure of the transport stream detected while parsing.
+///
+/// Events are attached to the [`Packet`](crate::Packet) in which they were detected, so
+/// applications can react (e.g. start or stop capturing a track) without diffing tables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ParserEvent {
+    /// The program list changed with a new PAT.
+    ///
+    /// Not emitted for the first PAT received.
+    ProgramsChanged {
+        /// Changes against the previous PAT.
+        diff: PatDiff,
+    },
+    /// A PMT was received for a program for the first time or with a new version.
+    PmtChanged {
+        /// Program number of the PMT.
+        program_num: u16,
+        /// PID carrying the PMT.
+        pmt_pid: u16,
+        /// New version of the PMT.
+        version: u8,
+        /// Changes against the previous PMT of the program; every stream is added by the first
+        /// PMT.
+        diff: PmtDiff,
+    },
+    /// An elementary stream was added to a program.
+    ///
+    /// Emitted for every stream of a program when its first PMT is received.
+    StreamAdded {
+        /// Program number containing the stream.
+        program_num: u16,
+        /// Elementary PID of the stream.
+        pid: u16,
+        /// Stream type of the stream.
+        stream_type: u8,
+    },
+    /// An elementary stream was removed from a program.
+    ///
+    /// A stream whose type changes is reported as removed and then added.
+    StreamRemoved {
+        /// Program number that contained the stream.
+        program_num: u16,
+        /// Elementary PID of the stream.
+        pid: u16,
+        /// Stream type of the stream.
+        stream_type: u8,
+    },
+    /// The PCR PID of a program changed.
+    PcrPidChanged {
+        /// Program number.
+        program_num: u16,
+        /// Previous PCR PID.
+        old_pid: u16,
+        /// New PCR PID.
+        new_pid: u16,
+    },
+    /// The PCR of a program jumped, either signaled by the discontinuity indicator or detected
+    /// as a backwards step or a forward step of more than one second.
+    PcrDiscontinuity {
+        /// Program number.
+        program_num: u16,
+        /// Last PCR before the discontinuity.
+        previous: PcrTimestamp,
+        /// First PCR after the discontinuity.
+        current: PcrTimestamp,
+        /// The discontinuity was signaled by the adaptation field.
+        indicated: bool,
+    },
+    /// A PAT or PMT section was received for the first time or with a new version.
+    ///
+    /// Versions are tracked separately for currently applicable tables and tables announced
+    /// for the future, so both are reported once per version.
+    TableVersionChanged {
+        /// PID carrying the table.
+        pid: u16,
+        /// Table ID.
+        table_id: u8,
+        /// Table ID extension (transport stream ID of a PAT, program number of a PMT).
+        table_id_extension: u16,
+        /// Previously received version, if any.
+        previous_version: Option<u8>,
+        /// New version.
+        version: u8,
+        /// The table is currently applicable rather than the next to become applicable.
+        current_next_indicator: bool,
+    },
+    /// The continuity counter of a packet did not follow the previous packet of its PID, or a
+    /// packet was repeated more than once.
+    ///
+    /// Only reported when continuity checking is enabled and strict mode is not.
+    ContinuityError {
+        /// PID of the packet.
+        pid: u16,
+        /// Continuity counter following the previous packet.
+        expected: u8,
+        /// Continuity counter of the packet.
+        got: u8,
+    },
+}
+
+/// Last known structure of a program, used to detect changes between PMT versions.
+#[derive(Debug)]
+pub(crate) struct ProgramState {
+    version: u8,
+    pmt: Pmt,
+    last_pcr: Option<PcrTimestamp>,
+}
+
+/// Elementary streams of a PMT as (PID, stream type) pairs.
+fn pmt_streams(pmt: &Pmt) -> Vec<(u16, u8)> {
+    pmt.es_infos
+        .iter()
+        .map(|info| {
+            (
+                info.header.elementary_pid(),
+                info.header.stream_type().into(),
+            )
+        })
+        .collect()
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Compares the version of a newly received PAT or PMT section against the last one
+    /// received on the PID and queues an event if it changed.
+    pub(crate) fn track_table_version(
+        &mut self,
+        pid: u16,
+        table_id: u8,
+        table_syntax: &PsiTableSyntax,
+    ) {
+        let current_next_indicator = table_syntax.current_next_indicator();
+        let version = table_syntax.version();
+        let previous_version = self
+            .table_versions
+            .insert((pid, table_id, current_next_indicator), version);
+        if previous_version != Some(version) {
+            self.pending_events.push(ParserEvent::TableVersionChanged {
+                pid,
+                table_id,
+                table_id_extension: table_syntax.table_id_extension(),
+                previous_version,
+                version,
+                current_next_indicator,
+            });
+        }
+    }
+
+    /// Compares a newly received PMT against the last known version of the program and queues
+    /// the resulting events.
+    pub(crate) fn update_program_state(
+        &mut self,
+        program_num: u16,
+        pmt_pid: u16,
+        version: u8,
+        pmt: &Pmt,
+    ) {
+        let old = self.program_states.get(&program_num);
+        let diff = PmtDiff::between(old.map(|old| &old.pmt), pmt);
+        if let Some(old) = old {
+            if old.version == version && diff.is_empty() {
+                /* Repeated PMT */
+                return;
+            }
+        }
+
+        let events = &mut self.pending_events;
+        let pcr_pid_change = diff.pcr_pid;
+        events.push(ParserEvent::PmtChanged {
+            program_num,
+            pmt_pid,
+            version,
+            diff,
+        });
+        let streams = pmt_streams(pmt);
+        let old_streams = old.map_or(Vec::new(), |old| pmt_streams(&old.pmt));
+        for &(pid, stream_type) in old_streams.iter() {
+            if !streams.contains(&(pid, stream_type)) {
+                events.push(ParserEvent::StreamRemoved {
+                    program_num,
+                    pid,
+                    stream_type,
+                });
+            }
+        }
+        for &(pid, stream_type) in streams.iter() {
+            if !old_streams.contains(&(pid, stream_type)) {
+                events.push(ParserEvent::StreamAdded {
+                    program_num,
+                    pid,
+                    stream_type,
+                });
+            }
+        }
+        if let Some((old_pid, new_pid)) = pcr_pid_change {
+            events.push(ParserEvent::PcrPidChanged {
+                program_num,
+                old_pid,
+                new_pid,
+            });
+        }
+
+        /* Keep tracking the PCR while its PID is unchanged */
+        let last_pcr = old
+            .filter(|_| pcr_pid_change.is_none())
+            .and_then(|old| old.last_pcr);
+        self.program_states.insert(
+            program_num,
+            ProgramState {
+                version,
+                pmt: pmt.clone(),
+                last_pcr,
+            },
+        );
+    }
+
+    /// Updates the latest PCR of every program using `pid` as its PCR PID and queues
+    /// discontinuity events.
+    pub(crate) fn track_pcr(&mut self, pid: u16, adaptation_field: &AdaptationField) {
+        let pcr = match adaptation_field.pcr {
+            Some(pcr) => pcr,
+            None => return,
+        };
+        let indicated = adaptation_field.header.discontinuity();
+        for (program_num, state) in self.program_states.iter_mut() {
+            if state.pmt.header.pcr_pid() != pid {
+                continue;
+            }
+            let mut discontinuous = false;
+            if let Some(previous) = state.last_pcr {
+                let step = pcr.base.wrapping_sub(previous.base);
+                if indicated || step > MAX_PCR_STEP {
+                    discontinuous = true;
+                    self.pending_events.push(ParserEvent::PcrDiscontinuity {
+                        program_num: *program_num,
+                        previous,
+                        current: pcr,
+                        indicated,
+                    });
+                }
+            }
+            state.last_pcr = Some(pcr);
+            if let Some(stats) = self.stats.as_mut() {
+                stats.record_program_pcr(*program_num, pcr, discontinuous);
+            }
+        }
+    }
+
+    /// Latest PCR received for a program, once its PMT has been parsed.
+    pub fn current_pcr(&self, program_num: u16) -> Option<PcrTimestamp> {
+        self.program_states
+            .get(&program_num)
+            .and_then(|state| state.last_pcr)
+    }
+}
@@ -0,0 +1,113 @@
+use super::{AppDetails, ErrorDetails, MpegTsParser, Packet, Result, SliceReader};
+
+/// Sizes of the extra data framing each 188-byte MPEG-TS packet.
+///
+/// Transport streams are commonly stored or transmitted with a 4-byte prefix (BDAV and other
+/// "timed" streams) and/or a 16 or 20-byte suffix (Reed-Solomon parity of DVB and ATSC
+/// transmissions). The same [`Framing`] describes the stored packet size for both reading and
+/// writing.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::Framing;
+/// assert_eq!(Framing::PLAIN.packet_size(), 188);
+/// assert_eq!(Framing::TIMED.packet_size(), 192);
+/// assert_eq!(Framing::DVB_RS.packet_size(), 204);
+/// assert_eq!(Framing::ATSC_RS.packet_size(), 208);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Framing {
+    /// Number of bytes preceding the sync byte (0 or 4).
+    pub prefix: usize,
+    /// Number of bytes following the 188-byte packet (0, 16 or 20).
+    pub suffix: usize,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+impl Framing {
+    /// Plain 188-byte packets.
+    pub const PLAIN: Framing = Framing {
+        prefix: 0,
+        suffix: 0,
+    };
+    /// 192-byte packets with a 4-byte prefix (BDAV/M2TS and other timed streams).
+    pub const TIMED: Framing = Framing {
+        prefix: 4,
+        suffix: 0,
+    };
+    /// 204-byte packets with 16 bytes of DVB Reed-Solomon parity.
+    pub const DVB_RS: Framing = Framing {
+        prefix: 0,
+        suffix: 16,
+    };
+    /// 208-byte packets with 20 bytes of ATSC Reed-Solomon parity.
+    pub const ATSC_RS: Framing = Framing {
+        prefix: 0,
+        suffix: 20,
+    };
+
+    /// Total stored size of one framed packet.
+    pub const fn packet_size(&self) -> usize {
+        self.prefix + 188 + self.suffix
+    }
+
+    /// Checks that the prefix and suffix sizes are among the known variants.
+    pub fn is_supported(&self) -> bool {
+        matches!(self.prefix, 0 | 4) && matches!(self.suffix, 0 | 16 | 20)
+    }
+
+    /// Splits one framed packet into prefix, MPEG-TS packet, and suffix.
+    ///
+    /// Returns [`None`] if `data` is not exactly [`Framing::packet_size`] bytes long.
+    pub fn split<'a>(&self, data: &'a [u8]) -> Option<(&'a [u8], &'a [u8], &'a [u8])> {
+        if data.len() != self.packet_size() {
+            return None;
+        }
+        let (prefix, rest) = data.split_at(self.prefix);
+        let (packet, suffix) = rest.split_at(188);
+        Some((prefix, packet, suffix))
+    }
+}
+
+/// Top-level parsed structure for one framed packet.
+#[derive(Debug)]
+pub struct FramedPacket<'a, D: AppDetails> {
+    /// Uninterpreted prefix bytes.
+    pub prefix: &'a [u8],
+    /// MPEG-TS packet.
+    pub packet: Packet<'a, D>,
+    /// Uninterpreted suffix bytes.
+    pub suffix: &'a [u8],
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Parse data for exactly one packet stored with the given [`Framing`].
+    ///
+    /// The prefix and suffix bytes are returned uninterpreted alongside the [`Packet`].
+    pub fn parse_framed<'a>(
+        &mut self,
+        framing: Framing,
+        data: &'a [u8],
+    ) -> Result<FramedPacket<'a, D>, D> {
+        let (prefix, _, suffix) = match framing.split(data) {
+            Some(parts) => parts,
+            None => {
+                return Err(SliceReader::<D>::new(data)
+                    .make_error(ErrorDetails::<D>::BadPacketLength(data.len())))
+            }
+        };
+        let mut reader = SliceReader::new(data);
+        reader.skip(framing.prefix)?;
+        Ok(FramedPacket {
+            prefix,
+            packet: self.parse_internal(reader.new_sub_reader(188)?)?,
+            suffix,
+        })
+    }
+}
@@ -0,0 +1,174 @@
+use super::{parse_pcr, parse_timestamp, Framing, PcrTimestamp, StreamId, Timestamp90k};
+use crate::prelude::*;
+use core::convert::TryInto;
+use hashbrown::HashMap;
+
+/// Packet with the `random_access` indicator set, recorded by [`RandomAccessIndex`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RandomAccessPoint {
+    /// Byte offset of the packet within the stream.
+    pub offset: u64,
+    /// PID of the packet.
+    pub pid: u16,
+    /// PCR carried in the adaptation field of the packet.
+    pub pcr: Option<PcrTimestamp>,
+    /// PTS of the PES unit starting in this packet or the next unit start of the PID.
+    pub pts: Option<Timestamp90k>,
+}
+
+/// Seek table of the random access points of a stream, built in one pass over the raw packets.
+///
+/// Packets are only inspected up to the PES header, so indexing is much cheaper than parsing
+/// the stream with a [`MpegTsParser`](crate::MpegTsParser).
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::{Framing, RandomAccessIndex, Timestamp90k};
+///
+/// let data = std::fs::read("capture.ts")?;
+/// let index = RandomAccessIndex::from_packets(&data, Framing::PLAIN);
+/// if let Some(point) = index.seek(0x1011, Timestamp90k::new(90000 * 60)) {
+///     println!("Decode from byte {}", point.offset);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct RandomAccessIndex {
+    points: Vec<RandomAccessPoint>,
+    /// Points of each PID still waiting for the PTS of a unit start.
+    pending: HashMap<u16, Vec<usize>>,
+}
+
+impl RandomAccessIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes a buffer of packets stored with the given [`Framing`], with offsets relative to
+    /// the start of `data`.
+    pub fn from_packets(data: &[u8], framing: Framing) -> Self {
+        let mut index = Self::new();
+        for (i, packet) in data.chunks_exact(framing.packet_size()).enumerate() {
+            let offset = (i * framing.packet_size()) as u64;
+            let packet = &packet[framing.prefix..framing.prefix + 188];
+            index.push(offset, packet.try_into().unwrap());
+        }
+        index
+    }
+
+    /// Inspects one 188-byte MPEG-TS packet found at byte `offset` of the stream.
+    pub fn push(&mut self, offset: u64, packet: &[u8; 188]) {
+        if packet[0] != 0x47 {
+            return;
+        }
+        let pid = u16::from_be_bytes([packet[1], packet[2]]) & 0x1fff;
+        let pusi = packet[1] & 0x40 != 0;
+        let has_adaptation_field = packet[3] & 0x20 != 0;
+        let has_payload = packet[3] & 0x10 != 0;
+
+        let mut payload_start = 4;
+        if has_adaptation_field {
+            let length = packet[4] as usize;
+            if length > 183 {
+                return;
+            }
+            payload_start += 1 + length;
+            let field = &packet[5..5 + length];
+            if length > 0 && field[0] & 0x40 != 0 {
+                let pcr = if field[0] & 0x10 != 0 && length >= 7 {
+                    Some(parse_pcr(field[1..7].try_into().unwrap()))
+                } else {
+                    None
+                };
+                self.pending.entry(pid).or_default().push(self.points.len());
+                self.points.push(RandomAccessPoint {
+                    offset,
+                    pid,
+                    pcr,
+                    pts: None,
+                });
+            }
+        }
+
+        if has_payload && pusi {
+            if let Some(pts) = read_pes_pts(&packet[payload_start..]) {
+                for i in self.pending.remove(&pid).unwrap_or_default() {
+                    self.points[i].pts = Some(pts);
+                }
+            }
+        }
+    }
+
+    /// Recorded random access points in stream order.
+    pub fn points(&self) -> &[RandomAccessPoint] {
+        &self.points
+    }
+
+    /// Last random access point of `pid` with a PTS at or before `pts`, which is where decoding
+    /// has to start to present `pts`.
+    pub fn seek(&self, pid: u16, pts: Timestamp90k) -> Option<&RandomAccessPoint> {
+        self.points
+            .iter()
+            .rev()
+            .find(|point| point.pid == pid && point.pts.is_some_and(|p| p.0 <= pts.0))
+    }
+}
+
+/// Reads the PTS of a PES header at the start of `payload`.
+fn read_pes_pts(payload: &[u8]) -> Option<Timestamp90k> {
+    if payload.len() < 14 || payload[..3] != [0, 0, 1] {
+        return None;
+    }
+    if !StreamId::from(payload[3]).has_optional_header() || payload[7] & 0x80 == 0 {
+        return None;
+    }
+    Some(Timestamp90k(parse_timestamp(
+        payload[9..14].try_into().unwrap(),
+    )))
+}
+
+#[test]
+fn test_random_access_index() {
+    use crate::testgen::TestStreamGenerator;
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+    gen.write_pat().write_pmt();
+    for pts in [0, 3003, 6006].iter() {
+        gen.write_pes(0x1011, Some(*pts), None, &[0; 500]);
+    }
+    let mut packets = gen.packets().to_vec();
+    /* Mark the first and last unit starts, which carry a PCR */
+    let starts: Vec<usize> = packets
+        .iter()
+        .enumerate()
+        .filter(|(_, packet)| packet[1] & 0x40 != 0 && packet[2] == 0x11)
+        .map(|(i, _)| i)
+        .collect();
+    packets[starts[0]][5] |= 0x40;
+    packets[starts[2]][5] |= 0x40;
+    /* Adaptation field only packet announcing the last unit */
+    let mut marker = [0xff; 188];
+    marker[..6].copy_from_slice(&[0x47, 0x10, 0x11, 0x20, 183, 0x40]);
+    packets.insert(starts[2], marker);
+    let data: Vec<u8> = packets.concat();
+
+    let index = RandomAccessIndex::from_packets(&data, Framing::PLAIN);
+    let points = index.points();
+    assert_eq!(points.len(), 3);
+    assert_eq!(points[0].offset, starts[0] as u64 * 188);
+    assert_eq!(points[0].pts, Some(Timestamp90k(0)));
+    assert_eq!(points[0].pcr.unwrap().base, Timestamp90k(0));
+    assert_eq!(points[1].offset, starts[2] as u64 * 188);
+    assert_eq!(points[1].pcr, None);
+    assert_eq!(points[1].pts, Some(Timestamp90k(6006)));
+    assert_eq!(points[2].offset, (starts[2] + 1) as u64 * 188);
+    assert_eq!(points[2].pts, Some(Timestamp90k(6006)));
+
+    assert_eq!(index.seek(0x1011, Timestamp90k(5000)), Some(&points[0]));
+    assert_eq!(index.seek(0x1011, Timestamp90k(7000)), Some(&points[2]));
+    assert_eq!(index.seek(0x1100, Timestamp90k(7000)), None);
+}
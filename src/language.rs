@@ -0,0 +1,121 @@
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
+
+/// ISO 639-2 bibliographic codes paired with their terminological equivalents.
+const ALIASES: [(&[u8; 3], &[u8; 3]); 20] = [
+    (b"alb", b"sqi"),
+    (b"arm", b"hye"),
+    (b"baq", b"eus"),
+    (b"bur", b"mya"),
+    (b"chi", b"zho"),
+    (b"cze", b"ces"),
+    (b"dut", b"nld"),
+    (b"fre", b"fra"),
+    (b"geo", b"kat"),
+    (b"ger", b"deu"),
+    (b"gre", b"ell"),
+    (b"ice", b"isl"),
+    (b"mac", b"mkd"),
+    (b"mao", b"mri"),
+    (b"may", b"msa"),
+    (b"per", b"fas"),
+    (b"rum", b"ron"),
+    (b"slo", b"slk"),
+    (b"tib", b"bod"),
+    (b"wel", b"cym"),
+];
+
+/// Three-letter ISO 639-2 language code as carried by language descriptors.
+///
+/// Codes are stored lowercased. ISO 639-2 defines separate bibliographic (B) and terminological
+/// (T) codes for some languages (e.g. `ger` and `deu`); [`LanguageCode::normalized`] maps B codes
+/// to their T equivalent so that both forms compare equal after normalization.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::LanguageCode;
+/// let code = LanguageCode::from_bytes(*b"GER");
+/// assert_eq!(code.to_string(), "ger");
+/// assert_eq!(code.normalized(), "deu".parse().unwrap());
+/// assert!(code.is_same_language(&LanguageCode::from_bytes(*b"deu")));
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LanguageCode([u8; 3]);
+
+impl LanguageCode {
+    /// Undetermined language (`und`).
+    pub const UNDETERMINED: LanguageCode = LanguageCode(*b"und");
+
+    /// Creates a code from the three raw bytes of a descriptor.
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        Self([
+            bytes[0].to_ascii_lowercase(),
+            bytes[1].to_ascii_lowercase(),
+            bytes[2].to_ascii_lowercase(),
+        ])
+    }
+
+    /// Raw bytes of the code.
+    pub fn as_bytes(&self) -> &[u8; 3] {
+        &self.0
+    }
+
+    /// The code as a string slice if it consists of ASCII letters.
+    pub fn as_str(&self) -> Option<&str> {
+        if self.0.iter().all(u8::is_ascii_alphabetic) {
+            core::str::from_utf8(&self.0).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Maps ISO 639-2 bibliographic codes to their terminological equivalent.
+    pub fn normalized(&self) -> Self {
+        match ALIASES.iter().find(|(b, _)| **b == self.0) {
+            Some((_, t)) => Self(**t),
+            None => *self,
+        }
+    }
+
+    /// Compares two codes after [`LanguageCode::normalized`] is applied to both.
+    pub fn is_same_language(&self, other: &LanguageCode) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl From<[u8; 3]> for LanguageCode {
+    fn from(bytes: [u8; 3]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl FromStr for LanguageCode {
+    type Err = ();
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [a, b, c] if s.is_ascii() => Ok(Self::from_bytes([*a, *b, *c])),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for LanguageCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for b in self.0.iter() {
+            if b.is_ascii_graphic() {
+                write!(f, "{}", *b as char)?;
+            } else {
+                write!(f, "\\x{:02x}", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for LanguageCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "LanguageCode({})", self)
+    }
+}
@@ -1,4 +1,4 @@
-//! Library for reading and (eventually) writing MPEG transport streams.
+//! Library for reading and writing MPEG transport streams.
 //!
 //! # Usage
 //! Simply add this crate as a dependency in your `Cargo.toml`.
@@ -7,37 +7,157 @@
 //! [dependencies]
 //! mpegts-io = "~0.1.0"
 //! ```
+//!
+//! # `no_std`
+//! Packet parsing only requires `alloc`. Disabling the default `std` feature removes the
+//! [`std::io`] based readers and writers and the MObj assembler.
+//!
+//! ```toml
+//! [dependencies]
+//! mpegts-io = { version = "~0.1.0", default-features = false }
+//! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(unused)]
 #![deny(missing_docs, unsafe_code, warnings)]
 
+extern crate alloc;
+
+/// Items of the `std` prelude that `no_std` builds import from `alloc`.
+mod prelude {
+    pub(crate) use alloc::borrow::ToOwned;
+    pub(crate) use alloc::boxed::Box;
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec::Vec;
+    pub(crate) use alloc::{format, vec};
+}
+use prelude::*;
+
+use alloc::collections::BTreeMap;
+use core::convert::From;
+use core::fmt::{Debug, Formatter};
+use core::result;
 use crc::{Crc, Digest, CRC_32_MPEG_2};
+use hashbrown::{HashMap, HashSet};
 use log::warn;
 use modular_bitfield_msb::prelude::*;
-use std::collections::{HashMap, HashSet};
-use std::convert::From;
-use std::fmt::{Debug, Formatter};
-use std::result;
 
 mod slice_reader;
-pub use slice_reader::SliceReader;
+pub use slice_reader::{BitReader, SliceReader, SliceReaderCheckpoint};
+
+mod framing;
+pub use framing::{FramedPacket, Framing};
+
+mod language;
+pub use language::LanguageCode;
+
+mod diff;
+pub use diff::{DescriptorDiff, PatDiff, PmtDiff};
+mod events;
+pub use events::ParserEvent;
+use events::ProgramState;
+
+mod warnings;
+pub use warnings::Warning;
+
+mod demux;
+use demux::DemuxHandlers;
+pub use demux::DemuxUnit;
+
+mod program_filter;
+pub use program_filter::PidFilter;
+use program_filter::ProgramFilter;
+
+mod stats;
+pub use stats::{PcrStats, PidStats, ScramblingTransition, StreamStats};
+
+mod rebase;
+pub use rebase::TimestampRebaser;
+
+mod index;
+pub use index::{RandomAccessIndex, RandomAccessPoint};
+
+mod timestamp;
+pub use timestamp::Timestamp90k;
+
+mod builder;
+use builder::{ContinuityState, ParserConfig};
+pub use builder::{MpegTsParserBuilder, TeiPolicy};
+
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+pub use writer::MpegTsWriter;
+
+#[cfg(feature = "std")]
+mod mux;
+#[cfg(feature = "std")]
+pub use mux::{Muxer, MuxerStream};
+
+mod reader;
+#[cfg(feature = "std")]
+pub use reader::MpegTsReader;
+pub use reader::{OwnedPacket, OwnedPayload};
+
+mod chunked;
+pub use chunked::{ChunkedPackets, ChunkedParser};
+
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+pub use stream::MpegTsStream;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "parallel")]
+pub use parallel::parse_parallel;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+
+#[cfg(feature = "json")]
+pub mod report;
 
 mod payload_unit;
-use payload_unit::{PayloadUnitBuilder, PayloadUnitObject};
+pub use payload_unit::FlushedUnit;
+use payload_unit::{BufferPool, PayloadUnitBuilder, PayloadUnitObject};
+
+mod stream_type;
+pub use stream_type::StreamType;
 
 mod psi;
 use psi::PsiBuilder;
 pub use psi::{
-    Descriptor, ElementaryStreamInfo, ElementaryStreamInfoHeader, PatEntry, PmtHeader, Psi,
-    PsiData, PsiHeader, PsiTableSyntax,
+    CompleteTable, Descriptor, DolbyVisionLayer, DolbyVisionStream, ElementaryStreamInfo,
+    ElementaryStreamInfoHeader, EsStreamInfo, PatBuilder, PatEntry, Pmt, PmtBuilder, PmtHeader,
+    Psi, PsiData, PsiHeader, PsiTableSyntax, SectionCollector,
 };
 
 mod pes;
-pub use pes::{Pes, PesHeader, PesOptionalHeader, PesUnitObject};
+pub use pes::{
+    DsmTrickMode, PStdBuffer, Pes, PesExtension, PesHeader, PesOptionalHeader, PesUnitObject,
+    ProgramPacketSequenceCounter, StreamId,
+};
 
+pub mod analysis;
+pub mod atsc;
 pub mod bdav;
+pub mod ca;
+pub mod descriptor;
+pub mod dsmcc;
+pub mod dvb;
 use bdav::DefaultBdavAppDetails;
 
+pub mod timed;
+
+pub mod es;
+
+#[cfg(feature = "std")]
+pub mod testgen;
+
 const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
 type CrcDigest = Digest<'static, u32>;
 
@@ -62,6 +182,31 @@ pub enum ErrorDetails<D: AppDetails> {
     BadPesHeader,
     /// Encountered when a PSI unit fails CRC check.
     PsiCrcMismatch,
+    /// Encountered when packet data does not match the size expected by its [`Framing`].
+    /// The [`usize`] parameter is the length of the offending data.
+    BadPacketLength(usize),
+    /// Encountered in strict mode when a packet's continuity counter does not follow the previous
+    /// packet of the PID. The [`u16`] parameter is the PID.
+    ContinuityError(u16),
+    /// Encountered in strict mode when a unit starts before the previous unit of the PID was
+    /// finished. The [`u16`] parameter is the PID.
+    UnfinishedPayloadUnit(u16),
+    /// Encountered in strict mode when a continuation packet arrives without a started unit.
+    /// The [`u16`] parameter is the PID.
+    UnexpectedContinuation(u16),
+    /// Encountered in strict mode when a unit would exceed the configured pending unit limit.
+    TooManyPendingUnits,
+    /// Encountered with [`TeiPolicy::Error`] when a packet has its transport error indicator set.
+    /// The [`u16`] parameter is the PID.
+    TransportError(u16),
+    /// Encountered when an Exp-Golomb code has more than 31 leading zero bits, exceeding the
+    /// 32-bit range.
+    BadExpGolomb,
+    /// Encountered when fields of an H.264 or HEVC parameter set hold values out of range.
+    BadParameterSet,
+    /// Encountered when reading from the underlying stream of an [`MpegTsReader`] fails.
+    #[cfg(feature = "std")]
+    IoError(std::io::Error),
     /// Application-defined error extension. Specified via [`AppDetails::AppErrorDetails`].
     AppError(D::AppErrorDetails),
 }
@@ -70,7 +215,7 @@ pub enum ErrorDetails<D: AppDetails> {
 /// and an error extension variant for these parsers via [`ErrorDetails::AppError`].
 ///
 /// See [`DefaultBdavAppDetails`] for an example of an application-defined AppDetails.
-pub trait AppDetails: Default {
+pub trait AppDetails: Default + 'static {
     /// The extension error type exposed via [`ErrorDetails::AppError`].
     type AppErrorDetails: Debug;
 
@@ -80,9 +225,20 @@ pub trait AppDetails: Default {
     /// Application-defined function to map a PES unit-start packet's `pid` into a new
     /// [`PesUnitObject`].
     ///
+    /// `stream_info` carries the stream type and descriptors of the PID from the PMT, if a PMT
+    /// listing the PID has been parsed. This allows choosing parsers by codec rather than PID.
+    /// `unit_length` is 0 for unbounded video units, whose length is only known once the next
+    /// unit of the PID starts. `self` is the instance owned by the parser, so runtime configuration such as selected PIDs
+    /// can be set with [`MpegTsParserBuilder::app_details`] or [`MpegTsParser::app_details_mut`].
+    ///
     /// The finished object will be returned to the application via [`Payload::Pes`] when the final
     /// packet is read.
-    fn new_pes_unit_data(pid: u16, unit_length: usize) -> Option<Box<dyn PesUnitObject<Self>>>;
+    fn new_pes_unit_data(
+        &self,
+        pid: u16,
+        unit_length: usize,
+        stream_info: Option<&EsStreamInfo>,
+    ) -> Option<Box<dyn PesUnitObject<Self>>>;
 }
 
 /// Basic [`AppDetails`] implementation with no added functionality.
@@ -94,7 +250,12 @@ impl AppDetails for DefaultAppDetails {
 
     type AppParserStorage = ();
 
-    fn new_pes_unit_data(pid: u16, unit_length: usize) -> Option<Box<dyn PesUnitObject<Self>>> {
+    fn new_pes_unit_data(
+        &self,
+        pid: u16,
+        unit_length: usize,
+        stream_info: Option<&EsStreamInfo>,
+    ) -> Option<Box<dyn PesUnitObject<Self>>> {
         None
     }
 }
@@ -113,7 +274,8 @@ pub type Result<T, D> = result::Result<T, Error<D>>;
 
 /// TSC information used in a packet's payload.
 #[repr(u8)]
-#[derive(Debug, BitfieldSpecifier)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, BitfieldSpecifier)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[bits = 2]
 pub enum TransportScramblingControl {
     /// Not scrambled.
@@ -183,18 +345,19 @@ macro_rules! pts_format_args {
 /// Program clock reference (PCR) for synchronizing the decoder with the encoder.
 ///
 /// Periodically sent for every program contained in the transport stream.
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PcrTimestamp {
-    /// 33-bits of a 90kHz base clock. May be formatted with [`pts_format_args`].
-    pub base: u64,
+    /// 33-bits of a 90kHz base clock.
+    pub base: Timestamp90k,
     /// 9-bits of a 27MHz clock rolling over every 300 counts to the base.
     pub extension: u16,
 }
 
 impl Debug for PcrTimestamp {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("PcrTimestamp")
-            .field("base", &pts_format_args!(self.base))
+            .field("base", &self.base)
             .field("extension", &self.extension)
             .finish()
     }
@@ -202,6 +365,7 @@ impl Debug for PcrTimestamp {
 
 /// Non-payload packet metadata.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AdaptationField {
     /// Header describing which fields are contained.
     pub header: AdaptationFieldHeader,
@@ -209,6 +373,59 @@ pub struct AdaptationField {
     pub pcr: Option<PcrTimestamp>,
     /// Original Program Clock Reference.
     pub opcr: Option<PcrTimestamp>,
+    /// Adaptation field extension.
+    pub extension: Option<AdaptationFieldExtension>,
+}
+
+/// Legal time window of an adaptation field extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LegalTimeWindow {
+    /// Set if the offset is valid.
+    pub valid: bool,
+    /// Offset in units of (300 / fs) seconds, where fs is the system clock frequency.
+    pub offset: u16,
+}
+
+/// Seamless splice point of an adaptation field extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SeamlessSplice {
+    /// Splice type, defining the splice decoding delay and maximum splice rate.
+    pub splice_type: u8,
+    /// Decoding time of the first access unit after the splice point.
+    pub dts_next_au: Timestamp90k,
+}
+
+/// Optional fields of the adaptation field extension.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Timestamp90k};
+/// let mut packet = [0xff_u8; 188];
+/// packet[..18].copy_from_slice(&[
+///     0x47, 0x01, 0x00, 0x20, 183, 0x01, 11, 0xff, 0x92, 0x34, 0xc0, 0x03, 0xe8, 0x31, 0x00,
+///     0x37, 0x77, 0x41,
+/// ]);
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let parsed = parser.parse(&packet).unwrap();
+/// let extension = parsed.adaptation_field.unwrap().extension.unwrap();
+/// assert_eq!(extension.ltw.unwrap().offset, 0x1234);
+/// assert_eq!(extension.piecewise_rate, Some(1000));
+/// let splice = extension.seamless_splice.unwrap();
+/// assert_eq!(splice.splice_type, 3);
+/// assert_eq!(splice.dts_next_au, Timestamp90k(900000));
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AdaptationFieldExtension {
+    /// Legal time window.
+    pub ltw: Option<LegalTimeWindow>,
+    /// Piecewise rate of the PID in units of 50 bytes/s.
+    pub piecewise_rate: Option<u32>,
+    /// Seamless splice point.
+    pub seamless_splice: Option<SeamlessSplice>,
 }
 
 /// Parsed payload of the packet.
@@ -230,13 +447,33 @@ pub enum Payload<'a, D> {
 
 /// Top-level parsed structure for one MPEG-TS packet.
 #[derive(Debug)]
-pub struct Packet<'a, D> {
+pub struct Packet<'a, D: AppDetails> {
     /// Packet link-layer header.
     pub header: PacketHeader,
     /// Optional adaptation field metadata.
     pub adaptation_field: Option<AdaptationField>,
     /// Optional payload data.
     pub payload: Option<Payload<'a, D>>,
+    /// Byte offset of the payload within the 188-byte packet, if the packet carries payload.
+    ///
+    /// For PSI units this is the location of the pointer field.
+    pub payload_offset: Option<usize>,
+    /// Number of stuffing bytes at the end of the adaptation field.
+    pub stuffing_len: usize,
+    /// Set if continuity checking found the packet to repeat the previous packet of its PID.
+    /// The payload of such packets is returned as [`Payload::Raw`] without being assembled.
+    pub duplicate: bool,
+    /// Stream structure changes detected while parsing this packet.
+    pub events: Vec<ParserEvent>,
+    /// Soft errors recovered from while parsing this packet.
+    pub warnings: Vec<Warning>,
+    /// Error the payload failed to parse with in lenient mode, in which case the payload is
+    /// returned as [`Payload::Raw`]. See [`MpegTsParserBuilder::lenient`].
+    pub payload_error: Option<Error<D>>,
+    /// Unbounded PES unit of the PID (packet length 0, used for large video frames) completed by
+    /// the unit start in this packet, unless delivered to a handler registered with
+    /// [`MpegTsParser::on_pid`].
+    pub ended_unit: Option<Pes<D>>,
 }
 
 /// MPEG-TS parser state capable of assembling payload units.
@@ -268,6 +505,23 @@ pub struct Packet<'a, D> {
 pub struct MpegTsParser<D: AppDetails = DefaultAppDetails> {
     pending_payload_units: HashMap<u16, PayloadUnitBuilder<D>>,
     known_pmt_pids: HashSet<u16>,
+    programs: BTreeMap<u16, u16>,
+    pmts: HashMap<u16, Pmt>,
+    es_streams: HashMap<u16, EsStreamInfo>,
+    ca_pids: HashMap<u16, ca::CaPid>,
+    program_states: HashMap<u16, ProgramState>,
+    table_versions: HashMap<(u16, u8, bool), u8>,
+    pending_events: Vec<ParserEvent>,
+    pending_warnings: Vec<Warning>,
+    ended_unit: Option<Pes<D>>,
+    program_filter: Option<ProgramFilter>,
+    pid_filter: Option<PidFilter>,
+    stats: Option<StreamStats>,
+    config: ParserConfig,
+    continuity_counters: HashMap<u16, ContinuityState>,
+    demux_handlers: DemuxHandlers<D>,
+    buffer_pool: BufferPool,
+    app_details: D,
     app_parser_storage: D::AppParserStorage,
 }
 
@@ -284,6 +538,45 @@ fn parse_timestamp(b: &[u8; 5]) -> u64 {
     ts
 }
 
+fn write_timestamp(b: &mut [u8; 5], ts: u64) {
+    /* Keep the 4-bit prefix; set all marker bits */
+    b[0] = (b[0] & 0xF0) | ((ts >> 29) as u8 & 0x0E) | 0x1;
+    b[1] = (ts >> 22) as u8;
+    b[2] = ((ts >> 14) as u8 & 0xFE) | 0x1;
+    b[3] = (ts >> 7) as u8;
+    b[4] = ((ts << 1) as u8 & 0xFE) | 0x1;
+}
+
+fn read_adaptation_field_extension<D: AppDetails>(
+    reader: &mut SliceReader<D>,
+) -> Result<AdaptationFieldExtension, D> {
+    let mut out = AdaptationFieldExtension::default();
+    if reader.remaining_len() == 0 {
+        return Ok(out);
+    }
+    let flags = reader.read_u8()?;
+    if flags & 0x80 != 0 {
+        let ltw = reader.read_be_u16()?;
+        out.ltw = Some(LegalTimeWindow {
+            valid: ltw & 0x8000 != 0,
+            offset: ltw & 0x7fff,
+        });
+    }
+    if flags & 0x40 != 0 {
+        let b = reader.read_array_ref::<3>()?;
+        out.piecewise_rate = Some(u32::from_be_bytes([0, b[0], b[1], b[2]]) & 0x3fffff);
+    }
+    if flags & 0x20 != 0 {
+        let b = reader.read_array_ref::<5>()?;
+        out.seamless_splice = Some(SeamlessSplice {
+            splice_type: b[0] >> 4,
+            dts_next_au: Timestamp90k(parse_timestamp(b)),
+        });
+    }
+    /* Remaining bytes are reserved or adaptation field descriptors */
+    Ok(out)
+}
+
 fn parse_pcr(b: &[u8; 6]) -> PcrTimestamp {
     let mut base: u64 = (b[0] as u64) << 25;
     base |= (b[1] as u64) << 17;
@@ -293,16 +586,40 @@ fn parse_pcr(b: &[u8; 6]) -> PcrTimestamp {
 
     let mut extension: u16 = ((b[4] & 0x1) as u16) << 8;
     extension |= b[5] as u16;
-    PcrTimestamp { base, extension }
+    PcrTimestamp {
+        base: Timestamp90k(base),
+        extension,
+    }
+}
+
+fn write_pcr(b: &mut [u8; 6], pcr: &PcrTimestamp) {
+    let base = pcr.base.0;
+    b[0] = (base >> 25) as u8;
+    b[1] = (base >> 17) as u8;
+    b[2] = (base >> 9) as u8;
+    b[3] = (base >> 1) as u8;
+    b[4] = ((base << 7) as u8 & 0x80) | 0x7E | ((pcr.extension >> 8) as u8 & 0x1);
+    b[5] = pcr.extension as u8;
 }
 
 impl<D: AppDetails> MpegTsParser<D> {
-    fn read_adaptation_field(&mut self, reader: &mut SliceReader<D>) -> Result<AdaptationField, D> {
+    /// Reads the adaptation field, also returning the number of trailing stuffing bytes.
+    fn read_adaptation_field(
+        &mut self,
+        reader: &mut SliceReader<D>,
+    ) -> Result<(AdaptationField, usize), D> {
         let mut out = AdaptationField {
-            header: read_bitfield!(reader, AdaptationFieldHeader),
+            header: AdaptationFieldHeader::new(),
             pcr: None,
             opcr: None,
+            extension: None,
         };
+        /* A zero length field is a single stuffing byte without flags */
+        if reader.peek(1)?[0] == 0 {
+            reader.skip(1)?;
+            return Ok((out, 0));
+        }
+        out.header = read_bitfield!(reader, AdaptationFieldHeader);
         let adaptation_field_length = out.header.length() as usize;
         if !(1..=183).contains(&adaptation_field_length) {
             warn!("Bad adaptation field length");
@@ -323,11 +640,21 @@ impl<D: AppDetails> MpegTsParser<D> {
             }
             out.opcr = Some(parse_pcr(a_reader.read_array_ref::<6>()?));
         }
-        // TODO: Splice Countdown
-        // TODO: Transport Private Data
-        // TODO: Adaptation Extension
+        /* Skip the remaining fields so only stuffing bytes are left */
+        if out.header.has_splice_countdown() {
+            a_reader.skip(1)?;
+        }
+        if out.header.has_transport_private_data() {
+            let length = a_reader.read_u8()? as usize;
+            a_reader.skip(length)?;
+        }
+        if out.header.has_adaptation_field_extension() {
+            let length = a_reader.read_u8()? as usize;
+            let mut e_reader = a_reader.new_sub_reader(length)?;
+            out.extension = Some(read_adaptation_field_extension(&mut e_reader)?);
+        }
 
-        Ok(out)
+        Ok((out, a_reader.remaining_len()))
     }
 
     fn read_payload<'a>(
@@ -336,15 +663,42 @@ impl<D: AppDetails> MpegTsParser<D> {
         pid: u16,
         mut reader: SliceReader<'a, D>,
     ) -> Result<Payload<'a, D>, D> {
+        /* Leave PIDs outside the selected program unassembled */
+        if !self.is_pid_selected(pid) {
+            return Ok(Payload::Raw(reader));
+        }
+
         if pusi {
             /* Make sure we're not starting an already-started unit */
-            if self.pending_payload_units.contains_key(&pid) {
-                warn!("Discarding unfinished unit packet on PID: {:x}", pid);
-                self.pending_payload_units.remove(&pid);
+            if let Some(builder) = self.pending_payload_units.remove(&pid) {
+                if builder.is_unbounded() {
+                    /* The unit start ends the unbounded unit of the PID */
+                    if let Payload::Pes(pes) = builder.finish(pid, self)? {
+                        self.ended_unit = Some(pes);
+                    }
+                } else {
+                    builder.recycle(self);
+                    if self.config.strict {
+                        return Err(
+                            reader.make_error(ErrorDetails::<D>::UnfinishedPayloadUnit(pid))
+                        );
+                    }
+                    self.push_warning(Warning::UnfinishedUnit { pid });
+                }
             }
 
-            /* Check for PAT/PMT/NIT */
-            if pid == 0 || self.known_pmt_pids.contains(&pid) {
+            /* Check for PAT/CAT/PMT/NIT/DSM-CC/ECM/EMM */
+            if pid == 0
+                || pid == ca::CAT_PID
+                || self.ca_pids.contains_key(&pid)
+                || dvb::is_si_pid(pid)
+                || pid == atsc::PSIP_PID
+                || self.known_pmt_pids.contains(&pid)
+                || self
+                    .es_streams
+                    .get(&pid)
+                    .is_some_and(|info| dsmcc::is_dsmcc_stream_type(info.stream_type))
+            {
                 self.start_psi(pid, &mut reader)
             }
             /* Check for PES if enough payload is present */
@@ -370,11 +724,25 @@ impl<D: AppDetails> MpegTsParser<D> {
             header: read_bitfield!(reader, PacketHeader),
             adaptation_field: None,
             payload: None,
+            payload_offset: None,
+            stuffing_len: 0,
+            duplicate: false,
+            events: Vec::new(),
+            warnings: Vec::new(),
+            payload_error: None,
+            ended_unit: None,
         };
+        /* Warnings of a packet that failed to parse are not carried over */
+        self.pending_warnings.clear();
+        self.ended_unit = None;
         if out.header.sync_byte() != 0x47 {
             return Err(reader.make_error(ErrorDetails::<D>::LostSync));
         }
 
+        if let Some(stats) = self.stats.as_mut() {
+            stats.record_packet(&out.header);
+        }
+
         /* Special cases exist for some PIDs */
         let pid = out.header.pid();
 
@@ -383,19 +751,88 @@ impl<D: AppDetails> MpegTsParser<D> {
             return Ok(out);
         }
 
+        /* Skip PIDs the application is not interested in */
+        if self.is_pid_filtered_out(pid) {
+            return Ok(out);
+        }
+
+        /* Packets marked as corrupt upstream may be left unparsed */
+        if out.header.tei() {
+            match self.config.tei_policy {
+                TeiPolicy::Parse => {}
+                TeiPolicy::Drop => {
+                    self.push_warning(Warning::TransportErrorDropped { pid });
+                    self.discard_payload_unit(pid);
+                    out.warnings = core::mem::take(&mut self.pending_warnings);
+                    return Ok(out);
+                }
+                TeiPolicy::Error => {
+                    self.discard_payload_unit(pid);
+                    return Err(reader.make_error(ErrorDetails::<D>::TransportError(pid)));
+                }
+            }
+        }
+
         /* Read adaptation field if it exists */
         if out.header.has_adaptation_field() {
-            out.adaptation_field = Some(self.read_adaptation_field(&mut reader)?);
+            let (adaptation_field, stuffing_len) = self.read_adaptation_field(&mut reader)?;
+            out.stuffing_len = stuffing_len;
+            if let (Some(stats), Some(pcr)) = (self.stats.as_mut(), adaptation_field.pcr) {
+                stats.record_pcr(pcr);
+            }
+            self.track_pcr(pid, &adaptation_field);
+            /* Counters may jump at splice points */
+            if adaptation_field.header.discontinuity() {
+                self.reset_for_discontinuity(pid);
+            }
+            out.adaptation_field = Some(adaptation_field);
         }
 
-        /* Read payload if it exists */
         if out.header.has_payload() {
-            out.payload = Some(self.read_payload(out.header.pusi(), pid, reader)?);
+            out.payload_offset = Some(188 - reader.remaining_len());
         }
 
+        /* Skip assembly of duplicate packets */
+        if !self.check_continuity(&out.header, &mut reader)? {
+            out.duplicate = true;
+            out.payload = Some(Payload::Raw(reader));
+        }
+        /* Read payload if it exists */
+        else if out.header.has_payload() {
+            /* Keep the payload around in case it fails to parse */
+            let raw = reader.clone();
+            match self.read_payload(out.header.pusi(), pid, reader) {
+                Ok(payload) => out.payload = self.dispatch_payload(pid, payload),
+                Err(err) if self.config.lenient => {
+                    self.discard_payload_unit(pid);
+                    out.payload = Some(Payload::Raw(raw));
+                    out.payload_error = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if let Some(pes) = self.ended_unit.take() {
+            out.ended_unit = self.dispatch_pes(pid, pes);
+        }
+
+        /* Hand over events raised by completed tables */
+        out.events = core::mem::take(&mut self.pending_events);
+        self.dispatch_new_streams(&out.events);
+        out.warnings = core::mem::take(&mut self.pending_warnings);
+
         Ok(out)
     }
 
+    /// The [`AppDetails`] instance creating [`PesUnitObject`]s.
+    pub fn app_details(&self) -> &D {
+        &self.app_details
+    }
+
+    /// Mutable access to the [`AppDetails`] instance, e.g. to reconfigure it between packets.
+    pub fn app_details_mut(&mut self) -> &mut D {
+        &mut self.app_details
+    }
+
     /// Parse data for exactly one 188-byte MPEG-TS packet.
     ///
     /// All information about the packet is returned as [`Packet`].
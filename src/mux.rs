@@ -0,0 +1,313 @@
+use super::pes::build_pes_unit;
+use super::{
+    AdaptationField, AdaptationFieldHeader, MpegTsWriter, PacketHeader, PatBuilder, PcrTimestamp,
+    PmtBuilder, Timestamp90k,
+};
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// Checks whether `interval` ticks have passed from `last` to `time`, across 33-bit wraparound.
+fn is_due(time: u64, last: u64, interval: u64) -> bool {
+    Timestamp90k::new(time).signed_diff(Timestamp90k::new(last)) >= interval as i64
+}
+
+/// Elementary stream packetized by a [`Muxer`].
+#[derive(Debug, Copy, Clone)]
+pub struct MuxerStream {
+    /// Elementary PID.
+    pub pid: u16,
+    /// Stream type listed in the PMT.
+    pub stream_type: u8,
+    /// PES stream ID used for units of the stream.
+    pub stream_id: u8,
+}
+
+/// Packetizes elementary stream frames of several PIDs into a single-program transport stream.
+///
+/// Frames are written in the order they are supplied, which should be ascending decode time.
+/// The PAT and PMT are repeated at a fixed interval of decode time, and PCRs are inserted on the
+/// PCR PID at a fixed interval, lagging the decode time by a configurable delay so decoders can
+/// buffer each frame before it is due.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Muxer, Payload};
+/// use std::convert::TryInto;
+///
+/// let mut muxer = Muxer::new(1, 0x100);
+/// muxer.add_stream(0x1011, 0x1b, 0xe0).add_stream(0x1100, 0x0f, 0xc0);
+/// let mut out = Vec::new();
+/// for i in 0..10 {
+///     let pts = 90000 + i * 3003;
+///     muxer.write_frame(&mut out, 0x1011, Some(pts), None, &[0; 1000])?;
+///     muxer.write_frame(&mut out, 0x1100, Some(pts), None, &[0; 200])?;
+/// }
+///
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let mut pes_count = 0;
+/// for packet in out.chunks(188) {
+///     if let Some(Payload::Pes(_)) = parser.parse(packet.try_into().unwrap()).unwrap().payload {
+///         pes_count += 1;
+///     }
+/// }
+/// assert_eq!(pes_count, 20);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Muxer {
+    writer: MpegTsWriter,
+    program_num: u16,
+    pmt_pid: u16,
+    pcr_pid: Option<u16>,
+    streams: Vec<MuxerStream>,
+    psi_version: u8,
+    psi_interval: u64,
+    pcr_interval: u64,
+    pcr_delay: u64,
+    last_psi: Option<u64>,
+    last_pcr: Option<u64>,
+}
+
+impl Muxer {
+    /// Creates a muxer for a program whose PMT is carried on `pmt_pid`.
+    pub fn new(program_num: u16, pmt_pid: u16) -> Self {
+        Self {
+            writer: MpegTsWriter::default(),
+            program_num,
+            pmt_pid,
+            pcr_pid: None,
+            streams: Vec::new(),
+            psi_version: 0,
+            psi_interval: 9000,
+            pcr_interval: 3600,
+            pcr_delay: 63000,
+            last_psi: None,
+            last_pcr: None,
+        }
+    }
+
+    /// Adds an elementary stream. The first stream added becomes the PCR PID unless set with
+    /// [`Muxer::set_pcr_pid`].
+    pub fn add_stream(&mut self, pid: u16, stream_type: u8, stream_id: u8) -> &mut Self {
+        self.streams.push(MuxerStream {
+            pid,
+            stream_type,
+            stream_id,
+        });
+        self.pcr_pid.get_or_insert(pid);
+        self.invalidate_psi();
+        self
+    }
+
+    /// Sets the PID carrying the PCR.
+    pub fn set_pcr_pid(&mut self, pcr_pid: u16) -> &mut Self {
+        self.pcr_pid = Some(pcr_pid);
+        self.invalidate_psi();
+        self
+    }
+
+    /// Sets the interval of PAT/PMT repetition in 90kHz ticks (default 100ms).
+    pub fn set_psi_interval(&mut self, interval: u64) -> &mut Self {
+        self.psi_interval = interval;
+        self
+    }
+
+    /// Sets the maximum interval between PCRs in 90kHz ticks (default 40ms).
+    pub fn set_pcr_interval(&mut self, interval: u64) -> &mut Self {
+        self.pcr_interval = interval;
+        self
+    }
+
+    /// Sets how far the PCR lags the decode time of written frames in 90kHz ticks
+    /// (default 700ms).
+    pub fn set_pcr_delay(&mut self, delay: u64) -> &mut Self {
+        self.pcr_delay = delay;
+        self
+    }
+
+    /// Elementary streams of the program.
+    pub fn streams(&self) -> &[MuxerStream] {
+        &self.streams
+    }
+
+    /// Forces the PAT/PMT to be written before the next frame with an incremented version.
+    fn invalidate_psi(&mut self) {
+        if self.last_psi.take().is_some() {
+            self.psi_version = (self.psi_version + 1) & 0x1f;
+        }
+    }
+
+    /// Writes one frame of an elementary stream as a PES unit, preceded by PSI and PCR packets
+    /// as they become due.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if `pid` was not added or a non-video frame is too
+    /// long for one PES unit.
+    pub fn write_frame<W: Write>(
+        &mut self,
+        out: &mut W,
+        pid: u16,
+        pts: Option<u64>,
+        dts: Option<u64>,
+        data: &[u8],
+    ) -> Result<()> {
+        let stream = match self.streams.iter().find(|s| s.pid == pid) {
+            Some(stream) => *stream,
+            None => return Err(Error::new(ErrorKind::InvalidInput, "Unknown PID")),
+        };
+        let is_video = (0xe0..=0xef).contains(&stream.stream_id);
+        if !is_video && 3 + 10 + data.len() > 0xffff {
+            return Err(Error::new(ErrorKind::InvalidInput, "Frame too long"));
+        }
+
+        /* Decode time drives PSI repetition and PCR insertion */
+        let time = dts.or(pts);
+        if let Some(time) = time {
+            if self
+                .last_psi
+                .is_none_or(|last| is_due(time, last, self.psi_interval))
+            {
+                self.write_psi(out)?;
+                self.last_psi = Some(time);
+            }
+        } else if self.last_psi.is_none() {
+            self.write_psi(out)?;
+        }
+
+        let pcr = match (time, self.pcr_pid) {
+            (Some(time), Some(pcr_pid))
+                if self
+                    .last_pcr
+                    .is_none_or(|last| is_due(time, last, self.pcr_interval)) =>
+            {
+                self.last_pcr = Some(time);
+                let pcr = PcrTimestamp {
+                    base: Timestamp90k::new(time.saturating_sub(self.pcr_delay)),
+                    extension: 0,
+                };
+                if pcr_pid == pid {
+                    Some(pcr)
+                } else {
+                    self.write_pcr_packet(out, pcr_pid, pcr)?;
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        let unit = build_pes_unit(stream.stream_id, pts, dts, data);
+        self.writer.write_unit(out, pid, &unit, pcr)?;
+        Ok(())
+    }
+
+    /// Writes an adaptation-only packet carrying a PCR.
+    fn write_pcr_packet<W: Write>(
+        &mut self,
+        out: &mut W,
+        pcr_pid: u16,
+        pcr: PcrTimestamp,
+    ) -> Result<()> {
+        let adaptation_field = AdaptationField {
+            header: AdaptationFieldHeader::new(),
+            pcr: Some(pcr),
+            opcr: None,
+            extension: None,
+        };
+        self.writer.write_packet(
+            out,
+            &PacketHeader::new().with_pid(pcr_pid),
+            Some(&adaptation_field),
+            &[],
+        )
+    }
+
+    /// Writes the PAT and PMT.
+    fn write_psi<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        let pat = PatBuilder::new(1)
+            .version(self.psi_version)
+            .program(self.program_num, self.pmt_pid)
+            .build();
+        self.writer
+            .write_unit(out, 0, &pat.to_payload_unit(), None)?;
+
+        let mut pmt = PmtBuilder::new(self.program_num, self.pcr_pid.unwrap_or(0x1fff))
+            .version(self.psi_version);
+        for stream in self.streams.iter() {
+            pmt = pmt.stream(stream.stream_type, stream.pid, None);
+        }
+        self.writer
+            .write_unit(out, self.pmt_pid, &pmt.build().to_payload_unit(), None)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_unbounded_video_frame() {
+    use crate::{DefaultAppDetails, MpegTsParser, Payload};
+    use core::convert::TryInto;
+
+    let mut muxer = Muxer::new(1, 0x100);
+    muxer.add_stream(0x1011, 0x1b, 0xe0);
+    let frame: Vec<u8> = (0..70000).map(|i| i as u8).collect();
+    let mut out = Vec::new();
+    muxer
+        .write_frame(&mut out, 0x1011, Some(90000), None, &frame)
+        .unwrap();
+    muxer
+        .write_frame(&mut out, 0x1011, Some(93003), None, &[0; 100])
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .strict(true)
+        .build();
+    let mut units = Vec::new();
+    for packet in out.chunks(188) {
+        let packet = parser.parse(packet.try_into().unwrap()).unwrap();
+        assert!(packet.warnings.is_empty());
+        if let Some(pes) = packet.ended_unit {
+            units.push((pes.pts.unwrap().0, pes.raw_data().unwrap().to_vec()));
+        }
+        if let Some(Payload::Pes(pes)) = packet.payload {
+            units.push((pes.pts.unwrap().0, pes.raw_data().unwrap().to_vec()));
+        }
+    }
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0].0, 90000);
+    assert_eq!(units[0].1, frame);
+    assert_eq!(units[1], (93003, vec![0; 100]));
+}
+
+#[test]
+fn test_timestamp_wraparound() {
+    use crate::{DefaultAppDetails, MpegTsParser, Payload};
+    use core::convert::TryInto;
+
+    let mut muxer = Muxer::new(1, 0x100);
+    muxer.add_stream(0x1100, 0x0f, 0xc0);
+    let mut out = Vec::new();
+    let start = Timestamp90k::WRAP - 9000;
+    for i in 0..10 {
+        let pts = Timestamp90k::new(start + i * 3000).0;
+        muxer
+            .write_frame(&mut out, 0x1100, Some(pts), None, &[0; 100])
+            .unwrap();
+    }
+
+    /* PAT and PMT every 100ms and a PCR with every other frame, across the wrap */
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut psi = 0;
+    let mut pcrs = 0;
+    for packet in out.chunks(188) {
+        let packet = parser.parse(packet.try_into().unwrap()).unwrap();
+        if let Some(Payload::Psi(_)) = packet.payload {
+            psi += 1;
+        }
+        if packet
+            .adaptation_field
+            .is_some_and(|field| field.pcr.is_some())
+        {
+            pcrs += 1;
+        }
+    }
+    assert_eq!(psi, 2 * 4);
+    assert_eq!(pcrs, 5);
+}
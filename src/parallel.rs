@@ -0,0 +1,163 @@
+use super::{AppDetails, Error, ErrorDetails, Framing, MpegTsParser, OwnedPacket, Result};
+use crate::prelude::*;
+use hashbrown::HashMap;
+use rayon::prelude::*;
+
+/// Reads the PID of a framed packet without parsing it.
+fn packet_pid(framing: Framing, packet: &[u8]) -> u16 {
+    let header = &packet[framing.prefix..];
+    u16::from_be_bytes([header[1], header[2]]) & 0x1fff
+}
+
+/// Parses a buffer of packets stored with the given [`Framing`], spreading PIDs over the rayon
+/// thread pool.
+///
+/// The PAT and PMTs are parsed first by a parser from `new_parser`. Packets of every other PID
+/// are then parsed in order by a separate parser per PID, which also replays the PAT and PMT
+/// packets so stream information is available to
+/// [`AppDetails::new_pes_unit_data`](crate::AppDetails::new_pes_unit_data). The results are
+/// merged back into stream order; events of the PAT and PMTs are only reported once.
+///
+/// Each PID has its own parser, so statistics and demux handlers of the parsers built by
+/// `new_parser` only see part of the stream. A trailing partial packet is reported as
+/// [`ErrorDetails::BadPacketLength`].
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{parse_parallel, DefaultAppDetails, Framing, MpegTsParser, MpegTsWriter};
+///
+/// let mut data = Vec::new();
+/// let mut writer = MpegTsWriter::default();
+/// writer.write_unit(&mut data, 0x100, &[0; 1000], None)?;
+/// writer.write_unit(&mut data, 0x101, &[0; 1000], None)?;
+///
+/// let packets = parse_parallel(&data, Framing::PLAIN, MpegTsParser::<DefaultAppDetails>::default);
+/// assert_eq!(packets.len(), data.len() / 188);
+/// assert_eq!(packets[0].as_ref().unwrap().header.pid(), 0x100);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn parse_parallel<D, F>(
+    data: &[u8],
+    framing: Framing,
+    new_parser: F,
+) -> Vec<Result<OwnedPacket<D>, D>>
+where
+    D: AppDetails,
+    D::AppErrorDetails: Send,
+    F: Fn() -> MpegTsParser<D> + Sync,
+{
+    let packet_size = framing.packet_size();
+    let packets: Vec<&[u8]> = data.chunks_exact(packet_size).collect();
+    let mut results: Vec<Option<Result<OwnedPacket<D>, D>>> = Vec::new();
+    results.resize_with(packets.len(), || None);
+
+    /* Parse the PAT and PMTs in order, grouping the other packets by PID */
+    let mut control_parser = new_parser();
+    let mut control = Vec::new();
+    let mut pids: HashMap<u16, Vec<usize>> = HashMap::new();
+    for (index, packet) in packets.iter().enumerate() {
+        let pid = packet_pid(framing, packet);
+        if pid == 0 || control_parser.known_pmt_pids.contains(&pid) {
+            control.push(index);
+            results[index] = Some(
+                control_parser
+                    .parse_framed(framing, packet)
+                    .map(OwnedPacket::from),
+            );
+        } else {
+            pids.entry(pid).or_default().push(index);
+        }
+    }
+
+    let pids: Vec<Vec<usize>> = pids.into_values().collect();
+    let parsed: Vec<Vec<_>> = pids
+        .into_par_iter()
+        .map(|indices| {
+            let mut parser = new_parser();
+            let mut out = Vec::with_capacity(indices.len());
+            let mut control = control.iter().copied().peekable();
+            for index in indices {
+                /* Replay the PSI preceding the packet, discarding the results */
+                while let Some(control_index) =
+                    control.next_if(|control_index| *control_index < index)
+                {
+                    let _ = parser.parse_framed(framing, packets[control_index]);
+                }
+                out.push((
+                    index,
+                    parser
+                        .parse_framed(framing, packets[index])
+                        .map(OwnedPacket::from),
+                ));
+            }
+            out
+        })
+        .collect();
+    for (index, result) in parsed.into_iter().flatten() {
+        results[index] = Some(result);
+    }
+
+    let mut results: Vec<Result<OwnedPacket<D>, D>> =
+        results.into_iter().map(Option::unwrap).collect();
+    let remaining = data.len() % packet_size;
+    if remaining > 0 {
+        results.push(Err(Error {
+            location: 0,
+            details: ErrorDetails::BadPacketLength(remaining),
+        }));
+    }
+    results
+}
+
+#[test]
+fn test_parse_parallel() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, OwnedPayload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x24, 0xe0);
+    gen.add_stream(0x1100, 0x81, 0xbd);
+    gen.write_pat().write_pmt();
+    for i in 0..4 {
+        gen.write_pes(0x1011, Some(i * 3003), None, &[0; 500]);
+        gen.write_pes(0x1100, Some(i * 2880), None, &[0; 200]);
+    }
+    let mut data = Vec::new();
+    for packet in gen.packets().iter() {
+        data.extend_from_slice(packet);
+    }
+    data.extend_from_slice(&[0x47; 10]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let sequential: Vec<_> = gen
+        .packets()
+        .iter()
+        .map(|packet| OwnedPacket::from(parser.parse(packet).unwrap()))
+        .collect();
+    let parallel = parse_parallel(
+        &data,
+        Framing::PLAIN,
+        MpegTsParser::<DefaultAppDetails>::default,
+    );
+    assert_eq!(parallel.len(), sequential.len() + 1);
+    for (parallel, sequential) in parallel.iter().zip(sequential.iter()) {
+        let parallel = parallel.as_ref().unwrap();
+        assert_eq!(parallel.header.pid(), sequential.header.pid());
+        assert_eq!(parallel.events.len(), sequential.events.len());
+        match (&parallel.payload, &sequential.payload) {
+            (Some(OwnedPayload::Pes(a)), Some(OwnedPayload::Pes(b))) => assert_eq!(a.pts, b.pts),
+            (Some(OwnedPayload::Psi(_)), Some(OwnedPayload::Psi(_)))
+            | (Some(OwnedPayload::PesPending), Some(OwnedPayload::PesPending))
+            | (None, None) => {}
+            (a, b) => panic!("payload mismatch: {:?} / {:?}", a, b),
+        }
+    }
+    assert!(matches!(
+        parallel.last(),
+        Some(Err(Error {
+            details: ErrorDetails::BadPacketLength(10),
+            ..
+        }))
+    ));
+}
@@ -1,4 +1,8 @@
-use super::{AppDetails, MpegTsParser, Payload, Pes, PsiBuilder, Result, SliceReader};
+use super::{
+    AppDetails, ErrorDetails, MpegTsParser, Payload, Pes, PsiBuilder, PsiHeader, PsiTableSyntax,
+    Result, SliceReader, Warning,
+};
+use crate::prelude::*;
 use enum_dispatch::enum_dispatch;
 use log::warn;
 
@@ -6,6 +10,12 @@ use log::warn;
 pub(crate) trait PayloadUnitObject<D: AppDetails> {
     fn extend_from_slice(&mut self, slice: &[u8]);
     fn finish<'a>(self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D>;
+    fn finish_from_slice<'a>(
+        self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D>;
     fn pending<'a>(&self) -> Result<Payload<'a, D>, D>;
 }
 
@@ -15,13 +25,89 @@ pub(crate) enum PayloadUnit<D: AppDetails> {
     Pes(Pes<D>),
 }
 
+/// Payload unit that was still being assembled when [`MpegTsParser::flush`] was called.
+#[derive(Debug)]
+pub enum FlushedUnit<D> {
+    /// PSI section cut short. Its bytes are left unparsed since the CRC cannot be validated.
+    Psi {
+        /// PID carrying the section.
+        pid: u16,
+        /// Section header.
+        header: PsiHeader,
+        /// Table syntax section, if the section has one.
+        table_syntax: Option<PsiTableSyntax>,
+        /// Section bytes received after the headers.
+        data: Vec<u8>,
+        /// Number of bytes the section was short of.
+        missing: usize,
+    },
+    /// PES unit finished with the data received so far.
+    Pes {
+        /// PID carrying the unit.
+        pid: u16,
+        /// The unit; its data object may reject or partially parse the truncated payload.
+        pes: Pes<D>,
+        /// Number of bytes the unit was short of.
+        missing: usize,
+    },
+}
+
+impl<D> FlushedUnit<D> {
+    /// PID carrying the unit.
+    pub fn pid(&self) -> u16 {
+        match self {
+            Self::Psi { pid, .. } | Self::Pes { pid, .. } => *pid,
+        }
+    }
+
+    /// Number of bytes the unit was short of.
+    pub fn missing(&self) -> usize {
+        match self {
+            Self::Psi { missing, .. } | Self::Pes { missing, .. } => *missing,
+        }
+    }
+}
+
+/// Number of idle buffers kept by [`BufferPool`].
+const MAX_POOLED_BUFFERS: usize = 16;
+
+/// Recycles the buffers payload units spanning several packets are assembled in, so repeating
+/// tables and PES units do not allocate once the pool is warm.
+///
+/// Buffers of PSI sections and abandoned units return to the pool automatically. PES data is
+/// handed to the application with the finished [`Pes`] and returns with
+/// [`MpegTsParser::recycle_pes`].
+#[derive(Default)]
+pub(crate) struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+}
+
+impl BufferPool {
+    /// Takes an empty buffer with room for at least `capacity` bytes.
+    pub fn take(&mut self, capacity: usize) -> Vec<u8> {
+        let mut buffer = self.buffers.pop().unwrap_or_default();
+        buffer.reserve_exact(capacity);
+        buffer
+    }
+
+    /// Returns a buffer to the pool once its contents are no longer needed.
+    pub fn recycle(&mut self, mut buffer: Vec<u8>) {
+        if buffer.capacity() > 0 && self.buffers.len() < MAX_POOLED_BUFFERS {
+            buffer.clear();
+            self.buffers.push(buffer);
+        }
+    }
+}
+
 pub(crate) struct PayloadUnitBuilder<D: AppDetails> {
     unit: PayloadUnit<D>,
-    remaining: usize,
+    /// Bytes left to complete the unit, `None` for unbounded PES units which are ended by the
+    /// next unit start of the PID.
+    remaining: Option<usize>,
 }
 
 impl<D: AppDetails> PayloadUnitBuilder<D> {
-    pub fn new<T: PayloadUnitObject<D>>(obj: T, obj_length: usize) -> Self
+    pub fn new<T: PayloadUnitObject<D>>(obj: T, obj_length: Option<usize>) -> Self
     where
         PayloadUnit<D>: From<T>,
     {
@@ -32,38 +118,101 @@ impl<D: AppDetails> PayloadUnitBuilder<D> {
     }
 
     pub fn append(&mut self, reader: &mut SliceReader<D>) -> Result<bool, D> {
-        if reader.remaining_len() <= self.remaining {
-            self.remaining -= reader.remaining_len();
+        let remaining = match self.remaining.as_mut() {
+            Some(remaining) => remaining,
+            None => {
+                self.unit.extend_from_slice(reader.read_to_end()?);
+                return Ok(false);
+            }
+        };
+        if reader.remaining_len() <= *remaining {
+            *remaining -= reader.remaining_len();
             self.unit.extend_from_slice(reader.read_to_end()?);
-            Ok(self.remaining == 0)
+            Ok(*remaining == 0)
         } else {
-            self.unit.extend_from_slice(reader.read(self.remaining)?);
-            self.remaining = 0;
+            self.unit.extend_from_slice(reader.read(*remaining)?);
+            *remaining = 0;
             Ok(true)
         }
     }
 
+    /// Whether the unit is only ended by the next unit start of its PID.
+    pub fn is_unbounded(&self) -> bool {
+        self.remaining.is_none()
+    }
+
     pub fn finish<'a>(self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D> {
-        assert_eq!(self.remaining, 0);
+        assert_eq!(self.remaining.unwrap_or(0), 0);
         self.unit.finish(pid, parser)
     }
 
     pub fn pending<'a>(&self) -> Result<Payload<'a, D>, D> {
         self.unit.pending()
     }
+
+    /// Returns the buffer of a unit that is abandoned before completion to the pool.
+    pub fn recycle(self, parser: &mut MpegTsParser<D>) {
+        match self.unit {
+            PayloadUnit::Psi(builder) => parser.buffer_pool.recycle(builder.into_parts().2),
+            PayloadUnit::Pes(pes) => parser.recycle_pes(pes),
+        }
+    }
+
+    fn flush(self, pid: u16, parser: &mut MpegTsParser<D>) -> FlushedUnit<D> {
+        let missing = self.remaining.unwrap_or(0);
+        match self.unit {
+            PayloadUnit::Psi(builder) => {
+                let (header, table_syntax, data) = builder.into_parts();
+                FlushedUnit::Psi {
+                    pid,
+                    header,
+                    table_syntax,
+                    data,
+                    missing,
+                }
+            }
+            PayloadUnit::Pes(mut pes) => {
+                if pes.data.finish(pid, parser).is_err() {
+                    warn!("Unable to finish truncated PES unit on PID: {:x}", pid);
+                }
+                FlushedUnit::Pes { pid, pes, missing }
+            }
+        }
+    }
 }
 
 impl<D: AppDetails> MpegTsParser<D> {
     pub(crate) fn start_payload_unit<'a, T: PayloadUnitObject<D>>(
         &mut self,
         obj: T,
-        length: usize,
+        length: Option<usize>,
         pid: u16,
         reader: &mut SliceReader<'a, D>,
     ) -> Result<Payload<'a, D>, D>
     where
         PayloadUnit<D>: From<T>,
     {
+        /* Units completing within this packet never occupy a pending slot */
+        let fits = length.is_some_and(|length| length <= reader.remaining_len());
+        let at_limit = self
+            .config
+            .max_pending_units
+            .is_some_and(|max| self.pending_payload_units.len() >= max);
+        if at_limit && !fits {
+            if self.config.strict {
+                return Err(reader.make_error(ErrorDetails::<D>::TooManyPendingUnits));
+            }
+            self.push_warning(Warning::TooManyPendingUnits { pid });
+            return Ok(Payload::Raw(reader.new_sub_reader(reader.remaining_len())?));
+        }
+
+        /* Units contained in this packet are parsed from the packet slice; only PES objects
+         * keeping their raw data copy it */
+        if let Some(length) = length.filter(|_| fits) {
+            let slice = reader.read(length)?;
+            return obj.finish_from_slice(slice, pid, self);
+        }
+
         let mut builder = PayloadUnitBuilder::new(obj, length);
         if builder.append(reader)? {
             builder.finish(pid, self)
@@ -74,6 +223,62 @@ impl<D: AppDetails> MpegTsParser<D> {
         }
     }
 
+    /// Ends assembly of all pending payload units, e.g. at the end of a file.
+    ///
+    /// Pending units are returned as [`FlushedUnit`] objects, ordered by PID. Unbounded video PES
+    /// units are finished here when no further unit start of their PID follows. PES units of PIDs
+    /// with a handler registered with [`MpegTsParser::on_pid`] are delivered to the handler
+    /// instead of being returned. Continuity state is reset as well, so parsing can resume with an unrelated stream; program and stream
+    /// information learned from PSI is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::testgen::TestStreamGenerator;
+    /// use mpegts_io::{DefaultAppDetails, FlushedUnit, MpegTsParser};
+    /// let mut gen = TestStreamGenerator::new(1, 0x100);
+    /// gen.add_stream(0x1011, 0x1b, 0xe0);
+    /// gen.write_pat().write_pmt().write_pes(0x1011, Some(0), None, &[0; 1000]);
+    ///
+    /// /* The stream is cut off before the last packet of the PES unit */
+    /// let packets = gen.packets();
+    /// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    /// for packet in &packets[..packets.len() - 1] {
+    ///     parser.parse(packet).unwrap();
+    /// }
+    /// let flushed = parser.flush();
+    /// assert!(matches!(flushed[0], FlushedUnit::Pes { pid: 0x1011, .. }));
+    /// assert!(flushed[0].missing() > 0);
+    /// assert!(parser.flush().is_empty());
+    /// ```
+    pub fn flush(&mut self) -> Vec<FlushedUnit<D>> {
+        let mut pending: Vec<_> = core::mem::take(&mut self.pending_payload_units)
+            .into_iter()
+            .collect();
+        pending.sort_unstable_by_key(|(pid, _)| *pid);
+        self.continuity_counters.clear();
+        pending
+            .into_iter()
+            .filter_map(|(pid, builder)| match builder.flush(pid, self) {
+                FlushedUnit::Pes { pid, pes, missing } => self
+                    .dispatch_pes(pid, pes)
+                    .map(|pes| FlushedUnit::Pes { pid, pes, missing }),
+                unit => Some(unit),
+            })
+            .collect()
+    }
+
+    /// Abandons the pending payload unit of a PID, returning whether there was one.
+    pub(crate) fn discard_payload_unit(&mut self, pid: u16) -> bool {
+        match self.pending_payload_units.remove(&pid) {
+            Some(builder) => {
+                builder.recycle(self);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub(crate) fn continue_payload_unit<'a>(
         &mut self,
         pid: u16,
@@ -91,13 +296,157 @@ impl<D: AppDetails> MpegTsParser<D> {
                 }
             }
             None => {
-                warn!(
-                    "Unknown payload continuation on non-start packet for PID: {:x}",
-                    pid
-                );
+                if self.config.strict {
+                    return Err(reader.make_error(ErrorDetails::<D>::UnexpectedContinuation(pid)));
+                }
+                self.push_warning(Warning::UnexpectedContinuation { pid });
                 /* Assume raw */
                 Ok(Payload::Raw(reader))
             }
         }
     }
 }
+
+#[test]
+fn test_single_packet_units() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, PsiData};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0);
+    gen.write_pat().write_pmt();
+    /* One unit filling a single packet exactly, then one spanning two packets */
+    gen.write_pes(0x1011, Some(0), None, &[0x11; 170]);
+    gen.write_pes(0x1011, Some(0), None, &[0x22; 171]);
+    let packets = gen.packets();
+    assert_eq!(packets.len(), 5);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let payloads: Vec<_> = packets
+        .iter()
+        .map(|p| parser.parse(p).unwrap().payload.unwrap())
+        .collect();
+    assert!(matches!(
+        payloads[1],
+        Payload::Psi(ref psi) if matches!(psi.data, PsiData::Pmt(_))
+    ));
+    /* PES units without an application parser own a copy of their data either way */
+    match &payloads[2] {
+        Payload::Pes(pes) => assert_eq!(pes.raw_data(), Some(&[0x11; 170][..])),
+        payload => panic!("unexpected payload {:?}", payload),
+    }
+    assert!(matches!(payloads[3], Payload::PesPending));
+    match &payloads[4] {
+        Payload::Pes(pes) => assert_eq!(pes.raw_data(), Some(&[0x22; 171][..])),
+        payload => panic!("unexpected payload {:?}", payload),
+    }
+}
+
+#[test]
+fn test_section_buffer_pool() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::DefaultAppDetails;
+
+    /* PMT long enough to span two packets */
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    for i in 0..40 {
+        gen.add_stream(0x1000 + i, 0x1b, 0xe0);
+    }
+    gen.write_pat().write_pmt().write_pmt();
+    let packets = gen.packets();
+    assert_eq!(packets.len(), 5);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    parser.parse(&packets[0]).unwrap();
+    parser.parse(&packets[1]).unwrap();
+    assert!(matches!(
+        parser.parse(&packets[2]).unwrap().payload,
+        Some(Payload::Psi(_))
+    ));
+    assert_eq!(parser.buffer_pool.buffers.len(), 1);
+    let pooled = parser.buffer_pool.buffers[0].as_ptr();
+
+    /* The next section is assembled in the recycled buffer */
+    parser.parse(&packets[3]).unwrap();
+    assert!(parser.buffer_pool.buffers.is_empty());
+    assert!(matches!(
+        parser.parse(&packets[4]).unwrap().payload,
+        Some(Payload::Psi(_))
+    ));
+    assert_eq!(parser.buffer_pool.buffers[0].as_ptr(), pooled);
+}
+
+#[test]
+fn test_pes_buffer_pool() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::DefaultAppDetails;
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(0), None, &[0x11; 1000]);
+    gen.write_pes(0x1011, Some(3003), None, &[0x22; 1000]);
+    let packets = gen.packets();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let mut units = Vec::new();
+    for packet in packets {
+        if let Some(Payload::Pes(pes)) = parser.parse(packet).unwrap().payload {
+            let data = pes.raw_data().unwrap();
+            units.push((data[data.len() - 1], data.as_ptr()));
+            /* The application is done with the unit */
+            parser.recycle_pes(pes);
+        }
+    }
+    assert_eq!(units.len(), 2);
+    assert_eq!((units[0].0, units[1].0), (0x11, 0x22));
+    /* The second unit is assembled in the buffer of the first */
+    assert_eq!(units[0].1, units[1].1);
+    assert_eq!(parser.buffer_pool.buffers.len(), 1);
+}
+
+#[test]
+fn test_flush_unbounded_units() {
+    use crate::pes::build_pes_unit;
+    use crate::{DefaultAppDetails, DemuxUnit, MpegTsWriter};
+    use core::convert::TryInto;
+    use std::sync::{Arc, Mutex};
+
+    /* Two unbounded video units too long for the 16-bit length field */
+    let frame = vec![0x11; 70000];
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    for (pid, pts) in [(0x1011, 0), (0x1012, 3003)].iter() {
+        let unit = build_pes_unit(0xe0, Some(*pts), None, &frame);
+        assert_eq!(unit[4..6], [0, 0]);
+        writer.write_unit(&mut out, *pid, &unit, None).unwrap();
+    }
+
+    let units = Arc::new(Mutex::new(Vec::new()));
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let units_ref = units.clone();
+    parser.on_pid(0x1012, move |pid, unit| {
+        if let DemuxUnit::Pes(pes) = unit {
+            units_ref
+                .lock()
+                .unwrap()
+                .push((pid, pes.raw_data().unwrap().len()));
+        }
+    });
+    for packet in out.chunks(188) {
+        let packet = parser.parse(packet.try_into().unwrap()).unwrap();
+        assert!(packet.ended_unit.is_none());
+        assert!(packet.warnings.is_empty());
+    }
+
+    let flushed = parser.flush();
+    assert_eq!(flushed.len(), 1);
+    match &flushed[0] {
+        FlushedUnit::Pes { pid, pes, missing } => {
+            assert_eq!((*pid, *missing), (0x1011, 0));
+            assert_eq!(pes.raw_data(), Some(&frame[..]));
+        }
+        unit => panic!("unexpected unit {:?}", unit),
+    }
+    assert_eq!(*units.lock().unwrap(), [(0x1012, 70000)]);
+}
@@ -1,11 +1,13 @@
 use super::{
-    parse_timestamp, pts_format_args, read_bitfield, AppDetails, ErrorDetails, MpegTsParser,
-    Payload, PayloadUnitObject, Result, SliceReader,
+    parse_timestamp, read_bitfield, write_timestamp, AppDetails, ErrorDetails, MpegTsParser,
+    Payload, PayloadUnitObject, PcrTimestamp, Result, SliceReader, Timestamp90k,
 };
+use crate::prelude::*;
+use alloc::rc::Rc;
+use core::any::Any;
+use core::fmt::{Debug, Formatter};
 use log::warn;
 use modular_bitfield_msb::prelude::*;
-use std::fmt::{Arguments, Debug, DebugStruct, Formatter};
-use std::rc::Rc;
 
 /// Header of PES unit.
 #[bitfield]
@@ -16,6 +18,130 @@ pub struct PesHeader {
     pub packet_length: B16,
 }
 
+impl PesHeader {
+    /// Classifies the stream ID of the PES unit.
+    pub fn stream_id_kind(&self) -> StreamId {
+        StreamId::from(self.stream_id())
+    }
+}
+
+/// Classified stream ID of a PES unit.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::StreamId;
+/// assert_eq!(StreamId::from(0xc1), StreamId::Audio(1));
+/// assert_eq!(u8::from(StreamId::Video(0)), 0xe0);
+/// assert!(!StreamId::Padding.has_optional_header());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StreamId {
+    /// Program stream map (0xBC).
+    ProgramStreamMap,
+    /// Private stream 1 (0xBD), e.g. AC-3 or DVB subtitles.
+    PrivateStream1,
+    /// Padding stream (0xBE).
+    Padding,
+    /// Private stream 2 (0xBF).
+    PrivateStream2,
+    /// MPEG audio stream with its 5-bit stream number (0xC0 to 0xDF).
+    Audio(u8),
+    /// MPEG video stream with its 4-bit stream number (0xE0 to 0xEF).
+    Video(u8),
+    /// ECM stream (0xF0).
+    Ecm,
+    /// EMM stream (0xF1).
+    Emm,
+    /// DSM-CC stream (0xF2).
+    Dsmcc,
+    /// ISO/IEC 13522 (MHEG) stream (0xF3).
+    Mheg,
+    /// ITU-T H.222.1 stream of type A to E, numbered 0 to 4 (0xF4 to 0xF8).
+    H2221(u8),
+    /// Ancillary stream (0xF9).
+    Ancillary,
+    /// ISO/IEC 14496-1 SL-packetized stream (0xFA).
+    SlPacketized,
+    /// ISO/IEC 14496-1 FlexMux stream (0xFB).
+    FlexMux,
+    /// Metadata stream (0xFC).
+    Metadata,
+    /// Extended stream ID (0xFD), found in the PES extension.
+    ExtendedStreamId,
+    /// Program stream directory (0xFF).
+    ProgramStreamDirectory,
+    /// Reserved stream ID.
+    Reserved(u8),
+}
+
+impl StreamId {
+    /// Checks whether PES units of the stream carry the optional header.
+    pub fn has_optional_header(&self) -> bool {
+        !matches!(
+            self,
+            StreamId::ProgramStreamMap
+                | StreamId::Padding
+                | StreamId::PrivateStream2
+                | StreamId::Ecm
+                | StreamId::Emm
+                | StreamId::Dsmcc
+                | StreamId::H2221(4)
+                | StreamId::ProgramStreamDirectory
+        )
+    }
+}
+
+impl From<u8> for StreamId {
+    fn from(value: u8) -> Self {
+        match value {
+            0xbc => StreamId::ProgramStreamMap,
+            0xbd => StreamId::PrivateStream1,
+            0xbe => StreamId::Padding,
+            0xbf => StreamId::PrivateStream2,
+            0xc0..=0xdf => StreamId::Audio(value & 0x1f),
+            0xe0..=0xef => StreamId::Video(value & 0xf),
+            0xf0 => StreamId::Ecm,
+            0xf1 => StreamId::Emm,
+            0xf2 => StreamId::Dsmcc,
+            0xf3 => StreamId::Mheg,
+            0xf4..=0xf8 => StreamId::H2221(value - 0xf4),
+            0xf9 => StreamId::Ancillary,
+            0xfa => StreamId::SlPacketized,
+            0xfb => StreamId::FlexMux,
+            0xfc => StreamId::Metadata,
+            0xfd => StreamId::ExtendedStreamId,
+            0xff => StreamId::ProgramStreamDirectory,
+            other => StreamId::Reserved(other),
+        }
+    }
+}
+
+impl From<StreamId> for u8 {
+    fn from(stream_id: StreamId) -> Self {
+        match stream_id {
+            StreamId::ProgramStreamMap => 0xbc,
+            StreamId::PrivateStream1 => 0xbd,
+            StreamId::Padding => 0xbe,
+            StreamId::PrivateStream2 => 0xbf,
+            StreamId::Audio(number) => 0xc0 | (number & 0x1f),
+            StreamId::Video(number) => 0xe0 | (number & 0xf),
+            StreamId::Ecm => 0xf0,
+            StreamId::Emm => 0xf1,
+            StreamId::Dsmcc => 0xf2,
+            StreamId::Mheg => 0xf3,
+            StreamId::H2221(stream_type) => 0xf4 + stream_type.min(4),
+            StreamId::Ancillary => 0xf9,
+            StreamId::SlPacketized => 0xfa,
+            StreamId::FlexMux => 0xfb,
+            StreamId::Metadata => 0xfc,
+            StreamId::ExtendedStreamId => 0xfd,
+            StreamId::ProgramStreamDirectory => 0xff,
+            StreamId::Reserved(other) => other,
+        }
+    }
+}
+
 /// Optional header of PES unit.
 #[bitfield]
 #[derive(Debug)]
@@ -37,26 +163,165 @@ pub struct PesOptionalHeader {
     pub additional_header_length: B8,
 }
 
+/// Trick mode of a PES packet, applying to digital storage media playback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DsmTrickMode {
+    /// Fast forward playback.
+    FastForward {
+        /// Fields to display: 0 = top only, 1 = bottom only, 2 = full frame.
+        field_id: u8,
+        /// Whether missing macroblocks may occur between slices.
+        intra_slice_refresh: bool,
+        /// Restricted set of non-zero DCT coefficients.
+        frequency_truncation: u8,
+    },
+    /// Slow motion playback.
+    SlowMotion {
+        /// Number of times each field (or frame) is displayed.
+        rep_cntrl: u8,
+    },
+    /// Freeze frame playback.
+    FreezeFrame {
+        /// Fields to display: 0 = top only, 1 = bottom only, 2 = full frame.
+        field_id: u8,
+    },
+    /// Fast reverse playback.
+    FastReverse {
+        /// Fields to display: 0 = top only, 1 = bottom only, 2 = full frame.
+        field_id: u8,
+        /// Whether missing macroblocks may occur between slices.
+        intra_slice_refresh: bool,
+        /// Restricted set of non-zero DCT coefficients.
+        frequency_truncation: u8,
+    },
+    /// Slow reverse playback.
+    SlowReverse {
+        /// Number of times each field (or frame) is displayed.
+        rep_cntrl: u8,
+    },
+    /// Reserved trick mode control; carries the whole field byte.
+    Reserved(u8),
+}
+
+impl DsmTrickMode {
+    fn from_byte(b: u8) -> Self {
+        let field_id = (b >> 3) & 0x3;
+        let intra_slice_refresh = b & 0x4 != 0;
+        let frequency_truncation = b & 0x3;
+        let rep_cntrl = b & 0x1f;
+        match b >> 5 {
+            0 => DsmTrickMode::FastForward {
+                field_id,
+                intra_slice_refresh,
+                frequency_truncation,
+            },
+            1 => DsmTrickMode::SlowMotion { rep_cntrl },
+            2 => DsmTrickMode::FreezeFrame { field_id },
+            3 => DsmTrickMode::FastReverse {
+                field_id,
+                intra_slice_refresh,
+                frequency_truncation,
+            },
+            4 => DsmTrickMode::SlowReverse { rep_cntrl },
+            _ => DsmTrickMode::Reserved(b),
+        }
+    }
+}
+
+/// Program packet sequence counter of a PES extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ProgramPacketSequenceCounter {
+    /// 7-bit counter incremented with each PES packet of the program.
+    pub counter: u8,
+    /// Set if the PES packet originates from an ISO/IEC 11172-1 (MPEG-1) stream.
+    pub mpeg1_mpeg2_identifier: bool,
+    /// Number of stuffing bytes in the original PES header.
+    pub original_stuff_length: u8,
+}
+
+/// P-STD buffer size of a PES extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PStdBuffer {
+    /// Unit of `size`: 1024 bytes if set, 128 bytes otherwise.
+    pub scale: bool,
+    /// 13-bit buffer size in units of `scale`.
+    pub size: u16,
+}
+
+impl PStdBuffer {
+    /// Buffer size in bytes.
+    pub fn size_bytes(&self) -> u32 {
+        self.size as u32 * if self.scale { 1024 } else { 128 }
+    }
+}
+
+/// PES extension of the optional header.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PesExtension {
+    /// Private data.
+    pub private_data: Option<[u8; 16]>,
+    /// Pack header of an ISO/IEC 11172-1 or 13818-1 program stream.
+    pub pack_header: Option<Vec<u8>>,
+    /// Program packet sequence counter.
+    pub program_packet_sequence_counter: Option<ProgramPacketSequenceCounter>,
+    /// P-STD buffer size.
+    pub p_std_buffer: Option<PStdBuffer>,
+    /// Data of the second extension (stream ID extension and TREF).
+    pub extension2: Option<Vec<u8>>,
+}
+
+impl PesExtension {
+    /// Stream ID extension of the second extension, if present.
+    pub fn stream_id_extension(&self) -> Option<u8> {
+        match self.extension2.as_deref() {
+            Some([b, ..]) if b & 0x80 == 0 => Some(b & 0x7f),
+            _ => None,
+        }
+    }
+}
+
 /// An elementary stream object that can be incrementally assembled from multiple
 /// sequential payloads and finished once the expected payload length has been read.
-pub trait PesUnitObject<D: AppDetails>: Debug {
+///
+/// Finished objects can be recovered from [`Pes::data`] as their concrete type with
+/// [`Pes::data_ref`] or [`Pes::into_data`]. Objects are `Send`, so parsers and parsed packets can
+/// be moved between threads.
+pub trait PesUnitObject<D: AppDetails>: Debug + Any + Send {
     /// Appends a slice of data to the payload unit.
     fn extend_from_slice(&mut self, slice: &[u8]);
     /// Finishes a payload unit after the last slice is appended.
     fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D>;
+    /// Finishes a payload unit contained entirely in one packet, borrowing its data from the
+    /// packet.
+    ///
+    /// The default implementation appends the slice and calls [`PesUnitObject::finish`], copying
+    /// the data. Objects that do not keep their raw data may parse the slice directly instead to
+    /// avoid the copy. Units without an application parser always own a copy of their data, as
+    /// [`Pes`] outlives the packet.
+    fn finish_from_slice(
+        &mut self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<(), D> {
+        self.extend_from_slice(slice);
+        self.finish(pid, parser)
+    }
+    /// Unparsed payload bytes, for objects that keep the PES data as-is.
+    fn raw_data(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
 #[derive(Default)]
 struct RawPesData(Vec<u8>);
 
-impl RawPesData {
-    pub fn new(capacity: usize) -> Self {
-        Self(Vec::with_capacity(capacity))
-    }
-}
-
 impl Debug for RawPesData {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("RawPesData")
             .field("len", &self.0.len())
             .finish()
@@ -71,6 +336,10 @@ impl<D: AppDetails> PesUnitObject<D> for RawPesData {
     fn finish(&mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<(), D> {
         Ok(())
     }
+
+    fn raw_data(&self) -> Option<&[u8]> {
+        Some(&self.0)
+    }
 }
 
 /// Parsed Packetized Elementary Stream data (PES).
@@ -80,16 +349,50 @@ impl<D: AppDetails> PesUnitObject<D> for RawPesData {
 pub struct Pes<D> {
     /// PES Header.
     pub header: PesHeader,
-    /// Extra header present when there is enough data and the stream ID is not 0xBF.
+    /// Extra header present when there is enough data and the stream ID carries it.
     pub optional_header: Option<PesOptionalHeader>,
     /// Presentation time stamp.
-    pub pts: Option<u64>,
+    pub pts: Option<Timestamp90k>,
     /// Decoder time stamp.
-    pub dts: Option<u64>,
+    pub dts: Option<Timestamp90k>,
+    /// Elementary stream clock reference.
+    pub escr: Option<PcrTimestamp>,
+    /// Rate of the elementary stream in units of 50 bytes/s.
+    pub es_rate: Option<u32>,
+    /// DSM trick mode.
+    pub dsm_trick_mode: Option<DsmTrickMode>,
+    /// 7 bits of private copyright information.
+    pub additional_copy_info: Option<u8>,
+    /// CRC-16 of the data of the previous PES packet.
+    pub previous_pes_crc: Option<u16>,
+    /// PES extension.
+    pub extension: Option<PesExtension>,
     /// PES data which is incomplete until the final packet arrives.
     pub data: Box<dyn PesUnitObject<D>>,
 }
 
+impl<D: AppDetails> Pes<D> {
+    /// Payload bytes of a unit that no [`AppDetails`] parser claimed.
+    pub fn raw_data(&self) -> Option<&[u8]> {
+        self.data.raw_data()
+    }
+
+    /// Downcasts the unit data to the concrete type returned from
+    /// [`AppDetails::new_pes_unit_data`].
+    pub fn data_ref<T: PesUnitObject<D>>(&self) -> Option<&T> {
+        (self.data.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    /// Takes ownership of the unit data as the concrete type returned from
+    /// [`AppDetails::new_pes_unit_data`].
+    pub fn into_data<T: PesUnitObject<D>>(self) -> Option<T> {
+        (self.data as Box<dyn Any>)
+            .downcast()
+            .ok()
+            .map(|data| *data)
+    }
+}
+
 impl<D: AppDetails> PayloadUnitObject<D> for Pes<D> {
     fn extend_from_slice(&mut self, slice: &[u8]) {
         self.data.extend_from_slice(slice);
@@ -100,32 +403,114 @@ impl<D: AppDetails> PayloadUnitObject<D> for Pes<D> {
         Ok(Payload::Pes(self))
     }
 
-    fn pending<'a>(&self) -> Result<Payload<'a, D>, D> {
-        Ok(Payload::PesPending)
+    fn finish_from_slice<'a>(
+        mut self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
+        self.data.finish_from_slice(slice, pid, parser)?;
+        Ok(Payload::Pes(self))
     }
-}
 
-fn fmt_pts_field(s: &mut DebugStruct, name: &str, ts: &Option<u64>) {
-    if let Some(ts) = ts {
-        s.field(name, &Some(pts_format_args!(ts)));
-    } else {
-        s.field(name, &Option::<Arguments>::None);
+    fn pending<'a>(&self) -> Result<Payload<'a, D>, D> {
+        Ok(Payload::PesPending)
     }
 }
 
 impl<D> Debug for Pes<D> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let mut s = f.debug_struct("Pes");
         s.field("header", &self.header);
         s.field("optional_header", &self.optional_header);
-        fmt_pts_field(&mut s, "pts", &self.pts);
-        fmt_pts_field(&mut s, "dts", &self.dts);
+        s.field("pts", &self.pts);
+        s.field("dts", &self.dts);
+        s.field("escr", &self.escr);
+        s.field("es_rate", &self.es_rate);
+        s.field("dsm_trick_mode", &self.dsm_trick_mode);
+        s.field("additional_copy_info", &self.additional_copy_info);
+        s.field("previous_pes_crc", &self.previous_pes_crc);
+        s.field("extension", &self.extension);
         s.field("data", &self.data);
         s.finish()
     }
 }
 
+/// Reads a fixed-size field of the optional header, failing with a PES header error.
+fn read_field<'a, D: AppDetails, const N: usize>(
+    reader: &mut SliceReader<'a, D>,
+    name: &str,
+) -> Result<&'a [u8; N], D> {
+    if reader.remaining_len() < N {
+        warn!("Short read of {}", name);
+        return Err(reader.make_error(ErrorDetails::<D>::BadPesHeader));
+    }
+    reader.read_array_ref::<N>()
+}
+
+fn read_escr(b: &[u8; 6]) -> PcrTimestamp {
+    let mut v = [0_u8; 8];
+    v[2..].copy_from_slice(b);
+    let v = u64::from_be_bytes(v);
+    PcrTimestamp {
+        base: Timestamp90k(
+            ((v >> 43) & 0x7) << 30 | ((v >> 27) & 0x7fff) << 15 | ((v >> 11) & 0x7fff),
+        ),
+        extension: ((v >> 1) & 0x1ff) as u16,
+    }
+}
+
+fn read_pes_extension<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<PesExtension, D> {
+    let flags = read_field::<D, 1>(reader, "PES extension flags")?[0];
+    let mut extension = PesExtension::default();
+    if flags & 0x80 != 0 {
+        extension.private_data = Some(*read_field::<D, 16>(reader, "PES private data")?);
+    }
+    if flags & 0x40 != 0 {
+        let length = read_field::<D, 1>(reader, "pack header length")?[0] as usize;
+        if reader.remaining_len() < length {
+            warn!("Short read of pack header");
+            return Err(reader.make_error(ErrorDetails::<D>::BadPesHeader));
+        }
+        extension.pack_header = Some(reader.read(length)?.to_vec());
+    }
+    if flags & 0x20 != 0 {
+        let b = read_field::<D, 2>(reader, "program packet sequence counter")?;
+        extension.program_packet_sequence_counter = Some(ProgramPacketSequenceCounter {
+            counter: b[0] & 0x7f,
+            mpeg1_mpeg2_identifier: b[1] & 0x40 != 0,
+            original_stuff_length: b[1] & 0x3f,
+        });
+    }
+    if flags & 0x10 != 0 {
+        let b = read_field::<D, 2>(reader, "P-STD buffer")?;
+        extension.p_std_buffer = Some(PStdBuffer {
+            scale: b[0] & 0x20 != 0,
+            size: u16::from_be_bytes(*b) & 0x1fff,
+        });
+    }
+    if flags & 0x01 != 0 {
+        let length = (read_field::<D, 1>(reader, "PES extension 2 length")?[0] & 0x7f) as usize;
+        if reader.remaining_len() < length {
+            warn!("Short read of PES extension 2");
+            return Err(reader.make_error(ErrorDetails::<D>::BadPesHeader));
+        }
+        extension.extension2 = Some(reader.read(length)?.to_vec());
+    }
+    Ok(extension)
+}
+
 impl<D: AppDetails> MpegTsParser<D> {
+    /// Hands a finished PES unit back to the parser once the application is done with it.
+    ///
+    /// The data buffer of a unit without an application parser is reused to assemble following
+    /// units, so long-running ingest does not allocate per unit. Other units are dropped.
+    pub fn recycle_pes(&mut self, pes: Pes<D>) {
+        if let Some(RawPesData(buffer)) = pes.into_data() {
+            self.buffer_pool.recycle(buffer);
+        }
+    }
+
     pub(crate) fn start_pes<'a>(
         &mut self,
         pid: u16,
@@ -133,43 +518,91 @@ impl<D: AppDetails> MpegTsParser<D> {
     ) -> Result<Payload<'a, D>, D> {
         let header = read_bitfield!(reader, PesHeader);
         let pes_length = header.packet_length() as usize;
+        /* Video units may leave the length unbounded, ending with the next unit start */
+        let unbounded = pes_length == 0;
         let mut optional_length = 0;
         let mut pts = None;
         let mut dts = None;
-        let optional_header = if pes_length >= 3 && header.stream_id() != 0xBF {
-            let pes_optional = read_bitfield!(reader, PesOptionalHeader);
-            let additional_length = pes_optional.additional_header_length() as usize;
-            optional_length = 3 + additional_length;
-            let mut o_reader = reader.new_sub_reader(additional_length)?;
-
-            if pes_optional.has_pts() {
-                if o_reader.remaining_len() < 5 {
-                    warn!("Short read of PTS");
-                    return Err(o_reader.make_error(ErrorDetails::<D>::BadPesHeader));
+        let mut escr = None;
+        let mut es_rate = None;
+        let mut dsm_trick_mode = None;
+        let mut additional_copy_info = None;
+        let mut previous_pes_crc = None;
+        let mut extension = None;
+        let optional_header =
+            if (unbounded || pes_length >= 3) && header.stream_id_kind().has_optional_header() {
+                let pes_optional = read_bitfield!(reader, PesOptionalHeader);
+                let additional_length = pes_optional.additional_header_length() as usize;
+                optional_length = 3 + additional_length;
+                let mut o_reader = reader.new_sub_reader(additional_length)?;
+
+                if pes_optional.has_pts() {
+                    pts = Some(Timestamp90k(parse_timestamp(read_field(
+                        &mut o_reader,
+                        "PTS",
+                    )?)));
                 }
-                pts = Some(parse_timestamp(o_reader.read_array_ref::<5>()?));
-            }
 
-            if pes_optional.has_dts() {
-                if o_reader.remaining_len() < 5 {
-                    warn!("Short read of DTS");
-                    return Err(o_reader.make_error(ErrorDetails::<D>::BadPesHeader));
+                if pes_optional.has_dts() {
+                    dts = Some(Timestamp90k(parse_timestamp(read_field(
+                        &mut o_reader,
+                        "DTS",
+                    )?)));
                 }
-                dts = Some(parse_timestamp(o_reader.read_array_ref::<5>()?));
-            }
 
-            // TODO: Other fields
-            Some(pes_optional)
-        } else {
+                if pes_optional.escr() {
+                    escr = Some(read_escr(read_field(&mut o_reader, "ESCR")?));
+                }
+
+                if pes_optional.es_rate() {
+                    let b = read_field::<D, 3>(&mut o_reader, "ES rate")?;
+                    es_rate = Some((u32::from_be_bytes([0, b[0], b[1], b[2]]) >> 1) & 0x3fffff);
+                }
+
+                if pes_optional.dsm_trick_mode() {
+                    let b = read_field::<D, 1>(&mut o_reader, "DSM trick mode")?;
+                    dsm_trick_mode = Some(DsmTrickMode::from_byte(b[0]));
+                }
+
+                if pes_optional.has_additional_copy_info() {
+                    let b = read_field::<D, 1>(&mut o_reader, "additional copy info")?;
+                    additional_copy_info = Some(b[0] & 0x7f);
+                }
+
+                if pes_optional.has_crc() {
+                    let b = read_field::<D, 2>(&mut o_reader, "previous PES CRC")?;
+                    previous_pes_crc = Some(u16::from_be_bytes(*b));
+                }
+
+                if pes_optional.has_extension() {
+                    extension = Some(read_pes_extension(&mut o_reader)?);
+                }
+
+                /* Remaining bytes are stuffing */
+                Some(pes_optional)
+            } else {
+                None
+            };
+
+        /* The optional header may not claim more than the PES packet length */
+        let unit_length = if unbounded {
             None
+        } else {
+            Some(
+                pes_length
+                    .checked_sub(optional_length)
+                    .ok_or_else(|| reader.make_error(ErrorDetails::<D>::BadPesHeader))?,
+            )
         };
 
-        let unit_length = pes_length - optional_length;
-
-        let data = if let Some(unit_data) = D::new_pes_unit_data(pid, unit_length) {
+        let capacity = unit_length.unwrap_or(0);
+        let data = if let Some(unit_data) =
+            self.app_details
+                .new_pes_unit_data(pid, capacity, self.es_streams.get(&pid))
+        {
             unit_data
         } else {
-            Box::new(RawPesData::new(unit_length))
+            Box::new(RawPesData(self.buffer_pool.take(capacity)))
         };
 
         self.start_payload_unit(
@@ -178,6 +611,12 @@ impl<D: AppDetails> MpegTsParser<D> {
                 optional_header,
                 pts,
                 dts,
+                escr,
+                es_rate,
+                dsm_trick_mode,
+                additional_copy_info,
+                previous_pes_crc,
+                extension,
                 data,
             },
             unit_length,
@@ -186,3 +625,138 @@ impl<D: AppDetails> MpegTsParser<D> {
         )
     }
 }
+
+/// Serializes a complete PES packet with an optional header carrying `pts` and `dts`.
+///
+/// Units too long for the 16-bit length field are written with a length of 0, which is only
+/// permitted for video streams.
+pub(crate) fn build_pes_unit(
+    stream_id: u8,
+    pts: Option<u64>,
+    dts: Option<u64>,
+    data: &[u8],
+) -> Vec<u8> {
+    let (pts_dts_flags, header_length) = match (pts, dts) {
+        (Some(_), Some(_)) => (0xc0, 10),
+        (Some(_), None) => (0x80, 5),
+        _ => (0x00, 0),
+    };
+    let packet_length = 3 + header_length + data.len();
+    let length_field = if packet_length > 0xffff {
+        0
+    } else {
+        packet_length as u16
+    };
+
+    let mut unit = Vec::with_capacity(6 + packet_length);
+    unit.extend_from_slice(&[0, 0, 1, stream_id]);
+    unit.extend_from_slice(&length_field.to_be_bytes());
+    unit.extend_from_slice(&[0x80, pts_dts_flags, header_length as u8]);
+    let mut ts_bytes = [0_u8; 5];
+    if let Some(pts) = pts {
+        ts_bytes[0] = if dts.is_some() { 0x30 } else { 0x20 };
+        write_timestamp(&mut ts_bytes, pts);
+        unit.extend_from_slice(&ts_bytes);
+    }
+    if let (Some(_), Some(dts)) = (pts, dts) {
+        ts_bytes[0] = 0x10;
+        write_timestamp(&mut ts_bytes, dts);
+        unit.extend_from_slice(&ts_bytes);
+    }
+    unit.extend_from_slice(data);
+    unit
+}
+
+#[test]
+fn test_optional_header_fields() {
+    use crate::{DefaultAppDetails, MpegTsWriter};
+    use core::convert::TryInto;
+
+    let escr_base: u64 = 0x1_2345_6789;
+    let escr = 0x3 << 46
+        | ((escr_base >> 30) & 0x7) << 43
+        | 1 << 42
+        | ((escr_base >> 15) & 0x7fff) << 27
+        | 1 << 26
+        | (escr_base & 0x7fff) << 11
+        | 1 << 10
+        | 0x155 << 1
+        | 1;
+    let es_rate: u32 = 1 << 23 | 0x12345 << 1 | 1;
+
+    let mut unit = vec![0, 0, 1, 0xe0, 0, 31, 0x80, 0xbf, 25];
+    let mut pts = [0x20, 0, 0, 0, 0];
+    write_timestamp(&mut pts, 90000);
+    unit.extend_from_slice(&pts);
+    unit.extend_from_slice(&escr.to_be_bytes()[2..]);
+    unit.extend_from_slice(&es_rate.to_be_bytes()[1..]);
+    unit.extend_from_slice(&[0x25, 0xaa, 0xbe, 0xef]);
+    unit.extend_from_slice(&[0x31, 0x91, 0xc5, 0x61, 0x23, 0x81, 0x05]);
+    unit.extend_from_slice(&[1, 2, 3]);
+
+    let mut out = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut out, 0x100, &unit, None)
+        .unwrap();
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let pes = match parser.parse(out[..].try_into().unwrap()).unwrap().payload {
+        Some(Payload::Pes(pes)) => pes,
+        other => panic!("unexpected payload {:?}", other),
+    };
+    assert_eq!(pes.pts, Some(Timestamp90k(90000)));
+    assert_eq!(
+        pes.escr,
+        Some(PcrTimestamp {
+            base: Timestamp90k(escr_base),
+            extension: 0x155
+        })
+    );
+    assert_eq!(pes.es_rate, Some(0x12345));
+    assert_eq!(
+        pes.dsm_trick_mode,
+        Some(DsmTrickMode::SlowMotion { rep_cntrl: 5 })
+    );
+    assert_eq!(pes.additional_copy_info, Some(0x2a));
+    assert_eq!(pes.previous_pes_crc, Some(0xbeef));
+    let extension = pes.extension.as_ref().unwrap();
+    assert_eq!(
+        extension.program_packet_sequence_counter,
+        Some(ProgramPacketSequenceCounter {
+            counter: 0x11,
+            mpeg1_mpeg2_identifier: true,
+            original_stuff_length: 5
+        })
+    );
+    assert_eq!(extension.p_std_buffer.unwrap().size_bytes(), 0x123 * 1024);
+    assert_eq!(extension.stream_id_extension(), Some(5));
+    assert_eq!(pes.raw_data(), Some(&[1, 2, 3][..]));
+}
+
+#[test]
+fn test_bad_optional_header_length() {
+    use crate::{DefaultAppDetails, MpegTsParser, MpegTsWriter};
+    use core::convert::TryInto;
+
+    /* Optional header of 3 + 10 bytes in a PES packet of 5 bytes */
+    let mut unit = vec![0, 0, 1, 0xe0, 0, 5, 0x80, 0x00, 10];
+    unit.extend_from_slice(&[0xff; 10]);
+    let mut out = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut out, 0x100, &unit, None)
+        .unwrap();
+    let packet: &[u8; 188] = out[..].try_into().unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let err = parser.parse(packet).unwrap_err();
+    assert!(matches!(err.details, ErrorDetails::BadPesHeader));
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .lenient(true)
+        .build();
+    let packet = parser.parse(packet).unwrap();
+    assert!(matches!(packet.payload, Some(Payload::Raw(_))));
+    assert!(matches!(
+        packet.payload_error.unwrap().details,
+        ErrorDetails::BadPesHeader
+    ));
+}
@@ -0,0 +1,178 @@
+use super::psi::Pmt;
+use super::{AppDetails, MpegTsParser};
+use hashbrown::HashSet;
+
+/// PID set of the program chosen with [`MpegTsParser::select_program`].
+#[derive(Debug)]
+pub(crate) struct ProgramFilter {
+    program_num: u16,
+    pmt_pid: Option<u16>,
+    pids: HashSet<u16>,
+}
+
+impl ProgramFilter {
+    fn new(program_num: u16) -> Self {
+        Self {
+            program_num,
+            pmt_pid: None,
+            pids: HashSet::new(),
+        }
+    }
+}
+
+/// PIDs to parse or skip, set with [`MpegTsParser::set_pid_filter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PidFilter {
+    /// Parses only the listed PIDs.
+    Allow(HashSet<u16>),
+    /// Parses all PIDs except the listed ones.
+    Deny(HashSet<u16>),
+}
+
+impl PidFilter {
+    /// Checks whether packets on `pid` pass the filter.
+    pub fn passes(&self, pid: u16) -> bool {
+        match self {
+            PidFilter::Allow(pids) => pids.contains(&pid),
+            PidFilter::Deny(pids) => !pids.contains(&pid),
+        }
+    }
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Skips packets of PIDs rejected by `filter` after parsing their header, or removes the
+    /// filter with [`None`].
+    ///
+    /// Skipped packets are returned without adaptation field or payload and are left out of
+    /// continuity checking and payload assembly. The PAT and PMTs are always parsed so stream
+    /// information stays available. Pending units of rejected PIDs are discarded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::{DefaultAppDetails, MpegTsParser, PidFilter};
+    /// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    /// parser.set_pid_filter(Some(PidFilter::Allow(vec![0x1200, 0x1201].into_iter().collect())));
+    /// assert!(parser.pid_filter().unwrap().passes(0x1200));
+    /// assert!(!parser.pid_filter().unwrap().passes(0x1011));
+    /// ```
+    pub fn set_pid_filter(&mut self, filter: Option<PidFilter>) {
+        if let Some(filter) = &filter {
+            let known_pmt_pids = &self.known_pmt_pids;
+            self.pending_payload_units
+                .retain(|pid, _| *pid == 0 || known_pmt_pids.contains(pid) || filter.passes(*pid));
+        }
+        self.pid_filter = filter;
+    }
+
+    /// Filter set with [`MpegTsParser::set_pid_filter`].
+    pub fn pid_filter(&self) -> Option<&PidFilter> {
+        self.pid_filter.as_ref()
+    }
+
+    /// Checks whether packets on `pid` should be parsed beyond their header under the PID filter.
+    pub(crate) fn is_pid_filtered_out(&self, pid: u16) -> bool {
+        match &self.pid_filter {
+            Some(filter) => pid != 0 && !self.known_pmt_pids.contains(&pid) && !filter.passes(pid),
+            None => false,
+        }
+    }
+
+    /// Restricts payload assembly to the PIDs of one program (PMT, PCR, and elementary streams).
+    ///
+    /// Filtering begins once the PAT mapping the program has been received and is kept up to date
+    /// as new PAT/PMT versions arrive. Payloads of PIDs outside the program are returned as
+    /// [`Payload::Raw`](crate::Payload::Raw) without being assembled. The PAT is always parsed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+    /// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    /// parser.select_program(1);
+    /// assert_eq!(parser.selected_program(), Some(1));
+    /// ```
+    pub fn select_program(&mut self, program_num: u16) {
+        self.program_filter = Some(ProgramFilter::new(program_num));
+    }
+
+    /// Removes the program selection so that all PIDs are assembled again.
+    pub fn clear_program_selection(&mut self) {
+        self.program_filter = None;
+    }
+
+    /// Program number chosen with [`MpegTsParser::select_program`].
+    pub fn selected_program(&self) -> Option<u16> {
+        self.program_filter.as_ref().map(|f| f.program_num)
+    }
+
+    /// Checks whether payloads on `pid` should be assembled under the current program selection.
+    pub(crate) fn is_pid_selected(&self, pid: u16) -> bool {
+        match &self.program_filter {
+            Some(filter) if filter.pmt_pid.is_some() => pid == 0 || filter.pids.contains(&pid),
+            _ => true,
+        }
+    }
+
+    /// Updates the selection from a PAT entry.
+    pub(crate) fn filter_pat_entry(&mut self, program_num: u16, pmt_pid: u16) {
+        if let Some(filter) = self.program_filter.as_mut() {
+            if filter.program_num == program_num && filter.pmt_pid != Some(pmt_pid) {
+                filter.pmt_pid = Some(pmt_pid);
+                filter.pids.clear();
+                filter.pids.insert(pmt_pid);
+            }
+        }
+    }
+
+    /// Updates the selection from the PMT of the selected program.
+    pub(crate) fn filter_pmt(&mut self, program_num: u16, pmt_pid: u16, pmt: &Pmt) {
+        let filter = match self.program_filter.as_mut() {
+            Some(filter) if filter.program_num == program_num => filter,
+            _ => return,
+        };
+        filter.pmt_pid = Some(pmt_pid);
+        filter.pids.clear();
+        filter.pids.insert(pmt_pid);
+        filter.pids.insert(pmt.header.pcr_pid());
+        for info in pmt.es_infos.iter() {
+            filter.pids.insert(info.header.elementary_pid());
+        }
+
+        /* Drop units of streams that left the program */
+        let pids = &filter.pids;
+        self.pending_payload_units
+            .retain(|pid, _| *pid == 0 || pids.contains(pid));
+    }
+}
+
+#[test]
+fn test_pid_filter() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, Payload};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x24, 0xe0);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(0), None, &[0; 100]);
+    gen.write_pes(0x1200, Some(0), None, &[0; 100]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .pid_filter(PidFilter::Allow(vec![0x1200].into_iter().collect()))
+        .build();
+    let packets: Vec<_> = gen
+        .packets()
+        .iter()
+        .map(|p| parser.parse(p).unwrap())
+        .collect();
+    assert!(matches!(packets[1].payload, Some(Payload::Psi(_))));
+    assert!(packets[2].payload.is_none() && packets[2].adaptation_field.is_none());
+    assert!(matches!(packets[3].payload, Some(Payload::Pes(_))));
+
+    parser.set_pid_filter(Some(PidFilter::Deny(vec![0x1200].into_iter().collect())));
+    assert!(parser.parse(&gen.packets()[2]).unwrap().payload.is_some());
+    assert!(parser.parse(&gen.packets()[3]).unwrap().payload.is_none());
+    parser.set_pid_filter(None);
+    assert!(parser.pid_filter().is_none());
+}
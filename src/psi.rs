@@ -1,28 +1,37 @@
+use super::atsc::{encode_psip, parse_psip, Mgt, Rrt, Stt, Vct, PSIP_PID};
+use super::ca::{CaMessage, CaPid, CAT_PID, CAT_TABLE_ID};
+use super::descriptor::{DoviConfigurationDescriptor, KnownDescriptor};
+use super::dsmcc::{is_dsmcc_table_id, DsmccSection};
+use super::dvb::{
+    is_eit_table_id, read_utc_time, DvbTime, Eit, Nit, Tot, EIT_PID, NIT_ACTUAL_TABLE_ID,
+    NIT_OTHER_TABLE_ID, NIT_PID, TDT_PID, TDT_TABLE_ID, TOT_TABLE_ID,
+};
 use super::{
-    read_bitfield, AppDetails, CrcDigest, Error, ErrorDetails, MpegTsParser, Payload,
-    PayloadUnitObject, Result, SliceReader, CRC,
+    read_bitfield, AppDetails, CrcDigest, Error, ErrorDetails, MpegTsParser, ParserEvent, PatDiff,
+    Payload, PayloadUnitObject, Result, SliceReader, StreamType, CRC,
 };
+use crate::prelude::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::marker::PhantomData;
+use hashbrown::HashMap;
 use log::warn;
 use modular_bitfield_msb::prelude::*;
 use smallvec::SmallVec;
-use std::marker::PhantomData;
 
 /// Header of PSI unit.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct PsiHeader {
     pub table_id: B8,
     pub section_syntax_indicator: bool,
     pub private_bit: bool,
     pub reserved_bits: B2,
-    #[skip]
-    pub unused_bits: B2,
-    pub section_length: B10,
+    pub section_length: B12,
 }
 
 /// Optional table syntax of PSI unit.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct PsiTableSyntax {
     pub table_id_extension: B16,
     pub reserved_bits: B2,
@@ -34,7 +43,7 @@ pub struct PsiTableSyntax {
 
 /// Entry of PAT.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct PatEntry {
     pub program_num: B16,
     pub reserved: B3,
@@ -42,7 +51,8 @@ pub struct PatEntry {
 }
 
 /// General purposed tagged data.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Descriptor {
     /// Tag of data's purpose.
     pub tag: u8,
@@ -51,6 +61,14 @@ pub struct Descriptor {
 }
 
 impl Descriptor {
+    /// Creates a descriptor from its tag and raw data.
+    pub fn new(tag: u8, data: &[u8]) -> Self {
+        Self {
+            tag,
+            data: SmallVec::from_slice(data),
+        }
+    }
+
     pub(crate) fn new_from_reader<D: AppDetails>(reader: &mut SliceReader<D>) -> Result<Self, D> {
         let tag = reader.read_u8()?;
         let len = reader.read_u8()?;
@@ -62,7 +80,7 @@ impl Descriptor {
 
 /// Header of PMT unit.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct PmtHeader {
     pub reserved: B3,
     pub pcr_pid: B13,
@@ -74,9 +92,9 @@ pub struct PmtHeader {
 
 /// Elementary stream info header.
 #[bitfield]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct ElementaryStreamInfoHeader {
-    pub stream_type: B8,
+    pub stream_type: StreamType,
     pub reserved: B3,
     pub elementary_pid: B13,
     pub reserved2: B4,
@@ -86,7 +104,8 @@ pub struct ElementaryStreamInfoHeader {
 }
 
 /// Elementary stream info.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ElementaryStreamInfo {
     /// Elementary stream info header.
     pub header: ElementaryStreamInfoHeader,
@@ -94,16 +113,145 @@ pub struct ElementaryStreamInfo {
     pub es_descriptors: SmallVec<[Descriptor; 4]>,
 }
 
+/// Elementary stream information resolved from the PMT, provided to
+/// [`AppDetails::new_pes_unit_data`] when a PES unit starts.
+#[derive(Debug, Clone)]
+pub struct EsStreamInfo {
+    /// Program number of the PMT listing the stream.
+    pub program_num: u16,
+    /// Stream type of the stream.
+    pub stream_type: u8,
+    /// Metadata descriptors of the stream.
+    pub es_descriptors: SmallVec<[Descriptor; 4]>,
+}
+
 /// Parsed PMT unit.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Pmt {
+    /// PMT header.
     pub header: PmtHeader,
+    /// Metadata descriptors of the program.
     pub program_descriptors: Vec<Descriptor>,
+    /// Elementary streams of the program.
     pub es_infos: Vec<ElementaryStreamInfo>,
 }
 
+/// Layers of a Dolby Vision video stream carried on one PID.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DolbyVisionLayer {
+    /// Base layer only; the enhancement layer, if any, is carried on another PID.
+    Base,
+    /// Enhancement layer depending on the base layer on `base_pid`.
+    Enhancement {
+        /// PID of the base layer.
+        base_pid: u16,
+    },
+    /// Base and enhancement layer on a single PID.
+    BaseAndEnhancement,
+}
+
+/// Dolby Vision role of an elementary stream, resolved from the PMT.
+#[derive(Debug, Copy, Clone)]
+pub struct DolbyVisionStream {
+    /// PID of the stream.
+    pub pid: u16,
+    /// Layers carried on the PID.
+    pub layer: DolbyVisionLayer,
+    /// Configuration of the stream, or of the dependent enhancement layer for a base layer
+    /// without its own descriptor.
+    pub config: DoviConfigurationDescriptor,
+}
+
+impl Pmt {
+    /// Resolves the Dolby Vision streams of the program from their video stream descriptors.
+    ///
+    /// Base layers of dual-PID streams (such as UHD Blu-ray profile 7) are listed even if only the
+    /// enhancement layer carries a descriptor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::descriptor::{DoviConfigurationDescriptor, KnownDescriptor};
+    /// use mpegts_io::{DolbyVisionLayer, PmtBuilder, PsiData, StreamType};
+    /// let config = DoviConfigurationDescriptor {
+    ///     version_major: 1,
+    ///     version_minor: 0,
+    ///     profile: 7,
+    ///     level: 6,
+    ///     rpu_present_flag: true,
+    ///     el_present_flag: true,
+    ///     bl_present_flag: false,
+    ///     dependency_pid: Some(0x1011),
+    ///     bl_signal_compatibility_id: Some(6),
+    /// };
+    /// let psi = PmtBuilder::new(1, 0x1001)
+    ///     .stream(StreamType::Hevc, 0x1011, vec![])
+    ///     .stream(
+    ///         StreamType::Hevc,
+    ///         0x1015,
+    ///         vec![KnownDescriptor::DoviConfiguration(config).to_descriptor()],
+    ///     )
+    ///     .build();
+    /// let pmt = match psi.data {
+    ///     PsiData::Pmt(pmt) => pmt,
+    ///     _ => unreachable!(),
+    /// };
+    /// let streams = pmt.dolby_vision_streams();
+    /// assert_eq!(streams[0].pid, 0x1011);
+    /// assert_eq!(streams[0].layer, DolbyVisionLayer::Base);
+    /// assert_eq!(streams[1].layer, DolbyVisionLayer::Enhancement { base_pid: 0x1011 });
+    /// assert_eq!(streams[1].config.profile, 7);
+    /// ```
+    pub fn dolby_vision_streams(&self) -> Vec<DolbyVisionStream> {
+        let mut streams = Vec::new();
+        for es_info in self.es_infos.iter() {
+            let pid = es_info.header.elementary_pid();
+            let config =
+                es_info
+                    .es_descriptors
+                    .iter()
+                    .find_map(|descriptor| match descriptor.parse() {
+                        KnownDescriptor::DoviConfiguration(config) => Some(config),
+                        _ => None,
+                    });
+            if let Some(config) = config {
+                let layer = match (config.bl_present_flag, config.dependency_pid) {
+                    (false, Some(base_pid)) => DolbyVisionLayer::Enhancement { base_pid },
+                    _ if config.el_present_flag => DolbyVisionLayer::BaseAndEnhancement,
+                    _ => DolbyVisionLayer::Base,
+                };
+                streams.push(DolbyVisionStream { pid, layer, config });
+            }
+        }
+        /* Tag base layers referenced by enhancement layers */
+        for i in 0..streams.len() {
+            if let DolbyVisionLayer::Enhancement { base_pid } = streams[i].layer {
+                let listed = self
+                    .es_infos
+                    .iter()
+                    .any(|es_info| es_info.header.elementary_pid() == base_pid);
+                if listed && !streams.iter().any(|stream| stream.pid == base_pid) {
+                    streams.push(DolbyVisionStream {
+                        pid: base_pid,
+                        layer: DolbyVisionLayer::Base,
+                        config: streams[i].config,
+                    });
+                }
+            }
+        }
+        streams.sort_by_key(|stream| {
+            self.es_infos
+                .iter()
+                .position(|es_info| es_info.header.elementary_pid() == stream.pid)
+        });
+        streams
+    }
+}
+
 /// Parsed PSI payload unit.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PsiData {
     /// Raw unit data.
     Raw(Vec<u8>),
@@ -111,6 +259,28 @@ pub enum PsiData {
     Pat(Vec<PatEntry>),
     /// PMT.
     Pmt(Pmt),
+    /// CAT descriptors.
+    Cat(Vec<Descriptor>),
+    /// ECM or EMM section on a PID registered by a CA descriptor.
+    CaMessage(CaMessage),
+    /// DVB NIT.
+    Nit(Nit),
+    /// DVB EIT.
+    Eit(Eit),
+    /// DVB TDT carrying the current UTC time.
+    Tdt(DvbTime),
+    /// DVB TOT.
+    Tot(Tot),
+    /// ATSC MGT.
+    Mgt(Mgt),
+    /// ATSC TVCT or CVCT.
+    Vct(Vct),
+    /// ATSC RRT.
+    Rrt(Rrt),
+    /// ATSC STT.
+    Stt(Stt),
+    /// DSM-CC section.
+    Dsmcc(DsmccSection),
 }
 
 /// Parsed Program Specific Information data (PSI).
@@ -118,6 +288,7 @@ pub enum PsiData {
 /// Encapsulates tables like PAT/PMT/NIT/CAT.
 /// Reference: <https://en.wikipedia.org/wiki/Program-specific_information>
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Psi {
     /// PSI Header.
     pub header: PsiHeader,
@@ -127,6 +298,282 @@ pub struct Psi {
     pub data: PsiData,
 }
 
+impl Descriptor {
+    /// Appends the tag, length, and data of the descriptor to `out`.
+    pub fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.tag);
+        out.push(self.data.len() as u8);
+        out.extend_from_slice(&self.data);
+    }
+}
+
+impl PsiData {
+    /// Appends the table body to `out`. Descriptor loop lengths of a PMT are recomputed.
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            PsiData::Raw(data) => out.extend_from_slice(data),
+            PsiData::Pat(entries) => {
+                for entry in entries.iter() {
+                    out.extend_from_slice(&entry.into_bytes());
+                }
+            }
+            PsiData::Pmt(pmt) => {
+                let mut program_info = Vec::new();
+                for descriptor in pmt.program_descriptors.iter() {
+                    descriptor.write_to(&mut program_info);
+                }
+                let header = PmtHeader::from_bytes(pmt.header.into_bytes())
+                    .with_program_info_length(program_info.len() as u16);
+                out.extend_from_slice(&header.into_bytes());
+                out.extend_from_slice(&program_info);
+                for es_info in pmt.es_infos.iter() {
+                    let mut es_info_data = Vec::new();
+                    for descriptor in es_info.es_descriptors.iter() {
+                        descriptor.write_to(&mut es_info_data);
+                    }
+                    let es_header =
+                        ElementaryStreamInfoHeader::from_bytes(es_info.header.into_bytes())
+                            .with_es_info_length(es_info_data.len() as u16);
+                    out.extend_from_slice(&es_header.into_bytes());
+                    out.extend_from_slice(&es_info_data);
+                }
+            }
+            PsiData::Cat(descriptors) => {
+                for descriptor in descriptors.iter() {
+                    descriptor.write_to(out);
+                }
+            }
+            PsiData::CaMessage(message) => out.extend_from_slice(&message.data),
+            PsiData::Nit(nit) => nit.encode(out),
+            PsiData::Eit(eit) => eit.encode(out),
+            PsiData::Tdt(time) => out.extend_from_slice(&time.to_bytes()),
+            PsiData::Tot(tot) => tot.encode(out),
+            PsiData::Mgt(_) | PsiData::Vct(_) | PsiData::Rrt(_) | PsiData::Stt(_) => {
+                encode_psip(self, out)
+            }
+            PsiData::Dsmcc(section) => section.encode(out),
+        }
+    }
+}
+
+impl Psi {
+    /// Serializes the section, computing the section length and CRC.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the section length exceeds 1023 bytes, or 4093 bytes for private, DSM-CC and
+    /// conditional access message sections.
+    pub fn to_section(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        if let Some(table_syntax) = self.table_syntax.as_ref() {
+            body.extend_from_slice(&table_syntax.into_bytes());
+        }
+        self.data.encode(&mut body);
+
+        /* Short sections have no CRC, except for the DVB TOT */
+        let has_crc = self.table_syntax.is_some() || matches!(self.data, PsiData::Tot(_));
+        let section_length = if has_crc { body.len() + 4 } else { body.len() };
+        let max_length = if self.header.private_bit()
+            || matches!(self.data, PsiData::Dsmcc(_) | PsiData::CaMessage(_))
+        {
+            0xffd
+        } else {
+            0x3ff
+        };
+        assert!(section_length <= max_length, "PSI section too long");
+        let header = PsiHeader::from_bytes(self.header.into_bytes())
+            .with_section_length(section_length as u16);
+        let mut section = Vec::with_capacity(3 + section_length);
+        section.extend_from_slice(&header.into_bytes());
+        section.extend_from_slice(&body);
+        if has_crc {
+            let crc = CRC.checksum(&section);
+            section.extend_from_slice(&crc.to_be_bytes());
+        }
+        section
+    }
+
+    /// Serializes the section preceded by a zero pointer field, ready to be packetized as a
+    /// payload unit (e.g. with [`MpegTsWriter::write_unit`](crate::MpegTsWriter::write_unit)).
+    pub fn to_payload_unit(&self) -> Vec<u8> {
+        let mut unit = self.to_section();
+        unit.insert(0, 0);
+        unit
+    }
+}
+
+/// Creates the header and table syntax of a single long-form section.
+fn new_long_section(
+    table_id: u8,
+    table_id_extension: u16,
+    version: u8,
+) -> (PsiHeader, Option<PsiTableSyntax>) {
+    let header = PsiHeader::new()
+        .with_table_id(table_id)
+        .with_section_syntax_indicator(true)
+        .with_reserved_bits(0x3);
+    let table_syntax = PsiTableSyntax::new()
+        .with_table_id_extension(table_id_extension)
+        .with_reserved_bits(0x3)
+        .with_version(version & 0x1f)
+        .with_current_next_indicator(true);
+    (header, Some(table_syntax))
+}
+
+/// Builder of a single-section PAT.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{MpegTsWriter, PatBuilder};
+/// let pat = PatBuilder::new(1).version(3).program(1, 0x100).build();
+/// let mut out = Vec::new();
+/// MpegTsWriter::default().write_unit(&mut out, 0, &pat.to_payload_unit(), None)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct PatBuilder {
+    transport_stream_id: u16,
+    version: u8,
+    entries: Vec<PatEntry>,
+}
+
+impl PatBuilder {
+    /// Starts a PAT of the transport stream with the given ID.
+    pub fn new(transport_stream_id: u16) -> Self {
+        Self {
+            transport_stream_id,
+            version: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Sets the version number of the table.
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Maps a program number to the PID carrying its PMT. Program number 0 maps the NIT PID.
+    pub fn program(mut self, program_num: u16, program_map_pid: u16) -> Self {
+        self.entries.push(
+            PatEntry::new()
+                .with_program_num(program_num)
+                .with_reserved(0x7)
+                .with_program_map_pid(program_map_pid),
+        );
+        self
+    }
+
+    /// Creates the table.
+    pub fn build(self) -> Psi {
+        let (header, table_syntax) = new_long_section(0x00, self.transport_stream_id, self.version);
+        Psi {
+            header,
+            table_syntax,
+            data: PsiData::Pat(self.entries),
+        }
+    }
+}
+
+/// Builder of a single-section PMT.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, MpegTsWriter, Payload, PmtBuilder, PsiData};
+/// use mpegts_io::PatBuilder;
+/// use std::convert::TryInto;
+///
+/// let pat = PatBuilder::new(1).program(1, 0x100).build();
+/// let pmt = PmtBuilder::new(1, 0x1011)
+///     .stream(0x1b, 0x1011, Vec::new())
+///     .stream(0x0f, 0x1100, Vec::new())
+///     .build();
+/// let mut writer = MpegTsWriter::default();
+/// let mut out = Vec::new();
+/// writer.write_unit(&mut out, 0, &pat.to_payload_unit(), None)?;
+/// writer.write_unit(&mut out, 0x100, &pmt.to_payload_unit(), None)?;
+///
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// parser.parse(out[..188].try_into().unwrap()).unwrap();
+/// let packet = parser.parse(out[188..].try_into().unwrap()).unwrap();
+/// if let Some(Payload::Psi(psi)) = packet.payload {
+///     if let PsiData::Pmt(pmt) = psi.data {
+///         assert_eq!(pmt.es_infos.len(), 2);
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct PmtBuilder {
+    program_num: u16,
+    version: u8,
+    pcr_pid: u16,
+    program_descriptors: Vec<Descriptor>,
+    es_infos: Vec<ElementaryStreamInfo>,
+}
+
+impl PmtBuilder {
+    /// Starts a PMT of `program_num` whose PCR is carried on `pcr_pid` (0x1fff if none).
+    pub fn new(program_num: u16, pcr_pid: u16) -> Self {
+        Self {
+            program_num,
+            version: 0,
+            pcr_pid,
+            program_descriptors: Vec::new(),
+            es_infos: Vec::new(),
+        }
+    }
+
+    /// Sets the version number of the table.
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Adds a descriptor applying to the whole program.
+    pub fn program_descriptor(mut self, descriptor: Descriptor) -> Self {
+        self.program_descriptors.push(descriptor);
+        self
+    }
+
+    /// Lists an elementary stream.
+    pub fn stream<T: Into<StreamType>, I: IntoIterator<Item = Descriptor>>(
+        mut self,
+        stream_type: T,
+        elementary_pid: u16,
+        es_descriptors: I,
+    ) -> Self {
+        self.es_infos.push(ElementaryStreamInfo {
+            header: ElementaryStreamInfoHeader::new()
+                .with_stream_type(stream_type.into())
+                .with_reserved(0x7)
+                .with_elementary_pid(elementary_pid)
+                .with_reserved2(0xf),
+            es_descriptors: es_descriptors.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Creates the table.
+    pub fn build(self) -> Psi {
+        let (header, table_syntax) = new_long_section(0x02, self.program_num, self.version);
+        Psi {
+            header,
+            table_syntax,
+            data: PsiData::Pmt(Pmt {
+                header: PmtHeader::new()
+                    .with_reserved(0x7)
+                    .with_pcr_pid(self.pcr_pid)
+                    .with_reserved2(0xf),
+                program_descriptors: self.program_descriptors,
+                es_infos: self.es_infos,
+            }),
+        }
+    }
+}
+
 pub(crate) struct PsiBuilder<D> {
     phantom: PhantomData<D>,
     header: PsiHeader,
@@ -137,20 +584,25 @@ pub(crate) struct PsiBuilder<D> {
 
 impl<D: AppDetails> PsiBuilder<D> {
     pub fn new(
-        capacity: usize,
+        data: Vec<u8>,
         header: PsiHeader,
         table_syntax: Option<PsiTableSyntax>,
-        hasher: CrcDigest,
+        hasher: Option<CrcDigest>,
     ) -> Self {
         Self {
             phantom: PhantomData,
             header,
             table_syntax,
-            data: Vec::with_capacity(capacity),
-            hasher: Some(hasher),
+            data,
+            hasher,
         }
     }
 
+    /// Header and unvalidated bytes received so far, for units that will never complete.
+    pub fn into_parts(self) -> (PsiHeader, Option<PsiTableSyntax>, Vec<u8>) {
+        (self.header, self.table_syntax, self.data)
+    }
+
     fn finish_substitute_data<'a>(mut self, data: PsiData) -> Result<Payload<'a, D>, D> {
         Ok(Payload::Psi(Psi {
             header: self.header,
@@ -159,28 +611,44 @@ impl<D: AppDetails> PsiBuilder<D> {
         }))
     }
 
-    fn finish_keep_raw_data<'a>(mut self) -> Result<Payload<'a, D>, D> {
+    fn finish_keep_raw_data<'a>(mut self, data: &[u8]) -> Result<Payload<'a, D>, D> {
         Ok(Payload::Psi(Psi {
             header: self.header,
             table_syntax: self.table_syntax,
-            data: PsiData::Raw(self.data),
+            data: PsiData::Raw(data.to_vec()),
         }))
     }
 
-    fn finish_pat<'a>(mut self, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D> {
+    fn finish_pat<'a>(
+        mut self,
+        data: &[u8],
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
         parser.known_pmt_pids.clear();
-        let mut reader = SliceReader::new(self.data.as_slice());
+        let mut reader = SliceReader::new(data);
         let mut pat_vec = Vec::with_capacity(reader.remaining_len() / 4);
         while reader.remaining_len() >= 4 {
             let entry = read_bitfield!(reader, PatEntry);
             parser.known_pmt_pids.insert(entry.program_map_pid());
+            parser.filter_pat_entry(entry.program_num(), entry.program_map_pid());
             pat_vec.push(entry);
         }
+        if self
+            .table_syntax
+            .is_none_or(|table_syntax| table_syntax.current_next_indicator())
+        {
+            parser.update_programs(&pat_vec);
+        }
         self.finish_substitute_data(PsiData::Pat(pat_vec))
     }
 
-    fn finish_pmt<'a>(mut self, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D> {
-        let mut reader = SliceReader::new(self.data.as_slice());
+    fn finish_pmt<'a>(
+        mut self,
+        data: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
         let header = read_bitfield!(reader, PmtHeader);
         let mut pmt = Pmt {
             header,
@@ -205,46 +673,182 @@ impl<D: AppDetails> PsiBuilder<D> {
             }
             pmt.es_infos.push(es_info);
         }
+        if let Some(table_syntax) = self.table_syntax.as_ref() {
+            if table_syntax.current_next_indicator() {
+                parser
+                    .pmts
+                    .insert(table_syntax.table_id_extension(), pmt.clone());
+                parser.update_es_streams(table_syntax.table_id_extension(), &pmt);
+                parser.update_ecm_pids(table_syntax.table_id_extension(), &pmt);
+                parser.filter_pmt(table_syntax.table_id_extension(), pid, &pmt);
+                parser.update_program_state(
+                    table_syntax.table_id_extension(),
+                    pid,
+                    table_syntax.version(),
+                    &pmt,
+                );
+            }
+        }
         self.finish_substitute_data(PsiData::Pmt(pmt))
     }
-}
 
-impl<D: AppDetails> PayloadUnitObject<D> for PsiBuilder<D> {
-    fn extend_from_slice(&mut self, slice: &[u8]) {
-        self.data.extend_from_slice(slice);
+    fn finish_cat<'a>(
+        mut self,
+        data: &[u8],
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let mut descriptors = Vec::new();
+        while reader.remaining_len() > 0 {
+            descriptors.push(Descriptor::new_from_reader(&mut reader)?);
+        }
+        if self
+            .table_syntax
+            .is_none_or(|table_syntax| table_syntax.current_next_indicator())
+        {
+            parser.update_emm_pids(&descriptors);
+        }
+        self.finish_substitute_data(PsiData::Cat(descriptors))
     }
 
-    fn finish<'a>(mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D> {
+    fn finish_ca_message<'a>(mut self, data: &[u8], ca_pid: CaPid) -> Result<Payload<'a, D>, D> {
+        self.finish_substitute_data(PsiData::CaMessage(CaMessage {
+            ca_system_id: ca_pid.ca_system_id,
+            kind: ca_pid.kind,
+            data: data.to_vec(),
+        }))
+    }
+
+    fn finish_eit<'a>(mut self, data: &[u8]) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let eit = Eit::parse(&mut reader)?;
+        self.finish_substitute_data(PsiData::Eit(eit))
+    }
+
+    fn finish_psip<'a>(mut self, data: &[u8], table_id: u8) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        match parse_psip(table_id, &mut reader)? {
+            Some(psip) => self.finish_substitute_data(psip),
+            None => self.finish_keep_raw_data(data),
+        }
+    }
+
+    fn finish_tdt<'a>(mut self, data: &[u8]) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let time = read_utc_time(&mut reader)?;
+        self.finish_substitute_data(PsiData::Tdt(time))
+    }
+
+    fn finish_tot<'a>(mut self, data: &[u8]) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let tot = Tot::parse(&mut reader)?;
+        self.finish_substitute_data(PsiData::Tot(tot))
+    }
+
+    fn finish_dsmcc<'a>(mut self, data: &[u8], table_id: u8) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let section = DsmccSection::parse(table_id, &mut reader)?;
+        self.finish_substitute_data(PsiData::Dsmcc(section))
+    }
+
+    fn finish_nit<'a>(mut self, data: &[u8]) -> Result<Payload<'a, D>, D> {
+        let mut reader = SliceReader::new(data);
+        let nit = Nit::parse(&mut reader)?;
+        self.finish_substitute_data(PsiData::Nit(nit))
+    }
+
+    /// Validates and parses the complete section data, either assembled from several packets or
+    /// borrowed from the packet containing the whole section.
+    fn finish_data<'a>(
+        mut self,
+        mut data: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
         /* Validate using CRC32 */
-        let len_minus_crc = self.data.len() - 4;
-        let mut hasher = self.hasher.take().expect("PSI hasher not set");
-        hasher.update(&self.data[..len_minus_crc]);
-        let actual_hash = hasher.finalize();
-        let expected_hash = SliceReader::new(&self.data[len_minus_crc..]).read_be_u32()?;
-        if expected_hash != actual_hash {
-            warn!("PSI hash mismatch for PID: {:x}", pid);
-            return Err(Error {
-                location: 0,
-                details: ErrorDetails::<D>::PsiCrcMismatch,
-            });
-        }
-        self.data.truncate(len_minus_crc);
+        if let Some(mut hasher) = self.hasher.take() {
+            let len_minus_crc = data.len() - 4;
+            hasher.update(&data[..len_minus_crc]);
+            let actual_hash = hasher.finalize();
+            let expected_hash = SliceReader::new(&data[len_minus_crc..]).read_be_u32()?;
+            if expected_hash != actual_hash {
+                warn!("PSI hash mismatch for PID: {:x}", pid);
+                return Err(Error {
+                    location: 0,
+                    details: ErrorDetails::<D>::PsiCrcMismatch,
+                });
+            }
+            data = &data[..len_minus_crc];
+        }
 
         /* Process table based on known type */
-        if self.header.private_bit() {
+        let table_id = self.header.table_id();
+        if pid == NIT_PID && (table_id == NIT_ACTUAL_TABLE_ID || table_id == NIT_OTHER_TABLE_ID) {
+            /* DVB NIT (sets the private bit as reserved_future_use) */
+            self.finish_nit(data)
+        } else if pid == EIT_PID && is_eit_table_id(table_id) {
+            /* DVB EIT */
+            self.finish_eit(data)
+        } else if pid == TDT_PID && table_id == TDT_TABLE_ID {
+            /* DVB TDT */
+            self.finish_tdt(data)
+        } else if pid == TDT_PID && table_id == TOT_TABLE_ID {
+            /* DVB TOT */
+            self.finish_tot(data)
+        } else if pid == PSIP_PID {
+            /* ATSC PSIP */
+            self.finish_psip(data, table_id)
+        } else if is_dsmcc_table_id(table_id) && self.table_syntax.is_some() {
+            /* DSM-CC (sets the private bit to the complement of the section syntax indicator) */
+            self.finish_dsmcc(data, table_id)
+        } else if let Some(ca_pid) = parser.ca_pid(pid) {
+            /* ECM/EMM (private sections of the conditional access system) */
+            self.finish_ca_message(data, ca_pid)
+        } else if self.header.private_bit() {
             /* Private tables are not defined in ISO/IEC 13818-1 */
-            self.finish_keep_raw_data()
-        } else if pid == 0 && self.header.table_id() == 0 {
+            self.finish_keep_raw_data(data)
+        } else if pid == 0 && table_id == 0 {
             /* PAT */
-            self.finish_pat(parser)
+            if let Some(table_syntax) = self.table_syntax.as_ref() {
+                parser.track_table_version(pid, table_id, table_syntax);
+            }
+            self.finish_pat(data, parser)
         } else if parser.known_pmt_pids.contains(&pid) {
             /* PMT */
-            self.finish_pmt(parser)
+            if let Some(table_syntax) = self.table_syntax.as_ref() {
+                parser.track_table_version(pid, table_id, table_syntax);
+            }
+            self.finish_pmt(data, pid, parser)
+        } else if pid == CAT_PID && table_id == CAT_TABLE_ID {
+            /* CAT */
+            self.finish_cat(data, parser)
         } else {
-            /* Unhandled table type (CAT?); keep data raw */
-            self.finish_keep_raw_data()
+            /* Unhandled table type; keep data raw */
+            self.finish_keep_raw_data(data)
         }
     }
+}
+
+impl<D: AppDetails> PayloadUnitObject<D> for PsiBuilder<D> {
+    fn extend_from_slice(&mut self, slice: &[u8]) {
+        self.data.extend_from_slice(slice);
+    }
+
+    fn finish<'a>(mut self, pid: u16, parser: &mut MpegTsParser<D>) -> Result<Payload<'a, D>, D> {
+        let data = core::mem::take(&mut self.data);
+        let result = self.finish_data(&data, pid, parser);
+        parser.buffer_pool.recycle(data);
+        result
+    }
+
+    fn finish_from_slice<'a>(
+        self,
+        slice: &[u8],
+        pid: u16,
+        parser: &mut MpegTsParser<D>,
+    ) -> Result<Payload<'a, D>, D> {
+        self.finish_data(slice, pid, parser)
+    }
 
     fn pending<'a>(&self) -> Result<Payload<'a, D>, D> {
         Ok(Payload::PsiPending)
@@ -252,6 +856,79 @@ impl<D: AppDetails> PayloadUnitObject<D> for PsiBuilder<D> {
 }
 
 impl<D: AppDetails> MpegTsParser<D> {
+    /// Replaces the known elementary streams of a program with those of a new PMT.
+    fn update_es_streams(&mut self, program_num: u16, pmt: &Pmt) {
+        self.es_streams
+            .retain(|_, info| info.program_num != program_num);
+        for es_info in pmt.es_infos.iter() {
+            self.es_streams.insert(
+                es_info.header.elementary_pid(),
+                EsStreamInfo {
+                    program_num,
+                    stream_type: es_info.header.stream_type().into(),
+                    es_descriptors: es_info.es_descriptors.clone(),
+                },
+            );
+        }
+    }
+
+    /// Replaces the known programs with those of a new PAT, dropping the PMTs of programs no
+    /// longer listed.
+    fn update_programs(&mut self, entries: &[PatEntry]) {
+        let old_programs = core::mem::take(&mut self.programs);
+        for entry in entries.iter() {
+            /* Program 0 points to the network PID rather than a PMT */
+            if entry.program_num() != 0 {
+                self.programs
+                    .insert(entry.program_num(), entry.program_map_pid());
+            }
+        }
+        if !old_programs.is_empty() {
+            let diff = PatDiff::between(&old_programs, &self.programs);
+            if !diff.is_empty() {
+                self.pending_events
+                    .push(ParserEvent::ProgramsChanged { diff });
+            }
+        }
+        let programs = &self.programs;
+        self.pmts
+            .retain(|program_num, _| programs.contains_key(program_num));
+        self.retain_ecm_pids();
+    }
+
+    /// Programs of the most recent PAT as (program number, PMT PID) pairs, ordered by program
+    /// number.
+    pub fn programs(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.programs
+            .iter()
+            .map(|(&program_num, &pmt_pid)| (program_num, pmt_pid))
+    }
+
+    /// PMT PID of a program listed in the most recent PAT.
+    pub fn pmt_pid(&self, program_num: u16) -> Option<u16> {
+        self.programs.get(&program_num).copied()
+    }
+
+    /// Most recent current PMT of a program.
+    pub fn pmt(&self, program_num: u16) -> Option<&Pmt> {
+        self.pmts.get(&program_num)
+    }
+
+    /// Elementary stream information of `pid` from the most recent PMT listing it.
+    pub fn es_stream_info(&self, pid: u16) -> Option<&EsStreamInfo> {
+        self.es_streams.get(&pid)
+    }
+
+    /// Buffer to assemble a section in; sections contained in the current packet are parsed in
+    /// place and need none.
+    fn section_buffer(&mut self, table_length: usize, reader: &SliceReader<D>) -> Vec<u8> {
+        if table_length > reader.remaining_len() {
+            self.buffer_pool.take(table_length)
+        } else {
+            Vec::new()
+        }
+    }
+
     pub(crate) fn start_psi<'a>(
         &mut self,
         pid: u16,
@@ -278,7 +955,12 @@ impl<D: AppDetails> MpegTsParser<D> {
         let psi_header = PsiHeader::from_bytes(*psi_header_bytes);
         let section_length = psi_header.section_length();
 
-        if section_length > 0 {
+        if psi_header.section_syntax_indicator() && section_length > 0 {
+            if section_length < 9 {
+                /* Must have length to read at least the table syntax and CRC32 */
+                warn!("Insufficient table length");
+                return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
+            }
             if reader.remaining_len() < 5 {
                 warn!("Short read of PSI table syntax");
                 return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
@@ -288,20 +970,421 @@ impl<D: AppDetails> MpegTsParser<D> {
             let psi_table_syntax = PsiTableSyntax::from_bytes(*psi_table_syntax_bytes);
 
             let table_length = (section_length - 5) as usize;
-            if table_length < 4 {
-                /* Must have length to read at least the CRC32 */
-                warn!("Insufficient table length");
-                return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
-            }
-
+            let buffer = self.section_buffer(table_length, reader);
+            self.start_payload_unit(
+                PsiBuilder::new(buffer, psi_header, Some(psi_table_syntax), Some(hasher)),
+                Some(table_length),
+                pid,
+                reader,
+            )
+        } else if section_length > 0 {
+            /* Short sections have no CRC, except for the DVB TOT */
+            let hasher = if psi_header.table_id() == TOT_TABLE_ID {
+                if section_length < 4 {
+                    warn!("Insufficient table length");
+                    return Err(reader.make_error(ErrorDetails::<D>::BadPsiHeader));
+                }
+                Some(hasher)
+            } else {
+                None
+            };
+            let table_length = section_length as usize;
+            let buffer = self.section_buffer(table_length, reader);
             self.start_payload_unit(
-                PsiBuilder::new(table_length, psi_header, Some(psi_table_syntax), hasher),
-                table_length,
+                PsiBuilder::new(buffer, psi_header, None, hasher),
+                Some(table_length),
                 pid,
                 reader,
             )
         } else {
-            PsiBuilder::new(0, psi_header, None, hasher).finish(pid, self)
+            PsiBuilder::new(Vec::new(), psi_header, None, None).finish(pid, self)
         }
     }
 }
+
+/// Identifies the sections of one table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct TableKey {
+    pid: u16,
+    table_id: u8,
+    table_id_extension: u16,
+}
+
+/// Sections collected for one version of a table.
+#[derive(Debug)]
+struct PendingTable {
+    version: u8,
+    last_section_num: u8,
+    sections: BTreeMap<u8, Psi>,
+    /* Section numbers that are never transmitted (unused sections of EIT segments) */
+    skipped: BTreeSet<u8>,
+}
+
+impl PendingTable {
+    fn is_complete(&self) -> bool {
+        (0..=self.last_section_num)
+            .all(|num| self.sections.contains_key(&num) || self.skipped.contains(&num))
+    }
+}
+
+/// All sections of one version of a table.
+#[derive(Debug)]
+pub struct CompleteTable {
+    /// PID carrying the table.
+    pub pid: u16,
+    /// Table ID.
+    pub table_id: u8,
+    /// Table ID extension (e.g. the program number of a PMT).
+    pub table_id_extension: u16,
+    /// Version of the table.
+    pub version: u8,
+    /// Sections ordered by section number.
+    pub sections: Vec<Psi>,
+}
+
+/// Groups PSI sections of tables spanning several sections.
+///
+/// Sections are grouped by PID, table ID, and table ID extension. A [`CompleteTable`] is emitted
+/// once all sections `0..=last_section_num` of a version have been added; repeated sections of
+/// an emitted version are ignored until the version changes. Sections of EIT segments beyond
+/// `segment_last_section_num` are not waited for. Sections without table syntax complete
+/// immediately, and sections not yet applicable (`current_next_indicator` clear) are ignored.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Payload, SectionCollector};
+///
+/// fn collect(parser: &mut MpegTsParser<DefaultAppDetails>, packets: &[[u8; 188]]) {
+///     let mut collector = SectionCollector::default();
+///     for packet in packets.iter() {
+///         let packet = parser.parse(packet).expect("Parse Error!");
+///         let pid = packet.header.pid();
+///         if let Some(Payload::Psi(psi)) = packet.payload {
+///             if let Some(table) = collector.add(pid, psi) {
+///                 println!("Table {:x} v{} complete", table.table_id, table.version);
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct SectionCollector {
+    pending: HashMap<TableKey, PendingTable>,
+    complete_versions: HashMap<TableKey, u8>,
+}
+
+impl SectionCollector {
+    /// Adds a section received on `pid`. Returns the table once all of its sections are added.
+    pub fn add(&mut self, pid: u16, psi: Psi) -> Option<CompleteTable> {
+        let table_id = psi.header.table_id();
+        let table_syntax = match psi.table_syntax {
+            Some(table_syntax) => table_syntax,
+            None => {
+                return Some(CompleteTable {
+                    pid,
+                    table_id,
+                    table_id_extension: 0,
+                    version: 0,
+                    sections: vec![psi],
+                })
+            }
+        };
+        if !table_syntax.current_next_indicator() {
+            return None;
+        }
+        let key = TableKey {
+            pid,
+            table_id,
+            table_id_extension: table_syntax.table_id_extension(),
+        };
+        let version = table_syntax.version();
+        if self.complete_versions.get(&key) == Some(&version) {
+            return None;
+        }
+
+        let pending = self.pending.entry(key).or_insert_with(|| PendingTable {
+            version,
+            last_section_num: table_syntax.last_section_num(),
+            sections: BTreeMap::new(),
+            skipped: BTreeSet::new(),
+        });
+        if pending.version != version {
+            *pending = PendingTable {
+                version,
+                last_section_num: table_syntax.last_section_num(),
+                sections: BTreeMap::new(),
+                skipped: BTreeSet::new(),
+            };
+        }
+        let section_num = table_syntax.section_num();
+        if let PsiData::Eit(eit) = &psi.data {
+            let segment_end = section_num | 0x7;
+            pending
+                .skipped
+                .extend(eit.segment_last_section_num.saturating_add(1)..=segment_end);
+        }
+        pending.sections.insert(section_num, psi);
+        if !pending.is_complete() {
+            return None;
+        }
+
+        let pending = self.pending.remove(&key)?;
+        self.complete_versions.insert(key, version);
+        Some(CompleteTable {
+            pid,
+            table_id,
+            table_id_extension: key.table_id_extension,
+            version,
+            sections: pending.sections.into_values().collect(),
+        })
+    }
+
+    /// Discards all collected sections and completed versions.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.complete_versions.clear();
+    }
+}
+
+#[test]
+fn test_psi_roundtrip() {
+    use crate::{DefaultAppDetails, MpegTsWriter};
+    use core::convert::TryInto;
+
+    let pat = PatBuilder::new(1).program(1, 0x100).build();
+    let descriptor = Descriptor {
+        tag: 0x05,
+        data: SmallVec::from_slice(b"HDMV"),
+    };
+    let pmt = PmtBuilder::new(1, 0x1011)
+        .version(7)
+        .program_descriptor(descriptor.clone())
+        .stream(0x1b, 0x1011, None)
+        .stream(0x90, 0x1200, Some(descriptor))
+        .build();
+    let section = pmt.to_section();
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, 0x100, &pmt.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    parser.parse(out[..188].try_into().unwrap()).unwrap();
+    match parser
+        .parse(out[188..].try_into().unwrap())
+        .unwrap()
+        .payload
+    {
+        Some(Payload::Psi(psi)) => {
+            assert!(matches!(psi.data, PsiData::Pmt(_)));
+            assert_eq!(psi.to_section(), section);
+        }
+        _ => panic!("PMT not parsed"),
+    }
+    assert_eq!(parser.es_stream_info(0x1200).unwrap().stream_type, 0x90);
+}
+
+#[test]
+fn test_section_collector() {
+    let section = |section_num, version| {
+        let mut pat = PatBuilder::new(1)
+            .version(version)
+            .program(section_num as u16 + 1, 0x100)
+            .build();
+        pat.table_syntax = pat.table_syntax.map(|table_syntax| {
+            table_syntax
+                .with_section_num(section_num)
+                .with_last_section_num(2)
+        });
+        pat
+    };
+
+    let mut collector = SectionCollector::default();
+    assert!(collector.add(0, section(1, 0)).is_none());
+    assert!(collector.add(0, section(0, 0)).is_none());
+    /* Version change discards the collected sections */
+    assert!(collector.add(0, section(2, 1)).is_none());
+    assert!(collector.add(0, section(0, 1)).is_none());
+    let table = collector.add(0, section(1, 1)).unwrap();
+    assert_eq!(table.version, 1);
+    let programs: Vec<u16> = table
+        .sections
+        .iter()
+        .map(|psi| match &psi.data {
+            PsiData::Pat(entries) => entries[0].program_num(),
+            _ => panic!("PAT not collected"),
+        })
+        .collect();
+    assert_eq!(programs, [1, 2, 3]);
+    /* Repeated sections of a complete version are ignored */
+    assert!(collector.add(0, section(0, 1)).is_none());
+}
+
+#[test]
+fn test_table_version_events() {
+    use crate::{DefaultAppDetails, MpegTsWriter, ParserEvent};
+    use core::convert::TryInto;
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    for version in [0, 0, 1].iter() {
+        let pat = PatBuilder::new(1)
+            .version(*version)
+            .program(1, 0x100)
+            .build();
+        writer
+            .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+            .unwrap();
+    }
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let events: Vec<Vec<ParserEvent>> = out
+        .chunks(188)
+        .map(|packet| parser.parse(packet.try_into().unwrap()).unwrap().events)
+        .collect();
+    assert!(matches!(
+        events[0][..],
+        [ParserEvent::TableVersionChanged {
+            pid: 0,
+            previous_version: None,
+            version: 0,
+            current_next_indicator: true,
+            ..
+        }]
+    ));
+    assert!(events[1].is_empty());
+    assert!(matches!(
+        events[2][..],
+        [ParserEvent::TableVersionChanged {
+            previous_version: Some(0),
+            version: 1,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn test_program_map() {
+    use crate::{DefaultAppDetails, MpegTsWriter};
+    use core::convert::TryInto;
+
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    let pat = PatBuilder::new(1)
+        .program(0, 0x10)
+        .program(2, 0x200)
+        .program(1, 0x100)
+        .build();
+    let pmt = PmtBuilder::new(1, 0x1011)
+        .stream(0x1b, 0x1011, None)
+        .build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    writer
+        .write_unit(&mut out, 0x100, &pmt.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    for packet in out.chunks(188) {
+        parser.parse(packet.try_into().unwrap()).unwrap();
+    }
+    assert_eq!(
+        parser.programs().collect::<Vec<_>>(),
+        [(1, 0x100), (2, 0x200)]
+    );
+    assert_eq!(parser.pmt_pid(2), Some(0x200));
+    assert_eq!(parser.pmt(1).unwrap().header.pcr_pid(), 0x1011);
+    assert!(parser.pmt(2).is_none());
+
+    /* Programs dropped from the PAT lose their PMT */
+    out.clear();
+    let pat = PatBuilder::new(1).version(1).program(2, 0x200).build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    parser.parse(out[..].try_into().unwrap()).unwrap();
+    assert_eq!(parser.programs().collect::<Vec<_>>(), [(2, 0x200)]);
+    assert!(parser.pmt(1).is_none());
+}
+
+#[test]
+fn test_table_diffs() {
+    use crate::{DefaultAppDetails, MpegTsWriter, ParserEvent, PmtDiff};
+    use core::convert::TryInto;
+
+    let language = Descriptor::new(0x0a, b"eng\0");
+    let pmts = [
+        PmtBuilder::new(1, 0x1011)
+            .stream(0x1b, 0x1011, None)
+            .stream(0x81, 0x1100, None)
+            .build(),
+        PmtBuilder::new(1, 0x1012)
+            .version(1)
+            .stream(0x24, 0x1011, None)
+            .stream(0x81, 0x1100, Some(language.clone()))
+            .stream(0x90, 0x1200, None)
+            .build(),
+    ];
+    let mut writer = MpegTsWriter::default();
+    let mut out = Vec::new();
+    let pat = PatBuilder::new(1).program(1, 0x100).build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+    for pmt in pmts.iter() {
+        writer
+            .write_unit(&mut out, 0x100, &pmt.to_payload_unit(), None)
+            .unwrap();
+    }
+    let pat = PatBuilder::new(1)
+        .version(1)
+        .program(1, 0x101)
+        .program(2, 0x200)
+        .build();
+    writer
+        .write_unit(&mut out, 0, &pat.to_payload_unit(), None)
+        .unwrap();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let events: Vec<Vec<ParserEvent>> = out
+        .chunks(188)
+        .map(|packet| parser.parse(packet.try_into().unwrap()).unwrap().events)
+        .collect();
+    let pmt_diffs: Vec<&PmtDiff> = events[1..3]
+        .iter()
+        .flatten()
+        .filter_map(|event| match event {
+            ParserEvent::PmtChanged { diff, .. } => Some(diff),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(pmt_diffs[0].streams_added, [(0x1011, 0x1b), (0x1100, 0x81)]);
+    assert_eq!(pmt_diffs[1].pcr_pid, Some((0x1011, 0x1012)));
+    assert_eq!(pmt_diffs[1].streams_added, [(0x1200, 0x90)]);
+    assert!(pmt_diffs[1].streams_removed.is_empty());
+    assert_eq!(pmt_diffs[1].stream_type_changes, [(0x1011, 0x1b, 0x24)]);
+    assert_eq!(pmt_diffs[1].es_descriptors.len(), 1);
+    assert_eq!(pmt_diffs[1].es_descriptors[0].0, 0x1100);
+    assert_eq!(pmt_diffs[1].es_descriptors[0].1.added, [language]);
+
+    /* The first PAT reports no program changes */
+    assert!(!events[0]
+        .iter()
+        .any(|event| matches!(event, ParserEvent::ProgramsChanged { .. })));
+    let pat_diff = events[3]
+        .iter()
+        .find_map(|event| match event {
+            ParserEvent::ProgramsChanged { diff } => Some(diff),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(pat_diff.programs_added, [(2, 0x200)]);
+    assert_eq!(pat_diff.pmt_pid_changes, [(1, 0x100, 0x101)]);
+}
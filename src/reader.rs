@@ -0,0 +1,263 @@
+use super::{
+    AdaptationField, AppDetails, DefaultAppDetails, Error, ErrorDetails, FramedPacket, Framing,
+    MpegTsParser, Packet, PacketHeader, ParserEvent, Payload, Pes, Psi, Result, Warning,
+};
+use crate::prelude::*;
+use smallvec::SmallVec;
+#[cfg(feature = "std")]
+use std::io::{ErrorKind, Read};
+
+/// Number of packets buffered by [`MpegTsReader`] per read call.
+const BUFFERED_PACKETS: usize = 64;
+
+/// Owned counterpart of [`Payload`].
+#[derive(Debug)]
+pub enum OwnedPayload<D> {
+    /// Unhandled payload type; parsing is left to the application.
+    Raw(Vec<u8>),
+    /// PSI payload unit is incomplete.
+    PsiPending,
+    /// Complete parsed PSI payload.
+    Psi(Psi),
+    /// PES payload unit is incomplete.
+    PesPending,
+    /// Complete parsed PES payload.
+    Pes(Pes<D>),
+}
+
+impl<'a, D: AppDetails> From<Payload<'a, D>> for OwnedPayload<D> {
+    fn from(payload: Payload<'a, D>) -> Self {
+        match payload {
+            Payload::Raw(mut reader) => {
+                OwnedPayload::Raw(reader.read_to_end().unwrap_or_default().to_vec())
+            }
+            Payload::PsiPending => OwnedPayload::PsiPending,
+            Payload::Psi(psi) => OwnedPayload::Psi(psi),
+            Payload::PesPending => OwnedPayload::PesPending,
+            Payload::Pes(pes) => OwnedPayload::Pes(pes),
+        }
+    }
+}
+
+/// Owned counterpart of [`Packet`] that does not borrow the packet data.
+#[derive(Debug)]
+pub struct OwnedPacket<D: AppDetails> {
+    /// Uninterpreted prefix bytes of framed packets (e.g. the BDAV header).
+    pub prefix: SmallVec<[u8; 4]>,
+    /// Packet link-layer header.
+    pub header: PacketHeader,
+    /// Optional adaptation field metadata.
+    pub adaptation_field: Option<AdaptationField>,
+    /// Optional payload data.
+    pub payload: Option<OwnedPayload<D>>,
+    /// Byte offset of the payload within the 188-byte packet, if the packet carries payload.
+    pub payload_offset: Option<usize>,
+    /// Number of stuffing bytes at the end of the adaptation field.
+    pub stuffing_len: usize,
+    /// Set if continuity checking found the packet to repeat the previous packet of its PID.
+    pub duplicate: bool,
+    /// Stream structure changes detected while parsing this packet.
+    pub events: Vec<ParserEvent>,
+    /// Soft errors recovered from while parsing this packet.
+    pub warnings: Vec<Warning>,
+    /// Error the payload failed to parse with in lenient mode.
+    pub payload_error: Option<Error<D>>,
+    /// Unbounded PES unit of the PID completed by the unit start in this packet.
+    pub ended_unit: Option<Pes<D>>,
+}
+
+impl<'a, D: AppDetails> From<Packet<'a, D>> for OwnedPacket<D> {
+    fn from(packet: Packet<'a, D>) -> Self {
+        Self {
+            prefix: SmallVec::new(),
+            header: packet.header,
+            adaptation_field: packet.adaptation_field,
+            payload: packet.payload.map(OwnedPayload::from),
+            payload_offset: packet.payload_offset,
+            stuffing_len: packet.stuffing_len,
+            duplicate: packet.duplicate,
+            events: packet.events,
+            warnings: packet.warnings,
+            payload_error: packet.payload_error,
+            ended_unit: packet.ended_unit,
+        }
+    }
+}
+
+impl<'a, D: AppDetails> From<FramedPacket<'a, D>> for OwnedPacket<D> {
+    fn from(framed: FramedPacket<'a, D>) -> Self {
+        let mut packet = OwnedPacket::from(framed.packet);
+        packet.prefix.extend_from_slice(framed.prefix);
+        packet
+    }
+}
+
+/// Iterator parsing packets from a [`Read`] stream.
+///
+/// Reads are buffered internally and short reads are retried until a whole packet is available.
+/// A trailing partial packet at the end of the stream is reported once as
+/// [`ErrorDetails::BadPacketLength`]. Read failures are reported as [`ErrorDetails::IoError`]
+/// and end the iteration.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, MpegTsReader, MpegTsWriter, OwnedPayload};
+/// use std::io::Cursor;
+///
+/// let mut data = Vec::new();
+/// MpegTsWriter::default().write_unit(&mut data, 0x100, &[0; 400], None)?;
+///
+/// let parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let reader = MpegTsReader::new(Cursor::new(data), parser);
+/// let mut count = 0;
+/// for packet in reader {
+///     let packet = packet.expect("Parse Error!");
+///     assert!(matches!(packet.payload, Some(OwnedPayload::Raw(_))));
+///     count += 1;
+/// }
+/// assert_eq!(count, 3);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub struct MpegTsReader<R, D: AppDetails = DefaultAppDetails> {
+    reader: R,
+    parser: MpegTsParser<D>,
+    framing: Framing,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, D: AppDetails> MpegTsReader<R, D> {
+    /// Creates a reader of plain 188-byte packets.
+    pub fn new(reader: R, parser: MpegTsParser<D>) -> Self {
+        Self::with_framing(reader, parser, Framing::PLAIN)
+    }
+
+    /// Creates a reader of packets stored with the given [`Framing`].
+    pub fn with_framing(reader: R, parser: MpegTsParser<D>, framing: Framing) -> Self {
+        Self {
+            reader,
+            parser,
+            framing,
+            buf: vec![0; framing.packet_size() * BUFFERED_PACKETS],
+            start: 0,
+            end: 0,
+            done: false,
+        }
+    }
+
+    /// Underlying parser.
+    pub fn parser(&self) -> &MpegTsParser<D> {
+        &self.parser
+    }
+
+    /// Underlying parser.
+    pub fn parser_mut(&mut self) -> &mut MpegTsParser<D> {
+        &mut self.parser
+    }
+
+    /// Returns the underlying stream. Buffered data that was not yet parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads until one whole packet is buffered. Returns `false` at the end of the stream.
+    fn fill(&mut self) -> std::io::Result<bool> {
+        let packet_size = self.framing.packet_size();
+        if self.end - self.start >= packet_size {
+            return Ok(true);
+        }
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        while self.end < packet_size {
+            match self.reader.read(&mut self.buf[self.end..]) {
+                Ok(0) => return Ok(false),
+                Ok(len) => self.end += len,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, D: AppDetails> Iterator for MpegTsReader<R, D> {
+    type Item = Result<OwnedPacket<D>, D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.fill() {
+            Ok(true) => {
+                let packet_size = self.framing.packet_size();
+                let data = &self.buf[self.start..self.start + packet_size];
+                self.start += packet_size;
+                Some(
+                    self.parser
+                        .parse_framed(self.framing, data)
+                        .map(OwnedPacket::from),
+                )
+            }
+            Ok(false) => {
+                self.done = true;
+                let remaining = self.end - self.start;
+                if remaining > 0 {
+                    Some(Err(Error {
+                        location: 0,
+                        details: ErrorDetails::BadPacketLength(remaining),
+                    }))
+                } else {
+                    None
+                }
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(Error {
+                    location: 0,
+                    details: ErrorDetails::IoError(e),
+                }))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_short_reads() {
+    use crate::MpegTsWriter;
+
+    /// Stream returning at most 100 bytes per read.
+    struct Trickle(std::io::Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(100);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    let mut data = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+    let packet_count = data.len() / 188;
+    data.extend_from_slice(&[0x47; 10]);
+
+    let reader = MpegTsReader::<_, DefaultAppDetails>::new(
+        Trickle(std::io::Cursor::new(data)),
+        MpegTsParser::default(),
+    );
+    let results: Vec<_> = reader.collect();
+    assert_eq!(results.len(), packet_count + 1);
+    assert!(results[..packet_count].iter().all(|r| r.is_ok()));
+    assert!(matches!(
+        results[packet_count].as_ref().unwrap_err().details,
+        ErrorDetails::BadPacketLength(10)
+    ));
+}
@@ -0,0 +1,192 @@
+use super::{parse_pcr, parse_timestamp, write_pcr, write_timestamp, Timestamp90k};
+use core::convert::TryInto;
+use hashbrown::HashSet;
+use log::warn;
+
+/// Modulus of 33-bit 90kHz timestamps.
+const TIMESTAMP_WRAP: u64 = 1 << 33;
+/// Modulus of 30-bit 27MHz BDAV arrival timestamps.
+const ATC_WRAP: u64 = 1 << 30;
+
+/// Rewrites PCR, PTS, DTS (and BDAV arrival timestamps) of a stream by a constant offset.
+///
+/// Used to concatenate streams: timestamps of the second stream are shifted so they continue
+/// from the end of the first stream. All arithmetic wraps at 33 bits (30 bits for BDAV arrival
+/// timestamps).
+///
+/// Optionally, the `discontinuity_indicator` is set on the first packet of each PID that carries
+/// an adaptation field, signaling the splice to decoders. Packets without an adaptation field are
+/// never resized to insert one.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::TimestampRebaser;
+/// /* First stream ends at 10s, second starts at 1s */
+/// let rebaser = TimestampRebaser::continuing(900000, 90000);
+/// assert_eq!(rebaser.rebase_timestamp(90000), 900000);
+/// /* Wraps at 33 bits */
+/// let rebaser = TimestampRebaser::new((1 << 33) - 10);
+/// assert_eq!(rebaser.rebase_timestamp(20), 10);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimestampRebaser {
+    offset: u64,
+    atc_offset: u32,
+    mark_discontinuity: bool,
+    flagged_pids: HashSet<u16>,
+}
+
+impl TimestampRebaser {
+    /// Creates a rebaser adding `offset` 90kHz ticks to every timestamp.
+    ///
+    /// BDAV arrival timestamps are offset by the equivalent number of 27MHz ticks.
+    pub fn new(offset: u64) -> Self {
+        let offset = offset % TIMESTAMP_WRAP;
+        Self {
+            offset,
+            atc_offset: (offset * 300 % ATC_WRAP) as u32,
+            mark_discontinuity: true,
+            flagged_pids: HashSet::new(),
+        }
+    }
+
+    /// Creates a rebaser mapping `second_start` of the second stream to `first_end` of the
+    /// first stream (both in 90kHz ticks).
+    ///
+    /// `first_end` is typically the last PTS of the first stream plus one frame duration.
+    pub fn continuing(first_end: u64, second_start: u64) -> Self {
+        Self::new((first_end + TIMESTAMP_WRAP - second_start % TIMESTAMP_WRAP) % TIMESTAMP_WRAP)
+    }
+
+    /// Offset added to 90kHz timestamps.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Overrides the offset added to BDAV arrival timestamps (27MHz ticks).
+    pub fn set_atc_offset(&mut self, atc_offset: u32) {
+        self.atc_offset = atc_offset % ATC_WRAP as u32;
+    }
+
+    /// Enables or disables setting the discontinuity indicator (enabled by default).
+    pub fn set_mark_discontinuity(&mut self, mark_discontinuity: bool) {
+        self.mark_discontinuity = mark_discontinuity;
+    }
+
+    /// Applies the offset to one 33-bit 90kHz timestamp.
+    pub fn rebase_timestamp(&self, ts: u64) -> u64 {
+        (ts + self.offset) % TIMESTAMP_WRAP
+    }
+
+    /// Rewrites the timestamps of one 188-byte MPEG-TS packet in place.
+    pub fn rebase(&mut self, packet: &mut [u8; 188]) {
+        if packet[0] != 0x47 {
+            warn!("Not rebasing packet without sync");
+            return;
+        }
+        let pid = u16::from_be_bytes([packet[1], packet[2]]) & 0x1fff;
+        let pusi = packet[1] & 0x40 != 0;
+        let has_adaptation_field = packet[3] & 0x20 != 0;
+        let has_payload = packet[3] & 0x10 != 0;
+        if pid == 0x1fff {
+            return;
+        }
+
+        let mut payload_start = 4;
+        if has_adaptation_field {
+            let length = packet[4] as usize;
+            if length > 183 {
+                warn!("Bad adaptation field length");
+                return;
+            }
+            payload_start += 1 + length;
+            if length > 0 {
+                self.rebase_adaptation_field(pid, &mut packet[5..5 + length]);
+            }
+        }
+
+        if has_payload && pusi {
+            self.rebase_pes_header(&mut packet[payload_start..]);
+        }
+    }
+
+    /// Rewrites the arrival timestamp and MPEG-TS timestamps of one 192-byte BDAV packet in
+    /// place.
+    pub fn rebase_bdav(&mut self, packet: &mut [u8; 192]) {
+        let header = u32::from_be_bytes(packet[..4].try_into().unwrap());
+        let atc = (header & 0x3fff_ffff) as u64;
+        let atc = (atc + self.atc_offset as u64) % ATC_WRAP;
+        packet[..4].copy_from_slice(&((header & 0xc000_0000) | atc as u32).to_be_bytes());
+        self.rebase((&mut packet[4..]).try_into().unwrap());
+    }
+
+    fn rebase_adaptation_field(&mut self, pid: u16, field: &mut [u8]) {
+        if self.mark_discontinuity && self.flagged_pids.insert(pid) {
+            field[0] |= 0x80;
+        }
+        if field[0] & 0x10 != 0 && field.len() >= 7 {
+            let pcr_bytes: &mut [u8; 6] = (&mut field[1..7]).try_into().unwrap();
+            let mut pcr = parse_pcr(pcr_bytes);
+            pcr.base = Timestamp90k(self.rebase_timestamp(pcr.base.0));
+            write_pcr(pcr_bytes, &pcr);
+        }
+    }
+
+    fn rebase_pes_header(&self, payload: &mut [u8]) {
+        if payload.len() < 9 || payload[..3] != [0, 0, 1] {
+            return;
+        }
+        /* Streams without the optional header */
+        if matches!(
+            payload[3],
+            0xBC | 0xBE | 0xBF | 0xF0 | 0xF1 | 0xF2 | 0xF8 | 0xFF
+        ) {
+            return;
+        }
+        let pts_dts_flags = payload[7] >> 6;
+        let header_end = 9 + payload[8] as usize;
+        for (flag, start) in [(0x2, 9), (0x1, 14)] {
+            if pts_dts_flags & flag == 0 || start + 5 > header_end.min(payload.len()) {
+                continue;
+            }
+            let ts_bytes: &mut [u8; 5] = (&mut payload[start..start + 5]).try_into().unwrap();
+            let ts = self.rebase_timestamp(parse_timestamp(ts_bytes));
+            write_timestamp(ts_bytes, ts);
+        }
+    }
+}
+
+#[test]
+fn test_rebase_packet() {
+    let mut packet = [0xff_u8; 188];
+    /* PUSI on PID 0x100 with adaptation field (PCR) and payload */
+    packet[..4].copy_from_slice(&[0x47, 0x41, 0x00, 0x30]);
+    packet[4..12].copy_from_slice(&[7, 0x10, 0, 0, 0, 0, 0x7e, 0]);
+    /* PES header with PTS and DTS of 0 */
+    packet[12..21].copy_from_slice(&[0, 0, 1, 0xe0, 0, 0, 0x80, 0xc0, 10]);
+    packet[21..31].copy_from_slice(&[0x31, 0, 1, 0, 1, 0x11, 0, 1, 0, 1]);
+
+    let mut rebaser = TimestampRebaser::new((1 << 33) + 90000);
+    rebaser.rebase(&mut packet);
+    assert_eq!(packet[5], 0x90);
+    assert_eq!(
+        parse_pcr((&packet[6..12]).try_into().unwrap()).base,
+        Timestamp90k(90000)
+    );
+    assert_eq!(
+        parse_timestamp((&packet[21..26]).try_into().unwrap()),
+        90000
+    );
+    assert_eq!(
+        parse_timestamp((&packet[26..31]).try_into().unwrap()),
+        90000
+    );
+    assert_eq!(packet[21] & 0xf0, 0x30);
+    assert_eq!(packet[26] & 0xf0, 0x10);
+
+    /* Discontinuity is only flagged once per PID */
+    packet[5] = 0x10;
+    rebaser.rebase(&mut packet);
+    assert_eq!(packet[5], 0x10);
+}
@@ -0,0 +1,153 @@
+//! JSON reports of parsed packets and program structure, enabled by the `json` feature.
+//!
+//! The JSON produced here follows the field names of the [`Serialize`] implementations rather
+//! than [`Debug`] formatting, and [`SCHEMA_VERSION`] is bumped whenever the layout changes
+//! incompatibly.
+//!
+//! # Example
+//!
+//! ```
+//! use mpegts_io::testgen::TestStreamGenerator;
+//! use mpegts_io::{DefaultAppDetails, MpegTsParser};
+//! let mut gen = TestStreamGenerator::new(1, 0x100);
+//! gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+//! gen.write_pat().write_pmt();
+//!
+//! let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+//! for packet in gen.packets() {
+//!     println!("{}", parser.parse(packet).unwrap().to_json());
+//! }
+//! let report = parser.report().to_json();
+//! assert_eq!(report["programs"][0]["pcr_pid"], 0x1011);
+//! assert_eq!(report["programs"][0]["streams"][0]["codec"], "H264");
+//! ```
+
+use super::descriptor::KnownDescriptor;
+use super::{AppDetails, LanguageCode, MpegTsParser, Packet, StreamType};
+use core::fmt::Debug;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Version of the JSON layout produced by this module.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Elementary stream of a program.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamEntry {
+    /// Elementary PID.
+    pub pid: u16,
+    /// Stream type as listed in the PMT.
+    pub stream_type: u8,
+    /// Stream type decoded into a known codec.
+    pub codec: StreamType,
+    /// Languages of the stream from its ISO 639 language descriptor.
+    pub languages: Vec<LanguageCode>,
+}
+
+/// Program listed in the PAT.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramEntry {
+    /// Program number.
+    pub program_num: u16,
+    /// PID carrying the PMT.
+    pub pmt_pid: u16,
+    /// PID carrying the PCR, once the PMT is received.
+    pub pcr_pid: Option<u16>,
+    /// Elementary streams of the current PMT.
+    pub streams: Vec<StreamEntry>,
+}
+
+/// Packet counts of one PID, present if statistics are enabled.
+#[derive(Debug, Clone, Serialize)]
+pub struct PidEntry {
+    /// PID.
+    pub pid: u16,
+    /// Number of packets carrying a payload.
+    pub payload_packets: u64,
+    /// Number of payload packets with a scrambled payload.
+    pub scrambled_packets: u64,
+}
+
+/// Program structure and statistics of the stream parsed so far.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamReport {
+    /// [`SCHEMA_VERSION`] of the report.
+    pub schema_version: u32,
+    /// Number of packets parsed, if statistics are enabled.
+    pub packet_count: Option<u64>,
+    /// Programs ordered by program number.
+    pub programs: Vec<ProgramEntry>,
+    /// PIDs ordered by PID, if statistics are enabled.
+    pub pids: Vec<PidEntry>,
+}
+
+impl StreamReport {
+    /// Converts the report to a JSON value.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).expect("Report is representable as JSON")
+    }
+}
+
+impl<D: AppDetails> Packet<'_, D> {
+    /// Converts the packet to a JSON value. Data of PES units claimed by an [`AppDetails`]
+    /// parser is left out.
+    pub fn to_json(&self) -> Value {
+        let mut value = serde_json::to_value(self).expect("Packet is representable as JSON");
+        value["schema_version"] = SCHEMA_VERSION.into();
+        value
+    }
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Summarizes the programs and statistics gathered so far.
+    pub fn report(&self) -> StreamReport {
+        let programs = self
+            .programs()
+            .map(|(program_num, pmt_pid)| {
+                let pmt = self.pmt(program_num);
+                ProgramEntry {
+                    program_num,
+                    pmt_pid,
+                    pcr_pid: pmt.map(|pmt| pmt.header.pcr_pid()),
+                    streams: pmt.map_or_else(Vec::new, |pmt| {
+                        pmt.es_infos
+                            .iter()
+                            .map(|info| StreamEntry {
+                                pid: info.header.elementary_pid(),
+                                stream_type: info.header.stream_type().into(),
+                                codec: info.header.stream_type(),
+                                languages: info
+                                    .es_descriptors
+                                    .iter()
+                                    .filter_map(|descriptor| match descriptor.parse() {
+                                        KnownDescriptor::Iso639Language(entries) => Some(entries),
+                                        _ => None,
+                                    })
+                                    .flatten()
+                                    .map(|entry| entry.language)
+                                    .collect(),
+                            })
+                            .collect()
+                    }),
+                }
+            })
+            .collect();
+        let mut pids: Vec<_> = self
+            .stats()
+            .into_iter()
+            .flat_map(|stats| stats.pids())
+            .map(|(pid, stats)| PidEntry {
+                pid: *pid,
+                payload_packets: stats.payload_packets,
+                scrambled_packets: stats.scrambled_packets,
+            })
+            .collect();
+        pids.sort_unstable_by_key(|entry| entry.pid);
+        StreamReport {
+            schema_version: SCHEMA_VERSION,
+            packet_count: self.stats().map(|stats| stats.packet_count()),
+            programs,
+            pids,
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! [`Serialize`] implementations for types that cannot derive it, enabled by the `serde` feature.
+//!
+//! Bitfield structs are serialized as structs of their getter values. PES unit data is only
+//! serialized for units no [`AppDetails`] parser claimed.
+
+use super::atsc::VirtualChannelHeader;
+use super::bdav::mobj::MObjCmd;
+use super::bdav::pg::{PgsPaletteEntry, TgFontStyle, UoMask};
+use super::{
+    AdaptationFieldHeader, AppDetails, ElementaryStreamInfoHeader, Error, ErrorDetails,
+    LanguageCode, Packet, PacketHeader, PatEntry, Payload, Pes, PesHeader, PesOptionalHeader,
+    PmtHeader, PsiHeader, PsiTableSyntax, SliceReader,
+};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+macro_rules! serialize_bitfield {
+    ($($ty:ident { $($field:ident),* $(,)? })*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    let fields = [$(stringify!($field)),*];
+                    let mut s = serializer.serialize_struct(stringify!($ty), fields.len())?;
+                    $(s.serialize_field(stringify!($field), &self.$field())?;)*
+                    s.end()
+                }
+            }
+        )*
+    };
+}
+
+serialize_bitfield! {
+    PacketHeader {
+        sync_byte, tei, pusi, priority, pid, tsc, has_adaptation_field, has_payload,
+        continuity_counter,
+    }
+    AdaptationFieldHeader {
+        length, discontinuity, random_access, priority, has_pcr, has_opcr, has_splice_countdown,
+        has_transport_private_data, has_adaptation_field_extension,
+    }
+    PsiHeader { table_id, section_syntax_indicator, private_bit, section_length }
+    PsiTableSyntax {
+        table_id_extension, version, current_next_indicator, section_num, last_section_num,
+    }
+    PatEntry { program_num, program_map_pid }
+    PmtHeader { pcr_pid, program_info_length }
+    ElementaryStreamInfoHeader { stream_type, elementary_pid, es_info_length }
+    PesHeader { stream_id, packet_length }
+    PesOptionalHeader {
+        scrambling_control, priority, data_alignment_indicator, copyright, original, has_pts,
+        has_dts, escr, es_rate, dsm_trick_mode, has_additional_copy_info, has_crc, has_extension,
+        additional_header_length,
+    }
+    VirtualChannelHeader {
+        major_channel_number, minor_channel_number, modulation_mode, carrier_frequency,
+        channel_tsid, program_number, etm_location, access_controlled, hidden, path_select,
+        out_of_band, hide_guide, service_type, source_id,
+    }
+    UoMask {
+        menu_call, title_search, chapter_search, time_search, skip_to_next_point,
+        skip_to_prev_point, play_firstplay, stop, pause_on, pause_off, still_off, forward,
+        backward, resume, move_up, move_down, move_left, move_right, select, activate,
+        select_and_activate, primary_audio_change, angle_change, popup_on, popup_off,
+        pg_enable_disable, pg_change, secondary_video_enable_disable, secondary_video_change,
+        secondary_audio_enable_disable, secondary_audio_change, pip_pg_change,
+    }
+    TgFontStyle { bold, italic, outline_border }
+}
+
+/// Serializes a palette as a sequence of its entries.
+pub(crate) fn serialize_palette<S: Serializer>(
+    entries: &[PgsPaletteEntry; 256],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    entries[..].serialize(serializer)
+}
+
+/// Serializes an optional palette as a sequence of its entries.
+pub(crate) fn serialize_palette_update<S: Serializer>(
+    entries: &Option<Box<[PgsPaletteEntry; 256]>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    entries
+        .as_ref()
+        .map(|entries| &entries[..])
+        .serialize(serializer)
+}
+
+/// Serialized as the 12 bytes of command bytecode.
+impl Serialize for MObjCmd {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.into_bytes().serialize(serializer)
+    }
+}
+
+impl Serialize for LanguageCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Serialized as the bytes remaining to be read.
+impl<D: AppDetails> Serialize for SliceReader<'_, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.remaining_slice().serialize(serializer)
+    }
+}
+
+impl<D: AppDetails> Serialize for Pes<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Pes", 11)?;
+        s.serialize_field("header", &self.header)?;
+        s.serialize_field("optional_header", &self.optional_header)?;
+        s.serialize_field("pts", &self.pts)?;
+        s.serialize_field("dts", &self.dts)?;
+        s.serialize_field("escr", &self.escr)?;
+        s.serialize_field("es_rate", &self.es_rate)?;
+        s.serialize_field("dsm_trick_mode", &self.dsm_trick_mode)?;
+        s.serialize_field("additional_copy_info", &self.additional_copy_info)?;
+        s.serialize_field("previous_pes_crc", &self.previous_pes_crc)?;
+        s.serialize_field("extension", &self.extension)?;
+        s.serialize_field("raw_data", &self.raw_data())?;
+        s.end()
+    }
+}
+
+impl<D: AppDetails> Serialize for Payload<'_, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Payload::Raw(reader) => {
+                serializer.serialize_newtype_variant("Payload", 0, "Raw", reader)
+            }
+            Payload::PsiPending => serializer.serialize_unit_variant("Payload", 1, "PsiPending"),
+            Payload::Psi(psi) => serializer.serialize_newtype_variant("Payload", 2, "Psi", psi),
+            Payload::PesPending => serializer.serialize_unit_variant("Payload", 3, "PesPending"),
+            Payload::Pes(pes) => serializer.serialize_newtype_variant("Payload", 4, "Pes", pes),
+        }
+    }
+}
+
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+/// let mut packet = [0xff_u8; 188];
+/// packet[..4].copy_from_slice(&[0x47, 0x01, 0x00, 0x10]);
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let json = serde_json::to_value(parser.parse(&packet).unwrap()).unwrap();
+/// assert_eq!(json["header"]["pid"], 0x100);
+/// assert_eq!(json["payload"]["Raw"].as_array().unwrap().len(), 184);
+/// ```
+/// Errors are serialized with the name of their [`ErrorDetails`] variant, as application error
+/// details are not required to be serializable.
+impl<D: AppDetails> Serialize for Error<D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let details = match &self.details {
+            ErrorDetails::PacketOverrun(_) => "PacketOverrun",
+            ErrorDetails::LostSync => "LostSync",
+            ErrorDetails::BadAdaptationHeader => "BadAdaptationHeader",
+            ErrorDetails::BadPsiHeader => "BadPsiHeader",
+            ErrorDetails::BadPesHeader => "BadPesHeader",
+            ErrorDetails::PsiCrcMismatch => "PsiCrcMismatch",
+            ErrorDetails::BadPacketLength(_) => "BadPacketLength",
+            ErrorDetails::ContinuityError(_) => "ContinuityError",
+            ErrorDetails::UnfinishedPayloadUnit(_) => "UnfinishedPayloadUnit",
+            ErrorDetails::UnexpectedContinuation(_) => "UnexpectedContinuation",
+            ErrorDetails::TooManyPendingUnits => "TooManyPendingUnits",
+            ErrorDetails::TransportError(_) => "TransportError",
+            ErrorDetails::BadExpGolomb => "BadExpGolomb",
+            ErrorDetails::BadParameterSet => "BadParameterSet",
+            ErrorDetails::IoError(_) => "IoError",
+            ErrorDetails::AppError(_) => "AppError",
+        };
+        let mut s = serializer.serialize_struct("Error", 2)?;
+        s.serialize_field("location", &self.location)?;
+        s.serialize_field("details", details)?;
+        s.end()
+    }
+}
+
+impl<D: AppDetails> Serialize for Packet<'_, D> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Packet", 10)?;
+        s.serialize_field("header", &self.header)?;
+        s.serialize_field("adaptation_field", &self.adaptation_field)?;
+        s.serialize_field("payload", &self.payload)?;
+        s.serialize_field("payload_offset", &self.payload_offset)?;
+        s.serialize_field("stuffing_len", &self.stuffing_len)?;
+        s.serialize_field("duplicate", &self.duplicate)?;
+        s.serialize_field("events", &self.events)?;
+        s.serialize_field("warnings", &self.warnings)?;
+        s.serialize_field("payload_error", &self.payload_error)?;
+        s.serialize_field("ended_unit", &self.ended_unit)?;
+        s.end()
+    }
+}
+
+#[test]
+fn test_serialize_units() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::{DefaultAppDetails, MpegTsParser};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1011, Some(900000), None, &[0x42; 16]);
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let json: Vec<_> = gen
+        .packets()
+        .iter()
+        .map(|packet| serde_json::to_value(parser.parse(packet).unwrap()).unwrap())
+        .collect();
+
+    let pmt = &json[1]["payload"]["Psi"];
+    assert_eq!(pmt["header"]["table_id"], 2);
+    assert_eq!(pmt["table_syntax"]["table_id_extension"], 1);
+    let es_info = &pmt["data"]["Pmt"]["es_infos"][0];
+    assert_eq!(es_info["header"]["stream_type"], "H264");
+    assert_eq!(es_info["header"]["elementary_pid"], 0x1011);
+    assert_eq!(json[1]["events"][2]["StreamAdded"]["pid"], 0x1011);
+
+    let pes = &json[2]["payload"]["Pes"];
+    assert_eq!(pes["header"]["stream_id"], 0xe0);
+    assert_eq!(pes["pts"], 900000);
+    assert_eq!(pes["raw_data"], serde_json::json!(vec![0x42; 16]));
+}
@@ -1,5 +1,6 @@
 use super::{AppDetails, Error, ErrorDetails, Result};
-use std::marker::PhantomData;
+use crate::prelude::*;
+use core::marker::PhantomData;
 
 /// Simple reader state for extracting data from a [`&[u8]`] slice.
 ///
@@ -18,15 +19,34 @@ use std::marker::PhantomData;
 #[derive(Debug)]
 pub struct SliceReader<'a, D> {
     phantom: PhantomData<D>,
+    data: &'a [u8],
     slice: &'a [u8],
     location: usize,
 }
 
+impl<D> Clone for SliceReader<'_, D> {
+    fn clone(&self) -> Self {
+        Self {
+            phantom: PhantomData,
+            data: self.data,
+            slice: self.slice,
+            location: self.location,
+        }
+    }
+}
+
+/// Saved position of a [`SliceReader`], returned from [`SliceReader::checkpoint`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SliceReaderCheckpoint {
+    position: usize,
+}
+
 impl<'a, D: AppDetails> SliceReader<'a, D> {
     /// Initializes a reader from any byte slice.
     pub fn new(slice: &'a [u8]) -> Self {
         Self {
             phantom: PhantomData,
+            data: slice,
             slice,
             location: 0,
         }
@@ -39,9 +59,11 @@ impl<'a, D: AppDetails> SliceReader<'a, D> {
     /// bounds checking of the nested data.
     pub fn new_sub_reader(&mut self, length: usize) -> Result<Self, D> {
         let location = self.location;
+        let slice = self.read(length)?;
         Ok(Self {
             phantom: PhantomData,
-            slice: self.read(length)?,
+            data: slice,
+            slice,
             location,
         })
     }
@@ -59,6 +81,64 @@ impl<'a, D: AppDetails> SliceReader<'a, D> {
         self.slice.len()
     }
 
+    /// Data remaining to be read, without advancing.
+    pub(crate) fn remaining_slice(&self) -> &'a [u8] {
+        self.slice
+    }
+
+    /// Number of bytes read or skipped from the start of the slice.
+    pub fn position(&self) -> usize {
+        self.data.len() - self.slice.len()
+    }
+
+    /// Moves the reader to `position` bytes from the start of the slice, backwards or forwards.
+    pub fn seek(&mut self, position: usize) -> Result<(), D> {
+        if position > self.data.len() {
+            return Err(
+                self.make_error(ErrorDetails::<D>::PacketOverrun(position - self.position()))
+            );
+        }
+        self.location = self.location - self.position() + position;
+        self.slice = &self.data[position..];
+        Ok(())
+    }
+
+    /// Saves the current position so speculative parsing may be backed out with
+    /// [`SliceReader::restore`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::SliceReader;
+    /// let some_data = [0x00, 0x00, 0x01, 0xe0];
+    /// let mut reader = SliceReader::new(&some_data);
+    /// let checkpoint = reader.checkpoint();
+    /// if reader.read_be_u24()? != 0x000001 || reader.read_u8()? != 0xbd {
+    ///     reader.restore(checkpoint);
+    /// }
+    /// assert_eq!(reader.position(), 0);
+    /// reader.seek(3)?;
+    /// assert_eq!(reader.read_u8()?, 0xe0);
+    /// assert!(reader.seek(5).is_err());
+    /// # Ok::<(), mpegts_io::Error<mpegts_io::DefaultAppDetails>>(())
+    /// ```
+    pub fn checkpoint(&self) -> SliceReaderCheckpoint {
+        SliceReaderCheckpoint {
+            position: self.position(),
+        }
+    }
+
+    /// Returns the reader to a position saved by [`SliceReader::checkpoint`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checkpoint was taken from a reader over a shorter slice.
+    pub fn restore(&mut self, checkpoint: SliceReaderCheckpoint) {
+        if self.seek(checkpoint.position).is_err() {
+            panic!("Checkpoint taken from another reader");
+        }
+    }
+
     /// Advance reader without extracting any data from the slice.
     pub fn skip(&mut self, length: usize) -> Result<(), D> {
         if length > self.slice.len() {
@@ -129,11 +209,21 @@ impl<'a, D: AppDetails> SliceReader<'a, D> {
         Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
     }
 
+    /// Read two bytes interpreted as big-endian [`i16`] two's complement.
+    pub fn read_be_i16(&mut self) -> Result<i16, D> {
+        Ok(i16::from_be_bytes(*self.read_array_ref::<2>()?))
+    }
+
     /// Read four bytes interpreted as big-endian [`u32`].
     pub fn read_be_u32(&mut self) -> Result<u32, D> {
         Ok(u32::from_be_bytes(*self.read_array_ref::<4>()?))
     }
 
+    /// Read four bytes interpreted as big-endian [`i32`] two's complement.
+    pub fn read_be_i32(&mut self) -> Result<i32, D> {
+        Ok(i32::from_be_bytes(*self.read_array_ref::<4>()?))
+    }
+
     /// Read five bytes interpreted as big-endian `u33`.
     pub fn read_be_u33(&mut self) -> Result<u64, D> {
         let bytes = *self.read_array_ref::<5>()?;
@@ -149,6 +239,44 @@ impl<'a, D: AppDetails> SliceReader<'a, D> {
         ]))
     }
 
+    /// Read five bytes interpreted as big-endian `u40`, such as a DVB MJD date and UTC time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::SliceReader;
+    /// let some_data = [0xc0, 0x79, 0x12, 0x45, 0x00, 0xff];
+    /// let mut reader = SliceReader::new(&some_data);
+    /// assert_eq!(reader.read_be_u40()?, 0xc0_7912_4500);
+    /// assert!(reader.read_be_u40().is_err());
+    /// # Ok::<(), mpegts_io::Error<mpegts_io::DefaultAppDetails>>(())
+    /// ```
+    pub fn read_be_u40(&mut self) -> Result<u64, D> {
+        let bytes = *self.read_array_ref::<5>()?;
+        Ok(u64::from_be_bytes([
+            0, 0, 0, bytes[0], bytes[1], bytes[2], bytes[3], bytes[4],
+        ]))
+    }
+
+    /// Read six bytes interpreted as big-endian `u48`.
+    pub fn read_be_u48(&mut self) -> Result<u64, D> {
+        let bytes = *self.read_array_ref::<6>()?;
+        Ok(u64::from_be_bytes([
+            0, 0, bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5],
+        ]))
+    }
+
+    /// Read eight bytes interpreted as big-endian [`u64`].
+    pub fn read_be_u64(&mut self) -> Result<u64, D> {
+        Ok(u64::from_be_bytes(*self.read_array_ref::<8>()?))
+    }
+
+    /// Read `length` bytes of text in DVB character coding, decoded with
+    /// [`decode_text`](crate::dvb::decode_text).
+    pub fn read_dvb_string(&mut self, length: usize) -> Result<String, D> {
+        Ok(crate::dvb::decode_text(self.read(length)?))
+    }
+
     /// Extract a fixed `length` sub-slice from this reader without advancing.
     pub fn peek(&mut self, length: usize) -> Result<&'a [u8], D> {
         if length > self.slice.len() {
@@ -195,6 +323,146 @@ impl<'a, D: AppDetails> SliceReader<'a, D> {
 #[macro_export]
 macro_rules! read_bitfield {
     ($reader:expr, $type:ty) => {
-        <$type>::from_bytes(*$reader.read_array_ref::<{ std::mem::size_of::<$type>() }>()?)
+        <$type>::from_bytes(*$reader.read_array_ref::<{ core::mem::size_of::<$type>() }>()?)
     };
 }
+
+/// Reader state for extracting bit-aligned fields from a [`&[u8]`] slice, most significant bit
+/// first.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::BitReader;
+/// let some_data = [0b1011_0001];
+/// let mut reader = BitReader::new(&some_data);
+/// assert_eq!(reader.read_bits(3)?, 0b101);
+/// assert!(reader.read_bit()?);
+/// assert_eq!(reader.remaining_bits(), 4);
+/// # Ok::<(), mpegts_io::Error<mpegts_io::DefaultAppDetails>>(())
+/// ```
+#[derive(Debug)]
+pub struct BitReader<'a, D> {
+    phantom: PhantomData<D>,
+    slice: &'a [u8],
+    bit_location: usize,
+}
+
+impl<'a, D: AppDetails> BitReader<'a, D> {
+    /// Initializes a reader from any byte slice.
+    pub fn new(slice: &'a [u8]) -> Self {
+        Self {
+            phantom: PhantomData,
+            slice,
+            bit_location: 0,
+        }
+    }
+
+    /// Creates an [`Error`] using the byte containing the current bit location.
+    pub fn make_error(&self, details: ErrorDetails<D>) -> Error<D> {
+        Error {
+            location: self.bit_location / 8,
+            details,
+        }
+    }
+
+    /// Number of bits remaining in the bit reader.
+    pub fn remaining_bits(&self) -> usize {
+        self.slice.len() * 8 - self.bit_location
+    }
+
+    /// Number of whole bytes consumed so far, counting a partially read byte.
+    pub fn bytes_consumed(&self) -> usize {
+        self.bit_location.div_ceil(8)
+    }
+
+    /// Read one bit interpreted as [`bool`].
+    pub fn read_bit(&mut self) -> Result<bool, D> {
+        Ok(self.read_bits(1)? != 0)
+    }
+
+    /// Read up to 32 bits interpreted as an unsigned big-endian integer.
+    pub fn read_bits(&mut self, count: usize) -> Result<u32, D> {
+        assert!(count <= 32);
+        if count > self.remaining_bits() {
+            return Err(self.make_error(ErrorDetails::<D>::PacketOverrun(count.div_ceil(8))));
+        }
+        let mut value = 0_u32;
+        for _ in 0..count {
+            let byte = self.slice[self.bit_location / 8];
+            let bit = (byte >> (7 - self.bit_location % 8)) & 0x1;
+            value = value << 1 | bit as u32;
+            self.bit_location += 1;
+        }
+        Ok(value)
+    }
+
+    /// Advance reader without extracting any bits.
+    pub fn skip_bits(&mut self, count: usize) -> Result<(), D> {
+        if count > self.remaining_bits() {
+            Err(self.make_error(ErrorDetails::<D>::PacketOverrun(count.div_ceil(8))))
+        } else {
+            self.bit_location += count;
+            Ok(())
+        }
+    }
+
+    /// Advance reader to the next byte boundary.
+    pub fn byte_align(&mut self) {
+        self.bit_location = self.bit_location.div_ceil(8) * 8;
+    }
+
+    /// Read an unsigned Exp-Golomb code (`ue(v)` of H.264/H.265).
+    ///
+    /// Codes running past the end of the slice result in [`ErrorDetails::PacketOverrun`], codes
+    /// with more than 31 leading zero bits in [`ErrorDetails::BadExpGolomb`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::{BitReader, DefaultAppDetails, ErrorDetails};
+    /// let some_data = [0b1010_0110, 0b0111_0000];
+    /// let mut reader = BitReader::new(&some_data);
+    /// assert_eq!(reader.read_exp_golomb()?, 0);
+    /// assert_eq!(reader.read_exp_golomb()?, 1);
+    /// assert_eq!(reader.read_exp_golomb()?, 2);
+    /// assert_eq!(reader.read_exp_golomb()?, 6);
+    /// assert!(reader.read_exp_golomb().is_err());
+    ///
+    /// let too_long = [0, 0, 0, 0, 0xff];
+    /// let mut reader = BitReader::<DefaultAppDetails>::new(&too_long);
+    /// let err = reader.read_exp_golomb().unwrap_err();
+    /// assert!(matches!(err.details, ErrorDetails::BadExpGolomb));
+    /// # Ok::<(), mpegts_io::Error<mpegts_io::DefaultAppDetails>>(())
+    /// ```
+    pub fn read_exp_golomb(&mut self) -> Result<u32, D> {
+        let mut leading_zeros = 0;
+        while !self.read_bit()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return Err(self.make_error(ErrorDetails::<D>::BadExpGolomb));
+            }
+        }
+        Ok(((1_u64 << leading_zeros) - 1 + self.read_bits(leading_zeros)? as u64) as u32)
+    }
+
+    /// Read a signed Exp-Golomb code (`se(v)` of H.264/H.265).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mpegts_io::BitReader;
+    /// let some_data = [0b1010_0110, 0b0100_0000];
+    /// let mut reader = BitReader::new(&some_data);
+    /// assert_eq!(reader.read_signed_exp_golomb()?, 0);
+    /// assert_eq!(reader.read_signed_exp_golomb()?, 1);
+    /// assert_eq!(reader.read_signed_exp_golomb()?, -1);
+    /// assert_eq!(reader.read_signed_exp_golomb()?, 2);
+    /// # Ok::<(), mpegts_io::Error<mpegts_io::DefaultAppDetails>>(())
+    /// ```
+    pub fn read_signed_exp_golomb(&mut self) -> Result<i32, D> {
+        let code = self.read_exp_golomb()?;
+        let magnitude = code.div_ceil(2) as i32;
+        Ok(if code % 2 == 1 { magnitude } else { -magnitude })
+    }
+}
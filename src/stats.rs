@@ -0,0 +1,276 @@
+use super::{AppDetails, MpegTsParser, PacketHeader, PcrTimestamp, TransportScramblingControl};
+use crate::prelude::*;
+use hashbrown::HashMap;
+
+/// Change of the transport scrambling control field observed on a PID.
+#[derive(Debug, Copy, Clone)]
+pub struct ScramblingTransition {
+    /// Index of the packet (counted from the first parsed packet) carrying the new state.
+    pub packet_index: u64,
+    /// Most recent PCR seen in the stream before the transition.
+    pub last_pcr: Option<PcrTimestamp>,
+    /// Previous scrambling state.
+    pub from: TransportScramblingControl,
+    /// New scrambling state.
+    pub to: TransportScramblingControl,
+}
+
+impl ScramblingTransition {
+    /// Checks if this is a key parity change (even to odd or odd to even).
+    pub fn is_key_change(&self) -> bool {
+        matches!(
+            (self.from, self.to),
+            (
+                TransportScramblingControl::ScrambledEvenKey,
+                TransportScramblingControl::ScrambledOddKey
+            ) | (
+                TransportScramblingControl::ScrambledOddKey,
+                TransportScramblingControl::ScrambledEvenKey
+            )
+        )
+    }
+}
+
+/// Statistics gathered for a single PID.
+#[derive(Debug, Default, Clone)]
+pub struct PidStats {
+    /// Number of packets carrying a payload.
+    pub payload_packets: u64,
+    /// Number of payload packets with a scrambled payload.
+    pub scrambled_packets: u64,
+    /// Scrambling state of the last payload packet.
+    pub current_tsc: Option<TransportScramblingControl>,
+    /// Every change of the scrambling state, in stream order.
+    pub scrambling_transitions: Vec<ScramblingTransition>,
+}
+
+impl PidStats {
+    /// Fraction of payload packets that were scrambled, in the range `0.0..=1.0`.
+    pub fn scrambled_fraction(&self) -> f64 {
+        if self.payload_packets == 0 {
+            0.0
+        } else {
+            self.scrambled_packets as f64 / self.payload_packets as f64
+        }
+    }
+}
+
+/// Number of 27MHz ticks from `earlier` forward to `pcr`, modulo the PCR range.
+fn pcr_ticks_since(pcr: PcrTimestamp, earlier: PcrTimestamp) -> u64 {
+    (pcr.base.wrapping_sub(earlier.base) * 300 + pcr.extension as u64)
+        .saturating_sub(earlier.extension as u64)
+}
+
+/// PCR timing statistics of one program, measured on the PCR PID named by its PMT.
+///
+/// Measurements restart after a PCR discontinuity.
+#[derive(Debug, Default, Clone)]
+pub struct PcrStats {
+    /// Number of PCRs received.
+    pub pcr_count: u64,
+    /// Interval between the last two PCRs in 27MHz ticks.
+    pub last_interval: Option<u64>,
+    /// Largest interval between consecutive PCRs in 27MHz ticks.
+    pub max_interval: Option<u64>,
+    /// Mux bitrate in bits/s derived from the bytes between the last two PCRs.
+    pub instantaneous_bitrate: Option<f64>,
+    /// Deviation of the last PCR from the value predicted by its byte position at the average
+    /// bitrate, in 27MHz ticks.
+    pub last_jitter: Option<i64>,
+    /// Largest absolute PCR deviation in 27MHz ticks.
+    pub max_jitter: Option<u64>,
+    last: Option<(u64, PcrTimestamp)>,
+    total_ticks: u64,
+    total_bytes: u64,
+}
+
+impl PcrStats {
+    /// Average mux bitrate in bits/s since the first PCR or the last discontinuity.
+    pub fn average_bitrate(&self) -> Option<f64> {
+        if self.total_ticks == 0 {
+            None
+        } else {
+            Some(self.total_bytes as f64 * 8.0 * 27_000_000.0 / self.total_ticks as f64)
+        }
+    }
+
+    /// Largest PCR interval in milliseconds. ISO/IEC 13818-1 allows at most 100 ms.
+    pub fn max_interval_ms(&self) -> Option<f64> {
+        self.max_interval.map(|ticks| ticks as f64 / 27_000.0)
+    }
+
+    /// Largest PCR deviation in nanoseconds. ISO/IEC 13818-1 allows at most 500 ns.
+    pub fn max_jitter_ns(&self) -> Option<f64> {
+        self.max_jitter.map(|ticks| ticks as f64 * 1000.0 / 27.0)
+    }
+
+    fn record(&mut self, packet_index: u64, pcr: PcrTimestamp, discontinuous: bool) {
+        self.pcr_count += 1;
+        let (last_index, last_pcr) = match self.last.replace((packet_index, pcr)) {
+            Some(last) if !discontinuous => last,
+            _ => {
+                self.total_ticks = 0;
+                self.total_bytes = 0;
+                return;
+            }
+        };
+        let ticks = pcr_ticks_since(pcr, last_pcr);
+        let bytes = (packet_index - last_index) * 188;
+        self.last_interval = Some(ticks);
+        self.max_interval = self.max_interval.max(Some(ticks));
+        if ticks > 0 {
+            self.instantaneous_bitrate = Some(bytes as f64 * 8.0 * 27_000_000.0 / ticks as f64);
+        }
+        /* Predict the arrival from the average rate before this PCR */
+        if self.total_bytes > 0 {
+            let expected = bytes as f64 * self.total_ticks as f64 / self.total_bytes as f64;
+            let jitter = ticks as i64 - expected.round() as i64;
+            self.last_jitter = Some(jitter);
+            self.max_jitter = self.max_jitter.max(Some(jitter.unsigned_abs()));
+        }
+        self.total_ticks += ticks;
+        self.total_bytes += bytes;
+    }
+}
+
+/// Statistics gathered across the stream when enabled with
+/// [`MpegTsParser::set_stats_enabled`].
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser};
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// parser.set_stats_enabled(true);
+/// let mut packet = [0xff_u8; 188];
+/// packet[..4].copy_from_slice(&[0x47, 0x01, 0x00, 0xd0]);
+/// parser.parse(&packet).unwrap();
+/// let stats = parser.stats().unwrap().pid(0x100).unwrap();
+/// assert_eq!(stats.scrambled_fraction(), 1.0);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct StreamStats {
+    packet_count: u64,
+    last_pcr: Option<PcrTimestamp>,
+    pids: HashMap<u16, PidStats>,
+    programs: HashMap<u16, PcrStats>,
+}
+
+impl StreamStats {
+    /// Number of packets parsed.
+    pub fn packet_count(&self) -> u64 {
+        self.packet_count
+    }
+
+    /// Statistics of one PID.
+    pub fn pid(&self, pid: u16) -> Option<&PidStats> {
+        self.pids.get(&pid)
+    }
+
+    /// Statistics of every PID seen.
+    pub fn pids(&self) -> &HashMap<u16, PidStats> {
+        &self.pids
+    }
+
+    /// PCR timing statistics of one program.
+    pub fn program_pcr(&self, program_num: u16) -> Option<&PcrStats> {
+        self.programs.get(&program_num)
+    }
+
+    /// PCR timing statistics of every program whose PCR was seen.
+    pub fn programs_pcr(&self) -> &HashMap<u16, PcrStats> {
+        &self.programs
+    }
+
+    pub(crate) fn record_program_pcr(
+        &mut self,
+        program_num: u16,
+        pcr: PcrTimestamp,
+        discontinuous: bool,
+    ) {
+        /* The current packet was already counted */
+        let packet_index = self.packet_count - 1;
+        self.programs
+            .entry(program_num)
+            .or_default()
+            .record(packet_index, pcr, discontinuous);
+    }
+
+    pub(crate) fn record_pcr(&mut self, pcr: PcrTimestamp) {
+        self.last_pcr = Some(pcr);
+    }
+
+    pub(crate) fn record_packet(&mut self, header: &PacketHeader) {
+        let packet_index = self.packet_count;
+        self.packet_count += 1;
+        if !header.has_payload() {
+            return;
+        }
+
+        let last_pcr = self.last_pcr;
+        let stats = self.pids.entry(header.pid()).or_default();
+        let tsc = header.tsc();
+        stats.payload_packets += 1;
+        if tsc != TransportScramblingControl::NotScrambled {
+            stats.scrambled_packets += 1;
+        }
+        if let Some(from) = stats.current_tsc.filter(|from| *from != tsc) {
+            stats.scrambling_transitions.push(ScramblingTransition {
+                packet_index,
+                last_pcr,
+                from,
+                to: tsc,
+            });
+        }
+        stats.current_tsc = Some(tsc);
+    }
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Enables or disables gathering of [`StreamStats`]. Disabling discards gathered statistics.
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.stats = None;
+        } else if self.stats.is_none() {
+            self.stats = Some(StreamStats::default());
+        }
+    }
+
+    /// Statistics gathered so far, if enabled.
+    pub fn stats(&self) -> Option<&StreamStats> {
+        self.stats.as_ref()
+    }
+}
+
+#[test]
+fn test_pcr_stats() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::DefaultAppDetails;
+
+    /* Units of equal size every 40 ms, with the last PCR arriving 100 ticks late */
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+    gen.write_pat().write_pmt();
+    for pts in [0, 3600, 7200, 10800, 14500].iter() {
+        gen.write_pes(0x1011, Some(*pts), None, &[0; 1000]);
+    }
+    let unit_packets = (gen.packets().len() as u64 - 2) / 5;
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .stats(true)
+        .build();
+    for packet in gen.packets() {
+        parser.parse(packet).unwrap();
+    }
+    let stats = parser.stats().unwrap().program_pcr(1).unwrap();
+    assert_eq!(stats.pcr_count, 5);
+    assert_eq!(stats.last_interval, Some(3700 * 300));
+    assert_eq!(stats.max_interval_ms(), Some(3700.0 / 90.0));
+    let bitrate = (unit_packets * 188 * 8) as f64 * 25.0;
+    assert_eq!(
+        stats.average_bitrate(),
+        Some(bitrate * 4.0 * 3600.0 / 14500.0)
+    );
+    assert_eq!(stats.last_jitter, Some(100 * 300));
+    assert_eq!(stats.max_jitter, Some(100 * 300));
+}
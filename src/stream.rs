@@ -0,0 +1,204 @@
+use super::{
+    AppDetails, DefaultAppDetails, Error, ErrorDetails, Framing, MpegTsParser, OwnedPacket, Result,
+};
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Number of packets buffered by [`MpegTsStream`] per read call.
+const BUFFERED_PACKETS: usize = 64;
+
+/// Asynchronous counterpart of [`crate::MpegTsReader`] parsing packets from an [`AsyncRead`]
+/// stream. Requires the `async` feature.
+///
+/// Reads are buffered internally and short reads are retried until a whole packet is available.
+/// A trailing partial packet at the end of the stream is reported once as
+/// [`ErrorDetails::BadPacketLength`]. Read failures are reported as [`ErrorDetails::IoError`]
+/// and end the stream.
+///
+/// Tokio readers may be adapted to [`AsyncRead`] with the `compat` module of `tokio-util`.
+///
+/// # Example
+///
+/// ```
+/// use futures_executor::block_on_stream;
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, MpegTsStream, MpegTsWriter};
+///
+/// let mut data = Vec::new();
+/// MpegTsWriter::default().write_unit(&mut data, 0x100, &[0; 400], None)?;
+///
+/// let parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let stream = MpegTsStream::new(&data[..], parser);
+/// let mut count = 0;
+/// for packet in block_on_stream(stream) {
+///     assert_eq!(packet.expect("Parse Error!").header.pid(), 0x100);
+///     count += 1;
+/// }
+/// assert_eq!(count, 3);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct MpegTsStream<R, D: AppDetails = DefaultAppDetails> {
+    reader: R,
+    parser: MpegTsParser<D>,
+    framing: Framing,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+    done: bool,
+}
+
+/* No field is ever pinned */
+impl<R: Unpin, D: AppDetails> Unpin for MpegTsStream<R, D> {}
+
+impl<R: AsyncRead + Unpin, D: AppDetails> MpegTsStream<R, D> {
+    /// Creates a stream of plain 188-byte packets.
+    pub fn new(reader: R, parser: MpegTsParser<D>) -> Self {
+        Self::with_framing(reader, parser, Framing::PLAIN)
+    }
+
+    /// Creates a stream of packets stored with the given [`Framing`].
+    pub fn with_framing(reader: R, parser: MpegTsParser<D>, framing: Framing) -> Self {
+        Self {
+            reader,
+            parser,
+            framing,
+            buf: vec![0; framing.packet_size() * BUFFERED_PACKETS],
+            start: 0,
+            end: 0,
+            done: false,
+        }
+    }
+
+    /// Underlying parser.
+    pub fn parser(&self) -> &MpegTsParser<D> {
+        &self.parser
+    }
+
+    /// Underlying parser.
+    pub fn parser_mut(&mut self) -> &mut MpegTsParser<D> {
+        &mut self.parser
+    }
+
+    /// Returns the underlying stream. Buffered data that was not yet parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads until one whole packet is buffered. Resolves to `false` at the end of the stream.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<std::io::Result<bool>> {
+        let packet_size = self.framing.packet_size();
+        if self.end - self.start >= packet_size {
+            return Poll::Ready(Ok(true));
+        }
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        while self.end < packet_size {
+            match Pin::new(&mut self.reader).poll_read(cx, &mut self.buf[self.end..]) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(false)),
+                Poll::Ready(Ok(len)) => self.end += len,
+                Poll::Ready(Err(e)) if e.kind() == ErrorKind::Interrupted => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            }
+        }
+        Poll::Ready(Ok(true))
+    }
+}
+
+impl<R: AsyncRead + Unpin, D: AppDetails> Stream for MpegTsStream<R, D> {
+    type Item = Result<OwnedPacket<D>, D>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        Poll::Ready(match this.poll_fill(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Ok(true)) => {
+                let packet_size = this.framing.packet_size();
+                let data = &this.buf[this.start..this.start + packet_size];
+                this.start += packet_size;
+                Some(
+                    this.parser
+                        .parse_framed(this.framing, data)
+                        .map(OwnedPacket::from),
+                )
+            }
+            Poll::Ready(Ok(false)) => {
+                this.done = true;
+                let remaining = this.end - this.start;
+                if remaining > 0 {
+                    Some(Err(Error {
+                        location: 0,
+                        details: ErrorDetails::BadPacketLength(remaining),
+                    }))
+                } else {
+                    None
+                }
+            }
+            Poll::Ready(Err(e)) => {
+                this.done = true;
+                Some(Err(Error {
+                    location: 0,
+                    details: ErrorDetails::IoError(e),
+                }))
+            }
+        })
+    }
+}
+
+#[test]
+fn test_pending_reads() {
+    use crate::MpegTsWriter;
+    use futures_executor::block_on_stream;
+
+    /// Stream alternating between pending and returning at most 100 bytes.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+
+    impl<'a> AsyncRead for Trickle<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let len = buf.len().min(100).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    let mut data = Vec::new();
+    MpegTsWriter::default()
+        .write_unit(&mut data, 0x100, &[0; 1000], None)
+        .unwrap();
+    let packet_count = data.len() / 188;
+    data.extend_from_slice(&[0x47; 10]);
+
+    let stream = MpegTsStream::<_, DefaultAppDetails>::new(
+        Trickle {
+            data: &data,
+            ready: false,
+        },
+        MpegTsParser::default(),
+    );
+    let results: Vec<_> = block_on_stream(stream).collect();
+    assert_eq!(results.len(), packet_count + 1);
+    assert!(results[..packet_count].iter().all(|r| r.is_ok()));
+    assert!(matches!(
+        results[packet_count].as_ref().unwrap_err().details,
+        ErrorDetails::BadPacketLength(10)
+    ));
+}
@@ -0,0 +1,199 @@
+use core::convert::TryFrom;
+use modular_bitfield_msb::error::{InvalidBitPattern, OutOfBounds};
+use modular_bitfield_msb::Specifier;
+use num_traits::FromPrimitive;
+
+macro_rules! stream_types {
+    ($($(#[$attr:meta])* $var:ident = $num:literal,)*) => {
+        /// Stream type of an elementary stream listed in the PMT.
+        ///
+        /// Covers the assignments of ISO/IEC 13818-1, DVB, ATSC and Blu-ray. Values without a
+        /// known assignment are kept as [`StreamType::Other`]. Some private values are assigned
+        /// differently by each standard; the most common assignment is used here (e.g. 0x86 is
+        /// SCTE-35, which Blu-ray uses for DTS-HD Master Audio).
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use mpegts_io::StreamType;
+        /// assert_eq!(StreamType::from(0x1b), StreamType::H264);
+        /// assert_eq!(u8::from(StreamType::Other(0x7f)), 0x7f);
+        /// assert!(StreamType::Hevc.is_video());
+        /// ```
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+        pub enum StreamType {
+            $($(#[$attr])* $var,)*
+            /// Stream type without a known assignment.
+            Other(u8),
+        }
+
+        impl From<u8> for StreamType {
+            fn from(value: u8) -> Self {
+                match value {
+                    $($num => StreamType::$var,)*
+                    other => StreamType::Other(other),
+                }
+            }
+        }
+
+        impl From<StreamType> for u8 {
+            fn from(stream_type: StreamType) -> Self {
+                match stream_type {
+                    $(StreamType::$var => $num,)*
+                    StreamType::Other(other) => other,
+                }
+            }
+        }
+    };
+}
+
+stream_types! {
+    /// ISO/IEC 11172-2 (MPEG-1) video.
+    Mpeg1Video = 0x01,
+    /// ISO/IEC 13818-2 (MPEG-2) video.
+    Mpeg2Video = 0x02,
+    /// ISO/IEC 11172-3 (MPEG-1) audio.
+    Mpeg1Audio = 0x03,
+    /// ISO/IEC 13818-3 (MPEG-2) audio.
+    Mpeg2Audio = 0x04,
+    /// Private sections.
+    PrivateSections = 0x05,
+    /// PES packets containing private data, e.g. DVB subtitles, teletext or AC-3.
+    PrivatePes = 0x06,
+    /// ISO/IEC 13522 MHEG.
+    Mheg = 0x07,
+    /// ISO/IEC 13818-1 Annex A DSM-CC.
+    DsmccAnnexA = 0x08,
+    /// ITU-T H.222.1.
+    H2221 = 0x09,
+    /// ISO/IEC 13818-6 type A (multiprotocol encapsulation).
+    DsmccMultiprotocol = 0x0a,
+    /// ISO/IEC 13818-6 type B (U-N messages, i.e. data and object carousels).
+    DsmccUnMessages = 0x0b,
+    /// ISO/IEC 13818-6 type C (stream descriptors).
+    DsmccStreamDescriptors = 0x0c,
+    /// ISO/IEC 13818-6 type D (sections of any type).
+    DsmccSections = 0x0d,
+    /// ISO/IEC 13818-1 auxiliary data.
+    Auxiliary = 0x0e,
+    /// ISO/IEC 13818-7 AAC audio with ADTS transport syntax.
+    AdtsAac = 0x0f,
+    /// ISO/IEC 14496-2 (MPEG-4) visual.
+    Mpeg4Visual = 0x10,
+    /// ISO/IEC 14496-3 AAC audio with LATM transport syntax.
+    LatmAac = 0x11,
+    /// ISO/IEC 14496-1 SL-packetized stream in PES packets.
+    Mpeg4SlPes = 0x12,
+    /// ISO/IEC 14496-1 SL-packetized stream in sections.
+    Mpeg4SlSections = 0x13,
+    /// ISO/IEC 13818-6 synchronized download protocol.
+    DsmccSynchronizedDownload = 0x14,
+    /// Metadata in PES packets.
+    MetadataPes = 0x15,
+    /// ITU-T H.264 (AVC) video.
+    H264 = 0x1b,
+    /// ITU-T H.264 Annex H (MVC) sub-bitstream.
+    Mvc = 0x20,
+    /// ITU-T H.265 (HEVC) video.
+    Hevc = 0x24,
+    /// ITU-T H.266 (VVC) video.
+    Vvc = 0x33,
+    /// Chinese AVS video.
+    Cavs = 0x42,
+    /// Blu-ray LPCM audio.
+    Lpcm = 0x80,
+    /// Dolby Digital (AC-3) audio.
+    Ac3 = 0x81,
+    /// DTS audio.
+    Dts = 0x82,
+    /// Dolby TrueHD audio.
+    TrueHd = 0x83,
+    /// Blu-ray Dolby Digital Plus (E-AC-3) audio.
+    Eac3 = 0x84,
+    /// Blu-ray DTS-HD High Resolution audio.
+    DtsHd = 0x85,
+    /// SCTE-35 splice information sections.
+    Scte35 = 0x86,
+    /// ATSC Dolby Digital Plus (E-AC-3) audio.
+    AtscEac3 = 0x87,
+    /// Blu-ray presentation graphics (PG) subtitles.
+    PresentationGraphics = 0x90,
+    /// Blu-ray interactive graphics (IG) menus.
+    InteractiveGraphics = 0x91,
+    /// Blu-ray text subtitles.
+    TextSubtitle = 0x92,
+    /// Blu-ray secondary Dolby Digital Plus audio.
+    SecondaryEac3 = 0xa1,
+    /// Blu-ray secondary DTS-HD audio.
+    SecondaryDtsHd = 0xa2,
+    /// Dirac video.
+    Dirac = 0xd1,
+    /// SMPTE VC-1 video.
+    Vc1 = 0xea,
+}
+
+impl StreamType {
+    /// Checks whether the stream type denotes a video stream.
+    pub fn is_video(&self) -> bool {
+        matches!(
+            self,
+            StreamType::Mpeg1Video
+                | StreamType::Mpeg2Video
+                | StreamType::Mpeg4Visual
+                | StreamType::H264
+                | StreamType::Mvc
+                | StreamType::Hevc
+                | StreamType::Vvc
+                | StreamType::Cavs
+                | StreamType::Dirac
+                | StreamType::Vc1
+        )
+    }
+
+    /// Checks whether the stream type denotes an audio stream.
+    pub fn is_audio(&self) -> bool {
+        matches!(
+            self,
+            StreamType::Mpeg1Audio
+                | StreamType::Mpeg2Audio
+                | StreamType::AdtsAac
+                | StreamType::LatmAac
+                | StreamType::Lpcm
+                | StreamType::Ac3
+                | StreamType::Dts
+                | StreamType::TrueHd
+                | StreamType::Eac3
+                | StreamType::DtsHd
+                | StreamType::AtscEac3
+                | StreamType::SecondaryEac3
+                | StreamType::SecondaryDtsHd
+        )
+    }
+}
+
+impl FromPrimitive for StreamType {
+    fn from_i64(n: i64) -> Option<Self> {
+        u8::try_from(n).ok().map(StreamType::from)
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        u8::try_from(n).ok().map(StreamType::from)
+    }
+}
+
+/* Allows StreamType as a bitfield member; every bit pattern is valid */
+impl Specifier for StreamType {
+    const BITS: usize = 8;
+    const STRUCT: bool = false;
+    type Bytes = u8;
+    type InOut = StreamType;
+
+    fn into_bytes(input: Self::InOut) -> Result<Self::Bytes, OutOfBounds> {
+        Ok(input.into())
+    }
+
+    fn from_bytes(bytes: Self::Bytes) -> Result<Self::InOut, InvalidBitPattern<Self::Bytes>> {
+        Ok(bytes.into())
+    }
+}
@@ -0,0 +1,273 @@
+//! Module for generating synthetic MPEG-TS and BDAV streams.
+//!
+//! [`TestStreamGenerator`] produces deterministic single-program streams containing a PAT, a PMT,
+//! PES units with configurable timestamp cadence, and PG segments. Errors such as continuity
+//! counter gaps and CRC corruption may be injected to exercise error handling without shipping
+//! binary captures.
+
+use super::pes::build_pes_unit;
+use super::{MpegTsWriter, PatBuilder, PcrTimestamp, PmtBuilder, Psi, Timestamp90k};
+use crate::prelude::*;
+
+/// Elementary stream listed in the generated PMT.
+#[derive(Debug, Copy, Clone)]
+pub struct TestStream {
+    /// Elementary PID.
+    pub pid: u16,
+    /// Stream type listed in the PMT.
+    pub stream_type: u8,
+    /// PES stream ID used for units of the stream.
+    pub stream_id: u8,
+}
+
+/// Generator of a single-program transport stream.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::testgen::TestStreamGenerator;
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, Payload};
+///
+/// let mut gen = TestStreamGenerator::new(1, 0x100);
+/// gen.add_stream(0x1011, 0x1b, 0xe0).set_pcr_pid(0x1011);
+/// gen.write_pat().write_pmt();
+/// gen.write_pes_sequence(0x1011, 0, 3003, 10, |i| vec![i as u8; 500]);
+///
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let mut pes_count = 0;
+/// for packet in gen.packets() {
+///     if let Some(Payload::Pes(_)) = parser.parse(packet)?.payload {
+///         pes_count += 1;
+///     }
+/// }
+/// assert_eq!(pes_count, 10);
+/// # Ok::<(), mpegts_io::Error<DefaultAppDetails>>(())
+/// ```
+#[derive(Debug)]
+pub struct TestStreamGenerator {
+    program_num: u16,
+    pmt_pid: u16,
+    pcr_pid: u16,
+    streams: Vec<TestStream>,
+    version: u8,
+    writer: MpegTsWriter,
+    skip_cc_pids: Vec<u16>,
+    corrupt_next_crc: bool,
+    packets: Vec<[u8; 188]>,
+}
+
+impl TestStreamGenerator {
+    /// Creates a generator for a program whose PMT is carried on `pmt_pid`.
+    pub fn new(program_num: u16, pmt_pid: u16) -> Self {
+        Self {
+            program_num,
+            pmt_pid,
+            pcr_pid: 0x1fff,
+            streams: Vec::new(),
+            version: 0,
+            writer: MpegTsWriter::default(),
+            skip_cc_pids: Vec::new(),
+            corrupt_next_crc: false,
+            packets: Vec::new(),
+        }
+    }
+
+    /// Lists an elementary stream in subsequently written PMTs.
+    pub fn add_stream(&mut self, pid: u16, stream_type: u8, stream_id: u8) -> &mut Self {
+        self.streams.push(TestStream {
+            pid,
+            stream_type,
+            stream_id,
+        });
+        self
+    }
+
+    /// Removes an elementary stream from subsequently written PMTs.
+    pub fn remove_stream(&mut self, pid: u16) -> &mut Self {
+        self.streams.retain(|s| s.pid != pid);
+        self
+    }
+
+    /// Sets the PCR PID listed in the PMT. PES units written on this PID carry a PCR equal to
+    /// their decode time.
+    pub fn set_pcr_pid(&mut self, pcr_pid: u16) -> &mut Self {
+        self.pcr_pid = pcr_pid;
+        self
+    }
+
+    /// Sets the version of subsequently written PAT and PMT sections.
+    pub fn set_version(&mut self, version: u8) -> &mut Self {
+        self.version = version & 0x1f;
+        self
+    }
+
+    /// Makes the next packet written on `pid` skip one continuity counter value.
+    pub fn inject_cc_gap(&mut self, pid: u16) -> &mut Self {
+        self.skip_cc_pids.push(pid);
+        self
+    }
+
+    /// Makes the next written PSI section carry an incorrect CRC.
+    pub fn inject_crc_error(&mut self) -> &mut Self {
+        self.corrupt_next_crc = true;
+        self
+    }
+
+    /// Writes a PAT mapping the program to the PMT PID.
+    pub fn write_pat(&mut self) -> &mut Self {
+        let pat = PatBuilder::new(1)
+            .version(self.version)
+            .program(self.program_num, self.pmt_pid)
+            .build();
+        let unit = self.psi_unit(&pat);
+        self.write_unit(0, &unit, None);
+        self
+    }
+
+    /// Writes a PMT listing the added streams.
+    pub fn write_pmt(&mut self) -> &mut Self {
+        let mut pmt = PmtBuilder::new(self.program_num, self.pcr_pid).version(self.version);
+        for stream in self.streams.iter() {
+            pmt = pmt.stream(stream.stream_type, stream.pid, None);
+        }
+        let unit = self.psi_unit(&pmt.build());
+        self.write_unit(self.pmt_pid, &unit, None);
+        self
+    }
+
+    /// Writes one PES unit on `pid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the PES unit exceeds the maximum PES packet length.
+    pub fn write_pes(
+        &mut self,
+        pid: u16,
+        pts: Option<u64>,
+        dts: Option<u64>,
+        data: &[u8],
+    ) -> &mut Self {
+        let stream_id = self
+            .streams
+            .iter()
+            .find(|s| s.pid == pid)
+            .map_or(0xbd, |s| s.stream_id);
+        let unit = build_pes_unit(stream_id, pts, dts, data);
+        assert!(unit.len() - 6 <= 0xffff, "PES unit too long");
+
+        let pcr = if pid == self.pcr_pid {
+            dts.or(pts).map(|base| PcrTimestamp {
+                base: Timestamp90k::new(base),
+                extension: 0,
+            })
+        } else {
+            None
+        };
+        self.write_unit(pid, &unit, pcr);
+        self
+    }
+
+    /// Writes `count` PES units on `pid` with PTS values starting at `start_pts` and advancing by
+    /// `pts_step`. The data of each unit is produced by `data_fn` from the unit index.
+    pub fn write_pes_sequence<F: FnMut(usize) -> Vec<u8>>(
+        &mut self,
+        pid: u16,
+        start_pts: u64,
+        pts_step: u64,
+        count: usize,
+        mut data_fn: F,
+    ) -> &mut Self {
+        for i in 0..count {
+            let pts = (start_pts + pts_step * i as u64) & ((1 << 33) - 1);
+            self.write_pes(pid, Some(pts), None, &data_fn(i));
+        }
+        self
+    }
+
+    /// Writes one PG/IG segment of `segment_type` (e.g. 0x14 for a palette) as a PES unit.
+    pub fn write_pg_segment(
+        &mut self,
+        pid: u16,
+        pts: u64,
+        segment_type: u8,
+        data: &[u8],
+    ) -> &mut Self {
+        let mut segment = Vec::with_capacity(3 + data.len());
+        segment.push(segment_type);
+        segment.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        segment.extend_from_slice(data);
+        self.write_pes(pid, Some(pts), None, &segment)
+    }
+
+    /// Generated packets.
+    pub fn packets(&self) -> &[[u8; 188]] {
+        &self.packets
+    }
+
+    /// Generated packets concatenated as a 188-byte packet stream.
+    pub fn to_ts(&self) -> Vec<u8> {
+        self.packets.concat()
+    }
+
+    /// Generated packets concatenated as a 192-byte BDAV stream. Arrival timestamps start at
+    /// `start_atc` and advance by `atc_step` 27MHz ticks per packet.
+    pub fn to_m2ts(&self, start_atc: u32, atc_step: u32) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.packets.len() * 192);
+        for (i, packet) in self.packets.iter().enumerate() {
+            let atc = start_atc.wrapping_add(atc_step.wrapping_mul(i as u32)) & 0x3fff_ffff;
+            out.extend_from_slice(&atc.to_be_bytes());
+            out.extend_from_slice(packet);
+        }
+        out
+    }
+
+    /// Serializes a PSI payload unit, corrupting its CRC if requested.
+    fn psi_unit(&mut self, psi: &Psi) -> Vec<u8> {
+        let mut unit = psi.to_payload_unit();
+        if core::mem::take(&mut self.corrupt_next_crc) {
+            let len = unit.len();
+            for byte in unit[len - 4..].iter_mut() {
+                *byte ^= 0xff;
+            }
+        }
+        unit
+    }
+
+    /// Packetizes a payload unit, applying any pending continuity counter gap.
+    fn write_unit(&mut self, pid: u16, unit: &[u8], pcr: Option<PcrTimestamp>) {
+        if let Some(idx) = self.skip_cc_pids.iter().position(|p| *p == pid) {
+            self.skip_cc_pids.remove(idx);
+            let counter = self.writer.continuity_counter(pid).unwrap_or(0xf);
+            self.writer.set_continuity_counter(pid, counter + 1);
+        }
+        let packets = self.writer.packetize_unit(pid, unit, pcr);
+        self.packets.extend(packets);
+    }
+}
+
+#[test]
+fn test_injected_errors() {
+    use crate::{DefaultAppDetails, ErrorDetails, MpegTsParser};
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().inject_crc_error().write_pmt();
+    gen.write_pes(0x1200, Some(0), None, &[0; 200]);
+    gen.inject_cc_gap(0x1200)
+        .write_pes(0x1200, Some(0), None, &[0; 10]);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .strict(true)
+        .check_continuity(true)
+        .build();
+    let results: Vec<_> = gen.packets().iter().map(|p| parser.parse(p)).collect();
+    assert!(matches!(
+        results[1].as_ref().unwrap_err().details,
+        ErrorDetails::PsiCrcMismatch
+    ));
+    assert!(results[2].is_ok() && results[3].is_ok());
+    assert!(matches!(
+        results[4].as_ref().unwrap_err().details,
+        ErrorDetails::ContinuityError(0x1200)
+    ));
+}
@@ -0,0 +1,104 @@
+//! Application module for 192-byte "timed" transport streams that are not BDAV.
+//!
+//! Some camcorders and recording appliances prepend a 4-byte header to every MPEG-TS packet
+//! without following the BDAV [`BdavPacketHeader`](crate::bdav::BdavPacketHeader) layout. The
+//! interpretation of this prefix is selected with the [`PacketPrefix`] type parameter of
+//! [`TimedParser`].
+
+use super::{
+    AppDetails, DefaultAppDetails, Framing, MpegTsParser, Packet, Payload, Result, SliceReader,
+};
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use modular_bitfield_msb::prelude::*;
+
+/// Interpretation of the 4-byte prefix of a timed packet.
+pub trait PacketPrefix: Debug + Sized {
+    /// Converts the raw prefix bytes into the interpreted form.
+    fn from_prefix_bytes(bytes: [u8; 4]) -> Self;
+}
+
+/// Prefix bytes kept as-is for prefixes with an unknown or proprietary layout.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RawPrefix(pub [u8; 4]);
+
+impl PacketPrefix for RawPrefix {
+    fn from_prefix_bytes(bytes: [u8; 4]) -> Self {
+        Self(bytes)
+    }
+}
+
+/// Prefix consisting entirely of a 32-bit big-endian arrival timestamp.
+#[bitfield]
+#[derive(Debug)]
+pub struct TimestampPrefix {
+    /// Arrival timestamp in units of the recording device's clock (normally 27 MHz).
+    pub timestamp: B32,
+}
+
+impl PacketPrefix for TimestampPrefix {
+    fn from_prefix_bytes(bytes: [u8; 4]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+/// Top-level parsed structure for one timed packet.
+#[derive(Debug)]
+pub struct TimedPacket<'a, P, D: AppDetails> {
+    /// Interpreted 4-byte prefix.
+    pub prefix: P,
+    /// MPEG-TS packet.
+    pub packet: Packet<'a, D>,
+}
+
+/// Top-level parser state for 192-byte timed packets.
+///
+/// # Example
+///
+/// ```no_run
+/// use mpegts_io::timed::{TimedParser, TimestampPrefix};
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// let mut file = File::open("recording.tts").expect("Unable to open!");
+/// let mut parser = TimedParser::<TimestampPrefix>::default();
+/// let mut packet = [0_u8; 192];
+/// while file.read_exact(&mut packet).is_ok() {
+///     let parsed_packet = parser.parse(&packet).expect("Parse Error!");
+///     println!("{}: {:?}", parsed_packet.prefix.timestamp(), parsed_packet.packet);
+/// }
+/// ```
+pub struct TimedParser<P: PacketPrefix = TimestampPrefix, D: AppDetails = DefaultAppDetails> {
+    parser: MpegTsParser<D>,
+    phantom: PhantomData<P>,
+}
+
+impl<P: PacketPrefix, D: AppDetails> Default for TimedParser<P, D>
+where
+    MpegTsParser<D>: Default,
+{
+    fn default() -> Self {
+        Self {
+            parser: MpegTsParser::default(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<P: PacketPrefix, D: AppDetails> TimedParser<P, D> {
+    /// Parse data for exactly one 192-byte timed packet.
+    ///
+    /// All information about the packet is returned as [`TimedPacket`].
+    ///
+    /// For payload units that span multiple packets, the relevant pending state is provided in
+    /// [`Payload`]. Once the final packet of the unit is read, the entire unit is parsed and made
+    /// available in the [`Payload`].
+    pub fn parse<'a>(&mut self, packet: &'a [u8; 192]) -> Result<TimedPacket<'a, P, D>, D> {
+        let framed = self.parser.parse_framed(Framing::TIMED, packet)?;
+        let prefix = *SliceReader::<D>::new(framed.prefix).read_array_ref::<4>()?;
+        Ok(TimedPacket {
+            prefix: P::from_prefix_bytes(prefix),
+            packet: framed.packet,
+        })
+    }
+}
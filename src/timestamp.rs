@@ -0,0 +1,107 @@
+use core::cmp::Ordering;
+use core::fmt::{Debug, Display, Formatter};
+use core::time::Duration;
+
+/// 33-bit timestamp of the 90kHz system clock, as used by PTS, DTS and the PCR base.
+///
+/// The clock wraps around every 2^33 ticks (about 26.5 hours); arithmetic and comparisons are
+/// performed modulo 2^33 so that timestamps on either side of a wraparound compare as expected.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::Timestamp90k;
+/// use std::cmp::Ordering;
+/// use std::time::Duration;
+/// let before = Timestamp90k::new((1 << 33) - 90000);
+/// let after = before.wrapping_add(180000);
+/// assert_eq!(after, Timestamp90k(90000));
+/// assert_eq!(after.wrapping_sub(before), 180000);
+/// assert_eq!(after.wrapping_cmp(before), Ordering::Greater);
+/// assert_eq!(after.duration_since(before), Duration::from_secs(2));
+/// ```
+#[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Timestamp90k(pub u64);
+
+impl Timestamp90k {
+    /// Number of ticks after which the clock wraps around.
+    pub const WRAP: u64 = 1 << 33;
+    /// Ticks per second.
+    pub const HZ: u64 = 90000;
+
+    /// Creates a timestamp, discarding bits above the 33-bit range.
+    pub fn new(ticks: u64) -> Self {
+        Self(ticks & (Self::WRAP - 1))
+    }
+
+    /// Advances the timestamp by `ticks`, wrapping around.
+    pub fn wrapping_add(self, ticks: u64) -> Self {
+        Self::new(self.0.wrapping_add(ticks))
+    }
+
+    /// Number of ticks from `earlier` forward to `self`, modulo 2^33.
+    pub fn wrapping_sub(self, earlier: Self) -> u64 {
+        self.0.wrapping_sub(earlier.0) & (Self::WRAP - 1)
+    }
+
+    /// Signed difference from `other` to `self`, assuming the two are less than half the clock
+    /// range (about 13 hours) apart.
+    pub fn signed_diff(self, other: Self) -> i64 {
+        let diff = self.wrapping_sub(other) as i64;
+        if diff >= (Self::WRAP / 2) as i64 {
+            diff - Self::WRAP as i64
+        } else {
+            diff
+        }
+    }
+
+    /// Orders two timestamps assuming they are less than half the clock range apart.
+    pub fn wrapping_cmp(self, other: Self) -> Ordering {
+        self.signed_diff(other).cmp(&0)
+    }
+
+    /// Checks whether the two timestamps are at most `window` ticks apart in either direction.
+    pub fn is_within(self, other: Self, window: u64) -> bool {
+        self.signed_diff(other).unsigned_abs() <= window
+    }
+
+    /// Time since the clock origin.
+    pub fn to_duration(self) -> Duration {
+        ticks_to_duration(self.0)
+    }
+
+    /// Time from `earlier` forward to `self`, modulo 2^33.
+    pub fn duration_since(self, earlier: Self) -> Duration {
+        ticks_to_duration(self.wrapping_sub(earlier))
+    }
+}
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    Duration::from_secs(ticks / Timestamp90k::HZ)
+        + Duration::from_nanos(ticks % Timestamp90k::HZ * 1_000_000_000 / Timestamp90k::HZ)
+}
+
+impl From<u64> for Timestamp90k {
+    fn from(ticks: u64) -> Self {
+        Self::new(ticks)
+    }
+}
+
+impl From<Timestamp90k> for u64 {
+    fn from(timestamp: Timestamp90k) -> Self {
+        timestamp.0
+    }
+}
+
+impl Display for Timestamp90k {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(crate::pts_format_args!(self.0))
+    }
+}
+
+impl Debug for Timestamp90k {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
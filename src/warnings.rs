@@ -0,0 +1,137 @@
+use super::{AppDetails, MpegTsParser};
+use crate::prelude::*;
+use core::fmt::{Display, Formatter};
+use log::warn;
+
+/// Soft error the parser recovered from while parsing a packet.
+///
+/// Warnings are attached to the [`Packet`](crate::Packet) that raised them and are also logged
+/// with [`log::warn`], so applications can count or react to damaged input without scraping
+/// log output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Warning {
+    /// A new payload unit started before the pending unit of the PID completed, which was
+    /// discarded.
+    UnfinishedUnit {
+        /// PID of the discarded unit.
+        pid: u16,
+    },
+    /// A packet continued a payload unit that was never started; its payload is returned raw.
+    UnexpectedContinuation {
+        /// PID of the packet.
+        pid: u16,
+    },
+    /// A payload unit was left unassembled because too many units were pending.
+    TooManyPendingUnits {
+        /// PID of the unit.
+        pid: u16,
+    },
+    /// A packet repeated the previous packet of its PID and was not assembled.
+    DuplicatePacket {
+        /// PID of the packet.
+        pid: u16,
+    },
+    /// A packet with the transport error indicator set was dropped.
+    TransportErrorDropped {
+        /// PID of the packet.
+        pid: u16,
+    },
+    /// The pending payload unit of a PID was discarded at a discontinuity indicator.
+    UnitInterruptedByDiscontinuity {
+        /// PID of the discarded unit.
+        pid: u16,
+    },
+    /// The data of a completed payload unit was malformed but partially parsed, e.g. truncated
+    /// PGS segments.
+    UnitData {
+        /// PID of the unit.
+        pid: u16,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Warning::UnfinishedUnit { pid } => {
+                write!(f, "Discarding unfinished unit packet on PID: {:x}", pid)
+            }
+            Warning::UnexpectedContinuation { pid } => write!(
+                f,
+                "Unknown payload continuation on non-start packet for PID: {:x}",
+                pid
+            ),
+            Warning::TooManyPendingUnits { pid } => {
+                write!(f, "Too many pending units; not assembling PID: {:x}", pid)
+            }
+            Warning::DuplicatePacket { pid } => {
+                write!(f, "Ignoring duplicate packet on PID: {:x}", pid)
+            }
+            Warning::TransportErrorDropped { pid } => {
+                write!(f, "Dropping packet with transport error on PID: {:x}", pid)
+            }
+            Warning::UnitInterruptedByDiscontinuity { pid } => write!(
+                f,
+                "Discarding unit interrupted by discontinuity on PID: {:x}",
+                pid
+            ),
+            Warning::UnitData { pid, message } => write!(f, "{} on PID: {:x}", message, pid),
+        }
+    }
+}
+
+impl<D: AppDetails> MpegTsParser<D> {
+    /// Logs a warning and attaches it to the packet being parsed.
+    ///
+    /// Available to [`PesUnitObject`](crate::PesUnitObject) implementations reporting malformed
+    /// unit data with [`Warning::UnitData`].
+    pub fn push_warning(&mut self, warning: Warning) {
+        warn!("{}", warning);
+        self.pending_warnings.push(warning);
+    }
+}
+
+#[test]
+fn test_packet_warnings() {
+    use crate::testgen::TestStreamGenerator;
+    use crate::DefaultAppDetails;
+
+    let mut gen = TestStreamGenerator::new(1, 0x100);
+    gen.add_stream(0x1200, 0x90, 0xbd);
+    gen.write_pat().write_pmt();
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    gen.write_pes(0x1200, Some(0), None, &[0; 400]);
+    let packets = gen.packets();
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::builder()
+        .check_continuity(true)
+        .build();
+    let stream = [packets[0], packets[1], packets[2], packets[2]];
+    let results: Vec<_> = stream.iter().map(|p| parser.parse(p).unwrap()).collect();
+    assert!(results[..3].iter().all(|packet| packet.warnings.is_empty()));
+    assert_eq!(
+        results[3].warnings,
+        [Warning::DuplicatePacket { pid: 0x1200 }]
+    );
+
+    /* Continuation without a started unit, then a new unit started before the pending one
+     * completed */
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let stream = [packets[0], packets[1], packets[3], packets[2], packets[5]];
+    let results: Vec<_> = stream.iter().map(|p| parser.parse(p).unwrap()).collect();
+    assert_eq!(
+        results[2].warnings,
+        [Warning::UnexpectedContinuation { pid: 0x1200 }]
+    );
+    assert!(results[3].warnings.is_empty());
+    assert_eq!(
+        results[4].warnings,
+        [Warning::UnfinishedUnit { pid: 0x1200 }]
+    );
+    assert_eq!(
+        results[2].warnings[0].to_string(),
+        "Unknown payload continuation on non-start packet for PID: 1200"
+    );
+}
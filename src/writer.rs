@@ -0,0 +1,207 @@
+use super::{write_pcr, AdaptationField, AdaptationFieldHeader, PacketHeader, PcrTimestamp};
+use core::convert::TryInto;
+use hashbrown::HashMap;
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// MPEG-TS packet serializer managing continuity counters per PID.
+///
+/// The continuity counter of each written header is replaced with the next value for its PID,
+/// and the sync byte, adaptation field length, and adaptation/payload presence flags are derived
+/// from the supplied data. Adaptation field stuffing is added to fill packets with short
+/// payloads.
+///
+/// # Example
+///
+/// ```
+/// use mpegts_io::{DefaultAppDetails, MpegTsParser, MpegTsWriter};
+/// use std::convert::TryInto;
+/// let mut writer = MpegTsWriter::default();
+/// let mut out = Vec::new();
+/// writer.write_unit(&mut out, 0x100, &[0x42; 300], None)?;
+/// assert_eq!(out.len(), 2 * 188);
+///
+/// let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+/// let packet = parser.parse(out[188..].try_into().unwrap()).unwrap();
+/// assert_eq!(packet.header.continuity_counter(), 1);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct MpegTsWriter {
+    continuity_counters: HashMap<u16, u8>,
+}
+
+impl MpegTsWriter {
+    /// Continuity counter of the last packet written on `pid`.
+    pub fn continuity_counter(&self, pid: u16) -> Option<u8> {
+        self.continuity_counters.get(&pid).copied()
+    }
+
+    /// Sets the continuity counter of the last packet written on `pid`, so the next packet
+    /// carrying payload uses `counter + 1`.
+    pub fn set_continuity_counter(&mut self, pid: u16, counter: u8) {
+        self.continuity_counters.insert(pid, counter & 0xf);
+    }
+
+    /// Serializes one packet from a header, optional adaptation field, and payload bytes.
+    ///
+    /// Only the PCR, OPCR, and the discontinuity, random access, and priority flags of the
+    /// adaptation field are written. Fails with [`ErrorKind::InvalidInput`] if the data does not
+    /// fit in 188 bytes.
+    pub fn build_packet(
+        &mut self,
+        header: &PacketHeader,
+        adaptation_field: Option<&AdaptationField>,
+        payload: &[u8],
+    ) -> Result<[u8; 188]> {
+        let pid = header.pid();
+        let has_payload = !payload.is_empty();
+        let af_fields_len = adaptation_field.map_or(0, |af| {
+            1 + 6 * (af.pcr.is_some() as usize + af.opcr.is_some() as usize)
+        });
+        let has_adaptation_field = adaptation_field.is_some() || payload.len() < 184;
+        if payload.len() > 184 || (has_adaptation_field && payload.len() + 1 + af_fields_len > 184)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Packet data exceeds 188 bytes",
+            ));
+        }
+
+        let continuity_counter = if has_payload {
+            let counter = self.continuity_counters.entry(pid).or_insert(0xf);
+            *counter = (*counter + 1) & 0xf;
+            *counter
+        } else {
+            self.continuity_counter(pid).unwrap_or(0)
+        };
+        let header = PacketHeader::new()
+            .with_sync_byte(0x47)
+            .with_tei(header.tei())
+            .with_pusi(header.pusi())
+            .with_priority(header.priority())
+            .with_pid(pid)
+            .with_tsc(header.tsc())
+            .with_has_adaptation_field(has_adaptation_field)
+            .with_has_payload(has_payload)
+            .with_continuity_counter(continuity_counter);
+
+        let mut packet = [0xff_u8; 188];
+        packet[..4].copy_from_slice(&header.into_bytes());
+        let payload_start = 188 - payload.len();
+        if has_adaptation_field {
+            let length = payload_start - 5;
+            if length > 0 {
+                let mut af_header = AdaptationFieldHeader::new().with_length(length as u8);
+                if let Some(af) = adaptation_field {
+                    af_header = af_header
+                        .with_discontinuity(af.header.discontinuity())
+                        .with_random_access(af.header.random_access())
+                        .with_priority(af.header.priority())
+                        .with_has_pcr(af.pcr.is_some())
+                        .with_has_opcr(af.opcr.is_some());
+                }
+                packet[4..6].copy_from_slice(&af_header.into_bytes());
+                let mut pos = 6;
+                for pcr in adaptation_field
+                    .iter()
+                    .flat_map(|af| af.pcr.iter().chain(af.opcr.iter()))
+                {
+                    write_pcr((&mut packet[pos..pos + 6]).try_into().unwrap(), pcr);
+                    pos += 6;
+                }
+            } else {
+                packet[4] = 0;
+            }
+        }
+        packet[payload_start..].copy_from_slice(payload);
+        Ok(packet)
+    }
+
+    /// Serializes one packet to `out`. See [`MpegTsWriter::build_packet`].
+    pub fn write_packet<W: Write>(
+        &mut self,
+        out: &mut W,
+        header: &PacketHeader,
+        adaptation_field: Option<&AdaptationField>,
+        payload: &[u8],
+    ) -> Result<()> {
+        out.write_all(&self.build_packet(header, adaptation_field, payload)?)
+    }
+
+    /// Splits a complete payload unit (PES packet or pointer field and PSI section) into
+    /// packets on `pid`, setting PUSI on the first packet.
+    ///
+    /// If given, `pcr` is carried in the adaptation field of the first packet.
+    pub fn packetize_unit(
+        &mut self,
+        pid: u16,
+        mut unit: &[u8],
+        pcr: Option<PcrTimestamp>,
+    ) -> Vec<[u8; 188]> {
+        let mut out = Vec::with_capacity(unit.len() / 184 + 1);
+        let mut adaptation_field = pcr.map(|pcr| AdaptationField {
+            header: AdaptationFieldHeader::new(),
+            pcr: Some(pcr),
+            opcr: None,
+            extension: None,
+        });
+        let mut first = true;
+        while first || !unit.is_empty() {
+            let capacity = if adaptation_field.is_some() { 176 } else { 184 };
+            let mut payload_len = unit.len().min(capacity);
+            if payload_len == 183 {
+                /* Leave room for an adaptation field flags byte */
+                payload_len -= 1;
+            }
+            let header = PacketHeader::new().with_pusi(first).with_pid(pid);
+            let packet = self
+                .build_packet(
+                    &header,
+                    adaptation_field.take().as_ref(),
+                    &unit[..payload_len],
+                )
+                .expect("Packetized data must fit");
+            out.push(packet);
+            unit = &unit[payload_len..];
+            first = false;
+        }
+        out
+    }
+
+    /// Packetizes a complete payload unit to `out`. See [`MpegTsWriter::packetize_unit`].
+    ///
+    /// Returns the number of packets written.
+    pub fn write_unit<W: Write>(
+        &mut self,
+        out: &mut W,
+        pid: u16,
+        unit: &[u8],
+        pcr: Option<PcrTimestamp>,
+    ) -> Result<usize> {
+        let packets = self.packetize_unit(pid, unit, pcr);
+        for packet in packets.iter() {
+            out.write_all(packet)?;
+        }
+        Ok(packets.len())
+    }
+}
+
+#[test]
+fn test_zero_length_adaptation_field() {
+    use crate::{DefaultAppDetails, MpegTsParser, Payload};
+
+    let mut writer = MpegTsWriter::default();
+    let header = PacketHeader::new().with_pid(0x100);
+    let packet = writer.build_packet(&header, None, &[0x42; 183]).unwrap();
+    assert_eq!(packet[4], 0);
+
+    let mut parser = MpegTsParser::<DefaultAppDetails>::default();
+    let parsed = parser.parse(&packet).unwrap();
+    let adaptation_field = parsed.adaptation_field.as_ref().unwrap();
+    assert_eq!(adaptation_field.header.length(), 0);
+    assert_eq!(parsed.payload_offset, Some(5));
+    match parsed.payload {
+        Some(Payload::Raw(mut raw)) => assert_eq!(raw.read_to_end().unwrap(), &[0x42; 183][..]),
+        payload => panic!("unexpected payload {:?}", payload),
+    }
+}